@@ -1,3 +1,18 @@
+use std::process::Command;
+
 fn main() {
+    // 构建时的 Git 提交，显示在诊断信息中
+    println!("cargo:rerun-if-changed=../.git/HEAD");
+    println!("cargo:rerun-if-changed=../.git/refs/heads");
+    let hash = Command::new("git")
+        .args(["rev-parse", "--short=12", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok());
+    if let Some(hash) = hash {
+        println!("cargo:rustc-env=MERCURIAL_BUILD_HASH={}", hash.trim());
+    }
+
     tauri_build::build();
-}
\ No newline at end of file
+}
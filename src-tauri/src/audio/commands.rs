@@ -2,15 +2,19 @@
 //!
 //! 包含播放控制、设备管理等命令。
 
+use super::decoder::ensure_decodable;
 use super::device::{get_all_audio_devices, AudioDeviceInfo};
+use super::ducking;
 use super::playback::{
-    check_track_finished, get_status, play_track_exclusive, play_track_shared, seek_track_shared,
-    PlaybackStatus,
+    check_track_finished, emit_playback_state, get_status, play_track_exclusive, play_track_shared,
+    seek_track_shared, stop_playback, PlaybackStatus,
 };
 
 #[cfg(windows)]
 use super::wasapi::WasapiExclusivePlayback;
 
+use crate::error::{AppError, AppResult};
+use crate::media::archive::playable_path;
 use crate::AppState;
 use cpal::traits::{DeviceTrait, HostTrait};
 use rodio::{OutputStreamBuilder, Sink};
@@ -39,16 +43,31 @@ pub fn get_spectrum_data(state: State<AppState>) -> Result<Vec<f32>, String> {
 }
 
 #[command]
-pub fn play_track(app: AppHandle, state: State<AppState>, path: String, position: Option<f32>) -> Result<(), String> {
-    if *state.player.exclusive_mode.lock().unwrap() {
-        play_track_exclusive(&app, &state, &path, position)
+pub fn play_track(app: AppHandle, state: State<AppState>, path: String, position: Option<f32>) -> AppResult<()> {
+    // 压缩包中的文件先解压到缓存，当前播放路径仍记录为虚拟路径
+    let playable = playable_path(&path)?;
+    ensure_decodable(&playable)?;
+
+    let result = if *state.player.exclusive_mode.lock().unwrap() {
+        play_track_exclusive(&app, &state, &playable, position)
     } else {
-        play_track_shared(&app, &state, &path, position)
+        play_track_shared(&app, &state, &playable, position)
+    };
+    result.map_err(AppError::AudioDecoder)?;
+    restore_virtual_path(&state, &playable, path);
+    emit_playback_state(&app, &state, Some(position.unwrap_or(0.0)), false);
+    Ok(())
+}
+
+/// 播放解压后的缓存文件后，把当前播放路径恢复为压缩包中的虚拟路径
+fn restore_virtual_path(state: &State<AppState>, playable: &str, path: String) {
+    if playable != path {
+        *state.player.current_path.lock().unwrap() = Some(path);
     }
 }
 
 #[command]
-pub fn pause_track(state: State<AppState>) -> Result<(), String> {
+pub fn pause_track(app: AppHandle, state: State<AppState>) -> Result<(), String> {
     // 使用 try_lock 避免阻塞
     let exclusive_mode = state.player.exclusive_mode.try_lock()
         .map(|g| *g)
@@ -68,11 +87,12 @@ pub fn pause_track(state: State<AppState>) -> Result<(), String> {
             sink.pause();
         }
     }
+    emit_playback_state(&app, &state, None, false);
     Ok(())
 }
 
 #[command]
-pub fn resume_track(state: State<AppState>) -> Result<(), String> {
+pub fn resume_track(app: AppHandle, state: State<AppState>) -> Result<(), String> {
     // 使用 try_lock 避免阻塞
     let exclusive_mode = state.player.exclusive_mode.try_lock()
         .map(|g| *g)
@@ -92,11 +112,20 @@ pub fn resume_track(state: State<AppState>) -> Result<(), String> {
             sink.play();
         }
     }
+    emit_playback_state(&app, &state, None, false);
     Ok(())
 }
 
+/// 停止播放并卸载当前音轨（清空播放队列时调用），系统媒体控件随之清空
 #[command]
-pub fn set_volume(state: State<AppState>, volume: f32) -> Result<(), String> {
+pub fn stop_track(app: AppHandle, state: State<AppState>) -> Result<(), String> {
+    stop_playback(&state.player);
+    emit_playback_state(&app, &state, None, false);
+    Ok(())
+}
+
+#[command]
+pub fn set_volume(app: AppHandle, state: State<AppState>, volume: f32) -> Result<(), String> {
     if !(0.0..=1.0).contains(&volume) {
         return Err("Volume must be between 0.0 and 1.0".to_string());
     }
@@ -115,15 +144,16 @@ pub fn set_volume(state: State<AppState>, volume: f32) -> Result<(), String> {
         {
             if let Ok(guard) = state.player.wasapi_player.try_lock() {
                 if let Some(ref wasapi) = *guard {
-                    wasapi.set_volume(volume)?;
+                    wasapi.set_volume(ducking::output_volume(volume))?;
                 }
             }
         }
     } else {
         if let Ok(sink) = state.player.sink.try_lock() {
-            sink.set_volume(volume);
+            sink.set_volume(ducking::output_volume(volume));
         }
     }
+    emit_playback_state(&app, &state, None, false);
     Ok(())
 }
 
@@ -140,11 +170,16 @@ pub fn is_track_finished(state: State<AppState>) -> Result<bool, String> {
 #[command]
 pub fn seek_track(app: AppHandle, state: State<AppState>, time: f32) -> Result<(), String> {
     let path = state.player.current_path.lock().unwrap().clone().ok_or("No track currently loaded")?;
-    if *state.player.exclusive_mode.lock().unwrap() {
-        play_track_exclusive(&app, &state, &path, Some(time))
+    let playable = playable_path(&path)?;
+    let result = if *state.player.exclusive_mode.lock().unwrap() {
+        play_track_exclusive(&app, &state, &playable, Some(time))
     } else {
-        seek_track_shared(&app, &state, &path, time)
-    }
+        seek_track_shared(&app, &state, &playable, time)
+    };
+    restore_virtual_path(&state, &playable, path);
+    result?;
+    emit_playback_state(&app, &state, Some(time), true);
+    Ok(())
 }
 
 // ============================================================================
@@ -168,10 +203,13 @@ pub fn set_audio_device(
     let exclusive_mode = *state.player.exclusive_mode.lock().unwrap();
 
     if exclusive_mode {
-        switch_to_wasapi_exclusive(&app, &state, &device_name, current_time)
+        switch_to_wasapi_exclusive(&app, &state, &device_name, current_time)?;
     } else {
-        switch_to_shared_mode(&app, &state, &device_name, current_time)
+        switch_to_shared_mode(&app, &state, &device_name, current_time)?;
     }
+    // 系统媒体控件与输出设备无关，切换后同步一次状态
+    emit_playback_state(&app, &state, current_time, false);
+    Ok(())
 }
 
 #[cfg(windows)]
@@ -330,20 +368,16 @@ pub fn get_current_audio_device(state: State<AppState>) -> Result<AudioDeviceInf
     let is_exclusive_mode = *state.player.exclusive_mode.lock().unwrap();
 
     let audio_mode_status = {
-        if is_exclusive_mode {
-            #[cfg(windows)]
-            {
-                if state.player.wasapi_player.lock().unwrap().is_some() {
-                    "exclusive"
-                } else {
-                    "standard"
-                }
-            }
-            #[cfg(not(windows))]
-            {
+        #[cfg(windows)]
+        {
+            if is_exclusive_mode && state.player.wasapi_player.lock().unwrap().is_some() {
+                "exclusive"
+            } else {
                 "standard"
             }
-        } else {
+        }
+        #[cfg(not(windows))]
+        {
             "standard"
         }
     }
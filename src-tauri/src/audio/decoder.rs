@@ -2,6 +2,8 @@
 //!
 //! 使用 Symphonia 库实现高性能音频解码，支持多种格式。
 
+use crate::error::{AppError, AppResult};
+use crate::paths::long_path;
 use crossbeam_channel::{unbounded, Receiver};
 use rodio::Source;
 use std::fs::File;
@@ -18,6 +20,26 @@ use symphonia::core::io::MediaSourceStream;
 use symphonia::core::meta::MetadataOptions;
 use symphonia::core::probe::Hint;
 
+/// Symphonia 当前无法解码的格式（可读取元数据，但不能播放）
+///
+/// Symphonia 0.5 没有 Monkey's Audio、WavPack、DSD 和 WMA 解码器，这些文件可以扫描和显示标签，
+/// 播放时返回 `UnsupportedFormat`；AIFF 由 Symphonia 直接解码，Opus 由 [`super::opus`] 解码。
+pub const UNDECODABLE_EXTENSIONS: &[&str] = &["ape", "wv", "dsf", "dff", "wma"];
+
+/// 检查文件是否可以被解码播放
+pub fn ensure_decodable(path: &str) -> AppResult<()> {
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_default();
+
+    if UNDECODABLE_EXTENSIONS.contains(&extension.as_str()) {
+        return Err(AppError::UnsupportedFormat(extension.to_uppercase()));
+    }
+    Ok(())
+}
+
 #[derive(Debug, PartialEq, Eq)]
 enum DecoderState {
     Uninitialized,
@@ -178,7 +200,7 @@ impl SymphoniaDecoder {
     pub fn new(path: &str) -> Result<Self, String> { Self::new_with_buffer_duration(path, None) }
 
     pub fn new_with_buffer_duration(path: &str, buffer_duration_ms: Option<u32>) -> Result<Self, String> {
-        let file = File::open(path).map_err(|e| e.to_string())?;
+        let file = File::open(long_path(path)).map_err(|e| e.to_string())?;
         let mss = MediaSourceStream::new(Box::new(file.try_clone().map_err(|e| e.to_string())?), Default::default());
         let mut hint = Hint::new();
         if let Some(ext) = Path::new(path).extension().and_then(|s| s.to_str()) { hint.with_extension(ext); }
@@ -320,7 +342,7 @@ impl SymphoniaDecoder {
     }
 
     fn initialize_decoder(&mut self) -> Result<(), String> {
-        let file = File::open(&self.path).map_err(|e| e.to_string())?;
+        let file = File::open(long_path(&self.path)).map_err(|e| e.to_string())?;
         let mss = MediaSourceStream::new(Box::new(file.try_clone().map_err(|e| e.to_string())?), Default::default());
         let mut hint = Hint::new();
         if let Some(ext) = Path::new(&self.path).extension().and_then(|s| s.to_str()) { hint.with_extension(ext); }
@@ -330,7 +352,7 @@ impl SymphoniaDecoder {
         let mut format = probed.format;
         let track = format.tracks().iter().find(|t| t.codec_params.codec != CODEC_TYPE_NULL).ok_or("No audio track found")?;
         let track_id = track.id;
-        let mut decoder = symphonia::default::get_codecs().make(&track.codec_params, &DecoderOptions::default()).map_err(|e| format!("Failed to create decoder: {e}"))?;
+        let mut decoder = super::opus::codecs().make(&track.codec_params, &DecoderOptions::default()).map_err(|e| format!("Failed to create decoder: {e}"))?;
         if self.current_sample > 0 {
             let seek_to = symphonia::core::formats::SeekTo::TimeStamp { ts: self.current_sample, track_id };
             if format.seek(symphonia::core::formats::SeekMode::Accurate, seek_to).is_ok() { decoder.reset(); } else { self.current_sample = 0; }
@@ -0,0 +1,295 @@
+//! 通话时自动闪避
+//!
+//! 其他程序开始语音通话时，按设置降低本程序的输出音量或暂停播放，通话结束后恢复。
+//!
+//! - 降低音量只作用于实际输出：通话期间用户调整音量照常生效并保存，通话结束后恢复为用户当前的音量
+//! - 暂停时由前端的播放器执行（`media-control` 事件）；通话结束后只继续由本功能暂停的播放，
+//!   通话期间用户自己继续播放后不再处理
+//!
+//! 通话的检测目前只在 Windows 上实现：在默认通信设备的会话管理器（`IAudioSessionManager2`）上注册闪避通知，
+//! 系统在通信会话打开和关闭时发出。系统自己的“通信活动”音量调整不受影响。其他平台上检测不到通话，
+//! 本功能不做任何事，状态中的 `supported` 为 `false`。
+
+use super::playback::{get_status, PlaybackStateEvent, PLAYBACK_STATE_EVENT};
+use crate::config::{DuckingConfig, DuckingMode};
+use crate::system::media_session::{send_control, MediaControl};
+use crate::AppState;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
+use tauri::{AppHandle, Emitter, Listener, Manager};
+
+/// 闪避状态变化的事件
+pub const DUCKING_CHANGED_EVENT: &str = "ducking-changed";
+
+/// 闪避的当前状态
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum DuckingState {
+    /// 没有通话，或设置为不处理
+    Inactive,
+    /// 正在降低音量
+    Ducked,
+    /// 因通话暂停，通话结束后继续
+    Paused,
+}
+
+/// 闪避状态，也是 `ducking-changed` 事件的负载
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DuckingStatus {
+    /// 当前平台能否检测通话
+    pub supported: bool,
+    /// 是否有其他程序在通话
+    pub call_active: bool,
+    pub state: DuckingState,
+}
+
+#[cfg(windows)]
+#[allow(unsafe_code)]
+mod platform {
+    use std::collections::BTreeSet;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::Duration;
+    use windows::core::{implement, Result, PCWSTR};
+    use windows::Win32::Media::Audio::{
+        eCommunications, eRender, IAudioSessionManager2, IAudioVolumeDuckNotification,
+        IAudioVolumeDuckNotification_Impl, IMMDevice, IMMDeviceEnumerator, MMDeviceEnumerator,
+    };
+    use windows::Win32::System::Com::{
+        CoCreateInstance, CoInitializeEx, CoTaskMemFree, CLSCTX_ALL, COINIT_MULTITHREADED,
+    };
+
+    pub const SUPPORTED: bool = true;
+
+    /// 检查默认通信设备是否变化的间隔
+    const DEVICE_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+    /// 正在进行的通信会话
+    static SESSIONS: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+    type OnChange = Arc<dyn Fn(bool) + Send + Sync>;
+
+    /// 接收闪避通知，报告是否还有通信会话
+    #[implement(IAudioVolumeDuckNotification)]
+    struct DuckListener {
+        on_change: OnChange,
+    }
+
+    impl DuckListener {
+        fn update(&self, session: &PCWSTR, active: bool) {
+            // 系统传入的是以 0 结尾的会话标识
+            let id = unsafe { session.to_string() }.unwrap_or_default();
+            let any = {
+                let mut sessions = SESSIONS.lock().unwrap();
+                if active {
+                    sessions.insert(id);
+                } else {
+                    sessions.remove(&id);
+                }
+                !sessions.is_empty()
+            };
+            (self.on_change)(any);
+        }
+    }
+
+    impl IAudioVolumeDuckNotification_Impl for DuckListener_Impl {
+        fn OnVolumeDuckNotification(&self, sessionid: &PCWSTR, _countcommunicationsessions: u32) -> Result<()> {
+            self.update(sessionid, true);
+            Ok(())
+        }
+
+        fn OnVolumeUnduckNotification(&self, sessionid: &PCWSTR) -> Result<()> {
+            self.update(sessionid, false);
+            Ok(())
+        }
+    }
+
+    /// 在一个设备上注册的闪避通知，释放时注销
+    struct Registration {
+        manager: IAudioSessionManager2,
+        listener: IAudioVolumeDuckNotification,
+    }
+
+    impl Drop for Registration {
+        fn drop(&mut self) {
+            let _ = unsafe { self.manager.UnregisterDuckNotification(&self.listener) };
+        }
+    }
+
+    /// 默认的通信输出设备及其标识
+    fn communications_device(enumerator: &IMMDeviceEnumerator) -> Result<(IMMDevice, String)> {
+        let device = unsafe { enumerator.GetDefaultAudioEndpoint(eRender, eCommunications) }?;
+        // 标识由系统分配，读取后需要释放
+        let id = unsafe { device.GetId() }?;
+        let text = unsafe { id.to_string() }.unwrap_or_default();
+        unsafe { CoTaskMemFree(Some(id.0.cast_const().cast())) };
+        Ok((device, text))
+    }
+
+    /// 接收设备上所有会话的闪避通知（会话标识为空）
+    fn register(device: &IMMDevice, on_change: OnChange) -> Result<Registration> {
+        let manager: IAudioSessionManager2 = unsafe { device.Activate(CLSCTX_ALL, None) }?;
+        let listener: IAudioVolumeDuckNotification = DuckListener { on_change }.into();
+        unsafe { manager.RegisterDuckNotification(PCWSTR::null(), &listener) }?;
+        Ok(Registration { manager, listener })
+    }
+
+    /// 在后台线程中注册闪避通知，默认通信设备变化时改为注册到新设备
+    pub fn start(on_change: OnChange) {
+        let spawned = thread::Builder::new().name("call-detection".to_string()).spawn(move || {
+            if let Err(e) = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) }.ok() {
+                eprintln!("Failed to initialize COM for call detection: {e}");
+                return;
+            }
+            let enumerator: IMMDeviceEnumerator =
+                match unsafe { CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL) } {
+                    Ok(enumerator) => enumerator,
+                    Err(e) => {
+                        eprintln!("Failed to create audio device enumerator: {e}");
+                        return;
+                    }
+                };
+            let mut registration: Option<Registration> = None;
+            let mut device_id: Option<String> = None;
+            loop {
+                let current = communications_device(&enumerator).ok();
+                if current.as_ref().map(|(_, id)| id) != device_id.as_ref() {
+                    // 原设备上的会话不再有通知，视为通话结束
+                    if registration.take().is_some() {
+                        SESSIONS.lock().unwrap().clear();
+                        on_change(false);
+                    }
+                    device_id = current.as_ref().map(|(_, id)| id.clone());
+                    registration = current.and_then(|(device, _)| {
+                        register(&device, Arc::clone(&on_change))
+                            .map_err(|e| eprintln!("Failed to register for call notifications: {e}"))
+                            .ok()
+                    });
+                }
+                thread::sleep(DEVICE_CHECK_INTERVAL);
+            }
+        });
+        if let Err(e) = spawned {
+            eprintln!("Failed to start call detection: {e}");
+        }
+    }
+}
+
+#[cfg(not(windows))]
+mod platform {
+    use std::sync::Arc;
+
+    pub const SUPPORTED: bool = false;
+
+    pub fn start(_on_change: Arc<dyn Fn(bool) + Send + Sync>) {}
+}
+
+/// 是否有其他程序在通话
+static CALL_ACTIVE: AtomicBool = AtomicBool::new(false);
+static STATE: Mutex<DuckingState> = Mutex::new(DuckingState::Inactive);
+static CONFIG: LazyLock<Mutex<DuckingConfig>> = LazyLock::new(|| Mutex::new(DuckingConfig::default()));
+
+/// 当前的闪避状态
+#[must_use]
+pub fn status() -> DuckingStatus {
+    DuckingStatus {
+        supported: platform::SUPPORTED,
+        call_active: CALL_ACTIVE.load(Ordering::Relaxed),
+        state: *STATE.lock().unwrap(),
+    }
+}
+
+/// 用户音量对应的实际输出音量：降低音量期间按设置的比例降低
+#[must_use]
+pub fn output_volume(volume: f32) -> f32 {
+    if *STATE.lock().unwrap() == DuckingState::Ducked {
+        volume * (1.0 - CONFIG.lock().unwrap().amount.clamp(0.0, 1.0))
+    } else {
+        volume
+    }
+}
+
+/// 按当前状态重新设置输出音量
+fn apply_output_volume(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let volume = output_volume(*state.player.target_volume.lock().unwrap());
+    if *state.player.exclusive_mode.lock().unwrap() {
+        #[cfg(windows)]
+        if let Some(wasapi) = state.player.wasapi_player.lock().unwrap().as_ref() {
+            let _ = wasapi.set_volume(volume);
+        }
+    } else {
+        state.player.sink.lock().unwrap().set_volume(volume);
+    }
+}
+
+/// 切换状态，进入或离开降低音量时重新设置输出音量，状态变化时发送事件
+fn set_state(app: &AppHandle, next: DuckingState) {
+    let previous = std::mem::replace(&mut *STATE.lock().unwrap(), next);
+    if previous == next {
+        return;
+    }
+    if previous == DuckingState::Ducked || next == DuckingState::Ducked {
+        apply_output_volume(app);
+    }
+    let _ = app.emit(DUCKING_CHANGED_EVENT, status());
+}
+
+/// 通话开始或结束
+fn on_call_changed(app: &AppHandle, active: bool) {
+    if CALL_ACTIVE.swap(active, Ordering::Relaxed) == active {
+        return;
+    }
+    let mode = CONFIG.lock().unwrap().mode;
+    let next = if active {
+        match mode {
+            DuckingMode::Duck => DuckingState::Ducked,
+            DuckingMode::Pause if get_status(&app.state()).is_ok_and(|status| status.is_playing) => {
+                send_control(app, MediaControl::Pause);
+                DuckingState::Paused
+            }
+            DuckingMode::Pause | DuckingMode::Off => DuckingState::Inactive,
+        }
+    } else {
+        if *STATE.lock().unwrap() == DuckingState::Paused {
+            send_control(app, MediaControl::Play);
+        }
+        DuckingState::Inactive
+    };
+    set_state(app, next);
+}
+
+/// 应用设置中的闪避选项；通话期间修改设置时立即按新设置处理（改为暂停不会暂停正在进行的播放）
+pub fn apply_config(app: &AppHandle, config: &DuckingConfig) {
+    *CONFIG.lock().unwrap() = config.clone();
+    let current = *STATE.lock().unwrap();
+    let next = match (CALL_ACTIVE.load(Ordering::Relaxed), config.mode, current) {
+        (true, DuckingMode::Duck, _) => DuckingState::Ducked,
+        (_, DuckingMode::Pause, DuckingState::Paused) => DuckingState::Paused,
+        _ => DuckingState::Inactive,
+    };
+    set_state(app, next);
+    // 降低的比例可能变化
+    if next == DuckingState::Ducked {
+        apply_output_volume(app);
+    }
+}
+
+/// 启动时开始检测通话；当前平台不支持时不做任何事
+pub fn start(app: &AppHandle) {
+    if !platform::SUPPORTED {
+        return;
+    }
+    // 通话期间用户自己继续播放后，通话结束时不再自动继续
+    let handle = app.clone();
+    app.listen(PLAYBACK_STATE_EVENT, move |event| {
+        let Ok(state) = serde_json::from_str::<PlaybackStateEvent>(event.payload()) else { return };
+        if state.is_playing && *STATE.lock().unwrap() == DuckingState::Paused {
+            set_state(&handle, DuckingState::Inactive);
+        }
+    });
+    let handle = app.clone();
+    platform::start(Arc::new(move |active| on_call_changed(&handle, active)));
+}
@@ -1,10 +1,12 @@
 //! 音频模块
 //!
-//! 提供音频播放、解码、设备管理等功能。
+//! 提供音频播放、解码、设备管理等功能，其他程序通话时自动降低音量或暂停。
 
 pub mod commands;
 pub mod decoder;
 pub mod device;
+pub mod ducking;
+pub mod opus;
 pub mod playback;
 
 #[cfg(windows)]
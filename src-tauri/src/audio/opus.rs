@@ -0,0 +1,231 @@
+//! Opus 解码器
+//!
+//! Symphonia 0.5 能解析 Ogg Opus 容器，但没有 Opus 解码器。这里用纯 Rust 的 `opus-decoder` 实现
+//! Symphonia 的 `Decoder` 接口，并与 Symphonia 自带的解码器注册在一起，播放和完整性检查都通过
+//! [`codecs`] 创建解码器。
+
+use opus_decoder::OpusMultistreamDecoder;
+use std::sync::LazyLock;
+use symphonia::core::audio::{AsAudioBufferRef, AudioBuffer, AudioBufferRef, Channels, Signal, SignalSpec};
+use symphonia::core::codecs::{
+    CodecDescriptor, CodecParameters, CodecRegistry, Decoder, DecoderOptions, FinalizeResult, CODEC_TYPE_OPUS,
+};
+use symphonia::core::errors::{decode_error, unsupported_error, Result};
+use symphonia::core::formats::Packet;
+use symphonia::core::support_codec;
+
+/// Opus 总是以 48 kHz 解码
+const SAMPLE_RATE: u32 = 48_000;
+/// 单个数据包最长 120 ms
+const MAX_FRAMES_PER_PACKET: usize = 5760;
+
+/// 映射族 1 的声道顺序（与 Vorbis 相同，RFC 7845 第 5.1.1.2 节）
+const VORBIS_ORDER: [&[Channels]; 8] = [
+    &[Channels::FRONT_LEFT],
+    &[Channels::FRONT_LEFT, Channels::FRONT_RIGHT],
+    &[Channels::FRONT_LEFT, Channels::FRONT_CENTRE, Channels::FRONT_RIGHT],
+    &[Channels::FRONT_LEFT, Channels::FRONT_RIGHT, Channels::REAR_LEFT, Channels::REAR_RIGHT],
+    &[Channels::FRONT_LEFT, Channels::FRONT_CENTRE, Channels::FRONT_RIGHT, Channels::REAR_LEFT, Channels::REAR_RIGHT],
+    &[
+        Channels::FRONT_LEFT,
+        Channels::FRONT_CENTRE,
+        Channels::FRONT_RIGHT,
+        Channels::REAR_LEFT,
+        Channels::REAR_RIGHT,
+        Channels::LFE1,
+    ],
+    &[
+        Channels::FRONT_LEFT,
+        Channels::FRONT_CENTRE,
+        Channels::FRONT_RIGHT,
+        Channels::SIDE_LEFT,
+        Channels::SIDE_RIGHT,
+        Channels::REAR_CENTRE,
+        Channels::LFE1,
+    ],
+    &[
+        Channels::FRONT_LEFT,
+        Channels::FRONT_CENTRE,
+        Channels::FRONT_RIGHT,
+        Channels::SIDE_LEFT,
+        Channels::SIDE_RIGHT,
+        Channels::REAR_LEFT,
+        Channels::REAR_RIGHT,
+        Channels::LFE1,
+    ],
+];
+
+/// Symphonia 自带的解码器加上 Opus
+pub fn codecs() -> &'static CodecRegistry {
+    static CODECS: LazyLock<CodecRegistry> = LazyLock::new(|| {
+        let mut registry = CodecRegistry::new();
+        symphonia::default::register_enabled_codecs(&mut registry);
+        registry.register_all::<OpusDecoder>();
+        registry
+    });
+    &CODECS
+}
+
+/// OpusHead 中与解码有关的字段
+#[derive(Debug, PartialEq)]
+struct OpusHead {
+    channels: usize,
+    streams: usize,
+    coupled_streams: usize,
+    /// 输出声道到流内声道的映射
+    mapping: Vec<u8>,
+    /// 输出增益（线性倍数）
+    gain: f32,
+}
+
+impl OpusHead {
+    fn parse(head: &[u8]) -> Option<Self> {
+        if head.len() < 19 || !head.starts_with(b"OpusHead") {
+            return None;
+        }
+        let channels = usize::from(head[9]);
+        // Q7.8 格式的分贝数
+        let gain_db = f32::from(i16::from_le_bytes([head[16], head[17]])) / 256.0;
+        let gain = 10f32.powf(gain_db / 20.0);
+
+        match head[18] {
+            0 if (1..=2).contains(&channels) => Some(Self {
+                channels,
+                streams: 1,
+                coupled_streams: channels - 1,
+                mapping: (0..channels as u8).collect(),
+                gain,
+            }),
+            1 if (1..=8).contains(&channels) && head.len() >= 21 + channels => Some(Self {
+                channels,
+                streams: usize::from(head[19]),
+                coupled_streams: usize::from(head[20]),
+                mapping: head[21..21 + channels].to_vec(),
+                gain,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Ogg Opus 的 Symphonia 解码器
+pub struct OpusDecoder {
+    params: CodecParameters,
+    decoder: OpusMultistreamDecoder,
+    gain: f32,
+    /// 解码器输出的第 i 个声道在 `buf` 中的位置
+    planes: Vec<usize>,
+    /// 交错排列的解码结果
+    pcm: Vec<f32>,
+    buf: AudioBuffer<f32>,
+}
+
+impl Decoder for OpusDecoder {
+    fn try_new(params: &CodecParameters, _: &DecoderOptions) -> Result<Self> {
+        if params.codec != CODEC_TYPE_OPUS {
+            return unsupported_error("opus: invalid codec type");
+        }
+        let Some(head) = params.extra_data.as_deref().and_then(OpusHead::parse) else {
+            return unsupported_error("opus: invalid OpusHead");
+        };
+        let Some(channels) = params.channels.filter(|channels| channels.count() == head.channels) else {
+            return unsupported_error("opus: unsupported channel layout");
+        };
+        let Ok(decoder) =
+            OpusMultistreamDecoder::new(SAMPLE_RATE, head.channels, head.streams, head.coupled_streams, &head.mapping)
+        else {
+            return unsupported_error("opus: invalid stream layout");
+        };
+
+        // Symphonia 的声道按位掩码顺序排列，映射族 1 的输出按 Vorbis 顺序排列
+        let planes = VORBIS_ORDER[head.channels - 1]
+            .iter()
+            .map(|channel| (channels.bits() & (channel.bits() - 1)).count_ones() as usize)
+            .collect();
+
+        Ok(Self {
+            params: params.clone(),
+            decoder,
+            gain: head.gain,
+            planes,
+            pcm: vec![0.0; MAX_FRAMES_PER_PACKET * head.channels],
+            buf: AudioBuffer::new(MAX_FRAMES_PER_PACKET as u64, SignalSpec::new(SAMPLE_RATE, channels)),
+        })
+    }
+
+    fn supported_codecs() -> &'static [CodecDescriptor] {
+        &[support_codec!(CODEC_TYPE_OPUS, "opus", "Opus")]
+    }
+
+    fn reset(&mut self) {
+        self.decoder.reset();
+    }
+
+    fn codec_params(&self) -> &CodecParameters {
+        &self.params
+    }
+
+    fn decode(&mut self, packet: &Packet) -> Result<AudioBufferRef<'_>> {
+        self.buf.clear();
+        let frames = match self.decoder.decode_float(&packet.data, &mut self.pcm, false) {
+            Ok(frames) => frames,
+            Err(_) => return decode_error("opus: invalid packet"),
+        };
+
+        self.buf.render_reserved(Some(frames));
+        let channels = self.planes.len();
+        for (index, &plane) in self.planes.iter().enumerate() {
+            for (frame, sample) in self.buf.chan_mut(plane).iter_mut().enumerate() {
+                *sample = self.pcm[frame * channels + index] * self.gain;
+            }
+        }
+        self.buf.trim(packet.trim_start() as usize, packet.trim_end() as usize);
+        Ok(self.buf.as_audio_buffer_ref())
+    }
+
+    fn finalize(&mut self) -> FinalizeResult {
+        FinalizeResult::default()
+    }
+
+    fn last_decoded(&self) -> AudioBufferRef<'_> {
+        self.buf.as_audio_buffer_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn head(channels: u8, gain_q8: i16, family: u8, tail: &[u8]) -> Vec<u8> {
+        let mut head = b"OpusHead".to_vec();
+        head.extend([1, channels]);
+        head.extend(312u16.to_le_bytes());
+        head.extend(48_000u32.to_le_bytes());
+        head.extend(gain_q8.to_le_bytes());
+        head.push(family);
+        head.extend(tail);
+        head
+    }
+
+    #[test]
+    fn parses_stereo_and_surround_heads() {
+        let stereo = OpusHead::parse(&head(2, 0, 0, &[])).unwrap();
+        assert_eq!((stereo.streams, stereo.coupled_streams, stereo.mapping), (1, 1, vec![0, 1]));
+        assert!((stereo.gain - 1.0).abs() < f32::EPSILON);
+
+        let surround = OpusHead::parse(&head(6, -6 * 256, 1, &[4, 2, 0, 4, 1, 2, 3, 5])).unwrap();
+        assert_eq!((surround.streams, surround.coupled_streams), (4, 2));
+        assert_eq!(surround.mapping, [0, 4, 1, 2, 3, 5]);
+        assert!((surround.gain - 0.501).abs() < 0.001);
+
+        assert_eq!(OpusHead::parse(&head(6, 0, 1, &[4, 2])), None);
+        assert_eq!(OpusHead::parse(&head(3, 0, 0, &[])), None);
+        assert_eq!(OpusHead::parse(b"OpusTags"), None);
+    }
+
+    #[test]
+    fn the_registry_knows_opus() {
+        assert!(codecs().get_codec(CODEC_TYPE_OPUS).is_some());
+        assert!(symphonia::default::get_codecs().get_codec(CODEC_TYPE_OPUS).is_none());
+    }
+}
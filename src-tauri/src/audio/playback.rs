@@ -7,11 +7,13 @@
 //! 无锁设计减少线程竞争
 
 use super::decoder::{LockFreeSymphoniaSource, SymphoniaDecoder};
+use super::ducking::{self, DuckingStatus};
 
 #[cfg(windows)]
 use super::wasapi::PlaybackState;
 use crate::equalizer::{EqSettings, EQ_BAND_COUNT};
-use crate::AppState;
+use crate::paths::long_path;
+use crate::{AppState, PlayerState};
 use rodio::Source;
 use spectrum_analyzer::scaling::divide_by_N_sqrt;
 use spectrum_analyzer::windows::hann_window;
@@ -96,13 +98,16 @@ const BATCH_SIZE: usize = 64;
 pub struct PlaybackStatus {
     pub is_playing: bool,
     pub position_secs: f32,
+    /// 用户设置的音量，通话时降低的音量不计算在内
     pub volume: f32,
+    /// 通话时的自动闪避
+    pub ducking: DuckingStatus,
 }
 
 impl PlaybackStatus {
     #[must_use]
-    pub const fn new(is_playing: bool, position_secs: f32, volume: f32) -> Self {
-        Self { is_playing, position_secs, volume }
+    pub fn new(is_playing: bool, position_secs: f32, volume: f32) -> Self {
+        Self { is_playing, position_secs, volume, ducking: ducking::status() }
     }
 }
 
@@ -123,23 +128,57 @@ fn emit_spectrum_update(app: &AppHandle, data: &[f32]) -> Result<(), Box<dyn std
     Ok(())
 }
 
+/// 音轨播放完毕时发送的事件
+pub const TRACK_ENDED_EVENT: &str = "track-ended";
+/// 播放位置事件（约每 100ms 一次）
+pub const PLAYBACK_POSITION_EVENT: &str = "playback-position";
+/// 播放状态变化事件：开始播放、暂停、继续、跳转和调整音量后发送
+pub const PLAYBACK_STATE_EVENT: &str = "playback-state";
+
 #[inline]
 fn emit_track_ended(app: &AppHandle) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    app.emit("track-ended", TrackEndedEvent {})?;
+    app.emit(TRACK_ENDED_EVENT, TrackEndedEvent {})?;
     Ok(())
 }
 
-#[derive(Debug, serde::Serialize, Clone)]
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct PlaybackPositionEvent {
     pub position: f32, // 秒
 }
 
 fn emit_playback_position(app: &AppHandle, position: f32) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    app.emit("playback-position", PlaybackPositionEvent { position })?;
+    app.emit(PLAYBACK_POSITION_EVENT, PlaybackPositionEvent { position })?;
     Ok(())
 }
 
+/// `playback-state` 事件
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaybackStateEvent {
+    /// 当前播放的文件
+    pub path: Option<String>,
+    pub is_playing: bool,
+    /// 播放位置（秒），只在开始播放和跳转时给出
+    pub position_secs: Option<f32>,
+    pub volume: f32,
+    /// 是否由跳转引起
+    pub seeked: bool,
+}
+
+/// 发送当前的播放状态
+pub fn emit_playback_state(app: &AppHandle, state: &State<AppState>, position_secs: Option<f32>, seeked: bool) {
+    let Ok(status) = get_status(state) else { return };
+    let path = state.player.current_path.try_lock().ok().and_then(|path| path.clone());
+    let _ = app.emit(PLAYBACK_STATE_EVENT, PlaybackStateEvent {
+        path,
+        is_playing: status.is_playing,
+        position_secs,
+        volume: status.volume,
+        seeked,
+    });
+}
+
 // ============================================================================
 // 批量处理缓冲区 - 减少函数调用开销
 // ============================================================================
@@ -476,7 +515,7 @@ pub fn play_track_shared(app: &AppHandle, state: &State<AppState>, path: &str, p
         // 直接停止，不做淡出（淡出会阻塞主线程）
         // 新音源会有 fade_in 效果来平滑过渡
         sink.stop();
-        sink.set_volume(*player.target_volume.lock().unwrap());
+        sink.set_volume(ducking::output_volume(*player.target_volume.lock().unwrap()));
     }
     *player.current_path.lock().unwrap() = Some(path.to_string());
     *player.current_source.lock().unwrap() = None;
@@ -501,7 +540,7 @@ pub fn play_track_shared(app: &AppHandle, state: &State<AppState>, path: &str, p
         }
         Err(e) => {
             println!("Symphonia解码失败，回退到rodio: {e}");
-            let file = File::open(path).map_err(|e| e.to_string())?;
+            let file = File::open(long_path(path)).map_err(|e| e.to_string())?;
             Box::new(
                 VisualizationSource::new(rodio::Decoder::new(BufReader::new(file)).map_err(|e| e.to_string())?, waveform, spectrum, Some(app.clone()))
                     .with_start_position(position.unwrap_or(0.0))
@@ -591,8 +630,24 @@ pub fn play_track_exclusive(_app: &AppHandle, _state: &State<AppState>, _path: &
     Err("Exclusive mode is only supported on Windows".to_string())
 }
 
+/// 停止播放并卸载当前音轨，释放对音频文件的占用（输出设备保持不变）
+pub fn stop_playback(player: &PlayerState) {
+    player.decode_thread_stop.store(true, Ordering::SeqCst);
+    player.sink.lock().unwrap().stop();
+    #[cfg(windows)]
+    {
+        if let Some(ref wasapi) = *player.wasapi_player.lock().unwrap() {
+            let _ = wasapi.stop();
+            let _ = wasapi.clear_buffer();
+        }
+    }
+    *player.current_source.lock().unwrap() = None;
+    *player.current_path.lock().unwrap() = None;
+}
+
 /// 根据采样率计算解码 chunk 大小
 /// 目标是保持约 ~21ms 的处理块（1024 @ 48kHz）
+#[cfg(windows)]
 #[must_use]
 const fn calculate_decode_chunk_size(sample_rate: u32) -> usize {
     match sample_rate {
@@ -825,6 +880,7 @@ fn decode_and_push_to_wasapi(
 
 /// 5.1/7.1 环绕声到立体声的专业混音
 /// 使用 ITU-R BS.775-1 标准的下混系数
+#[cfg(windows)]
 fn downmix_surround_to_stereo(samples: &[f32], src_ch: usize, frame: usize) -> (f32, f32) {
     let start = frame * src_ch;
     
@@ -872,6 +928,7 @@ fn downmix_surround_to_stereo(samples: &[f32], src_ch: usize, frame: usize) -> (
     )
 }
 
+#[cfg(windows)]
 fn convert_channels(samples: &[f32], src_ch: u16, target_ch: u16) -> Vec<f32> {
     if src_ch == target_ch { return samples.to_vec(); }
     let (src, tgt) = (src_ch as usize, target_ch as usize);
@@ -928,7 +985,7 @@ pub fn seek_track_shared(app: &AppHandle, state: &State<AppState>, path: &str, t
         let sink = player.sink.lock().unwrap();
         // 直接停止，不做阻塞的淡出
         sink.stop();
-        sink.set_volume(*player.target_volume.lock().unwrap());
+        sink.set_volume(ducking::output_volume(*player.target_volume.lock().unwrap()));
     }
     let sink = player.sink.lock().unwrap();
     sink.append(source);
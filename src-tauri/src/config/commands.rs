@@ -2,10 +2,22 @@
 //!
 //! 这个模块包含所有与配置管理相关的功能，包括加载、保存、导入、导出等。
 
-use super::manager::AppConfig;
+use super::manager::{AppConfig, NeteaseConfig, NetworkConfig};
+use crate::audio::ducking;
+use crate::error::{AppError, AppResult};
+use crate::i18n;
+use crate::media::filesystem::{apply_extensions, supported_extensions, validate_extensions};
+use crate::media::http_client::{
+    apply_network_config, set_offline, test_connection, validate_proxy_url, ConnectionTest,
+};
+use crate::media::netease::{apply_api_config, test_api_connection, ApiConnectionTest};
+use crate::media::watcher;
+use crate::paths::{canonical_key, resolved_key};
+use crate::system::{file_associations, media_keys, power};
 use crate::AppState;
+use crate::tr;
 use std::path::Path;
-use tauri::{command, State};
+use tauri::{command, AppHandle, State};
 
 /// 验证路径是否安全（不在敏感目录中）
 fn is_path_safe(path: &str) -> Result<(), String> {
@@ -13,7 +25,7 @@ fn is_path_safe(path: &str) -> Result<(), String> {
     
     // 规范化路径
     let canonical = path.canonicalize()
-        .map_err(|_| "无法解析路径，请确保目录存在".to_string())?;
+        .map_err(|_| tr!("directory.unresolved"))?;
     let path_str = canonical.to_string_lossy().to_lowercase();
     
     // Windows 敏感目录
@@ -30,7 +42,7 @@ fn is_path_safe(path: &str) -> Result<(), String> {
         ];
         for pattern in &forbidden {
             if path_str.contains(pattern) {
-                return Err(format!("安全限制：不允许添加系统敏感目录"));
+                return Err(tr!("directory.sensitive"));
             }
         }
     }
@@ -51,14 +63,14 @@ fn is_path_safe(path: &str) -> Result<(), String> {
         ];
         for pattern in &forbidden {
             if path_str.contains(pattern) {
-                return Err(format!("安全限制：不允许添加系统敏感目录"));
+                return Err(tr!("directory.sensitive"));
             }
         }
     }
     
     // 确保是目录
     if !canonical.is_dir() {
-        return Err("指定的路径不是一个目录".to_string());
+        return Err(tr!("directory.notDirectory"));
     }
     
     Ok(())
@@ -78,8 +90,24 @@ pub fn load_config(state: State<AppState>) -> Result<AppConfig, String> {
 
 /// 保存配置
 #[command]
-pub fn save_config(state: State<AppState>, config: AppConfig) -> Result<(), String> {
-    state.config_manager.save_config(&config)
+pub fn save_config(app: AppHandle, state: State<AppState>, config: AppConfig) -> Result<(), String> {
+    state.config_manager.save_config(&config)?;
+    apply_extensions(&config.directory_scan.audio_extensions);
+    if let Err(e) = apply_network_config(&config.network) {
+        eprintln!("Failed to apply proxy settings: {e}");
+    }
+    if let Err(e) = apply_api_config(&config.netease) {
+        eprintln!("Failed to apply NetEase API settings: {e}");
+    }
+    set_offline(&app, config.network.offline_mode);
+    state.lyrics_providers.set_order(&config.lyrics.provider_order);
+    media_keys::apply_config(&app, &config.general);
+    file_associations::apply(config.general.register_file_associations);
+    i18n::apply(&app, &config.general.language);
+    power::apply_config(&app, &config.power);
+    ducking::apply_config(&app, &config.audio.ducking);
+    watcher::apply_config(&app, &config);
+    Ok(())
 }
 
 /// 导出配置到指定路径
@@ -96,8 +124,22 @@ pub fn import_config(state: State<AppState>, file_path: String) -> Result<AppCon
 
 /// 重置配置为默认值
 #[command]
-pub fn reset_config(state: State<AppState>) -> Result<AppConfig, String> {
-    state.config_manager.reset_config()
+pub fn reset_config(app: AppHandle, state: State<AppState>) -> Result<AppConfig, String> {
+    let config = state.config_manager.reset_config()?;
+    apply_extensions(&config.directory_scan.audio_extensions);
+    if let Err(e) = apply_network_config(&config.network) {
+        eprintln!("Failed to apply proxy settings: {e}");
+    }
+    apply_api_config(&config.netease)?;
+    set_offline(&app, config.network.offline_mode);
+    state.lyrics_providers.set_order(&config.lyrics.provider_order);
+    media_keys::apply_config(&app, &config.general);
+    file_associations::apply(config.general.register_file_associations);
+    i18n::apply(&app, &config.general.language);
+    power::apply_config(&app, &config.power);
+    ducking::apply_config(&app, &config.audio.ducking);
+    watcher::apply_config(&app, &config);
+    Ok(config)
 }
 
 /// 添加音乐目录
@@ -107,9 +149,11 @@ pub fn add_music_directory(state: State<AppState>, path: String) -> Result<Vec<S
     is_path_safe(&path)?;
     
     let mut config = state.config_manager.load_config()?;
-    if !config.music_directories.contains(&path) {
+    let key = resolved_key(&path);
+    if !config.music_directories.iter().any(|existing| resolved_key(existing) == key) {
         config.music_directories.push(path);
         state.config_manager.save_config(&config)?;
+        state.library_watcher.set_directories(config.music_directories.clone())?;
     }
     Ok(config.music_directories)
 }
@@ -118,8 +162,10 @@ pub fn add_music_directory(state: State<AppState>, path: String) -> Result<Vec<S
 #[command]
 pub fn remove_music_directory(state: State<AppState>, path: String) -> Result<Vec<String>, String> {
     let mut config = state.config_manager.load_config()?;
-    config.music_directories.retain(|p| p != &path);
+    let key = canonical_key(&path);
+    config.music_directories.retain(|p| canonical_key(p) != key);
     state.config_manager.save_config(&config)?;
+    state.library_watcher.set_directories(config.music_directories.clone())?;
     Ok(config.music_directories)
 }
 
@@ -134,6 +180,7 @@ pub fn set_music_directories(state: State<AppState>, paths: Vec<String>) -> Resu
     let mut config = state.config_manager.load_config()?;
     config.music_directories = paths;
     state.config_manager.save_config(&config)?;
+    state.library_watcher.set_directories(config.music_directories.clone())?;
     Ok(config.music_directories)
 }
 
@@ -143,3 +190,98 @@ pub fn get_music_directories(state: State<AppState>) -> Result<Vec<String>, Stri
     let config = state.config_manager.load_config()?;
     Ok(config.music_directories)
 }
+
+/// 获取视为音频文件的扩展名列表
+#[command]
+pub fn get_supported_extensions() -> Vec<String> {
+    supported_extensions()
+}
+
+/// 设置视为音频文件的扩展名列表，返回保存后的列表
+///
+/// 每一项都必须是不含点的小写扩展名（如 `flac`），列表不能为空。下一次扫描起生效，
+/// 目录监视器立即使用新的列表。
+#[command]
+pub fn set_supported_extensions(state: State<AppState>, extensions: Vec<String>) -> AppResult<Vec<String>> {
+    let extensions = validate_extensions(&extensions).map_err(AppError::Config)?;
+    let mut config = state.config_manager.load_config()?;
+    config.directory_scan.audio_extensions.clone_from(&extensions);
+    state.config_manager.save_config(&config)?;
+    apply_extensions(&extensions);
+    Ok(extensions)
+}
+
+/// 获取在线功能使用的代理设置
+#[command]
+pub fn get_network_proxy(state: State<AppState>) -> AppResult<NetworkConfig> {
+    Ok(state.config_manager.load_config()?.network)
+}
+
+/// 设置在线功能使用的代理（http / https / socks5），返回保存后的设置
+///
+/// `proxy_url` 为空时恢复使用系统代理设置；`no_proxy` 为不经过代理的主机列表，逗号分隔。立即生效。
+#[command]
+pub fn set_network_proxy(
+    state: State<AppState>,
+    proxy_url: Option<String>,
+    no_proxy: Option<String>,
+) -> AppResult<NetworkConfig> {
+    let mut config = state.config_manager.load_config()?;
+    let network = NetworkConfig {
+        proxy_url: proxy_url.map(|url| url.trim().to_string()).filter(|url| !url.is_empty()),
+        no_proxy: no_proxy.map(|hosts| hosts.trim().to_string()).filter(|hosts| !hosts.is_empty()),
+        ..config.network
+    };
+    if let Some(url) = &network.proxy_url {
+        validate_proxy_url(url).map_err(AppError::Config)?;
+    }
+    apply_network_config(&network).map_err(AppError::Config)?;
+
+    config.network = network.clone();
+    state.config_manager.save_config(&config)?;
+    Ok(network)
+}
+
+/// 开启或关闭离线模式，立即生效并保存到配置
+///
+/// 离线模式下歌词来源、封面下载和 MusicBrainz 查询都不发出请求，返回离线错误或使用缓存；
+/// 开关变化时发送 `offline-mode-changed` 事件，进行中的批量下载会暂停到关闭离线模式。
+#[command]
+pub fn set_offline_mode(app: AppHandle, state: State<AppState>, offline: bool) -> AppResult<()> {
+    let mut config = state.config_manager.load_config()?;
+    config.network.offline_mode = offline;
+    state.config_manager.save_config(&config)?;
+    set_offline(&app, offline);
+    Ok(())
+}
+
+/// 通过当前的代理设置发送一次轻量请求，报告能否连接及延迟
+#[command]
+pub async fn test_network_connection(state: State<'_, AppState>) -> AppResult<ConnectionTest> {
+    let network = state.config_manager.load_config()?.network;
+    Ok(test_connection(&network).await)
+}
+
+/// 获取网易云音乐接口设置
+#[command]
+pub fn get_netease_api_config(state: State<AppState>) -> AppResult<NeteaseConfig> {
+    Ok(state.config_manager.load_config()?.netease)
+}
+
+/// 设置网易云音乐接口地址、接口类型、User-Agent、附加请求头和 Cookie，返回保存后的设置
+///
+/// 地址为空时直接访问网易云音乐；设置不合法时返回配置错误，不保存也不生效。保存后立即生效。
+#[command]
+pub fn set_netease_api_config(state: State<AppState>, config: NeteaseConfig) -> AppResult<NeteaseConfig> {
+    let netease = apply_api_config(&config).map_err(AppError::Config)?;
+    let mut app_config = state.config_manager.load_config()?;
+    app_config.netease = netease.clone();
+    state.config_manager.save_config(&app_config)?;
+    Ok(netease)
+}
+
+/// 用当前的网易云音乐接口设置搜索一次，报告能否使用、延迟和错误类型
+#[command]
+pub async fn test_netease_connection() -> ApiConnectionTest {
+    test_api_connection().await
+}
@@ -7,6 +7,7 @@ pub mod manager;
 
 // 重新导出常用类型
 pub use manager::{
-    AppConfig, AudioConfig, ConfigManager, DirectoryScanConfig, GeneralConfig, PlaylistConfig,
-    TitleExtractionConfig,
+    AppConfig, AudioConfig, ConfigManager, DirectoryScanConfig, DuckingConfig, DuckingMode, GeneralConfig, LastfmConfig,
+    ListenBrainzConfig, LyricsConfig, MetadataConfig, NeteaseApiFlavor, NeteaseConfig, NetworkConfig, PlaylistConfig,
+    PowerConfig, TitleExtractionConfig,
 };
@@ -2,6 +2,9 @@
 //!
 //! 定义应用程序的自定义错误类型和结果类型。
 
+use crate::media::netease::NeteaseError;
+use crate::tr;
+use serde::ser::{Serialize, SerializeStruct, Serializer};
 use std::fmt;
 
 /// 自定义错误类型，用于音乐播放器应用
@@ -16,28 +19,118 @@ pub enum AppError {
     FileNotFound(String),
     /// 无效的文件路径
     InvalidPath(String),
+    /// 不支持的音频格式
+    UnsupportedFormat(String),
+    /// 任务被用户取消
+    Cancelled,
+    /// 目标位置已有文件，不会被覆盖
+    Conflict(String),
+    /// 文件在读取后被其他程序修改，没有被覆盖；附带磁盘上的当前内容和修改时间（毫秒）
+    ContentConflict { path: String, current: String, modified: u64 },
+    /// 目标位置已有用户自己的文件，没有被覆盖；附带现有内容和要写入的内容，便于比较
+    OverwriteConflict { path: String, current: String, proposed: String },
+    /// 文件所在位置不支持回收站（如部分网络共享），需要用户明确选择永久删除
+    TrashUnsupported(String),
+    /// 网络共享无法访问（休眠、断开或超时）
+    NetworkUnavailable(String),
+    /// 压缩包被加密，无法读取其中的文件
+    EncryptedArchive(String),
     /// 配置相关错误
     Config(String),
     /// Tauri 相关错误
     Tauri(tauri::Error),
     /// JSON 序列化/反序列化错误
     Json(serde_json::Error),
+    /// 曲库数据库错误
+    Database(rusqlite::Error),
+    /// 在线请求错误，序列化时使用在线错误自己的错误码
+    Online(NeteaseError),
     /// 其他通用错误
     Other(String),
 }
 
 impl fmt::Display for AppError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            Self::Io(err) => tr!("error.io", detail = err),
+            Self::AudioDecoder(err) => tr!("error.audioDecoder", detail = err),
+            Self::FileNotFound(path) => tr!("error.fileNotFound", path = path),
+            Self::InvalidPath(path) => tr!("error.invalidPath", path = path),
+            Self::UnsupportedFormat(format) => tr!("error.unsupportedFormat", format = format),
+            Self::Cancelled => tr!("error.cancelled"),
+            Self::Conflict(path) => tr!("error.conflict", path = path),
+            Self::ContentConflict { path, .. } => tr!("error.contentConflict", path = path),
+            Self::OverwriteConflict { path, .. } => tr!("error.overwriteConflict", path = path),
+            Self::TrashUnsupported(paths) => tr!("error.trashUnsupported", paths = paths),
+            Self::NetworkUnavailable(path) => tr!("error.networkUnavailable", path = path),
+            Self::EncryptedArchive(path) => tr!("error.encryptedArchive", path = path),
+            Self::Config(err) => tr!("error.config", detail = err),
+            Self::Tauri(err) => tr!("error.tauri", detail = err),
+            Self::Json(err) => tr!("error.json", detail = err),
+            Self::Database(err) => tr!("error.database", detail = err),
+            Self::Online(err) => return write!(f, "{err}"),
+            Self::Other(err) => tr!("error.other", detail = err),
+        };
+        f.write_str(&message)
+    }
+}
+
+impl AppError {
+    /// 稳定的错误码，供前端区分错误类型
+    #[must_use]
+    pub const fn code(&self) -> &'static str {
         match self {
-            Self::Io(err) => write!(f, "IO error: {err}"),
-            Self::AudioDecoder(err) => write!(f, "Audio decoder error: {err}"),
-            Self::FileNotFound(path) => write!(f, "File not found: {path}"),
-            Self::InvalidPath(path) => write!(f, "Invalid file path: {path}"),
-            Self::Config(err) => write!(f, "Configuration error: {err}"),
-            Self::Tauri(err) => write!(f, "Tauri error: {err}"),
-            Self::Json(err) => write!(f, "JSON error: {err}"),
-            Self::Other(err) => write!(f, "Error: {err}"),
+            Self::Io(_) => "IO",
+            Self::AudioDecoder(_) => "AUDIO_DECODER",
+            Self::FileNotFound(_) => "FILE_NOT_FOUND",
+            Self::InvalidPath(_) => "INVALID_PATH",
+            Self::UnsupportedFormat(_) => "UNSUPPORTED_FORMAT",
+            Self::Cancelled => "CANCELLED",
+            Self::Conflict(_) => "CONFLICT",
+            Self::ContentConflict { .. } => "CONTENT_CONFLICT",
+            Self::OverwriteConflict { .. } => "OVERWRITE_CONFLICT",
+            Self::TrashUnsupported(_) => "TRASH_UNSUPPORTED",
+            Self::NetworkUnavailable(_) => "NETWORK_UNAVAILABLE",
+            Self::EncryptedArchive(_) => "ENCRYPTED_ARCHIVE",
+            Self::Config(_) => "CONFIG",
+            Self::Tauri(_) => "TAURI",
+            Self::Json(_) => "JSON",
+            Self::Database(_) => "DATABASE",
+            Self::Online(err) => err.code(),
+            Self::Other(_) => "OTHER",
+        }
+    }
+
+    /// 供前端处理错误所需的附加数据
+    fn details(&self) -> Option<serde_json::Value> {
+        match self {
+            Self::ContentConflict { path, current, modified } => {
+                Some(serde_json::json!({ "path": path, "current": current, "modified": modified }))
+            }
+            Self::OverwriteConflict { path, current, proposed } => {
+                Some(serde_json::json!({ "path": path, "current": current, "proposed": proposed }))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// 序列化为 `{ code, message }`，便于前端按错误码处理；有附加数据时带上 `details`
+///
+/// 在线请求错误与在线命令直接返回的 `NeteaseError` 形式相同（限流时带 `retryAfterSecs`）。
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if let Self::Online(err) = self {
+            return err.serialize(serializer);
         }
+        let details = self.details();
+        let mut state = serializer.serialize_struct("AppError", if details.is_some() { 3 } else { 2 })?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        if let Some(details) = details {
+            state.serialize_field("details", &details)?;
+        }
+        state.end()
     }
 }
 
@@ -47,6 +140,8 @@ impl std::error::Error for AppError {
             Self::Io(err) => Some(err),
             Self::Tauri(err) => Some(err),
             Self::Json(err) => Some(err),
+            Self::Database(err) => Some(err),
+            Self::Online(err) => Some(err),
             _ => None,
         }
     }
@@ -70,6 +165,18 @@ impl From<serde_json::Error> for AppError {
     }
 }
 
+impl From<rusqlite::Error> for AppError {
+    fn from(err: rusqlite::Error) -> Self {
+        Self::Database(err)
+    }
+}
+
+impl From<NeteaseError> for AppError {
+    fn from(err: NeteaseError) -> Self {
+        Self::Online(err)
+    }
+}
+
 impl From<String> for AppError {
     fn from(err: String) -> Self {
         Self::Other(err)
@@ -82,6 +189,87 @@ impl From<&str> for AppError {
     }
 }
 
+impl From<AppError> for String {
+    fn from(err: AppError) -> Self {
+        err.to_string()
+    }
+}
+
 /// 应用结果类型
 #[allow(dead_code)]
 pub type AppResult<T> = Result<T, AppError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn io_error() -> std::io::Error {
+        std::io::Error::other("disk")
+    }
+
+    /// 前端按这些错误码处理错误，改动错误码需要同步修改前端
+    #[test]
+    fn error_codes_are_stable() {
+        let cases: Vec<(AppError, &str)> = vec![
+            (AppError::Io(io_error()), "IO"),
+            (AppError::AudioDecoder(String::new()), "AUDIO_DECODER"),
+            (AppError::FileNotFound(String::new()), "FILE_NOT_FOUND"),
+            (AppError::InvalidPath(String::new()), "INVALID_PATH"),
+            (AppError::UnsupportedFormat(String::new()), "UNSUPPORTED_FORMAT"),
+            (AppError::Cancelled, "CANCELLED"),
+            (AppError::Conflict(String::new()), "CONFLICT"),
+            (
+                AppError::ContentConflict { path: String::new(), current: String::new(), modified: 0 },
+                "CONTENT_CONFLICT",
+            ),
+            (
+                AppError::OverwriteConflict { path: String::new(), current: String::new(), proposed: String::new() },
+                "OVERWRITE_CONFLICT",
+            ),
+            (AppError::TrashUnsupported(String::new()), "TRASH_UNSUPPORTED"),
+            (AppError::NetworkUnavailable(String::new()), "NETWORK_UNAVAILABLE"),
+            (AppError::EncryptedArchive(String::new()), "ENCRYPTED_ARCHIVE"),
+            (AppError::Config(String::new()), "CONFIG"),
+            (AppError::Tauri(tauri::Error::Io(io_error())), "TAURI"),
+            (AppError::Json(serde_json::from_str::<Value>("{").unwrap_err()), "JSON"),
+            (AppError::Database(rusqlite::Error::QueryReturnedNoRows), "DATABASE"),
+            (AppError::Online(NeteaseError::Offline), "OFFLINE_MODE"),
+            (AppError::Other(String::new()), "OTHER"),
+        ];
+        for (error, code) in cases {
+            assert_eq!(error.code(), code);
+            let json = serde_json::to_value(&error).unwrap();
+            assert_eq!(json["code"], code);
+            assert_eq!(json["message"], error.to_string());
+        }
+    }
+
+    #[test]
+    fn errors_serialize_as_code_and_message() {
+        let json = serde_json::to_value(AppError::FileNotFound("/music/a.flac".to_string())).unwrap();
+        let Value::Object(fields) = json else { panic!("expected an object") };
+        assert_eq!(fields.keys().collect::<Vec<_>>(), ["code", "message"]);
+        assert!(fields["message"].as_str().unwrap().contains("/music/a.flac"));
+    }
+
+    #[test]
+    fn conflicts_carry_details() {
+        let current = "[00:01.00]".to_string();
+        let error = AppError::ContentConflict { path: "a.lrc".to_string(), current, modified: 7 };
+        let json = serde_json::to_value(&error).unwrap();
+        assert_eq!(json["details"], json!({ "path": "a.lrc", "current": "[00:01.00]", "modified": 7 }));
+
+        let (current, proposed) = ("old".to_string(), "new".to_string());
+        let error = AppError::OverwriteConflict { path: "a.cue".to_string(), current, proposed };
+        let json = serde_json::to_value(&error).unwrap();
+        assert_eq!(json["details"], json!({ "path": "a.cue", "current": "old", "proposed": "new" }));
+    }
+
+    #[test]
+    fn online_errors_keep_their_own_shape() {
+        let json = serde_json::to_value(AppError::from(NeteaseError::RateLimited(Some(5)))).unwrap();
+        let message = "Rate limited, retry after 5s";
+        assert_eq!(json, json!({ "code": "RATE_LIMITED", "message": message, "retryAfterSecs": 5 }));
+    }
+}
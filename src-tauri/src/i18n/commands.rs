@@ -0,0 +1,18 @@
+//! 多语言相关的 Tauri 命令
+
+use super::{apply, system_locale, Language};
+use tauri::{command, AppHandle};
+
+/// 操作系统的语言（BCP-47 标签，如 `zh-CN`、`en-US`）
+#[command]
+pub fn get_system_locale() -> String {
+    system_locale()
+}
+
+/// 切换后端文字的语言：`system` 跟随系统，其他为 BCP-47 标签（如 `zh-CN`、`en`）；返回实际使用的语言
+///
+/// 只在运行时生效，设置中的 `general.language` 随配置一起保存。
+#[command]
+pub fn set_language(app: AppHandle, tag: String) -> Language {
+    apply(&app, &tag)
+}
@@ -0,0 +1,196 @@
+//! 后端文字的多语言支持
+//!
+//! 错误信息、扫描报告、任务栏按钮等由后端生成、直接显示给用户的文字按界面语言输出。译文是按键查找的
+//! 简单目录（`en.json`、`zh-CN.json`，编译时嵌入），文字中的 `{name}` 由 [`tr!`](crate::tr) 的参数替换；
+//! 当前语言缺少的键使用英文，英文也没有时原样输出键名。
+//!
+//! 界面语言由设置中的 `general.language` 决定，`system` 表示跟随系统语言。语言可以在运行时切换，
+//! 切换后发送 `language-changed` 事件，后端显示的按钮等据此刷新。
+
+pub mod commands;
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::LazyLock;
+use tauri::{AppHandle, Emitter};
+
+/// 界面语言变化的事件
+pub const LANGUAGE_CHANGED_EVENT: &str = "language-changed";
+/// 表示跟随系统语言的设置值
+pub const SYSTEM_LANGUAGE: &str = "system";
+/// 无法读取系统语言时使用的标签
+const FALLBACK_LOCALE: &str = "en-US";
+
+/// 后端支持的语言
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    #[serde(rename = "en")]
+    En,
+    #[serde(rename = "zh-CN")]
+    ZhCn,
+}
+
+impl Language {
+    const ALL: [Self; 2] = [Self::En, Self::ZhCn];
+
+    /// BCP-47 标签
+    #[must_use]
+    pub const fn tag(self) -> &'static str {
+        match self {
+            Self::En => "en",
+            Self::ZhCn => "zh-CN",
+        }
+    }
+
+    /// 按 BCP-47 标签选择支持的语言：中文（`zh`、`zh-CN`、`zh-Hans` 等）用简体中文，其他用英文
+    #[must_use]
+    pub fn from_tag(tag: &str) -> Self {
+        if primary_subtag(tag).eq_ignore_ascii_case("zh") { Self::ZhCn } else { Self::En }
+    }
+
+    const fn index(self) -> u8 {
+        match self {
+            Self::En => 0,
+            Self::ZhCn => 1,
+        }
+    }
+
+    fn catalog(self) -> &'static HashMap<String, String> {
+        &CATALOGS[usize::from(self.index())]
+    }
+}
+
+/// `language-changed` 事件
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LanguageChangedEvent {
+    /// 实际使用的语言
+    pub language: Language,
+    /// 设置中的值（`system` 或 BCP-47 标签）
+    pub setting: String,
+}
+
+/// 各语言的译文，顺序与 [`Language::index`] 一致
+static CATALOGS: LazyLock<[HashMap<String, String>; 2]> = LazyLock::new(|| {
+    [include_str!("en.json"), include_str!("zh-CN.json")].map(|text| serde_json::from_str(text).unwrap_or_default())
+});
+
+/// 当前语言的 [`Language::index`]
+static CURRENT: AtomicU8 = AtomicU8::new(0);
+
+/// 标签的主语言部分（`zh-Hans-CN` -> `zh`，也接受 `zh_CN.UTF-8` 这样的 POSIX 写法）
+fn primary_subtag(tag: &str) -> &str {
+    tag.split(['-', '_', '.']).next().unwrap_or_default()
+}
+
+/// 操作系统的语言（BCP-47 标签，如 `zh-CN`、`en-US`），读取失败时为 `en-US`
+#[must_use]
+pub fn system_locale() -> String {
+    sys_locale::get_locale().unwrap_or_else(|| FALLBACK_LOCALE.to_string())
+}
+
+/// 设置中的语言对应的 BCP-47 标签：`system` 或空值时为系统语言
+#[must_use]
+pub fn locale_for_setting(setting: &str) -> String {
+    if setting.is_empty() || setting == SYSTEM_LANGUAGE { system_locale() } else { setting.to_string() }
+}
+
+/// 设置中的语言的主语言代码（如 `zh`、`en`、`fr`），按语言区分的设置（如排序时忽略的冠词）用它查找
+#[must_use]
+pub fn language_code(setting: &str) -> String {
+    primary_subtag(&locale_for_setting(setting)).to_ascii_lowercase()
+}
+
+/// 当前语言
+#[must_use]
+pub fn current() -> Language {
+    let index = CURRENT.load(Ordering::Relaxed);
+    Language::ALL.into_iter().find(|language| language.index() == index).unwrap_or(Language::En)
+}
+
+/// 切换当前语言，返回是否有变化
+pub fn set_current(language: Language) -> bool {
+    CURRENT.swap(language.index(), Ordering::Relaxed) != language.index()
+}
+
+/// 按设置切换语言，语言有变化时发送 `language-changed` 事件，返回实际使用的语言
+pub fn apply(app: &AppHandle, setting: &str) -> Language {
+    let language = Language::from_tag(&locale_for_setting(setting));
+    if set_current(language) {
+        let _ = app.emit(LANGUAGE_CHANGED_EVENT, LanguageChangedEvent { language, setting: setting.to_string() });
+    }
+    language
+}
+
+/// 当前语言中键对应的文字
+#[must_use]
+pub fn text(key: &str) -> String {
+    lookup(key).to_string()
+}
+
+fn lookup(key: &str) -> &str {
+    current()
+        .catalog()
+        .get(key)
+        .or_else(|| Language::En.catalog().get(key))
+        .map_or(key, String::as_str)
+}
+
+/// 当前语言中键对应的文字，`{name}` 替换为同名参数；没有对应参数的占位符原样保留
+#[must_use]
+pub fn format(key: &str, args: &[(&str, &dyn fmt::Display)]) -> String {
+    let template = lookup(key);
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let value = rest
+            .find('}')
+            .and_then(|end| args.iter().find(|(name, _)| *name == &rest[1..end]).map(|(_, value)| (end, value)));
+        if let Some((end, value)) = value {
+            output.push_str(&value.to_string());
+            rest = &rest[end + 1..];
+        } else {
+            output.push('{');
+            rest = &rest[1..];
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+/// 按当前语言取出文字：`tr!("scan.alreadyRunning")`，带参数时 `tr!("error.fileNotFound", path = path)`
+#[macro_export]
+macro_rules! tr {
+    ($key:literal) => {
+        $crate::i18n::text($key)
+    };
+    ($key:literal, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::format($key, &[$((stringify!($name), &$value as &dyn ::std::fmt::Display)),+])
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn catalogs_have_the_same_keys() {
+        let keys = |language: Language| language.catalog().keys().cloned().collect::<BTreeSet<_>>();
+        let (en, zh) = (keys(Language::En), keys(Language::ZhCn));
+        assert!(!en.is_empty());
+        assert_eq!(en.difference(&zh).collect::<Vec<_>>(), Vec::<&String>::new(), "missing in zh-CN.json");
+        assert_eq!(zh.difference(&en).collect::<Vec<_>>(), Vec::<&String>::new(), "missing in en.json");
+    }
+
+    #[test]
+    fn placeholders_are_filled_by_name() {
+        let message = format("online.rateLimitedRetry", &[("seconds", &5)]);
+        assert_eq!(message, "Rate limited, retry after 5s");
+        assert_eq!(format("no.such.key", &[]), "no.such.key");
+    }
+}
@@ -6,9 +6,13 @@ pub mod audio;
 pub mod config;
 pub mod equalizer;
 pub mod error;
+pub mod i18n;
+pub mod library;
 pub mod media;
+pub mod paths;
 pub mod plugins;
 pub mod system;
+pub mod tasks;
 
 use audio::SymphoniaSource;
 
@@ -16,7 +20,11 @@ use audio::SymphoniaSource;
 use audio::WasapiExclusivePlayback;
 
 use config::ConfigManager;
+use library::LibraryDb;
+use media::lyrics_provider::LyricsProviderRegistry;
+use media::watcher::LibraryWatcher;
 use equalizer::{Equalizer, GlobalEqualizer};
+use tasks::TaskRegistry;
 
 use rodio::Sink;
 use std::sync::atomic::{AtomicBool, AtomicU64};
@@ -70,6 +78,14 @@ pub struct AppState {
     pub config_manager: ConfigManager,
     /// 全局均衡器
     pub equalizer: GlobalEqualizer,
+    /// 可取消的后台任务
+    pub tasks: Arc<TaskRegistry>,
+    /// 曲库目录监视器
+    pub library_watcher: LibraryWatcher,
+    /// 曲库数据库
+    pub library: Arc<LibraryDb>,
+    /// 按优先级排列的在线歌词来源
+    pub lyrics_providers: LyricsProviderRegistry,
 }
 
 // 重新导出常用类型
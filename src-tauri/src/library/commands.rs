@@ -0,0 +1,154 @@
+//! 曲库相关的 Tauri 命令
+//!
+//! 查询命令只读取数据库，不访问文件系统（最近添加中尚未扫描的音乐目录除外）。
+
+use super::db::{LibraryAlbum, LibraryArtist, LibraryFilter, LibraryPage, LibraryStats, RecentlyAdded};
+use super::playlist_match::{match_online_playlist_internal, PlaylistMatchResult};
+use super::recent::recently_added_internal;
+use super::search::search_library_internal;
+use super::sync_playlists;
+use crate::error::AppResult;
+use crate::media::scan_filter::ScanFilter;
+use crate::media::sorting::SortSpec;
+use crate::media::{MetadataOptions, TrackMetadata};
+use crate::media::filesystem::{scan_audio_dirs, ScanProgress};
+use crate::media::netease::OnlinePlaylistEntry;
+use crate::tasks::run_blocking;
+use crate::AppState;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{command, State};
+
+/// 未指定 `limit` 时每页返回的曲目数
+const DEFAULT_PAGE_SIZE: usize = 500;
+/// 未指定 `limit` 时返回的搜索结果数
+const DEFAULT_SEARCH_LIMIT: usize = 200;
+/// 未指定 `limit` 时返回的最近添加专辑数
+const DEFAULT_RECENT_LIMIT: usize = 20;
+/// 一天的秒数
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// 按条件分页查询曲目，默认按路径的自然顺序排列
+#[command]
+pub async fn library_query(
+    state: State<'_, AppState>,
+    filter: Option<LibraryFilter>,
+    sort: Option<SortSpec>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+) -> AppResult<LibraryPage> {
+    let db = Arc::clone(&state.library);
+    run_blocking(move || {
+        db.query(
+            &filter.unwrap_or_default(),
+            &sort.unwrap_or_default(),
+            limit.unwrap_or(DEFAULT_PAGE_SIZE),
+            offset.unwrap_or(0),
+        )
+    })
+    .await
+}
+
+/// 搜索曲库
+///
+/// 忽略大小写和变音符号，在标题、艺术家、专辑、专辑艺术家和文件名中匹配；支持 `artist:`、`album:`、
+/// `albumartist:`、`title:`、`genre:`、`year:`、`file:`、`format:`、`isrc:`、`label:` 和 `catalog:` 字段条件。
+/// 结果按标题前缀匹配、词匹配、子串匹配、文件名匹配的顺序排列。
+#[command]
+pub async fn search_library(
+    state: State<'_, AppState>,
+    query: String,
+    limit: Option<usize>,
+) -> AppResult<Vec<TrackMetadata>> {
+    let db = Arc::clone(&state.library);
+    run_blocking(move || Ok(search_library_internal(&db, &query, limit.unwrap_or(DEFAULT_SEARCH_LIMIT)))).await
+}
+
+/// 把在线歌单对应到曲库中的本地文件
+///
+/// `entries` 为 `netease_get_playlist` 返回的歌曲；按标题搜索曲库后比较艺术家、专辑和时长，
+/// 返回每首歌对应的本地文件和没有找到的歌曲。
+#[command]
+pub async fn match_online_playlist_to_library(
+    state: State<'_, AppState>,
+    entries: Vec<OnlinePlaylistEntry>,
+) -> AppResult<PlaylistMatchResult> {
+    let db = Arc::clone(&state.library);
+    run_blocking(move || Ok(match_online_playlist_internal(&db, entries))).await
+}
+
+/// 获取曲库中的所有艺术家
+#[command]
+pub async fn library_get_artists(state: State<'_, AppState>) -> AppResult<Vec<LibraryArtist>> {
+    let db = Arc::clone(&state.library);
+    run_blocking(move || db.artists()).await
+}
+
+/// 获取专辑列表，指定 `artist` 时只返回该艺术家的专辑
+#[command]
+pub async fn library_get_albums(state: State<'_, AppState>, artist: Option<String>) -> AppResult<Vec<LibraryAlbum>> {
+    let db = Arc::clone(&state.library);
+    run_blocking(move || db.albums(artist.as_deref())).await
+}
+
+/// 获取最近添加的曲目，按专辑分组，最新的在前
+///
+/// `limit` 为返回的专辑（组）数，`days` 指定时只返回最近这些天内加入的曲目。曲库中的曲目只查询数据库，
+/// 可以在每次启动时调用；配置中尚未扫描的音乐目录按文件的创建或修改时间计算。
+#[command]
+pub async fn get_recently_added(
+    state: State<'_, AppState>,
+    limit: Option<usize>,
+    days: Option<u32>,
+) -> AppResult<Vec<RecentlyAdded>> {
+    let config = state.config_manager.load_config()?;
+    let db = Arc::clone(&state.library);
+    let since = days.map_or(0, |days| {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| i64::try_from(d.as_secs()).unwrap_or(0));
+        now - i64::from(days) * SECONDS_PER_DAY
+    });
+    run_blocking(move || {
+        recently_added_internal(
+            &db,
+            &config.music_directories,
+            &ScanFilter::from_config(&config),
+            &MetadataOptions::from_config(&config),
+            since,
+            limit.unwrap_or(DEFAULT_RECENT_LIMIT),
+        )
+    })
+    .await
+}
+
+/// 获取曲库统计信息
+#[command]
+pub async fn library_stats(state: State<'_, AppState>) -> AppResult<LibraryStats> {
+    let db = Arc::clone(&state.library);
+    run_blocking(move || db.stats()).await
+}
+
+/// 删除曲库数据库并重新扫描配置中的音乐目录
+///
+/// 用于数据库损坏或与文件不一致时，返回重建后的统计信息。可通过 `cancel_task` 取消，
+/// 取消时数据库中只包含已读取的曲目。
+#[command]
+pub async fn rebuild_library(state: State<'_, AppState>, task_id: Option<String>) -> AppResult<LibraryStats> {
+    let config = state.config_manager.load_config()?;
+    let db = Arc::clone(&state.library);
+    let tasks = Arc::clone(&state.tasks);
+    let task_id = task_id.unwrap_or_else(|| "rebuild-library".to_string());
+
+    run_blocking(move || {
+        let token = tasks.start(&task_id);
+        let result = db.reset().and_then(|()| {
+            let progress = ScanProgress::default();
+            let playlists = scan_audio_dirs(&config.music_directories, &config, false, &progress, &token)?;
+            // 数据库刚被清空，不需要检查目录缩减
+            sync_playlists(&db, &config.music_directories, &playlists, !token.is_cancelled(), false, &progress);
+            db.stats()
+        });
+        tasks.finish(&task_id, &token);
+        result
+    })
+    .await
+}
@@ -0,0 +1,1119 @@
+//! 曲库数据库
+//!
+//! 扫描结果写入 SQLite（data/library.db），界面查询曲目、艺术家和专辑时不再访问文件系统。
+//! 所有写入经由同一个写连接串行执行，查询使用单独的读连接（WAL 模式下互不阻塞）。
+
+use super::schema::migrate;
+use crate::error::{AppError, AppResult};
+use crate::media::cover::CoverSource;
+use crate::media::filesystem::existing_path;
+use crate::media::network::is_network_path;
+use crate::media::organize::FileMove;
+use crate::media::sorting::{natural_cmp, SortDirection, SortField, SortSpec};
+use crate::media::TrackMetadata;
+use crate::paths::canonical_key;
+use crate::tr;
+use rusqlite::functions::FunctionFlags;
+use rusqlite::types::ToSql;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Row, Transaction};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// 数据库文件名
+pub const LIBRARY_DB_FILE: &str = "library.db";
+
+/// 打开失败时使用的内存数据库（读写连接共享同一份数据）
+const MEMORY_DB_URI: &str = "file:merplayer-library?mode=memory&cache=shared";
+
+/// 写入 `tracks` 表的元数据列，顺序与 `track_params` 一致
+const TRACK_COLUMNS: &[&str] = &[
+    "path", "name", "title", "artist", "artists", "album", "album_artist", "artist_sort", "album_artist_sort",
+    "album_sort", "genre", "year", "track_number", "track_total", "disc_number", "isrc", "label",
+    "catalog_number", "duration", "has_cover", "cover_source", "bitrate", "sample_rate", "channels", "bit_depth",
+    "format", "encoder", "container", "mp3_info", "encoding_suspect", "encoding_repair", "file_size", "tag_error",
+];
+
+/// 曲目查询条件，所有条件之间为“与”关系
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryFilter {
+    /// 在标题、艺术家、专辑和文件名中做子串匹配
+    pub text: Option<String>,
+    /// 艺术家（匹配拆分后的任一艺术家或专辑艺术家）
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub album_artist: Option<String>,
+    pub genre: Option<String>,
+    pub year: Option<u32>,
+    pub format: Option<String>,
+    /// 只返回该扫描根目录下的曲目
+    pub root: Option<String>,
+}
+
+/// 分页查询结果
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryPage {
+    pub tracks: Vec<TrackMetadata>,
+    /// 满足条件的曲目总数（不受分页影响）
+    pub total: usize,
+}
+
+/// 艺术家条目
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryArtist {
+    pub id: i64,
+    pub name: String,
+    pub sort_name: Option<String>,
+    pub track_count: usize,
+    pub album_count: usize,
+}
+
+/// 专辑条目
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryAlbum {
+    pub id: i64,
+    pub title: String,
+    pub album_artist: String,
+    pub year: Option<u32>,
+    pub track_count: usize,
+    /// 总时长（秒）
+    pub duration: f64,
+    /// 用于获取封面的代表曲目（第一个有封面的曲目）
+    pub cover_track: Option<String>,
+}
+
+/// 最近添加的一张专辑（没有专辑标签的曲目单独成组）
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RecentlyAdded {
+    pub album: Option<String>,
+    pub album_artist: Option<String>,
+    /// 组内最近一首曲目加入曲库的时间（Unix 秒）
+    pub added_at: i64,
+    /// 按碟号、音轨号排列
+    pub tracks: Vec<TrackMetadata>,
+    /// 结果是否来自曲库数据库，未扫描的目录按文件的创建或修改时间计算
+    pub indexed: bool,
+}
+
+impl RecentlyAdded {
+    /// 由同一组的曲目创建，专辑信息取自第一首
+    #[must_use]
+    pub fn from_tracks(tracks: Vec<TrackMetadata>, added_at: i64, indexed: bool) -> Self {
+        let first = tracks.first();
+        Self {
+            album: first.and_then(|track| track.album.clone()),
+            album_artist: first.and_then(|track| track.album_artist.clone().or_else(|| track.artist.clone())),
+            added_at,
+            tracks,
+            indexed,
+        }
+    }
+}
+
+/// 曲库统计
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryStats {
+    pub tracks: usize,
+    pub albums: usize,
+    pub artists: usize,
+    pub roots: usize,
+    /// 总时长（秒）
+    pub total_duration: f64,
+    /// 总文件大小（字节）
+    pub total_size: u64,
+    /// 最近一次扫描的时间（Unix 秒）
+    pub last_scanned: Option<i64>,
+}
+
+/// 曲库数据库，保存在 `AppState` 中
+pub struct LibraryDb {
+    path: PathBuf,
+    writer: Mutex<Connection>,
+    reader: Mutex<Connection>,
+    /// 每次写入后递增，用于判断派生的索引是否过期
+    revision: AtomicU64,
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| i64::try_from(d.as_secs()).unwrap_or(i64::MAX))
+}
+
+/// 数据库中的路径写法（`canonical_key`），同一文件的不同写法对应同一条记录
+#[must_use]
+pub fn track_key(path: &str) -> String {
+    canonical_key(path)
+}
+
+/// 匹配目录下所有路径的 LIKE 模式
+fn under_pattern(dir: &str) -> String {
+    format!("{}/%", escape_like(track_key(dir).trim_end_matches('/')))
+}
+
+fn open_connection(target: &str) -> AppResult<Connection> {
+    let conn = Connection::open(target)?;
+    conn.busy_timeout(Duration::from_secs(5))?;
+    conn.create_collation("NATURAL_ORDER", natural_cmp)?;
+    // 迁移时把旧的路径写法转为 `track_key`
+    let flags = FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC;
+    conn.create_scalar_function("canonical_key", 1, flags, |ctx| Ok(track_key(&ctx.get::<String>(0)?)))?;
+    conn.execute_batch("PRAGMA journal_mode = WAL; PRAGMA synchronous = NORMAL; PRAGMA foreign_keys = ON;")?;
+    Ok(conn)
+}
+
+fn open_pair(target: &str) -> AppResult<(Connection, Connection)> {
+    let mut writer = open_connection(target)?;
+    migrate(&mut writer)?;
+    // 检查文件是否损坏
+    let status: String = writer.query_row("PRAGMA quick_check", [], |row| row.get(0))?;
+    if status != "ok" {
+        return Err(AppError::Other(tr!("library.databaseCorrupt", status = status)));
+    }
+    let reader = open_connection(target)?;
+    Ok((writer, reader))
+}
+
+/// 删除数据库文件及 WAL 附属文件
+fn remove_db_files(path: &Path) {
+    for suffix in ["", "-wal", "-shm"] {
+        let mut file = path.as_os_str().to_owned();
+        file.push(suffix);
+        let _ = fs::remove_file(PathBuf::from(file));
+    }
+}
+
+fn cover_source_name(source: CoverSource) -> &'static str {
+    match source {
+        CoverSource::Embedded => "embedded",
+        CoverSource::Folder => "folder",
+        CoverSource::None => "none",
+    }
+}
+
+fn parse_cover_source(name: &str) -> CoverSource {
+    match name {
+        "embedded" => CoverSource::Embedded,
+        "folder" => CoverSource::Folder,
+        _ => CoverSource::None,
+    }
+}
+
+fn to_json<T: Serialize>(value: Option<&T>) -> Option<String> {
+    value.and_then(|v| serde_json::to_string(v).ok())
+}
+
+/// 按 `TRACK_COLUMNS` 的顺序生成参数
+fn track_params(track: &TrackMetadata) -> Vec<Box<dyn ToSql>> {
+    vec![
+        Box::new(track_key(&track.path)),
+        Box::new(track.name.clone()),
+        Box::new(track.title.clone()),
+        Box::new(track.artist.clone()),
+        Box::new(serde_json::to_string(&track.artists).unwrap_or_else(|_| "[]".to_string())),
+        Box::new(track.album.clone()),
+        Box::new(track.album_artist.clone()),
+        Box::new(track.artist_sort.clone()),
+        Box::new(track.album_artist_sort.clone()),
+        Box::new(track.album_sort.clone()),
+        Box::new(track.genre.clone()),
+        Box::new(track.year),
+        Box::new(track.track_number),
+        Box::new(track.track_total),
+        Box::new(track.disc_number),
+        Box::new(track.isrc.clone()),
+        Box::new(track.label.clone()),
+        Box::new(track.catalog_number.clone()),
+        Box::new(track.duration),
+        Box::new(track.has_cover),
+        Box::new(cover_source_name(track.cover_source)),
+        Box::new(track.bitrate),
+        Box::new(track.sample_rate),
+        Box::new(track.channels),
+        Box::new(track.bit_depth),
+        Box::new(track.format.clone()),
+        Box::new(track.encoder.clone()),
+        Box::new(track.container.clone()),
+        Box::new(to_json(track.mp3_info.as_ref())),
+        Box::new(track.encoding_suspect),
+        Box::new(to_json(track.encoding_repair.as_ref())),
+        Box::new(track.file_size),
+        Box::new(track.tag_error.clone()),
+    ]
+}
+
+/// 从 `tracks` 表的一行还原元数据（不含封面数据）
+fn track_from_row(row: &Row<'_>) -> rusqlite::Result<TrackMetadata> {
+    let artists: String = row.get("artists")?;
+    let cover_source: String = row.get("cover_source")?;
+    let mp3_info: Option<String> = row.get("mp3_info")?;
+    let encoding_repair: Option<String> = row.get("encoding_repair")?;
+
+    Ok(TrackMetadata {
+        path: row.get("path")?,
+        name: row.get("name")?,
+        title: row.get("title")?,
+        artist: row.get("artist")?,
+        artists: serde_json::from_str(&artists).unwrap_or_default(),
+        album: row.get("album")?,
+        album_artist: row.get("album_artist")?,
+        artist_sort: row.get("artist_sort")?,
+        album_artist_sort: row.get("album_artist_sort")?,
+        album_sort: row.get("album_sort")?,
+        genre: row.get("genre")?,
+        year: row.get("year")?,
+        track_number: row.get("track_number")?,
+        track_total: row.get("track_total")?,
+        disc_number: row.get("disc_number")?,
+        isrc: row.get("isrc")?,
+        label: row.get("label")?,
+        catalog_number: row.get("catalog_number")?,
+        duration: row.get("duration")?,
+        cover: None,
+        has_cover: row.get("has_cover")?,
+        cover_source: parse_cover_source(&cover_source),
+        bitrate: row.get("bitrate")?,
+        sample_rate: row.get("sample_rate")?,
+        channels: row.get("channels")?,
+        bit_depth: row.get("bit_depth")?,
+        format: row.get("format")?,
+        encoder: row.get("encoder")?,
+        container: row.get("container")?,
+        mp3_info: mp3_info.and_then(|json| serde_json::from_str(&json).ok()),
+        encoding_suspect: row.get("encoding_suspect")?,
+        encoding_repair: encoding_repair.and_then(|json| serde_json::from_str(&json).ok()),
+        file_size: row.get("file_size")?,
+        tag_error: row.get("tag_error")?,
+    })
+}
+
+/// 文件的修改时间（Unix 秒）
+///
+/// `path` 是 `track_key` 写法，非 Windows 平台上要先换回磁盘上实际的路径。
+fn file_mtime(path: &str) -> Option<i64> {
+    fs::metadata(existing_path(path)?)
+        .ok()?
+        .modified()
+        .ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()
+        .and_then(|d| i64::try_from(d.as_secs()).ok())
+}
+
+fn upsert_artist(tx: &Transaction<'_>, name: &str, sort_name: Option<&str>) -> rusqlite::Result<i64> {
+    tx.query_row(
+        "INSERT INTO artists (name, sort_name) VALUES (?1, ?2)
+         ON CONFLICT(name) DO UPDATE SET sort_name = COALESCE(excluded.sort_name, artists.sort_name)
+         RETURNING id",
+        params![name, sort_name],
+        |row| row.get(0),
+    )
+}
+
+fn upsert_album(tx: &Transaction<'_>, track: &TrackMetadata) -> rusqlite::Result<Option<i64>> {
+    let Some(title) = track.album.as_deref() else {
+        return Ok(None);
+    };
+    let album_artist = track.album_artist.as_deref().or(track.artist.as_deref()).unwrap_or_default();
+    tx.query_row(
+        "INSERT INTO albums (title, album_artist, sort_title, year) VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(title, album_artist) DO UPDATE SET
+             sort_title = COALESCE(excluded.sort_title, albums.sort_title),
+             year = COALESCE(excluded.year, albums.year)
+         RETURNING id",
+        params![title, album_artist, track.album_sort, track.year],
+        |row| row.get(0),
+    )
+    .map(Some)
+}
+
+/// 查找路径所属的扫描根目录（取最长的匹配）
+fn root_for(path: &str, roots: &[(String, i64)]) -> Option<i64> {
+    let path = canonical_key(path);
+    roots
+        .iter()
+        .filter(|(root, _)| path.starts_with(root.as_str()) && path[root.len()..].starts_with('/'))
+        .max_by_key(|(root, _)| root.len())
+        .map(|(_, id)| *id)
+}
+
+fn load_roots(tx: &Transaction<'_>) -> rusqlite::Result<Vec<(String, i64)>> {
+    let mut stmt = tx.prepare_cached("SELECT path, id FROM scan_roots")?;
+    stmt.query_map([], |row| Ok((canonical_key(&row.get::<_, String>(0)?), row.get(1)?)))?
+        .collect()
+}
+
+/// 写入一条曲目及其艺术家、专辑
+fn upsert_track(
+    tx: &Transaction<'_>,
+    track: &TrackMetadata,
+    roots: &[(String, i64)],
+    generation: i64,
+    now: i64,
+) -> rusqlite::Result<()> {
+    let album_id = upsert_album(tx, track)?;
+
+    let fixed = ["root_id", "album_id", "mtime", "size", "seen_generation", "added_at"];
+    let columns: Vec<&str> = fixed.iter().chain(TRACK_COLUMNS).copied().collect();
+    let placeholders: Vec<String> = (1..=columns.len()).map(|i| format!("?{i}")).collect();
+    let updates: Vec<String> = columns
+        .iter()
+        .filter(|column| **column != "added_at" && **column != "path")
+        .map(|column| format!("{column} = excluded.{column}"))
+        .collect();
+    let sql = format!(
+        "INSERT INTO tracks ({}) VALUES ({}) ON CONFLICT(path) DO UPDATE SET {} RETURNING id",
+        columns.join(", "),
+        placeholders.join(", "),
+        updates.join(", ")
+    );
+
+    let mut values: Vec<Box<dyn ToSql>> = vec![
+        Box::new(root_for(&track.path, roots)),
+        Box::new(album_id),
+        Box::new(file_mtime(&track.path)),
+        Box::new(track.file_size),
+        Box::new(generation),
+        Box::new(now),
+    ];
+    values.extend(track_params(track));
+    let track_id: i64 = tx
+        .prepare_cached(&sql)?
+        .query_row(params_from_iter(values.iter()), |row| row.get(0))?;
+
+    tx.execute("DELETE FROM track_artists WHERE track_id = ?1", params![track_id])?;
+    let names: Vec<&str> = if track.artists.is_empty() {
+        track.artist.as_deref().into_iter().collect()
+    } else {
+        track.artists.iter().map(String::as_str).collect()
+    };
+    let single = names.len() == 1;
+    for (position, name) in names.into_iter().enumerate() {
+        let sort_name = if single { track.artist_sort.as_deref() } else { None };
+        let artist_id = upsert_artist(tx, name, sort_name)?;
+        tx.execute(
+            "INSERT OR IGNORE INTO track_artists (track_id, artist_id, position) VALUES (?1, ?2, ?3)",
+            params![track_id, artist_id, position],
+        )?;
+    }
+    Ok(())
+}
+
+/// 删除不再被任何曲目引用的专辑和艺术家
+fn remove_orphans(tx: &Transaction<'_>) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "DELETE FROM albums WHERE id NOT IN (SELECT album_id FROM tracks WHERE album_id IS NOT NULL);
+         DELETE FROM artists WHERE id NOT IN (SELECT artist_id FROM track_artists);",
+    )
+}
+
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+/// 追加参数并返回其编号
+fn push_param(values: &mut Vec<Box<dyn ToSql>>, value: Box<dyn ToSql>) -> usize {
+    values.push(value);
+    values.len()
+}
+
+/// 根据查询条件生成 WHERE 子句与参数
+fn filter_clause(filter: &LibraryFilter) -> (String, Vec<Box<dyn ToSql>>) {
+    let mut clauses: Vec<String> = Vec::new();
+    let mut values: Vec<Box<dyn ToSql>> = Vec::new();
+
+    if let Some(text) = filter.text.as_deref().map(str::trim).filter(|t| !t.is_empty()) {
+        let n = push_param(&mut values, Box::new(format!("%{}%", escape_like(text))));
+        clauses.push(format!(
+            "(t.title LIKE ?{n} ESCAPE '\\' OR t.artist LIKE ?{n} ESCAPE '\\' OR t.album LIKE ?{n} ESCAPE '\\' \
+             OR t.album_artist LIKE ?{n} ESCAPE '\\' OR t.name LIKE ?{n} ESCAPE '\\')"
+        ));
+    }
+    if let Some(artist) = &filter.artist {
+        let n = push_param(&mut values, Box::new(artist.clone()));
+        clauses.push(format!(
+            "(t.album_artist = ?{n} COLLATE NOCASE OR t.id IN (SELECT ta.track_id FROM track_artists ta \
+             JOIN artists a ON a.id = ta.artist_id WHERE a.name = ?{n} COLLATE NOCASE))"
+        ));
+    }
+    for (column, value) in [
+        ("album", &filter.album),
+        ("album_artist", &filter.album_artist),
+        ("genre", &filter.genre),
+        ("format", &filter.format),
+    ] {
+        if let Some(value) = value {
+            let n = push_param(&mut values, Box::new(value.clone()));
+            clauses.push(format!("t.{column} = ?{n} COLLATE NOCASE"));
+        }
+    }
+    if let Some(year) = filter.year {
+        let n = push_param(&mut values, Box::new(year));
+        clauses.push(format!("t.year = ?{n}"));
+    }
+    if let Some(root) = &filter.root {
+        let n = push_param(&mut values, Box::new(root.clone()));
+        clauses.push(format!("t.root_id = (SELECT id FROM scan_roots WHERE path = ?{n})"));
+    }
+
+    let clause = if clauses.is_empty() { String::new() } else { format!("WHERE {}", clauses.join(" AND ")) };
+    (clause, values)
+}
+
+/// 排序字段对应的 SQL 表达式，文本使用自然顺序
+fn order_terms(field: SortField) -> &'static [&'static str] {
+    match field {
+        SortField::Title => &["COALESCE(t.title, t.name) COLLATE NATURAL_ORDER"],
+        SortField::Artist => &[
+            "COALESCE(t.album_artist_sort, t.album_artist, t.artist_sort, t.artist) COLLATE NATURAL_ORDER",
+            "COALESCE(t.album_sort, t.album) COLLATE NATURAL_ORDER",
+            "t.disc_number",
+            "t.track_number",
+        ],
+        SortField::Album => &["COALESCE(t.album_sort, t.album) COLLATE NATURAL_ORDER", "t.disc_number", "t.track_number"],
+        SortField::TrackNumber => &["t.disc_number", "t.track_number"],
+        SortField::Year => &["t.year"],
+        SortField::Duration => &["t.duration"],
+        SortField::Path => &["t.path COLLATE NATURAL_ORDER"],
+        SortField::DateModified => &["t.mtime"],
+        SortField::DateAdded => &["t.added_at"],
+    }
+}
+
+fn order_clause(sort: SortSpec) -> String {
+    let direction = if sort.direction == SortDirection::Desc { "DESC" } else { "ASC" };
+    let mut terms: Vec<String> = order_terms(sort.field).iter().map(|term| format!("{term} {direction}")).collect();
+    if let Some(secondary) = sort.secondary {
+        terms.extend(order_terms(secondary).iter().map(|term| format!("{term} ASC")));
+    }
+    terms.push("t.path COLLATE NATURAL_ORDER ASC".to_string());
+    format!("ORDER BY {}", terms.join(", "))
+}
+
+impl LibraryDb {
+    fn touch(&self) {
+        self.revision.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 数据库内容的版本号，任何写入后都会变化
+    #[must_use]
+    pub fn revision(&self) -> u64 {
+        self.revision.load(Ordering::Relaxed)
+    }
+
+    /// 打开（必要时创建并迁移）数据库
+    pub fn open(path: &Path) -> AppResult<Self> {
+        let (writer, reader) = open_pair(&path.to_string_lossy())?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            writer: Mutex::new(writer),
+            reader: Mutex::new(reader),
+            revision: AtomicU64::new(0),
+        })
+    }
+
+    /// 打开数据库，失败时删除损坏的文件重新创建，仍失败则退回内存数据库
+    #[must_use]
+    pub fn open_or_recreate(path: &Path) -> Self {
+        match Self::open(path) {
+            Ok(db) => return db,
+            Err(e) => eprintln!("Failed to open library database, recreating: {e}"),
+        }
+
+        remove_db_files(path);
+        match Self::open(path) {
+            Ok(db) => db,
+            Err(e) => {
+                eprintln!("Failed to recreate library database, using in-memory database: {e}");
+                let (writer, reader) = open_pair(MEMORY_DB_URI).expect("无法创建内存数据库");
+                Self {
+                    path: path.to_path_buf(),
+                    writer: Mutex::new(writer),
+                    reader: Mutex::new(reader),
+                    revision: AtomicU64::new(0),
+                }
+            }
+        }
+    }
+
+    /// 删除数据库文件并重新创建空的数据库
+    pub fn reset(&self) -> AppResult<()> {
+        let mut writer = self.writer.lock().unwrap();
+        let mut reader = self.reader.lock().unwrap();
+        // 先关闭现有连接才能删除文件
+        *writer = Connection::open_in_memory()?;
+        *reader = Connection::open_in_memory()?;
+
+        remove_db_files(&self.path);
+        let (new_writer, new_reader) = open_pair(&self.path.to_string_lossy())?;
+        *writer = new_writer;
+        *reader = new_reader;
+        self.touch();
+        Ok(())
+    }
+
+    /// 写入扫描结果
+    ///
+    /// `roots` 为本次扫描的根目录。`complete` 为 `true` 时，这些根目录下本次未出现的曲目会被删除；
+    /// 扫描被取消时应传入 `false`，只更新已读取的曲目。
+    pub fn sync_scan(&self, roots: &[String], tracks: &[TrackMetadata], complete: bool) -> AppResult<()> {
+        let mut conn = self.writer.lock().unwrap();
+        let tx = conn.transaction()?;
+        let now = unix_now();
+
+        let mut root_ids = Vec::new();
+        for root in roots {
+            let id: i64 = tx.query_row(
+                "INSERT INTO scan_roots (path, last_scanned, is_network) VALUES (?1, ?2, ?3)
+                 ON CONFLICT(path) DO UPDATE SET
+                     last_scanned = excluded.last_scanned, is_network = excluded.is_network, offline_since = NULL
+                 RETURNING id",
+                params![root, now, is_network_path(Path::new(root))],
+                |row| row.get(0),
+            )?;
+            root_ids.push(id);
+        }
+
+        let generation: i64 =
+            tx.query_row("SELECT COALESCE(MAX(seen_generation), 0) + 1 FROM tracks", [], |row| row.get(0))?;
+        let all_roots = load_roots(&tx)?;
+        for track in tracks {
+            upsert_track(&tx, track, &all_roots, generation, now)?;
+        }
+
+        if complete {
+            for id in root_ids {
+                tx.execute(
+                    "DELETE FROM tracks WHERE root_id = ?1 AND seen_generation <> ?2",
+                    params![id, generation],
+                )?;
+            }
+        }
+        remove_orphans(&tx)?;
+        tx.commit()?;
+        self.touch();
+        Ok(())
+    }
+
+    /// 新增或更新曲目（不影响其他曲目）
+    pub fn upsert_tracks(&self, tracks: &[TrackMetadata]) -> AppResult<()> {
+        if tracks.is_empty() {
+            return Ok(());
+        }
+        let mut conn = self.writer.lock().unwrap();
+        let tx = conn.transaction()?;
+        let now = unix_now();
+        let generation: i64 =
+            tx.query_row("SELECT COALESCE(MAX(seen_generation), 0) FROM tracks", [], |row| row.get(0))?;
+        let roots = load_roots(&tx)?;
+        for track in tracks {
+            upsert_track(&tx, track, &roots, generation, now)?;
+        }
+        remove_orphans(&tx)?;
+        tx.commit()?;
+        self.touch();
+        Ok(())
+    }
+
+    /// 更新已在曲库中的曲目，不在曲库中时返回 `false`
+    pub fn update_track(&self, track: &TrackMetadata) -> AppResult<bool> {
+        let exists = self.get_track(&track.path)?.is_some();
+        if exists {
+            self.upsert_tracks(std::slice::from_ref(track))?;
+        }
+        Ok(exists)
+    }
+
+    /// 删除曲目
+    pub fn remove_tracks(&self, paths: &[String]) -> AppResult<()> {
+        if paths.is_empty() {
+            return Ok(());
+        }
+        let mut conn = self.writer.lock().unwrap();
+        let tx = conn.transaction()?;
+        for path in paths {
+            tx.execute("DELETE FROM tracks WHERE path = ?1", params![track_key(path)])?;
+        }
+        remove_orphans(&tx)?;
+        tx.commit()?;
+        self.touch();
+        Ok(())
+    }
+
+    /// 删除目录下的所有曲目（目录被删除或移出曲库时使用）
+    pub fn remove_under(&self, dir: &str) -> AppResult<()> {
+        let prefix = under_pattern(dir);
+        let mut conn = self.writer.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM tracks WHERE path LIKE ?1 ESCAPE '\\'", params![prefix])?;
+        remove_orphans(&tx)?;
+        tx.commit()?;
+        self.touch();
+        Ok(())
+    }
+
+    /// 更新移动过的曲目路径，保留曲目 ID 与加入时间
+    pub fn move_tracks(&self, moves: &[FileMove]) -> AppResult<()> {
+        if moves.is_empty() {
+            return Ok(());
+        }
+        let mut conn = self.writer.lock().unwrap();
+        let tx = conn.transaction()?;
+        for file_move in moves {
+            let to = track_key(&file_move.to);
+            // 目标路径已有记录时（如扫描先写入了新路径），保留旧记录的身份
+            tx.execute("DELETE FROM tracks WHERE path = ?1", params![to])?;
+            tx.execute(
+                "UPDATE OR REPLACE lyrics_offsets SET path = ?2 WHERE path = ?1",
+                params![track_key(&file_move.from), to],
+            )?;
+            tx.execute(
+                "UPDATE tracks SET path = ?2, name = ?3 WHERE path = ?1",
+                params![
+                    track_key(&file_move.from),
+                    to,
+                    Path::new(&file_move.to)
+                        .file_name()
+                        .map(|n| n.to_string_lossy().to_string())
+                        .unwrap_or_default()
+                ],
+            )?;
+        }
+        remove_orphans(&tx)?;
+        tx.commit()?;
+        self.touch();
+        Ok(())
+    }
+
+    /// 分页查询曲目
+    pub fn query(&self, filter: &LibraryFilter, sort: &SortSpec, limit: usize, offset: usize) -> AppResult<LibraryPage> {
+        let conn = self.reader.lock().unwrap();
+        let (clause, values) = filter_clause(filter);
+
+        let total: usize = conn.query_row(
+            &format!("SELECT COUNT(*) FROM tracks t {clause}"),
+            params_from_iter(values.iter()),
+            |row| row.get(0),
+        )?;
+
+        let sql = format!("SELECT t.* FROM tracks t {clause} {} LIMIT {limit} OFFSET {offset}", order_clause(*sort));
+        let mut stmt = conn.prepare(&sql)?;
+        let tracks = stmt
+            .query_map(params_from_iter(values.iter()), track_from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(LibraryPage { tracks, total })
+    }
+
+    /// 读取全部曲目
+    pub fn all_tracks(&self) -> AppResult<Vec<TrackMetadata>> {
+        let conn = self.reader.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT * FROM tracks")?;
+        let tracks = stmt.query_map([], track_from_row)?.collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(tracks)
+    }
+
+    /// 曲库中所有曲目的路径
+    pub fn all_paths(&self) -> AppResult<Vec<String>> {
+        let conn = self.reader.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT path FROM tracks")?;
+        let paths = stmt.query_map([], |row| row.get(0))?.collect::<rusqlite::Result<Vec<String>>>()?;
+        Ok(paths)
+    }
+
+    /// 目录下的曲目路径
+    pub fn paths_under(&self, dir: &str) -> AppResult<Vec<String>> {
+        let prefix = under_pattern(dir);
+        let conn = self.reader.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT path FROM tracks WHERE path LIKE ?1 ESCAPE '\\'")?;
+        let paths = stmt.query_map(params![prefix], |row| row.get(0))?.collect::<rusqlite::Result<Vec<String>>>()?;
+        Ok(paths)
+    }
+
+    /// 位于网络共享上的扫描根目录
+    pub fn network_roots(&self) -> AppResult<Vec<String>> {
+        let conn = self.reader.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT path FROM scan_roots WHERE is_network <> 0")?;
+        let roots = stmt.query_map([], |row| row.get(0))?.collect::<rusqlite::Result<Vec<String>>>()?;
+        Ok(roots)
+    }
+
+    /// 标记根目录离线（如驱动器被拔出）或恢复在线，返回根目录是否在曲库中
+    ///
+    /// 离线根目录中的曲目保留在曲库中；已离线的根目录再次标记时保留原来的离线时间。
+    pub fn set_root_offline(&self, root: &str, offline: bool) -> AppResult<bool> {
+        let key = canonical_key(root);
+        let conn = self.writer.lock().unwrap();
+        let roots = conn
+            .prepare("SELECT id, path FROM scan_roots")?
+            .query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let ids: Vec<i64> =
+            roots.into_iter().filter(|(_, path)| canonical_key(path) == key).map(|(id, _)| id).collect();
+        for id in &ids {
+            if offline {
+                conn.execute(
+                    "UPDATE scan_roots SET offline_since = COALESCE(offline_since, ?2) WHERE id = ?1",
+                    params![id, unix_now()],
+                )?;
+            } else {
+                conn.execute("UPDATE scan_roots SET offline_since = NULL WHERE id = ?1", params![id])?;
+            }
+        }
+        drop(conn);
+        self.touch();
+        Ok(!ids.is_empty())
+    }
+
+    /// 被标记为离线的根目录及离线开始时间（Unix 秒）
+    pub fn offline_roots(&self) -> AppResult<Vec<(String, i64)>> {
+        let conn = self.reader.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT path, offline_since FROM scan_roots WHERE offline_since IS NOT NULL")?;
+        let roots = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?.collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(roots)
+    }
+
+    /// 目录下的曲目数
+    pub fn count_under(&self, dir: &str) -> AppResult<usize> {
+        let prefix = under_pattern(dir);
+        let conn = self.reader.lock().unwrap();
+        let count = conn.query_row(
+            "SELECT COUNT(*) FROM tracks WHERE path LIKE ?1 ESCAPE '\\'",
+            params![prefix],
+            |row| row.get(0),
+        )?;
+        Ok(count)
+    }
+
+    /// 目录是否位于已扫描的根目录中（根目录本身也算）
+    pub fn is_indexed(&self, dir: &str) -> AppResult<bool> {
+        let dir = canonical_key(dir);
+        let conn = self.reader.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT path FROM scan_roots")?;
+        let roots = stmt.query_map([], |row| row.get::<_, String>(0))?.collect::<rusqlite::Result<Vec<String>>>()?;
+        Ok(roots.iter().map(|root| canonical_key(root)).any(|root| {
+            dir == root || (dir.starts_with(&root) && dir[root.len()..].starts_with('/'))
+        }))
+    }
+
+    /// 目录下每个曲目的路径、文件大小（字节）和时长（秒）
+    pub fn file_stats_under(&self, dir: &str) -> AppResult<Vec<(String, u64, Option<f64>)>> {
+        let prefix = under_pattern(dir);
+        let conn = self.reader.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT path, COALESCE(file_size, size, 0), duration FROM tracks WHERE path LIKE ?1 ESCAPE '\\'",
+        )?;
+        let rows = stmt
+            .query_map(params![prefix], |row| {
+                Ok((row.get(0)?, row.get::<_, i64>(1)?.try_into().unwrap_or(0), row.get(2)?))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+    }
+
+    /// 最近加入曲库的曲目，按专辑分组，最新的组在前，最多返回 `limit` 组
+    ///
+    /// 只包含 `since`（Unix 秒）之后加入的曲目，专辑中较早加入的曲目不会出现在组内。
+    pub fn recently_added(&self, since: i64, limit: usize) -> AppResult<Vec<RecentlyAdded>> {
+        let conn = self.reader.lock().unwrap();
+        let mut groups_stmt = conn.prepare(
+            "SELECT album_id, CASE WHEN album_id IS NULL THEN id END AS single, MAX(added_at) AS latest
+             FROM tracks
+             WHERE added_at >= ?1
+             GROUP BY album_id, single
+             ORDER BY latest DESC
+             LIMIT ?2",
+        )?;
+        let groups = groups_stmt
+            .query_map(params![since, i64::try_from(limit).unwrap_or(i64::MAX)], |row| {
+                Ok((row.get::<_, Option<i64>>(0)?, row.get::<_, Option<i64>>(1)?, row.get::<_, i64>(2)?))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let mut album_stmt = conn.prepare(
+            "SELECT * FROM tracks WHERE album_id = ?1 AND added_at >= ?2 ORDER BY disc_number, track_number, path",
+        )?;
+        let mut single_stmt = conn.prepare("SELECT * FROM tracks WHERE id = ?1")?;
+        let mut recent = Vec::with_capacity(groups.len());
+        for (album_id, single, added_at) in groups {
+            let tracks = match (album_id, single) {
+                (Some(album_id), _) => album_stmt.query_map(params![album_id, since], track_from_row)?,
+                (None, Some(id)) => single_stmt.query_map(params![id], track_from_row)?,
+                (None, None) => continue,
+            }
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+            recent.push(RecentlyAdded::from_tracks(tracks, added_at, true));
+        }
+        Ok(recent)
+    }
+
+    /// 曲目加入曲库的时间（Unix 秒），不在曲库中时返回 `None`
+    pub fn added_at(&self, path: &str) -> AppResult<Option<i64>> {
+        let conn = self.reader.lock().unwrap();
+        let added_at = conn
+            .query_row("SELECT added_at FROM tracks WHERE path = ?1", params![track_key(path)], |row| row.get(0))
+            .optional()?;
+        Ok(added_at)
+    }
+
+    /// 批量读取加入曲库的时间，键为 `track_key`（`canonical_key`）写法的路径，不在曲库中的路径不会出现在结果中
+    pub fn added_times<'a>(&self, paths: impl IntoIterator<Item = &'a str>) -> AppResult<HashMap<String, i64>> {
+        let conn = self.reader.lock().unwrap();
+        let mut stmt = conn.prepare_cached("SELECT added_at FROM tracks WHERE path = ?1")?;
+        let mut times = HashMap::new();
+        for path in paths {
+            let key = track_key(path);
+            if let Some(added_at) = stmt.query_row(params![key], |row| row.get(0)).optional()? {
+                times.insert(key, added_at);
+            }
+        }
+        Ok(times)
+    }
+
+    /// 曲目的歌词时间偏移（毫秒），没有设置时为 0
+    pub fn lyrics_offset(&self, path: &str) -> AppResult<i64> {
+        let conn = self.reader.lock().unwrap();
+        let offset = conn
+            .query_row("SELECT offset_ms FROM lyrics_offsets WHERE path = ?1", params![track_key(path)], |row| {
+                row.get(0)
+            })
+            .optional()?;
+        Ok(offset.unwrap_or(0))
+    }
+
+    /// 设置曲目的歌词时间偏移，为 0 时删除记录
+    pub fn set_lyrics_offset(&self, path: &str, offset_ms: i64) -> AppResult<()> {
+        let conn = self.writer.lock().unwrap();
+        if offset_ms == 0 {
+            conn.execute("DELETE FROM lyrics_offsets WHERE path = ?1", params![track_key(path)])?;
+        } else {
+            conn.execute(
+                "INSERT INTO lyrics_offsets (path, offset_ms) VALUES (?1, ?2)
+                 ON CONFLICT(path) DO UPDATE SET offset_ms = excluded.offset_ms",
+                params![track_key(path), offset_ms],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// 读取单个曲目
+    pub fn get_track(&self, path: &str) -> AppResult<Option<TrackMetadata>> {
+        let conn = self.reader.lock().unwrap();
+        let track = conn
+            .query_row("SELECT * FROM tracks WHERE path = ?1", params![track_key(path)], track_from_row)
+            .optional()?;
+        Ok(track)
+    }
+
+    /// 艺术家列表（按排序名）
+    pub fn artists(&self) -> AppResult<Vec<LibraryArtist>> {
+        let conn = self.reader.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT a.id, a.name, a.sort_name, COUNT(DISTINCT ta.track_id), COUNT(DISTINCT t.album_id)
+             FROM artists a
+             JOIN track_artists ta ON ta.artist_id = a.id
+             JOIN tracks t ON t.id = ta.track_id
+             GROUP BY a.id
+             ORDER BY COALESCE(a.sort_name, a.name) COLLATE NOCASE",
+        )?;
+        let artists = stmt
+            .query_map([], |row| {
+                Ok(LibraryArtist {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    sort_name: row.get(2)?,
+                    track_count: row.get(3)?,
+                    album_count: row.get(4)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(artists)
+    }
+
+    /// 专辑列表，`artist` 不为空时只返回该艺术家参与的专辑
+    pub fn albums(&self, artist: Option<&str>) -> AppResult<Vec<LibraryAlbum>> {
+        let conn = self.reader.lock().unwrap();
+        let filter = if artist.is_some() {
+            "WHERE al.album_artist = ?1 COLLATE NOCASE OR al.id IN (
+                 SELECT t2.album_id FROM tracks t2
+                 JOIN track_artists ta ON ta.track_id = t2.id
+                 JOIN artists a ON a.id = ta.artist_id
+                 WHERE a.name = ?1)"
+        } else {
+            ""
+        };
+        let sql = format!(
+            "SELECT al.id, al.title, al.album_artist, al.year, COUNT(t.id), COALESCE(SUM(t.duration), 0),
+                    (SELECT c.path FROM tracks c WHERE c.album_id = al.id AND c.has_cover = 1
+                     ORDER BY c.disc_number, c.track_number LIMIT 1)
+             FROM albums al
+             JOIN tracks t ON t.album_id = al.id
+             {filter}
+             GROUP BY al.id
+             ORDER BY al.album_artist COLLATE NOCASE, COALESCE(al.sort_title, al.title) COLLATE NOCASE"
+        );
+        let mut stmt = conn.prepare(&sql)?;
+        let map = |row: &Row<'_>| {
+            Ok(LibraryAlbum {
+                id: row.get(0)?,
+                title: row.get(1)?,
+                album_artist: row.get(2)?,
+                year: row.get(3)?,
+                track_count: row.get(4)?,
+                duration: row.get(5)?,
+                cover_track: row.get(6)?,
+            })
+        };
+        let albums = match artist {
+            Some(artist) => stmt.query_map(params![artist], map)?.collect::<rusqlite::Result<Vec<_>>>()?,
+            None => stmt.query_map([], map)?.collect::<rusqlite::Result<Vec<_>>>()?,
+        };
+        Ok(albums)
+    }
+
+    /// 曲库统计
+    pub fn stats(&self) -> AppResult<LibraryStats> {
+        let conn = self.reader.lock().unwrap();
+        let stats = conn.query_row(
+            "SELECT
+                 (SELECT COUNT(*) FROM tracks),
+                 (SELECT COUNT(*) FROM albums),
+                 (SELECT COUNT(*) FROM artists),
+                 (SELECT COUNT(*) FROM scan_roots),
+                 (SELECT COALESCE(SUM(duration), 0) FROM tracks),
+                 (SELECT COALESCE(SUM(file_size), 0) FROM tracks),
+                 (SELECT MAX(last_scanned) FROM scan_roots)",
+            [],
+            |row| {
+                Ok(LibraryStats {
+                    tracks: row.get(0)?,
+                    albums: row.get(1)?,
+                    artists: row.get(2)?,
+                    roots: row.get(3)?,
+                    total_duration: row.get(4)?,
+                    total_size: row.get::<_, i64>(5)?.try_into().unwrap_or(0),
+                    last_scanned: row.get(6)?,
+                })
+            },
+        )?;
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use tempfile::TempDir;
+
+    #[test]
+    fn upsert_records_the_modification_time_of_the_file_on_disk() {
+        let dir = TempDir::new().unwrap();
+        let db = LibraryDb::open(&dir.path().join(LIBRARY_DB_FILE)).unwrap();
+        let path = dir.path().join("song.flac");
+        File::create(&path).unwrap().set_modified(UNIX_EPOCH + Duration::from_secs(1_234_567)).unwrap();
+        let path = path.to_string_lossy();
+        let track = TrackMetadata { path: track_key(&path), name: "song.flac".to_string(), ..Default::default() };
+
+        db.upsert_tracks(&[track]).unwrap();
+
+        let conn = db.reader.lock().unwrap();
+        let mtime: Option<i64> = conn
+            .query_row("SELECT mtime FROM tracks WHERE path = ?1", params![track_key(&path)], |row| row.get(0))
+            .unwrap();
+        assert_eq!(mtime, Some(1_234_567));
+    }
+
+    #[test]
+    fn artist_filters_ignore_case() {
+        let dir = TempDir::new().unwrap();
+        let db = LibraryDb::open(&dir.path().join(LIBRARY_DB_FILE)).unwrap();
+        let track = TrackMetadata {
+            path: "/music/song.flac".to_string(),
+            name: "song.flac".to_string(),
+            artist: Some("Jay Chou".to_string()),
+            artists: vec!["Jay Chou".to_string()],
+            album: Some("Ye Hui Mei".to_string()),
+            album_artist: Some("Various Artists".to_string()),
+            ..Default::default()
+        };
+        db.upsert_tracks(&[track]).unwrap();
+
+        let count = |filter: LibraryFilter| db.query(&filter, &SortSpec::default(), 10, 0).unwrap().total;
+        assert_eq!(count(LibraryFilter { artist: Some("JAY CHOU".to_string()), ..Default::default() }), 1);
+        assert_eq!(count(LibraryFilter { artist: Some("various artists".to_string()), ..Default::default() }), 1);
+        assert_eq!(count(LibraryFilter { album_artist: Some("VARIOUS ARTISTS".to_string()), ..Default::default() }), 1);
+        assert_eq!(count(LibraryFilter { artist: Some("Jay".to_string()), ..Default::default() }), 0);
+    }
+
+    #[test]
+    fn queries_sort_by_every_field_in_natural_order() {
+        let dir = TempDir::new().unwrap();
+        let db = LibraryDb::open(&dir.path().join(LIBRARY_DB_FILE)).unwrap();
+        let tracks: Vec<_> = [10, 9]
+            .into_iter()
+            .map(|n| TrackMetadata {
+                path: format!("/music/{n}.flac"),
+                name: format!("{n}.flac"),
+                title: Some(format!("Track {n}")),
+                ..Default::default()
+            })
+            .collect();
+        db.upsert_tracks(&tracks).unwrap();
+
+        for field in [
+            SortField::Title,
+            SortField::Artist,
+            SortField::Album,
+            SortField::TrackNumber,
+            SortField::Year,
+            SortField::Duration,
+            SortField::Path,
+            SortField::DateModified,
+            SortField::DateAdded,
+        ] {
+            let sort = SortSpec { field, ..SortSpec::default() };
+            assert_eq!(db.query(&LibraryFilter::default(), &sort, 10, 0).unwrap().total, 2, "{field:?}");
+        }
+
+        let sort = SortSpec { field: SortField::Title, ..SortSpec::default() };
+        let page = db.query(&LibraryFilter::default(), &sort, 10, 0).unwrap();
+        let titles: Vec<_> = page.tracks.iter().filter_map(|track| track.title.as_deref()).collect();
+        assert_eq!(titles, ["Track 9", "Track 10"]);
+    }
+
+    #[test]
+    fn legacy_backslash_paths_are_rekeyed() {
+        let dir = TempDir::new().unwrap();
+        let db_path = dir.path().join(LIBRARY_DB_FILE);
+        {
+            let db = LibraryDb::open(&db_path).unwrap();
+            let conn = db.writer.lock().unwrap();
+            conn.execute_batch(
+                r"INSERT INTO tracks (path, name, added_at) VALUES ('\music\a.flac', 'a.flac', 1);
+                  INSERT INTO tracks (path, name, added_at) VALUES ('/music/a.flac', 'a.flac', 2);
+                  INSERT INTO tracks (path, name, added_at) VALUES ('\music\b.flac', 'b.flac', 3);
+                  INSERT INTO lyrics_offsets (path, offset_ms) VALUES ('\music\b.flac', 250);
+                  PRAGMA user_version = 5;",
+            )
+            .unwrap();
+        }
+
+        let db = LibraryDb::open(&db_path).unwrap();
+        let mut paths = db.all_paths().unwrap();
+        paths.sort();
+        assert_eq!(paths, ["/music/a.flac", "/music/b.flac"]);
+        assert_eq!(db.lyrics_offset(r"\music\b.flac").unwrap(), 250);
+        assert_eq!(db.added_at("/music/b.flac").unwrap(), Some(3));
+        assert_eq!(db.paths_under(r"\music").unwrap().len(), 2);
+    }
+}
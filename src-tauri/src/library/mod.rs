@@ -0,0 +1,81 @@
+//! 曲库模块
+//!
+//! 把扫描得到的曲目保存在 SQLite 数据库中，供界面按条件查询。
+
+pub mod commands;
+pub mod db;
+pub mod playlist_match;
+pub mod recent;
+mod schema;
+pub mod search;
+
+use crate::media::filesystem::ScanProgress;
+use crate::media::missing::find_missing_paths;
+use crate::media::{Playlist, TrackMetadata};
+use crate::tasks::CancelToken;
+use crate::tr;
+use std::path::Path;
+
+// 重新导出常用类型
+pub use db::{
+    track_key, LibraryAlbum, LibraryArtist, LibraryDb, LibraryFilter, LibraryPage, LibraryStats, RecentlyAdded,
+    LIBRARY_DB_FILE,
+};
+pub use schema::SCHEMA_VERSION;
+
+/// 曲目数减少到原来的这个比例以下时视为目录异常缩减
+const SHRINK_RATIO: f64 = 0.5;
+
+/// 原有曲目少于这个数时不检查缩减
+const SHRINK_MIN_TRACKS: usize = 20;
+
+/// 根目录中的曲目是否比曲库记录骤减，是则检查丢失的文件并记录警告
+fn is_shrunk(db: &LibraryDb, root: &str, tracks: &[TrackMetadata], progress: &ScanProgress) -> bool {
+    let previous = match db.paths_under(root) {
+        Ok(paths) => paths,
+        Err(e) => {
+            eprintln!("Failed to read library tracks under '{root}': {e}");
+            return false;
+        }
+    };
+    let prefix = format!("{}/", track_key(root).trim_end_matches('/'));
+    let current = tracks.iter().filter(|track| track_key(&track.path).starts_with(&prefix)).count();
+    if previous.len() < SHRINK_MIN_TRACKS || current as f64 >= previous.len() as f64 * SHRINK_RATIO {
+        return false;
+    }
+
+    let missing = find_missing_paths(&previous, &CancelToken::default(), |_, _| {}).map_or(0, |check| check.missing.len());
+    progress.warn(
+        Path::new(root),
+        tr!("scan.tracksMissing", previous = previous.len(), current = current, missing = missing),
+    );
+    true
+}
+
+/// 把扫描结果写入曲库，失败时只记录日志
+///
+/// 不存在的目录（如未挂载的移动硬盘）以及无法访问或读取超时的网络目录不参与同步，其中的曲目会保留。
+/// `complete` 为 `false`（扫描被取消）时不删除任何曲目。`guard_shrunk` 为 `true` 时，
+/// 曲目数骤减的目录也不删除曲目，只通过 `progress` 记录警告，等用户确认后再清理。
+pub fn sync_playlists(
+    db: &LibraryDb,
+    paths: &[String],
+    playlists: &[Playlist],
+    complete: bool,
+    guard_shrunk: bool,
+    progress: &ScanProgress,
+) {
+    search::remember_scan(playlists);
+    let tracks: Vec<_> = playlists.iter().flat_map(|playlist| playlist.files.iter().cloned()).collect();
+    let mut roots: Vec<String> = paths
+        .iter()
+        .filter(|path| !progress.is_incomplete(Path::new(path)) && Path::new(path).is_dir())
+        .cloned()
+        .collect();
+    if complete && guard_shrunk {
+        roots.retain(|root| !is_shrunk(db, root, &tracks, progress));
+    }
+    if let Err(e) = db.sync_scan(&roots, &tracks, complete) {
+        eprintln!("Failed to update library database: {e}");
+    }
+}
@@ -0,0 +1,111 @@
+//! 把在线歌单对应到曲库中的本地文件
+//!
+//! 每首歌先按标题在曲库中搜索（搜不到时去掉括号中的版本说明再搜），再用自动匹配歌词的打分规则比较标题、艺术家、
+//! 专辑和时长，得分达到阈值且时长相近的最佳结果作为对应的本地文件。
+
+use super::db::LibraryDb;
+use super::search::search_library_internal;
+use crate::media::lyrics_match::{
+    score_candidate, MatchScore, TrackQuery, CONFIDENCE_THRESHOLD, DURATION_TOLERANCE_SECS,
+};
+use crate::media::netease::{OnlinePlaylistEntry, SearchSongResult};
+use crate::media::TrackMetadata;
+use serde::Serialize;
+use std::time::Duration;
+
+/// 每首歌在曲库中搜索的结果数
+const SEARCH_LIMIT: usize = 20;
+
+/// 一首歌的对应结果
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaylistEntryMatch {
+    pub entry: OnlinePlaylistEntry,
+    /// 对应的本地文件，没有找到时为 `None`
+    pub path: Option<String>,
+    pub score: Option<MatchScore>,
+}
+
+/// 整个歌单的对应结果
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaylistMatchResult {
+    /// 按歌单顺序排列的每首歌的结果
+    pub entries: Vec<PlaylistEntryMatch>,
+    /// 找到的本地文件，按歌单顺序排列，可直接加入播放队列或保存为播放列表
+    pub paths: Vec<String>,
+    /// 没有找到的歌曲
+    pub missing: Vec<OnlinePlaylistEntry>,
+}
+
+/// 去掉标题末尾括号中的版本说明，如 `(Live)`、`（伴奏）`
+fn base_title(title: &str) -> &str {
+    title.find(['(', '（', '[', '【']).map_or(title, |index| title[..index].trim_end())
+}
+
+/// 按标题搜索曲库
+fn search_by_title(db: &LibraryDb, title: &str) -> Vec<TrackMetadata> {
+    let query = |title: &str| format!("title:\"{}\"", title.replace('"', " "));
+    let hits = search_library_internal(db, &query(title), SEARCH_LIMIT);
+    let base = base_title(title);
+    if hits.is_empty() && !base.is_empty() && base != title {
+        return search_library_internal(db, &query(base), SEARCH_LIMIT);
+    }
+    hits
+}
+
+/// 本地音轨转换为打分用的候选
+fn as_candidate(track: &TrackMetadata) -> SearchSongResult {
+    let local = TrackQuery::from_metadata(track);
+    SearchSongResult {
+        id: track.path.clone(),
+        name: local.title,
+        artist: local.artist.unwrap_or_default(),
+        album: local.album.unwrap_or_default(),
+        album_id: None,
+        cover_url: None,
+        duration: local
+            .duration
+            .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+            .and_then(|duration| i64::try_from(duration.as_millis()).ok())
+            .unwrap_or(0),
+    }
+}
+
+/// 为一首歌找到最匹配的本地文件
+fn match_entry(db: &LibraryDb, entry: &OnlinePlaylistEntry) -> Option<(String, MatchScore)> {
+    if entry.title.trim().is_empty() {
+        return None;
+    }
+    let query = TrackQuery {
+        title: entry.title.clone(),
+        artist: Some(entry.artist.clone()).filter(|artist| !artist.trim().is_empty()),
+        album: Some(entry.album.clone()).filter(|album| !album.trim().is_empty()),
+        duration: (entry.duration > 0).then(|| entry.duration as f64 / 1000.0),
+        ..Default::default()
+    };
+
+    search_by_title(db, &entry.title)
+        .iter()
+        .map(|track| (track.path.clone(), score_candidate(&query, &as_candidate(track))))
+        .filter(|(_, score)| score.total >= CONFIDENCE_THRESHOLD)
+        .filter(|(_, score)| score.duration_diff.is_none_or(|diff| diff <= DURATION_TOLERANCE_SECS))
+        .max_by(|(_, a), (_, b)| a.total.total_cmp(&b.total))
+}
+
+/// 把歌单中的每首歌对应到曲库中的本地文件
+#[must_use]
+pub fn match_online_playlist_internal(db: &LibraryDb, entries: Vec<OnlinePlaylistEntry>) -> PlaylistMatchResult {
+    let mut result =
+        PlaylistMatchResult { entries: Vec::with_capacity(entries.len()), paths: Vec::new(), missing: Vec::new() };
+    for entry in entries {
+        let found = match_entry(db, &entry);
+        match &found {
+            Some((path, _)) => result.paths.push(path.clone()),
+            None => result.missing.push(entry.clone()),
+        }
+        let (path, score) = found.unzip();
+        result.entries.push(PlaylistEntryMatch { entry, path, score });
+    }
+    result
+}
@@ -0,0 +1,86 @@
+//! 最近添加的曲目
+//!
+//! 曲库中的曲目按首次被扫描或监视器发现的时间排列；尚未扫描到曲库的音乐目录按文件的创建时间
+//! （不支持时用修改时间）计算，并按专辑标签分组。
+
+use super::db::{LibraryDb, RecentlyAdded};
+use crate::error::AppResult;
+use crate::media::filesystem::collect_audio_paths_filtered;
+use crate::media::metadata_cache::cached_track_metadata;
+use crate::media::scan_filter::ScanFilter;
+use crate::media::sorting::natural_cmp;
+use crate::media::text::grouping_key;
+use crate::media::{MetadataOptions, TrackMetadata};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fs;
+use std::time::UNIX_EPOCH;
+
+/// 文件的创建时间（Unix 秒），文件系统不记录创建时间时使用修改时间
+fn file_added_time(path: &str) -> Option<i64> {
+    let metadata = fs::metadata(path).ok()?;
+    let time = metadata.created().or_else(|_| metadata.modified()).ok()?;
+    i64::try_from(time.duration_since(UNIX_EPOCH).ok()?.as_secs()).ok()
+}
+
+/// 分组键：有专辑标签时按专辑和专辑艺术家，否则每个文件单独成组
+fn group_key(track: &TrackMetadata) -> String {
+    match track.album.as_deref() {
+        Some(album) => {
+            let artist = track.album_artist.as_deref().or(track.artist.as_deref()).unwrap_or_default();
+            format!("{}\0{}", grouping_key(album), grouping_key(artist))
+        }
+        None => format!("\0{}", track.path),
+    }
+}
+
+/// 未扫描的目录中 `since` 之后出现的文件，按专辑分组
+fn unindexed_groups(directories: &[String], filter: &ScanFilter, options: &MetadataOptions, since: i64) -> Vec<RecentlyAdded> {
+    let mut groups: HashMap<String, (i64, Vec<TrackMetadata>)> = HashMap::new();
+    for path in collect_audio_paths_filtered(directories, filter) {
+        // 先按时间过滤，只读取较新文件的元数据
+        let Some(added_at) = file_added_time(&path).filter(|time| *time >= since) else {
+            continue;
+        };
+        let Ok(track) = cached_track_metadata(&path, options) else {
+            continue;
+        };
+        let group = groups.entry(group_key(&track)).or_default();
+        group.0 = group.0.max(added_at);
+        group.1.push(track);
+    }
+
+    groups
+        .into_values()
+        .map(|(added_at, mut tracks)| {
+            tracks.sort_by(|a, b| {
+                (a.disc_number, a.track_number)
+                    .cmp(&(b.disc_number, b.track_number))
+                    .then_with(|| natural_cmp(&a.path, &b.path))
+            });
+            RecentlyAdded::from_tracks(tracks, added_at, false)
+        })
+        .collect()
+}
+
+/// 最近添加的专辑，最新的在前，最多 `limit` 组
+///
+/// 曲库中的曲目只查询数据库；`directories` 中尚未扫描的目录才会遍历文件。
+pub fn recently_added_internal(
+    db: &LibraryDb,
+    directories: &[String],
+    filter: &ScanFilter,
+    options: &MetadataOptions,
+    since: i64,
+    limit: usize,
+) -> AppResult<Vec<RecentlyAdded>> {
+    let mut recent = db.recently_added(since, limit)?;
+
+    let unindexed: Vec<String> = directories.iter().filter(|dir| !db.is_indexed(dir).unwrap_or(true)).cloned().collect();
+    if !unindexed.is_empty() {
+        recent.extend(unindexed_groups(&unindexed, filter, options, since));
+        recent.sort_by_key(|group| Reverse(group.added_at));
+        recent.truncate(limit);
+    }
+    Ok(recent)
+}
@@ -0,0 +1,137 @@
+//! 曲库数据库结构与迁移
+//!
+//! 迁移按顺序编号，已执行到的版本记录在 `PRAGMA user_version` 中。
+//! 新增迁移只能追加到 `MIGRATIONS` 末尾，不能修改已发布的迁移。
+
+use crate::error::{AppError, AppResult};
+use crate::tr;
+use rusqlite::Connection;
+
+/// 按版本排列的迁移脚本，第 N 项把数据库从版本 N 升级到 N + 1
+const MIGRATIONS: &[&str] = &[
+    // 1: 初始结构
+    "
+    CREATE TABLE scan_roots (
+        id INTEGER PRIMARY KEY,
+        path TEXT NOT NULL UNIQUE,
+        last_scanned INTEGER
+    );
+
+    CREATE TABLE artists (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+        sort_name TEXT
+    );
+
+    CREATE TABLE albums (
+        id INTEGER PRIMARY KEY,
+        title TEXT NOT NULL COLLATE NOCASE,
+        album_artist TEXT NOT NULL DEFAULT '' COLLATE NOCASE,
+        sort_title TEXT,
+        year INTEGER,
+        UNIQUE (title, album_artist)
+    );
+
+    CREATE TABLE tracks (
+        id INTEGER PRIMARY KEY,
+        path TEXT NOT NULL UNIQUE,
+        root_id INTEGER REFERENCES scan_roots(id) ON DELETE SET NULL,
+        album_id INTEGER REFERENCES albums(id) ON DELETE SET NULL,
+        mtime INTEGER,
+        size INTEGER,
+        seen_generation INTEGER NOT NULL DEFAULT 0,
+        added_at INTEGER NOT NULL,
+        name TEXT NOT NULL,
+        title TEXT,
+        artist TEXT,
+        artists TEXT NOT NULL DEFAULT '[]',
+        album TEXT,
+        album_artist TEXT,
+        artist_sort TEXT,
+        album_artist_sort TEXT,
+        album_sort TEXT,
+        genre TEXT,
+        year INTEGER,
+        track_number INTEGER,
+        track_total INTEGER,
+        disc_number INTEGER,
+        isrc TEXT,
+        label TEXT,
+        catalog_number TEXT,
+        duration REAL,
+        has_cover INTEGER NOT NULL DEFAULT 0,
+        cover_source TEXT NOT NULL DEFAULT 'none',
+        bitrate INTEGER,
+        sample_rate INTEGER,
+        channels INTEGER,
+        bit_depth INTEGER,
+        format TEXT,
+        encoder TEXT,
+        container TEXT,
+        mp3_info TEXT,
+        encoding_suspect INTEGER NOT NULL DEFAULT 0,
+        encoding_repair TEXT,
+        file_size INTEGER,
+        tag_error TEXT
+    );
+
+    CREATE TABLE track_artists (
+        track_id INTEGER NOT NULL REFERENCES tracks(id) ON DELETE CASCADE,
+        artist_id INTEGER NOT NULL REFERENCES artists(id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        PRIMARY KEY (track_id, artist_id)
+    );
+
+    CREATE INDEX tracks_root ON tracks(root_id);
+    CREATE INDEX track_artists_artist ON track_artists(artist_id);
+    CREATE INDEX tracks_album ON tracks(album_id);
+    CREATE INDEX tracks_genre ON tracks(genre COLLATE NOCASE);
+    ",
+    // 2: 标记网络共享上的根目录，共享离线时其中的曲目不视为丢失
+    "
+    ALTER TABLE scan_roots ADD COLUMN is_network INTEGER NOT NULL DEFAULT 0;
+    ",
+    // 3: 按加入时间查询最近添加的曲目
+    "
+    CREATE INDEX tracks_added ON tracks(added_at);
+    ",
+    // 4: 记录离线的根目录（如拔出的移动硬盘），其中的曲目标记为不可用而不是删除
+    "
+    ALTER TABLE scan_roots ADD COLUMN offline_since INTEGER;
+    ",
+    // 5: 逐曲的歌词时间偏移，不随曲目记录删除，重新扫描或重新获取歌词后仍然有效
+    "
+    CREATE TABLE lyrics_offsets (
+        path TEXT PRIMARY KEY,
+        offset_ms INTEGER NOT NULL
+    );
+    ",
+    // 6: 路径改为 `canonical_key` 写法（之前是反斜杠写法）；只有大小写或分隔符不同的重复记录保留一条
+    "
+    UPDATE OR IGNORE tracks SET path = canonical_key(path);
+    DELETE FROM tracks WHERE path <> canonical_key(path);
+    UPDATE OR IGNORE lyrics_offsets SET path = canonical_key(path);
+    DELETE FROM lyrics_offsets WHERE path <> canonical_key(path);
+    ",
+];
+
+/// 当前代码对应的数据库版本
+pub const SCHEMA_VERSION: usize = MIGRATIONS.len();
+
+/// 将数据库升级到最新版本
+///
+/// 每个迁移在单独的事务中执行；数据库版本比代码更新时返回错误，避免旧版本程序破坏数据。
+pub fn migrate(conn: &mut Connection) -> AppResult<()> {
+    let version: usize = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    if version > SCHEMA_VERSION {
+        return Err(AppError::Other(tr!("library.databaseTooNew", version = version, supported = SCHEMA_VERSION)));
+    }
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        let tx = conn.transaction()?;
+        tx.execute_batch(migration)?;
+        tx.pragma_update(None, "user_version", index + 1)?;
+        tx.commit()?;
+    }
+    Ok(())
+}
@@ -0,0 +1,329 @@
+//! 曲库搜索
+//!
+//! 在标题、艺术家、专辑、专辑艺术家和文件名中做忽略大小写与变音符号的子串匹配。
+//! 搜索使用预先规范化的内存索引，曲库数据库变化后在下次搜索时重建；数据库为空时
+//! 退回到最近一次扫描的结果。查询中可以使用 `artist:foo album:"bar baz"` 形式的字段条件。
+
+use super::db::LibraryDb;
+use crate::media::text::{is_cjk, search_key};
+use crate::media::{Playlist, TrackMetadata};
+use std::sync::{Arc, LazyLock, Mutex};
+
+/// 可在查询中使用的字段条件
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchField {
+    Title,
+    Artist,
+    Album,
+    AlbumArtist,
+    Genre,
+    Year,
+    /// 文件路径
+    File,
+    Format,
+    Isrc,
+    Label,
+    CatalogNumber,
+}
+
+impl SearchField {
+    /// 解析字段名（忽略大小写），未知字段返回 `None`
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "title" => Some(Self::Title),
+            "artist" => Some(Self::Artist),
+            "album" => Some(Self::Album),
+            "albumartist" | "album_artist" => Some(Self::AlbumArtist),
+            "genre" => Some(Self::Genre),
+            "year" => Some(Self::Year),
+            "file" | "filename" | "path" => Some(Self::File),
+            "format" => Some(Self::Format),
+            "isrc" => Some(Self::Isrc),
+            "label" => Some(Self::Label),
+            "catalognumber" | "catalog_number" | "catalog" | "cat" => Some(Self::CatalogNumber),
+            _ => None,
+        }
+    }
+}
+
+/// 解析后的查询
+#[derive(Debug, Clone, Default)]
+pub struct SearchQuery {
+    /// 自由文本词（已转换为搜索键），全部需要匹配
+    pub terms: Vec<String>,
+    /// 字段条件（值已转换为搜索键），全部需要匹配
+    pub filters: Vec<(SearchField, String)>,
+}
+
+impl SearchQuery {
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty() && self.filters.is_empty()
+    }
+}
+
+/// 按空白拆分查询，双引号内的空白不拆分
+fn tokenize(query: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in query.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+/// 解析查询字符串
+///
+/// `字段:值` 形式且字段名已知时作为字段条件，其余部分（包括 `C:\Music` 这样的路径）作为自由文本。
+#[must_use]
+pub fn parse_query(query: &str) -> SearchQuery {
+    let mut parsed = SearchQuery::default();
+    for token in tokenize(query) {
+        let filter = token.split_once(':').and_then(|(name, value)| {
+            let field = SearchField::from_name(name)?;
+            let value = search_key(value);
+            (!value.trim().is_empty()).then(|| (field, value.trim().to_string()))
+        });
+        if let Some(filter) = filter {
+            parsed.filters.push(filter);
+        } else {
+            let term = search_key(&token);
+            if !term.trim().is_empty() {
+                parsed.terms.push(term.trim().to_string());
+            }
+        }
+    }
+    parsed
+}
+
+/// `needle` 是否出现在 `haystack` 中某个词的开头
+fn contains_word(haystack: &str, needle: &str) -> bool {
+    // 中日韩文字之间没有空格分词，匹配到任意位置都视为词的开头
+    let cjk = needle.chars().next().is_some_and(is_cjk);
+    haystack.match_indices(needle).any(|(index, _)| {
+        cjk || haystack[..index].chars().next_back().is_none_or(|prev| !prev.is_alphanumeric() || is_cjk(prev))
+    })
+}
+
+/// 索引中的一条曲目，各字段已转换为搜索键
+struct IndexedTrack {
+    track: TrackMetadata,
+    title: String,
+    artists: Vec<String>,
+    album: String,
+    album_artist: String,
+    file_name: String,
+    path: String,
+    genre: String,
+    year: String,
+    format: String,
+    isrc: String,
+    label: String,
+    catalog_number: String,
+}
+
+fn key(value: Option<&String>) -> String {
+    value.map(|v| search_key(v)).unwrap_or_default()
+}
+
+impl IndexedTrack {
+    fn new(track: TrackMetadata) -> Self {
+        let mut artists: Vec<String> = track.artists.iter().map(|a| search_key(a)).collect();
+        if let Some(artist) = &track.artist {
+            artists.push(search_key(artist));
+        }
+        Self {
+            title: search_key(track.title.as_deref().unwrap_or(&track.name)),
+            artists,
+            album: key(track.album.as_ref()),
+            album_artist: key(track.album_artist.as_ref()),
+            file_name: search_key(&track.name),
+            path: search_key(&track.path.replace('\\', "/")),
+            genre: key(track.genre.as_ref()),
+            year: track.year.map(|y| y.to_string()).unwrap_or_default(),
+            format: key(track.format.as_ref()),
+            isrc: key(track.isrc.as_ref()).replace('-', ""),
+            label: key(track.label.as_ref()),
+            catalog_number: key(track.catalog_number.as_ref()),
+            track,
+        }
+    }
+
+    /// 用于自由文本匹配的元数据字段（不含文件名）
+    fn metadata_fields(&self) -> impl Iterator<Item = &str> {
+        [self.title.as_str(), self.album.as_str(), self.album_artist.as_str()]
+            .into_iter()
+            .chain(self.artists.iter().map(String::as_str))
+    }
+
+    fn matches_filter(&self, field: SearchField, value: &str) -> bool {
+        match field {
+            SearchField::Title => self.title.contains(value),
+            SearchField::Artist => {
+                self.artists.iter().any(|a| a.contains(value)) || self.album_artist.contains(value)
+            }
+            SearchField::Album => self.album.contains(value),
+            SearchField::AlbumArtist => self.album_artist.contains(value),
+            SearchField::Genre => self.genre.contains(value),
+            SearchField::Year => self.year == value,
+            SearchField::File => self.path.contains(value),
+            SearchField::Format => self.format == value,
+            SearchField::Isrc => self.isrc.contains(&value.replace('-', "")),
+            SearchField::Label => self.label.contains(value),
+            SearchField::CatalogNumber => self.catalog_number.contains(value),
+        }
+    }
+
+    /// 计算匹配等级，不匹配时返回 `None`
+    ///
+    /// 0：标题以查询开头；1：每个词都在元数据中作为词的开头出现；2：每个词都在元数据中出现；
+    /// 3：有词只在文件名中出现。
+    fn rank(&self, query: &SearchQuery, phrase: &str) -> Option<u8> {
+        if !query.filters.iter().all(|(field, value)| self.matches_filter(*field, value)) {
+            return None;
+        }
+        if query.terms.is_empty() {
+            return Some(0);
+        }
+
+        let mut rank = 0;
+        for term in &query.terms {
+            let term_rank = if self.metadata_fields().any(|field| contains_word(field, term)) {
+                1
+            } else if self.metadata_fields().any(|field| field.contains(term.as_str())) {
+                2
+            } else if self.file_name.contains(term.as_str()) {
+                3
+            } else {
+                return None;
+            };
+            rank = rank.max(term_rank);
+        }
+
+        if self.title.starts_with(phrase) { Some(0) } else { Some(rank) }
+    }
+}
+
+/// 预先规范化的搜索索引
+pub struct SearchIndex {
+    tracks: Vec<IndexedTrack>,
+}
+
+impl SearchIndex {
+    #[must_use]
+    pub fn new(tracks: Vec<TrackMetadata>) -> Self {
+        Self { tracks: tracks.into_iter().map(IndexedTrack::new).collect() }
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.tracks.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.tracks.is_empty()
+    }
+
+    /// 搜索并按匹配等级、标题和路径排序，最多返回 `limit` 条
+    #[must_use]
+    pub fn search(&self, query: &SearchQuery, limit: usize) -> Vec<TrackMetadata> {
+        if query.is_empty() {
+            return Vec::new();
+        }
+        let phrase = query.terms.join(" ");
+
+        let mut hits: Vec<(u8, &IndexedTrack)> = self
+            .tracks
+            .iter()
+            .filter_map(|entry| entry.rank(query, &phrase).map(|rank| (rank, entry)))
+            .collect();
+        hits.sort_by(|(rank_a, a), (rank_b, b)| {
+            rank_a
+                .cmp(rank_b)
+                .then_with(|| a.title.cmp(&b.title))
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        hits.into_iter().take(limit).map(|(_, entry)| entry.track.clone()).collect()
+    }
+}
+
+/// 索引的数据来源及其版本
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum IndexSource {
+    Library(u64),
+    LastScan(u64),
+}
+
+/// 最近一次扫描的曲目（版本号，曲目）
+static LAST_SCAN: LazyLock<Mutex<(u64, Arc<Vec<TrackMetadata>>)>> =
+    LazyLock::new(|| Mutex::new((0, Arc::new(Vec::new()))));
+
+/// 当前索引
+static SEARCH_INDEX: LazyLock<Mutex<Option<(IndexSource, Arc<SearchIndex>)>>> = LazyLock::new(|| Mutex::new(None));
+
+/// 记录最近一次扫描的结果，曲库数据库不可用时搜索使用
+pub fn remember_scan(playlists: &[Playlist]) {
+    let tracks: Vec<TrackMetadata> = playlists
+        .iter()
+        .flat_map(|playlist| playlist.files.iter())
+        .map(|track| TrackMetadata { cover: None, ..track.clone() })
+        .collect();
+    let mut last = LAST_SCAN.lock().unwrap();
+    *last = (last.0 + 1, Arc::new(tracks));
+}
+
+/// 获取最新的索引，数据变化后重建
+fn current_index(db: &LibraryDb) -> Arc<SearchIndex> {
+    let mut index = SEARCH_INDEX.lock().unwrap();
+    let library = IndexSource::Library(db.revision());
+    if let Some((source, cached)) = index.as_ref() {
+        if *source == library {
+            return Arc::clone(cached);
+        }
+    }
+
+    let built = match db.all_tracks() {
+        Ok(tracks) if !tracks.is_empty() => (library, Arc::new(SearchIndex::new(tracks))),
+        result => {
+            if let Err(e) = result {
+                eprintln!("Failed to read library database, searching last scan: {e}");
+            }
+            let (version, tracks) = {
+                let last = LAST_SCAN.lock().unwrap();
+                (last.0, Arc::clone(&last.1))
+            };
+            let source = IndexSource::LastScan(version);
+            match index.as_ref() {
+                Some((cached_source, cached)) if *cached_source == source => (source, Arc::clone(cached)),
+                _ => (source, Arc::new(SearchIndex::new(tracks.as_ref().clone()))),
+            }
+        }
+    };
+    let result = Arc::clone(&built.1);
+    *index = Some(built);
+    result
+}
+
+/// 搜索曲库
+#[must_use]
+pub fn search_library_internal(db: &LibraryDb, query: &str, limit: usize) -> Vec<TrackMetadata> {
+    let query = parse_query(query);
+    if query.is_empty() {
+        return Vec::new();
+    }
+    current_index(db).search(&query, limit)
+}
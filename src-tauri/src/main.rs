@@ -8,10 +8,16 @@
 use mercurial_player::{
     AppState, PlayerState, audio,
     config,
-    config::ConfigManager,
+    config::{ConfigManager, LyricsConfig},
     equalizer,
     equalizer::{Equalizer, GlobalEqualizer},
+    i18n,
+    library,
+    library::{LibraryDb, LIBRARY_DB_FILE},
     media, plugins, system,
+    media::lyrics_provider::LyricsProviderRegistry,
+    media::watcher::LibraryWatcher,
+    tasks::TaskRegistry,
 };
 
 #[cfg(windows)]
@@ -51,15 +57,59 @@ fn main() {
         eprintln!("Failed to initialize config files: {e}");
     }
 
-    // 从配置加载独占模式设置
-    let exclusive_mode_enabled = config_manager
-        .load_config()
-        .map(|c| c.audio.exclusive_mode)
-        .unwrap_or(false);
+    // 从配置加载独占模式设置和支持的扩展名
+    let startup_config = config_manager.load_config().ok();
+    let exclusive_mode_enabled = startup_config.as_ref().is_some_and(|c| c.audio.exclusive_mode);
+    if let Some(config) = &startup_config {
+        media::filesystem::apply_extensions(&config.directory_scan.audio_extensions);
+        if let Err(e) = media::http_client::apply_network_config(&config.network) {
+            eprintln!("Failed to apply proxy settings: {e}");
+        }
+        if let Err(e) = media::netease::apply_api_config(&config.netease) {
+            eprintln!("Failed to apply NetEase API settings: {e}");
+        }
+    }
+
+    // 后端文字的语言，启动前端之前确定
+    let language = startup_config.as_ref().map_or(i18n::SYSTEM_LANGUAGE, |c| c.general.language.as_str());
+    i18n::set_current(i18n::Language::from_tag(&i18n::locale_for_setting(language)));
+
+    let power_config = startup_config.as_ref().map(|c| c.power.clone()).unwrap_or_default();
+    let ducking_config = startup_config.as_ref().map(|c| c.audio.ducking.clone()).unwrap_or_default();
+    let offline_mode = startup_config.as_ref().is_some_and(|c| c.network.offline_mode);
+    let register_file_associations = startup_config.as_ref().is_some_and(|c| c.general.register_file_associations);
+    let launch_args: Vec<String> = std::env::args().collect();
+    system::launch::set_pending(&launch_args);
+    let lyrics_provider_order = startup_config.as_ref().map_or_else(
+        || LyricsConfig::default().provider_order,
+        |config| config.lyrics.provider_order.clone(),
+    );
 
     println!("Loaded exclusive mode from config: {exclusive_mode_enabled}");
 
+    // 加载上次保存的元数据缓存，供增量扫描使用
+    if let Ok(dir) = config_manager.get_cache_directory("metadata") {
+        media::metadata_cache::load_persisted(&dir);
+    }
+    if let Ok(dir) = config_manager.get_cache_directory("archives") {
+        media::archive::set_cache_directory(dir);
+    }
+    if let Ok(dir) = config_manager.get_data_directory() {
+        media::scan_checkpoint::set_checkpoint_directory(dir.clone());
+        media::scan::set_report_directory(dir);
+    }
+
+    // 打开曲库数据库，损坏时自动重建
+    let library_db = match config_manager.get_data_directory() {
+        Ok(dir) => LibraryDb::open_or_recreate(&dir.join(LIBRARY_DB_FILE)),
+        Err(e) => {
+            eprintln!("Failed to get data directory: {e}");
+            LibraryDb::open_or_recreate(&std::env::temp_dir().join(LIBRARY_DB_FILE))
+        }
+    };
+
     // 根据独占模式设置创建播放器
+    #[cfg_attr(not(windows), allow(unused_variables))]
     let (sink, wasapi_player) = {
         if exclusive_mode_enabled {
             create_exclusive_mode_player(&device_name)
@@ -102,15 +152,44 @@ fn main() {
         },
         config_manager,
         equalizer: GlobalEqualizer::new(),
+        tasks: Arc::new(TaskRegistry::new()),
+        library_watcher: LibraryWatcher::new(),
+        library: Arc::new(library_db),
+        lyrics_providers: LyricsProviderRegistry::new(&lyrics_provider_order),
     };
 
     tauri::Builder::default()
+        .plugin(system::single_instance::plugin())
         .manage(app_state)
-        .setup(|_app| {
+        .setup(move |app| {
+            media::commands::start_configured_library_watch(app.handle());
+            media::commands::resume_interrupted_scan(app.handle());
+            media::roots::start_root_monitor(app.handle());
+            media::netease_limiter::init(app.handle());
+            media::http_client::set_offline(app.handle(), offline_mode);
+            media::scrobble::start(app.handle());
+            system::media_keys::start(app.handle());
+            system::notifications::start(app.handle());
+            system::theme::start(app.handle());
+            system::power::apply_config(app.handle(), &power_config);
+            system::power::start(app.handle());
+            audio::ducking::apply_config(app.handle(), &ducking_config);
+            audio::ducking::start(app.handle());
+            system::autostart::apply_launch_args(app.handle(), &launch_args);
+            system::file_associations::apply(register_file_associations);
+            #[cfg(target_os = "linux")]
+            system::mpris::start(app.handle());
+            #[cfg(windows)]
+            system::smtc::start(app.handle());
+            #[cfg(windows)]
+            system::taskbar::start(app.handle());
+            #[cfg(target_os = "macos")]
+            system::nowplaying::start(app.handle());
+
             #[cfg(debug_assertions)]
             {
                 use tauri::Manager;
-                let window = _app.get_webview_window("main").unwrap();
+                let window = app.get_webview_window("main").unwrap();
                 window.open_devtools();
             }
             Ok(())
@@ -118,25 +197,118 @@ fn main() {
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_shell::init())
+        .plugin(system::media_keys::plugin())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
         .invoke_handler(tauri::generate_handler![
             // 文件系统命令
             media::commands::read_directory,
+            media::commands::get_folder_tree,
             media::commands::get_audio_files,
             media::commands::read_lyrics_file,
             media::commands::write_lyrics_file,
+            media::commands::find_lyrics_file,
+            media::commands::save_lyrics_as_lrc,
+            media::commands::get_embedded_lyrics,
+            media::commands::get_lyrics_offset,
+            media::commands::set_lyrics_offset,
+            media::commands::write_embedded_lyrics,
             media::commands::get_all_audio_files,
+            media::commands::start_audio_scan,
+            media::commands::start_library_scan,
+            media::commands::cancel_scan,
+            media::commands::resume_pending_scan,
+            media::commands::get_last_scan_report,
+            media::commands::rescan_library_incremental,
+            media::commands::test_exclude_pattern,
             media::commands::check_file_exists,
+            media::commands::check_files_exist,
+            media::commands::get_library_roots_status,
+            media::commands::classify_dropped_paths,
+            media::commands::get_file_properties,
+            media::commands::organize_files,
+            media::commands::move_files,
+            media::commands::move_file,
+            media::commands::import_files,
+            media::commands::get_folder_stats,
+            media::commands::list_archive_tracks,
+            media::commands::start_library_watch,
+            media::commands::stop_library_watch,
+            // 曲库命令
+            library::commands::library_query,
+            library::commands::library_get_artists,
+            library::commands::library_get_albums,
+            library::commands::library_stats,
+            library::commands::get_recently_added,
+            library::commands::search_library,
+            library::commands::match_online_playlist_to_library,
+            library::commands::rebuild_library,
             // 元数据命令
             media::commands::get_track_metadata,
+            media::commands::get_track_metadata_full,
             media::commands::get_tracks_metadata_batch,
+            media::commands::refresh_track_metadata,
+            media::commands::get_track_cover,
+            media::commands::copy_track_info,
+            media::commands::copy_file_path,
+            media::commands::copy_cover_image,
             media::commands::extract_cover,
+            media::commands::remove_track_cover,
+            media::commands::parse_cue_sheet,
+            media::commands::write_track_tags,
+            media::commands::fix_tag_encoding,
+            media::commands::write_album_metadata,
+            media::commands::copy_tags,
+            media::commands::export_library,
+            media::commands::import_ratings,
+            media::commands::import_playlist,
+            media::commands::export_playlist,
+            media::commands::relink_missing_tracks,
+            media::commands::apply_relink,
+            media::commands::find_missing_files,
+            media::commands::prune_missing,
+            // 指纹识别命令
+            media::commands::compute_fingerprint,
+            media::commands::compute_fingerprints_batch,
+            media::commands::compute_file_hash,
+            media::commands::validate_audio_files,
+            media::commands::compute_audio_hash,
+            media::commands::find_duplicates,
+            media::commands::find_duplicate_tracks,
+            media::commands::resolve_duplicates,
+            media::commands::trash_files,
+            media::commands::delete_files_permanently,
+            media::commands::lastfm_get_auth_url,
+            media::commands::lastfm_complete_auth,
+            media::commands::listenbrainz_validate_token,
+            media::commands::get_scrobble_status,
+            media::commands::lookup_track,
+            media::commands::apply_lookup_result,
             // 网易云音乐API命令
+            media::commands::lyrics_search,
+            media::commands::lyrics_fetch,
             media::commands::netease_search_songs,
+            media::commands::netease_get_song_detail,
+            media::commands::netease_search_albums,
+            media::commands::netease_get_album_tracks,
+            media::commands::netease_get_playlist,
             media::commands::netease_get_lyrics,
+            media::commands::qqmusic_search_songs,
+            media::commands::qqmusic_get_lyrics,
+            media::commands::get_lyrics_cache_stats,
+            media::commands::clear_lyrics_cache,
+            media::commands::merge_lyrics,
+            media::commands::netease_auto_match_lyrics,
+            media::commands::netease_set_lyrics_match,
+            media::commands::netease_download_lyrics_batch,
+            media::commands::netease_get_cover,
+            media::commands::fetch_and_embed_cover,
+            media::commands::fetch_and_embed_album_cover,
             // 播放命令
             audio::commands::play_track,
             audio::commands::pause_track,
             audio::commands::resume_track,
+            audio::commands::stop_track,
             audio::commands::set_volume,
             audio::commands::get_playback_status,
             audio::commands::seek_track,
@@ -155,10 +327,33 @@ fn main() {
             config::commands::remove_music_directory,
             config::commands::set_music_directories,
             config::commands::get_music_directories,
+            config::commands::get_supported_extensions,
+            config::commands::set_supported_extensions,
+            config::commands::get_network_proxy,
+            config::commands::set_network_proxy,
+            config::commands::test_network_connection,
+            config::commands::get_netease_api_config,
+            config::commands::set_netease_api_config,
+            config::commands::test_netease_connection,
+            config::commands::set_offline_mode,
             // 系统命令
             system::commands::get_system_info,
+            system::commands::copy_diagnostics_to_clipboard,
             system::commands::get_system_fonts,
             system::commands::get_platform,
+            system::commands::cancel_task,
+            system::commands::get_task_status,
+            system::commands::get_running_tasks,
+            system::commands::take_launch_files,
+            system::commands::get_system_theme,
+            system::commands::get_system_accent_color,
+            system::commands::get_autostart_status,
+            system::commands::set_autostart,
+            system::commands::get_power_status,
+            system::commands::show_in_file_manager,
+            // 多语言命令
+            i18n::commands::get_system_locale,
+            i18n::commands::set_language,
             // 音频设备命令
             audio::commands::get_audio_devices,
             audio::commands::set_audio_device,
@@ -188,8 +383,23 @@ fn main() {
             plugins::commands::save_screenshot,
             plugins::commands::open_screenshots_directory,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|_app, event| match event {
+            tauri::RunEvent::Exit => {
+                #[cfg(target_os = "linux")]
+                system::mpris::stop();
+                #[cfg(windows)]
+                system::smtc::stop();
+                #[cfg(windows)]
+                system::taskbar::stop();
+                #[cfg(target_os = "macos")]
+                system::nowplaying::stop();
+            }
+            #[cfg(target_os = "macos")]
+            tauri::RunEvent::Opened { urls } => system::launch::open_urls(_app, &urls),
+            _ => {}
+        });
 }
 
 /// 创建独占模式播放器
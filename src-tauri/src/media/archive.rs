@@ -0,0 +1,387 @@
+//! ZIP 压缩包模块
+//!
+//! 压缩包中的音频文件用 `压缩包路径!条目名` 形式的虚拟路径表示，如 `D:\Downloads\Album.zip!CD1/01 - Intro.flac`。
+//! 读取元数据和播放前先把条目解压到缓存目录（cache/archives），因此压缩包中无法随机访问的格式也能正常解码。
+//! 缓存文件名由压缩包路径、大小、修改时间和条目名计算，压缩包变化后自动使用新的缓存；
+//! 缓存总大小超过上限时按最近使用时间淘汰。
+
+use super::filesystem::has_audio_extension;
+use super::metadata::{get_track_metadata_with_options, MetadataOptions, TrackMetadata};
+use super::sorting::natural_cmp;
+use crate::error::{AppError, AppResult};
+use crate::paths::{canonical_key, long_path};
+use crate::tr;
+use rayon::prelude::*;
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+use zip::result::ZipError;
+use zip::ZipArchive;
+
+/// 虚拟路径中压缩包路径与条目名之间的分隔符
+pub const ARCHIVE_SEPARATOR: char = '!';
+
+/// 支持的压缩包扩展名
+const ARCHIVE_EXTENSION: &str = "zip";
+
+/// 解压缓存的总大小上限（字节）
+const MAX_CACHE_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+
+/// 解压缓存目录，启动时设置，未设置时使用系统临时目录
+static CACHE_DIR: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// 临时文件序号，避免并发解压同一条目时互相覆盖
+static NEXT_PART: AtomicU64 = AtomicU64::new(1);
+
+/// 设置解压缓存目录
+pub fn set_cache_directory(dir: PathBuf) {
+    *CACHE_DIR.write().unwrap() = Some(dir);
+}
+
+fn cache_dir() -> io::Result<PathBuf> {
+    let dir = CACHE_DIR
+        .read()
+        .unwrap()
+        .clone()
+        .unwrap_or_else(|| std::env::temp_dir().join("merplayer-archives"));
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// 路径是否为支持的压缩包
+#[must_use]
+pub fn is_archive(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case(ARCHIVE_EXTENSION))
+}
+
+/// 拆分虚拟路径，返回压缩包路径和条目名（条目名统一为 `/` 分隔）；普通路径返回 `None`
+#[must_use]
+pub fn split_archive_path(path: &str) -> Option<(&str, String)> {
+    let marker = format!(".{ARCHIVE_EXTENSION}{ARCHIVE_SEPARATOR}");
+    // ASCII 小写不改变字节位置
+    let index = path.to_ascii_lowercase().rfind(&marker)?;
+    let split = index + marker.len() - 1;
+    let entry = &path[split + 1..];
+    (!entry.is_empty()).then(|| (&path[..split], entry.replace('\\', "/")))
+}
+
+/// 由压缩包路径和条目名组成虚拟路径
+#[must_use]
+pub fn archive_entry_path(zip_path: &str, entry: &str) -> String {
+    format!("{zip_path}{ARCHIVE_SEPARATOR}{entry}")
+}
+
+fn zip_error(zip_path: &str, error: ZipError) -> AppError {
+    match error {
+        ZipError::UnsupportedArchive(message) if message == ZipError::PASSWORD_REQUIRED => {
+            AppError::EncryptedArchive(zip_path.to_string())
+        }
+        ZipError::InvalidPassword => AppError::EncryptedArchive(zip_path.to_string()),
+        ZipError::Io(e) => AppError::Io(e),
+        e => AppError::Other(tr!("archive.readFailed", path = zip_path, detail = e)),
+    }
+}
+
+fn open_archive(zip_path: &str) -> AppResult<ZipArchive<File>> {
+    let file = File::open(long_path(zip_path)).map_err(|e| match e.kind() {
+        ErrorKind::NotFound => AppError::FileNotFound(zip_path.to_string()),
+        _ => AppError::Io(e),
+    })?;
+    ZipArchive::new(file).map_err(|e| zip_error(zip_path, e))
+}
+
+/// 压缩包中的音频条目名（按自然顺序）
+///
+/// 有任一音频条目被加密时返回 `EncryptedArchive`。
+pub fn archive_entries(zip_path: &str) -> AppResult<Vec<String>> {
+    let mut archive = open_archive(zip_path)?;
+    let mut entries = Vec::new();
+    for index in 0..archive.len() {
+        let file = archive.by_index_raw(index).map_err(|e| zip_error(zip_path, e))?;
+        if file.is_dir() || !has_audio_extension(Path::new(file.name())) {
+            continue;
+        }
+        if file.encrypted() {
+            return Err(AppError::EncryptedArchive(zip_path.to_string()));
+        }
+        entries.push(file.name().to_string());
+    }
+    entries.sort_by(|a, b| natural_cmp(a, b));
+    Ok(entries)
+}
+
+/// 缓存文件名：压缩包路径、大小、修改时间和条目名的摘要，保留条目的扩展名供解码器识别格式
+fn cache_file_name(zip_path: &str, entry: &str) -> AppResult<String> {
+    let metadata = fs::metadata(long_path(zip_path))?;
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |duration| duration.as_nanos());
+
+    let mut hasher = Sha256::new();
+    hasher.update(canonical_key(zip_path).as_bytes());
+    hasher.update(metadata.len().to_le_bytes());
+    hasher.update(modified.to_le_bytes());
+    hasher.update(entry.as_bytes());
+    let hash = format!("{:x}", hasher.finalize());
+
+    let extension = Path::new(entry).extension().map(|ext| ext.to_string_lossy().to_lowercase()).unwrap_or_default();
+    Ok(format!("{}.{extension}", &hash[..32]))
+}
+
+/// 更新修改时间，作为最近使用时间
+fn touch(path: &Path) {
+    let _ = File::options().append(true).open(path).and_then(|file| file.set_modified(SystemTime::now()));
+}
+
+/// 缓存超过 `max_bytes` 时删除最久未使用的文件，`keep` 不会被删除
+fn evict(dir: &Path, keep: &Path, max_bytes: u64) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut files: Vec<(PathBuf, u64, SystemTime)> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            metadata.is_file().then(|| (entry.path(), metadata.len(), metadata.modified().unwrap_or(UNIX_EPOCH)))
+        })
+        .collect();
+    let mut total: u64 = files.iter().map(|(_, size, _)| size).sum();
+    files.sort_by_key(|(_, _, modified)| *modified);
+    for (path, size, _) in files {
+        if total <= max_bytes {
+            break;
+        }
+        if path != keep && fs::remove_file(&path).is_ok() {
+            total -= size;
+        }
+    }
+}
+
+/// 把虚拟路径指向的条目解压到缓存目录，返回缓存文件路径；已解压过时直接返回
+pub fn extract_entry(path: &str) -> AppResult<PathBuf> {
+    let (zip_path, entry) = split_archive_path(path).ok_or_else(|| AppError::InvalidPath(path.to_string()))?;
+    let dir = cache_dir()?;
+    let target = dir.join(cache_file_name(zip_path, &entry)?);
+    if target.is_file() {
+        touch(&target);
+        return Ok(target);
+    }
+
+    let mut archive = open_archive(zip_path)?;
+    let index = archive.index_for_name(&entry).ok_or_else(|| AppError::FileNotFound(path.to_string()))?;
+    if archive.by_index_raw(index).map_err(|e| zip_error(zip_path, e))?.encrypted() {
+        return Err(AppError::EncryptedArchive(zip_path.to_string()));
+    }
+    let mut reader = archive.by_index(index).map_err(|e| zip_error(zip_path, e))?;
+
+    let part = target.with_extension(format!("part{}", NEXT_PART.fetch_add(1, Ordering::Relaxed)));
+    let copied = File::create(&part).and_then(|mut file| io::copy(&mut reader, &mut file));
+    if let Err(e) = copied.and_then(|_| fs::rename(&part, &target)) {
+        let _ = fs::remove_file(&part);
+        return Err(AppError::Io(e));
+    }
+    evict(&dir, &target, MAX_CACHE_BYTES);
+    Ok(target)
+}
+
+/// 返回可以直接打开的文件路径：虚拟路径解压后返回缓存文件，普通路径原样返回
+pub fn playable_path(path: &str) -> AppResult<String> {
+    if split_archive_path(path).is_none() {
+        return Ok(path.to_string());
+    }
+    Ok(extract_entry(path)?.to_string_lossy().to_string())
+}
+
+/// 读取压缩包条目的元数据，返回的路径为虚拟路径
+pub fn read_entry_metadata(path: &str, options: &MetadataOptions) -> Result<TrackMetadata, String> {
+    let extracted = extract_entry(path).map_err(|e| e.to_string())?;
+    let mut metadata = get_track_metadata_with_options(&extracted.to_string_lossy(), options)?;
+    metadata.path = path.replace('/', "\\");
+    metadata.name = split_archive_path(path)
+        .and_then(|(_, entry)| entry.rsplit('/').next().map(str::to_string))
+        .unwrap_or_default();
+    Ok(metadata)
+}
+
+/// 列出压缩包中的音频文件及其元数据
+///
+/// 条目并行解压和读取，无法读取的条目被跳过。压缩包被加密时返回 `EncryptedArchive`。
+pub fn list_archive_tracks_internal(zip_path: &str, options: &MetadataOptions) -> AppResult<Vec<TrackMetadata>> {
+    let entries = archive_entries(zip_path)?;
+    Ok(entries
+        .par_iter()
+        .filter_map(|entry| {
+            let path = archive_entry_path(zip_path, entry);
+            read_entry_metadata(&path, options)
+                .map_err(|e| eprintln!("Failed to read archive entry '{path}': {e}"))
+                .ok()
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    /// 只有存储（不压缩）条目的 ZIP，`encrypted` 的条目设置加密标志
+    fn write_zip(dir: &TempDir, name: &str, entries: &[(&str, &[u8], bool)]) -> String {
+        let mut bytes = Vec::new();
+        let mut central = Vec::new();
+        for (entry, data, encrypted) in entries {
+            let mut crc = flate2::Crc::new();
+            crc.update(data);
+            let offset = u32::try_from(bytes.len()).unwrap();
+            let size = u32::try_from(data.len()).unwrap();
+            let name_len = u16::try_from(entry.len()).unwrap();
+            let flags = u16::from(*encrypted);
+
+            // 本地文件头
+            bytes.extend(0x0403_4B50u32.to_le_bytes());
+            bytes.extend(20u16.to_le_bytes());
+            bytes.extend(flags.to_le_bytes());
+            bytes.extend([0; 6]);
+            bytes.extend(crc.sum().to_le_bytes());
+            bytes.extend(size.to_le_bytes());
+            bytes.extend(size.to_le_bytes());
+            bytes.extend(name_len.to_le_bytes());
+            bytes.extend(0u16.to_le_bytes());
+            bytes.extend(entry.as_bytes());
+            bytes.extend(*data);
+
+            // 中央目录项
+            central.extend(0x0201_4B50u32.to_le_bytes());
+            central.extend(20u16.to_le_bytes());
+            central.extend(20u16.to_le_bytes());
+            central.extend(flags.to_le_bytes());
+            central.extend([0; 6]);
+            central.extend(crc.sum().to_le_bytes());
+            central.extend(size.to_le_bytes());
+            central.extend(size.to_le_bytes());
+            central.extend(name_len.to_le_bytes());
+            central.extend([0; 12]);
+            central.extend(offset.to_le_bytes());
+            central.extend(entry.as_bytes());
+        }
+
+        let count = u16::try_from(entries.len()).unwrap();
+        let central_offset = u32::try_from(bytes.len()).unwrap();
+        let central_size = u32::try_from(central.len()).unwrap();
+        bytes.extend(central);
+        bytes.extend(0x0605_4B50u32.to_le_bytes());
+        bytes.extend([0; 4]);
+        bytes.extend(count.to_le_bytes());
+        bytes.extend(count.to_le_bytes());
+        bytes.extend(central_size.to_le_bytes());
+        bytes.extend(central_offset.to_le_bytes());
+        bytes.extend(0u16.to_le_bytes());
+
+        let path = dir.path().join(name);
+        fs::write(&path, bytes).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn archive_paths_are_split_at_the_last_zip_marker() {
+        assert_eq!(
+            split_archive_path(r"D:\Music\Album.ZIP!CD1\01 - Intro.flac"),
+            Some((r"D:\Music\Album.ZIP", "CD1/01 - Intro.flac".to_string()))
+        );
+        assert_eq!(split_archive_path("/music/a.Zip!song.mp3"), Some(("/music/a.Zip", "song.mp3".to_string())));
+        // 压缩包中的压缩包：条目名从最后一个 `.zip!` 之后开始
+        assert_eq!(
+            split_archive_path("/music/outer.zip!inner.zip!song.flac"),
+            Some(("/music/outer.zip!inner.zip", "song.flac".to_string()))
+        );
+        assert_eq!(split_archive_path("/music/a.zip!"), None);
+        assert_eq!(split_archive_path("/music/wow!.flac"), None);
+        assert_eq!(split_archive_path("/music/a.zip"), None);
+
+        let path = archive_entry_path("/music/a.zip", "CD1/01.flac");
+        assert_eq!(split_archive_path(&path), Some(("/music/a.zip", "CD1/01.flac".to_string())));
+    }
+
+    #[test]
+    fn cache_file_names_depend_on_the_archive_and_entry() {
+        let dir = TempDir::new().unwrap();
+        let zip = write_zip(&dir, "a.zip", &[("01.flac", b"one", false)]);
+
+        let name = cache_file_name(&zip, "CD1/01.FLAC").unwrap();
+        let (hash, extension) = name.split_once('.').unwrap();
+        assert_eq!(hash.len(), 32);
+        assert_eq!(extension, "flac");
+        assert_eq!(cache_file_name(&zip, "CD1/01.FLAC").unwrap(), name);
+        assert_ne!(cache_file_name(&zip, "CD1/02.flac").unwrap(), name);
+
+        // 压缩包内容变化（大小不同）后使用新的缓存
+        write_zip(&dir, "a.zip", &[("01.flac", b"changed", false)]);
+        assert_ne!(cache_file_name(&zip, "CD1/01.FLAC").unwrap(), name);
+
+        assert!(cache_file_name(&dir.path().join("missing.zip").to_string_lossy(), "01.flac").is_err());
+    }
+
+    #[test]
+    fn eviction_removes_the_least_recently_used_files_over_the_limit() {
+        let dir = TempDir::new().unwrap();
+        let file = |name: &str, secs: u64| {
+            let path = dir.path().join(name);
+            fs::write(&path, [0u8; 100]).unwrap();
+            File::options()
+                .append(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(UNIX_EPOCH + Duration::from_secs(secs))
+                .unwrap();
+            path
+        };
+        let oldest = file("oldest.flac", 1_000);
+        let old = file("old.flac", 2_000);
+        let recent = file("recent.flac", 3_000);
+        let newest = file("newest.flac", 4_000);
+
+        // 400 字节超过 250 字节的上限：最旧的 `keep` 保留，依次删除其后最旧的文件
+        evict(dir.path(), &oldest, 250);
+        assert!(oldest.exists());
+        assert!(!old.exists());
+        assert!(!recent.exists());
+        assert!(newest.exists());
+
+        // 不超过上限时不删除
+        evict(dir.path(), &newest, 250);
+        assert!(oldest.exists() && newest.exists());
+    }
+
+    #[test]
+    fn audio_entries_are_listed_in_natural_order() {
+        let dir = TempDir::new().unwrap();
+        let zip = write_zip(
+            &dir,
+            "album.zip",
+            &[("10.flac", b"", false), ("cover.jpg", b"", false), ("CD1/", b"", false), ("2.mp3", b"", false)],
+        );
+        assert_eq!(archive_entries(&zip).unwrap(), ["2.mp3", "10.flac"]);
+    }
+
+    #[test]
+    fn encrypted_entries_are_reported() {
+        let dir = TempDir::new().unwrap();
+        let zip = write_zip(&dir, "locked.zip", &[("01.flac", &[0; 24], true)]);
+
+        assert!(matches!(archive_entries(&zip), Err(AppError::EncryptedArchive(path)) if path == zip));
+        let entry = archive_entry_path(&zip, "01.flac");
+        assert!(matches!(extract_entry(&entry), Err(AppError::EncryptedArchive(path)) if path == zip));
+
+        // 只有非音频条目被加密时照常列出
+        let zip = write_zip(&dir, "notes.zip", &[("01.flac", b"", false), ("notes.txt", &[0; 24], true)]);
+        assert_eq!(archive_entries(&zip).unwrap(), ["01.flac"]);
+    }
+}
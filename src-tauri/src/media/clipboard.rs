@@ -0,0 +1,61 @@
+//! 复制当前音轨的信息
+//!
+//! 按模板（与整理文件的模板使用相同的占位符，如 `{artist} - {title}`）复制音轨信息、复制文件路径，
+//! 或把封面图片本身放到系统剪贴板。由后端写入剪贴板，不受各平台 WebView 剪贴板接口差异的影响。
+
+use super::metadata::{track_cover_data, MetadataOptions};
+use super::metadata_cache::cached_track_metadata;
+use super::organize::render_template;
+use crate::error::AppResult;
+use crate::tr;
+use crate::AppState;
+use serde::Serialize;
+use tauri::image::Image;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+/// 未指定模板时复制的音轨信息
+pub const DEFAULT_TRACK_INFO_FORMAT: &str = "{artist} - {title}";
+
+/// 复制到剪贴板的封面
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CopiedCover {
+    /// 封面所属的音轨
+    pub path: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// 当前播放的音轨，没有时返回错误
+fn current_track(app: &AppHandle) -> AppResult<String> {
+    let path = app.state::<AppState>().player.current_path.lock().unwrap().clone();
+    path.ok_or_else(|| tr!("clipboard.noTrack").into())
+}
+
+/// 按模板渲染当前音轨的信息并复制，返回复制的文本
+pub fn copy_track_info(app: &AppHandle, format: &str, options: &MetadataOptions) -> AppResult<String> {
+    let path = current_track(app)?;
+    let metadata = cached_track_metadata(&path, options)?;
+    let text = render_template(format, &metadata)?;
+    app.clipboard().write_text(text.clone()).map_err(|e| e.to_string())?;
+    Ok(text)
+}
+
+/// 复制当前音轨的文件路径，返回复制的路径
+pub fn copy_file_path(app: &AppHandle) -> AppResult<String> {
+    let path = current_track(app)?;
+    app.clipboard().write_text(path.clone()).map_err(|e| e.to_string())?;
+    Ok(path)
+}
+
+/// 把当前音轨的封面解码后以图片形式复制；平台剪贴板不支持图片时返回错误
+pub fn copy_cover_image(app: &AppHandle, options: &MetadataOptions) -> AppResult<CopiedCover> {
+    let path = current_track(app)?;
+    let data = track_cover_data(&path, options)?.ok_or_else(|| tr!("clipboard.noCover"))?;
+    let decoded = image::load_from_memory(&data).map_err(|e| tr!("cover.decodeFailed", detail = e))?.into_rgba8();
+    let (width, height) = decoded.dimensions();
+    let image = Image::new_owned(decoded.into_raw(), width, height);
+    app.clipboard().write_image(&image).map_err(|e| e.to_string())?;
+    Ok(CopiedCover { path, width, height })
+}
@@ -2,14 +2,193 @@
 //!
 //! 包含文件系统操作和元数据获取命令。
 
+use super::archive::list_archive_tracks_internal;
+use super::clipboard::{self, CopiedCover, DEFAULT_TRACK_INFO_FORMAT};
+use super::cover_embed::{
+    fetch_cover, prepare_embed_cover, AlbumCoverEmbedResult, CoverSource, EmbedCover, EmbedCoverResult,
+};
+use super::cue::{parse_cue_sheet_internal, CueSheet};
+use super::fingerprint::{fingerprint_with_cache, FingerprintBatchItem, FingerprintCache, FingerprintResult};
+use super::file_properties::{file_properties_internal, FileProperties};
 use super::filesystem::{
-    check_file_exists_internal, get_all_audio_files_from_dirs, get_audio_files_from_dir, read_dir,
-    read_lyrics_file_internal, write_lyrics_file_internal,
+    check_file_exists_internal, collect_audio_paths, collect_audio_paths_filtered, read_dir, read_lyrics_file_internal,
+    scan_audio_dir, scan_audio_dirs, write_lyrics_file_internal, LyricsFileContent, ScanProgress,
+};
+use super::lastfm::{self, AuthRequest, Credentials};
+use super::listenbrainz;
+use super::lrc_file::{save_lyrics_as_lrc_internal, LrcSaveOptions};
+use super::lyrics::{get_embedded_lyrics_internal, write_embedded_lyrics_internal, EmbeddedLyrics};
+use super::lyrics_batch::{
+    has_local_lyrics, lyrics_text, save_lyrics, LyricsBatchItem, LyricsBatchItemEvent, LyricsBatchOptions,
+    LyricsBatchReport, LyricsBatchStatus, LyricsSaveTarget, LYRICS_BATCH_ITEM_EVENT,
+};
+use super::lyrics_cache::{LyricsCache, LyricsCacheStats};
+use super::lyrics_finder::{find_lyrics_file_internal, LyricsFileMatch};
+use super::lyrics_match::{
+    auto_match_in_album, auto_match_lyrics, AutoMatchOutcome, LyricsMatch, LyricsMatchCache, TrackQuery,
+};
+use super::lyrics_merge::{self, MergedLyrics};
+use super::lyrics_provider::{fetch_cached, LyricsProvider, ProviderSong};
+use super::metadata::{
+    Playlist, TrackMetadata, TrackMetadataFull, MetadataOptions, get_track_metadata_with_options,
+    get_track_metadata_full_internal, get_track_cover_internal, ExtractCoverOptions,
+    extract_cover_internal,
+};
+use super::dropped::{classify_dropped_paths_internal, DroppedPath};
+use super::duplicates::{
+    find_duplicate_tracks_internal, resolve_duplicates_internal, DuplicateAction, DuplicateStrategy,
+    DuplicateTrackReport, ResolveDuplicatesResult,
+};
+use super::export::{export_library_internal, import_ratings_internal, ExportFormat, ExportResult, ImportRatingsResult};
+use super::http_client::is_offline;
+use super::hashing::{
+    compute_file_hash_internal, find_duplicates_internal, DuplicateReport, HashCache, HashKind,
+};
+use super::folder_stats::{folder_stats_internal, FolderStats};
+use super::folder_tree::{folder_tree_internal, FolderNode};
+use super::import::{
+    import_files_internal, FilesImportedEvent, ImportFilesOptions, ImportFilesResult, FILES_IMPORTED_EVENT,
 };
-use super::metadata::{Playlist, TrackMetadata, get_track_metadata_internal, extract_cover_internal};
+use super::integrity::{
+    estimate_validation, validate_audio_files_internal, ValidationResult, VALIDATION_ESTIMATE_EVENT,
+};
+use super::metadata_cache::{cached_track_metadata, persist, refresh_and_notify};
+use super::missing::{
+    check_files_exist_internal, find_missing_files_internal, prune_missing_internal, FileStatus, MissingFilesReport,
+    PathReferences, PruneResult, MISSING_FILES_PRUNED_EVENT,
+};
+use super::musicbrainz::{lookup_recording, LookupCandidate, LookupOutcome};
 use super::netease;
+use super::network::probe_dir;
+use super::organize::{
+    move_files_internal, organize_files_internal, FileConflict, FileMove, MoveFilesResult, OrganizeResult,
+    TRACK_PATHS_CHANGED_EVENT,
+};
+use super::playlist_file::{
+    export_playlist_internal, import_playlist_internal, ImportedPlaylist, PlaylistExportResult, PlaylistFormat,
+};
+use super::qqmusic;
+use super::relink::{apply_relink_internal, relink_missing_tracks_internal, RelinkApplyResult, RelinkProposal};
+use super::rescan::{rescan_library_internal, RescanSummary};
+use super::roots::{roots_status_internal, LibraryRootStatus};
+use super::scan::{emit_warnings, last_scan_report, start_scan, ScanReport, SCAN_TASK_PREFIX};
+use super::scan_checkpoint::{clear_checkpoint, load_checkpoint, with_checkpoints, ScanCheckpoint};
+use super::scan_filter::{test_exclude_pattern_internal, ExcludePatternTest, ScanFilter};
+use super::scrobble::{self, ScrobbleStatus};
+use super::sorting::{sort_tracks, SortContext, SortField, SortSpec};
+use super::tag_writer::{
+    fix_tag_encoding_internal, is_current_track, parse_picture_type, remove_track_cover_internal,
+    write_album_metadata_internal, write_files_checked, write_tags_checked, write_track_tags_internal,
+    copy_tags_internal, AlbumChanges, AlbumWriteResult, CopyTagsResult, RemoveCoverResult, TagChanges,
+};
+use super::trash::{
+    delete_files_permanently_internal, trash_files_internal, DeleteFilesResult, FilesDeletedEvent, FILES_DELETED_EVENT,
+};
+use super::watcher::{
+    self, LibraryFilesEvent, LyricsFileChangedEvent, LIBRARY_FILES_REMOVED_EVENT, LYRICS_FILE_CHANGED_EVENT,
+};
+use crate::audio::playback::stop_playback;
+use crate::config::{LastfmConfig, ListenBrainzConfig};
+use crate::error::{AppError, AppResult};
+use crate::library::sync_playlists;
+use crate::paths::same_path;
+use crate::system::power;
+use crate::tasks::{run_blocking, CancelToken, TaskProgressEvent, TASK_PROGRESS_EVENT};
 use crate::AppState;
-use tauri::{State, command};
+use crate::tr;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State, command};
+
+/// 未指定 `max_depth` 时目录树展开的层数
+const DEFAULT_FOLDER_TREE_DEPTH: usize = 3;
+/// 批量下载在离线模式下暂停时检查开关的间隔
+const OFFLINE_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 根据当前配置构建元数据读取选项
+/// 写入标签后更新元数据缓存和曲库记录并发送 `track-metadata-changed`
+///
+/// 写入时已标记为自身写入，目录监视不会再处理这次修改；重新读取失败时返回写入后读到的元数据。
+fn refresh_written(app: &AppHandle, state: &State<AppState>, path: &str, written: TrackMetadata) -> TrackMetadata {
+    refresh_and_notify(app, path, &metadata_options(state)).unwrap_or(written)
+}
+
+fn metadata_options(state: &State<AppState>) -> MetadataOptions {
+    state
+        .config_manager
+        .load_config()
+        .map(|config| MetadataOptions::from_config(&config))
+        .unwrap_or_default()
+}
+
+/// 按歌词设置中的有效期打开在线歌词缓存
+fn lyrics_cache(state: &State<AppState>) -> Result<LyricsCache, String> {
+    let lyrics = state.config_manager.load_config().map(|config| config.lyrics).unwrap_or_default();
+    Ok(LyricsCache::new(state.config_manager.get_cache_directory("online_lyrics")?, lyrics.cache_ttl_days))
+}
+
+/// 按歌词设置决定返回哪些在线歌词层
+fn lyric_layers(state: &State<AppState>) -> netease::LyricLayers {
+    let lyrics = state.config_manager.load_config().map(|config| config.lyrics).unwrap_or_default();
+    netease::LyricLayers::from_config(&lyrics)
+}
+
+/// 音轨的歌词时间偏移（毫秒），读取失败时为 0
+fn track_lyrics_offset(state: &State<AppState>, path: &str) -> i64 {
+    state.library.lyrics_offset(path).unwrap_or_else(|e| {
+        eprintln!("Failed to read lyrics offset for {path}: {e}");
+        0
+    })
+}
+
+/// 按名称查找在线歌词来源
+fn lyrics_provider(state: &State<AppState>, name: &str) -> Result<Arc<dyn LyricsProvider>, netease::NeteaseError> {
+    state.lyrics_providers.get(name).ok_or_else(|| netease::NeteaseError::NotFound(format!("lyrics provider {name}")))
+}
+
+/// 根据当前配置构建扫描过滤规则
+fn scan_filter(state: &State<AppState>) -> ScanFilter {
+    state
+        .config_manager
+        .load_config()
+        .map(|config| ScanFilter::from_config(&config))
+        .unwrap_or_default()
+}
+
+/// 将元数据缓存保存到磁盘，失败时只记录日志
+fn persist_metadata_cache(state: &State<AppState>) {
+    match state.config_manager.get_cache_directory("metadata") {
+        Ok(dir) => persist_metadata_cache_to(&dir),
+        Err(e) => eprintln!("Failed to save metadata cache: {e}"),
+    }
+}
+
+fn persist_metadata_cache_to(dir: &Path) {
+    if let Err(e) = persist(dir) {
+        eprintln!("Failed to save metadata cache: {e}");
+    }
+}
+
+/// 按排序方式排列每个播放列表中的音轨，未指定时按路径的自然顺序
+fn sort_playlists(state: &State<AppState>, playlists: &mut [Playlist], sort: Option<SortSpec>) {
+    let mut context = state
+        .config_manager
+        .load_config()
+        .map(|config| SortContext::from_config(&config))
+        .unwrap_or_default();
+    let spec = sort.unwrap_or_default();
+    if spec.uses(SortField::DateAdded) {
+        let paths = playlists.iter().flat_map(|playlist| &playlist.files).map(|track| track.path.as_str());
+        match state.library.added_times(paths) {
+            Ok(added_at) => context.added_at = added_at,
+            Err(e) => eprintln!("Failed to load library added times: {e}"),
+        }
+    }
+    for playlist in playlists {
+        sort_tracks(&mut playlist.files, &spec, &context);
+    }
+}
 
 /// 读取指定目录中的子目录列表
 #[command]
@@ -17,20 +196,252 @@ pub fn read_directory(path: String) -> Result<Vec<String>, String> {
     read_dir(&path)
 }
 
+/// 获取浏览侧栏使用的目录树
+///
+/// 一次遍历返回最多 `max_depth` 层（默认 3 层）子目录，每个目录带有直接包含的音频文件数，
+/// 以及子树中是否有音频文件。更深的目录不展开，`has_children` 为 `true` 时可以该目录为根再次请求。
+/// 无权限的子目录标记为不可访问，不会让整个请求失败。
+#[command]
+pub async fn get_folder_tree(
+    state: State<'_, AppState>,
+    root: String,
+    max_depth: Option<usize>,
+) -> AppResult<FolderNode> {
+    let filter = scan_filter(&state);
+    run_blocking(move || {
+        folder_tree_internal(&root, max_depth.unwrap_or(DEFAULT_FOLDER_TREE_DEPTH), &filter, &CancelToken::default())
+    })
+    .await
+}
+
 /// 获取指定目录中的所有音频文件，并创建播放列表
+///
+/// `include_covers` 默认为 `false`，封面可通过 `get_track_cover` 按需获取。
+/// `sort` 未指定时按路径的自然顺序排列。目录位于无法访问的网络共享上时返回 `NETWORK_UNAVAILABLE`，
+/// 读取超时的文件通过 `scan-warning` 事件报告。
 #[command]
-pub fn get_audio_files(path: String) -> Result<Playlist, String> {
-    get_audio_files_from_dir(&path)
+pub fn get_audio_files(
+    app: AppHandle,
+    state: State<AppState>,
+    path: String,
+    include_covers: Option<bool>,
+    sort: Option<SortSpec>,
+) -> AppResult<Playlist> {
+    let options = metadata_options(&state).with_covers(include_covers.unwrap_or(false));
+    let progress = ScanProgress::default();
+    let result = scan_audio_dir(&path, &options, &scan_filter(&state), &progress, &CancelToken::default());
+    emit_warnings(&app, None, &progress);
+    let mut playlist = result?;
+    sort_playlists(&state, std::slice::from_mut(&mut playlist), sort);
+    Ok(playlist)
 }
 
 /// 获取多个目录中的所有音频文件，并创建播放列表
+///
+/// `sort` 未指定时按路径的自然顺序排列。无法访问的网络目录被跳过，全部无法访问时返回 `NETWORK_UNAVAILABLE`。
 #[command]
 pub fn get_all_audio_files(
+    app: AppHandle,
     state: State<AppState>,
     paths: Vec<String>,
-) -> Result<Vec<Playlist>, String> {
+    include_covers: Option<bool>,
+    sort: Option<SortSpec>,
+) -> AppResult<Vec<Playlist>> {
     let config = state.config_manager.load_config()?;
-    get_all_audio_files_from_dirs(&paths, &config)
+    let progress = ScanProgress::default();
+    let result =
+        scan_audio_dirs(&paths, &config, include_covers.unwrap_or(false), &progress, &CancelToken::default());
+    let mut playlists = result.inspect_err(|_| emit_warnings(&app, None, &progress))?;
+    persist_metadata_cache(&state);
+    sync_playlists(&state.library, &paths, &playlists, true, config.directory_scan.guard_shrunk_roots, &progress);
+    emit_warnings(&app, None, &progress);
+    sort_playlists(&state, &mut playlists, sort);
+    Ok(playlists)
+}
+
+/// 在后台扫描单个目录，立即返回扫描 ID
+///
+/// 与 `get_audio_files` 结果相同，但通过 `scan-progress` 报告进度，完成后在 `scan-complete`
+/// 事件中返回只含一个播放列表的数组。已有扫描在运行时返回错误。
+#[command]
+pub fn start_audio_scan(
+    app: AppHandle,
+    state: State<AppState>,
+    path: String,
+    include_covers: Option<bool>,
+) -> AppResult<String> {
+    probe_dir(Path::new(&path))?;
+    let options = metadata_options(&state).with_covers(include_covers.unwrap_or(false));
+    let filter = scan_filter(&state);
+    let guard_shrunk = state
+        .config_manager
+        .load_config()
+        .map_or(true, |config| config.directory_scan.guard_shrunk_roots);
+    let cache_dir = state.config_manager.get_cache_directory("metadata")?;
+    let library = Arc::clone(&state.library);
+
+    start_scan(app, Arc::clone(&state.tasks), move |progress, cancel| {
+        let playlists = vec![scan_audio_dir(&path, &options, &filter, progress, cancel)?];
+        persist_metadata_cache_to(&cache_dir);
+        sync_playlists(&library, &[path], &playlists, !cancel.is_cancelled(), guard_shrunk, progress);
+        Ok(playlists)
+    })
+}
+
+/// 在后台扫描多个目录，立即返回扫描 ID
+///
+/// 与 `get_all_audio_files` 结果相同，播放列表通过 `scan-complete` 事件返回。
+///
+/// 扫描期间定期写入检查点，程序在扫描中途退出后可通过 `resume_pending_scan` 继续。
+#[command]
+pub fn start_library_scan(
+    app: AppHandle,
+    state: State<AppState>,
+    paths: Vec<String>,
+    include_covers: Option<bool>,
+) -> AppResult<String> {
+    start_library_scan_internal(app, &state, paths, include_covers.unwrap_or(false))
+}
+
+fn start_library_scan_internal(
+    app: AppHandle,
+    state: &AppState,
+    paths: Vec<String>,
+    include_covers: bool,
+) -> AppResult<String> {
+    let config = state.config_manager.load_config()?;
+    let cache_dir = state.config_manager.get_cache_directory("metadata")?;
+    let library = Arc::clone(&state.library);
+    let checkpoint = ScanCheckpoint::new(&paths, include_covers, &config);
+
+    start_scan(app, Arc::clone(&state.tasks), move |progress, cancel| {
+        let playlists = with_checkpoints(checkpoint, progress, &cache_dir, || {
+            scan_audio_dirs(&paths, &config, include_covers, progress, cancel)
+        })?;
+        persist_metadata_cache_to(&cache_dir);
+        let guard_shrunk = config.directory_scan.guard_shrunk_roots;
+        sync_playlists(&library, &paths, &playlists, !cancel.is_cancelled(), guard_shrunk, progress);
+        Ok(playlists)
+    })
+}
+
+/// 继续上次被中断的曲库扫描，返回新的扫描 ID；没有未完成的扫描时返回 `None`
+///
+/// 已读取过且未变化的文件直接使用元数据缓存。扫描目录或过滤规则在中断后发生变化时，
+/// 旧的检查点被丢弃并返回 `None`。已有扫描在运行时返回错误。
+#[command]
+pub fn resume_pending_scan(app: AppHandle, state: State<AppState>) -> AppResult<Option<String>> {
+    resume_pending_scan_internal(app, &state)
+}
+
+fn resume_pending_scan_internal(app: AppHandle, state: &AppState) -> AppResult<Option<String>> {
+    let Some(checkpoint) = load_checkpoint() else {
+        return Ok(None);
+    };
+    let config = state.config_manager.load_config()?;
+    if !checkpoint.is_valid_for(&config) {
+        clear_checkpoint();
+        return Ok(None);
+    }
+    start_library_scan_internal(app, state, checkpoint.roots, checkpoint.include_covers).map(Some)
+}
+
+/// 启动时按配置自动继续被中断的扫描
+pub fn resume_interrupted_scan(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let enabled = state
+        .config_manager
+        .load_config()
+        .is_ok_and(|config| config.directory_scan.resume_interrupted_scans);
+    if !enabled {
+        return;
+    }
+    if let Err(e) = resume_pending_scan_internal(app.clone(), &state) {
+        eprintln!("Failed to resume interrupted scan: {e}");
+    }
+}
+
+/// 取消后台扫描，扫描不存在时返回 `false`
+///
+/// 遍历会尽快停止，随后发送带有部分结果的 `scan-cancelled` 事件。
+#[command]
+pub fn cancel_scan(state: State<AppState>, scan_id: String) -> bool {
+    scan_id.starts_with(SCAN_TASK_PREFIX) && state.tasks.cancel(&scan_id)
+}
+
+/// 获取最近一次后台扫描的报告（重启后为上次保存的报告），从未扫描过时返回 `None`
+#[command]
+pub fn get_last_scan_report() -> Option<ScanReport> {
+    last_scan_report()
+}
+
+/// 增量重新扫描目录，只重新读取新增或变化的文件
+///
+/// 返回各类变化的数量，并发送逐文件事件（见 `RescanChanges::emit`）。过程中发送 `task-progress`
+/// 事件，可通过 `cancel_task` 取消；取消时已读取的文件仍保留在缓存中。
+#[command]
+pub async fn rescan_library_incremental(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    paths: Vec<String>,
+    task_id: Option<String>,
+) -> AppResult<RescanSummary> {
+    let options = metadata_options(&state);
+    let filter = scan_filter(&state);
+    let hash_dir = state.config_manager.get_cache_directory("hash")?;
+    let tasks = Arc::clone(&state.tasks);
+    let task_id = task_id.unwrap_or_else(|| "rescan-library".to_string());
+
+    let changes = {
+        let app = app.clone();
+        run_blocking(move || {
+            let token = tasks.start(&task_id);
+            let mut hashes = HashCache::load(&hash_dir);
+            let on_progress = |completed: usize, total: usize, path: &str| {
+                let _ = app.emit(TASK_PROGRESS_EVENT, TaskProgressEvent {
+                    task_id: task_id.clone(),
+                    kind: "rescan".to_string(),
+                    completed,
+                    total,
+                    current: (!path.is_empty()).then(|| path.to_string()),
+                });
+            };
+
+            let result = rescan_library_internal(&paths, &options, &filter, &mut hashes, &token, on_progress);
+            tasks.finish(&task_id, &token);
+            if let Err(e) = hashes.save() {
+                eprintln!("Failed to save hash cache: {e}");
+            }
+            result
+        })
+        .await
+    };
+    persist_metadata_cache(&state);
+
+    let changes = changes?;
+    if let Err(e) = changes.apply_to(&state.library) {
+        eprintln!("Failed to update library database: {e}");
+    }
+    changes.emit(&app);
+    Ok(changes.summary())
+}
+
+/// 检查排除模式是否匹配示例路径，供设置界面预览
+///
+/// 模式无效时返回错误信息。
+#[command]
+pub fn test_exclude_pattern(pattern: String, sample_path: String) -> Result<ExcludePatternTest, String> {
+    test_exclude_pattern_internal(&pattern, &sample_path)
+}
+
+/// 获取文件属性（大小、时间、只读标记、规范路径和所属音乐目录），曲库外的文件同样可用
+///
+/// 文件不存在时返回 `FILE_NOT_FOUND`。
+#[command]
+pub async fn get_file_properties(state: State<'_, AppState>, path: String) -> AppResult<FileProperties> {
+    let directories = state.config_manager.load_config().map(|config| config.music_directories).unwrap_or_default();
+    let library = Arc::clone(&state.library);
+    run_blocking(move || file_properties_internal(&library, &path, &directories)).await
 }
 
 /// 检查文件是否存在
@@ -39,53 +450,1788 @@ pub fn check_file_exists(path: String) -> Result<bool, String> {
     Ok(check_file_exists_internal(&path))
 }
 
-/// 读取歌词文件内容
+/// 批量检查文件是否存在，返回与 `paths` 一一对应的状态（exists、missing、offline）
+///
+/// 位于离线网络共享或离线根目录中的文件返回 `offline`，不会逐个等待超时。
 #[command]
-pub fn read_lyrics_file(path: String) -> Result<String, String> {
+pub async fn check_files_exist(state: State<'_, AppState>, paths: Vec<String>) -> AppResult<Vec<FileStatus>> {
+    let library = Arc::clone(&state.library);
+    run_blocking(move || Ok(check_files_exist_internal(&library, &paths))).await
+}
+
+/// 获取各音乐目录是否在线、是否位于可移动卷或网络共享上，以及曲库中的曲目数
+///
+/// 状态变化时会另外发送 `library-root-offline` / `library-root-online` 事件。
+#[command]
+pub async fn get_library_roots_status(state: State<'_, AppState>) -> AppResult<Vec<LibraryRootStatus>> {
+    let directories = state.config_manager.load_config().map(|config| config.music_directories).unwrap_or_default();
+    let library = Arc::clone(&state.library);
+    run_blocking(move || roots_status_internal(&library, &directories)).await
+}
+
+/// 判断拖放到窗口上的路径各是什么，结果与 `paths` 一一对应
+///
+/// 音频文件附带不含封面的元数据，目录附带音频文件数（只探测有限数量的目录项，
+/// 超出时 `truncated` 为 `true`），不支持的文件附带原因。
+#[command]
+pub async fn classify_dropped_paths(state: State<'_, AppState>, paths: Vec<String>) -> AppResult<Vec<DroppedPath>> {
+    let filter = scan_filter(&state);
+    let options = metadata_options(&state);
+    run_blocking(move || Ok(classify_dropped_paths_internal(&paths, &filter, &options))).await
+}
+
+/// 读取歌词文件内容，自动检测编码并返回检测到的编码
+#[command]
+pub fn read_lyrics_file(path: String) -> Result<LyricsFileContent, String> {
     read_lyrics_file_internal(&path)
 }
 
-/// 写入歌词文件内容
+/// 写入歌词文件内容，返回写入后的修改时间
+///
+/// 默认写入 UTF-8，`preserve_encoding` 为 `true` 时覆盖已有文件沿用其原编码。
+/// 传入读取时得到的 `expected_modified` 且文件已被其他程序修改时返回 `CONTENT_CONFLICT`，
+/// `details.current` 为磁盘上的当前内容。
+#[command]
+pub fn write_lyrics_file(
+    path: String,
+    content: String,
+    preserve_encoding: Option<bool>,
+    expected_modified: Option<u64>,
+) -> AppResult<u64> {
+    write_lyrics_file_internal(&path, &content, preserve_encoding.unwrap_or(false), expected_modified)
+}
+
+/// 查找音频文件对应的歌词文件
+///
+/// 返回最佳匹配及其可信度（exact、normalized、title）和其余候选。
+#[command]
+pub fn find_lyrics_file(state: State<AppState>, audio_path: String) -> LyricsFileMatch {
+    let subfolders = state
+        .config_manager
+        .load_config()
+        .map(|config| config.lyrics.search_subfolders)
+        .unwrap_or_default();
+    find_lyrics_file_internal(&audio_path, &subfolders, &metadata_options(&state))
+}
+
+/// 把获取到的在线歌词保存为音频文件旁的同名 .lrc 文件，返回歌词文件路径
+///
+/// 标签行取自音轨的标题、艺术家和专辑，`options` 控制是否插入翻译和罗马音，以及音轨的歌词时间偏移
+/// 写入时间标签还是 `[offset:]` 标签。已有用户自己的歌词文件时
+/// 返回 `OVERWRITE_CONFLICT`（`details.current` 为现有内容，`details.proposed` 为要写入的内容），
+/// `overwrite` 为 true 时才会覆盖。保存的是当前播放的曲目时发送 `lyrics-file-changed` 事件。
+#[command]
+pub async fn save_lyrics_as_lrc(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    audio_path: String,
+    lyrics: netease::LyricsData,
+    options: Option<LrcSaveOptions>,
+) -> AppResult<String> {
+    let metadata_options = metadata_options(&state);
+    let options = options.unwrap_or_default();
+    let offset_ms = state.library.lyrics_offset(&audio_path)?;
+    let track_path = audio_path.clone();
+    let lrc_path = run_blocking(move || {
+        let metadata = cached_track_metadata(&track_path, &metadata_options).ok();
+        save_lyrics_as_lrc_internal(&track_path, metadata.as_ref(), &lyrics, &options, offset_ms)
+    })
+    .await?;
+
+    if is_current_track(&state, &audio_path) {
+        let _ = app.emit(LYRICS_FILE_CHANGED_EVENT, LyricsFileChangedEvent {
+            track_path: audio_path,
+            paths: vec![lrc_path.clone()],
+        });
+    }
+    Ok(lrc_path)
+}
+
+/// 获取音轨的元数据信息（包含封面）
 #[command]
-pub fn write_lyrics_file(path: String, content: String) -> Result<(), String> {
-    write_lyrics_file_internal(&path, &content)
+pub fn get_track_metadata(state: State<AppState>, path: String) -> Result<TrackMetadata, String> {
+    get_track_metadata_with_options(&path, &metadata_options(&state).with_covers(true))
 }
 
-/// 获取音轨的元数据信息
+/// 获取音轨的扩展元数据（包含封面和自定义字段）
 #[command]
-pub fn get_track_metadata(path: String) -> Result<TrackMetadata, String> {
-    get_track_metadata_internal(&path)
+pub fn get_track_metadata_full(state: State<AppState>, path: String) -> Result<TrackMetadataFull, String> {
+    get_track_metadata_full_internal(&path, &metadata_options(&state).with_covers(true))
 }
 
 /// 批量获取多个音轨的元数据信息
 /// 返回成功获取的元数据列表，失败的文件会被跳过
 #[command]
-pub fn get_tracks_metadata_batch(paths: Vec<String>) -> Vec<TrackMetadata> {
+pub fn get_tracks_metadata_batch(
+    state: State<AppState>,
+    paths: Vec<String>,
+    include_covers: Option<bool>,
+) -> Vec<TrackMetadata> {
+    let options = metadata_options(&state).with_covers(include_covers.unwrap_or(false));
     paths
         .into_iter()
-        .filter_map(|path| get_track_metadata_internal(&path).ok())
+        .filter_map(|path| cached_track_metadata(&path, &options).ok())
         .collect()
 }
 
-/// 搜索网易云音乐歌曲
+/// 重新读取单个文件的元数据
+///
+/// 跳过并更新元数据缓存，发送 `track-metadata-changed` 事件；文件不存在时返回
+/// `FILE_NOT_FOUND` 并发送 `track-missing` 事件。
+#[command]
+pub async fn refresh_track_metadata(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    path: String,
+) -> AppResult<TrackMetadata> {
+    let options = metadata_options(&state);
+    run_blocking(move || refresh_and_notify(&app, &path, &options)).await
+}
+
+/// 列出 ZIP 压缩包中的音频文件
+///
+/// 返回的曲目路径为 `压缩包路径!条目名` 形式的虚拟路径，可直接传给 `play_track`；
+/// 压缩包被加密时返回 `ENCRYPTED_ARCHIVE`。
+#[command]
+pub async fn list_archive_tracks(state: State<'_, AppState>, zip_path: String) -> AppResult<Vec<TrackMetadata>> {
+    let options = metadata_options(&state);
+    run_blocking(move || list_archive_tracks_internal(&zip_path, &options)).await
+}
+
+/// 搜索在线歌曲
+///
+/// 指定 `provider` 时只搜索该来源，否则按优先级搜索所有启用的来源并合并结果；
+/// 某个来源出错时跳过，所有来源都出错时返回第一个错误。
+#[command]
+pub async fn lyrics_search(
+    state: State<'_, AppState>,
+    query: String,
+    provider: Option<String>,
+    limit: Option<u32>,
+    offset: Option<u32>,
+) -> Result<Vec<ProviderSong>, netease::NeteaseError> {
+    let (limit, offset) = (limit.unwrap_or(10), offset.unwrap_or(0));
+    let providers = match provider {
+        Some(name) => vec![lyrics_provider(&state, &name)?],
+        None => state.lyrics_providers.enabled(),
+    };
+
+    let mut songs = Vec::new();
+    let mut first_error = None;
+    for provider in &providers {
+        match provider.search(&query, limit, offset).await {
+            Ok(mut found) => songs.append(&mut found),
+            Err(e) => {
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+    match first_error {
+        Some(e) if songs.is_empty() => Err(e),
+        _ => Ok(songs),
+    }
+}
+
+/// 获取在线歌词
+///
+/// `layers` 指定是否返回翻译和罗马音，为空时按歌词设置中的偏好。优先使用未过期的歌词缓存，
+/// `force_refresh` 时重新下载；下载失败时退回过期的缓存。指定 `trackPath` 时应用该音轨的歌词时间偏移。
+#[command]
+pub async fn lyrics_fetch(
+    state: State<'_, AppState>,
+    provider: String,
+    song_id: String,
+    layers: Option<netease::LyricLayers>,
+    force_refresh: Option<bool>,
+    track_path: Option<String>,
+) -> Result<netease::LyricsData, netease::NeteaseError> {
+    let provider = lyrics_provider(&state, &provider)?;
+    let layers = layers.unwrap_or_else(|| lyric_layers(&state));
+    let cache = lyrics_cache(&state).map_err(netease::NeteaseError::Cache)?;
+    let lyrics = fetch_cached(provider.as_ref(), &song_id, force_refresh.unwrap_or(false), &cache).await?;
+    let offset_ms = track_path.map_or(0, |path| track_lyrics_offset(&state, &path));
+    Ok(lyrics.with_layers(layers).with_offset(offset_ms))
+}
+
+/// 搜索网易云音乐歌曲（兼容旧版前端，等同于指定来源的 `lyrics_search`）
 #[command]
 pub async fn netease_search_songs(
     keyword: String,
     limit: Option<u32>,
     offset: Option<u32>,
-) -> Result<Vec<netease::SearchSongResult>, String> {
+) -> Result<Vec<netease::SearchSongResult>, netease::NeteaseError> {
     netease::search_songs(&keyword, limit.unwrap_or(10), offset.unwrap_or(0)).await
 }
 
-/// 获取网易云音乐歌词
+/// 批量获取网易云音乐歌曲详情（专辑、封面、精确时长、发行时间），结果按传入顺序排列
 #[command]
-pub async fn netease_get_lyrics(song_id: String) -> Result<netease::LyricsData, String> {
-    netease::get_lyrics(&song_id).await
+pub async fn netease_get_song_detail(
+    song_ids: Vec<String>,
+) -> Result<Vec<netease::SongDetailInfo>, netease::NeteaseError> {
+    netease::get_song_details(&song_ids).await
 }
 
+/// 搜索网易云音乐专辑
+#[command]
+pub async fn netease_search_albums(
+    keyword: String,
+    limit: Option<u32>,
+) -> Result<Vec<netease::AlbumSearchResult>, netease::NeteaseError> {
+    netease::search_albums(&keyword, limit.unwrap_or(10)).await
+}
+
+/// 获取网易云音乐专辑的曲目列表（歌曲 ID、曲号和时长），按专辑中的顺序排列
+#[command]
+pub async fn netease_get_album_tracks(album_id: String) -> Result<Vec<netease::AlbumTrack>, netease::NeteaseError> {
+    netease::get_album_tracks(&album_id).await
+}
+
+/// 获取网易云音乐歌词（兼容旧版前端，等同于 `lyrics_fetch`）
+#[command]
+pub async fn netease_get_lyrics(
+    state: State<'_, AppState>,
+    song_id: String,
+    layers: Option<netease::LyricLayers>,
+    force_refresh: Option<bool>,
+) -> Result<netease::LyricsData, netease::NeteaseError> {
+    lyrics_fetch(state, "netease".to_string(), song_id, layers, force_refresh, None).await
+}
+
+/// 搜索 QQ 音乐歌曲，结果结构与 `netease_search_songs` 相同，歌曲 ID 为 songmid
+#[command]
+pub async fn qqmusic_search_songs(
+    keyword: String,
+    limit: Option<u32>,
+    offset: Option<u32>,
+) -> Result<Vec<netease::SearchSongResult>, netease::NeteaseError> {
+    qqmusic::search_songs(&keyword, limit.unwrap_or(10), offset.unwrap_or(0)).await
+}
+
+/// 获取 QQ 音乐歌词（兼容旧版前端，等同于 `lyrics_fetch`）
+#[command]
+pub async fn qqmusic_get_lyrics(
+    state: State<'_, AppState>,
+    song_id: String,
+    layers: Option<netease::LyricLayers>,
+    force_refresh: Option<bool>,
+) -> Result<netease::LyricsData, netease::NeteaseError> {
+    lyrics_fetch(state, "qqmusic".to_string(), song_id, layers, force_refresh, None).await
+}
+
+/// 获取在线歌词缓存的统计信息
+#[command]
+pub fn get_lyrics_cache_stats(state: State<AppState>) -> Result<LyricsCacheStats, String> {
+    Ok(lyrics_cache(&state)?.stats())
+}
+
+/// 清空在线歌词缓存，返回删除的条目数；已记录的自动匹配结果保留
+#[command]
+pub fn clear_lyrics_cache(state: State<AppState>) -> Result<usize, String> {
+    Ok(lyrics_cache(&state)?.clear())
+}
+
+/// 按时间对齐合并原文、翻译和罗马音歌词，返回逐行结构和多行 LRC
+#[command]
+pub fn merge_lyrics(original: String, translation: Option<String>, romanization: Option<String>) -> MergedLyrics {
+    lyrics_merge::merge_lyrics(&original, translation.as_deref().unwrap_or(""), romanization.as_deref().unwrap_or(""))
+}
+
+/// 自动为音轨匹配在线歌词
+///
+/// 根据标签中的标题、艺术家和时长按歌词设置中的来源顺序搜索并打分，最佳候选足够可信时返回歌词和匹配信息，
+/// 否则返回所有来源的候选列表。匹配到的来源和歌曲 ID 按文件路径缓存，之后直接使用。
+/// 结果包含各部分得分，`includeRunnersUp` 为 true 时还附带同一来源中得分次高的候选。
+/// 返回的歌词已应用该音轨的歌词时间偏移。
+#[command]
+pub async fn netease_auto_match_lyrics(
+    state: State<'_, AppState>,
+    path: String,
+    include_runners_up: Option<bool>,
+) -> AppResult<AutoMatchOutcome> {
+    let options = metadata_options(&state);
+    let cache_dir = state.config_manager.get_cache_directory("lyrics")?;
+    let track_path = path.clone();
+    let metadata = run_blocking(move || Ok(cached_track_metadata(&track_path, &options)?)).await?;
+
+    let mut cache = LyricsMatchCache::load(&cache_dir);
+    let lyrics_cache = lyrics_cache(&state)?;
+    let query = TrackQuery::from_metadata(&metadata);
+    let layers = lyric_layers(&state);
+    let mut outcome =
+        auto_match_lyrics(&path, &query, &state.lyrics_providers, layers, &mut cache, &lyrics_cache).await?;
+    if !include_runners_up.unwrap_or(false) {
+        outcome.clear_runners_up();
+    }
+    outcome.apply_offset(track_lyrics_offset(&state, &path));
+    Ok(outcome)
+}
+
+/// 记录音轨对应的在线歌曲（用户手动选择时），`song_id` 为空时清除记录（撤销自动匹配）
+///
+/// `provider` 为歌曲所在的来源，默认为网易云音乐。
+#[command]
+pub fn netease_set_lyrics_match(
+    state: State<AppState>,
+    path: String,
+    song_id: Option<String>,
+    provider: Option<String>,
+) -> Result<(), String> {
+    let cache_dir = state.config_manager.get_cache_directory("lyrics")?;
+    let mut cache = LyricsMatchCache::load(&cache_dir);
+    match song_id {
+        Some(song_id) => {
+            let provider = provider.unwrap_or_else(|| "netease".to_string());
+            cache.insert(&path, LyricsMatch { provider, song_id, score: 1.0, instrumental: false });
+        }
+        None => {
+            if !cache.remove(&path) {
+                return Ok(());
+            }
+        }
+    }
+    cache.save()
+}
+
+/// 批量为没有歌词的音轨下载网易云音乐歌词
+///
+/// 已有歌词文件或内嵌歌词的音轨会被跳过；`saveTo` 为 `cacheOnly` 时缓存中已有歌词的音轨也跳过。
+/// 以前已确定为纯音乐的音轨直接记为 `instrumental`，不再请求。
+/// 请求经过网易云音乐的限流队列，每处理完一个文件发送 `task-progress` 和带匹配得分的 `lyrics-batch-item` 事件，
+/// 指定 `albumId` 时先按曲号和标题把音轨对应到该网易云音乐专辑的曲目，专辑中对应不上的音轨再单独搜索。
+/// 可通过 `cancel_task` 取消，取消时返回已处理部分的报告。开启离线模式时暂停，关闭后从暂停的文件继续。
+#[command]
+pub async fn netease_download_lyrics_batch(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    paths: Vec<String>,
+    options: Option<LyricsBatchOptions>,
+    task_id: Option<String>,
+) -> AppResult<LyricsBatchReport> {
+    let LyricsBatchOptions { save_to, include_runners_up, album_id } = options.unwrap_or_default();
+    let album = match album_id.as_deref().filter(|id| !id.trim().is_empty()) {
+        Some(id) => netease::get_album_tracks(id.trim()).await?,
+        None => Vec::new(),
+    };
+    let metadata_options = metadata_options(&state);
+    let subfolders =
+        state.config_manager.load_config().map(|config| config.lyrics.search_subfolders).unwrap_or_default();
+    let layers = lyric_layers(&state);
+    let providers = &state.lyrics_providers;
+    let mut match_cache = LyricsMatchCache::load(&state.config_manager.get_cache_directory("lyrics")?);
+    let lyrics_cache = lyrics_cache(&state)?;
+    let task_id = task_id.unwrap_or_else(|| "lyrics-download".to_string());
+    let token = state.tasks.start(&task_id);
+
+    let total = paths.len();
+    let mut report = LyricsBatchReport::default();
+    for (index, path) in paths.into_iter().enumerate() {
+        if token.is_cancelled() {
+            report.cancelled = true;
+            break;
+        }
+        let _ = app.emit(TASK_PROGRESS_EVENT, TaskProgressEvent {
+            task_id: task_id.clone(),
+            kind: "lyrics-download".to_string(),
+            completed: index,
+            total,
+            current: Some(path.clone()),
+        });
+
+        wait_while_offline(&token).await;
+        if token.is_cancelled() {
+            report.cancelled = true;
+            break;
+        }
+
+        let (track_path, options, folders) = (path.clone(), metadata_options.clone(), subfolders.clone());
+        let local = run_blocking(move || {
+            let has_lyrics = has_local_lyrics(&track_path, &folders, &options);
+            Ok((has_lyrics, cached_track_metadata(&track_path, &options)))
+        })
+        .await;
+        let previous = match_cache.get(&path);
+        let cached = save_to == LyricsSaveTarget::CacheOnly
+            && previous.is_some_and(|found| lyrics_cache.has_fresh(&found.provider, &found.song_id));
+        let instrumental = previous.filter(|found| found.instrumental).map(|found| found.song_id.clone());
+
+        let item = match local {
+            Ok((true, _)) => LyricsBatchItem::new(&path, LyricsBatchStatus::AlreadyHad),
+            _ if instrumental.is_some() => LyricsBatchItem {
+                song_id: instrumental,
+                ..LyricsBatchItem::new(&path, LyricsBatchStatus::Instrumental)
+            },
+            _ if cached => LyricsBatchItem::new(&path, LyricsBatchStatus::AlreadyHad),
+            Ok((false, Ok(metadata))) => {
+                let query = TrackQuery::from_metadata(&metadata);
+                // 被限流时等限流队列恢复后再试一次；处理中开启了离线模式时暂停，关闭后重新处理该文件
+                let mut retried = false;
+                let mut outcome = loop {
+                    let outcome =
+                        auto_match_in_album(&path, &query, &album, providers, layers, &mut match_cache, &lyrics_cache)
+                            .await;
+                    match &outcome {
+                        Err(netease::NeteaseError::Offline) if !token.is_cancelled() => {
+                            wait_while_offline(&token).await;
+                        }
+                        Err(netease::NeteaseError::RateLimited(_)) if !retried && !token.is_cancelled() => {
+                            retried = true;
+                        }
+                        _ => break outcome,
+                    }
+                };
+                if !include_runners_up && let Ok(outcome) = &mut outcome {
+                    outcome.clear_runners_up();
+                }
+                batch_item(&state, &path, outcome, save_to).await
+            }
+            Ok((false, Err(e))) => LyricsBatchItem::failed(&path, e),
+            Err(e) => LyricsBatchItem::failed(&path, e),
+        };
+
+        let _ = app.emit(LYRICS_BATCH_ITEM_EVENT, LyricsBatchItemEvent {
+            task_id: task_id.clone(),
+            completed: index + 1,
+            total,
+            item: item.clone(),
+        });
+        report.push(item);
+    }
+
+    state.tasks.finish(&task_id, &token);
+    let _ = app.emit(TASK_PROGRESS_EVENT, TaskProgressEvent {
+        task_id,
+        kind: "lyrics-download".to_string(),
+        completed: total,
+        total,
+        current: None,
+    });
+    Ok(report)
+}
+
+/// 离线模式下暂停批量任务，直到关闭离线模式或任务被取消
+async fn wait_while_offline(token: &CancelToken) {
+    while is_offline() && !token.is_cancelled() {
+        tokio::time::sleep(OFFLINE_POLL_INTERVAL).await;
+    }
+}
+
+/// 按自动匹配的结果保存歌词，生成批量下载中该文件的结果
+async fn batch_item(
+    state: &State<'_, AppState>,
+    path: &str,
+    outcome: Result<AutoMatchOutcome, netease::NeteaseError>,
+    save_to: LyricsSaveTarget,
+) -> LyricsBatchItem {
+    match outcome {
+        Ok(AutoMatchOutcome::Matched { lyrics, song_id, score, match_score, runners_up, .. }) => {
+            let item = LyricsBatchItem {
+                song_id: Some(song_id),
+                score: Some(score),
+                match_score,
+                runners_up,
+                ..LyricsBatchItem::new(path, LyricsBatchStatus::Matched)
+            };
+            if lyrics.kind == netease::LyricsKind::Instrumental {
+                return LyricsBatchItem { status: LyricsBatchStatus::Instrumental, ..item };
+            }
+            if lyrics.lrc.trim().is_empty() {
+                let error = tr!("lyrics.noLyrics");
+                return LyricsBatchItem { error: Some(error), status: LyricsBatchStatus::Failed, ..item };
+            }
+            if save_to == LyricsSaveTarget::Embed && is_current_track(state, path) {
+                let error = tr!("tags.currentlyPlaying");
+                return LyricsBatchItem { error: Some(error), status: LyricsBatchStatus::Failed, ..item };
+            }
+            let lyrics = lyrics.with_offset(track_lyrics_offset(state, path));
+            let (track_path, text) = (path.to_string(), lyrics_text(&lyrics));
+            match run_blocking(move || save_lyrics(&track_path, &text, save_to).map_err(AppError::Other)).await {
+                Ok(()) => item,
+                Err(e) => LyricsBatchItem { error: Some(e.to_string()), status: LyricsBatchStatus::Failed, ..item },
+            }
+        }
+        Ok(AutoMatchOutcome::Candidates { candidates }) => {
+            let best = candidates.first();
+            LyricsBatchItem {
+                song_id: best.map(|c| c.song.id.clone()),
+                score: best.map(|c| c.score.total),
+                match_score: best.map(|c| c.score),
+                ..LyricsBatchItem::new(path, LyricsBatchStatus::LowConfidence)
+            }
+        }
+        Ok(AutoMatchOutcome::NoMatch) => LyricsBatchItem::new(path, LyricsBatchStatus::LowConfidence),
+        Err(e) => LyricsBatchItem::failed_online(path, &e),
+    }
+}
+
+/// 获取网易云音乐歌曲或专辑的封面
+///
+/// `size` 为边长像素（由服务端缩放），为空时下载原图；图片保存在封面缓存目录中，返回文件路径和 data URL。
+#[command]
+pub async fn netease_get_cover(
+    state: State<'_, AppState>,
+    song_id: Option<String>,
+    album_id: Option<String>,
+    size: Option<u32>,
+) -> Result<netease::CoverImage, netease::NeteaseError> {
+    let cache_dir = state.config_manager.get_cache_directory("covers").map_err(netease::NeteaseError::Cache)?;
+    netease::get_cover(song_id.as_deref(), album_id.as_deref(), size.unwrap_or(0), &cache_dir).await
+}
+
+/// 获取网易云音乐歌单的全部歌曲
+///
+/// `playlist` 可以是歌单 ID、歌单链接或 App 分享的文本；私密歌单返回 `PRIVATE`，地区限制返回 `BLOCKED`。
+#[command]
+pub async fn netease_get_playlist(playlist: String) -> Result<netease::OnlinePlaylist, netease::NeteaseError> {
+    netease::get_playlist(&playlist).await
+}
+
+/// 用自动匹配找到音轨对应的在线歌曲，作为封面来源
+async fn auto_cover_source(state: &State<'_, AppState>, path: &str) -> AppResult<CoverSource> {
+    let options = metadata_options(state);
+    let cache_dir = state.config_manager.get_cache_directory("lyrics")?;
+    let track_path = path.to_string();
+    let metadata = run_blocking(move || Ok(cached_track_metadata(&track_path, &options)?)).await?;
+
+    let mut cache = LyricsMatchCache::load(&cache_dir);
+    let lyrics_cache = lyrics_cache(state)?;
+    let query = TrackQuery::from_metadata(&metadata);
+    let layers = lyric_layers(state);
+    let outcome =
+        auto_match_lyrics(path, &query, &state.lyrics_providers, layers, &mut cache, &lyrics_cache).await?;
+    match outcome {
+        AutoMatchOutcome::Matched { provider, song_id, .. } if provider == "netease" => {
+            Ok(CoverSource::NeteaseSong(song_id))
+        }
+        AutoMatchOutcome::Matched { song: Some(netease::SearchSongResult { cover_url: Some(url), .. }), .. } => {
+            Ok(CoverSource::Url(url))
+        }
+        AutoMatchOutcome::Matched { .. } => Err(AppError::Other(tr!("cover.matchHasNoCover"))),
+        AutoMatchOutcome::Candidates { .. } | AutoMatchOutcome::NoMatch => {
+            Err(AppError::Other(tr!("cover.noConfidentMatch")))
+        }
+    }
+}
+
+/// 下载封面原图并按设置的最大边长准备要嵌入的图片
+async fn download_embed_cover(state: &State<'_, AppState>, source: &str, track_path: &str) -> AppResult<EmbedCover> {
+    let source = match CoverSource::parse(source)? {
+        CoverSource::Auto => auto_cover_source(state, track_path).await?,
+        source => source,
+    };
+    let cache_dir = state.config_manager.get_cache_directory("covers")?;
+    let image = fetch_cover(&source, &cache_dir).await?;
+    let max_size = state.config_manager.load_config()?.metadata.max_embed_cover_size;
+    run_blocking(move || Ok(prepare_embed_cover(Path::new(&image.path), max_size)?)).await
+}
+
+/// 下载在线封面并作为正面封面嵌入音频文件
+///
+/// `source` 为图片地址、网易云音乐歌曲 ID 或 `auto`（使用自动匹配到的歌曲）；图片超过设置的最大边长时先缩小。
+/// `dry_run` 为 `true` 时只返回要嵌入的图片供预览。写入后更新元数据缓存并发送 `track-metadata-changed`。
+#[command]
+pub async fn fetch_and_embed_cover(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    audio_path: String,
+    source: String,
+    dry_run: Option<bool>,
+) -> AppResult<EmbedCoverResult> {
+    let cover = download_embed_cover(&state, &source, &audio_path).await?;
+    if dry_run.unwrap_or(false) {
+        return Ok(EmbedCoverResult { cover, metadata: None });
+    }
+    if is_current_track(&state, &audio_path) {
+        return Err(AppError::Other(tr!("tags.currentlyPlaying")));
+    }
+
+    let options = metadata_options(&state);
+    run_blocking(move || {
+        write_tags_checked(&audio_path, &TagChanges::default(), Some(&cover.cover_change()?))
+            .map_err(|e| AppError::Other(e.to_string()))?;
+        let metadata = refresh_and_notify(&app, &audio_path, &options)?;
+        Ok(EmbedCoverResult { cover, metadata: Some(metadata) })
+    })
+    .await
+}
+
+/// 为专辑中的多个文件嵌入同一在线封面
+///
+/// 图片只下载一次；`source` 为 `auto` 时用第一个文件自动匹配。写入规则与 `write_album_metadata` 相同，
+/// 在后台线程中逐个写入并发送 `task-progress` 事件；`dry_run` 为 `true` 时只返回要嵌入的图片。
+#[command]
+pub async fn fetch_and_embed_album_cover(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    paths: Vec<String>,
+    source: String,
+    dry_run: Option<bool>,
+    task_id: Option<String>,
+) -> AppResult<AlbumCoverEmbedResult> {
+    let first = paths.first().ok_or_else(|| AppError::Other(tr!("tags.noFiles")))?;
+    let cover = download_embed_cover(&state, &source, first).await?;
+    if dry_run.unwrap_or(false) {
+        return Ok(AlbumCoverEmbedResult { cover, results: Vec::new() });
+    }
+
+    let options = metadata_options(&state);
+    let tasks = Arc::clone(&state.tasks);
+    let current_path = Arc::clone(&state.player.current_path);
+    let task_id = task_id.unwrap_or_else(|| "embed-album-cover".to_string());
+
+    run_blocking(move || {
+        let change = cover.cover_change()?;
+        let token = tasks.start(&task_id);
+        let total = paths.len();
+        let is_playing = |path: &str| {
+            current_path
+                .lock()
+                .unwrap()
+                .as_deref()
+                .is_some_and(|current| same_path(current, path))
+        };
+        let on_progress = |completed: usize, path: &str| {
+            let _ = app.emit(TASK_PROGRESS_EVENT, TaskProgressEvent {
+                task_id: task_id.clone(),
+                kind: "write-tags".to_string(),
+                completed,
+                total,
+                current: Some(path.to_string()),
+            });
+        };
+
+        let mut results =
+            write_files_checked(&paths, &TagChanges::default(), Some(&change), is_playing, &token, on_progress);
+        tasks.finish(&task_id, &token);
+        for result in results.iter_mut().filter(|result| result.metadata.is_some()) {
+            if let Ok(metadata) = refresh_and_notify(&app, &result.path, &options) {
+                result.metadata = Some(metadata);
+            }
+        }
+
+        let _ = app.emit(TASK_PROGRESS_EVENT, TaskProgressEvent {
+            task_id: task_id.clone(),
+            kind: "write-tags".to_string(),
+            completed: total,
+            total,
+            current: None,
+        });
+        Ok(AlbumCoverEmbedResult { cover, results })
+    })
+    .await
+}
+
+
+/// 获取音轨封面（data URL），没有封面时返回 `None`
+#[command]
+pub fn get_track_cover(state: State<AppState>, path: String) -> Result<Option<String>, String> {
+    get_track_cover_internal(&path, &metadata_options(&state))
+}
+
+/// 按模板（如 `{artist} - {title}`，占位符与整理文件相同，为空时使用 `{artist} - {title}`）
+/// 把当前音轨的信息复制到剪贴板，返回复制的文本
+#[command]
+pub async fn copy_track_info(app: AppHandle, state: State<'_, AppState>, format: Option<String>) -> AppResult<String> {
+    let options = metadata_options(&state);
+    let format = format.filter(|f| !f.trim().is_empty()).unwrap_or_else(|| DEFAULT_TRACK_INFO_FORMAT.to_string());
+    run_blocking(move || clipboard::copy_track_info(&app, &format, &options)).await
+}
+
+/// 把当前音轨的文件路径复制到剪贴板，返回复制的路径
+#[command]
+pub fn copy_file_path(app: AppHandle) -> AppResult<String> {
+    clipboard::copy_file_path(&app)
+}
+
+/// 把当前音轨的封面图片复制到剪贴板；没有封面或平台剪贴板不支持图片时返回错误
+#[command]
+pub async fn copy_cover_image(app: AppHandle, state: State<'_, AppState>) -> AppResult<CopiedCover> {
+    let options = metadata_options(&state);
+    run_blocking(move || clipboard::copy_cover_image(&app, &options)).await
+}
 
 /// 提取音频文件的封面并保存到指定路径
 #[command]
-pub fn extract_cover(audio_path: String, output_path: String) -> Result<String, String> {
-    extract_cover_internal(&audio_path, &output_path)
+pub fn extract_cover(
+    audio_path: String,
+    output_path: String,
+    options: Option<ExtractCoverOptions>,
+) -> Result<String, String> {
+    extract_cover_internal(&audio_path, &output_path, &options.unwrap_or_default())
+}
+
+/// 移除音频文件中嵌入的封面
+///
+/// `picture_type` 为空时移除全部图片；正在播放的文件不允许修改。
+/// 移除后更新元数据缓存和曲库记录（包括 `has_cover`）并发送 `track-metadata-changed`。
+#[command]
+pub fn remove_track_cover(
+    app: AppHandle,
+    state: State<AppState>,
+    audio_path: String,
+    picture_type: Option<String>,
+) -> Result<RemoveCoverResult, String> {
+    if is_current_track(&state, &audio_path) {
+        return Err(tr!("tags.currentlyPlaying"));
+    }
+
+    let picture_type = picture_type.as_deref().map(parse_picture_type).transpose()?;
+    let mut result = remove_track_cover_internal(&audio_path, picture_type)?;
+    if result.removed_count > 0 {
+        result.metadata = refresh_written(&app, &state, &audio_path, result.metadata);
+    }
+    Ok(result)
+}
+
+/// 解析 CUE 表单文件
+#[command]
+pub fn parse_cue_sheet(path: String) -> Result<CueSheet, String> {
+    parse_cue_sheet_internal(&path)
+}
+
+/// 修改音轨标签
+///
+/// 写入后更新元数据缓存和曲库记录，并发送 `track-metadata-changed`。
+#[command]
+pub fn write_track_tags(
+    app: AppHandle,
+    state: State<AppState>,
+    path: String,
+    changes: TagChanges,
+) -> Result<TrackMetadata, String> {
+    if is_current_track(&state, &path) {
+        return Err(tr!("tags.currentlyPlaying"));
+    }
+
+    let metadata = write_track_tags_internal(&path, &changes)?;
+    Ok(refresh_written(&app, &state, &path, metadata))
+}
+
+/// 歌词时间偏移的最大绝对值（毫秒）
+const MAX_LYRICS_OFFSET_MS: i64 = 10 * 60 * 1000;
+
+/// 读取音轨的歌词时间偏移（毫秒），正数表示歌词推后，没有设置时为 0
+#[command]
+pub fn get_lyrics_offset(state: State<AppState>, path: String) -> AppResult<i64> {
+    state.library.lyrics_offset(&path)
+}
+
+/// 设置音轨的歌词时间偏移（毫秒），为 0 时清除
+///
+/// 偏移保存在曲库数据库中，重新获取歌词后仍然有效；后端为该音轨返回在线歌词时应用到所有时间上。
+#[command]
+pub fn set_lyrics_offset(state: State<AppState>, path: String, offset_ms: i64) -> AppResult<()> {
+    if offset_ms.abs() > MAX_LYRICS_OFFSET_MS {
+        return Err(AppError::Other(tr!("lyrics.offsetTooLarge", seconds = MAX_LYRICS_OFFSET_MS / 1000)));
+    }
+    state.library.set_lyrics_offset(&path, offset_ms)
+}
+
+/// 读取文件中嵌入的歌词
+#[command]
+pub fn get_embedded_lyrics(path: String) -> Result<Option<EmbeddedLyrics>, String> {
+    get_embedded_lyrics_internal(&path)
+}
+
+/// 将歌词写入文件标签，替换已有的歌词
+///
+/// `synced` 为 `true` 时 MP3 额外写入 SYLT 同步歌词。
+#[command]
+pub fn write_embedded_lyrics(
+    state: State<AppState>,
+    path: String,
+    lrc_text: String,
+    synced: bool,
+) -> Result<(), String> {
+    if is_current_track(&state, &path) {
+        return Err(tr!("tags.currentlyPlaying"));
+    }
+
+    write_embedded_lyrics_internal(&path, &lrc_text, synced)
+}
+
+/// 按指定编码修复乱码标签
+///
+/// 只在用户确认后调用，扫描时不会自动改写文件。写入后同 `write_track_tags` 更新缓存并通知前端。
+#[command]
+pub fn fix_tag_encoding(
+    app: AppHandle,
+    state: State<AppState>,
+    path: String,
+    encoding: String,
+) -> Result<TrackMetadata, String> {
+    if is_current_track(&state, &path) {
+        return Err(tr!("tags.currentlyPlaying"));
+    }
+
+    let metadata = fix_tag_encoding_internal(&path, &encoding)?;
+    Ok(refresh_written(&app, &state, &path, metadata))
+}
+
+/// 计算音轨的声学指纹
+///
+/// 在后台线程中运行，可通过 `cancel_task` 取消；结果按文件哈希缓存。
+#[command]
+pub async fn compute_fingerprint(
+    state: State<'_, AppState>,
+    path: String,
+    task_id: Option<String>,
+) -> AppResult<FingerprintResult> {
+    let cache_dir = state.config_manager.get_cache_directory("fingerprint")?;
+    let tasks = Arc::clone(&state.tasks);
+
+    run_blocking(move || {
+        let task_id = task_id.unwrap_or_else(|| format!("fingerprint:{path}"));
+        let token = tasks.start(&task_id);
+        let mut cache = FingerprintCache::load(&cache_dir);
+        let result = fingerprint_with_cache(&path, &mut cache, &token);
+        tasks.finish(&task_id, &token);
+
+        if result.is_ok() {
+            if let Err(e) = cache.save() {
+                eprintln!("Failed to save fingerprint cache: {e}");
+            }
+        }
+        result
+    })
+    .await
+}
+
+/// 批量计算声学指纹
+///
+/// 每处理完一个文件发送一次 `task-progress` 事件；单个文件失败不会中断整个批次。
+/// 使用电池时暂停，接上电源后继续（见 [`power::pause_while_on_battery`]）。
+#[command]
+pub async fn compute_fingerprints_batch(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    paths: Vec<String>,
+    task_id: String,
+) -> AppResult<Vec<FingerprintBatchItem>> {
+    let cache_dir = state.config_manager.get_cache_directory("fingerprint")?;
+    let tasks = Arc::clone(&state.tasks);
+
+    run_blocking(move || {
+        let token = tasks.start(&task_id);
+        let mut cache = FingerprintCache::load(&cache_dir);
+        let total = paths.len();
+        let mut items = Vec::with_capacity(total);
+
+        for (index, path) in paths.into_iter().enumerate() {
+            power::pause_while_on_battery(&app, &task_id, &token);
+            let _ = app.emit(TASK_PROGRESS_EVENT, TaskProgressEvent {
+                task_id: task_id.clone(),
+                kind: "fingerprint".to_string(),
+                completed: index,
+                total,
+                current: Some(path.clone()),
+            });
+
+            match fingerprint_with_cache(&path, &mut cache, &token) {
+                Ok(result) => items.push(FingerprintBatchItem { path, result: Some(result), error: None }),
+                Err(AppError::Cancelled) => break,
+                Err(e) => items.push(FingerprintBatchItem { path, result: None, error: Some(e.to_string()) }),
+            }
+        }
+
+        tasks.finish(&task_id, &token);
+        if let Err(e) = cache.save() {
+            eprintln!("Failed to save fingerprint cache: {e}");
+        }
+
+        if token.is_cancelled() {
+            return Err(AppError::Cancelled);
+        }
+
+        let _ = app.emit(TASK_PROGRESS_EVENT, TaskProgressEvent {
+            task_id,
+            kind: "fingerprint".to_string(),
+            completed: total,
+            total,
+            current: None,
+        });
+        Ok(items)
+    })
+    .await
+}
+
+/// 检查音频文件是否完整
+///
+/// 默认只做快速检查（头部、时长和结尾）；`full_decode` 为 `true` 时把每个文件解码到结尾并校验 FLAC 的 MD5，
+/// 开始前发送 `validation-estimate` 事件说明预计耗时。文件在线程池中并行检查，每检查完一个文件发送一次
+/// `task-progress` 事件，可通过 `cancel_task` 取消。
+#[command]
+pub async fn validate_audio_files(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    paths: Vec<String>,
+    full_decode: Option<bool>,
+    task_id: Option<String>,
+) -> AppResult<Vec<ValidationResult>> {
+    let full_decode = full_decode.unwrap_or(false);
+    let tasks = Arc::clone(&state.tasks);
+    let task_id = task_id.unwrap_or_else(|| "validate-audio".to_string());
+
+    run_blocking(move || {
+        let token = tasks.start(&task_id);
+        if full_decode {
+            let _ = app.emit(VALIDATION_ESTIMATE_EVENT, estimate_validation(&task_id, &paths));
+        }
+        let on_progress = |completed: usize, total: usize, path: &str| {
+            let _ = app.emit(TASK_PROGRESS_EVENT, TaskProgressEvent {
+                task_id: task_id.clone(),
+                kind: "validate".to_string(),
+                completed,
+                total,
+                current: (!path.is_empty()).then(|| path.to_string()),
+            });
+        };
+        let result = validate_audio_files_internal(&paths, full_decode, &token, on_progress);
+        tasks.finish(&task_id, &token);
+        result
+    })
+    .await
+}
+
+/// 计算去掉标签区域后的文件哈希（SHA-256），用于精确重复检测
+#[command]
+pub async fn compute_file_hash(path: String) -> AppResult<String> {
+    run_blocking(move || compute_file_hash_internal(&path).map_err(AppError::Other)).await
+}
+
+/// 计算解码后音频数据的哈希（SHA-256）
+///
+/// 与标签无关，结果按路径和修改时间缓存，可通过 `cancel_task` 取消。
+#[command]
+pub async fn compute_audio_hash(
+    state: State<'_, AppState>,
+    path: String,
+    task_id: Option<String>,
+) -> AppResult<String> {
+    let cache_dir = state.config_manager.get_cache_directory("hash")?;
+    let tasks = Arc::clone(&state.tasks);
+
+    run_blocking(move || {
+        let task_id = task_id.unwrap_or_else(|| format!("audio-hash:{path}"));
+        let token = tasks.start(&task_id);
+        let mut cache = HashCache::load(&cache_dir);
+        let result = cache.get_or_compute(&path, HashKind::Audio, &token);
+        tasks.finish(&task_id, &token);
+
+        if result.is_ok() {
+            if let Err(e) = cache.save() {
+                eprintln!("Failed to save hash cache: {e}");
+            }
+        }
+        result
+    })
+    .await
+}
+
+/// 按哈希查找重复文件
+///
+/// `kind` 默认为 `file`（去掉标签后的文件字节），`audio` 则比较解码后的音频。
+/// 每处理一个文件发送一次 `task-progress` 事件，已计算的哈希即使被取消也会写入缓存。
+#[command]
+pub async fn find_duplicates(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    paths: Vec<String>,
+    kind: Option<HashKind>,
+    task_id: Option<String>,
+) -> AppResult<DuplicateReport> {
+    let cache_dir = state.config_manager.get_cache_directory("hash")?;
+    let tasks = Arc::clone(&state.tasks);
+    let task_id = task_id.unwrap_or_else(|| "find-duplicates".to_string());
+
+    run_blocking(move || {
+        let token = tasks.start(&task_id);
+        let mut cache = HashCache::load(&cache_dir);
+        let total = paths.len();
+        let on_progress = |completed: usize, path: &str| {
+            let _ = app.emit(TASK_PROGRESS_EVENT, TaskProgressEvent {
+                task_id: task_id.clone(),
+                kind: "hash".to_string(),
+                completed,
+                total,
+                current: (!path.is_empty()).then(|| path.to_string()),
+            });
+        };
+
+        let result = find_duplicates_internal(&paths, kind.unwrap_or(HashKind::File), &mut cache, &token, on_progress);
+        tasks.finish(&task_id, &token);
+        if let Err(e) = cache.save() {
+            eprintln!("Failed to save hash cache: {e}");
+        }
+        result
+    })
+    .await
+}
+
+/// 通过 AcoustID / MusicBrainz 查询音轨信息
+///
+/// 只返回候选结果，不会修改文件；写入需显式调用 `apply_lookup_result`。
+#[command]
+pub async fn lookup_track(state: State<'_, AppState>, path: String) -> AppResult<LookupOutcome> {
+    let config = state.config_manager.load_config()?;
+    let api_key = config.metadata.acoustid_api_key.clone();
+    let options = MetadataOptions::from_config(&config);
+    let cache_dir = state.config_manager.get_cache_directory("fingerprint")?;
+    let tasks = Arc::clone(&state.tasks);
+    let use_fingerprint = !api_key.trim().is_empty();
+
+    let (metadata, fingerprint) = run_blocking(move || {
+        let metadata = get_track_metadata_with_options(&path, &options)?;
+        let fingerprint = if use_fingerprint {
+            let task_id = format!("lookup:{path}");
+            let token = tasks.start(&task_id);
+            let mut cache = FingerprintCache::load(&cache_dir);
+            let result = fingerprint_with_cache(&path, &mut cache, &token);
+            tasks.finish(&task_id, &token);
+            if result.is_ok() {
+                if let Err(e) = cache.save() {
+                    eprintln!("Failed to save fingerprint cache: {e}");
+                }
+            }
+            result.map_err(|e| eprintln!("Fingerprint unavailable for '{path}': {e}")).ok()
+        } else {
+            None
+        };
+        Ok((metadata, fingerprint))
+    })
+    .await?;
+
+    // 标题回退为文件名时，去掉扩展名再搜索
+    let title = match metadata.title.as_deref() {
+        Some(title) if title == metadata.name => Path::new(title)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string()),
+        other => other.map(str::to_string),
+    };
+
+    Ok(lookup_recording(
+        &api_key,
+        fingerprint
+            .as_ref()
+            .map(|fp| (fp.fingerprint.as_str(), fp.duration.unwrap_or(fp.analyzed_duration))),
+        title.as_deref(),
+        metadata.artist.as_deref(),
+    )
+    .await)
+}
+
+/// 将选中的查询结果写入标签（包括 MusicBrainz ID）
+#[command]
+pub fn apply_lookup_result(
+    app: AppHandle,
+    state: State<AppState>,
+    path: String,
+    candidate: LookupCandidate,
+) -> Result<TrackMetadata, String> {
+    if is_current_track(&state, &path) {
+        return Err(tr!("tags.currentlyPlaying"));
+    }
+
+    let metadata = write_track_tags_internal(&path, &candidate.to_tag_changes())?;
+    Ok(refresh_written(&app, &state, &path, metadata))
+}
+
+/// 将源文件的标签复制到目标文件
+///
+/// `fields` 为空时复制全部字段，包括图片和歌词。复制后更新目标文件的缓存和曲库记录并通知前端。
+#[command]
+pub fn copy_tags(
+    app: AppHandle,
+    state: State<AppState>,
+    source_path: String,
+    dest_path: String,
+    fields: Option<Vec<String>>,
+) -> Result<CopyTagsResult, String> {
+    if is_current_track(&state, &dest_path) {
+        return Err(tr!("tags.currentlyPlaying"));
+    }
+
+    let mut result = copy_tags_internal(&source_path, &dest_path, fields.as_deref())?;
+    if !result.copied.is_empty() {
+        result.metadata = refresh_written(&app, &state, &dest_path, result.metadata);
+    }
+    Ok(result)
+}
+
+/// 批量写入专辑共享字段
+///
+/// 在后台线程中逐个写入并发送 `task-progress` 事件，返回每个文件的结果。
+/// 写入成功的文件更新元数据缓存和曲库记录，并发送 `track-metadata-changed`。
+#[command]
+pub async fn write_album_metadata(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    paths: Vec<String>,
+    changes: AlbumChanges,
+    task_id: Option<String>,
+) -> AppResult<Vec<AlbumWriteResult>> {
+    let options = metadata_options(&state);
+    let tasks = Arc::clone(&state.tasks);
+    let current_path = Arc::clone(&state.player.current_path);
+    let task_id = task_id.unwrap_or_else(|| "write-album-metadata".to_string());
+
+    run_blocking(move || {
+        let token = tasks.start(&task_id);
+        let total = paths.len();
+        let is_playing = |path: &str| {
+            current_path
+                .lock()
+                .unwrap()
+                .as_deref()
+                .is_some_and(|current| same_path(current, path))
+        };
+        let on_progress = |completed: usize, path: &str| {
+            let _ = app.emit(TASK_PROGRESS_EVENT, TaskProgressEvent {
+                task_id: task_id.clone(),
+                kind: "write-tags".to_string(),
+                completed,
+                total,
+                current: Some(path.to_string()),
+            });
+        };
+
+        let mut results = write_album_metadata_internal(&paths, &changes, is_playing, &token, on_progress);
+        tasks.finish(&task_id, &token);
+        for result in results.iter_mut().flatten().filter(|result| result.metadata.is_some()) {
+            if let Ok(metadata) = refresh_and_notify(&app, &result.path, &options) {
+                result.metadata = Some(metadata);
+            }
+        }
+
+        let _ = app.emit(TASK_PROGRESS_EVENT, TaskProgressEvent {
+            task_id: task_id.clone(),
+            kind: "write-tags".to_string(),
+            completed: total,
+            total,
+            current: None,
+        });
+        results.map_err(AppError::Other)
+    })
+    .await
+}
+
+/// 按标签模板重命名/移动文件
+///
+/// `dry_run` 为 `true` 时只返回计划的路径变更。实际移动时和 `move_files` 一样更新曲库数据库和元数据缓存，
+/// 之后发送 `track-paths-changed` 事件，前端据此更新播放队列和缓存。
+#[command]
+pub async fn organize_files(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    paths: Vec<String>,
+    pattern: String,
+    dry_run: bool,
+) -> AppResult<OrganizeResult> {
+    let config = state.config_manager.load_config()?;
+    let options = MetadataOptions::from_config(&config);
+    let library = Arc::clone(&state.library);
+    let current_path = Arc::clone(&state.player.current_path);
+
+    let result = run_blocking(move || {
+        let directories = &config.music_directories;
+        organize_files_internal(&library, &paths, &pattern, dry_run, directories, &options, &current_path)
+    })
+    .await?;
+
+    if !result.dry_run && !result.moves.is_empty() {
+        let _ = app.emit(TRACK_PATHS_CHANGED_EVENT, &result.moves);
+    }
+    Ok(result)
+}
+
+/// 批量移动或重命名文件
+///
+/// 目标目录不存在时自动创建，目标文件已存在时不覆盖（冲突原因 `exists`）。曲库数据库在同一事务中更新，
+/// 移动成功后发送 `track-paths-changed` 事件，前端据此更新播放队列、播放列表和播放统计。
+#[command]
+pub async fn move_files(app: AppHandle, state: State<'_, AppState>, moves: Vec<FileMove>) -> AppResult<MoveFilesResult> {
+    let library = Arc::clone(&state.library);
+    let current_path = Arc::clone(&state.player.current_path);
+
+    let result = run_blocking(move || move_files_internal(&library, moves, &current_path)).await?;
+
+    if !result.moved.is_empty() {
+        let _ = app.emit(TRACK_PATHS_CHANGED_EVENT, &result.moved);
+    }
+    Ok(result)
+}
+
+/// 移动或重命名单个文件
+///
+/// 目标已存在时返回 `CONFLICT` 错误，源文件不存在时返回 `FILE_NOT_FOUND`。
+/// 其余行为与 `move_files` 相同。
+#[command]
+pub async fn move_file(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    source: String,
+    destination: String,
+) -> AppResult<FileMove> {
+    let file_move = FileMove { from: source, to: destination };
+    let mut result = move_files(app, state, vec![file_move.clone()]).await?;
+
+    if let Some(moved) = result.moved.pop() {
+        return Ok(moved);
+    }
+    match result.conflicts.pop() {
+        Some(FileConflict { to: Some(to), reason, .. }) if reason == "exists" => Err(AppError::Conflict(to)),
+        Some(FileConflict { from, reason, .. }) if reason == "missing" => Err(AppError::FileNotFound(from)),
+        Some(FileConflict { reason, .. }) => Err(AppError::Other(reason)),
+        // 源路径与目标路径相同，无需移动
+        None => Ok(file_move),
+    }
+}
+
+/// 统计文件夹中的音频文件数、总大小、总时长和各格式的分布
+///
+/// 已扫描到曲库中的目录直接查询数据库；否则按扫描过滤规则遍历目录，每读取一个文件发送一次
+/// `task-progress` 事件，可通过 `cancel_task` 取消。
+#[command]
+pub async fn get_folder_stats(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    path: String,
+    task_id: Option<String>,
+) -> AppResult<FolderStats> {
+    let options = metadata_options(&state);
+    let filter = scan_filter(&state);
+    let library = Arc::clone(&state.library);
+    let tasks = Arc::clone(&state.tasks);
+    let task_id = task_id.unwrap_or_else(|| format!("folder-stats:{path}"));
+
+    run_blocking(move || {
+        let token = tasks.start(&task_id);
+        let on_progress = |completed: usize, total: usize, current: &str| {
+            let _ = app.emit(TASK_PROGRESS_EVENT, TaskProgressEvent {
+                task_id: task_id.clone(),
+                kind: "folder-stats".to_string(),
+                completed,
+                total,
+                current: Some(current.to_string()),
+            });
+        };
+        let result = folder_stats_internal(&library, &path, &filter, &options, &token, on_progress);
+        tasks.finish(&task_id, &token);
+        result
+    })
+    .await
+}
+
+/// 把曲库外的文件导入到音乐目录
+///
+/// `paths` 可以包含文件和目录（目录中的音频文件都会导入）。每处理一个文件发送一次 `task-progress` 事件，
+/// 可通过 `cancel_task` 取消。导入完成后发送 `files-imported` 事件，前端据此刷新曲库，
+/// `addToQueue` 为真时把新文件加入播放队列。
+#[command]
+pub async fn import_files(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    paths: Vec<String>,
+    options: Option<ImportFilesOptions>,
+    task_id: Option<String>,
+) -> AppResult<ImportFilesResult> {
+    let config = state.config_manager.load_config()?;
+    let options = options.unwrap_or_default();
+    let root = options
+        .destination
+        .clone()
+        .or_else(|| config.music_directories.first().cloned())
+        .ok_or_else(|| AppError::Config(tr!("library.noMusicDirectory")))?;
+    let metadata_options = MetadataOptions::from_config(&config);
+    let library = Arc::clone(&state.library);
+    let tasks = Arc::clone(&state.tasks);
+    let task_id = task_id.unwrap_or_else(|| "import-files".to_string());
+
+    let (result, event) = run_blocking({
+        let app = app.clone();
+        move || {
+            let token = tasks.start(&task_id);
+            let on_progress = |completed: usize, total: usize, path: &str| {
+                let _ = app.emit(TASK_PROGRESS_EVENT, TaskProgressEvent {
+                    task_id: task_id.clone(),
+                    kind: "import".to_string(),
+                    completed,
+                    total,
+                    current: Some(path.to_string()),
+                });
+            };
+            let files = collect_audio_paths(&paths);
+            let result = import_files_internal(
+                &library,
+                &files,
+                Path::new(&root),
+                &options,
+                &metadata_options,
+                &token,
+                on_progress,
+            )
+            .map(|(result, tracks)| (result, FilesImportedEvent { tracks, add_to_queue: options.add_to_queue }));
+            tasks.finish(&task_id, &token);
+            result
+        }
+    })
+    .await?;
+
+    if !event.tracks.is_empty() {
+        let _ = app.emit(FILES_IMPORTED_EVENT, event);
+    }
+    Ok(result)
+}
+
+/// 开始监视曲库目录
+///
+/// `directories` 为空时使用配置中的音乐目录。文件变化合并后以 `library-files-added`、
+/// `library-files-removed`、`library-files-modified` 事件发送，目录丢失时发送 `watch-lost`。
+#[command]
+pub fn start_library_watch(
+    app: AppHandle,
+    state: State<AppState>,
+    directories: Option<Vec<String>>,
+) -> AppResult<()> {
+    let config = state.config_manager.load_config()?;
+    let directories = directories.unwrap_or(config.music_directories.clone());
+    state
+        .library_watcher
+        .start(app, directories, MetadataOptions::from_config(&config), ScanFilter::from_config(&config))
+        .map_err(AppError::Other)
+}
+
+/// 停止监视曲库目录
+#[command]
+pub fn stop_library_watch(state: State<AppState>) {
+    state.library_watcher.stop();
+}
+
+/// 按配置在启动时开始监视音乐目录
+pub fn start_configured_library_watch(app: &AppHandle) {
+    if let Ok(config) = app.state::<AppState>().config_manager.load_config() {
+        watcher::apply_config(app, &config);
+    }
+}
+
+/// 导出曲库元数据到 JSON 或 CSV 文件
+///
+/// `paths` 可以是目录或文件，逐条写入输出文件，过程中发送 `task-progress` 事件，可通过 `cancel_task` 取消。
+#[command]
+pub async fn export_library(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    paths: Vec<String>,
+    format: ExportFormat,
+    output_path: String,
+    task_id: Option<String>,
+) -> AppResult<ExportResult> {
+    let options = metadata_options(&state);
+    let tasks = Arc::clone(&state.tasks);
+    let task_id = task_id.unwrap_or_else(|| "export-library".to_string());
+
+    run_blocking(move || {
+        let token = tasks.start(&task_id);
+        let on_progress = |completed: usize, total: usize, path: &str| {
+            let _ = app.emit(TASK_PROGRESS_EVENT, TaskProgressEvent {
+                task_id: task_id.clone(),
+                kind: "export".to_string(),
+                completed,
+                total,
+                current: (!path.is_empty()).then(|| path.to_string()),
+            });
+        };
+
+        let result = export_library_internal(&paths, format, &output_path, &options, &token, on_progress);
+        tasks.finish(&task_id, &token);
+        result
+    })
+    .await
+}
+
+/// 从 CSV 文件的 rating 列导入评分（1-5，0 表示清除），正在播放的文件会被跳过
+#[command]
+pub async fn import_ratings(state: State<'_, AppState>, csv_path: String) -> AppResult<ImportRatingsResult> {
+    let current_path = Arc::clone(&state.player.current_path);
+
+    run_blocking(move || {
+        let is_playing = |path: &str| {
+            current_path
+                .lock()
+                .unwrap()
+                .as_deref()
+                .is_some_and(|current| same_path(current, path))
+        };
+        import_ratings_internal(&csv_path, is_playing).map_err(AppError::Other)
+    })
+    .await
+}
+
+/// 导入 M3U/M3U8、PLS 或 XSPF 播放列表文件，找不到的文件保留并列在 `missing` 中
+#[command]
+pub async fn import_playlist(state: State<'_, AppState>, path: String) -> AppResult<ImportedPlaylist> {
+    let options = metadata_options(&state);
+    run_blocking(move || import_playlist_internal(&path, &options).map_err(AppError::Other)).await
+}
+
+/// 导出播放列表文件，未指定格式时根据扩展名判断
+#[command]
+pub async fn export_playlist(
+    state: State<'_, AppState>,
+    output_path: String,
+    paths: Vec<String>,
+    name: Option<String>,
+    format: Option<PlaylistFormat>,
+    relative: Option<bool>,
+) -> AppResult<PlaylistExportResult> {
+    let options = metadata_options(&state);
+    run_blocking(move || {
+        export_playlist_internal(&output_path, name.as_deref(), &paths, format, relative.unwrap_or(false), &options)
+            .map_err(AppError::Other)
+    })
+    .await
+}
+
+/// 在 `search_roots` 中为丢失的文件寻找候选，返回旧路径到候选的映射建议
+///
+/// 需要读取候选文件元数据时每处理一个文件发送一次 `task-progress` 事件。
+#[command]
+pub async fn relink_missing_tracks(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    missing_paths: Vec<String>,
+    search_roots: Vec<String>,
+    task_id: Option<String>,
+) -> AppResult<Vec<RelinkProposal>> {
+    let options = metadata_options(&state);
+    let filter = scan_filter(&state);
+    let library = Arc::clone(&state.library);
+    let tasks = Arc::clone(&state.tasks);
+    let task_id = task_id.unwrap_or_else(|| "relink-missing".to_string());
+
+    run_blocking(move || {
+        let token = tasks.start(&task_id);
+        let on_progress = |completed: usize, total: usize, path: &str| {
+            let _ = app.emit(TASK_PROGRESS_EVENT, TaskProgressEvent {
+                task_id: task_id.clone(),
+                kind: "relink".to_string(),
+                completed,
+                total,
+                current: Some(path.to_string()),
+            });
+        };
+        let result = relink_missing_tracks_internal(
+            &library,
+            &missing_paths,
+            &search_roots,
+            &filter,
+            &options,
+            &token,
+            on_progress,
+        );
+        tasks.finish(&task_id, &token);
+        result
+    })
+    .await
+}
+
+/// 应用确认后的重新定位映射
+///
+/// 更新曲库数据库后发送 `track-paths-changed` 事件，前端据此更新播放队列和播放统计。
+#[command]
+pub async fn apply_relink(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    mappings: Vec<FileMove>,
+) -> AppResult<RelinkApplyResult> {
+    let library = Arc::clone(&state.library);
+    let result = run_blocking(move || apply_relink_internal(&library, mappings)).await?;
+    if !result.applied.is_empty() {
+        let _ = app.emit(TRACK_PATHS_CHANGED_EVENT, &result.applied);
+    }
+    Ok(result)
+}
+
+/// 检查曲库以及 `references`（前端的播放队列、播放列表等）中的文件是否存在
+///
+/// 返回按路径排列的丢失文件及引用它们的来源，每检查一批路径发送一次 `task-progress` 事件。
+#[command]
+pub async fn find_missing_files(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    references: Option<Vec<PathReferences>>,
+    task_id: Option<String>,
+) -> AppResult<MissingFilesReport> {
+    let library = Arc::clone(&state.library);
+    let tasks = Arc::clone(&state.tasks);
+    let task_id = task_id.unwrap_or_else(|| "find-missing".to_string());
+
+    run_blocking(move || {
+        let token = tasks.start(&task_id);
+        let on_progress = |completed: usize, total: usize| {
+            let _ = app.emit(TASK_PROGRESS_EVENT, TaskProgressEvent {
+                task_id: task_id.clone(),
+                kind: "missing".to_string(),
+                completed,
+                total,
+                current: None,
+            });
+        };
+        let result = find_missing_files_internal(&library, &references.unwrap_or_default(), &token, on_progress);
+        tasks.finish(&task_id, &token);
+        result
+    })
+    .await
+}
+
+/// 清除对丢失文件的引用
+///
+/// 从曲库数据库和元数据缓存中删除后发送 `missing-files-pruned` 事件，前端据此更新播放队列和播放统计。
+#[command]
+pub async fn prune_missing(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    paths: Vec<String>,
+) -> AppResult<PruneResult> {
+    let library = Arc::clone(&state.library);
+    let result = run_blocking(move || prune_missing_internal(&library, paths)).await?;
+    if !result.removed.is_empty() {
+        let _ = app.emit(MISSING_FILES_PRUNED_EVENT, &result.removed);
+    }
+    Ok(result)
+}
+
+/// 查找重复曲目
+///
+/// `paths` 可以包含目录，未指定时检查整个曲库。`strategy` 默认为 `exact`（音频哈希，使用哈希缓存），
+/// 也可以按 `tags` 或 `filename` 比较。文件在线程池中并行处理，每处理一个文件发送一次 `task-progress` 事件。
+#[command]
+pub async fn find_duplicate_tracks(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    paths: Option<Vec<String>>,
+    strategy: Option<DuplicateStrategy>,
+    task_id: Option<String>,
+) -> AppResult<DuplicateTrackReport> {
+    let options = metadata_options(&state);
+    let filter = scan_filter(&state);
+    let cache_dir = state.config_manager.get_cache_directory("hash")?;
+    let library = Arc::clone(&state.library);
+    let tasks = Arc::clone(&state.tasks);
+    let task_id = task_id.unwrap_or_else(|| "find-duplicate-tracks".to_string());
+
+    run_blocking(move || {
+        let token = tasks.start(&task_id);
+        let on_progress = |completed: usize, total: usize, path: &str| {
+            let _ = app.emit(TASK_PROGRESS_EVENT, TaskProgressEvent {
+                task_id: task_id.clone(),
+                kind: "duplicates".to_string(),
+                completed,
+                total,
+                current: Some(path.to_string()),
+            });
+        };
+
+        let result = match paths {
+            Some(paths) => Ok(collect_audio_paths_filtered(&paths, &filter)),
+            None => library.all_paths(),
+        }
+        .and_then(|paths| {
+            let mut cache = HashCache::load(&cache_dir);
+            let strategy = strategy.unwrap_or(DuplicateStrategy::Exact);
+            let result = find_duplicate_tracks_internal(&paths, strategy, &mut cache, &options, &token, on_progress);
+            if let Err(e) = cache.save() {
+                eprintln!("Failed to save hash cache: {e}");
+            }
+            result
+        });
+        tasks.finish(&task_id, &token);
+        result
+    })
+    .await
+}
+
+/// 处理重复曲目中多余的副本
+///
+/// `action` 为 `trash` 时移到回收站，为 `exclude` 时加入扫描排除列表（文件保留）。处理成功的文件
+/// 从曲库中移除，并通过 `library-files-removed` 事件通知前端。
+#[command]
+pub async fn resolve_duplicates(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    keep: Vec<String>,
+    remove: Vec<String>,
+    action: DuplicateAction,
+) -> AppResult<ResolveDuplicatesResult> {
+    let library = Arc::clone(&state.library);
+    let config_manager = state.config_manager.clone();
+    let current_path = Arc::clone(&state.player.current_path);
+
+    let result = run_blocking(move || {
+        let is_playing = |path: &str| {
+            current_path
+                .lock()
+                .unwrap()
+                .as_deref()
+                .is_some_and(|current| same_path(current, path))
+        };
+        resolve_duplicates_internal(&library, &config_manager, &keep, remove, action, is_playing)
+    })
+    .await?;
+
+    if !result.removed.is_empty() {
+        let _ = app.emit(LIBRARY_FILES_REMOVED_EVENT, LibraryFilesEvent { paths: result.removed.clone() });
+    }
+    Ok(result)
+}
+
+/// 通知前端文件已被删除
+fn emit_files_deleted(app: &AppHandle, result: &DeleteFilesResult) {
+    if !result.deleted.is_empty() {
+        let _ = app.emit(LIBRARY_FILES_REMOVED_EVENT, LibraryFilesEvent { paths: result.deleted.clone() });
+        let _ = app.emit(
+            FILES_DELETED_EVENT,
+            FilesDeletedEvent { paths: result.deleted.clone(), stopped_playback: result.stopped_playback },
+        );
+    }
+}
+
+/// 正在播放的文件在 `paths` 中且 `stop` 为真时停止播放，返回是否停止了播放
+fn stop_if_playing(state: &State<AppState>, paths: &[String], stop: bool) -> bool {
+    let playing = state
+        .player
+        .current_path
+        .lock()
+        .unwrap()
+        .as_deref()
+        .is_some_and(|current| paths.iter().any(|path| same_path(current, path)));
+    if playing && stop {
+        stop_playback(&state.player);
+    }
+    playing && stop
+}
+
+/// 把文件移到系统回收站
+///
+/// 正在播放的文件默认跳过（原因为 `playing`），`stop_playback` 为真时先停止播放再删除。
+/// 所在位置不支持回收站时返回 `TRASH_UNSUPPORTED` 错误，不处理任何文件，
+/// 确认后可改用 `delete_files_permanently`。删除成功的文件从曲库中移除，并发出 `files-deleted` 事件。
+#[command]
+pub async fn trash_files(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    paths: Vec<String>,
+    stop_playback: Option<bool>,
+) -> AppResult<DeleteFilesResult> {
+    let stopped = stop_if_playing(&state, &paths, stop_playback.unwrap_or(false));
+    let library = Arc::clone(&state.library);
+    let current_path = Arc::clone(&state.player.current_path);
+
+    let mut result = run_blocking(move || {
+        let is_playing = |path: &str| {
+            current_path
+                .lock()
+                .unwrap()
+                .as_deref()
+                .is_some_and(|current| same_path(current, path))
+        };
+        trash_files_internal(&library, paths, is_playing)
+    })
+    .await?;
+    result.stopped_playback = stopped;
+
+    emit_files_deleted(&app, &result);
+    Ok(result)
+}
+
+/// 永久删除文件（不经过回收站，无法恢复）
+///
+/// 只应在用户明确确认后调用，通常用于 `trash_files` 返回 `TRASH_UNSUPPORTED` 之后。
+/// 正在播放的文件处理方式与 `trash_files` 相同。
+#[command]
+pub async fn delete_files_permanently(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    paths: Vec<String>,
+    stop_playback: Option<bool>,
+) -> AppResult<DeleteFilesResult> {
+    let stopped = stop_if_playing(&state, &paths, stop_playback.unwrap_or(false));
+    let library = Arc::clone(&state.library);
+    let current_path = Arc::clone(&state.player.current_path);
+
+    let mut result = run_blocking(move || {
+        let is_playing = |path: &str| {
+            current_path
+                .lock()
+                .unwrap()
+                .as_deref()
+                .is_some_and(|current| same_path(current, path))
+        };
+        delete_files_permanently_internal(&library, paths, is_playing)
+    })
+    .await?;
+    result.stopped_playback = stopped;
+
+    emit_files_deleted(&app, &result);
+    Ok(result)
+}
+
+/// 设置中填写的 Last.fm API 账号
+fn lastfm_credentials(state: &State<'_, AppState>) -> AppResult<Credentials> {
+    let config = state.config_manager.load_config()?.lastfm;
+    if config.api_key.is_empty() || config.api_secret.is_empty() {
+        return Err(AppError::Config(tr!("lastfm.missingCredentials")));
+    }
+    Ok(Credentials { api_key: config.api_key, api_secret: config.api_secret })
+}
+
+/// 开始 Last.fm 授权，返回授权页面的地址和令牌；用户在浏览器中同意后调用 `lastfm_complete_auth`
+#[command]
+pub async fn lastfm_get_auth_url(state: State<'_, AppState>) -> AppResult<AuthRequest> {
+    let credentials = lastfm_credentials(&state)?;
+    let token = lastfm::get_token(&credentials).await.map_err(|e| AppError::Other(e.to_string()))?;
+    Ok(AuthRequest { url: lastfm::auth_url(&credentials.api_key, &token), token })
+}
+
+/// 完成 Last.fm 授权：保存会话密钥和用户名，然后提交队列中积压的播放记录
+#[command]
+pub async fn lastfm_complete_auth(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    token: String,
+) -> AppResult<LastfmConfig> {
+    let credentials = lastfm_credentials(&state)?;
+    let session =
+        lastfm::get_session(&credentials, &token).await.map_err(|e| AppError::Other(e.to_string()))?;
+    let mut config = state.config_manager.load_config()?;
+    config.lastfm.session_key = session.key;
+    config.lastfm.username = session.name;
+    state.config_manager.save_config(&config)?;
+    scrobble::retry(&app);
+    Ok(config.lastfm)
+}
+
+/// 检查 ListenBrainz 用户令牌，有效时连同用户名保存，然后提交队列中积压的播放记录
+#[command]
+pub async fn listenbrainz_validate_token(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    token: String,
+) -> AppResult<ListenBrainzConfig> {
+    let token = token.trim().to_string();
+    let validation = listenbrainz::validate_token(&token).await.map_err(|e| AppError::Other(e.to_string()))?;
+    if !validation.valid {
+        return Err(AppError::Config(tr!("listenbrainz.invalidToken")));
+    }
+    let mut config = state.config_manager.load_config()?;
+    config.listenbrainz.token = token;
+    config.listenbrainz.username = validation.user_name.unwrap_or_default();
+    state.config_manager.save_config(&config)?;
+    scrobble::retry(&app);
+    Ok(config.listenbrainz)
+}
+
+/// 各服务播放记录的提交状态：队列中等待提交的数量和最近一次错误
+#[command]
+pub fn get_scrobble_status(app: AppHandle) -> ScrobbleStatus {
+    scrobble::status(&app)
 }
@@ -0,0 +1,151 @@
+//! 目录封面模块
+//!
+//! 音轨没有嵌入封面时，在所在目录中查找 cover.jpg / folder.jpg 等图片。
+//! 每个目录只列举一次，图片的 data URL 也只编码一次。
+//! 从网络下载的封面保存在磁盘缓存目录中，按来源和尺寸命名，下次直接读取。
+
+use crate::paths::long_path;
+use crate::tr;
+use base64::{engine::general_purpose, Engine as _};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+
+/// 最多缓存的目录封面 data URL 数量
+const MAX_CACHED_DATA_URLS: usize = 64;
+
+/// 封面来源
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CoverSource {
+    /// 文件内嵌的图片
+    Embedded,
+    /// 所在目录中的图片文件
+    Folder,
+    #[default]
+    None,
+}
+
+/// 目录 -> 封面图片路径（没有时为 `None`）
+static FOLDER_COVERS: LazyLock<Mutex<HashMap<PathBuf, Option<PathBuf>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+/// 封面图片路径 -> data URL
+static COVER_DATA_URLS: LazyLock<Mutex<HashMap<PathBuf, String>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// 在目录中按候选文件名顺序查找封面（不区分大小写）
+fn find_cover_in_dir(dir: &Path, candidates: &[String]) -> Option<PathBuf> {
+    let entries: Vec<(String, PathBuf)> = fs::read_dir(dir)
+        .ok()?
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_file()))
+        .map(|entry| (entry.file_name().to_string_lossy().to_lowercase(), entry.path()))
+        .collect();
+
+    candidates.iter().find_map(|candidate| {
+        let candidate = candidate.to_lowercase();
+        entries
+            .iter()
+            .find(|(name, _)| *name == candidate)
+            .map(|(_, path)| path.clone())
+    })
+}
+
+/// 查找音轨所在目录的封面图片，结果按目录缓存
+#[must_use]
+pub fn folder_cover_path(track_path: &Path, candidates: &[String]) -> Option<PathBuf> {
+    if candidates.is_empty() {
+        return None;
+    }
+    let dir = track_path.parent()?;
+
+    if let Some(cached) = FOLDER_COVERS.lock().unwrap().get(dir) {
+        return cached.clone();
+    }
+
+    let found = find_cover_in_dir(dir, candidates);
+    FOLDER_COVERS.lock().unwrap().insert(dir.to_path_buf(), found.clone());
+    found
+}
+
+/// 根据文件头识别图片格式，返回对应扩展名
+#[must_use]
+pub fn sniff_image_extension(data: &[u8]) -> Option<&'static str> {
+    if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("jpg")
+    } else if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("png")
+    } else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+        Some("gif")
+    } else if data.len() >= 12 && &data[0..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+        Some("webp")
+    } else if data.starts_with(b"BM") {
+        Some("bmp")
+    } else {
+        None
+    }
+}
+
+/// 根据扩展名推断图片 MIME 类型
+fn image_mime_type(path: &Path) -> &'static str {
+    match path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase)
+        .as_deref()
+    {
+        Some("png") => "image/png",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("bmp") => "image/bmp",
+        _ => "image/jpeg",
+    }
+}
+
+/// 读取目录封面并编码为 data URL，同一图片只读取一次
+pub fn folder_cover_data_url(image_path: &Path) -> Result<String, String> {
+    if let Some(cached) = COVER_DATA_URLS.lock().unwrap().get(image_path) {
+        return Ok(cached.clone());
+    }
+
+    let data = fs::read(long_path(image_path)).map_err(|e| tr!("cover.readFailed", detail = e))?;
+    let data_url = format!(
+        "data:{};base64,{}",
+        image_mime_type(image_path),
+        general_purpose::STANDARD.encode(data)
+    );
+
+    let mut cache = COVER_DATA_URLS.lock().unwrap();
+    if cache.len() >= MAX_CACHED_DATA_URLS {
+        cache.clear();
+    }
+    cache.insert(image_path.to_path_buf(), data_url.clone());
+    Ok(data_url)
+}
+
+/// 清空目录封面缓存，重新扫描目录前调用以发现新增或删除的图片
+pub fn clear_folder_cover_cache() {
+    FOLDER_COVERS.lock().unwrap().clear();
+    COVER_DATA_URLS.lock().unwrap().clear();
+}
+
+/// 在磁盘封面缓存中查找键对应的图片（`<key>.<ext>`）
+#[must_use]
+pub fn cached_cover_file(cache_dir: &Path, key: &str) -> Option<PathBuf> {
+    ["jpg", "png", "webp", "gif", "bmp"]
+        .iter()
+        .map(|ext| cache_dir.join(format!("{key}.{ext}")))
+        .find(|path| path.is_file())
+}
+
+/// 把图片写入磁盘封面缓存，扩展名按文件头识别；先写临时文件再重命名，避免留下不完整的图片
+pub fn store_cover_file(cache_dir: &Path, key: &str, data: &[u8]) -> Result<PathBuf, String> {
+    let ext = sniff_image_extension(data).ok_or_else(|| tr!("cover.unknownFormat"))?;
+    let path = cache_dir.join(format!("{key}.{ext}"));
+    let temp_path = cache_dir.join(format!("{key}.{ext}.tmp"));
+    fs::write(&temp_path, data)
+        .and_then(|()| fs::rename(&temp_path, &path))
+        .map_err(|e| tr!("cover.cacheWriteFailed", detail = e))?;
+    Ok(path)
+}
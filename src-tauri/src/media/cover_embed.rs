@@ -0,0 +1,156 @@
+//! 下载在线封面并嵌入音频文件
+//!
+//! 封面来源可以是图片地址或网易云音乐歌曲 ID，`auto` 由调用方换成自动匹配到的歌曲。图片经共用的 HTTP 客户端下载，
+//! 原图保存在封面磁盘缓存中；边长超过设置的上限时先缩小，WebP、GIF、BMP 转为 JPEG（多数播放器只认 JPEG 和 PNG），
+//! 然后作为正面封面写入标签。
+
+use super::cover::sniff_image_extension;
+use super::metadata::TrackMetadata;
+use super::netease::{get_cover, get_cover_from_url, CoverImage, NeteaseError};
+use super::tag_writer::{AlbumWriteResult, CoverChange};
+use crate::paths::long_path;
+use crate::tr;
+use base64::{engine::general_purpose, Engine as _};
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, GenericImageView, ImageFormat};
+use lofty::picture::{Picture, PictureType};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+
+/// 重新编码为 JPEG 时的质量
+const JPEG_QUALITY: u8 = 90;
+
+/// 封面来源
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CoverSource {
+    /// 图片地址
+    Url(String),
+    /// 网易云音乐歌曲 ID
+    NeteaseSong(String),
+    /// 使用自动匹配到的歌曲
+    Auto,
+}
+
+impl CoverSource {
+    /// 解析前端传入的来源：`auto`、`http(s)://` 开头的地址或纯数字的歌曲 ID
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let source = source.trim();
+        if source.eq_ignore_ascii_case("auto") {
+            Ok(Self::Auto)
+        } else if source.starts_with("https://") || source.starts_with("http://") {
+            Ok(Self::Url(source.to_string()))
+        } else if !source.is_empty() && source.bytes().all(|b| b.is_ascii_digit()) {
+            Ok(Self::NeteaseSong(source.to_string()))
+        } else {
+            Err(tr!("cover.unknownSource", source = source))
+        }
+    }
+}
+
+/// 准备嵌入的封面
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct EmbedCover {
+    /// 封面缓存中的原图路径
+    pub cache_path: String,
+    /// 要嵌入的图片（data URL），用于确认前预览
+    pub data_url: String,
+    pub width: u32,
+    pub height: u32,
+    /// 是否按最大边长缩小过
+    pub resized: bool,
+    /// 要嵌入的图片数据
+    #[serde(skip)]
+    pub data: Vec<u8>,
+}
+
+impl EmbedCover {
+    /// 作为正面封面写入标签的修改
+    pub fn cover_change(&self) -> Result<CoverChange, String> {
+        let mut picture =
+            Picture::from_reader(&mut self.data.as_slice()).map_err(|e| tr!("tags.imageFormatUnknown", detail = e))?;
+        picture.set_pic_type(PictureType::CoverFront);
+        Ok(CoverChange::Set(picture))
+    }
+}
+
+/// 单个文件嵌入封面的结果
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmbedCoverResult {
+    pub cover: EmbedCover,
+    /// 写入后的元数据，`dry_run` 时为 `None`
+    pub metadata: Option<TrackMetadata>,
+}
+
+/// 为多个文件嵌入同一封面的结果
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlbumCoverEmbedResult {
+    pub cover: EmbedCover,
+    /// 每个文件的写入结果，`dry_run` 时为空
+    pub results: Vec<AlbumWriteResult>,
+}
+
+/// 图片地址在封面缓存中的文件名
+fn url_cache_key(url: &str) -> String {
+    let hash = format!("{:x}", Sha256::digest(url.as_bytes()));
+    format!("url-{}", &hash[..32])
+}
+
+/// 下载封面原图（已缓存时直接使用缓存）
+pub async fn fetch_cover(source: &CoverSource, cache_dir: &Path) -> Result<CoverImage, NeteaseError> {
+    match source {
+        CoverSource::Url(url) => get_cover_from_url(url, &url_cache_key(url), cache_dir).await,
+        CoverSource::NeteaseSong(song_id) => get_cover(Some(song_id), None, 0, cache_dir).await,
+        CoverSource::Auto => Err(NeteaseError::NotFound("auto cover source".to_string())),
+    }
+}
+
+/// 按图片格式编码
+fn encode_image(image: &DynamicImage, as_png: bool) -> Result<Vec<u8>, String> {
+    let mut output = Vec::new();
+    let result = if as_png {
+        image.write_to(&mut Cursor::new(&mut output), ImageFormat::Png)
+    } else {
+        // JPEG 不支持透明通道
+        DynamicImage::ImageRgb8(image.to_rgb8())
+            .write_with_encoder(JpegEncoder::new_with_quality(&mut output, JPEG_QUALITY))
+    };
+    result.map_err(|e| tr!("cover.encodeFailed", detail = e))?;
+    Ok(output)
+}
+
+/// 读取缓存中的原图，按最大边长缩小并转为可嵌入的格式；`max_size` 为 0 时不缩小
+pub fn prepare_embed_cover(image_path: &Path, max_size: u32) -> Result<EmbedCover, String> {
+    let original = fs::read(long_path(image_path)).map_err(|e| tr!("cover.readFailed", detail = e))?;
+    let extension = sniff_image_extension(&original).ok_or_else(|| tr!("cover.unknownFormat"))?;
+    let decoded = image::load_from_memory(&original).map_err(|e| tr!("cover.decodeFailed", detail = e))?;
+    let (width, height) = decoded.dimensions();
+
+    let resize = max_size > 0 && width.max(height) > max_size;
+    let (data, extension, (width, height)) = if resize {
+        let resized = decoded.resize(max_size, max_size, FilterType::Lanczos3);
+        let as_png = extension == "png";
+        (encode_image(&resized, as_png)?, if as_png { "png" } else { "jpg" }, resized.dimensions())
+    } else if matches!(extension, "jpg" | "png") {
+        (original, extension, (width, height))
+    } else {
+        (encode_image(&decoded, false)?, "jpg", (width, height))
+    };
+
+    let mime = if extension == "png" { "image/png" } else { "image/jpeg" };
+    let data_url = format!("data:{mime};base64,{}", general_purpose::STANDARD.encode(&data));
+    Ok(EmbedCover {
+        cache_path: image_path.to_string_lossy().to_string(),
+        data_url,
+        width,
+        height,
+        resized: resize,
+        data,
+    })
+}
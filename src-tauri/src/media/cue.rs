@@ -0,0 +1,351 @@
+//! CUE 表单解析模块
+//!
+//! 解析 CUE 文件中的全局信息、FILE 条目和各音轨的 INDEX 偏移。
+
+use super::encoding::decode_text;
+use crate::tr;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// CUE 时间单位：每秒 75 帧
+const FRAMES_PER_SECOND: f64 = 75.0;
+
+/// 解析后的 CUE 表单
+#[derive(Debug, Serialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CueSheet {
+    pub path: String,
+    pub encoding: String,
+    pub performer: Option<String>,
+    pub title: Option<String>,
+    pub songwriter: Option<String>,
+    pub catalog: Option<String>,
+    pub files: Vec<CueFile>,
+    /// 无法识别或格式错误的行
+    pub warnings: Vec<String>,
+}
+
+/// CUE 中的 FILE 条目
+#[derive(Debug, Serialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CueFile {
+    /// 相对 CUE 所在目录解析后的路径
+    pub path: String,
+    /// CUE 中原始书写的文件名
+    pub original_name: String,
+    pub file_type: String,
+    pub exists: bool,
+    pub tracks: Vec<CueTrack>,
+}
+
+/// CUE 中的单个音轨
+#[derive(Debug, Serialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CueTrack {
+    pub number: u32,
+    pub track_type: String,
+    pub title: Option<String>,
+    pub performer: Option<String>,
+    pub songwriter: Option<String>,
+    pub isrc: Option<String>,
+    /// INDEX 00 偏移（秒）
+    pub index00: Option<f64>,
+    /// INDEX 01 偏移（秒）
+    pub index01: Option<f64>,
+    /// PREGAP 时长（秒）
+    pub pregap: Option<f64>,
+    /// POSTGAP 时长（秒）
+    pub postgap: Option<f64>,
+}
+
+/// 将 mm:ss:ff 格式转换为秒数
+#[must_use]
+pub fn parse_cue_time(value: &str) -> Option<f64> {
+    let mut parts = value.trim().split(':');
+    let minutes: u32 = parts.next()?.parse().ok()?;
+    let seconds: u32 = parts.next()?.parse().ok()?;
+    let frames: u32 = parts.next()?.parse().ok()?;
+    if parts.next().is_some() || seconds >= 60 || frames >= 75 {
+        return None;
+    }
+    Some(f64::from(minutes) * 60.0 + f64::from(seconds) + f64::from(frames) / FRAMES_PER_SECOND)
+}
+
+/// 拆分一行 CUE 命令为关键字和参数（支持双引号包裹）
+fn tokenize(line: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+
+    for c in line.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+
+    tokens
+}
+
+/// 相对 CUE 所在目录解析 FILE 路径
+fn resolve_file_path(cue_dir: &Path, name: &str) -> PathBuf {
+    let normalized: PathBuf = name.split(['/', '\\']).filter(|s| !s.is_empty()).collect();
+    let is_absolute = Path::new(name).is_absolute() || name.starts_with('/') || name.starts_with('\\');
+    if is_absolute {
+        PathBuf::from(name)
+    } else {
+        cue_dir.join(normalized)
+    }
+}
+
+/// 解析 CUE 文本内容
+#[must_use]
+pub fn parse_cue_content(content: &str, cue_dir: &Path) -> CueSheet {
+    let mut sheet = CueSheet::default();
+
+    for (line_no, raw_line) in content.lines().enumerate() {
+        let line = raw_line.trim().trim_start_matches('\u{feff}');
+        if line.is_empty() {
+            continue;
+        }
+
+        let tokens = tokenize(line);
+        let Some(keyword) = tokens.first().map(|k| k.to_uppercase()) else {
+            continue;
+        };
+        let arg = tokens.get(1).cloned();
+        let warn = |sheet: &mut CueSheet, reason: String| {
+            sheet.warnings.push(tr!("cue.warning", line = line_no + 1, reason = reason, text = line));
+        };
+
+        let current_track = sheet.files.last_mut().and_then(|f| f.tracks.last_mut());
+
+        match keyword.as_str() {
+            "REM" => {}
+            "FILE" => {
+                let Some(name) = arg else {
+                    warn(&mut sheet, tr!("cue.fileWithoutName"));
+                    continue;
+                };
+                let resolved = resolve_file_path(cue_dir, &name);
+                sheet.files.push(CueFile {
+                    exists: resolved.exists(),
+                    path: resolved.to_string_lossy().to_string(),
+                    original_name: name,
+                    file_type: tokens.get(2).cloned().unwrap_or_default(),
+                    tracks: Vec::new(),
+                });
+            }
+            "TRACK" => {
+                let Some(file) = sheet.files.last_mut() else {
+                    warn(&mut sheet, tr!("cue.trackBeforeFile"));
+                    continue;
+                };
+                let Some(number) = arg.as_deref().and_then(|n| n.parse().ok()) else {
+                    warn(&mut sheet, tr!("cue.invalidTrackNumber"));
+                    continue;
+                };
+                file.tracks.push(CueTrack {
+                    number,
+                    track_type: tokens.get(2).cloned().unwrap_or_default(),
+                    ..Default::default()
+                });
+            }
+            "INDEX" => {
+                let Some(track) = current_track else {
+                    warn(&mut sheet, tr!("cue.indexOutsideTrack"));
+                    continue;
+                };
+                let number = arg.as_deref().and_then(|n| n.parse::<u32>().ok());
+                let time = tokens.get(2).and_then(|t| parse_cue_time(t));
+                match (number, time) {
+                    (Some(0), Some(t)) => track.index00 = Some(t),
+                    (Some(1), Some(t)) => track.index01 = Some(t),
+                    (Some(_), Some(_)) => {}
+                    _ => warn(&mut sheet, tr!("cue.malformedIndex")),
+                }
+            }
+            "PREGAP" | "POSTGAP" => {
+                let Some(track) = current_track else {
+                    warn(&mut sheet, tr!("cue.gapOutsideTrack"));
+                    continue;
+                };
+                match arg.as_deref().and_then(parse_cue_time) {
+                    Some(t) if keyword == "PREGAP" => track.pregap = Some(t),
+                    Some(t) => track.postgap = Some(t),
+                    None => warn(&mut sheet, tr!("cue.malformedGap")),
+                }
+            }
+            "TITLE" | "PERFORMER" | "SONGWRITER" => {
+                if let Some(track) = current_track {
+                    match keyword.as_str() {
+                        "TITLE" => track.title = arg,
+                        "PERFORMER" => track.performer = arg,
+                        _ => track.songwriter = arg,
+                    }
+                } else {
+                    match keyword.as_str() {
+                        "TITLE" => sheet.title = arg,
+                        "PERFORMER" => sheet.performer = arg,
+                        _ => sheet.songwriter = arg,
+                    }
+                }
+            }
+            "ISRC" => {
+                if let Some(track) = current_track {
+                    track.isrc = arg;
+                } else {
+                    warn(&mut sheet, tr!("cue.isrcOutsideTrack"));
+                }
+            }
+            "CATALOG" => sheet.catalog = arg,
+            "FLAGS" | "CDTEXTFILE" => {}
+            _ => warn(&mut sheet, tr!("cue.unknownCommand")),
+        }
+    }
+
+    sheet
+}
+
+/// 读取并解析 CUE 文件
+pub fn parse_cue_sheet_internal(path: &str) -> Result<CueSheet, String> {
+    let cue_path = Path::new(path);
+    let bytes = fs::read(cue_path).map_err(|e| tr!("cue.readFailed", detail = e))?;
+    let decoded = decode_text(&bytes);
+
+    let cue_dir = cue_path.parent().unwrap_or_else(|| Path::new("."));
+    let mut sheet = parse_cue_content(&decoded.text, cue_dir);
+    sheet.path = path.to_string();
+    sheet.encoding = decoded.encoding.to_string();
+
+    Ok(sheet)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::media::encoding::encode_with;
+    use tempfile::TempDir;
+
+    const MULTI_FILE_CUE: &str = r#"REM GENRE Pop
+REM DATE 2003
+PERFORMER "周杰伦"
+TITLE "叶惠美"
+CATALOG 0000000000000
+FILE "CD1\01 以父之名.flac" WAVE
+  TRACK 01 AUDIO
+    TITLE "以父之名"
+    ISRC TWA530300101
+    INDEX 01 00:00:00
+  TRACK 02 AUDIO
+    TITLE "懦夫"
+    PERFORMER "周杰伦 & 方文山"
+    INDEX 00 05:41:70
+    INDEX 01 05:43:12
+FILE "CD1/03 晴天.flac" WAVE
+  TRACK 03 AUDIO
+    TITLE "晴天"
+    PREGAP 00:02:00
+    INDEX 01 00:00:00
+    POSTGAP 00:01:37
+"#;
+
+    #[test]
+    fn parses_mm_ss_ff_with_75_frames_per_second() {
+        assert_eq!(parse_cue_time("00:00:00"), Some(0.0));
+        assert_eq!(parse_cue_time("01:02:00"), Some(62.0));
+        assert_eq!(parse_cue_time("00:00:75"), None);
+        assert_eq!(parse_cue_time("00:60:00"), None);
+        // 分钟可以超过 99（超长的整轨文件）
+        assert_eq!(parse_cue_time("120:00:00"), Some(7200.0));
+
+        let time = parse_cue_time("05:43:12").unwrap();
+        assert!((time - (343.0 + 12.0 / 75.0)).abs() < 1e-9);
+        let time = parse_cue_time(" 00:00:74 ").unwrap();
+        assert!((time - 74.0 / 75.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn rejects_malformed_times() {
+        for value in ["", "1:2", "1:2:3:4", "aa:bb:cc", "-1:00:00", "00:00:1.5"] {
+            assert_eq!(parse_cue_time(value), None, "{value}");
+        }
+    }
+
+    #[test]
+    fn parses_multi_file_sheets() {
+        let sheet = parse_cue_content(MULTI_FILE_CUE, Path::new("/music/album"));
+        assert!(sheet.warnings.is_empty(), "{:?}", sheet.warnings);
+        assert_eq!(sheet.performer.as_deref(), Some("周杰伦"));
+        assert_eq!(sheet.title.as_deref(), Some("叶惠美"));
+        assert_eq!(sheet.catalog.as_deref(), Some("0000000000000"));
+        assert_eq!(sheet.files.len(), 2);
+
+        let first = &sheet.files[0];
+        assert_eq!(first.original_name, r"CD1\01 以父之名.flac");
+        assert_eq!(Path::new(&first.path), Path::new("/music/album").join("CD1").join("01 以父之名.flac"));
+        assert_eq!(first.file_type, "WAVE");
+        assert_eq!(first.tracks.iter().map(|t| t.number).collect::<Vec<_>>(), [1, 2]);
+        assert_eq!(first.tracks[0].isrc.as_deref(), Some("TWA530300101"));
+        assert_eq!(first.tracks[1].performer.as_deref(), Some("周杰伦 & 方文山"));
+        assert!((first.tracks[1].index00.unwrap() - (341.0 + 70.0 / 75.0)).abs() < 1e-9);
+        assert!((first.tracks[1].index01.unwrap() - (343.0 + 12.0 / 75.0)).abs() < 1e-9);
+
+        // 第二个 FILE 的索引从该文件的开头重新计时
+        let second = &sheet.files[1];
+        assert_eq!(Path::new(&second.path), Path::new("/music/album").join("CD1").join("03 晴天.flac"));
+        assert_eq!(second.tracks.len(), 1);
+        let track = &second.tracks[0];
+        assert_eq!((track.number, track.title.as_deref()), (3, Some("晴天")));
+        assert_eq!(track.index01, Some(0.0));
+        assert_eq!(track.pregap, Some(2.0));
+        assert!((track.postgap.unwrap() - (1.0 + 37.0 / 75.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn warns_about_commands_in_the_wrong_place() {
+        let content = "TRACK 01 AUDIO\nFILE \"a.wav\" WAVE\nINDEX 01 00:00:00\nTRACK xx AUDIO\nTRACK 01 AUDIO\n\
+                       INDEX 01 00:00:99\nBOGUS 1\n";
+        let sheet = parse_cue_content(content, Path::new("."));
+        let lines: Vec<_> = sheet.warnings.iter().map(|w| w.split(':').next().unwrap().to_string()).collect();
+        assert_eq!(lines, ["Line 1", "Line 3", "Line 4", "Line 6", "Line 7"]);
+        assert_eq!(sheet.warnings[4], "Line 7: unknown command: BOGUS 1");
+        assert_eq!(sheet.files[0].tracks.len(), 1);
+    }
+
+    #[test]
+    fn reads_gbk_sheets_and_marks_missing_files() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("CD1")).unwrap();
+        fs::write(dir.path().join("CD1").join("03 晴天.flac"), b"").unwrap();
+        let cue = dir.path().join("album.cue");
+        fs::write(&cue, encode_with(MULTI_FILE_CUE, "GBK", false).unwrap()).unwrap();
+
+        let sheet = parse_cue_sheet_internal(&cue.to_string_lossy()).unwrap();
+        assert_eq!(sheet.encoding, "GBK");
+        assert_eq!(sheet.files[0].tracks[0].title.as_deref(), Some("以父之名"));
+        assert!(!sheet.files[0].exists);
+        assert!(sheet.files[1].exists);
+    }
+
+    #[test]
+    fn reads_utf8_sheets_with_bom() {
+        let dir = TempDir::new().unwrap();
+        let cue = dir.path().join("album.cue");
+        fs::write(&cue, encode_with(MULTI_FILE_CUE, "UTF-8", true).unwrap()).unwrap();
+
+        let sheet = parse_cue_sheet_internal(&cue.to_string_lossy()).unwrap();
+        assert_eq!(sheet.encoding, "UTF-8");
+        assert_eq!(sheet.performer.as_deref(), Some("周杰伦"));
+        assert!(sheet.warnings.is_empty());
+    }
+}
@@ -0,0 +1,305 @@
+//! 自定义标签字段模块
+//!
+//! 读取和写入 ID3 TXXX 帧、未映射的 Vorbis 注释和 MP4 自由格式原子。
+//! 字段名统一为大写的 Vorbis 风格（如 MUSICBRAINZ_ALBUMID），Picard 的约定名称会被换算。
+
+use super::tag_writer::{is_mp3, read_id3_or_default, read_tagged_file, save_id3_atomic};
+use crate::tr;
+use id3::frame::ExtendedText;
+use id3::TagLike;
+use lofty::file::TaggedFileExt;
+use lofty::tag::{ItemKey, ItemValue, Tag, TagItem, TagType};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+use std::path::Path;
+
+/// MP4 自由格式原子中 iTunes 使用的前缀
+const FREEFORM_PREFIX: &str = "----:com.apple.iTunes:";
+
+/// 统一字段名与 ID3 TXXX / MP4 自由格式中 Picard 使用的名称
+const KEY_ALIASES: &[(&str, &str)] = &[
+    ("MUSICBRAINZ_ALBUMID", "MusicBrainz Album Id"),
+    ("MUSICBRAINZ_ARTISTID", "MusicBrainz Artist Id"),
+    ("MUSICBRAINZ_ALBUMARTISTID", "MusicBrainz Album Artist Id"),
+    ("MUSICBRAINZ_RELEASEGROUPID", "MusicBrainz Release Group Id"),
+    ("MUSICBRAINZ_RELEASETRACKID", "MusicBrainz Release Track Id"),
+    ("MUSICBRAINZ_TRACKID", "MusicBrainz Track Id"),
+    ("MUSICBRAINZ_WORKID", "MusicBrainz Work Id"),
+    ("RELEASETYPE", "MusicBrainz Album Type"),
+    ("RELEASESTATUS", "MusicBrainz Album Status"),
+    ("RELEASECOUNTRY", "MusicBrainz Album Release Country"),
+    ("ACOUSTID_ID", "Acoustid Id"),
+    ("ACOUSTID_FINGERPRINT", "Acoustid Fingerprint"),
+    ("ORIGINALYEAR", "originalyear"),
+];
+
+/// 自定义字段（同名字段允许出现多次）
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomTag {
+    pub key: String,
+    pub value: String,
+}
+
+/// 统一字段名：去掉自由格式前缀、换算 Picard 名称、转大写并把空格换成下划线
+#[must_use]
+pub fn canonical_key(raw: &str) -> String {
+    let name = match raw.strip_prefix("----:") {
+        Some(freeform) => freeform.split_once(':').map_or(freeform, |(_, name)| name),
+        None => raw,
+    };
+    let name = name.trim();
+
+    KEY_ALIASES
+        .iter()
+        .find(|(_, alias)| alias.eq_ignore_ascii_case(name))
+        .map_or_else(|| name.to_uppercase().replace(' ', "_"), |(key, _)| (*key).to_string())
+}
+
+/// 统一字段名在 TXXX / 自由格式中的写法
+fn alias_for(key: &str) -> &str {
+    KEY_ALIASES
+        .iter()
+        .find(|(canonical, _)| *canonical == key)
+        .map_or(key, |(_, alias)| alias)
+}
+
+/// 已经映射到 TrackMetadata 的字段，不作为自定义字段返回
+fn is_mapped(key: &ItemKey) -> bool {
+    matches!(
+        key,
+        ItemKey::TrackTitle
+            | ItemKey::TrackArtist
+            | ItemKey::AlbumTitle
+            | ItemKey::AlbumArtist
+            | ItemKey::TrackArtistSortOrder
+            | ItemKey::AlbumArtistSortOrder
+            | ItemKey::AlbumTitleSortOrder
+            | ItemKey::Genre
+            | ItemKey::Year
+            | ItemKey::RecordingDate
+            | ItemKey::TrackNumber
+            | ItemKey::TrackTotal
+            | ItemKey::DiscNumber
+            | ItemKey::DiscTotal
+            | ItemKey::EncoderSoftware
+            | ItemKey::EncoderSettings
+            | ItemKey::Isrc
+            | ItemKey::Label
+            | ItemKey::CatalogNumber
+            | ItemKey::Lyrics
+    )
+}
+
+/// 从 lofty 标签中提取自定义字段
+fn custom_tags_from_lofty(tag: &Tag) -> Vec<CustomTag> {
+    let tag_type = tag.tag_type();
+    tag.items()
+        .filter(|item| !is_mapped(item.key()))
+        .filter_map(|item| {
+            let raw = match item.key() {
+                ItemKey::Unknown(raw) => raw.as_str(),
+                key => key.map_key(tag_type, false)?,
+            };
+            let include = match tag_type {
+                TagType::Mp4Ilst => raw.starts_with("----:"),
+                TagType::Id3v1 => false,
+                TagType::Id3v2 => matches!(item.key(), ItemKey::Unknown(_)),
+                _ => true,
+            };
+            let value = item.value().text()?;
+            include.then(|| CustomTag { key: canonical_key(raw), value: value.to_string() })
+        })
+        .collect()
+}
+
+/// 读取文件中的自定义字段
+///
+/// MP3 读取全部 TXXX 帧，其他格式读取主标签中未映射的字段。
+pub fn read_custom_tags(path: &Path) -> Result<Vec<CustomTag>, String> {
+    if is_mp3(path) {
+        let tag = read_id3_or_default(path)?;
+        return Ok(tag
+            .extended_texts()
+            .map(|txxx| CustomTag { key: canonical_key(&txxx.description), value: txxx.value.clone() })
+            // 目录编号已作为独立字段返回
+            .filter(|custom| custom.key != "CATALOGNUMBER")
+            .collect());
+    }
+
+    let tagged_file = read_tagged_file(path)?;
+    Ok(tagged_file.primary_tag().map(custom_tags_from_lofty).unwrap_or_default())
+}
+
+/// 将自定义字段修改应用到 lofty 标签上（值为空字符串时删除）
+///
+/// 真正的自定义字段在 lofty 中是 `ItemKey::Unknown`，`Tag::insert` 会拒绝所有未知键，
+/// 所以这类字段用 `insert_unchecked` 写入，保存时按原样写成 Vorbis 注释、APE 项或 MP4 自由格式原子。
+pub fn apply_custom_tags<S: BuildHasher>(tag: &mut Tag, changes: &HashMap<String, String, S>) -> Result<(), String> {
+    let tag_type = tag.tag_type();
+    for (key, value) in changes {
+        let key = canonical_key(key);
+        if tag_type == TagType::Id3v1 {
+            return Err(tr!("tags.customFieldUnsupported", key = key));
+        }
+        let item_key = match tag_type {
+            TagType::Mp4Ilst => ItemKey::from_key(tag_type, &format!("{FREEFORM_PREFIX}{}", alias_for(&key))),
+            _ => ItemKey::from_key(tag_type, &key),
+        };
+        if is_mapped(&item_key) {
+            return Err(tr!("tags.notCustomField", key = key));
+        }
+
+        tag.remove_key(&item_key);
+        let value = value.trim();
+        if value.is_empty() {
+            continue;
+        }
+        let item = TagItem::new(item_key, ItemValue::Text(value.to_string()));
+        if matches!(item.key(), ItemKey::Unknown(_)) {
+            tag.insert_unchecked(item);
+        } else if !tag.insert(item) {
+            return Err(tr!("tags.customFieldUnsupported", key = key));
+        }
+    }
+    Ok(())
+}
+
+/// 写入 MP3 的 TXXX 自定义字段（值为空字符串时删除）
+pub fn write_custom_tags_id3<S: BuildHasher>(path: &Path, changes: &HashMap<String, String, S>) -> Result<(), String> {
+    let mut tag = read_id3_or_default(path)?;
+
+    for (key, value) in changes {
+        let key = canonical_key(key);
+        let existing: Vec<String> = tag
+            .extended_texts()
+            .filter(|txxx| canonical_key(&txxx.description) == key)
+            .map(|txxx| txxx.description.clone())
+            .collect();
+        for description in existing {
+            tag.remove_extended_text(Some(&description), None);
+        }
+
+        let value = value.trim();
+        if !value.is_empty() {
+            tag.add_frame(ExtendedText { description: alias_for(&key).to_string(), value: value.to_string() });
+        }
+    }
+
+    save_id3_atomic(&tag, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::media::tag_writer::{primary_tag_or_insert, save_tagged_file_atomic};
+    use std::fs;
+    use std::path::PathBuf;
+    use tempfile::TempDir;
+
+    /// 只有 STREAMINFO 和 PADDING 块的 FLAC（10 秒、44.1 kHz、双声道、16 位）
+    fn flac_fixture() -> Vec<u8> {
+        let mut bytes = b"fLaC".to_vec();
+        bytes.extend([0, 0, 0, 34]);
+        bytes.extend(4096u16.to_be_bytes());
+        bytes.extend(4096u16.to_be_bytes());
+        bytes.extend([0; 6]);
+        let packed: u64 = (44_100 << 44) | (1 << 41) | (15 << 36) | 0x6_BAA8;
+        bytes.extend(packed.to_be_bytes());
+        bytes.extend([0; 16]);
+        bytes.extend([0x81, 0, 0x04, 0]);
+        bytes.extend([0; 1024]);
+        bytes
+    }
+
+    fn atom(name: [u8; 4], content: &[u8]) -> Vec<u8> {
+        let mut bytes = ((content.len() + 8) as u32).to_be_bytes().to_vec();
+        bytes.extend(name);
+        bytes.extend(content);
+        bytes
+    }
+
+    /// 只有一条音频轨道（10 秒、44.1 kHz）而没有标签的 M4A
+    fn m4a_fixture() -> Vec<u8> {
+        let mut mdhd = vec![0; 12];
+        mdhd.extend(44_100u32.to_be_bytes());
+        mdhd.extend(441_000u32.to_be_bytes());
+        mdhd.extend([0; 4]);
+        let mut hdlr = vec![0; 8];
+        hdlr.extend(b"soun");
+        hdlr.extend([0; 13]);
+        let mdia = atom(*b"mdia", &[atom(*b"mdhd", &mdhd), atom(*b"hdlr", &hdlr)].concat());
+
+        let mut ftyp = b"M4A ".to_vec();
+        ftyp.extend([0; 4]);
+        ftyp.extend(b"M4A isom");
+        [atom(*b"ftyp", &ftyp), atom(*b"moov", &atom(*b"trak", &mdia)), atom(*b"mdat", &[0; 64])].concat()
+    }
+
+    fn write_fixture(dir: &TempDir, name: &str, bytes: &[u8]) -> PathBuf {
+        let path = dir.path().join(name);
+        fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    fn write(path: &Path, changes: &[(&str, &str)]) -> Result<(), String> {
+        let changes: HashMap<String, String> =
+            changes.iter().map(|(key, value)| ((*key).to_string(), (*value).to_string())).collect();
+        let mut tagged_file = read_tagged_file(path)?;
+        apply_custom_tags(primary_tag_or_insert(&mut tagged_file), &changes)?;
+        save_tagged_file_atomic(&tagged_file, path)
+    }
+
+    fn read(path: &Path) -> Vec<(String, String)> {
+        let mut tags: Vec<_> = read_custom_tags(path).unwrap().into_iter().map(|tag| (tag.key, tag.value)).collect();
+        tags.sort();
+        tags
+    }
+
+    fn assert_round_trip(path: &Path) {
+        write(path, &[("my_field", "晴天"), ("MusicBrainz Album Id", "7b0f5c32-1c4c-4b0e-9a9b-31c0b3b1c6b4")]).unwrap();
+        assert_eq!(
+            read(path),
+            [
+                ("MUSICBRAINZ_ALBUMID".to_string(), "7b0f5c32-1c4c-4b0e-9a9b-31c0b3b1c6b4".to_string()),
+                ("MY_FIELD".to_string(), "晴天".to_string()),
+            ]
+        );
+
+        write(path, &[("MY_FIELD", "")]).unwrap();
+        assert_eq!(read(path).len(), 1);
+    }
+
+    #[test]
+    fn custom_fields_round_trip_through_flac() {
+        let dir = TempDir::new().unwrap();
+        let path = write_fixture(&dir, "track.flac", &flac_fixture());
+        assert_round_trip(&path);
+    }
+
+    #[test]
+    fn custom_fields_round_trip_through_m4a_freeform_atoms() {
+        let dir = TempDir::new().unwrap();
+        let path = write_fixture(&dir, "track.m4a", &m4a_fixture());
+        assert_round_trip(&path);
+
+        write(&path, &[("MY_FIELD", "晴天")]).unwrap();
+        let tagged_file = read_tagged_file(&path).unwrap();
+        let tag = tagged_file.primary_tag().unwrap();
+        assert_eq!(tag.get_string(&ItemKey::Unknown(format!("{FREEFORM_PREFIX}MY_FIELD"))), Some("晴天"));
+    }
+
+    #[test]
+    fn mapped_fields_are_not_custom() {
+        let dir = TempDir::new().unwrap();
+        let path = write_fixture(&dir, "track.flac", &flac_fixture());
+        assert!(write(&path, &[("TITLE", "晴天")]).is_err());
+    }
+
+    #[test]
+    fn picard_names_are_canonicalized() {
+        assert_eq!(canonical_key("----:com.apple.iTunes:MusicBrainz Album Id"), "MUSICBRAINZ_ALBUMID");
+        assert_eq!(canonical_key("my field"), "MY_FIELD");
+    }
+}
@@ -0,0 +1,154 @@
+//! 拖放路径分类模块
+//!
+//! 文件拖放到窗口上时一次判断每个路径是什么：可以播放的音频文件、要扫描的目录、
+//! 压缩包、要导入的播放列表、歌词文件，或者不支持的文件（附带原因）。
+//! 目录只做有上限的浅层探测，拖入整个磁盘根目录时不会遍历全部文件。
+
+use super::archive::{archive_entries, is_archive};
+use super::filesystem::has_audio_extension;
+use super::lyrics_finder::{is_lrc_text, is_lyrics_file};
+use super::metadata::{MetadataOptions, TrackMetadata};
+use super::metadata_cache::cached_track_metadata;
+use super::network::probe_dir;
+use super::playlist_file::PlaylistFormat;
+use super::scan_filter::ScanFilter;
+use crate::error::AppError;
+use rayon::prelude::*;
+use serde::Serialize;
+use std::path::Path;
+use walkdir::WalkDir;
+
+/// 探测目录时最多检查的目录项数，超过后只报告"至少"多少个音频文件
+const MAX_PROBE_ENTRIES: usize = 2000;
+
+/// 常见但未必在配置列表中的音频扩展名，用于说明文件为什么不被支持
+const KNOWN_AUDIO_EXTENSIONS: &[&str] = &[
+    "mp3", "flac", "wav", "ogg", "oga", "m4a", "m4b", "mp4", "aac", "opus", "ape", "wv", "aiff", "aif", "aifc",
+    "wma", "dsf", "dff", "mka", "mpc", "tta", "alac", "caf", "ac3", "dts", "mp2", "spx", "webm",
+];
+
+/// 不支持的原因
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum UnsupportedReason {
+    /// 路径不存在
+    NotFound,
+    /// 所在网络共享无法访问
+    Offline,
+    /// 看起来是音频文件，但扩展名不在配置的扩展名列表中
+    ExtensionNotEnabled,
+    /// `.txt` 文件不包含 LRC 时间标签
+    NotLyrics,
+    /// 无法识别的文件类型
+    UnknownType,
+}
+
+/// 拖放路径的分类
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum DroppedPath {
+    /// 音频文件；无法读取标签时 `metadata` 为 `None`，仍可尝试播放
+    #[serde(rename_all = "camelCase")]
+    AudioFile { path: String, metadata: Option<Box<TrackMetadata>> },
+    /// 目录；`truncated` 为 `true` 时 `audio_count` 表示"至少"这么多
+    #[serde(rename_all = "camelCase")]
+    Directory { path: String, audio_count: usize, truncated: bool },
+    /// ZIP 压缩包及其中的音频文件数
+    #[serde(rename_all = "camelCase")]
+    Archive { path: String, audio_count: usize },
+    /// 播放列表文件
+    #[serde(rename_all = "camelCase")]
+    PlaylistFile { path: String, format: PlaylistFormat },
+    /// 歌词文件
+    #[serde(rename_all = "camelCase")]
+    LyricsFile { path: String },
+    /// 不支持的文件，`extension` 为小写扩展名
+    #[serde(rename_all = "camelCase")]
+    Unsupported { path: String, reason: UnsupportedReason, extension: Option<String> },
+}
+
+fn extension_of(path: &Path) -> Option<String> {
+    path.extension().map(|ext| ext.to_string_lossy().to_lowercase())
+}
+
+/// 统计目录中的音频文件，最多检查 `MAX_PROBE_ENTRIES` 个目录项
+fn probe_directory(path: &str, filter: &ScanFilter) -> DroppedPath {
+    let directory = Path::new(path);
+    if let Err(e) = probe_dir(directory) {
+        let reason = match e {
+            AppError::NetworkUnavailable(_) => UnsupportedReason::Offline,
+            _ => UnsupportedReason::NotFound,
+        };
+        return DroppedPath::Unsupported { path: path.to_string(), reason, extension: None };
+    }
+
+    let mut audio_count = 0;
+    let mut truncated = false;
+    let entries = WalkDir::new(directory)
+        .follow_links(filter.follow_symlinks())
+        .into_iter()
+        .filter_entry(|entry| filter.allows(entry))
+        .filter_map(Result::ok);
+    for (index, entry) in entries.enumerate() {
+        if index >= MAX_PROBE_ENTRIES {
+            truncated = true;
+            break;
+        }
+        let entry_path = entry.path();
+        if entry.file_type().is_file()
+            && (has_audio_extension(entry_path) || (filter.scan_archives() && is_archive(entry_path)))
+        {
+            audio_count += 1;
+        }
+    }
+    DroppedPath::Directory { path: path.to_string(), audio_count, truncated }
+}
+
+fn classify(path: &str, filter: &ScanFilter, options: &MetadataOptions) -> DroppedPath {
+    let file = Path::new(path);
+    if file.is_dir() {
+        return probe_directory(path, filter);
+    }
+    let extension = extension_of(file);
+    if !file.is_file() {
+        return DroppedPath::Unsupported { path: path.to_string(), reason: UnsupportedReason::NotFound, extension };
+    }
+
+    if has_audio_extension(file) {
+        let metadata = cached_track_metadata(path, options).ok().map(Box::new);
+        return DroppedPath::AudioFile { path: path.to_string(), metadata };
+    }
+    if is_archive(file) {
+        let audio_count = archive_entries(path).map_or(0, |entries| entries.len());
+        return DroppedPath::Archive { path: path.to_string(), audio_count };
+    }
+    if let Some(format) = PlaylistFormat::from_path(file) {
+        return DroppedPath::PlaylistFile { path: path.to_string(), format };
+    }
+    if is_lyrics_file(file) {
+        if extension.as_deref() == Some("txt") && !is_lrc_text(file) {
+            let reason = UnsupportedReason::NotLyrics;
+            return DroppedPath::Unsupported { path: path.to_string(), reason, extension };
+        }
+        return DroppedPath::LyricsFile { path: path.to_string() };
+    }
+
+    let reason = if extension.as_deref().is_some_and(|ext| KNOWN_AUDIO_EXTENSIONS.contains(&ext)) {
+        UnsupportedReason::ExtensionNotEnabled
+    } else {
+        UnsupportedReason::UnknownType
+    };
+    DroppedPath::Unsupported { path: path.to_string(), reason, extension }
+}
+
+/// 判断每个拖放路径的类型，结果与 `paths` 一一对应
+///
+/// 音频文件读取不含封面的元数据（经过元数据缓存），各路径并行处理。
+#[must_use]
+pub fn classify_dropped_paths_internal(
+    paths: &[String],
+    filter: &ScanFilter,
+    options: &MetadataOptions,
+) -> Vec<DroppedPath> {
+    paths.par_iter().map(|path| classify(path, filter, options)).collect()
+}
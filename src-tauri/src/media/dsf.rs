@@ -0,0 +1,94 @@
+//! DSF 文件解析模块
+//!
+//! lofty 不支持 DSF，这里直接读取 DSD/fmt 块头部和文件末尾的 ID3v2 标签。
+
+use crate::paths::long_path;
+use crate::tr;
+use id3::TagLike;
+use std::fs::File;
+use std::io::{Cursor, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// DSF 头部信息
+#[derive(Debug, Clone, Copy)]
+pub struct DsfInfo {
+    pub sample_rate: u32,
+    pub channels: u8,
+    pub sample_count: u64,
+    pub metadata_offset: u64,
+}
+
+impl DsfInfo {
+    /// 时长（秒）
+    #[must_use]
+    pub fn duration_secs(&self) -> f64 {
+        if self.sample_rate == 0 {
+            0.0
+        } else {
+            self.sample_count as f64 / f64::from(self.sample_rate)
+        }
+    }
+
+    /// 码率（kbps），DSD 每个采样 1 bit
+    #[must_use]
+    pub fn bitrate_kbps(&self) -> u32 {
+        (u64::from(self.sample_rate) * u64::from(self.channels) / 1000) as u32
+    }
+}
+
+/// DSF 中 ID3v2 标签里的常用字段
+#[derive(Debug, Default, Clone)]
+pub struct DsfTags {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub album_artist: Option<String>,
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap_or_default())
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap_or_default())
+}
+
+/// 读取 DSF 头部
+pub fn read_dsf_info(path: &Path) -> Result<DsfInfo, String> {
+    let mut file = File::open(long_path(path)).map_err(|e| tr!("file.openFailed", detail = e))?;
+    let mut header = [0u8; 76];
+    file.read_exact(&mut header).map_err(|e| tr!("dsf.headerIncomplete", detail = e))?;
+
+    if &header[0..4] != b"DSD " || &header[28..32] != b"fmt " {
+        return Err(tr!("dsf.invalid"));
+    }
+
+    Ok(DsfInfo {
+        metadata_offset: read_u64(&header, 20),
+        channels: read_u32(&header, 52) as u8,
+        sample_rate: read_u32(&header, 56),
+        sample_count: read_u64(&header, 64),
+    })
+}
+
+/// 读取 DSF 末尾的 ID3v2 标签
+pub fn read_dsf_tags(path: &Path, info: &DsfInfo) -> Result<Option<DsfTags>, String> {
+    if info.metadata_offset == 0 {
+        return Ok(None);
+    }
+
+    let mut file = File::open(long_path(path)).map_err(|e| tr!("file.openFailed", detail = e))?;
+    file.seek(SeekFrom::Start(info.metadata_offset))
+        .map_err(|e| tr!("dsf.id3SeekFailed", detail = e))?;
+    let mut data = Vec::new();
+    file.read_to_end(&mut data).map_err(|e| tr!("tags.id3ReadFailed", detail = e))?;
+
+    let tag = id3::Tag::read_from2(Cursor::new(data)).map_err(|e| tr!("dsf.id3ParseFailed", detail = e))?;
+
+    Ok(Some(DsfTags {
+        title: tag.title().map(str::to_string),
+        artist: tag.artist().map(str::to_string),
+        album: tag.album().map(str::to_string),
+        album_artist: tag.album_artist().map(str::to_string),
+    }))
+}
@@ -0,0 +1,283 @@
+//! 重复曲目查找模块
+//!
+//! 按音频哈希（精确重复）、标签（标题 + 艺术家 + 时长 ± 2 秒）或文件名查找重复曲目，
+//! 并按用户的选择把多余的副本移到回收站或加入曲库排除列表。
+
+use super::filesystem::check_file_exists_internal;
+use super::hashing::{compute_hash, DuplicateFile, HashCache, HashFailure, HashKind};
+use super::metadata::{MetadataOptions, TrackMetadata};
+use super::metadata_cache::{cached_track_metadata, invalidate};
+use super::text::search_key;
+use super::trash::{move_to_trash, DeleteFailure, DeleteFailureReason};
+use crate::config::ConfigManager;
+use crate::error::{AppError, AppResult};
+use crate::library::LibraryDb;
+use crate::paths::same_path;
+use crate::tasks::CancelToken;
+use crate::tr;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// 按标签比较时允许的时长误差（秒）
+const DURATION_TOLERANCE: f64 = 2.0;
+
+/// 重复判断方式
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicateStrategy {
+    /// 解码后的音频哈希相同
+    Exact,
+    /// 标题、艺术家相同且时长相差不超过 2 秒
+    Tags,
+    /// 文件名（不含扩展名）相同
+    Filename,
+}
+
+/// 多余副本的处理方式
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicateAction {
+    /// 移到回收站
+    Trash,
+    /// 加入扫描排除列表并从曲库移除，文件保留
+    Exclude,
+}
+
+/// 一组重复曲目，按码率和文件大小从高到低排列
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateTrackGroup {
+    /// 分组依据（哈希、规范化的标签或文件名）
+    pub key: String,
+    pub files: Vec<DuplicateFile>,
+}
+
+/// 重复曲目查找结果
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateTrackReport {
+    pub strategy: DuplicateStrategy,
+    pub groups: Vec<DuplicateTrackGroup>,
+    /// 无法读取或计算哈希的文件
+    pub failed: Vec<HashFailure>,
+}
+
+/// 处理结果
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolveDuplicatesResult {
+    pub action: DuplicateAction,
+    pub removed: Vec<String>,
+    pub failed: Vec<DeleteFailure>,
+}
+
+/// 单个文件的读取结果
+struct Probe {
+    path: String,
+    size: u64,
+    track: Option<TrackMetadata>,
+    /// 精确查找时的音频哈希
+    hash: Option<String>,
+}
+
+impl Probe {
+    fn file(&self) -> DuplicateFile {
+        DuplicateFile {
+            path: self.path.clone(),
+            size: self.size,
+            bitrate: self.track.as_ref().and_then(|t| t.bitrate),
+            format: self.track.as_ref().and_then(|t| t.format.clone()),
+        }
+    }
+}
+
+/// 标签分组键，没有标题时返回 `None`
+fn tags_key(track: &TrackMetadata) -> Option<String> {
+    let title = search_key(track.title.as_deref()?).trim().to_string();
+    let artist = track.artist.as_deref().map(|a| search_key(a).trim().to_string()).unwrap_or_default();
+    (!title.is_empty()).then(|| format!("{artist} - {title}"))
+}
+
+fn filename_key(path: &str) -> Option<String> {
+    let stem = Path::new(&path.replace('\\', "/")).file_stem()?.to_string_lossy().trim().to_lowercase();
+    (!stem.is_empty()).then_some(stem)
+}
+
+/// 把同一标签的文件按时长拆分，相邻时长相差超过容差时分开
+fn split_by_duration(mut probes: Vec<&Probe>) -> Vec<Vec<&Probe>> {
+    probes.sort_by(|a, b| {
+        let duration = |p: &Probe| p.track.as_ref().and_then(|t| t.duration).unwrap_or(0.0);
+        duration(a).total_cmp(&duration(b))
+    });
+    let mut clusters: Vec<Vec<&Probe>> = Vec::new();
+    let mut last: Option<f64> = None;
+    for probe in probes {
+        let Some(duration) = probe.track.as_ref().and_then(|t| t.duration) else {
+            continue;
+        };
+        match (clusters.last_mut(), last) {
+            (Some(cluster), Some(previous)) if duration - previous <= DURATION_TOLERANCE => cluster.push(probe),
+            _ => clusters.push(vec![probe]),
+        }
+        last = Some(duration);
+    }
+    clusters
+}
+
+/// 查找重复曲目
+///
+/// 文件在线程池中并行读取，每处理一个文件调用一次 `on_progress(completed, total, path)`。
+/// 精确查找使用哈希缓存，新计算的哈希即使被取消也会写入缓存。只返回包含两个及以上文件的组。
+pub fn find_duplicate_tracks_internal(
+    paths: &[String],
+    strategy: DuplicateStrategy,
+    cache: &mut HashCache,
+    options: &MetadataOptions,
+    cancel: &CancelToken,
+    on_progress: impl Fn(usize, usize, &str) + Sync,
+) -> AppResult<DuplicateTrackReport> {
+    let completed = AtomicUsize::new(0);
+    let shared_cache = &*cache;
+    let results: Vec<Result<(Probe, bool), HashFailure>> = paths
+        .par_iter()
+        .filter(|_| !cancel.is_cancelled())
+        .map(|path| {
+            let failure = |message: String| HashFailure { path: path.clone(), message };
+            let size = fs::metadata(path).map_err(|e| failure(tr!("file.metadataFailed", detail = e)))?.len();
+            let track = cached_track_metadata(path, options).ok();
+
+            let (hash, computed) = if strategy == DuplicateStrategy::Exact {
+                match shared_cache.fresh(path, HashKind::Audio) {
+                    Some(hash) => (Some(hash), false),
+                    None => match compute_hash(path, HashKind::Audio, cancel) {
+                        Ok(hash) => (Some(hash), true),
+                        Err(AppError::Cancelled) => (None, false),
+                        Err(e) => return Err(failure(e.to_string())),
+                    },
+                }
+            } else {
+                (None, false)
+            };
+
+            on_progress(completed.fetch_add(1, Ordering::Relaxed) + 1, paths.len(), path);
+            Ok((Probe { path: path.clone(), size, track, hash }, computed))
+        })
+        .collect();
+
+    let mut probes = Vec::with_capacity(results.len());
+    let mut failed = Vec::new();
+    for result in results {
+        match result {
+            Ok((probe, computed)) => {
+                if let (true, Some(hash)) = (computed, &probe.hash) {
+                    cache.insert(&probe.path, HashKind::Audio, hash.clone());
+                }
+                probes.push(probe);
+            }
+            Err(failure) => failed.push(failure),
+        }
+    }
+    cancel.check()?;
+
+    let mut by_key: HashMap<String, Vec<&Probe>> = HashMap::new();
+    for probe in &probes {
+        let key = match strategy {
+            DuplicateStrategy::Exact => probe.hash.clone(),
+            DuplicateStrategy::Tags => probe.track.as_ref().and_then(tags_key),
+            DuplicateStrategy::Filename => filename_key(&probe.path),
+        };
+        if let Some(key) = key {
+            by_key.entry(key).or_default().push(probe);
+        }
+    }
+
+    let mut groups = Vec::new();
+    for (key, members) in by_key.into_iter().filter(|(_, members)| members.len() > 1) {
+        let clusters = if strategy == DuplicateStrategy::Tags { split_by_duration(members) } else { vec![members] };
+        for cluster in clusters.into_iter().filter(|cluster| cluster.len() > 1) {
+            let mut files: Vec<DuplicateFile> = cluster.iter().map(|probe| probe.file()).collect();
+            files.sort_by(|a, b| b.bitrate.cmp(&a.bitrate).then_with(|| b.size.cmp(&a.size)));
+            groups.push(DuplicateTrackGroup { key: key.clone(), files });
+        }
+    }
+    groups.sort_by(|a, b| a.files[0].path.cmp(&b.files[0].path));
+
+    Ok(DuplicateTrackReport { strategy, groups, failed })
+}
+
+/// 转义 glob 特殊字符，使路径只匹配自身
+fn escape_glob(path: &str) -> String {
+    let mut escaped = String::with_capacity(path.len());
+    for c in path.chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '{' | '}') {
+            escaped.push('[');
+            escaped.push(c);
+            escaped.push(']');
+        } else {
+            escaped.push(c);
+        }
+    }
+    escaped
+}
+
+/// 处理多余的副本
+///
+/// `keep` 中的文件不会被处理，同时出现在 `keep` 和 `remove` 中时返回错误。正在播放的文件会被跳过。
+/// 处理成功的文件从曲库数据库和元数据缓存中移除。
+pub fn resolve_duplicates_internal(
+    db: &LibraryDb,
+    config_manager: &ConfigManager,
+    keep: &[String],
+    remove: Vec<String>,
+    action: DuplicateAction,
+    is_playing: impl Fn(&str) -> bool,
+) -> AppResult<ResolveDuplicatesResult> {
+    if let Some(path) = remove.iter().find(|path| keep.iter().any(|kept| same_path(kept, path))) {
+        return Err(AppError::Other(tr!("duplicates.keepAndDelete", path = path)));
+    }
+
+    let mut result = ResolveDuplicatesResult { action, removed: Vec::new(), failed: Vec::new() };
+    let mut candidates = Vec::with_capacity(remove.len());
+    for path in remove {
+        if is_playing(&path) {
+            result.failed.push(DeleteFailure::new(path, DeleteFailureReason::Playing));
+        } else if !check_file_exists_internal(&path) {
+            result.failed.push(DeleteFailure::new(path, DeleteFailureReason::NotFound));
+        } else {
+            candidates.push(path);
+        }
+    }
+
+    match action {
+        DuplicateAction::Trash => {
+            for path in candidates {
+                match move_to_trash(&path) {
+                    Ok(()) => result.removed.push(path),
+                    Err(message) => result.failed.push(DeleteFailure::error(path, message)),
+                }
+            }
+        }
+        DuplicateAction::Exclude => {
+            let mut config = config_manager.load_config()?;
+            let patterns = &mut config.directory_scan.exclude_patterns;
+            for path in &candidates {
+                let pattern = escape_glob(&path.replace('\\', "/"));
+                if !patterns.contains(&pattern) {
+                    patterns.push(pattern);
+                }
+            }
+            config_manager.save_config(&config)?;
+            result.removed = candidates;
+        }
+    }
+
+    db.remove_tracks(&result.removed)?;
+    for path in &result.removed {
+        invalidate(path);
+    }
+    Ok(result)
+}
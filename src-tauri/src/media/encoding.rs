@@ -0,0 +1,273 @@
+//! 文本编码检测模块
+//!
+//! 用于读取 CUE、歌词、播放列表等常见的非 UTF-8 文本文件。
+
+use chardetng::EncodingDetector;
+use encoding_rs::Encoding;
+use crate::tr;
+
+/// 解码后的文本
+#[derive(Debug, Clone)]
+pub struct DecodedText {
+    /// UTF-8 文本内容
+    pub text: String,
+    /// 检测到的编码名称（如 "UTF-8"、"GBK"、"Shift_JIS"）
+    pub encoding: &'static str,
+    /// 是否带有 BOM
+    pub had_bom: bool,
+}
+
+/// 检测字节内容的编码
+///
+/// 优先识别 BOM，其次使用 chardetng 的启发式检测。
+#[must_use]
+pub fn detect_encoding(bytes: &[u8]) -> (&'static Encoding, bool) {
+    if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+        return (encoding, true);
+    }
+
+    // 合法的 UTF-8 直接使用，避免短文本被误判
+    if std::str::from_utf8(bytes).is_ok() {
+        return (encoding_rs::UTF_8, false);
+    }
+
+    let mut detector = EncodingDetector::new();
+    detector.feed(bytes, true);
+    (detector.guess(None, true), false)
+}
+
+/// 将字节内容解码为 UTF-8 文本
+#[must_use]
+pub fn decode_text(bytes: &[u8]) -> DecodedText {
+    let (encoding, had_bom) = detect_encoding(bytes);
+    let (text, actual_encoding, _) = encoding.decode(bytes);
+
+    DecodedText {
+        text: text.into_owned(),
+        encoding: actual_encoding.name(),
+        had_bom,
+    }
+}
+
+/// 按指定编码名称解码
+pub fn decode_with(bytes: &[u8], encoding_name: &str) -> Result<String, String> {
+    let encoding = Encoding::for_label(encoding_name.as_bytes())
+        .ok_or_else(|| tr!("encoding.unsupported", encoding = encoding_name))?;
+    let (text, _, had_errors) = encoding.decode(bytes);
+    if had_errors {
+        return Err(tr!("encoding.invalidContent", encoding = encoding.name()));
+    }
+    Ok(text.into_owned())
+}
+
+/// 按指定编码名称编码文本
+///
+/// `with_bom` 为 `true` 时在开头写入该编码的 BOM（只对 UTF-8 和 UTF-16 有效）。
+/// 文本中有该编码无法表示的字符时返回错误，不会写入替代字符。
+pub fn encode_with(text: &str, encoding_name: &str, with_bom: bool) -> Result<Vec<u8>, String> {
+    let encoding = Encoding::for_label(encoding_name.as_bytes())
+        .ok_or_else(|| tr!("encoding.unsupported", encoding = encoding_name))?;
+
+    // encoding_rs 不输出 UTF-16，需要手动编码
+    if encoding == encoding_rs::UTF_16LE || encoding == encoding_rs::UTF_16BE {
+        let little_endian = encoding == encoding_rs::UTF_16LE;
+        let mut units: Vec<u16> = if with_bom { vec![0xFEFF] } else { Vec::new() };
+        units.extend(text.encode_utf16());
+        return Ok(units
+            .into_iter()
+            .flat_map(|unit| if little_endian { unit.to_le_bytes() } else { unit.to_be_bytes() })
+            .collect());
+    }
+
+    let mut bytes = if with_bom && encoding == encoding_rs::UTF_8 { vec![0xEF, 0xBB, 0xBF] } else { Vec::new() };
+    let (encoded, _, had_errors) = encoding.encode(text);
+    if had_errors {
+        return Err(tr!("encoding.unencodable", encoding = encoding.name()));
+    }
+    bytes.extend_from_slice(&encoded);
+    Ok(bytes)
+}
+
+/// 乱码修复结果
+#[derive(Debug, Clone)]
+pub struct RepairedText {
+    /// 修复后的文本
+    pub text: String,
+    /// 用于重新解码的编码名称
+    pub encoding: &'static str,
+}
+
+/// 将按 Latin-1 解码的字符串还原为原始字节
+///
+/// 含有 U+00FF 以上字符时说明不是 Latin-1 乱码，返回 `None`。
+fn latin1_bytes(text: &str) -> Option<Vec<u8>> {
+    text.chars().map(|c| u8::try_from(u32::from(c)).ok()).collect()
+}
+
+/// 判断字节序列是否像被当作 Latin-1 存储的双字节编码
+///
+/// 双字节编码中的高位字节几乎总是成对出现，真正的西欧文本（如 "Café"）则多为孤立的高位字节。
+fn looks_like_mojibake(bytes: &[u8]) -> bool {
+    let high_count = bytes.iter().filter(|b| **b >= 0x80).count();
+    if high_count < 2 {
+        return false;
+    }
+
+    let paired = bytes
+        .iter()
+        .enumerate()
+        .filter(|(i, b)| {
+            **b >= 0x80
+                && (bytes.get(i + 1).is_some_and(|n| *n >= 0x80)
+                    || i.checked_sub(1).and_then(|p| bytes.get(p)).is_some_and(|n| *n >= 0x80))
+        })
+        .count();
+
+    paired * 10 >= high_count * 8
+}
+
+/// 是否为中日韩文字或全角标点
+const fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3000}'..='\u{303F}'   // CJK 符号和标点
+        | '\u{3040}'..='\u{30FF}' // 平假名、片假名
+        | '\u{3400}'..='\u{4DBF}' // 扩展 A
+        | '\u{4E00}'..='\u{9FFF}' // 基本汉字
+        | '\u{AC00}'..='\u{D7AF}' // 韩文音节
+        | '\u{F900}'..='\u{FAFF}' // 兼容汉字
+        | '\u{FF00}'..='\u{FFEF}' // 全角 ASCII、半角片假名
+    )
+}
+
+/// 非 ASCII 字符中中日韩字符所占的比例
+fn cjk_score(text: &str) -> f64 {
+    let non_ascii: Vec<char> = text.chars().filter(|c| !c.is_ascii()).collect();
+    if non_ascii.is_empty() {
+        return 0.0;
+    }
+    let cjk = non_ascii.iter().filter(|c| is_cjk(**c)).count();
+    cjk as f64 / non_ascii.len() as f64
+}
+
+/// 尝试修复 Latin-1 乱码
+///
+/// 依次尝试 GBK、Big5、Shift_JIS，按中日韩字符比例选出最佳结果；
+/// 得分相同时按上述顺序优先。无法确认为乱码时返回 `None`。
+#[must_use]
+pub fn repair_mojibake(text: &str) -> Option<RepairedText> {
+    let bytes = latin1_bytes(text)?;
+    if !looks_like_mojibake(&bytes) {
+        return None;
+    }
+
+    let mut best: Option<(f64, RepairedText)> = None;
+    for encoding in [encoding_rs::GBK, encoding_rs::BIG5, encoding_rs::SHIFT_JIS] {
+        let Some(decoded) = encoding.decode_without_bom_handling_and_without_replacement(&bytes) else {
+            continue;
+        };
+        let score = cjk_score(&decoded);
+        if score >= 0.7 && best.as_ref().is_none_or(|(best_score, _)| score > *best_score) {
+            best = Some((score, RepairedText { text: decoded.into_owned(), encoding: encoding.name() }));
+        }
+    }
+
+    best.map(|(_, repaired)| repaired)
+}
+
+/// 按指定编码重新解释 Latin-1 乱码字符串
+pub fn reinterpret_latin1(text: &str, encoding_name: &str) -> Result<String, String> {
+    let bytes = latin1_bytes(text).ok_or_else(|| tr!("encoding.notLatin1"))?;
+    decode_with(&bytes, encoding_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 把文本按指定编码编码后再按 Latin-1 解码，得到的就是旧标签里常见的乱码
+    fn mojibake(text: &str, encoding: &'static Encoding) -> String {
+        let (bytes, _, had_errors) = encoding.encode(text);
+        assert!(!had_errors);
+        bytes.iter().map(|&b| char::from(b)).collect()
+    }
+
+    #[test]
+    fn repairs_gbk_mojibake() {
+        let repaired = repair_mojibake(&mojibake("周杰伦 - 晴天", encoding_rs::GBK)).unwrap();
+        assert_eq!(repaired.text, "周杰伦 - 晴天");
+        assert_eq!(repaired.encoding, "GBK");
+    }
+
+    #[test]
+    fn repairs_big5_mojibake() {
+        for text in ["五月天 - 倔強", "陳奕迅"] {
+            let repaired = repair_mojibake(&mojibake(text, encoding_rs::BIG5)).unwrap();
+            assert_eq!(repaired.text, text);
+            assert_eq!(repaired.encoding, "Big5");
+        }
+    }
+
+    /// Shift_JIS 的假名字节通常也是合法的 GBK 汉字，得分相同时按顺序选 GBK，需要用户指定编码
+    #[test]
+    fn shift_jis_ties_fall_back_to_gbk_and_can_be_reinterpreted() {
+        let garbled = mojibake("浜崎あゆみ", encoding_rs::SHIFT_JIS);
+        assert_eq!(repair_mojibake(&garbled).unwrap().encoding, "GBK");
+        assert_eq!(reinterpret_latin1(&garbled, "Shift_JIS").unwrap(), "浜崎あゆみ");
+    }
+
+    #[test]
+    fn western_text_is_not_mojibake() {
+        for text in ["Café del Mar", "Beyoncé", "Motörhead", "Sigur Rós - Ágætis byrjun", "Plain ASCII"] {
+            assert!(repair_mojibake(text).is_none(), "{text}");
+        }
+    }
+
+    #[test]
+    fn correctly_decoded_text_is_left_alone() {
+        assert!(repair_mojibake("周杰伦").is_none());
+        assert!(reinterpret_latin1("周杰伦", "GBK").is_err());
+    }
+
+    #[test]
+    fn detects_byte_order_marks() {
+        for (encoding, name) in [("UTF-8", "UTF-8"), ("UTF-16LE", "UTF-16LE"), ("UTF-16BE", "UTF-16BE")] {
+            let decoded = decode_text(&encode_with("歌词 Lyrics", encoding, true).unwrap());
+            assert_eq!(decoded.text, "歌词 Lyrics", "{encoding}");
+            assert_eq!(decoded.encoding, name);
+            assert!(decoded.had_bom);
+        }
+    }
+
+    #[test]
+    fn valid_utf8_without_bom_is_utf8() {
+        let decoded = decode_text("Ünïcödé 歌词".as_bytes());
+        assert_eq!(decoded.encoding, "UTF-8");
+        assert!(!decoded.had_bom);
+        // 纯 ASCII 同样按 UTF-8 处理
+        assert_eq!(detect_encoding(b"TITLE \"Song\"").0, encoding_rs::UTF_8);
+    }
+
+    #[test]
+    fn detects_legacy_cjk_charsets() {
+        let samples = [
+            ("故事的小黄花，从出生那年就飘着，童年的荡秋千，随记忆一直晃到现在", "GBK"),
+            ("刮風這天，我試過握著你手，但偏偏雨漸漸大到我看你不見", "Big5"),
+            ("いつも何度でも呼んでいる、胸のどこか奥で。いつも心躍る夢を見たい", "Shift_JIS"),
+        ];
+        for (text, encoding) in samples {
+            let decoded = decode_text(&encode_with(text, encoding, false).unwrap());
+            assert_eq!(decoded.encoding, encoding);
+            assert_eq!(decoded.text, text);
+            assert!(!decoded.had_bom);
+        }
+    }
+
+    #[test]
+    fn encode_and_decode_with_named_encodings_round_trip() {
+        let bytes = encode_with("晴天", "gb2312", false).unwrap();
+        assert_eq!(decode_with(&bytes, "GBK").unwrap(), "晴天");
+        assert!(encode_with("晴天", "windows-1252", false).is_err());
+        assert!(decode_with(&[0x81], "Shift_JIS").is_err());
+        assert!(decode_with(b"", "no-such-encoding").is_err());
+    }
+}
@@ -0,0 +1,368 @@
+//! 曲库导出模块
+//!
+//! 将音轨元数据逐条写入 JSON 或 CSV 文件，并支持从 CSV 导回评分。
+
+use super::filesystem::collect_audio_paths;
+use super::metadata::{MetadataOptions, TrackMetadata};
+use super::metadata_cache::cached_track_metadata;
+use super::rating::{read_rating, write_rating};
+use crate::error::{AppError, AppResult};
+use crate::paths::long_path;
+use crate::tasks::CancelToken;
+use crate::tr;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// CSV 导出的列，顺序即输出顺序
+const CSV_COLUMNS: &[&str] = &[
+    "path", "name", "title", "artist", "artists", "album", "albumArtist", "artistSort", "albumArtistSort",
+    "albumSort", "genre", "year", "trackNumber", "trackTotal", "discNumber", "isrc", "label", "catalogNumber",
+    "duration", "bitrate", "sampleRate", "channels", "bitDepth", "format", "encoder", "container", "hasCover",
+    "rating",
+];
+
+/// 导出格式
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Json,
+    Csv,
+}
+
+/// 导出结果
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportResult {
+    pub output_path: String,
+    /// 成功写出的音轨数
+    pub exported: usize,
+    /// 读取失败而跳过的文件
+    pub failed: Vec<ExportFailure>,
+}
+
+/// 导出或导入评分失败的文件
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportFailure {
+    pub path: String,
+    pub message: String,
+}
+
+/// 评分导入结果
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportRatingsResult {
+    pub updated: usize,
+    /// 评分为空、文件不存在或正在播放而跳过的行
+    pub skipped: usize,
+    pub errors: Vec<ExportFailure>,
+}
+
+fn opt_to_string<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+/// 生成一行 CSV 记录，与 `CSV_COLUMNS` 一一对应
+///
+/// `path` 是文件在磁盘上的实际路径；`TrackMetadata.path` 统一使用 `\` 分隔，在其他平台上无法再打开。
+fn csv_record(path: &str, track: &TrackMetadata, rating: Option<u8>) -> Vec<String> {
+    vec![
+        path.to_string(),
+        track.name.clone(),
+        track.title.clone().unwrap_or_default(),
+        track.artist.clone().unwrap_or_default(),
+        track.artists.join("; "),
+        track.album.clone().unwrap_or_default(),
+        track.album_artist.clone().unwrap_or_default(),
+        track.artist_sort.clone().unwrap_or_default(),
+        track.album_artist_sort.clone().unwrap_or_default(),
+        track.album_sort.clone().unwrap_or_default(),
+        track.genre.clone().unwrap_or_default(),
+        opt_to_string(track.year),
+        opt_to_string(track.track_number),
+        opt_to_string(track.track_total),
+        opt_to_string(track.disc_number),
+        track.isrc.clone().unwrap_or_default(),
+        track.label.clone().unwrap_or_default(),
+        track.catalog_number.clone().unwrap_or_default(),
+        track.duration.map(|d| format!("{d:.3}")).unwrap_or_default(),
+        opt_to_string(track.bitrate),
+        opt_to_string(track.sample_rate),
+        opt_to_string(track.channels),
+        opt_to_string(track.bit_depth),
+        track.format.clone().unwrap_or_default(),
+        track.encoder.clone().unwrap_or_default(),
+        track.container.clone().unwrap_or_default(),
+        track.has_cover.to_string(),
+        opt_to_string(rating),
+    ]
+}
+
+/// 生成一条 JSON 记录（去掉封面数据，附加评分），`path` 与 CSV 相同为实际路径
+fn json_record(path: &str, track: &TrackMetadata, rating: Option<u8>) -> Result<serde_json::Value, String> {
+    let mut value = serde_json::to_value(track).map_err(|e| tr!("export.serializeFailed", detail = e))?;
+    if let Some(object) = value.as_object_mut() {
+        object.remove("cover");
+        object.insert("path".to_string(), path.into());
+        object.insert("rating".to_string(), rating.into());
+    }
+    Ok(value)
+}
+
+/// 按格式逐条写出记录
+enum RecordWriter {
+    Json { writer: BufWriter<File>, first: bool },
+    Csv(Box<csv::Writer<BufWriter<File>>>),
+}
+
+impl RecordWriter {
+    fn create(path: &Path, format: ExportFormat) -> Result<Self, String> {
+        let file = File::create(path).map_err(|e| tr!("export.createFailed", detail = e))?;
+        let mut writer = BufWriter::new(file);
+        match format {
+            ExportFormat::Json => {
+                writer.write_all(b"[\n").map_err(|e| tr!("export.writeFailed", detail = e))?;
+                Ok(Self::Json { writer, first: true })
+            }
+            ExportFormat::Csv => {
+                let mut csv_writer = csv::Writer::from_writer(writer);
+                csv_writer
+                    .write_record(CSV_COLUMNS)
+                    .map_err(|e| tr!("export.writeFailed", detail = e))?;
+                Ok(Self::Csv(Box::new(csv_writer)))
+            }
+        }
+    }
+
+    fn write(&mut self, path: &str, track: &TrackMetadata, rating: Option<u8>) -> Result<(), String> {
+        match self {
+            Self::Json { writer, first } => {
+                if !*first {
+                    writer.write_all(b",\n").map_err(|e| tr!("export.writeFailed", detail = e))?;
+                }
+                *first = false;
+                serde_json::to_writer(&mut *writer, &json_record(path, track, rating)?)
+                    .map_err(|e| tr!("export.writeFailed", detail = e))
+            }
+            Self::Csv(writer) => writer
+                .write_record(csv_record(path, track, rating))
+                .map_err(|e| tr!("export.writeFailed", detail = e)),
+        }
+    }
+
+    fn finish(self) -> Result<(), String> {
+        match self {
+            Self::Json { mut writer, .. } => {
+                writer.write_all(b"\n]\n").and_then(|()| writer.flush())
+            }
+            Self::Csv(mut writer) => writer.flush(),
+        }
+        .map_err(|e| tr!("export.writeFailed", detail = e))
+    }
+}
+
+/// 导出曲库元数据
+///
+/// `inputs` 可以是目录或单个文件，目录会被递归扫描。每读取一个文件调用一次
+/// `on_progress(completed, total, path)`；取消时删除未完成的导出文件。
+pub fn export_library_internal(
+    inputs: &[String],
+    format: ExportFormat,
+    output_path: &str,
+    options: &MetadataOptions,
+    cancel: &CancelToken,
+    on_progress: impl Fn(usize, usize, &str),
+) -> AppResult<ExportResult> {
+    let paths = collect_audio_paths(inputs);
+    let total = paths.len();
+    let output = Path::new(output_path);
+
+    let mut writer = RecordWriter::create(output, format)?;
+    let mut result = ExportResult { output_path: output_path.to_string(), exported: 0, failed: Vec::new() };
+    let options = options.clone().with_covers(false);
+
+    for (index, path) in paths.iter().enumerate() {
+        if cancel.is_cancelled() {
+            drop(writer);
+            let _ = fs::remove_file(output);
+            return Err(AppError::Cancelled);
+        }
+        on_progress(index, total, path);
+
+        match cached_track_metadata(path, &options) {
+            Ok(track) => {
+                writer.write(path, &track, read_rating(path))?;
+                result.exported += 1;
+            }
+            Err(message) => result.failed.push(ExportFailure { path: path.clone(), message }),
+        }
+    }
+
+    writer.finish()?;
+    on_progress(total, total, "");
+    Ok(result)
+}
+
+/// 找到 CSV 中的路径对应的文件
+///
+/// 早期版本导出的 `path` 列把 `/` 换成了 `\`，在其他平台上找不到文件时换回 `/` 再试一次。
+fn resolve_import_path(path: &str) -> Option<String> {
+    if long_path(path).is_file() {
+        return Some(path.to_string());
+    }
+    if cfg!(windows) {
+        return None;
+    }
+    let restored = path.replace('\\', "/");
+    long_path(&restored).is_file().then_some(restored)
+}
+
+/// 从 CSV 文件导入评分
+///
+/// 需要包含 `path` 与 `rating` 两列（不区分大小写）。评分为空的行会被跳过，
+/// 评分为 0 时移除文件中的评分。
+pub fn import_ratings_internal(csv_path: &str, is_playing: impl Fn(&str) -> bool) -> Result<ImportRatingsResult, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_path(csv_path)
+        .map_err(|e| tr!("export.csvOpenFailed", detail = e))?;
+
+    let headers = reader.headers().map_err(|e| tr!("export.csvHeaderFailed", detail = e))?.clone();
+    let column = |name: &str| headers.iter().position(|h| h.trim().eq_ignore_ascii_case(name));
+    let path_column = column("path").ok_or_else(|| tr!("export.csvMissingColumn", column = "path"))?;
+    let rating_column = column("rating").ok_or_else(|| tr!("export.csvMissingColumn", column = "rating"))?;
+
+    let mut result = ImportRatingsResult::default();
+    for record in reader.records() {
+        let record = record.map_err(|e| tr!("export.csvRowFailed", detail = e))?;
+        let path = record.get(path_column).unwrap_or_default().trim();
+        let rating = record.get(rating_column).unwrap_or_default().trim();
+
+        if path.is_empty() || rating.is_empty() {
+            result.skipped += 1;
+            continue;
+        }
+        let Some(path) = resolve_import_path(path).filter(|path| !is_playing(path)) else {
+            result.skipped += 1;
+            continue;
+        };
+
+        let stars = match rating.parse::<u8>() {
+            Ok(stars) if stars <= 5 => stars,
+            _ => {
+                result.errors.push(ExportFailure {
+                    path,
+                    message: tr!("export.invalidRating", rating = rating),
+                });
+                continue;
+            }
+        };
+
+        match write_rating(&path, Some(stars)) {
+            Ok(()) => result.updated += 1,
+            Err(message) => result.errors.push(ExportFailure { path, message }),
+        }
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use tempfile::TempDir;
+
+    /// 在 `music/` 下放一个无法读取标签的音轨，元数据退回到文件名
+    fn library(dir: &TempDir) -> (String, PathBuf) {
+        let music = dir.path().join("music");
+        let track = music.join("Artist").join("song.flac");
+        fs::create_dir_all(track.parent().unwrap()).unwrap();
+        fs::write(&track, "not audio").unwrap();
+        (music.to_string_lossy().to_string(), track)
+    }
+
+    fn export(input: String, format: ExportFormat, output: &Path) -> ExportResult {
+        let output = output.to_string_lossy();
+        let options = MetadataOptions::default();
+        export_library_internal(&[input], format, &output, &options, &CancelToken::default(), |_, _, _| {}).unwrap()
+    }
+
+    #[test]
+    fn csv_export_writes_the_path_on_disk() {
+        let dir = TempDir::new().unwrap();
+        let (music, track) = library(&dir);
+        let output = dir.path().join("library.csv");
+
+        assert_eq!(export(music, ExportFormat::Csv, &output).exported, 1);
+        let mut reader = csv::Reader::from_path(&output).unwrap();
+        let record = reader.records().next().unwrap().unwrap();
+        assert_eq!(Path::new(&record[0]), track);
+        assert!(Path::new(&record[0]).is_file());
+    }
+
+    #[test]
+    fn json_export_writes_the_path_on_disk() {
+        let dir = TempDir::new().unwrap();
+        let (music, track) = library(&dir);
+        let output = dir.path().join("library.json");
+
+        assert_eq!(export(music, ExportFormat::Json, &output).exported, 1);
+        let records: Vec<serde_json::Value> = serde_json::from_slice(&fs::read(&output).unwrap()).unwrap();
+        assert_eq!(Path::new(records[0]["path"].as_str().unwrap()), track);
+        assert!(records[0].get("cover").is_none());
+    }
+
+    fn write_csv(dir: &TempDir, rows: &[[&str; 2]]) -> String {
+        let path = dir.path().join("ratings.csv");
+        let mut writer = csv::Writer::from_path(&path).unwrap();
+        writer.write_record(["path", "rating"]).unwrap();
+        for row in rows {
+            writer.write_record(row).unwrap();
+        }
+        writer.flush().unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn import_skips_empty_missing_and_playing_rows() {
+        let dir = TempDir::new().unwrap();
+        let (_, track) = library(&dir);
+        let track = track.to_string_lossy().to_string();
+        let missing = dir.path().join("missing.flac").to_string_lossy().to_string();
+        let rows = [["", "3"], [track.as_str(), ""], [missing.as_str(), "3"], [track.as_str(), "4"]];
+        let csv = write_csv(&dir, &rows);
+
+        let result = import_ratings_internal(&csv, |path| path == track).unwrap();
+        assert_eq!(result.skipped, 4);
+        assert_eq!(result.updated, 0);
+        assert!(result.errors.is_empty());
+    }
+
+    #[test]
+    fn import_rejects_out_of_range_ratings() {
+        let dir = TempDir::new().unwrap();
+        let (_, track) = library(&dir);
+        let csv = write_csv(&dir, &[[track.to_string_lossy().as_ref(), "9"]]);
+
+        let result = import_ratings_internal(&csv, |_| false).unwrap();
+        assert_eq!(result.errors.len(), 1);
+        assert!(result.errors[0].message.contains('9'));
+    }
+
+    /// 早期版本导出的路径用 `\` 分隔，在其他平台上仍能找到文件
+    #[cfg(not(windows))]
+    #[test]
+    fn import_accepts_backslash_paths_from_older_exports() {
+        let dir = TempDir::new().unwrap();
+        let (_, track) = library(&dir);
+        let legacy = track.to_string_lossy().replace('/', "\\");
+        let csv = write_csv(&dir, &[[legacy.as_str(), "9"]]);
+
+        let result = import_ratings_internal(&csv, |_| false).unwrap();
+        assert_eq!(result.skipped, 0);
+        assert_eq!(Path::new(&result.errors[0].path), track);
+    }
+}
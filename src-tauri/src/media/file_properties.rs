@@ -0,0 +1,62 @@
+//! 文件属性模块
+//!
+//! 为曲目信息对话框提供文件大小、创建和修改时间、只读标记、规范路径以及所属的音乐目录。
+//! 曲库外的文件同样可以查询。
+
+use crate::error::{AppError, AppResult};
+use crate::library::LibraryDb;
+use crate::paths::{display_path, is_within, long_path};
+use serde::Serialize;
+use std::fs;
+use std::io::ErrorKind;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 文件属性
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FileProperties {
+    pub path: String,
+    /// 文件大小（字节）
+    pub size: u64,
+    /// 创建时间（Unix 秒），文件系统不记录时为 `None`
+    pub created: Option<i64>,
+    /// 修改时间（Unix 秒）
+    pub modified: Option<i64>,
+    pub read_only: bool,
+    /// 解析符号链接和 `..` 后的绝对路径
+    pub canonical_path: String,
+    /// 包含该文件的音乐目录（取最长的匹配），不在任何音乐目录中时为 `None`
+    pub library_root: Option<String>,
+    /// 加入曲库的时间（Unix 秒），不在曲库中时为 `None`
+    pub added_at: Option<i64>,
+}
+
+fn unix_seconds(time: std::io::Result<SystemTime>) -> Option<i64> {
+    i64::try_from(time.ok()?.duration_since(UNIX_EPOCH).ok()?.as_secs()).ok()
+}
+
+/// 读取文件属性，文件不存在时返回 `FileNotFound`
+pub fn file_properties_internal(db: &LibraryDb, path: &str, music_directories: &[String]) -> AppResult<FileProperties> {
+    let metadata = fs::metadata(long_path(path)).map_err(|e| match e.kind() {
+        ErrorKind::NotFound => AppError::FileNotFound(path.to_string()),
+        _ => AppError::Io(e),
+    })?;
+    let canonical_path = fs::canonicalize(long_path(path))
+        .map_or_else(|_| path.to_string(), |canonical| display_path(canonical).to_string_lossy().to_string());
+    let library_root = music_directories
+        .iter()
+        .filter(|root| is_within(path, root) || is_within(&canonical_path, root))
+        .max_by_key(|root| root.len())
+        .cloned();
+
+    Ok(FileProperties {
+        path: path.to_string(),
+        size: metadata.len(),
+        created: unix_seconds(metadata.created()),
+        modified: unix_seconds(metadata.modified()),
+        read_only: metadata.permissions().readonly(),
+        canonical_path,
+        library_root,
+        added_at: db.added_at(path)?,
+    })
+}
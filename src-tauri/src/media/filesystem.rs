@@ -1,17 +1,49 @@
 //! 文件系统操作模块
 //!
-//! 提供目录读取、文件检查等功能。
+//! 提供目录读取、文件检查等功能。网络共享上的目录在扫描时逐个操作限时，避免休眠的共享让扫描无限期挂起。
 
-use super::metadata::{get_track_metadata_internal, Playlist};
+use super::archive::{archive_entries, archive_entry_path, is_archive, split_archive_path};
+use super::cover::clear_folder_cover_cache;
+use super::encoding::{decode_text, detect_encoding, encode_with};
+use super::metadata::{MetadataOptions, Playlist, TrackMetadata};
+use super::metadata_cache::cached_track_metadata;
+use super::network::{is_network_path, probe_dir, with_timeout};
+use super::scan_filter::ScanFilter;
+use super::tag_writer::temp_path_for;
+use super::watcher::mark_self_write;
 use crate::config::AppConfig;
+use crate::error::{AppError, AppResult};
+use crate::paths::long_path;
+use crate::system::power;
+use crate::tasks::CancelToken;
+use crate::tr;
+use crossbeam_channel::RecvTimeoutError;
 use rayon::prelude::*;
-use std::collections::HashMap;
-use std::fs;
-use std::path::Path;
+use serde::{Deserialize, Serialize};
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{LazyLock, Mutex, RwLock};
+use std::time::{Duration, Instant, UNIX_EPOCH};
 use walkdir::{DirEntry, WalkDir};
 
-/// 支持的音频文件扩展名
-pub const AUDIO_EXTENSIONS: &[&str] = &["mp3", "flac", "wav", "ogg", "m4a", "aac"];
+/// 默认支持的音频文件扩展名，配置中的 `directory_scan.audio_extensions` 为空时使用
+pub const AUDIO_EXTENSIONS: &[&str] = &[
+    "mp3", "flac", "wav", "ogg", "m4a", "m4b", "aac", "opus", "ape", "wv", "aiff", "aif", "wma", "dsf",
+];
+
+/// 当前支持的扩展名（小写、不含点），启动时和修改配置后更新，扫描器、监视器和拖放检查都从这里读取
+static SUPPORTED_EXTENSIONS: LazyLock<RwLock<Vec<String>>> =
+    LazyLock::new(|| RwLock::new(AUDIO_EXTENSIONS.iter().map(|ext| (*ext).to_string()).collect()));
+
+/// 遍历网络目录时检查取消和超时的间隔
+const WALK_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// 网络目录中连续超时的文件数达到该值时停止读取剩余文件
+const MAX_CONSECUTIVE_TIMEOUTS: usize = 3;
 
 /// 读取指定目录中的子目录列表
 pub fn read_dir(path: &str) -> Result<Vec<String>, String> {
@@ -29,89 +61,510 @@ pub fn read_dir(path: &str) -> Result<Vec<String>, String> {
         .ok_or_else(|| "Failed to convert paths".to_string())
 }
 
+/// 扫描中遇到的非致命问题（如符号链接循环、网络文件读取超时）
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanWarning {
+    pub path: String,
+    pub message: String,
+}
+
+/// 扫描中无法读取元数据的文件
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanFailure {
+    pub path: String,
+    pub reason: String,
+}
+
+/// 目录扫描进度，扫描线程更新，进度事件定时读取
+#[derive(Debug, Default)]
+pub struct ScanProgress {
+    discovered: AtomicUsize,
+    processed: AtomicUsize,
+    /// 遍历到的文件数（包括非音频文件）
+    files_seen: AtomicUsize,
+    /// 被过滤规则跳过的文件和目录数
+    excluded: AtomicUsize,
+    /// 扩展名（小写）-> 音频文件数
+    formats: Mutex<BTreeMap<String, usize>>,
+    failures: Mutex<Vec<ScanFailure>>,
+    current: Mutex<Option<String>>,
+    warnings: Mutex<Vec<ScanWarning>>,
+    /// 无法访问或因超时没有完整读取的根目录
+    incomplete_roots: Mutex<Vec<PathBuf>>,
+    /// 已扫描完的根目录
+    completed_roots: Mutex<Vec<String>>,
+}
+
+impl ScanProgress {
+    /// 已发现的音频文件数
+    #[must_use]
+    pub fn discovered(&self) -> usize {
+        self.discovered.load(Ordering::Relaxed)
+    }
+
+    /// 已读取元数据的文件数
+    #[must_use]
+    pub fn processed(&self) -> usize {
+        self.processed.load(Ordering::Relaxed)
+    }
+
+    /// 遍历到的文件数（包括非音频文件）
+    #[must_use]
+    pub fn files_seen(&self) -> usize {
+        self.files_seen.load(Ordering::Relaxed)
+    }
+
+    /// 被排除规则或隐藏文件规则跳过的文件和目录数（跳过的目录只计一次，不含其中的文件）
+    #[must_use]
+    pub fn excluded(&self) -> usize {
+        self.excluded.load(Ordering::Relaxed)
+    }
+
+    /// 按扩展名统计的音频文件数
+    #[must_use]
+    pub fn formats(&self) -> BTreeMap<String, usize> {
+        self.formats.lock().unwrap().clone()
+    }
+
+    /// 无法读取元数据的文件及原因
+    #[must_use]
+    pub fn failures(&self) -> Vec<ScanFailure> {
+        self.failures.lock().unwrap().clone()
+    }
+
+    /// 已扫描完的根目录（多目录扫描时按顺序逐个完成）
+    #[must_use]
+    pub fn completed_roots(&self) -> Vec<String> {
+        self.completed_roots.lock().unwrap().clone()
+    }
+
+    /// 最近处理的文件
+    #[must_use]
+    pub fn current(&self) -> Option<String> {
+        self.current.lock().unwrap().clone()
+    }
+
+    /// 取出尚未报告的警告
+    pub fn take_warnings(&self) -> Vec<ScanWarning> {
+        std::mem::take(&mut *self.warnings.lock().unwrap())
+    }
+
+    /// 记录一条警告，随下一次进度事件发送
+    pub fn warn(&self, path: &Path, message: String) {
+        eprintln!("{message}");
+        self.warnings.lock().unwrap().push(ScanWarning { path: path.to_string_lossy().to_string(), message });
+    }
+
+    fn fail(&self, path: &str, reason: String) {
+        eprintln!("Failed to get metadata for file '{path}': {reason}");
+        self.failures.lock().unwrap().push(ScanFailure { path: path.to_string(), reason });
+    }
+
+    fn count_audio(&self, path: &Path) {
+        self.discovered.fetch_add(1, Ordering::Relaxed);
+        self.files_seen.fetch_add(1, Ordering::Relaxed);
+        let extension = path.extension().map(|ext| ext.to_string_lossy().to_lowercase()).unwrap_or_default();
+        *self.formats.lock().unwrap().entry(extension).or_default() += 1;
+    }
+
+    fn mark_incomplete(&self, root: &Path) {
+        let mut roots = self.incomplete_roots.lock().unwrap();
+        if !roots.iter().any(|existing| existing == root) {
+            roots.push(root.to_path_buf());
+        }
+    }
+
+    /// 根目录是否无法访问或因超时有文件没有读取，这样的根目录不能据扫描结果删除曲库记录
+    #[must_use]
+    pub fn is_incomplete(&self, root: &Path) -> bool {
+        self.incomplete_roots.lock().unwrap().iter().any(|existing| existing == root)
+    }
+}
+
+/// 规范化路径，失败时返回原路径
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// 目录遍历中的一步
+enum WalkEvent {
+    /// 进入了一个目录
+    Dir(PathBuf),
+    Audio(DirEntry),
+    /// 非音频文件
+    File,
+    /// 被过滤规则跳过的条目数，遍历结束时发送
+    Excluded(usize),
+    /// 重复或无法读取的条目，只用于判断遍历是否停滞
+    Other,
+    Warning(PathBuf, String),
+}
+
+/// 遍历目录，被过滤的目录不会被进入，取消或 `emit` 返回 `false` 后立即停止
+///
+/// 启用 `ScanFilter::scan_archives` 时 ZIP 压缩包也作为音频文件报告，读取元数据时再展开。
+/// 跟随符号链接时，循环链接会被跳过并报告为警告；同一目录或文件经由多个链接到达时只保留一次，
+/// 再次到达的目录同样报告为警告。
+fn walk(
+    dir: &Path,
+    max_depth: Option<usize>,
+    filter: &ScanFilter,
+    cancel: &CancelToken,
+    mut emit: impl FnMut(WalkEvent) -> bool,
+) {
+    let follow = filter.follow_symlinks();
+    let mut walker = WalkDir::new(dir).follow_links(follow);
+    if let Some(max_depth) = max_depth {
+        walker = walker.max_depth(max_depth);
+    }
+
+    let mut visited_dirs: HashSet<PathBuf> = HashSet::new();
+    let mut seen_files: HashSet<PathBuf> = HashSet::new();
+    let excluded = Cell::new(0);
+    // 已经经由其他链接遍历过而被跳过的目录及其实际路径，`filter_entry` 中无法直接发送事件
+    let revisited: RefCell<Vec<(PathBuf, PathBuf)>> = RefCell::new(Vec::new());
+    let mut entries = walker.into_iter().filter_entry(|entry| {
+        if !filter.allows(entry) {
+            excluded.set(excluded.get() + 1);
+            return false;
+        }
+        if !follow || !entry.file_type().is_dir() {
+            return true;
+        }
+        let target = canonical(entry.path());
+        if visited_dirs.insert(target.clone()) {
+            return true;
+        }
+        revisited.borrow_mut().push((entry.path().to_path_buf(), target));
+        false
+    });
+    loop {
+        let next = entries.next();
+        for (path, target) in revisited.take() {
+            // 指向自己的上级目录时是循环，否则只是经由另一个链接到达了同一目录
+            let is_loop = path.parent().is_some_and(|parent| canonical(parent).starts_with(&target));
+            let (shown, target) = (path.display(), target.display());
+            let message = if is_loop {
+                tr!("scan.symlinkLoop", path = shown, target = target)
+            } else {
+                tr!("scan.symlinkRevisit", path = shown, target = target)
+            };
+            if !emit(WalkEvent::Warning(path, message)) {
+                return;
+            }
+        }
+        let Some(result) = next else {
+            break;
+        };
+        if cancel.is_cancelled() {
+            break;
+        }
+        let event = match result {
+            Ok(entry) if entry.file_type().is_dir() => WalkEvent::Dir(entry.path().to_path_buf()),
+            Ok(entry)
+                if (is_audio_file(&entry) || (filter.scan_archives() && is_archive(entry.path())))
+                    && (!follow || seen_files.insert(canonical(entry.path()))) =>
+            {
+                WalkEvent::Audio(entry)
+            }
+            Ok(entry) if entry.file_type().is_file() && !is_audio_file(&entry) => WalkEvent::File,
+            Ok(_) => WalkEvent::Other,
+            Err(e) => match (e.path(), e.loop_ancestor()) {
+                (Some(path), Some(ancestor)) => WalkEvent::Warning(
+                    path.to_path_buf(),
+                    tr!("scan.symlinkLoop", path = path.display(), target = ancestor.display()),
+                ),
+                _ => WalkEvent::Other,
+            },
+        };
+        if !emit(event) {
+            return;
+        }
+    }
+    if excluded.get() > 0 {
+        emit(WalkEvent::Excluded(excluded.get()));
+    }
+}
+
+/// 遍历目录收集音频文件
+///
+/// 网络目录在单独的线程中遍历，超过 `ScanFilter::network_timeout` 没有任何进展时放弃剩余部分，
+/// 记录警告并把根目录标记为未完整读取。
+fn walk_audio_files(
+    dir: &Path,
+    max_depth: Option<usize>,
+    filter: &ScanFilter,
+    progress: &ScanProgress,
+    cancel: &CancelToken,
+) -> Vec<DirEntry> {
+    let mut files = Vec::new();
+    let mut handle = |event: WalkEvent| match event {
+        WalkEvent::Audio(entry) => {
+            progress.count_audio(entry.path());
+            files.push(entry);
+        }
+        WalkEvent::File => {
+            progress.files_seen.fetch_add(1, Ordering::Relaxed);
+        }
+        WalkEvent::Excluded(count) => {
+            progress.excluded.fetch_add(count, Ordering::Relaxed);
+        }
+        WalkEvent::Warning(path, message) => progress.warn(&path, message),
+        WalkEvent::Dir(_) | WalkEvent::Other => {}
+    };
+
+    if !is_network_path(dir) {
+        walk(dir, max_depth, filter, cancel, |event| {
+            handle(event);
+            true
+        });
+        return files;
+    }
+
+    let (sender, receiver) = crossbeam_channel::unbounded();
+    {
+        let (dir, filter, cancel) = (dir.to_path_buf(), filter.clone(), cancel.clone());
+        std::thread::spawn(move || walk(&dir, max_depth, &filter, &cancel, |event| sender.send(event).is_ok()));
+    }
+
+    let timeout = filter.network_timeout();
+    let mut current = dir.to_path_buf();
+    let mut last_event = Instant::now();
+    while !cancel.is_cancelled() {
+        match receiver.recv_timeout(WALK_POLL_INTERVAL) {
+            Ok(event) => {
+                last_event = Instant::now();
+                if let WalkEvent::Dir(path) = &event {
+                    current.clone_from(path);
+                }
+                handle(event);
+            }
+            Err(RecvTimeoutError::Timeout) if last_event.elapsed() < timeout => {}
+            Err(RecvTimeoutError::Timeout) => {
+                let message = tr!("scan.directoryTimeout", seconds = timeout.as_secs(), path = current.display());
+                progress.warn(&current, message);
+                progress.mark_incomplete(dir);
+                break;
+            }
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+    files
+}
+
+/// 读取单个文件的元数据，`timeout` 不为空时在单独的线程中读取，超时返回 `None`
+fn read_track(
+    path: &str,
+    options: &MetadataOptions,
+    timeout: Option<Duration>,
+) -> Option<Result<TrackMetadata, String>> {
+    match timeout {
+        None => Some(cached_track_metadata(path, options)),
+        Some(timeout) => {
+            let (path, options) = (path.to_string(), options.clone());
+            with_timeout(timeout, move || cached_track_metadata(&path, &options))
+        }
+    }
+}
+
+/// 把遍历结果展开为要读取的文件：(分组路径, 文件路径)
+///
+/// 压缩包展开为其中的音频条目，分组路径为去掉扩展名的压缩包路径下的条目，
+/// 按文件夹创建播放列表时每个压缩包成为一个播放列表。
+fn expand_entries(entries: &[DirEntry], progress: &ScanProgress) -> Vec<(PathBuf, String)> {
+    let mut files = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let path = entry.path();
+        let file_path = path.to_string_lossy().to_string();
+        if !is_archive(path) {
+            files.push((path.to_path_buf(), file_path));
+            continue;
+        }
+        match archive_entries(&file_path) {
+            Ok(names) => {
+                let group = path.with_extension("");
+                files.extend(names.iter().map(|name| {
+                    let file_name = name.rsplit('/').next().unwrap_or(name);
+                    (group.join(file_name), archive_entry_path(&file_path, name))
+                }));
+            }
+            Err(e) => {
+                progress.warn(path, tr!("archive.readFailed", path = file_path, detail = e));
+                progress.fail(&file_path, e.to_string());
+            }
+        }
+    }
+    files
+}
+
+/// 并行读取元数据，取消后剩余文件被跳过；使用电池时只用一个线程（见 [`power::run_background`]）
+///
+/// `root` 位于网络共享上时每个文件的读取受超时限制，超时的文件记录为警告；连续多个文件超时后
+/// 视为共享已断开，剩余文件不再读取。有文件超时的根目录会被标记为未完整读取。
+fn read_tracks(
+    root: &Path,
+    entries: &[DirEntry],
+    options: &MetadataOptions,
+    filter: &ScanFilter,
+    progress: &ScanProgress,
+    cancel: &CancelToken,
+) -> Vec<(PathBuf, TrackMetadata)> {
+    let timeout = is_network_path(root).then(|| filter.network_timeout());
+    let consecutive_timeouts = AtomicUsize::new(0);
+    let entries = expand_entries(entries, progress);
+    power::run_background(|| {
+        entries
+            .into_par_iter()
+            .filter_map(|(group, file_path)| {
+                if cancel.is_cancelled() || consecutive_timeouts.load(Ordering::Relaxed) >= MAX_CONSECUTIVE_TIMEOUTS {
+                    return None;
+                }
+                let metadata = if let Some(result) = read_track(&file_path, options, timeout) {
+                    consecutive_timeouts.store(0, Ordering::Relaxed);
+                    result.map_err(|e| progress.fail(&file_path, e)).ok()
+                } else {
+                    let seconds = filter.network_timeout().as_secs();
+                    let message = tr!("scan.fileTimeout", seconds = seconds, path = file_path);
+                    progress.warn(Path::new(&file_path), message);
+                    progress.fail(&file_path, tr!("scan.readTimeout", seconds = seconds));
+                    progress.mark_incomplete(root);
+                    if consecutive_timeouts.fetch_add(1, Ordering::Relaxed) + 1 == MAX_CONSECUTIVE_TIMEOUTS {
+                        let message = tr!("scan.networkDisconnected", path = root.display());
+                        progress.warn(root, message);
+                    }
+                    None
+                };
+                progress.processed.fetch_add(1, Ordering::Relaxed);
+                *progress.current.lock().unwrap() = Some(file_path);
+                metadata.map(|metadata| (group, metadata))
+            })
+            .collect()
+    })
+}
+
+fn dir_playlist_name(dir: &Path) -> String {
+    dir.file_name()
+        .map_or_else(|| tr!("common.unknown"), |s| s.to_string_lossy().to_string())
+}
+
 /// 获取指定目录中的所有音频文件，并创建播放列表
-pub fn get_audio_files_from_dir(path: &str) -> Result<Playlist, String> {
+pub fn get_audio_files_from_dir(
+    path: &str,
+    options: &MetadataOptions,
+    filter: &ScanFilter,
+) -> Result<Playlist, String> {
+    scan_audio_dir(path, options, filter, &ScanProgress::default(), &CancelToken::default()).map_err(String::from)
+}
+
+/// 扫描单个目录并创建播放列表，可报告进度和取消（取消时返回已读取的部分）
+///
+/// 目录位于无法访问的网络共享上时很快返回 `NetworkUnavailable`，不会等待系统的网络超时。
+pub fn scan_audio_dir(
+    path: &str,
+    options: &MetadataOptions,
+    filter: &ScanFilter,
+    progress: &ScanProgress,
+    cancel: &CancelToken,
+) -> AppResult<Playlist> {
     let dir = Path::new(path);
-    if !dir.is_dir() {
-        return Err("Provided path is not a directory".to_string());
-    }
+    probe_dir(dir)?;
+    clear_folder_cover_cache();
 
-    let audio_files: Vec<_> = WalkDir::new(dir)
+    let audio_files = walk_audio_files(dir, None, filter, progress, cancel);
+    let tracks = read_tracks(dir, &audio_files, options, filter, progress, cancel)
         .into_iter()
-        .filter_map(Result::ok)
-        .filter(is_audio_file)
-        .collect();
-
-    let tracks: Vec<_> = audio_files
-        .par_iter()
-        .filter_map(|entry| {
-            let file_path = entry.path().to_string_lossy().to_string();
-            get_track_metadata_internal(&file_path)
-                .map_err(|e| eprintln!("Failed to get metadata for file '{file_path}': {e}"))
-                .ok()
-        })
+        .map(|(_, metadata)| metadata)
         .collect();
 
-    let playlist_name = dir
-        .file_name()
-        .map_or_else(|| "Unknown".to_string(), |s| s.to_string_lossy().to_string());
-
     Ok(Playlist {
-        name: playlist_name,
+        name: dir_playlist_name(dir),
         files: tracks,
     })
 }
 
 /// 获取多个目录中的所有音频文件，并创建播放列表
-pub fn get_all_audio_files_from_dirs(paths: &[String], config: &AppConfig) -> Result<Vec<Playlist>, String> {
+pub fn get_all_audio_files_from_dirs(
+    paths: &[String],
+    config: &AppConfig,
+    include_covers: bool,
+) -> Result<Vec<Playlist>, String> {
+    scan_audio_dirs(paths, config, include_covers, &ScanProgress::default(), &CancelToken::default())
+        .map_err(String::from)
+}
+
+/// 扫描多个目录并创建播放列表，可报告进度和取消（取消时返回已读取的部分）
+///
+/// 无法访问的网络目录记录为警告并跳过；所有目录都是无法访问的网络目录时返回 `NetworkUnavailable`。
+pub fn scan_audio_dirs(
+    paths: &[String],
+    config: &AppConfig,
+    include_covers: bool,
+    progress: &ScanProgress,
+    cancel: &CancelToken,
+) -> AppResult<Vec<Playlist>> {
     let mut all_playlists: Vec<Playlist> = Vec::new();
+    let options = MetadataOptions::from_config(config).with_covers(include_covers);
+    let filter = ScanFilter::from_config(config);
+    clear_folder_cover_cache();
 
+    let mut unavailable = Vec::new();
     for path in paths {
+        if cancel.is_cancelled() {
+            break;
+        }
         let dir = Path::new(path);
-        if !dir.is_dir() {
-            eprintln!("Provided path is not a directory: {path}");
-            continue;
+        match probe_dir(dir) {
+            Ok(()) => {}
+            Err(AppError::NetworkUnavailable(_)) => {
+                progress.warn(dir, tr!("scan.networkUnavailable", path = path));
+                progress.mark_incomplete(dir);
+                unavailable.push(path.as_str());
+                continue;
+            }
+            Err(_) => {
+                eprintln!("Provided path is not a directory: {path}");
+                continue;
+            }
         }
 
         if config.directory_scan.enable_subdirectory_scan && config.playlist.folder_based_playlists {
-            let playlists = scan_with_folder_playlists(dir, config.directory_scan.max_depth as usize);
-            all_playlists.extend(playlists);
-        } else if let Some(playlist) = scan_single_playlist(dir) {
+            let max_depth = config.directory_scan.max_depth as usize;
+            all_playlists.extend(scan_with_folder_playlists(dir, max_depth, &options, &filter, progress, cancel));
+        } else if let Some(playlist) = scan_single_playlist(dir, &options, &filter, progress, cancel) {
             all_playlists.push(playlist);
         }
+        if !cancel.is_cancelled() {
+            progress.completed_roots.lock().unwrap().push(path.clone());
+        }
     }
 
+    if !unavailable.is_empty() && unavailable.len() == paths.len() {
+        return Err(AppError::NetworkUnavailable(unavailable.join(", ")));
+    }
     Ok(all_playlists)
 }
 
 /// 扫描目录并按文件夹创建播放列表
-fn scan_with_folder_playlists(dir: &Path, max_depth: usize) -> Vec<Playlist> {
-    let audio_files: Vec<_> = WalkDir::new(dir)
-        .max_depth(max_depth)
-        .into_iter()
-        .filter_map(Result::ok)
-        .filter(is_audio_file)
-        .collect();
-
-    let tracks_with_folders: Vec<_> = audio_files
-        .par_iter()
-        .filter_map(|entry| {
-            let parent_dir = entry.path().parent().unwrap_or(dir);
-            let folder_name = parent_dir
-                .file_name()
-                .and_then(|name| name.to_str())
-                .unwrap_or("Unknown")
-                .to_string();
-
-            let file_path = entry.path().to_string_lossy().to_string();
-            get_track_metadata_internal(&file_path)
-                .map(|metadata| (folder_name, metadata))
-                .ok()
-        })
-        .collect();
+fn scan_with_folder_playlists(
+    dir: &Path,
+    max_depth: usize,
+    options: &MetadataOptions,
+    filter: &ScanFilter,
+    progress: &ScanProgress,
+    cancel: &CancelToken,
+) -> Vec<Playlist> {
+    let audio_files = walk_audio_files(dir, Some(max_depth), filter, progress, cancel);
 
     let mut folder_playlists: HashMap<String, Playlist> = HashMap::new();
-    for (folder_name, metadata) in tracks_with_folders {
+    for (file_path, metadata) in read_tracks(dir, &audio_files, options, filter, progress, cancel) {
+        let folder_name = file_path
+            .parent()
+            .unwrap_or(dir)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .map_or_else(|| tr!("common.unknown"), str::to_string);
         folder_playlists
             .entry(folder_name.clone())
             .or_insert_with(|| Playlist::new(folder_name))
@@ -125,40 +578,70 @@ fn scan_with_folder_playlists(dir: &Path, max_depth: usize) -> Vec<Playlist> {
 }
 
 /// 扫描目录创建单个播放列表
-fn scan_single_playlist(dir: &Path) -> Option<Playlist> {
-    let playlist_name = dir
-        .file_name()
-        .map_or_else(|| "Unknown".to_string(), |s| s.to_string_lossy().to_string());
-
-    let audio_files: Vec<_> = WalkDir::new(dir)
+fn scan_single_playlist(
+    dir: &Path,
+    options: &MetadataOptions,
+    filter: &ScanFilter,
+    progress: &ScanProgress,
+    cancel: &CancelToken,
+) -> Option<Playlist> {
+    let audio_files = walk_audio_files(dir, None, filter, progress, cancel);
+    let tracks: Vec<_> = read_tracks(dir, &audio_files, options, filter, progress, cancel)
         .into_iter()
-        .filter_map(Result::ok)
-        .filter(is_audio_file)
-        .collect();
-
-    let tracks: Vec<_> = audio_files
-        .par_iter()
-        .filter_map(|entry| {
-            let file_path = entry.path().to_string_lossy().to_string();
-            get_track_metadata_internal(&file_path).ok()
-        })
+        .map(|(_, metadata)| metadata)
         .collect();
 
     if tracks.is_empty() {
         None
     } else {
         Some(Playlist {
-            name: playlist_name,
+            name: dir_playlist_name(dir),
             files: tracks,
         })
     }
 }
 
-/// 检查文件是否存在
+/// 收集输入中的所有音频文件路径
+///
+/// 目录会被递归遍历，单个文件只在扩展名受支持时保留。
+#[must_use]
+pub fn collect_audio_paths(inputs: &[String]) -> Vec<String> {
+    collect_audio_paths_filtered(inputs, &ScanFilter::default())
+}
+
+/// 按过滤规则收集输入中的所有音频文件路径，被排除的目录不会被进入，压缩包展开为其中的条目
+#[must_use]
+pub fn collect_audio_paths_filtered(inputs: &[String], filter: &ScanFilter) -> Vec<String> {
+    let mut paths = Vec::new();
+    for input in inputs {
+        let path = Path::new(input);
+        if path.is_dir() {
+            let progress = ScanProgress::default();
+            let entries = walk_audio_files(path, None, filter, &progress, &CancelToken::default());
+            paths.extend(expand_entries(&entries, &progress).into_iter().map(|(_, file_path)| file_path));
+        } else if path.is_file() && has_audio_extension(path) {
+            paths.push(input.clone());
+        }
+    }
+    paths
+}
+
+/// 检查文件是否存在，压缩包中的文件只检查压缩包本身
 #[must_use]
 pub fn check_file_exists_internal(path: &str) -> bool {
+    if let Some((zip_path, _)) = split_archive_path(path) {
+        return Path::new(zip_path).is_file();
+    }
+    existing_path(path).is_some()
+}
+
+/// 磁盘上实际存在的路径写法
+///
+/// `TrackMetadata.path` 统一使用 `\` 分隔，原样找不到时换用另一种分隔符再试一次。
+#[must_use]
+pub fn existing_path(path: &str) -> Option<String> {
     if Path::new(path).exists() {
-        return true;
+        return Some(path.to_string());
     }
 
     // 尝试另一种路径分隔符格式
@@ -168,31 +651,326 @@ pub fn check_file_exists_internal(path: &str) -> bool {
         path.replace('\\', "/")
     };
 
-    alt_path != path && Path::new(&alt_path).exists()
+    (alt_path != path && Path::new(&alt_path).exists()).then_some(alt_path)
+}
+
+/// 歌词文件内容
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LyricsFileContent {
+    /// 解码后的文本
+    pub content: String,
+    /// 检测到的编码名称（如 "UTF-8"、"GBK"、"UTF-16LE"）
+    pub encoding: String,
+    pub had_bom: bool,
+    /// 修改时间（毫秒），写入时传回用于检测外部修改
+    pub modified: u64,
+}
+
+/// 文件修改时间（自 Unix 纪元起的毫秒数），无法读取时为 0
+fn modified_millis(metadata: &fs::Metadata) -> u64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |duration| u64::try_from(duration.as_millis()).unwrap_or(u64::MAX))
 }
 
 /// 读取歌词文件内容
-pub fn read_lyrics_file_internal(path: &str) -> Result<String, String> {
-    fs::read_to_string(path).map_err(|e| e.to_string())
+///
+/// 按 BOM 和内容检测编码（GBK、Big5、Shift_JIS、UTF-16 等），解码为 UTF-8 后返回，BOM 不包含在文本中。
+pub fn read_lyrics_file_internal(path: &str) -> Result<LyricsFileContent, String> {
+    let file_path = long_path(path);
+    let bytes = fs::read(&file_path).map_err(|e| e.to_string())?;
+    let modified = fs::metadata(&file_path).map(|metadata| modified_millis(&metadata)).unwrap_or(0);
+    let decoded = decode_text(&bytes);
+    Ok(LyricsFileContent {
+        content: decoded.text,
+        encoding: decoded.encoding.to_string(),
+        had_bom: decoded.had_bom,
+        modified,
+    })
 }
 
-/// 写入歌词文件内容
-pub fn write_lyrics_file_internal(path: &str, content: &str) -> Result<(), String> {
+/// 写入歌词文件内容，返回写入后的修改时间（毫秒）
+///
+/// 默认以不带 BOM 的 UTF-8 写入；`preserve_encoding` 为 `true` 且文件已存在时沿用原文件的编码和 BOM，
+/// 内容中有原编码无法表示的字符时返回错误，原文件保持不变。
+///
+/// 传入 `expected_modified`（读取时得到的修改时间）且文件此后被修改过时返回 `ContentConflict`，
+/// 附带磁盘上的当前内容。先写入同目录下的临时文件再替换原文件，覆盖前把原内容保存为 `<文件名>.bak`（只保留一份）。
+pub fn write_lyrics_file_internal(
+    path: &str,
+    content: &str,
+    preserve_encoding: bool,
+    expected_modified: Option<u64>,
+) -> AppResult<u64> {
+    let file_path = Path::new(path);
     // 确保父目录存在
-    if let Some(parent) = Path::new(path).parent()
+    if let Some(parent) = file_path.parent()
         && !parent.exists()
     {
-        fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory: {e}"))?;
+        fs::create_dir_all(long_path(parent))?;
+    }
+
+    let target = long_path(file_path);
+    let existing = match fs::read(&target) {
+        Ok(bytes) => Some(bytes),
+        Err(e) if e.kind() == ErrorKind::NotFound => None,
+        Err(e) => return Err(e.into()),
+    };
+
+    if let (Some(expected), Some(existing)) = (expected_modified, &existing) {
+        let modified = modified_millis(&fs::metadata(&target)?);
+        if modified != expected {
+            return Err(AppError::ContentConflict {
+                path: path.to_string(),
+                current: decode_text(existing).text,
+                modified,
+            });
+        }
+    }
+
+    let bytes = match existing.as_deref().filter(|_| preserve_encoding) {
+        Some(existing) => {
+            let (encoding, had_bom) = detect_encoding(existing);
+            encode_with(content, encoding.name(), had_bom)?
+        }
+        None => content.as_bytes().to_vec(),
+    };
+
+    if let Some(existing) = &existing {
+        let file_name = file_path.file_name().unwrap_or_default().to_string_lossy();
+        fs::write(long_path(file_path.with_file_name(format!("{file_name}.bak"))), existing)?;
     }
-    
-    fs::write(path, content).map_err(|e| format!("Failed to write file: {e}"))
+
+    let temp_path = long_path(temp_path_for(file_path));
+    mark_self_write(file_path);
+    let written = File::create(&temp_path)
+        .and_then(|mut file| file.write_all(&bytes).and_then(|()| file.sync_all()))
+        .and_then(|()| fs::rename(&temp_path, &target));
+    if let Err(e) = written {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e.into());
+    }
+    mark_self_write(file_path);
+
+    Ok(fs::metadata(&target).map(|metadata| modified_millis(&metadata)).unwrap_or(0))
 }
 
 /// 检查是否为音频文件
 fn is_audio_file(entry: &DirEntry) -> bool {
-    entry
-        .path()
-        .extension()
-        .and_then(|ext| ext.to_str())
-        .is_some_and(|ext| AUDIO_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+    has_audio_extension(entry.path())
+}
+
+/// 检查路径的扩展名是否为支持的音频格式
+///
+/// 扫描时每个文件都会调用，比较时不分配内存。
+#[must_use]
+pub fn has_audio_extension(path: &Path) -> bool {
+    let Some(ext) = path.extension().and_then(|ext| ext.to_str()) else {
+        return false;
+    };
+    SUPPORTED_EXTENSIONS.read().unwrap().iter().any(|supported| supported.eq_ignore_ascii_case(ext))
+}
+
+/// 当前支持的音频文件扩展名
+#[must_use]
+pub fn supported_extensions() -> Vec<String> {
+    SUPPORTED_EXTENSIONS.read().unwrap().clone()
+}
+
+/// 检查扩展名列表：不能为空，每一项都必须是不含点和空白的小写扩展名；返回去重后的列表
+pub fn validate_extensions(extensions: &[String]) -> Result<Vec<String>, String> {
+    if extensions.is_empty() {
+        return Err(tr!("extensions.empty"));
+    }
+    let mut validated: Vec<String> = Vec::with_capacity(extensions.len());
+    for ext in extensions {
+        if ext.is_empty() || ext.contains('.') || ext.chars().any(char::is_whitespace) {
+            return Err(tr!("extensions.invalid", ext = ext));
+        }
+        if ext.chars().any(char::is_uppercase) {
+            return Err(tr!("extensions.notLowercase", ext = ext));
+        }
+        if !validated.contains(ext) {
+            validated.push(ext.clone());
+        }
+    }
+    Ok(validated)
+}
+
+/// 使用配置中的扩展名列表，之后的扫描和监视立即生效
+///
+/// 手动编辑的配置可能带点或大写，这里统一处理；处理后为空时恢复默认列表。
+pub fn apply_extensions(extensions: &[String]) {
+    let mut normalized: Vec<String> = Vec::with_capacity(extensions.len());
+    for ext in extensions {
+        let ext = ext.trim().trim_start_matches('.').to_lowercase();
+        if !ext.is_empty() && !normalized.contains(&ext) {
+            normalized.push(ext);
+        }
+    }
+    if normalized.is_empty() {
+        normalized = AUDIO_EXTENSIONS.iter().map(|ext| (*ext).to_string()).collect();
+    }
+    *SUPPORTED_EXTENSIONS.write().unwrap() = normalized;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[cfg(unix)]
+    fn symlink_dir(target: &Path, link: &Path) -> std::io::Result<()> {
+        std::os::unix::fs::symlink(target, link)
+    }
+
+    /// 没有开发者模式或管理员权限时无法创建，调用方跳过测试
+    #[cfg(windows)]
+    fn symlink_dir(target: &Path, link: &Path) -> std::io::Result<()> {
+        std::os::windows::fs::symlink_dir(target, link)
+    }
+
+    fn filter(follow_symlinks: bool) -> ScanFilter {
+        let mut config = AppConfig::default();
+        config.directory_scan.follow_symlinks = follow_symlinks;
+        ScanFilter::from_config(&config)
+    }
+
+    /// `a` 和 `b` 互相链接，`a` 中还有指向根目录的链接；不能创建符号链接时返回 `None`
+    fn linked_library() -> Option<TempDir> {
+        let dir = TempDir::new().unwrap();
+        let (a, b) = (dir.path().join("a"), dir.path().join("b"));
+        fs::create_dir_all(&a).unwrap();
+        fs::create_dir_all(&b).unwrap();
+        fs::write(a.join("one.flac"), b"").unwrap();
+        fs::write(b.join("two.flac"), b"").unwrap();
+        symlink_dir(&b, &a.join("to_b")).ok()?;
+        symlink_dir(&a, &b.join("to_a")).ok()?;
+        symlink_dir(dir.path(), &a.join("root")).ok()?;
+        Some(dir)
+    }
+
+    fn scan(dir: &TempDir, follow_symlinks: bool) -> (Vec<String>, Vec<ScanWarning>) {
+        let progress = ScanProgress::default();
+        let files = walk_audio_files(dir.path(), None, &filter(follow_symlinks), &progress, &CancelToken::default());
+        let mut names: Vec<String> = files.iter().map(|e| e.file_name().to_string_lossy().to_string()).collect();
+        names.sort();
+        (names, progress.take_warnings())
+    }
+
+    #[test]
+    fn symlink_cycles_terminate_with_deduplicated_files() {
+        let Some(dir) = linked_library() else {
+            return;
+        };
+
+        let (names, warnings) = scan(&dir, true);
+        assert_eq!(names, ["one.flac", "two.flac"]);
+        // 两个指向上级目录的链接由 WalkDir 报告为循环，经由链接先到达的目录在第二次到达时被跳过
+        assert_eq!(warnings.len(), 3, "{warnings:?}");
+    }
+
+    #[test]
+    fn links_are_not_followed_by_default() {
+        let Some(dir) = linked_library() else {
+            return;
+        };
+
+        let (names, warnings) = scan(&dir, false);
+        assert_eq!(names, ["one.flac", "two.flac"]);
+        assert!(warnings.is_empty());
+    }
+
+    /// 各编码的歌词文件样本：(文件名, 编码, 是否带 BOM, 内容)
+    const LYRICS_FIXTURES: &[(&str, &str, bool, &str)] = &[
+        (
+            "gbk.lrc",
+            "GBK",
+            false,
+            "[ti:Qing Tian]\n[00:29.10]故事的小黄花，从出生那年就飘着，\n\
+             [00:36.20]童年的荡秋千，随记忆一直晃到现在\n",
+        ),
+        (
+            "big5.lrc",
+            "Big5",
+            false,
+            "[ti:Qing Tian]\n[01:20.00]刮風這天，我試過握著你手，\n[01:27.10]但偏偏雨漸漸大到我看你不見\n",
+        ),
+        (
+            "sjis.lrc",
+            "Shift_JIS",
+            false,
+            "[ti:Itsumo Nando Demo]\n[00:15.00]いつも何度でも呼んでいる、胸のどこか奥で。\n\
+             [00:22.30]いつも心躍る夢を見たい\n",
+        ),
+        ("utf16le.lrc", "UTF-16LE", true, "[ti:Yesterday]\n[00:01.00]Yesterday 昨日\n[00:05.00]歌词 Lyrics\n"),
+        ("utf8bom.lrc", "UTF-8", true, "[ti:Ünïcödé]\n[00:01.00]歌词 Lyrics\n"),
+    ];
+
+    fn write_fixture(dir: &TempDir, name: &str, encoding: &str, with_bom: bool, content: &str) -> String {
+        let path = dir.path().join(name);
+        fs::write(&path, encode_with(content, encoding, with_bom).unwrap()).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn lyrics_files_are_decoded_from_their_detected_encoding() {
+        let dir = TempDir::new().unwrap();
+        for &(name, encoding, with_bom, content) in LYRICS_FIXTURES {
+            let path = write_fixture(&dir, name, encoding, with_bom, content);
+            let read = read_lyrics_file_internal(&path).unwrap();
+            assert_eq!(read.content, content, "{name}");
+            assert_eq!(read.encoding, encoding, "{name}");
+            assert_eq!(read.had_bom, with_bom, "{name}");
+            assert!(read.modified > 0);
+        }
+    }
+
+    #[test]
+    fn lyrics_are_written_as_utf8_by_default() {
+        let dir = TempDir::new().unwrap();
+        let (name, encoding, with_bom, content) = LYRICS_FIXTURES[0];
+        let path = write_fixture(&dir, name, encoding, with_bom, content);
+        let edited = content.replace("小黄花", "小黄花 (live)");
+
+        write_lyrics_file_internal(&path, &edited, false, None).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), edited.as_bytes());
+        // 覆盖前的原文件保存为 .bak
+        assert_eq!(fs::read(format!("{path}.bak")).unwrap(), encode_with(content, encoding, false).unwrap());
+    }
+
+    #[test]
+    fn lyrics_can_keep_the_original_encoding() {
+        let dir = TempDir::new().unwrap();
+        for &(name, encoding, with_bom, content) in LYRICS_FIXTURES {
+            let path = write_fixture(&dir, name, encoding, with_bom, content);
+            let edited = format!("{content}[03:00.00]~\n");
+            write_lyrics_file_internal(&path, &edited, true, None).unwrap();
+            assert_eq!(fs::read(&path).unwrap(), encode_with(&edited, encoding, with_bom).unwrap(), "{name}");
+        }
+    }
+
+    #[test]
+    fn unrepresentable_lyrics_leave_the_file_unchanged() {
+        let dir = TempDir::new().unwrap();
+        let (name, encoding, with_bom, content) = LYRICS_FIXTURES[2];
+        let path = write_fixture(&dir, name, encoding, with_bom, content);
+
+        assert!(write_lyrics_file_internal(&path, "[00:01.00]🎵 한국어", true, None).is_err());
+        assert_eq!(read_lyrics_file_internal(&path).unwrap().content, content);
+    }
+
+    #[test]
+    fn lyrics_modified_elsewhere_are_reported_as_conflicts() {
+        let dir = TempDir::new().unwrap();
+        let (name, encoding, with_bom, content) = LYRICS_FIXTURES[0];
+        let path = write_fixture(&dir, name, encoding, with_bom, content);
+        let modified = read_lyrics_file_internal(&path).unwrap().modified;
+
+        let error = write_lyrics_file_internal(&path, "[00:01.00]new", false, Some(modified + 1)).unwrap_err();
+        assert!(matches!(error, AppError::ContentConflict { current, .. } if current == content));
+    }
 }
@@ -0,0 +1,180 @@
+//! 声学指纹模块
+//!
+//! 复用 Symphonia 解码路径，计算 AcoustID 使用的 Chromaprint 指纹，结果按文件哈希缓存。
+
+use crate::audio::SymphoniaDecoder;
+use crate::error::{AppError, AppResult};
+use crate::paths::long_path;
+use crate::tasks::CancelToken;
+use crate::tr;
+use base64::{engine::general_purpose, Engine as _};
+use rodio::Source;
+use rusty_chromaprint::{Configuration, FingerprintCompressor, Fingerprinter};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// 参与指纹计算的最大时长（秒）
+const MAX_ANALYZE_SECS: u32 = 120;
+/// 每处理多少个采样检查一次取消标志
+const CANCEL_CHECK_INTERVAL: usize = 1 << 16;
+
+/// 指纹计算结果
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FingerprintResult {
+    /// 压缩并 Base64 编码后的指纹（AcoustID 格式）
+    pub fingerprint: String,
+    /// 实际分析的时长（秒）
+    pub analyzed_duration: f64,
+    /// 文件总时长（秒）
+    pub duration: Option<f64>,
+    /// 文件内容的 MD5
+    pub file_hash: String,
+}
+
+/// 批量计算中单个文件的结果
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FingerprintBatchItem {
+    pub path: String,
+    pub result: Option<FingerprintResult>,
+    pub error: Option<String>,
+}
+
+/// 计算文件内容的 MD5
+pub fn file_hash(path: &Path) -> Result<String, String> {
+    let mut file = File::open(long_path(path)).map_err(|e| tr!("file.openFailed", detail = e))?;
+    let mut context = md5::Context::new();
+    let mut buffer = vec![0u8; 1 << 16];
+    loop {
+        let read = file.read(&mut buffer).map_err(|e| tr!("file.readFailed", detail = e))?;
+        if read == 0 {
+            break;
+        }
+        context.consume(&buffer[..read]);
+    }
+    Ok(format!("{:x}", context.compute()))
+}
+
+/// 指纹缓存（cache/fingerprint/fingerprints.json）
+pub struct FingerprintCache {
+    path: PathBuf,
+    entries: HashMap<String, FingerprintResult>,
+}
+
+impl FingerprintCache {
+    /// 从缓存目录加载，文件不存在或损坏时返回空缓存
+    #[must_use]
+    pub fn load(cache_dir: &Path) -> Self {
+        let path = cache_dir.join("fingerprints.json");
+        let entries = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self { path, entries }
+    }
+
+    #[must_use]
+    pub fn get(&self, hash: &str) -> Option<&FingerprintResult> {
+        self.entries.get(hash)
+    }
+
+    pub fn insert(&mut self, result: FingerprintResult) {
+        self.entries.insert(result.file_hash.clone(), result);
+    }
+
+    /// 先写临时文件再重命名，写入中断时不会留下不完整的缓存
+    pub fn save(&self) -> Result<(), String> {
+        let content = serde_json::to_string(&self.entries).map_err(|e| tr!("fingerprint.cacheSerializeFailed", detail = e))?;
+        let temp_path = self.path.with_extension("json.tmp");
+        fs::write(&temp_path, content)
+            .and_then(|()| fs::rename(&temp_path, &self.path))
+            .map_err(|e| tr!("fingerprint.cacheWriteFailed", detail = e))
+    }
+}
+
+/// 计算音频文件的指纹
+///
+/// 解码前 120 秒为单声道 PCM 交给 Chromaprint，重采样由 Chromaprint 内部完成。
+pub fn compute_fingerprint_internal(path: &str, cancel: &CancelToken) -> AppResult<FingerprintResult> {
+    let hash = file_hash(Path::new(path))?;
+    compute_with_hash(path, hash, cancel)
+}
+
+fn compute_with_hash(path: &str, file_hash: String, cancel: &CancelToken) -> AppResult<FingerprintResult> {
+    let decoder = SymphoniaDecoder::new(path).map_err(AppError::AudioDecoder)?;
+    let sample_rate = decoder.sample_rate();
+    let channels = usize::from(decoder.channels().max(1));
+    let duration = decoder.total_duration().map(|d| d.as_secs_f64());
+
+    let config = Configuration::preset_test2();
+    let mut printer = Fingerprinter::new(&config);
+    printer
+        .start(sample_rate, 1)
+        .map_err(|e| AppError::AudioDecoder(tr!("fingerprint.initFailed", detail = format!("{e:?}"))))?;
+
+    let max_frames = (sample_rate * MAX_ANALYZE_SECS) as usize;
+    let mut frames = 0usize;
+    let mut chunk: Vec<i16> = Vec::with_capacity(4096);
+    let mut frame_sum = 0.0f32;
+    let mut frame_channel = 0usize;
+
+    for sample in decoder {
+        frame_sum += sample;
+        frame_channel += 1;
+        if frame_channel < channels {
+            continue;
+        }
+
+        let mono = frame_sum / channels as f32;
+        chunk.push((mono.clamp(-1.0, 1.0) * f32::from(i16::MAX)) as i16);
+        frame_sum = 0.0;
+        frame_channel = 0;
+        frames += 1;
+
+        if chunk.len() == chunk.capacity() {
+            printer.consume(&chunk);
+            chunk.clear();
+        }
+        if frames.is_multiple_of(CANCEL_CHECK_INTERVAL) {
+            cancel.check()?;
+        }
+        if frames >= max_frames {
+            break;
+        }
+    }
+    printer.consume(&chunk);
+    printer.finish();
+
+    if frames == 0 {
+        return Err(AppError::AudioDecoder(tr!("hashing.noAudio")));
+    }
+
+    let compressed = FingerprintCompressor::from(&config).compress(printer.fingerprint());
+
+    Ok(FingerprintResult {
+        fingerprint: general_purpose::URL_SAFE_NO_PAD.encode(compressed),
+        analyzed_duration: frames as f64 / f64::from(sample_rate),
+        duration,
+        file_hash,
+    })
+}
+
+/// 优先从缓存读取指纹，未命中时计算并写入缓存
+pub fn fingerprint_with_cache(
+    path: &str,
+    cache: &mut FingerprintCache,
+    cancel: &CancelToken,
+) -> AppResult<FingerprintResult> {
+    let hash = file_hash(Path::new(path))?;
+    if let Some(cached) = cache.get(&hash) {
+        return Ok(cached.clone());
+    }
+
+    let result = compute_with_hash(path, hash, cancel)?;
+    cache.insert(result.clone());
+    Ok(result)
+}
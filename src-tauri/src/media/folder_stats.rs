@@ -0,0 +1,115 @@
+//! 文件夹统计模块
+//!
+//! 统计目录中的音频文件数、总大小、总时长以及各格式的数量和大小。
+//! 已扫描到曲库中的目录直接查询数据库，结果按数据库版本缓存，监视器写入数据库后自动失效；
+//! 未扫描的目录按扫描器的过滤规则遍历，并读取每个文件的元数据。
+
+use super::filesystem::collect_audio_paths_filtered;
+use super::metadata::MetadataOptions;
+use super::metadata_cache::cached_track_metadata;
+use super::scan_filter::ScanFilter;
+use crate::error::AppResult;
+use crate::library::LibraryDb;
+use crate::paths::canonical_key;
+use crate::tasks::CancelToken;
+use rayon::prelude::*;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{LazyLock, Mutex};
+
+/// 已扫描目录的统计缓存：规范化路径 -> (数据库版本, 统计)
+static FOLDER_STATS: LazyLock<Mutex<HashMap<String, (u64, FolderStats)>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// 单个格式的统计
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FormatStats {
+    /// 小写的扩展名，如 `flac`
+    pub format: String,
+    pub count: usize,
+    /// 总大小（字节）
+    pub size: u64,
+}
+
+/// 文件夹统计
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct FolderStats {
+    pub path: String,
+    pub tracks: usize,
+    /// 总大小（字节）
+    pub total_size: u64,
+    /// 总时长（秒），读不到时长的文件不计入
+    pub total_duration: f64,
+    /// 按文件数从多到少排列
+    pub formats: Vec<FormatStats>,
+    /// 结果是否来自曲库数据库
+    pub indexed: bool,
+}
+
+impl FolderStats {
+    fn collect(path: &str, indexed: bool, files: impl IntoIterator<Item = (String, u64, Option<f64>)>) -> Self {
+        let mut stats = Self { path: path.to_string(), indexed, ..Self::default() };
+        let mut formats: HashMap<String, (usize, u64)> = HashMap::new();
+        for (file, size, duration) in files {
+            stats.tracks += 1;
+            stats.total_size += size;
+            stats.total_duration += duration.unwrap_or(0.0);
+            let format = Path::new(&file.replace('\\', "/"))
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            let entry = formats.entry(format).or_default();
+            entry.0 += 1;
+            entry.1 += size;
+        }
+        stats.formats = formats.into_iter().map(|(format, (count, size))| FormatStats { format, count, size }).collect();
+        stats.formats.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.format.cmp(&b.format)));
+        stats
+    }
+}
+
+/// 统计目录
+///
+/// 遍历目录时每读取一个文件调用一次 `on_progress(completed, total, path)`，取消时返回 `Cancelled`。
+pub fn folder_stats_internal(
+    db: &LibraryDb,
+    path: &str,
+    filter: &ScanFilter,
+    options: &MetadataOptions,
+    cancel: &CancelToken,
+    on_progress: impl Fn(usize, usize, &str) + Sync,
+) -> AppResult<FolderStats> {
+    if db.is_indexed(path)? {
+        let key = canonical_key(path);
+        let revision = db.revision();
+        if let Some((cached_revision, stats)) = FOLDER_STATS.lock().unwrap().get(&key) {
+            if *cached_revision == revision {
+                return Ok(stats.clone());
+            }
+        }
+        let stats = FolderStats::collect(path, true, db.file_stats_under(path)?);
+        FOLDER_STATS.lock().unwrap().insert(key, (revision, stats.clone()));
+        return Ok(stats);
+    }
+
+    let files = collect_audio_paths_filtered(&[path.to_string()], filter);
+    cancel.check()?;
+    let completed = AtomicUsize::new(0);
+    let entries: Vec<(String, u64, Option<f64>)> = files
+        .par_iter()
+        .filter(|_| !cancel.is_cancelled())
+        .map(|file| {
+            let size = fs::metadata(file).map(|m| m.len()).unwrap_or(0);
+            let duration = cached_track_metadata(file, options).ok().and_then(|track| track.duration);
+            on_progress(completed.fetch_add(1, Ordering::Relaxed) + 1, files.len(), file);
+            (file.clone(), size, duration)
+        })
+        .collect();
+    cancel.check()?;
+    Ok(FolderStats::collect(path, false, entries))
+}
@@ -0,0 +1,142 @@
+//! 文件夹树模块
+//!
+//! 为浏览侧栏一次遍历构建目录树，每个目录带有直接包含的音频文件数和子树中是否有音频文件，
+//! 遍历时应用扫描器的过滤规则。超过 `max_depth` 的目录不展开，界面需要时再以该目录为根请求。
+
+use super::archive::is_archive;
+use super::filesystem::has_audio_extension;
+use super::network::probe_dir;
+use super::scan_filter::ScanFilter;
+use super::sorting::natural_cmp;
+use crate::error::AppResult;
+use crate::tasks::CancelToken;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// 目录树中的一个目录
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FolderNode {
+    pub path: String,
+    pub name: String,
+    /// 直接包含的音频文件数（不含子目录）
+    pub audio_count: usize,
+    /// 子树中是否有音频文件，界面据此隐藏空目录
+    pub has_audio: bool,
+    /// 是否有子目录；超过深度限制的目录 `children` 为空，但此项仍为 `true`
+    pub has_children: bool,
+    /// 是否能读取（无权限的目录为 `false`，其内容未知）
+    pub accessible: bool,
+    /// 子目录，按自然顺序排列
+    pub children: Vec<Self>,
+}
+
+/// 遍历中收集的目录信息
+#[derive(Default)]
+struct NodeInfo {
+    audio_count: usize,
+    has_audio: bool,
+    has_children: bool,
+    inaccessible: bool,
+    children: Vec<PathBuf>,
+}
+
+fn build(path: &Path, nodes: &mut HashMap<PathBuf, NodeInfo>) -> FolderNode {
+    let info = nodes.remove(path).unwrap_or_default();
+    let mut children: Vec<FolderNode> = info.children.iter().map(|child| build(child, nodes)).collect();
+    children.sort_by(|a, b| natural_cmp(&a.name, &b.name));
+    FolderNode {
+        path: path.to_string_lossy().to_string(),
+        name: path.file_name().map_or_else(|| path.to_string_lossy().to_string(), |n| n.to_string_lossy().to_string()),
+        audio_count: info.audio_count,
+        has_audio: info.has_audio,
+        has_children: info.has_children,
+        accessible: !info.inaccessible,
+        children,
+    }
+}
+
+/// 构建以 `root` 为根、最多 `max_depth` 层子目录的目录树
+///
+/// 超过深度限制的目录只用于判断上层目录的 `has_audio`，一旦确认有音频文件就不再深入。
+/// 无法读取的子目录标记为不可访问，不影响其他目录；根目录本身不可访问时返回错误。
+pub fn folder_tree_internal(
+    root: &str,
+    max_depth: usize,
+    filter: &ScanFilter,
+    cancel: &CancelToken,
+) -> AppResult<FolderNode> {
+    let root_path = PathBuf::from(root);
+    probe_dir(&root_path)?;
+
+    let mut nodes: HashMap<PathBuf, NodeInfo> = HashMap::new();
+    nodes.insert(root_path.clone(), NodeInfo::default());
+
+    let mut entries = WalkDir::new(&root_path)
+        .follow_links(filter.follow_symlinks())
+        .into_iter()
+        .filter_entry(|entry| filter.allows(entry));
+    while let Some(result) = entries.next() {
+        cancel.check()?;
+        let entry = match result {
+            Ok(entry) => entry,
+            Err(e) => {
+                // 符号链接循环不算无法访问
+                if e.loop_ancestor().is_none()
+                    && let Some(path) = e.path()
+                    && e.depth() <= max_depth
+                {
+                    nodes.entry(path.to_path_buf()).or_default().inaccessible = true;
+                }
+                continue;
+            }
+        };
+        let depth = entry.depth();
+        let path = entry.path();
+
+        if entry.file_type().is_dir() {
+            if depth == 0 {
+                continue;
+            }
+            let parent = path.parent().unwrap_or(&root_path).to_path_buf();
+            if depth <= max_depth {
+                nodes.entry(path.to_path_buf()).or_default();
+                let parent = nodes.entry(parent).or_default();
+                parent.has_children = true;
+                parent.children.push(path.to_path_buf());
+            } else {
+                if depth == max_depth + 1 {
+                    nodes.entry(parent).or_default().has_children = true;
+                }
+                // 被截断的上层目录已确认有音频文件时不必继续深入
+                let truncated = path.ancestors().nth(depth - max_depth);
+                if truncated.and_then(|dir| nodes.get(dir)).is_some_and(|info| info.has_audio) {
+                    entries.skip_current_dir();
+                }
+            }
+            continue;
+        }
+
+        if !(has_audio_extension(path) || (filter.scan_archives() && is_archive(path))) {
+            continue;
+        }
+        if let Some(parent) = path.parent().and_then(|parent| nodes.get_mut(parent)) {
+            parent.audio_count += 1;
+        }
+        for ancestor in path.ancestors().skip(1) {
+            if let Some(info) = nodes.get_mut(ancestor) {
+                if info.has_audio {
+                    break;
+                }
+                info.has_audio = true;
+            }
+            if ancestor == root_path {
+                break;
+            }
+        }
+    }
+
+    Ok(build(&root_path, &mut nodes))
+}
@@ -0,0 +1,341 @@
+//! 音频哈希与重复检测模块
+//!
+//! 文件哈希跳过 ID3v2/ID3v1/APEv2 和 FLAC 元数据块，只对音频数据做 SHA-256，
+//! 用于精确重复；音频哈希对解码后的 PCM 做 SHA-256，与标签和封装方式无关。
+
+use super::dsf::read_dsf_info;
+use super::lame::id3v2_size;
+use super::metadata::MetadataOptions;
+use super::metadata_cache::cached_track_metadata;
+use crate::audio::SymphoniaDecoder;
+use crate::error::{AppError, AppResult};
+use crate::paths::{canonical_key, long_path};
+use crate::tasks::CancelToken;
+use crate::tr;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// 每处理多少个采样检查一次取消标志
+const CANCEL_CHECK_INTERVAL: usize = 1 << 18;
+
+/// 哈希类型
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum HashKind {
+    /// 去掉标签后的文件字节
+    File,
+    /// 解码后的 PCM
+    Audio,
+}
+
+/// 重复组中的单个文件
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateFile {
+    pub path: String,
+    pub size: u64,
+    pub bitrate: Option<u32>,
+    pub format: Option<String>,
+}
+
+/// 共享同一哈希的一组文件，按码率从高到低排列
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateGroup {
+    pub hash: String,
+    pub files: Vec<DuplicateFile>,
+}
+
+/// 无法计算哈希的文件
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HashFailure {
+    pub path: String,
+    pub message: String,
+}
+
+/// 重复检测结果
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateReport {
+    pub groups: Vec<DuplicateGroup>,
+    pub failed: Vec<HashFailure>,
+}
+
+/// 缓存条目，文件大小或修改时间变化后失效
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+struct HashEntry {
+    modified: u64,
+    size: u64,
+    file_hash: Option<String>,
+    audio_hash: Option<String>,
+}
+
+/// 哈希缓存（cache/hash/hashes.json），以 `canonical_key` 为键，文件大小或修改时间变化后失效
+pub struct HashCache {
+    path: PathBuf,
+    entries: HashMap<String, HashEntry>,
+}
+
+/// 读取文件大小和修改时间（秒）
+fn file_stamp(path: &Path) -> Result<(u64, u64), String> {
+    let metadata = fs::metadata(path).map_err(|e| tr!("file.metadataFailed", detail = e))?;
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_secs());
+    Ok((modified, metadata.len()))
+}
+
+impl HashCache {
+    /// 从缓存目录加载，文件不存在或损坏时返回空缓存
+    ///
+    /// 旧版本的缓存以原始路径为键，加载时统一改为 `canonical_key`。
+    #[must_use]
+    pub fn load(cache_dir: &Path) -> Self {
+        let path = cache_dir.join("hashes.json");
+        let entries = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<HashMap<String, HashEntry>>(&content).ok())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(key, entry)| (canonical_key(&key), entry))
+            .collect();
+        Self { path, entries }
+    }
+
+    /// 先写临时文件再重命名，写入中断时不会留下不完整的缓存
+    pub fn save(&self) -> Result<(), String> {
+        let content = serde_json::to_string(&self.entries).map_err(|e| tr!("hashing.cacheSerializeFailed", detail = e))?;
+        let temp_path = self.path.with_extension("json.tmp");
+        fs::write(&temp_path, content)
+            .and_then(|()| fs::rename(&temp_path, &self.path))
+            .map_err(|e| tr!("hashing.cacheWriteFailed", detail = e))
+    }
+
+    /// 只从缓存读取哈希，不检查文件是否变化（用于已不存在的文件）
+    #[must_use]
+    pub fn cached(&self, path: &str, kind: HashKind) -> Option<&str> {
+        let entry = self.entries.get(&canonical_key(path))?;
+        match kind {
+            HashKind::File => entry.file_hash.as_deref(),
+            HashKind::Audio => entry.audio_hash.as_deref(),
+        }
+    }
+
+    /// 优先从缓存读取哈希，未命中或文件已变化时重新计算
+    pub fn get_or_compute(&mut self, path: &str, kind: HashKind, cancel: &CancelToken) -> AppResult<String> {
+        let (modified, size) = file_stamp(Path::new(path))?;
+        let entry = self.entries.entry(canonical_key(path)).or_default();
+        if entry.modified != modified || entry.size != size {
+            *entry = HashEntry { modified, size, ..Default::default() };
+        }
+
+        let slot = match kind {
+            HashKind::File => &mut entry.file_hash,
+            HashKind::Audio => &mut entry.audio_hash,
+        };
+        if let Some(hash) = slot {
+            return Ok(hash.clone());
+        }
+
+        let hash = compute_hash(path, kind, cancel)?;
+        *slot = Some(hash.clone());
+        Ok(hash)
+    }
+
+    /// 缓存中与文件当前大小和修改时间一致的哈希
+    #[must_use]
+    pub fn fresh(&self, path: &str, kind: HashKind) -> Option<String> {
+        let (modified, size) = file_stamp(Path::new(path)).ok()?;
+        let entry = self
+            .entries
+            .get(&canonical_key(path))
+            .filter(|entry| entry.modified == modified && entry.size == size)?;
+        match kind {
+            HashKind::File => entry.file_hash.clone(),
+            HashKind::Audio => entry.audio_hash.clone(),
+        }
+    }
+
+    /// 写入在别处计算好的哈希（用于并行计算）
+    pub fn insert(&mut self, path: &str, kind: HashKind, hash: String) {
+        let Ok((modified, size)) = file_stamp(Path::new(path)) else {
+            return;
+        };
+        let entry = self.entries.entry(canonical_key(path)).or_default();
+        if entry.modified != modified || entry.size != size {
+            *entry = HashEntry { modified, size, ..Default::default() };
+        }
+        match kind {
+            HashKind::File => entry.file_hash = Some(hash),
+            HashKind::Audio => entry.audio_hash = Some(hash),
+        }
+    }
+}
+
+/// 不经过缓存计算哈希
+pub fn compute_hash(path: &str, kind: HashKind, cancel: &CancelToken) -> AppResult<String> {
+    match kind {
+        HashKind::File => Ok(compute_file_hash_internal(path)?),
+        HashKind::Audio => compute_audio_hash_internal(path, cancel),
+    }
+}
+
+/// 跳过 FLAC 元数据块，返回第一个音频帧的偏移
+fn flac_audio_start(file: &mut File, start: u64) -> io::Result<u64> {
+    let mut offset = start + 4;
+    let mut header = [0u8; 4];
+    loop {
+        file.seek(SeekFrom::Start(offset))?;
+        file.read_exact(&mut header)?;
+        let length = u64::from(u32::from_be_bytes([0, header[1], header[2], header[3]]));
+        offset += 4 + length;
+        if header[0] & 0x80 != 0 {
+            return Ok(offset);
+        }
+    }
+}
+
+/// 计算文件末尾 ID3v1 与 APEv2 标签占用的字节数
+fn trailing_tags_size(file: &mut File, end: u64) -> io::Result<u64> {
+    let mut end = end;
+
+    if end >= 128 {
+        let mut tag = [0u8; 3];
+        file.seek(SeekFrom::Start(end - 128))?;
+        file.read_exact(&mut tag)?;
+        if &tag == b"TAG" {
+            end -= 128;
+        }
+    }
+
+    if end >= 32 {
+        let mut footer = [0u8; 32];
+        file.seek(SeekFrom::Start(end - 32))?;
+        file.read_exact(&mut footer)?;
+        if &footer[0..8] == b"APETAGEX" {
+            let size = u64::from(u32::from_le_bytes([footer[12], footer[13], footer[14], footer[15]]));
+            let flags = u32::from_le_bytes([footer[20], footer[21], footer[22], footer[23]]);
+            let header = if flags & 0x8000_0000 != 0 { 32 } else { 0 };
+            end = end.saturating_sub(size + header);
+        }
+    }
+
+    Ok(end)
+}
+
+/// 确定文件中音频数据的字节范围
+fn audio_byte_range(path: &Path, file: &mut File) -> Result<(u64, u64), String> {
+    let len = file.metadata().map_err(|e| tr!("file.metadataFailed", detail = e))?.len();
+
+    let mut header = [0u8; 10];
+    let read = file.read(&mut header).map_err(|e| tr!("file.readFailed", detail = e))?;
+    let header = &header[..read];
+
+    if header.starts_with(b"DSD ") {
+        let info = read_dsf_info(path)?;
+        let end = if info.metadata_offset == 0 { len } else { info.metadata_offset.min(len) };
+        return Ok((0, end));
+    }
+
+    let mut start = id3v2_size(header).min(len);
+    file.seek(SeekFrom::Start(start)).map_err(|e| tr!("hashing.seekFailed", detail = e))?;
+    let mut magic = [0u8; 4];
+    if file.read_exact(&mut magic).is_ok() && &magic == b"fLaC" {
+        start = flac_audio_start(file, start).map_err(|e| tr!("hashing.flacBlocksFailed", detail = e))?;
+    }
+
+    let end = trailing_tags_size(file, len).map_err(|e| tr!("hashing.trailerFailed", detail = e))?;
+    Ok((start, end.max(start)))
+}
+
+/// 计算去掉标签区域后的文件 SHA-256
+///
+/// MP4、Ogg 等格式的标签位于容器内部，这类文件按整个文件计算。
+pub fn compute_file_hash_internal(path: &str) -> Result<String, String> {
+    let file_path = Path::new(path);
+    let mut file = File::open(long_path(file_path)).map_err(|e| tr!("file.openFailed", detail = e))?;
+    let (start, end) = audio_byte_range(file_path, &mut file)?;
+
+    file.seek(SeekFrom::Start(start)).map_err(|e| tr!("hashing.seekFailed", detail = e))?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file.take(end - start), &mut hasher).map_err(|e| tr!("file.readFailed", detail = e))?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// 计算解码后 PCM 的 SHA-256
+pub fn compute_audio_hash_internal(path: &str, cancel: &CancelToken) -> AppResult<String> {
+    let decoder = SymphoniaDecoder::new(path).map_err(AppError::AudioDecoder)?;
+    let mut hasher = Sha256::new();
+    let mut samples = 0usize;
+
+    for sample in decoder {
+        hasher.update(sample.to_le_bytes());
+        samples += 1;
+        if samples.is_multiple_of(CANCEL_CHECK_INTERVAL) {
+            cancel.check()?;
+        }
+    }
+
+    if samples == 0 {
+        return Err(AppError::AudioDecoder(tr!("hashing.noAudio")));
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// 查找重复文件
+///
+/// 逐个计算哈希（命中缓存时直接使用），每处理一个文件调用一次 `on_progress(completed, path)`。
+/// 只返回包含两个及以上文件的组。
+pub fn find_duplicates_internal(
+    paths: &[String],
+    kind: HashKind,
+    cache: &mut HashCache,
+    cancel: &CancelToken,
+    on_progress: impl Fn(usize, &str),
+) -> AppResult<DuplicateReport> {
+    let mut by_hash: HashMap<String, Vec<String>> = HashMap::new();
+    let mut report = DuplicateReport::default();
+
+    for (index, path) in paths.iter().enumerate() {
+        cancel.check()?;
+        on_progress(index, path);
+
+        match cache.get_or_compute(path, kind, cancel) {
+            Ok(hash) => by_hash.entry(hash).or_default().push(path.clone()),
+            Err(AppError::Cancelled) => return Err(AppError::Cancelled),
+            Err(e) => report.failed.push(HashFailure { path: path.clone(), message: e.to_string() }),
+        }
+    }
+
+    let options = MetadataOptions::default();
+    for (hash, group_paths) in by_hash.into_iter().filter(|(_, p)| p.len() > 1) {
+        let mut files: Vec<DuplicateFile> = group_paths
+            .into_iter()
+            .map(|path| {
+                let size = fs::metadata(&path).map_or(0, |m| m.len());
+                let metadata = cached_track_metadata(&path, &options).ok();
+                DuplicateFile {
+                    bitrate: metadata.as_ref().and_then(|m| m.bitrate),
+                    format: metadata.and_then(|m| m.format),
+                    path,
+                    size,
+                }
+            })
+            .collect();
+        files.sort_by(|a, b| b.bitrate.cmp(&a.bitrate).then_with(|| b.size.cmp(&a.size)));
+        report.groups.push(DuplicateGroup { hash, files });
+    }
+    report.groups.sort_by(|a, b| a.files[0].path.cmp(&b.files[0].path));
+
+    on_progress(paths.len(), "");
+    Ok(report)
+}
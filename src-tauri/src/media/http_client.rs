@@ -1,21 +1,152 @@
 //! HTTP 客户端单例
 //!
-//! 提供可重用的 HTTP 客户端，避免重复创建连接
+//! 提供可重用的 HTTP 客户端，避免重复创建连接。
+//! 配置了代理时所有在线功能都经过该代理，否则使用系统代理设置；修改代理后客户端会被重新创建。
+//! 应用网络设置时同时更新网易云音乐接口的限流速率。
+//! 离线模式开启时所有在线功能在发出请求前返回离线错误，有缓存的功能改用缓存。
 
-use std::sync::LazyLock;
-use reqwest::Client;
+use crate::config::NetworkConfig;
+use crate::media::netease_limiter;
+use crate::tr;
+use reqwest::{Client, NoProxy, Proxy};
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{LazyLock, RwLock};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+/// 离线模式开关变化事件名称
+pub const OFFLINE_MODE_CHANGED_EVENT: &str = "offline-mode-changed";
+
+/// 检查网络连接时请求的地址
+const CONNECTIVITY_CHECK_URL: &str = "https://music.163.com/";
+
+/// 通用客户端的超时设置（总超时, 连接超时）
+const DEFAULT_TIMEOUTS: (Duration, Duration) = (Duration::from_secs(30), Duration::from_secs(10));
+/// 网易云音乐接口的超时设置：接口响应通常很快，超时更短，失败时由调用方重试
+const NETEASE_TIMEOUTS: (Duration, Duration) = (Duration::from_secs(10), Duration::from_secs(5));
 
 /// 全局 HTTP 客户端实例
-static HTTP_CLIENT: LazyLock<Client> = LazyLock::new(|| {
-    Client::builder()
-        .timeout(std::time::Duration::from_secs(30))
-        .connect_timeout(std::time::Duration::from_secs(10))
-        .build()
-        .expect("Failed to create HTTP client")
+static HTTP_CLIENT: LazyLock<RwLock<Client>> = LazyLock::new(|| {
+    RwLock::new(build_client(DEFAULT_TIMEOUTS, &NetworkConfig::default()).expect("Failed to create HTTP client"))
+});
+
+/// 网易云音乐接口使用的客户端
+static NETEASE_CLIENT: LazyLock<RwLock<Client>> = LazyLock::new(|| {
+    RwLock::new(build_client(NETEASE_TIMEOUTS, &NetworkConfig::default()).expect("Failed to create HTTP client"))
 });
 
+/// 是否处于离线模式
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+/// 是否设置了代理
+static PROXY_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// 离线模式开关变化事件负载
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct OfflineModeEvent {
+    pub offline: bool,
+}
+
+/// 网络连接检查结果
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionTest {
+    pub success: bool,
+    /// 收到响应所用的时间（毫秒）
+    pub latency_ms: u64,
+    pub status: Option<u16>,
+    pub error: Option<String>,
+    /// 是否经过配置的代理（否则使用系统代理设置）
+    pub via_proxy: bool,
+}
+
+/// 检查代理地址：只支持 http、https 和 socks5（socks5h）
+pub fn validate_proxy_url(url: &str) -> Result<(), String> {
+    let (scheme, rest) = url.split_once("://").ok_or_else(|| tr!("network.proxyMissingScheme", url = url))?;
+    if !matches!(scheme.to_ascii_lowercase().as_str(), "http" | "https" | "socks5" | "socks5h") {
+        return Err(tr!("network.proxyUnsupportedScheme", scheme = scheme));
+    }
+    if rest.trim_matches('/').is_empty() {
+        return Err(tr!("network.proxyMissingHost", url = url));
+    }
+    Proxy::all(url).map(|_| ()).map_err(|e| tr!("network.proxyInvalid", detail = e))
+}
+
+fn build_client((timeout, connect_timeout): (Duration, Duration), network: &NetworkConfig) -> Result<Client, String> {
+    let mut builder = Client::builder().timeout(timeout).connect_timeout(connect_timeout);
+    if let Some(url) = network.proxy_url.as_deref().filter(|url| !url.trim().is_empty()) {
+        let proxy = Proxy::all(url.trim())
+            .map_err(|e| tr!("network.proxyInvalid", detail = e))?
+            .no_proxy(network.no_proxy.as_deref().and_then(NoProxy::from_string));
+        builder = builder.proxy(proxy);
+    }
+    builder.build().map_err(|e| tr!("network.clientFailed", detail = e))
+}
+
+/// 按网络设置重新创建所有客户端，之后的请求使用新的代理设置和限流速率
+pub fn apply_network_config(network: &NetworkConfig) -> Result<(), String> {
+    let general = build_client(DEFAULT_TIMEOUTS, network)?;
+    let netease = build_client(NETEASE_TIMEOUTS, network)?;
+    *HTTP_CLIENT.write().unwrap() = general;
+    *NETEASE_CLIENT.write().unwrap() = netease;
+    let proxy_enabled = network.proxy_url.as_deref().is_some_and(|url| !url.trim().is_empty());
+    PROXY_ENABLED.store(proxy_enabled, Ordering::Relaxed);
+    netease_limiter::set_rate(network.netease_requests_per_second);
+    Ok(())
+}
+
+/// 开启或关闭离线模式，开关发生变化时发送 `offline-mode-changed` 事件
+pub fn set_offline(app: &AppHandle, offline: bool) {
+    if OFFLINE.swap(offline, Ordering::Relaxed) != offline {
+        let _ = app.emit(OFFLINE_MODE_CHANGED_EVENT, OfflineModeEvent { offline });
+    }
+}
+
+/// 是否处于离线模式；所有在线功能在构建请求前检查
+#[must_use]
+pub fn is_offline() -> bool {
+    OFFLINE.load(Ordering::Relaxed)
+}
+
+/// 请求是否经过设置的代理（否则使用系统代理设置），用于区分代理连接失败
+#[must_use]
+pub fn uses_proxy() -> bool {
+    PROXY_ENABLED.load(Ordering::Relaxed)
+}
+
 /// 获取全局 HTTP 客户端
 #[must_use]
-pub fn get_client() -> &'static Client {
-    &HTTP_CLIENT
+pub fn get_client() -> Client {
+    HTTP_CLIENT.read().unwrap().clone()
+}
+
+/// 获取网易云音乐接口使用的 HTTP 客户端
+#[must_use]
+pub fn get_netease_client() -> Client {
+    NETEASE_CLIENT.read().unwrap().clone()
+}
+
+/// 通过当前的网络设置发送一次轻量请求，检查能否连接
+pub async fn test_connection(network: &NetworkConfig) -> ConnectionTest {
+    let via_proxy = network.proxy_url.as_deref().is_some_and(|url| !url.trim().is_empty());
+    if is_offline() {
+        let error = Some(tr!("network.offline"));
+        return ConnectionTest { success: false, latency_ms: 0, status: None, error, via_proxy };
+    }
+    let started = Instant::now();
+    let result = get_netease_client().head(CONNECTIVITY_CHECK_URL).send().await;
+    let latency_ms = u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX);
+    match result {
+        Ok(response) => ConnectionTest {
+            // 能收到任何响应都说明连接可用
+            success: true,
+            latency_ms,
+            status: Some(response.status().as_u16()),
+            error: None,
+            via_proxy,
+        },
+        Err(e) => ConnectionTest { success: false, latency_ms, status: None, error: Some(e.to_string()), via_proxy },
+    }
 }
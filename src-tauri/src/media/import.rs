@@ -0,0 +1,177 @@
+//! 外部文件导入模块
+//!
+//! 把曲库外的文件（如刚下载的单曲或整张专辑）按整理模板复制或移动到音乐目录中。
+//! 先读取元数据计算目标路径，复制后逐字节校验，成功的文件写入曲库数据库。
+
+use super::metadata::{get_track_metadata_with_options, MetadataOptions, TrackMetadata};
+use super::metadata_cache::{cached_track_metadata, invalidate};
+use super::organize::{copy_file_verified, move_file, target_path, FileConflict, FileMove, DEFAULT_ORGANIZE_PATTERN};
+use crate::error::AppResult;
+use crate::library::LibraryDb;
+use crate::paths::{canonical_key, same_path};
+use crate::tasks::CancelToken;
+use crate::tr;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// 文件导入完成事件名称，前端据此刷新曲库并按需加入播放队列
+pub const FILES_IMPORTED_EVENT: &str = "files-imported";
+
+/// 重命名时最多尝试的序号
+const MAX_RENAME_ATTEMPTS: u32 = 999;
+
+/// 目标文件已存在时的处理方式
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CollisionPolicy {
+    /// 跳过该文件
+    #[default]
+    Skip,
+    /// 在文件名后加上 ` (1)`、` (2)` 等序号
+    Rename,
+}
+
+/// 导入选项
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ImportFilesOptions {
+    /// 移动文件而不是复制
+    pub move_files: bool,
+    pub on_collision: CollisionPolicy,
+    /// 整理模板，默认为 `{album_artist}/{album}/{track:02} - {title}`
+    pub pattern: Option<String>,
+    /// 目标根目录，默认为第一个音乐目录
+    pub destination: Option<String>,
+    /// 导入后加入当前播放队列
+    pub add_to_queue: bool,
+}
+
+/// 导入结果
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportFilesResult {
+    /// 成功导入的文件（源路径 -> 曲库中的新路径）
+    pub imported: Vec<FileMove>,
+    /// 目标已存在而跳过的文件
+    pub skipped: Vec<FileConflict>,
+    /// 读取、复制或校验失败的文件
+    pub failed: Vec<FileConflict>,
+    /// 导入被中途取消，未处理的文件不出现在任何列表中
+    pub cancelled: bool,
+}
+
+/// 导入完成事件负载
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FilesImportedEvent {
+    pub tracks: Vec<TrackMetadata>,
+    pub add_to_queue: bool,
+}
+
+/// 为已存在的目标找一个可用的文件名，如 `01 - Title (1).flac`
+fn free_target(target: &Path, claimed: &HashSet<String>) -> Option<PathBuf> {
+    let stem = target.file_stem()?.to_string_lossy().to_string();
+    let ext = target.extension().map(|ext| format!(".{}", ext.to_string_lossy())).unwrap_or_default();
+    (1..=MAX_RENAME_ATTEMPTS)
+        .map(|n| target.with_file_name(format!("{stem} ({n}){ext}")))
+        .find(|candidate| !candidate.exists() && !claimed.contains(&claim_key(candidate)))
+}
+
+fn claim_key(path: &Path) -> String {
+    canonical_key(&path.to_string_lossy())
+}
+
+/// 把 `paths` 导入到 `root` 下
+///
+/// 文件按顺序处理，开始处理每个文件前调用 `on_progress(completed, total, path)`。取消时停止处理剩余文件，
+/// 已导入的文件仍写入曲库数据库并在结果中列出。移动模式下源文件原有的曲库记录会被移除。
+/// 返回结果和新文件的元数据。
+pub fn import_files_internal(
+    db: &LibraryDb,
+    paths: &[String],
+    root: &Path,
+    options: &ImportFilesOptions,
+    metadata_options: &MetadataOptions,
+    cancel: &CancelToken,
+    on_progress: impl Fn(usize, usize, &str),
+) -> AppResult<(ImportFilesResult, Vec<TrackMetadata>)> {
+    let pattern = options.pattern.as_deref().filter(|p| !p.trim().is_empty()).unwrap_or(DEFAULT_ORGANIZE_PATTERN);
+    let mut result = ImportFilesResult::default();
+    let mut tracks = Vec::new();
+    let mut claimed: HashSet<String> = HashSet::new();
+
+    for (index, path) in paths.iter().enumerate() {
+        if cancel.is_cancelled() {
+            result.cancelled = true;
+            break;
+        }
+        on_progress(index, paths.len(), path);
+
+        let source = Path::new(path);
+        let failure = |to: Option<&Path>, reason: String| FileConflict {
+            from: path.clone(),
+            to: to.map(|to| to.to_string_lossy().to_string()),
+            reason,
+        };
+
+        let mut target = match get_track_metadata_with_options(path, metadata_options)
+            .and_then(|metadata| target_path(root, pattern, &metadata, source))
+        {
+            Ok(target) => target,
+            Err(e) => {
+                result.failed.push(failure(None, e));
+                continue;
+            }
+        };
+
+        // 文件已经在目标位置时只需要写入曲库
+        if !same_path(path, &target.to_string_lossy()) {
+            let taken = target.exists() || claimed.contains(&claim_key(&target));
+            target = match (taken, options.on_collision) {
+                (false, _) => target,
+                (true, CollisionPolicy::Skip) => {
+                    result.skipped.push(failure(Some(&target), "exists".to_string()));
+                    continue;
+                }
+                (true, CollisionPolicy::Rename) => {
+                    let Some(free) = free_target(&target, &claimed) else {
+                        result.failed.push(failure(Some(&target), "exists".to_string()));
+                        continue;
+                    };
+                    free
+                }
+            };
+
+            let transferred =
+                if options.move_files { move_file(source, &target) } else { copy_file_verified(source, &target) };
+            if let Err(e) = transferred {
+                result.failed.push(failure(Some(&target), tr!("import.failed", detail = e)));
+                continue;
+            }
+            claimed.insert(claim_key(&target));
+            if options.move_files {
+                invalidate(path);
+            }
+        }
+
+        let target = target.to_string_lossy().to_string();
+        match cached_track_metadata(&target, metadata_options) {
+            Ok(track) => tracks.push(track),
+            Err(e) => eprintln!("Failed to read imported file '{target}': {e}"),
+        }
+        result.imported.push(FileMove { from: path.clone(), to: target });
+    }
+
+    if options.move_files {
+        let moved: Vec<String> = result
+            .imported
+            .iter()
+            .filter(|file| !same_path(&file.from, &file.to))
+            .map(|file| file.from.clone())
+            .collect();
+        db.remove_tracks(&moved)?;
+    }
+    db.upsert_tracks(&tracks)?;
+    Ok((result, tracks))
+}
@@ -0,0 +1,360 @@
+//! 文件完整性检查模块
+//!
+//! 快速检查：能否识别格式并解码第一个数据包，头部报告的时长与文件大小是否相符，
+//! 以及能否定位到结尾附近并解码（截断的文件通常在这里失败）。
+//! 完整检查在此基础上把整个文件解码到结尾，找出中途损坏的位置，并校验 FLAC 的 MD5 签名。
+//! Symphonia 无法解码的格式只检查标签和头部。
+
+use crate::audio::decoder::UNDECODABLE_EXTENSIONS;
+use crate::audio::opus;
+use crate::error::{AppError, AppResult};
+use crate::paths::long_path;
+use crate::tasks::CancelToken;
+use crate::tr;
+use lofty::file::AudioFile;
+use lofty::probe::Probe;
+use rayon::prelude::*;
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::ErrorKind;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use symphonia::core::codecs::{Decoder, DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error;
+use symphonia::core::formats::{FormatOptions, FormatReader, SeekMode, SeekTo};
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+use symphonia::core::units::{Time, TimeBase};
+
+/// 完整检查开始前的耗时估计事件名称
+pub const VALIDATION_ESTIMATE_EVENT: &str = "validation-estimate";
+
+/// 估计耗时时假设的单线程解码速度（字节/秒）
+const DECODE_BYTES_PER_SEC: f64 = 30.0 * 1024.0 * 1024.0;
+
+/// 头部时长与文件大小换算出的码率低于此值（比特/秒）时，认为文件比头部声明的短
+const MIN_SANE_BITRATE: f64 = 4_000.0;
+/// 换算出的码率高于此值（比特/秒）时，认为头部时长不可信
+const MAX_SANE_BITRATE: f64 = 40_000_000.0;
+/// 解码得到的时长比头部时长短超过这么多（秒）时视为截断
+const TRUNCATION_TOLERANCE_SECS: f64 = 0.5;
+/// 快速检查时定位到距结尾多少秒的位置
+const TAIL_PROBE_SECS: f64 = 2.0;
+/// 每解码多少个数据包检查一次取消标志
+const CANCEL_CHECK_PACKETS: usize = 256;
+
+/// 检查结果
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum ValidationStatus {
+    Ok,
+    /// 文件比头部声明的短；`actualSecs` 为实际能解码到的位置，快速检查时可能未知
+    #[serde(rename_all = "camelCase")]
+    Truncated { expected_secs: f64, actual_secs: Option<f64> },
+    /// 在 `offsetSecs` 处解码失败
+    #[serde(rename_all = "camelCase")]
+    DecodeError { offset_secs: f64, message: String },
+    /// 无法识别格式或头部信息不可信
+    #[serde(rename_all = "camelCase")]
+    HeaderCorrupt { message: String },
+    /// 解码后的音频与 FLAC 头部中的 MD5 签名不一致
+    ChecksumMismatch,
+    /// 文件不存在或无法读取
+    #[serde(rename_all = "camelCase")]
+    Unreadable { message: String },
+}
+
+/// 单个文件的检查结果
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationResult {
+    pub path: String,
+    #[serde(flatten)]
+    pub status: ValidationStatus,
+    /// 头部报告的时长（秒）
+    pub duration: Option<f64>,
+    /// 是否解码到了结尾
+    pub fully_decoded: bool,
+    /// MD5 签名是否一致，没有校验（非 FLAC、没有签名或未完整解码）时为 `None`
+    pub md5_verified: Option<bool>,
+}
+
+/// 完整检查的耗时估计
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationEstimate {
+    pub task_id: String,
+    pub files: usize,
+    pub total_bytes: u64,
+    /// 按并行线程数估计的耗时（秒）
+    pub estimated_secs: f64,
+}
+
+/// 估计完整解码 `paths` 所需的时间
+#[must_use]
+pub fn estimate_validation(task_id: &str, paths: &[String]) -> ValidationEstimate {
+    let total_bytes: u64 = paths.iter().filter_map(|path| fs::metadata(long_path(path)).ok()).map(|m| m.len()).sum();
+    let threads = rayon::current_num_threads().max(1) as f64;
+    ValidationEstimate {
+        task_id: task_id.to_string(),
+        files: paths.len(),
+        total_bytes,
+        estimated_secs: total_bytes as f64 / (DECODE_BYTES_PER_SEC * threads),
+    }
+}
+
+fn checked(path: &str, status: ValidationStatus, duration: Option<f64>) -> ValidationResult {
+    ValidationResult { path: path.to_string(), status, duration, fully_decoded: false, md5_verified: None }
+}
+
+fn header_corrupt(path: &str, message: impl Into<String>) -> ValidationResult {
+    checked(path, ValidationStatus::HeaderCorrupt { message: message.into() }, None)
+}
+
+/// 头部时长与文件大小是否相符，不相符时返回对应的状态
+fn check_duration(duration: f64, file_size: u64) -> Option<ValidationStatus> {
+    if !duration.is_finite() || duration <= 0.0 {
+        return Some(ValidationStatus::HeaderCorrupt { message: tr!("integrity.invalidDuration", duration = duration) });
+    }
+    let bitrate = file_size as f64 * 8.0 / duration;
+    if bitrate < MIN_SANE_BITRATE {
+        return Some(ValidationStatus::Truncated { expected_secs: duration, actual_secs: None });
+    }
+    if bitrate > MAX_SANE_BITRATE {
+        return Some(ValidationStatus::HeaderCorrupt {
+            message: tr!("integrity.durationMismatch", seconds = format!("{duration:.1}")),
+        });
+    }
+    None
+}
+
+/// Symphonia 无法解码的格式：只读取头部和标签
+fn validate_undecodable(path: &str, file_size: u64) -> ValidationResult {
+    let tagged = match Probe::open(long_path(path)).and_then(|probe| probe.read()) {
+        Ok(tagged) => tagged,
+        Err(e) => return header_corrupt(path, e.to_string()),
+    };
+    let duration = tagged.properties().duration().as_secs_f64();
+    let status = check_duration(duration, file_size).unwrap_or(ValidationStatus::Ok);
+    checked(path, status, Some(duration))
+}
+
+struct OpenedTrack {
+    format: Box<dyn FormatReader>,
+    decoder: Box<dyn Decoder>,
+    track_id: u32,
+    time_base: Option<TimeBase>,
+    sample_rate: Option<u32>,
+    n_frames: Option<u64>,
+    /// PCM 数据应有的字节数，用于直接判断 WAV/AIFF 是否被截断
+    pcm_bytes: Option<u64>,
+}
+
+fn open_track(path: &str) -> Result<OpenedTrack, String> {
+    let file = File::open(long_path(path)).map_err(|e| e.to_string())?;
+    let mss = MediaSourceStream::new(Box::new(file), Default::default());
+    let mut hint = Hint::new();
+    if let Some(ext) = Path::new(path).extension().and_then(|ext| ext.to_str()) {
+        hint.with_extension(ext);
+    }
+    let probed = symphonia::default::get_probe()
+        .format(&hint, mss, &FormatOptions::default(), &MetadataOptions::default())
+        .map_err(|e| e.to_string())?;
+    let format = probed.format;
+    let track = format
+        .tracks()
+        .iter()
+        .find(|track| track.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or_else(|| tr!("integrity.noAudioTrack"))?;
+    let params = &track.codec_params;
+    let codecs = opus::codecs();
+    let is_pcm = codecs.get_codec(params.codec).is_some_and(|codec| codec.short_name.starts_with("pcm_"));
+    let pcm_bytes = match (is_pcm, params.n_frames, params.channels, params.bits_per_sample) {
+        (true, Some(frames), Some(channels), Some(bits)) => {
+            Some(frames * channels.count() as u64 * u64::from(bits) / 8)
+        }
+        _ => None,
+    };
+    let decoder = codecs.make(params, &DecoderOptions { verify: true }).map_err(|e| e.to_string())?;
+    Ok(OpenedTrack {
+        track_id: track.id,
+        time_base: params.time_base,
+        sample_rate: params.sample_rate,
+        n_frames: params.n_frames,
+        pcm_bytes,
+        format,
+        decoder,
+    })
+}
+
+impl OpenedTrack {
+    fn frame_seconds(&self, frames: u64) -> f64 {
+        self.sample_rate.map_or(0.0, |rate| frames as f64 / f64::from(rate))
+    }
+
+    /// 数据包时间戳对应的秒数，没有时间基准时按采样率换算
+    fn ts_seconds(&self, ts: u64) -> f64 {
+        self.time_base.map_or_else(
+            || self.frame_seconds(ts),
+            |base| {
+                let time = base.calc_time(ts);
+                time.seconds as f64 + time.frac
+            },
+        )
+    }
+
+    fn duration(&self) -> Option<f64> {
+        self.n_frames.map(|frames| self.frame_seconds(frames))
+    }
+
+    /// 解码下一个属于音轨的数据包，返回解码得到的帧数；到达结尾时返回 `None`
+    ///
+    /// 失败时返回出错数据包的时间戳（读取数据包本身失败时为 `None`）和错误信息。
+    fn decode_next(&mut self) -> Result<Option<u64>, (Option<u64>, String)> {
+        loop {
+            let packet = match self.format.next_packet() {
+                Ok(packet) => packet,
+                Err(Error::IoError(e)) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+                Err(Error::ResetRequired) => {
+                    self.decoder.reset();
+                    continue;
+                }
+                Err(e) => return Err((None, e.to_string())),
+            };
+            if packet.track_id() != self.track_id {
+                continue;
+            }
+            return match self.decoder.decode(&packet) {
+                Ok(decoded) => Ok(Some(decoded.frames() as u64)),
+                Err(e) => Err((Some(packet.ts()), e.to_string())),
+            };
+        }
+    }
+}
+
+/// 快速检查：定位到结尾附近并解码一个数据包
+fn check_tail(track: &mut OpenedTrack, duration: f64) -> Option<ValidationStatus> {
+    let target = (duration - TAIL_PROBE_SECS).max(0.0);
+    let seek_to = SeekTo::Time { time: Time::from(target), track_id: Some(track.track_id) };
+    // 不支持定位的格式无法在不完整解码的情况下判断
+    let Ok(seeked) = track.format.seek(SeekMode::Coarse, seek_to) else {
+        return None;
+    };
+    track.decoder.reset();
+    match track.decode_next() {
+        Ok(Some(_)) => None,
+        Ok(None) => Some(ValidationStatus::Truncated { expected_secs: duration, actual_secs: None }),
+        Err((ts, message)) => {
+            let offset_secs = track.ts_seconds(ts.unwrap_or(seeked.actual_ts));
+            Some(ValidationStatus::DecodeError { offset_secs, message })
+        }
+    }
+}
+
+/// 把文件解码到结尾，返回遇到的第一个问题
+fn decode_to_end(track: &mut OpenedTrack, cancel: &CancelToken) -> AppResult<(Option<ValidationStatus>, Option<bool>)> {
+    let mut decoded = 0u64;
+    let mut packets = 0usize;
+    loop {
+        packets += 1;
+        if packets == CANCEL_CHECK_PACKETS {
+            packets = 0;
+            cancel.check()?;
+        }
+        match track.decode_next() {
+            Ok(Some(frames)) => decoded += frames,
+            Ok(None) => break,
+            Err((ts, message)) => {
+                let offset_secs = ts.map_or_else(|| track.frame_seconds(decoded), |ts| track.ts_seconds(ts));
+                return Ok((Some(ValidationStatus::DecodeError { offset_secs, message }), None));
+            }
+        }
+    }
+
+    if let Some(expected_secs) = track.duration() {
+        let actual_secs = track.frame_seconds(decoded);
+        if expected_secs - actual_secs > TRUNCATION_TOLERANCE_SECS {
+            return Ok((Some(ValidationStatus::Truncated { expected_secs, actual_secs: Some(actual_secs) }), None));
+        }
+    }
+    let verified = track.decoder.finalize().verify_ok;
+    let status = (verified == Some(false)).then_some(ValidationStatus::ChecksumMismatch);
+    Ok((status, verified))
+}
+
+/// 检查单个文件，`full_decode` 为 `true` 时解码整个文件
+pub fn validate_file(path: &str, full_decode: bool, cancel: &CancelToken) -> AppResult<ValidationResult> {
+    let file_size = match fs::metadata(long_path(path)) {
+        Ok(metadata) if metadata.is_file() => metadata.len(),
+        Ok(_) => return Err(AppError::InvalidPath(path.to_string())),
+        Err(e) if e.kind() == ErrorKind::NotFound => return Err(AppError::FileNotFound(path.to_string())),
+        Err(e) => return Err(AppError::Io(e)),
+    };
+    let extension = Path::new(path).extension().map(|ext| ext.to_string_lossy().to_lowercase()).unwrap_or_default();
+    if UNDECODABLE_EXTENSIONS.contains(&extension.as_str()) {
+        return Ok(validate_undecodable(path, file_size));
+    }
+
+    let mut track = match open_track(path) {
+        Ok(track) => track,
+        Err(message) => return Ok(header_corrupt(path, message)),
+    };
+    let duration = track.duration();
+    if let Some(expected) = track.pcm_bytes
+        && expected > file_size
+    {
+        let actual_secs = duration.map(|secs| secs * file_size as f64 / expected as f64);
+        let status = ValidationStatus::Truncated { expected_secs: duration.unwrap_or(0.0), actual_secs };
+        return Ok(checked(path, status, duration));
+    }
+    if let Some(status) = duration.and_then(|secs| check_duration(secs, file_size)) {
+        return Ok(checked(path, status, duration));
+    }
+
+    if !full_decode {
+        if let Err((_, message)) = track.decode_next() {
+            let status = ValidationStatus::DecodeError { offset_secs: 0.0, message };
+            return Ok(checked(path, status, duration));
+        }
+        let status = duration.and_then(|secs| check_tail(&mut track, secs)).unwrap_or(ValidationStatus::Ok);
+        return Ok(checked(path, status, duration));
+    }
+    let (status, md5_verified) = decode_to_end(&mut track, cancel)?;
+    Ok(ValidationResult {
+        path: path.to_string(),
+        status: status.unwrap_or(ValidationStatus::Ok),
+        duration,
+        fully_decoded: true,
+        md5_verified,
+    })
+}
+
+/// 并行检查多个文件，结果与 `paths` 一一对应；不存在或无法读取的文件记为 `Unreadable`
+///
+/// 每检查完一个文件调用一次 `on_progress(completed, total, path)`，取消时返回 `Cancelled`。
+pub fn validate_audio_files_internal(
+    paths: &[String],
+    full_decode: bool,
+    cancel: &CancelToken,
+    on_progress: impl Fn(usize, usize, &str) + Sync,
+) -> AppResult<Vec<ValidationResult>> {
+    let completed = AtomicUsize::new(0);
+    let results: Vec<Option<ValidationResult>> = paths
+        .par_iter()
+        .map(|path| {
+            if cancel.is_cancelled() {
+                return None;
+            }
+            let result = match validate_file(path, full_decode, cancel) {
+                Ok(result) => Some(result),
+                Err(AppError::Cancelled) => None,
+                Err(e) => Some(checked(path, ValidationStatus::Unreadable { message: e.to_string() }, None)),
+            };
+            on_progress(completed.fetch_add(1, Ordering::Relaxed) + 1, paths.len(), path);
+            result
+        })
+        .collect();
+    cancel.check()?;
+    Ok(results.into_iter().flatten().collect())
+}
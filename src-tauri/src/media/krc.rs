@@ -0,0 +1,119 @@
+//! 酷狗 KRC 歌词解码
+//!
+//! KRC 文件以 `krc1` 开头，其余部分先用固定的 16 字节密钥循环异或，再用 zlib 压缩。
+//! 解压后的文本每行形如 `[行开始,行时长]<字偏移,字时长,0>字...`，字偏移相对于行开始；
+//! `[language:...]` 标签是 base64 编码的 JSON，包含逐行翻译（type 1）和逐字罗马音（type 0）。
+
+use super::word_lyrics::{LyricWord, WordTimedLine};
+use base64::{engine::general_purpose, Engine as _};
+use flate2::read::ZlibDecoder;
+use serde::Deserialize;
+use std::io::Read;
+use crate::tr;
+
+const KRC_HEADER: &[u8] = b"krc1";
+const KRC_KEY: [u8; 16] =
+    [0x40, 0x47, 0x61, 0x77, 0x5e, 0x32, 0x74, 0x47, 0x51, 0x36, 0x31, 0x2d, 0xce, 0xd2, 0x6e, 0x69];
+
+/// 解析后的 KRC 歌词
+#[derive(Debug, Default)]
+pub struct KrcLyrics {
+    pub lines: Vec<WordTimedLine>,
+    /// 逐行翻译（行开始时间, 文本）
+    pub translation: Vec<(u32, String)>,
+    /// 逐行罗马音（行开始时间, 文本）
+    pub romanization: Vec<(u32, String)>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LanguageTag {
+    #[serde(default)]
+    content: Vec<LanguageContent>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LanguageContent {
+    /// 0 为罗马音，1 为翻译
+    #[serde(rename = "type")]
+    kind: i32,
+    #[serde(default, rename = "lyricContent")]
+    lyric_content: Vec<Vec<String>>,
+}
+
+/// 解密并解压 KRC 数据，返回歌词文本
+pub fn decrypt_krc(data: &[u8]) -> Result<String, String> {
+    let body = data.strip_prefix(KRC_HEADER).ok_or_else(|| tr!("krc.invalid"))?;
+    let decoded: Vec<u8> = body.iter().zip(KRC_KEY.iter().cycle()).map(|(byte, key)| byte ^ key).collect();
+    let mut text = String::new();
+    ZlibDecoder::new(decoded.as_slice())
+        .read_to_string(&mut text)
+        .map_err(|e| tr!("krc.decompressFailed", detail = e))?;
+    Ok(text.trim_start_matches('\u{feff}').to_string())
+}
+
+/// 解析 `a,b` 或 `a,b,c` 形式的时间，返回前两个数
+fn parse_pair(value: &str) -> Option<(u32, u32)> {
+    let mut parts = value.split(',');
+    let first = parts.next()?.trim().parse().ok()?;
+    let second = parts.next()?.trim().parse().ok()?;
+    Some((first, second))
+}
+
+/// 解析一行 `[开始,时长]<偏移,时长,0>字...`
+fn parse_line(line: &str) -> Option<WordTimedLine> {
+    let inner = line.strip_prefix('[')?;
+    let end = inner.find(']')?;
+    let (start, duration) = parse_pair(&inner[..end])?;
+
+    let mut words = Vec::new();
+    let mut rest = &inner[end + 1..];
+    while let Some(open) = rest.find('<') {
+        let Some(close) = rest[open..].find('>').map(|i| open + i) else { break };
+        let Some((offset, word_duration)) = parse_pair(&rest[open + 1..close]) else { break };
+        rest = &rest[close + 1..];
+        let text_end = rest.find('<').unwrap_or(rest.len());
+        words.push(LyricWord {
+            start: start.saturating_add(offset),
+            duration: word_duration,
+            text: rest[..text_end].to_string(),
+        });
+        rest = &rest[text_end..];
+    }
+    Some(WordTimedLine { start, duration, words })
+}
+
+fn parse_language(encoded: &str) -> Option<LanguageTag> {
+    let json = general_purpose::STANDARD.decode(encoded.trim()).ok()?;
+    serde_json::from_slice(&json).ok()
+}
+
+/// 解析 KRC 歌词文本
+#[must_use]
+pub fn parse_krc(text: &str) -> KrcLyrics {
+    let mut result = KrcLyrics::default();
+    let mut language = None;
+    for line in text.lines().map(str::trim) {
+        if let Some(encoded) = line.strip_prefix("[language:").and_then(|rest| rest.strip_suffix(']')) {
+            language = parse_language(encoded);
+        } else if let Some(parsed) = parse_line(line) {
+            result.lines.push(parsed);
+        }
+    }
+
+    // 翻译和罗马音按行序号对应歌词行
+    for content in language.map(|tag| tag.content).unwrap_or_default() {
+        let entries: Vec<(u32, String)> = result
+            .lines
+            .iter()
+            .zip(content.lyric_content)
+            .map(|(line, parts)| (line.start, parts.concat().trim().to_string()))
+            .filter(|(_, text)| !text.is_empty())
+            .collect();
+        match content.kind {
+            0 => result.romanization = entries,
+            1 => result.translation = entries,
+            _ => {}
+        }
+    }
+    result
+}
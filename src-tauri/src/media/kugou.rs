@@ -0,0 +1,172 @@
+//! 酷狗音乐歌词 API
+//!
+//! 提供从酷狗音乐搜索歌曲和获取 KRC 逐字歌词的功能，结果与网易云音乐接口的结构相同。
+//! 歌曲 ID 使用文件哈希；获取歌词时先按哈希查询歌词候选，再下载第一个候选的 KRC 并解码。
+//! 请求与网易云音乐共用 HTTP 客户端、重试策略和限流队列，错误同样以 `NeteaseError` 返回。
+
+use super::krc::{decrypt_krc, parse_krc};
+use super::lyrics::sync_entries_to_lrc;
+use super::lyrics_provider::{tag_songs, LyricsProvider, ProviderFuture, ProviderSong};
+use super::netease::{fetch_json, LyricsData, NeteaseError, SearchSongResult};
+use super::word_lyrics::word_lines_to_lrc;
+use base64::{engine::general_purpose, Engine as _};
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, USER_AGENT};
+use serde::Deserialize;
+
+const SEARCH_URL: &str = "https://mobilecdn.kugou.com/api/v3/search/song";
+const LYRIC_SEARCH_URL: &str = "https://krcs.kugou.com/search";
+const LYRIC_DOWNLOAD_URL: &str = "https://lyrics.kugou.com/download";
+
+/// 搜索接口响应结构
+#[derive(Debug, Deserialize)]
+struct SearchResponse {
+    #[serde(default)]
+    errcode: i32,
+    data: Option<SearchData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchData {
+    #[serde(default)]
+    info: Vec<SearchSong>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchSong {
+    hash: String,
+    #[serde(default)]
+    songname: String,
+    #[serde(default)]
+    singername: String,
+    #[serde(default)]
+    album_name: String,
+    #[serde(default)]
+    album_id: String,
+    /// 时长（秒）
+    #[serde(default)]
+    duration: i64,
+}
+
+/// 歌词候选接口响应结构
+#[derive(Debug, Deserialize)]
+struct LyricSearchResponse {
+    #[serde(default)]
+    candidates: Vec<LyricCandidate>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LyricCandidate {
+    id: String,
+    accesskey: String,
+}
+
+/// 歌词下载接口响应结构
+#[derive(Debug, Deserialize)]
+struct LyricDownloadResponse {
+    status: i32,
+    #[serde(default)]
+    content: String,
+}
+
+fn build_headers() -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(ACCEPT, HeaderValue::from_static("application/json, text/plain, */*"));
+    headers.insert(USER_AGENT, HeaderValue::from_static("Android14-AndroidPhone-11451-376-0-FeeCacheModule-wifi"));
+    headers
+}
+
+/// 搜索歌曲，`offset` 按 `limit` 换算为页码
+pub async fn search_songs(keyword: &str, limit: u32, offset: u32) -> Result<Vec<SearchSongResult>, NeteaseError> {
+    let limit = limit.max(1);
+    let page = (offset / limit + 1).to_string();
+    let limit = limit.to_string();
+    let params = [
+        ("keyword", keyword),
+        ("page", page.as_str()),
+        ("pagesize", limit.as_str()),
+        ("format", "json"),
+        ("showtype", "1"),
+    ];
+
+    let data: SearchResponse =
+        fetch_json("kugou search", &build_headers(), |client| client.get(SEARCH_URL).query(&params)).await?;
+    if data.errcode != 0 {
+        return Err(NeteaseError::Network(format!("kugou search: API code {}", data.errcode)));
+    }
+
+    let songs = data
+        .data
+        .map(|d| d.info)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|s| SearchSongResult {
+            id: s.hash,
+            name: s.songname,
+            artist: s.singername.replace('、', "/"),
+            album: s.album_name,
+            album_id: (!s.album_id.is_empty()).then_some(s.album_id),
+            cover_url: None,
+            duration: s.duration * 1000,
+        })
+        .collect();
+    Ok(songs)
+}
+
+/// 获取 KRC 歌词，返回展平的 LRC、翻译、罗马音和逐字歌词
+pub async fn get_lyrics(hash: &str) -> Result<LyricsData, NeteaseError> {
+    let what = format!("kugou lyric {hash}");
+    let params = [("ver", "1"), ("man", "yes"), ("client", "mobi"), ("hash", hash)];
+    let found: LyricSearchResponse =
+        fetch_json(&what, &build_headers(), |client| client.get(LYRIC_SEARCH_URL).query(&params)).await?;
+    let candidate = found.candidates.into_iter().next().ok_or_else(|| NeteaseError::NotFound(what.clone()))?;
+
+    let params = [
+        ("ver", "1"),
+        ("client", "pc"),
+        ("id", candidate.id.as_str()),
+        ("accesskey", candidate.accesskey.as_str()),
+        ("fmt", "krc"),
+        ("charset", "utf8"),
+    ];
+    let data: LyricDownloadResponse =
+        fetch_json(&what, &build_headers(), |client| client.get(LYRIC_DOWNLOAD_URL).query(&params)).await?;
+    if data.status != 200 || data.content.is_empty() {
+        return Err(NeteaseError::NotFound(what));
+    }
+
+    let raw = general_purpose::STANDARD
+        .decode(data.content.trim())
+        .map_err(|e| NeteaseError::Parse(format!("{what}: invalid base64 lyric: {e}")))?;
+    let text = decrypt_krc(&raw).map_err(|e| NeteaseError::Parse(format!("{what}: {e}")))?;
+    let krc = parse_krc(&text);
+
+    Ok(LyricsData {
+        lrc: word_lines_to_lrc(&krc.lines),
+        tlyric: if krc.translation.is_empty() { String::new() } else { sync_entries_to_lrc(&krc.translation) },
+        romalrc: if krc.romanization.is_empty() { String::new() } else { sync_entries_to_lrc(&krc.romanization) },
+        ..Default::default()
+    }
+    .with_words(krc.lines)
+    .classified())
+}
+
+/// 酷狗音乐歌词来源
+pub struct KugouProvider;
+
+impl LyricsProvider for KugouProvider {
+    fn name(&self) -> &'static str {
+        "kugou"
+    }
+
+    fn is_available(&self) -> bool {
+        true
+    }
+
+    fn search<'a>(&'a self, query: &'a str, limit: u32, offset: u32) -> ProviderFuture<'a, Vec<ProviderSong>> {
+        Box::pin(async move { Ok(tag_songs(self.name(), search_songs(query, limit, offset).await?)) })
+    }
+
+    fn get_lyrics<'a>(&'a self, song_id: &'a str) -> ProviderFuture<'a, LyricsData> {
+        Box::pin(get_lyrics(song_id))
+    }
+}
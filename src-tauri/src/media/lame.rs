@@ -0,0 +1,290 @@
+//! MP3 Xing/Info/VBRI 与 LAME 头部解析模块
+//!
+//! 元数据展示和无缝播放的编码器延迟裁剪共用这里的解析结果。
+
+use crate::paths::long_path;
+use crate::tr;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// 在首帧之前最多搜索的字节数
+const SYNC_SEARCH_WINDOW: usize = 8192;
+
+/// 码率模式
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum BitrateMode {
+    Cbr,
+    Vbr,
+    Abr,
+    Unknown,
+}
+
+/// MP3 编码信息
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Mp3Info {
+    /// 信息头类型："Xing"、"Info" 或 "VBRI"
+    pub header: Option<String>,
+    /// LAME 头中的编码器版本（如 "LAME3.100"）
+    pub encoder: Option<String>,
+    pub bitrate_mode: BitrateMode,
+    /// 编码器在开头插入的采样数
+    pub encoder_delay: Option<u32>,
+    /// 编码器在末尾补齐的采样数
+    pub encoder_padding: Option<u32>,
+    /// 音频帧总数
+    pub frame_count: Option<u32>,
+}
+
+fn read_u32_be(bytes: &[u8], offset: usize) -> Option<u32> {
+    bytes
+        .get(offset..offset + 4)
+        .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+/// 计算 ID3v2 标签占用的字节数，没有标签时返回 0
+#[must_use]
+pub fn id3v2_size(header: &[u8]) -> u64 {
+    if header.len() < 10 || &header[0..3] != b"ID3" {
+        return 0;
+    }
+    let size = header[6..10]
+        .iter()
+        .fold(0u64, |acc, b| (acc << 7) | u64::from(b & 0x7F));
+    let footer = if header[5] & 0x10 != 0 { 10 } else { 0 };
+    10 + size + footer
+}
+
+/// 查找第一个 MPEG Layer III 帧头
+fn find_frame_sync(bytes: &[u8]) -> Option<usize> {
+    bytes.windows(4).position(|w| {
+        w[0] == 0xFF
+            && w[1] & 0xE0 == 0xE0
+            && (w[1] >> 3) & 0x03 != 0x01
+            && (w[1] >> 1) & 0x03 == 0x01
+            && w[2] >> 4 != 0x0F
+            && (w[2] >> 2) & 0x03 != 0x03
+    })
+}
+
+/// 从首帧开始的字节中解析 Xing/Info/VBRI 与 LAME 头
+#[must_use]
+pub fn parse_first_frame(frame: &[u8]) -> Option<Mp3Info> {
+    if frame.len() < 4 {
+        return None;
+    }
+
+    let is_mpeg1 = (frame[1] >> 3) & 0x03 == 0x03;
+    let is_mono = (frame[3] >> 6) & 0x03 == 0x03;
+    let side_info = match (is_mpeg1, is_mono) {
+        (true, true) => 17,
+        (true, false) => 32,
+        (false, true) => 9,
+        (false, false) => 17,
+    };
+
+    let xing_offset = 4 + side_info;
+    match frame.get(xing_offset..xing_offset + 4) {
+        Some(b"Xing" | b"Info") => Some(parse_xing(frame, xing_offset)),
+        _ if frame.get(36..40) == Some(b"VBRI") => Some(Mp3Info {
+            header: Some("VBRI".to_string()),
+            encoder: Some("Fraunhofer".to_string()),
+            bitrate_mode: BitrateMode::Vbr,
+            encoder_delay: frame.get(42..44).map(|b| u32::from(u16::from_be_bytes([b[0], b[1]]))),
+            encoder_padding: None,
+            frame_count: read_u32_be(frame, 50),
+        }),
+        _ => Some(Mp3Info {
+            header: None,
+            encoder: None,
+            bitrate_mode: BitrateMode::Unknown,
+            encoder_delay: None,
+            encoder_padding: None,
+            frame_count: None,
+        }),
+    }
+}
+
+/// 解析 Xing/Info 头及其后的 LAME 扩展
+fn parse_xing(frame: &[u8], offset: usize) -> Mp3Info {
+    let is_info = &frame[offset..offset + 4] == b"Info";
+    let flags = read_u32_be(frame, offset + 4).unwrap_or(0);
+
+    let mut cursor = offset + 8;
+    let mut frame_count = None;
+    if flags & 0x01 != 0 {
+        frame_count = read_u32_be(frame, cursor);
+        cursor += 4;
+    }
+    if flags & 0x02 != 0 {
+        cursor += 4;
+    }
+    if flags & 0x04 != 0 {
+        cursor += 100;
+    }
+    if flags & 0x08 != 0 {
+        cursor += 4;
+    }
+
+    let mut info = Mp3Info {
+        header: Some(if is_info { "Info" } else { "Xing" }.to_string()),
+        encoder: None,
+        bitrate_mode: if is_info { BitrateMode::Cbr } else { BitrateMode::Vbr },
+        encoder_delay: None,
+        encoder_padding: None,
+        frame_count,
+    };
+
+    // LAME 扩展：9 字节版本号、版本/VBR 方法、……、偏移 21 处的 3 字节延迟/补齐
+    let Some(lame) = frame.get(cursor..cursor + 24) else {
+        return info;
+    };
+    let version = String::from_utf8_lossy(&lame[0..9])
+        .trim_end_matches(['\0', ' '])
+        .to_string();
+    if !version.is_empty() && version.chars().all(|c| c.is_ascii_graphic() || c == ' ') {
+        info.encoder = Some(version);
+    }
+
+    if lame.starts_with(b"LAME") || lame.starts_with(b"Lavc") || lame.starts_with(b"Lavf") {
+        info.bitrate_mode = match lame[9] & 0x0F {
+            1 | 8 => BitrateMode::Cbr,
+            2 | 9 => BitrateMode::Abr,
+            3..=6 => BitrateMode::Vbr,
+            _ => info.bitrate_mode,
+        };
+        let delay = (u32::from(lame[21]) << 4) | (u32::from(lame[22]) >> 4);
+        let padding = (u32::from(lame[22] & 0x0F) << 8) | u32::from(lame[23]);
+        info.encoder_delay = Some(delay);
+        info.encoder_padding = Some(padding);
+    }
+
+    info
+}
+
+/// 读取 MP3 文件首帧中的编码信息
+pub fn read_mp3_info(path: &Path) -> Result<Option<Mp3Info>, String> {
+    let mut file = File::open(long_path(path)).map_err(|e| tr!("file.openFailed", detail = e))?;
+
+    let mut header = [0u8; 10];
+    let read = file.read(&mut header).map_err(|e| tr!("file.readFailed", detail = e))?;
+    let start = id3v2_size(&header[..read]);
+
+    file.seek(SeekFrom::Start(start)).map_err(|e| tr!("lame.seekFailed", detail = e))?;
+    let mut buffer = Vec::with_capacity(SYNC_SEARCH_WINDOW);
+    file.take(SYNC_SEARCH_WINDOW as u64)
+        .read_to_end(&mut buffer)
+        .map_err(|e| tr!("lame.frameReadFailed", detail = e))?;
+
+    Ok(find_frame_sync(&buffer).and_then(|pos| parse_first_frame(&buffer[pos..])))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    /// MPEG-1 Layer III、128 kbps、44.1 kHz、立体声的帧头
+    const FRAME_HEADER: [u8; 4] = [0xFF, 0xFB, 0x90, 0x00];
+
+    /// 首帧：帧头、32 字节边信息，之后是 `tag`（Xing/Info 头）
+    fn frame(tag: &[u8]) -> Vec<u8> {
+        let mut frame = FRAME_HEADER.to_vec();
+        frame.extend([0; 32]);
+        frame.extend(tag);
+        frame.resize(frame.len().max(417), 0);
+        frame
+    }
+
+    /// Xing/Info 头（帧数、字节数、TOC、质量四项都有）加 LAME 扩展
+    fn xing(name: [u8; 4], frames: u32, lame: Option<(&[u8; 9], u8, u32, u32)>) -> Vec<u8> {
+        let mut tag = name.to_vec();
+        tag.extend(0x0Fu32.to_be_bytes());
+        tag.extend(frames.to_be_bytes());
+        tag.extend(417u32.to_be_bytes());
+        tag.extend([0; 100]);
+        tag.extend(50u32.to_be_bytes());
+        if let Some((version, method, delay, padding)) = lame {
+            let mut ext = version.to_vec();
+            ext.push(method);
+            ext.resize(21, 0);
+            let packed = (delay << 12) | padding;
+            ext.extend(&packed.to_be_bytes()[1..]);
+            tag.extend(ext);
+        }
+        tag
+    }
+
+    #[test]
+    fn lame_header_gives_the_gapless_delay_and_padding() {
+        let info = parse_first_frame(&frame(&xing(*b"Xing", 1_000, Some((b"LAME3.100", 0x03, 576, 1_152))))).unwrap();
+        assert_eq!(info.header.as_deref(), Some("Xing"));
+        assert_eq!(info.encoder.as_deref(), Some("LAME3.100"));
+        assert_eq!(info.bitrate_mode, BitrateMode::Vbr);
+        assert_eq!(info.encoder_delay, Some(576));
+        assert_eq!(info.encoder_padding, Some(1_152));
+        assert_eq!(info.frame_count, Some(1_000));
+
+        // Info 头表示 CBR，LAME 扩展中的方法 1 同样是 CBR；延迟和补齐的 12 位边界
+        let info = parse_first_frame(&frame(&xing(*b"Info", 20, Some((b"LAME3.99r", 0x01, 4_095, 1))))).unwrap();
+        assert_eq!(info.header.as_deref(), Some("Info"));
+        assert_eq!(info.bitrate_mode, BitrateMode::Cbr);
+        assert_eq!((info.encoder_delay, info.encoder_padding), (Some(4_095), Some(1)));
+    }
+
+    #[test]
+    fn frames_without_an_info_header_have_no_gapless_data() {
+        let info = parse_first_frame(&frame(&[])).unwrap();
+        assert_eq!(info.header, None);
+        assert_eq!(info.bitrate_mode, BitrateMode::Unknown);
+        assert_eq!((info.encoder_delay, info.encoder_padding), (None, None));
+
+        // 只有 Xing 头、没有 LAME 扩展
+        let info = parse_first_frame(&frame(&xing(*b"Xing", 300, None))).unwrap();
+        assert_eq!(info.frame_count, Some(300));
+        assert_eq!(info.encoder, None);
+        assert_eq!(info.encoder_delay, None);
+    }
+
+    #[test]
+    fn truncated_frames_are_parsed_as_far_as_possible() {
+        assert!(parse_first_frame(&FRAME_HEADER[..3]).is_none());
+
+        // LAME 扩展被截断：保留 Xing 头中的信息，不给出延迟
+        let mut bytes = FRAME_HEADER.to_vec();
+        bytes.extend([0; 32]);
+        bytes.extend(xing(*b"Xing", 1_000, Some((b"LAME3.100", 0x03, 576, 1_152))));
+        bytes.truncate(bytes.len() - 4);
+        let info = parse_first_frame(&bytes).unwrap();
+        assert_eq!(info.frame_count, Some(1_000));
+        assert_eq!(info.encoder_delay, None);
+
+        // Xing 标志之后就结束：没有帧数
+        bytes.truncate(4 + 32 + 6);
+        let info = parse_first_frame(&bytes).unwrap();
+        assert_eq!(info.header.as_deref(), Some("Xing"));
+        assert_eq!(info.frame_count, None);
+    }
+
+    #[test]
+    fn the_first_frame_is_found_after_the_id3v2_tag() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("track.mp3");
+        // 20 字节的 ID3v2 标签（同步安全整数），其后是几个无效字节和首帧
+        let mut bytes = b"ID3\x04\x00\x00\x00\x00\x00\x14".to_vec();
+        bytes.extend([0; 20]);
+        bytes.extend([0x00, 0xFF, 0x00]);
+        bytes.extend(frame(&xing(*b"Xing", 42, Some((b"LAME3.100", 0x03, 576, 700)))));
+        fs::write(&path, bytes).unwrap();
+
+        let info = read_mp3_info(&path).unwrap().unwrap();
+        assert_eq!(info.frame_count, Some(42));
+        assert_eq!(info.encoder_padding, Some(700));
+
+        assert!(read_mp3_info(&dir.path().join("missing.mp3")).is_err());
+    }
+}
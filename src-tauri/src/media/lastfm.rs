@@ -0,0 +1,192 @@
+//! Last.fm 接口
+//!
+//! 桌面程序的授权流程：用 `auth.getToken` 取得令牌，用户在浏览器中打开授权页面同意后，用同一个令牌
+//! 调用 `auth.getSession` 换取长期有效的会话密钥。之后的“正在播放”和播放记录都用会话密钥签名提交。
+//! 所有请求都按接口规定用 API 密钥和共享密钥计算 `api_sig`。
+
+use super::scrobble::ScrobbleTrack;
+use crate::media::http_client::{get_client, is_offline};
+use crate::tr;
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+
+const API_URL: &str = "https://ws.audioscrobbler.com/2.0/";
+const AUTH_URL: &str = "https://www.last.fm/api/auth/";
+
+/// 一次最多提交的播放记录数
+pub const MAX_BATCH: usize = 50;
+
+/// 接口凭据（在 Last.fm 网站上申请的 API 账号）
+#[derive(Debug, Clone)]
+pub struct Credentials {
+    pub api_key: String,
+    pub api_secret: String,
+}
+
+/// 授权请求：用户在浏览器中打开 `url` 同意授权后，用 `token` 完成授权
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthRequest {
+    pub url: String,
+    pub token: String,
+}
+
+/// 授权后得到的会话
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub key: String,
+    /// 用户名
+    pub name: String,
+}
+
+/// Last.fm 请求的错误
+#[derive(Debug, Clone)]
+pub enum LastfmError {
+    /// 网络问题或服务器错误
+    Network(String),
+    /// 离线模式已开启，没有发送请求
+    Offline,
+    /// 接口返回的错误码和说明
+    Api { code: u64, message: String },
+}
+
+impl LastfmError {
+    /// 是否应该保留播放记录稍后重试：网络问题、操作失败（8）、会话失效（9，重新授权后可以提交）、
+    /// 服务暂时不可用（11、16）和超出频率限制（29）
+    #[must_use]
+    pub const fn is_retryable(&self) -> bool {
+        match self {
+            Self::Network(_) | Self::Offline => true,
+            Self::Api { code, .. } => matches!(code, 8 | 9 | 11 | 16 | 29),
+        }
+    }
+}
+
+impl fmt::Display for LastfmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Network(message) => write!(f, "Network error: {message}"),
+            Self::Offline => f.write_str(&tr!("network.offline")),
+            Self::Api { code, message } => write!(f, "Last.fm error {code}: {message}"),
+        }
+    }
+}
+
+/// 按名称排序后拼接全部参数和共享密钥，取 MD5
+fn sign(params: &[(String, String)], secret: &str) -> String {
+    let mut sorted: Vec<&(String, String)> = params.iter().collect();
+    sorted.sort_by(|a, b| a.0.cmp(&b.0));
+    let mut text: String = sorted.iter().flat_map(|(name, value)| [name.as_str(), value.as_str()]).collect();
+    text.push_str(secret);
+    format!("{:x}", md5::compute(text))
+}
+
+/// 发送一次签名的 POST 请求，返回解析后的 JSON
+async fn call(
+    method: &str,
+    mut params: Vec<(String, String)>,
+    credentials: &Credentials,
+) -> Result<Value, LastfmError> {
+    if is_offline() {
+        return Err(LastfmError::Offline);
+    }
+    params.push(("method".to_string(), method.to_string()));
+    params.push(("api_key".to_string(), credentials.api_key.clone()));
+    let signature = sign(&params, &credentials.api_secret);
+    params.push(("api_sig".to_string(), signature));
+    params.push(("format".to_string(), "json".to_string()));
+
+    let response = get_client()
+        .post(API_URL)
+        .form(&params)
+        .send()
+        .await
+        .map_err(|e| LastfmError::Network(format!("Request failed: {e}")))?;
+    let status = response.status();
+    let body = response.text().await.map_err(|e| LastfmError::Network(format!("Read response failed: {e}")))?;
+    let value: Option<Value> = serde_json::from_str(&body).ok();
+    if let Some(code) = value.as_ref().and_then(|v| v.get("error")).and_then(Value::as_u64) {
+        let message = value.as_ref().and_then(|v| v["message"].as_str()).unwrap_or_default().to_string();
+        return Err(LastfmError::Api { code, message });
+    }
+    match value {
+        Some(value) if status.is_success() => Ok(value),
+        _ => Err(LastfmError::Network(format!("HTTP error: {status}"))),
+    }
+}
+
+fn string_field(value: &Value, pointer: &str) -> Result<String, LastfmError> {
+    value
+        .pointer(pointer)
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| LastfmError::Api { code: 0, message: format!("Missing {pointer} in response") })
+}
+
+/// 申请授权令牌
+pub async fn get_token(credentials: &Credentials) -> Result<String, LastfmError> {
+    let value = call("auth.getToken", Vec::new(), credentials).await?;
+    string_field(&value, "/token")
+}
+
+/// 用户同意授权的页面地址
+#[must_use]
+pub fn auth_url(api_key: &str, token: &str) -> String {
+    format!("{AUTH_URL}?api_key={}&token={}", urlencoding::encode(api_key), urlencoding::encode(token))
+}
+
+/// 用户同意授权后，用令牌换取会话密钥
+pub async fn get_session(credentials: &Credentials, token: &str) -> Result<Session, LastfmError> {
+    let params = vec![("token".to_string(), token.to_string())];
+    let value = call("auth.getSession", params, credentials).await?;
+    Ok(Session { key: string_field(&value, "/session/key")?, name: string_field(&value, "/session/name")? })
+}
+
+/// 音轨的参数，批量提交时参数名带 `[序号]` 后缀
+fn track_params(track: &ScrobbleTrack, suffix: &str) -> Vec<(String, String)> {
+    let mut params = vec![
+        (format!("artist{suffix}"), track.artist.clone()),
+        (format!("track{suffix}"), track.title.clone()),
+        (format!("duration{suffix}"), track.duration_secs.to_string()),
+    ];
+    if let Some(album) = &track.album {
+        params.push((format!("album{suffix}"), album.clone()));
+    }
+    if let Some(album_artist) = &track.album_artist {
+        params.push((format!("albumArtist{suffix}"), album_artist.clone()));
+    }
+    if let Some(track_number) = track.track_number {
+        params.push((format!("trackNumber{suffix}"), track_number.to_string()));
+    }
+    if let Some(mbid) = &track.recording_mbid {
+        params.push((format!("mbid{suffix}"), mbid.clone()));
+    }
+    params
+}
+
+/// 发送“正在播放”
+pub async fn update_now_playing(
+    credentials: &Credentials,
+    session_key: &str,
+    track: &ScrobbleTrack,
+) -> Result<(), LastfmError> {
+    let mut params = track_params(track, "");
+    params.push(("sk".to_string(), session_key.to_string()));
+    call("track.updateNowPlaying", params, credentials).await.map(|_| ())
+}
+
+/// 提交播放记录，最多 [`MAX_BATCH`] 条
+pub async fn scrobble(
+    credentials: &Credentials,
+    session_key: &str,
+    tracks: &[ScrobbleTrack],
+) -> Result<(), LastfmError> {
+    let mut params = vec![("sk".to_string(), session_key.to_string())];
+    for (index, track) in tracks.iter().take(MAX_BATCH).enumerate() {
+        let suffix = format!("[{index}]");
+        params.extend(track_params(track, &suffix));
+        params.push((format!("timestamp{suffix}"), track.started_at.to_string()));
+    }
+    call("track.scrobble", params, credentials).await.map(|_| ())
+}
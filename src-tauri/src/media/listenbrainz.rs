@@ -0,0 +1,169 @@
+//! ListenBrainz 接口
+//!
+//! 用户令牌在 ListenBrainz 网站的设置页面中获取，请求时放在 `Authorization: Token ...` 头中。
+//! 播放记录（listen）可以一次提交多条；标签中有 MusicBrainz ID 时一并提交，便于服务端准确关联录音。
+
+use super::scrobble::ScrobbleTrack;
+use crate::media::http_client::{get_client, is_offline};
+use crate::tr;
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION, USER_AGENT};
+use reqwest::Response;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::fmt;
+
+const API_URL: &str = "https://api.listenbrainz.org/1";
+const CLIENT_NAME: &str = "Mercurial Player";
+
+/// 一次最多提交的播放记录数（接口上限为 1000，分小批失败时重试的量也少）
+pub const MAX_BATCH: usize = 100;
+
+/// ListenBrainz 请求的错误
+#[derive(Debug, Clone)]
+pub enum ListenBrainzError {
+    /// 网络问题
+    Network(String),
+    /// 离线模式已开启，没有发送请求
+    Offline,
+    /// 接口返回的 HTTP 状态和说明
+    Api { status: u16, message: String },
+}
+
+impl ListenBrainzError {
+    /// 是否应该保留播放记录稍后重试：网络问题、令牌无效（401，更换令牌后可以提交）、
+    /// 超出频率限制（429）和服务器错误
+    #[must_use]
+    pub const fn is_retryable(&self) -> bool {
+        match self {
+            Self::Network(_) | Self::Offline => true,
+            Self::Api { status, .. } => *status == 401 || *status == 429 || *status >= 500,
+        }
+    }
+}
+
+impl fmt::Display for ListenBrainzError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Network(message) => write!(f, "Network error: {message}"),
+            Self::Offline => f.write_str(&tr!("network.offline")),
+            Self::Api { status, message } => write!(f, "ListenBrainz error {status}: {message}"),
+        }
+    }
+}
+
+/// `validate-token` 的结果
+#[derive(Debug, Deserialize, Clone)]
+pub struct TokenValidation {
+    pub valid: bool,
+    pub user_name: Option<String>,
+}
+
+fn build_headers(token: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        USER_AGENT,
+        HeaderValue::from_static(concat!(
+            "MercurialPlayer/",
+            env!("CARGO_PKG_VERSION"),
+            " ( https://github.com/JDBeWL/MerPlayer )"
+        )),
+    );
+    if let Ok(value) = HeaderValue::from_str(&format!("Token {token}")) {
+        headers.insert(AUTHORIZATION, value);
+    }
+    headers
+}
+
+/// 读取响应内容，非成功状态时取出接口给出的错误说明
+async fn read_body(response: Response) -> Result<String, ListenBrainzError> {
+    let status = response.status();
+    let body = response.text().await.map_err(|e| ListenBrainzError::Network(format!("Read response failed: {e}")))?;
+    if !status.is_success() {
+        let message = serde_json::from_str::<Value>(&body)
+            .ok()
+            .and_then(|value| value["error"].as_str().map(str::to_string))
+            .unwrap_or_else(|| status.to_string());
+        return Err(ListenBrainzError::Api { status: status.as_u16(), message });
+    }
+    Ok(body)
+}
+
+/// 检查用户令牌，有效时返回用户名
+pub async fn validate_token(token: &str) -> Result<TokenValidation, ListenBrainzError> {
+    if is_offline() {
+        return Err(ListenBrainzError::Offline);
+    }
+    let response = get_client()
+        .get(format!("{API_URL}/validate-token"))
+        .headers(build_headers(token))
+        .send()
+        .await
+        .map_err(|e| ListenBrainzError::Network(format!("Request failed: {e}")))?;
+    let body = read_body(response).await?;
+    serde_json::from_str(&body)
+        .map_err(|e| ListenBrainzError::Api { status: 200, message: format!("Invalid response: {e}") })
+}
+
+/// 一条播放记录的音轨信息
+fn track_metadata(track: &ScrobbleTrack) -> Value {
+    let mut additional_info = json!({
+        "duration_ms": u64::from(track.duration_secs) * 1000,
+        "media_player": CLIENT_NAME,
+        "submission_client": CLIENT_NAME,
+        "submission_client_version": env!("CARGO_PKG_VERSION"),
+    });
+    if let Some(track_number) = track.track_number {
+        additional_info["tracknumber"] = json!(track_number);
+    }
+    if let Some(mbid) = &track.recording_mbid {
+        additional_info["recording_mbid"] = json!(mbid);
+    }
+    if let Some(mbid) = &track.release_mbid {
+        additional_info["release_mbid"] = json!(mbid);
+    }
+    if let Some(mbid) = &track.track_mbid {
+        additional_info["track_mbid"] = json!(mbid);
+    }
+    if !track.artist_mbids.is_empty() {
+        additional_info["artist_mbids"] = json!(track.artist_mbids);
+    }
+    let mut metadata = json!({
+        "artist_name": track.artist,
+        "track_name": track.title,
+        "additional_info": additional_info,
+    });
+    if let Some(album) = &track.album {
+        metadata["release_name"] = json!(album);
+    }
+    metadata
+}
+
+async fn submit(token: &str, listen_type: &str, payload: Vec<Value>) -> Result<(), ListenBrainzError> {
+    if is_offline() {
+        return Err(ListenBrainzError::Offline);
+    }
+    let response = get_client()
+        .post(format!("{API_URL}/submit-listens"))
+        .headers(build_headers(token))
+        .json(&json!({ "listen_type": listen_type, "payload": payload }))
+        .send()
+        .await
+        .map_err(|e| ListenBrainzError::Network(format!("Request failed: {e}")))?;
+    read_body(response).await.map(|_| ())
+}
+
+/// 发送“正在播放”
+pub async fn playing_now(token: &str, track: &ScrobbleTrack) -> Result<(), ListenBrainzError> {
+    submit(token, "playing_now", vec![json!({ "track_metadata": track_metadata(track) })]).await
+}
+
+/// 提交播放记录，最多 [`MAX_BATCH`] 条
+pub async fn submit_listens(token: &str, tracks: &[ScrobbleTrack]) -> Result<(), ListenBrainzError> {
+    let payload: Vec<Value> = tracks
+        .iter()
+        .take(MAX_BATCH)
+        .map(|track| json!({ "listened_at": track.started_at, "track_metadata": track_metadata(track) }))
+        .collect();
+    let listen_type = if payload.len() == 1 { "single" } else { "import" };
+    submit(token, listen_type, payload).await
+}
@@ -0,0 +1,159 @@
+//! 把在线歌词保存为 .lrc 文件
+//!
+//! 生成带 `[ti:]`、`[ar:]`、`[al:]`、`[by:]` 标签的标准 LRC，按选项在原文行后插入罗马音和翻译，
+//! 以不带 BOM 的 UTF-8 保存为音频文件旁的同名 .lrc 文件，查找歌词文件时会直接找到它。
+//! 本程序生成的文件带有 `[by:MerPlayer]`；用户自己的歌词文件只有明确要求覆盖时才会被替换。
+//! 音轨设置了歌词时间偏移时，按选项把偏移写入每个时间标签，或保留原始时间并写入 `[offset:]` 标签。
+
+use super::encoding::decode_text;
+use super::filesystem::write_lyrics_file_internal;
+use super::lyrics::{lrc_to_sync_entries, sync_entries_to_lrc};
+use super::lyrics_merge::merge_lyrics;
+use super::metadata::TrackMetadata;
+use super::netease::{LyricsData, LyricsKind};
+use crate::error::{AppError, AppResult};
+use crate::paths::long_path;
+use crate::tr;
+use serde::Deserialize;
+use std::fmt::Write as _;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+/// 写入 `[by:]` 标签的制作者，用于识别本程序生成的文件
+const LRC_CREATOR: &str = "MerPlayer";
+
+/// 歌词时间偏移的保存方式
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum LrcOffsetMode {
+    /// 平移每个时间标签
+    #[default]
+    Bake,
+    /// 保留原始时间，写入 `[offset:]` 标签
+    Tag,
+}
+
+/// 保存选项
+#[derive(Debug, Deserialize, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub struct LrcSaveOptions {
+    /// 在原文行后插入翻译
+    #[serde(default = "default_true")]
+    pub include_translation: bool,
+    /// 在原文行后插入罗马音
+    #[serde(default)]
+    pub include_romanization: bool,
+    /// 覆盖已有的用户歌词文件
+    #[serde(default)]
+    pub overwrite: bool,
+    #[serde(default)]
+    pub offset_mode: LrcOffsetMode,
+}
+
+const fn default_true() -> bool {
+    true
+}
+
+impl Default for LrcSaveOptions {
+    fn default() -> Self {
+        Self {
+            include_translation: true,
+            include_romanization: false,
+            overwrite: false,
+            offset_mode: LrcOffsetMode::Bake,
+        }
+    }
+}
+
+/// 标签值中不能出现换行和方括号
+fn tag_value(value: &str) -> String {
+    value.replace(['\r', '\n'], " ").replace('[', "(").replace(']', ")").trim().to_string()
+}
+
+/// 生成 LRC 文本：标签行之后是按时间排列的歌词行
+///
+/// `offset_ms` 为音轨的歌词时间偏移（正数表示歌词推后），`lyrics` 中已应用的偏移会先还原。
+#[must_use]
+pub fn compose_lrc(
+    metadata: Option<&TrackMetadata>,
+    lyrics: &LyricsData,
+    options: &LrcSaveOptions,
+    offset_ms: i64,
+) -> String {
+    let mut lrc = String::new();
+    if let Some(metadata) = metadata {
+        let tags =
+            [("ti", metadata.title.as_deref()), ("ar", metadata.artist.as_deref()), ("al", metadata.album.as_deref())];
+        for (tag, value) in tags {
+            if let Some(value) = value.map(tag_value).filter(|v| !v.is_empty()) {
+                let _ = writeln!(lrc, "[{tag}:{value}]");
+            }
+        }
+    }
+    let _ = writeln!(lrc, "[by:{LRC_CREATOR}]");
+
+    let lyrics = match options.offset_mode {
+        LrcOffsetMode::Bake => lyrics.clone().with_offset(offset_ms),
+        LrcOffsetMode::Tag => {
+            // LRC 的 offset 为正时歌词提前，与这里的约定相反
+            if offset_ms != 0 {
+                let _ = writeln!(lrc, "[offset:{}]", -offset_ms);
+            }
+            lyrics.clone().with_offset(0)
+        }
+    };
+
+    let translation = if options.include_translation { lyrics.tlyric.as_str() } else { "" };
+    let romanization = if options.include_romanization { lyrics.romalrc.as_str() } else { "" };
+    if translation.trim().is_empty() && romanization.trim().is_empty() {
+        // 去掉来源歌词自带的标签行，只保留带时间的行
+        lrc.push_str(&sync_entries_to_lrc(&lrc_to_sync_entries(&lyrics.lrc)));
+    } else {
+        lrc.push_str(&merge_lyrics(&lyrics.lrc, translation, romanization).lrc);
+    }
+    lrc
+}
+
+/// 是否为本程序生成的歌词文件
+fn is_generated(content: &str) -> bool {
+    content
+        .lines()
+        .map(str::trim)
+        .take_while(|line| line.starts_with('['))
+        .any(|line| line.strip_prefix("[by:").and_then(|rest| rest.strip_suffix(']')) == Some(LRC_CREATOR))
+}
+
+/// 把歌词保存为音频文件旁的同名 .lrc 文件，返回歌词文件路径
+///
+/// 已有不是本程序生成的歌词文件且 `overwrite` 为 `false` 时返回 `OverwriteConflict`，附带现有内容和要写入的内容。
+pub fn save_lyrics_as_lrc_internal(
+    audio_path: &str,
+    metadata: Option<&TrackMetadata>,
+    lyrics: &LyricsData,
+    options: &LrcSaveOptions,
+    offset_ms: i64,
+) -> AppResult<String> {
+    if lyrics.kind == LyricsKind::Instrumental {
+        return Err(AppError::Other(tr!("lyrics.instrumentalNoLrc")));
+    }
+    if lrc_to_sync_entries(&lyrics.lrc).is_empty() {
+        return Err(AppError::Other(tr!("lyrics.untimedNoLrc")));
+    }
+    let lrc_path = Path::new(audio_path).with_extension("lrc").to_string_lossy().to_string();
+    let content = compose_lrc(metadata, lyrics, options, offset_ms);
+
+    if !options.overwrite {
+        let existing = match fs::read(long_path(Path::new(&lrc_path))) {
+            Ok(bytes) => Some(decode_text(&bytes).text),
+            Err(e) if e.kind() == ErrorKind::NotFound => None,
+            Err(e) => return Err(e.into()),
+        };
+        if let Some(current) = existing.filter(|current| !is_generated(current) && *current != content) {
+            return Err(AppError::OverwriteConflict { path: lrc_path, current, proposed: content });
+        }
+    }
+
+    write_lyrics_file_internal(&lrc_path, &content, false, None)?;
+    Ok(lrc_path)
+}
@@ -0,0 +1,322 @@
+//! 内嵌歌词模块
+//!
+//! MP3 使用 USLT/SYLT 帧，FLAC/Ogg 使用 LYRICS 注释，M4A 使用 ©lyr，写入时替换已有歌词。
+
+use super::tag_writer::{is_mp3, read_id3_or_default, read_tagged_file, save_id3_atomic, save_tagged_file_atomic};
+use id3::frame::{Lyrics, SynchronisedLyrics, SynchronisedLyricsType, TimestampFormat};
+use id3::TagLike;
+use lofty::file::TaggedFileExt;
+use lofty::tag::{ItemKey, ItemValue, Tag, TagItem, TagType};
+use serde::Serialize;
+use std::fmt::Write as _;
+use std::path::Path;
+use crate::tr;
+
+/// USLT/SYLT 帧的语言代码（未指定）
+const LYRICS_LANGUAGE: &str = "und";
+
+/// 文件中嵌入的歌词
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct EmbeddedLyrics {
+    /// 歌词文本，同步歌词为 LRC 格式
+    pub text: String,
+    /// 是否带时间轴
+    pub synced: bool,
+}
+
+/// 解析 LRC 时间标签（mm:ss、mm:ss.xx、mm:ss.xxx 或 mm:ss:xx），返回毫秒
+fn parse_timestamp(tag: &str) -> Option<u32> {
+    let (minutes, rest) = tag.split_once(':')?;
+    let minutes: u32 = minutes.trim().parse().ok()?;
+    let (seconds, fraction) = match rest.split_once(['.', ':']) {
+        Some((seconds, fraction)) => (seconds, fraction),
+        None => (rest, ""),
+    };
+    let seconds: u32 = seconds.trim().parse().ok()?;
+    if seconds >= 60 || !fraction.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let millis = match fraction.len() {
+        0 => 0,
+        1 => fraction.parse::<u32>().ok()? * 100,
+        2 => fraction.parse::<u32>().ok()? * 10,
+        _ => fraction[..3].parse().ok()?,
+    };
+    Some((minutes * 60 + seconds) * 1000 + millis)
+}
+
+/// 将 LRC 文本转换为 SYLT 同步条目
+///
+/// 一行有多个时间标签时会生成多条；[ar:] 等元信息行被忽略。
+#[must_use]
+pub fn lrc_to_sync_entries(lrc: &str) -> Vec<(u32, String)> {
+    let mut entries = Vec::new();
+
+    for line in lrc.lines() {
+        let mut rest = line.trim();
+        let mut times = Vec::new();
+        while let Some(inner) = rest.strip_prefix('[') {
+            let Some(end) = inner.find(']') else { break };
+            let Some(time) = parse_timestamp(&inner[..end]) else { break };
+            times.push(time);
+            rest = &inner[end + 1..];
+        }
+        for time in times {
+            entries.push((time, rest.to_string()));
+        }
+    }
+
+    entries.sort_by_key(|(time, _)| *time);
+    entries
+}
+
+/// 按偏移（毫秒）平移时间，正数推后，不早于 0
+#[must_use]
+pub fn shift_time(time: u32, offset_ms: i64) -> u32 {
+    (i64::from(time) + offset_ms).clamp(0, i64::from(u32::MAX)) as u32
+}
+
+/// 平移 LRC 文本中的所有时间标签，[ar:] 等元信息行被去掉；没有时间标签时原样返回
+#[must_use]
+pub fn shift_lrc(lrc: &str, offset_ms: i64) -> String {
+    let entries = lrc_to_sync_entries(lrc);
+    if offset_ms == 0 || entries.is_empty() {
+        return lrc.to_string();
+    }
+    let shifted: Vec<(u32, String)> =
+        entries.into_iter().map(|(time, text)| (shift_time(time, offset_ms), text)).collect();
+    sync_entries_to_lrc(&shifted)
+}
+
+/// 将 SYLT 同步条目转换为 LRC 文本
+#[must_use]
+pub fn sync_entries_to_lrc(entries: &[(u32, String)]) -> String {
+    let mut lrc = String::new();
+    for (time, text) in entries {
+        let centis = time / 10;
+        let _ = writeln!(lrc, "[{:02}:{:02}.{:02}]{text}", centis / 6000, (centis / 100) % 60, centis % 100);
+    }
+    lrc
+}
+
+/// 读取文件中嵌入的歌词
+///
+/// MP3 优先读取 USLT，只有 SYLT 时转换为 LRC 文本。
+pub fn get_embedded_lyrics_internal(path: &str) -> Result<Option<EmbeddedLyrics>, String> {
+    let file_path = Path::new(path);
+
+    if is_mp3(file_path) {
+        let tag = read_id3_or_default(file_path)?;
+        let has_sylt = tag.synchronised_lyrics().next().is_some();
+        if let Some(lyrics) = tag.lyrics().next() {
+            return Ok(Some(EmbeddedLyrics {
+                synced: has_sylt || !lrc_to_sync_entries(&lyrics.text).is_empty(),
+                text: lyrics.text.clone(),
+            }));
+        }
+        return Ok(tag.synchronised_lyrics().next().map(|sylt| EmbeddedLyrics {
+            text: sync_entries_to_lrc(&sylt.content),
+            synced: true,
+        }));
+    }
+
+    let tagged_file = read_tagged_file(file_path)?;
+    Ok(tagged_file
+        .tags()
+        .iter()
+        .find_map(|tag| tag.get_string(&ItemKey::Lyrics))
+        .map(|text| EmbeddedLyrics {
+            synced: !lrc_to_sync_entries(text).is_empty(),
+            text: text.to_string(),
+        }))
+}
+
+/// 将歌词写入文件标签
+///
+/// MP3 的 `synced` 为 `true` 时写入 SYLT，同时在 USLT 中保留原始 LRC 文本，
+/// 供不支持 SYLT 的播放器显示；其他格式直接保存 LRC 文本。
+pub fn write_embedded_lyrics_internal(path: &str, lrc_text: &str, synced: bool) -> Result<(), String> {
+    let file_path = Path::new(path);
+
+    if is_mp3(file_path) {
+        let mut tag = read_id3_or_default(file_path)?;
+        tag.remove_all_lyrics();
+        tag.remove_all_synchronised_lyrics();
+
+        if synced {
+            let content = lrc_to_sync_entries(lrc_text);
+            if content.is_empty() {
+                return Err(tr!("lyrics.noTimestamps"));
+            }
+            tag.add_frame(SynchronisedLyrics {
+                lang: LYRICS_LANGUAGE.to_string(),
+                timestamp_format: TimestampFormat::Ms,
+                content_type: SynchronisedLyricsType::Lyrics,
+                description: String::new(),
+                content,
+            });
+        }
+        tag.add_frame(Lyrics {
+            lang: LYRICS_LANGUAGE.to_string(),
+            description: String::new(),
+            text: lrc_text.to_string(),
+        });
+
+        return save_id3_atomic(&tag, file_path);
+    }
+
+    let mut tagged_file = read_tagged_file(file_path)?;
+    if tagged_file.primary_tag().is_none() {
+        let tag_type = tagged_file.primary_tag_type();
+        if tag_type == TagType::Id3v1 {
+            return Err(tr!("lyrics.embedUnsupported"));
+        }
+        tagged_file.insert_tag(Tag::new(tag_type));
+    }
+
+    let tag = tagged_file.primary_tag_mut().ok_or_else(|| tr!("tags.createFailed"))?;
+    tag.remove_key(&ItemKey::Lyrics);
+    if !tag.insert(TagItem::new(ItemKey::Lyrics, ItemValue::Text(lrc_text.to_string()))) {
+        return Err(tr!("lyrics.embedUnsupported"));
+    }
+
+    save_tagged_file_atomic(&tagged_file, file_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    const PLAIN_LYRICS: &str = "故事的小黄花\n从出生那年就飘着\n\n童年的荡秋千 🎐\n随记忆一直晃到现在";
+    const SYNCED_LYRICS: &str =
+        "[00:29.10]故事的小黄花\n[00:32.50]从出生那年就飘着\n[00:36.20]童年的荡秋千\n[00:39.80]随记忆一直晃到现在\n";
+
+    /// 没有标签的 MP3：若干帧 MPEG-1 Layer III（128 kbps、44.1 kHz）
+    fn write_mp3(dir: &TempDir) -> String {
+        let mut frame = vec![0xFF, 0xFB, 0x90, 0x00];
+        frame.resize(417, 0);
+        let path = dir.path().join("track.mp3");
+        fs::write(&path, frame.repeat(20)).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    /// 只有 STREAMINFO 和 PADDING 块的 FLAC（10 秒、44.1 kHz、双声道、16 位）
+    ///
+    /// lofty 在 STREAMINFO 之后追加标签时不会清除它的最后一块标志，所以和编码器的输出一样留一个 PADDING 块。
+    fn write_flac(dir: &TempDir) -> String {
+        let mut bytes = b"fLaC".to_vec();
+        bytes.extend([0, 0, 0, 34]);
+        bytes.extend(4096u16.to_be_bytes());
+        bytes.extend(4096u16.to_be_bytes());
+        bytes.extend([0; 6]);
+        let packed: u64 = (44_100 << 44) | (1 << 41) | (15 << 36) | 0x6_BAA8;
+        bytes.extend(packed.to_be_bytes());
+        bytes.extend([0; 16]);
+        bytes.extend([0x81, 0, 0x04, 0]);
+        bytes.extend([0; 1024]);
+        bytes.extend([0; 1024]);
+        let path = dir.path().join("track.flac");
+        fs::write(&path, bytes).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    fn read(path: &str) -> EmbeddedLyrics {
+        get_embedded_lyrics_internal(path).unwrap().expect("lyrics should be embedded")
+    }
+
+    #[test]
+    fn plain_lyrics_round_trip_through_mp3() {
+        let dir = TempDir::new().unwrap();
+        let path = write_mp3(&dir);
+        assert!(get_embedded_lyrics_internal(&path).unwrap().is_none());
+
+        write_embedded_lyrics_internal(&path, PLAIN_LYRICS, false).unwrap();
+        let lyrics = read(&path);
+        assert_eq!(lyrics.text, PLAIN_LYRICS);
+        assert!(!lyrics.synced);
+    }
+
+    #[test]
+    fn synced_lyrics_round_trip_through_mp3_without_duplicate_frames() {
+        let dir = TempDir::new().unwrap();
+        let path = write_mp3(&dir);
+
+        write_embedded_lyrics_internal(&path, PLAIN_LYRICS, false).unwrap();
+        write_embedded_lyrics_internal(&path, SYNCED_LYRICS, true).unwrap();
+        write_embedded_lyrics_internal(&path, SYNCED_LYRICS, true).unwrap();
+        let lyrics = read(&path);
+        assert_eq!(lyrics.text, SYNCED_LYRICS);
+        assert!(lyrics.synced);
+
+        let tag = id3::Tag::read_from_path(&path).unwrap();
+        assert_eq!(tag.lyrics().count(), 1);
+        let sylt: Vec<_> = tag.synchronised_lyrics().collect();
+        assert_eq!(sylt.len(), 1);
+        assert_eq!(sylt[0].content, lrc_to_sync_entries(SYNCED_LYRICS));
+    }
+
+    /// 只有 SYLT 帧（其他软件写入）时转换为 LRC
+    #[test]
+    fn sylt_only_tags_are_read_as_lrc() {
+        let dir = TempDir::new().unwrap();
+        let path = write_mp3(&dir);
+        let mut tag = id3::Tag::new();
+        tag.add_frame(SynchronisedLyrics {
+            lang: LYRICS_LANGUAGE.to_string(),
+            timestamp_format: TimestampFormat::Ms,
+            content_type: SynchronisedLyricsType::Lyrics,
+            description: String::new(),
+            content: lrc_to_sync_entries(SYNCED_LYRICS),
+        });
+        tag.write_to_path(&path, id3::Version::Id3v24).unwrap();
+
+        let lyrics = read(&path);
+        assert_eq!(lyrics.text, SYNCED_LYRICS);
+        assert!(lyrics.synced);
+    }
+
+    #[test]
+    fn synced_lyrics_need_timestamps() {
+        let dir = TempDir::new().unwrap();
+        let path = write_mp3(&dir);
+        assert!(write_embedded_lyrics_internal(&path, PLAIN_LYRICS, true).is_err());
+        assert!(get_embedded_lyrics_internal(&path).unwrap().is_none());
+    }
+
+    #[test]
+    fn lyrics_round_trip_through_flac_and_replace_existing_ones() {
+        let dir = TempDir::new().unwrap();
+        let path = write_flac(&dir);
+
+        write_embedded_lyrics_internal(&path, PLAIN_LYRICS, false).unwrap();
+        let lyrics = read(&path);
+        assert_eq!(lyrics.text, PLAIN_LYRICS);
+        assert!(!lyrics.synced);
+
+        write_embedded_lyrics_internal(&path, SYNCED_LYRICS, true).unwrap();
+        let lyrics = read(&path);
+        assert_eq!(lyrics.text, SYNCED_LYRICS);
+        assert!(lyrics.synced);
+
+        let tagged_file = read_tagged_file(Path::new(&path)).unwrap();
+        let tag = tagged_file.primary_tag().unwrap();
+        assert_eq!(tag.get_strings(&ItemKey::Lyrics).count(), 1);
+    }
+
+    #[test]
+    fn lrc_and_sync_entries_round_trip() {
+        let entries = lrc_to_sync_entries("[ar:周杰伦]\n[00:36.20][01:10.05]童年的荡秋千\n[00:29.10]故事的小黄花\n");
+        assert_eq!(
+            entries,
+            [
+                (29_100, "故事的小黄花".to_string()),
+                (36_200, "童年的荡秋千".to_string()),
+                (70_050, "童年的荡秋千".to_string())
+            ]
+        );
+        assert_eq!(lrc_to_sync_entries(&sync_entries_to_lrc(&entries)), entries);
+    }
+}
@@ -0,0 +1,177 @@
+//! 批量下载歌词
+//!
+//! 为一组音轨逐个运行自动匹配流程。已有同名歌词文件或内嵌歌词的音轨直接跳过，
+//! 只保存到缓存时缓存中已有歌词的音轨也跳过，已确定为纯音乐的音轨不再请求，因此重复运行几乎不会发出请求。
+//! 下载的歌词按选项保存为音频旁的 .lrc 文件、只保存在缓存中或写入标签。
+
+use super::filesystem::write_lyrics_file_internal;
+use super::lyrics::{get_embedded_lyrics_internal, lrc_to_sync_entries, write_embedded_lyrics_internal};
+use super::lyrics_finder::find_lyrics_file_internal;
+use super::lyrics_match::{MatchCandidate, MatchScore};
+use super::lyrics_merge::merge_lyrics;
+use super::metadata::MetadataOptions;
+use super::netease::{LyricsData, NeteaseError};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// 每处理完一个文件发送的事件
+pub const LYRICS_BATCH_ITEM_EVENT: &str = "lyrics-batch-item";
+
+/// 下载的歌词保存到哪里
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum LyricsSaveTarget {
+    /// 保存为音频文件旁的同名 .lrc 文件
+    #[default]
+    LrcFile,
+    /// 只保存在在线歌词缓存中
+    CacheOnly,
+    /// 写入音频文件的标签
+    Embed,
+}
+
+/// 批量下载选项
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct LyricsBatchOptions {
+    #[serde(default)]
+    pub save_to: LyricsSaveTarget,
+    /// 结果中是否附带得分次高的候选
+    #[serde(default)]
+    pub include_runners_up: bool,
+    /// 网易云音乐专辑 ID：先按曲号和标题把音轨对应到该专辑的曲目，对应不上的再单独搜索
+    #[serde(default)]
+    pub album_id: Option<String>,
+}
+
+/// 单个文件的处理结果
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum LyricsBatchStatus {
+    /// 已匹配并保存
+    Matched,
+    /// 已有歌词，没有下载
+    AlreadyHad,
+    /// 没有足够可信的候选，需要手动选择
+    LowConfidence,
+    /// 纯音乐，没有歌词可保存
+    Instrumental,
+    Failed,
+}
+
+/// 单个文件的结果
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LyricsBatchItem {
+    pub path: String,
+    pub status: LyricsBatchStatus,
+    /// 匹配到的（或得分最高的候选）歌曲 ID
+    pub song_id: Option<String>,
+    /// 匹配得分（满分 1.0），没有候选时为 `None`
+    pub score: Option<f64>,
+    /// 得分的各个部分，使用以前的匹配结果时为 `None`
+    pub match_score: Option<MatchScore>,
+    /// 得分次高的候选（选项 `includeRunnersUp` 开启时）
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub runners_up: Vec<MatchCandidate>,
+    pub error: Option<String>,
+    /// 在线请求出错时的错误码（与在线命令返回的 `code` 相同，如 `RATE_LIMITED`、`OFFLINE_MODE`）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<String>,
+}
+
+impl LyricsBatchItem {
+    #[must_use]
+    pub fn new(path: &str, status: LyricsBatchStatus) -> Self {
+        Self {
+            path: path.to_string(),
+            status,
+            song_id: None,
+            score: None,
+            match_score: None,
+            runners_up: Vec::new(),
+            error: None,
+            error_code: None,
+        }
+    }
+
+    #[must_use]
+    pub fn failed(path: &str, error: impl ToString) -> Self {
+        Self { error: Some(error.to_string()), ..Self::new(path, LyricsBatchStatus::Failed) }
+    }
+
+    /// 在线请求出错，附带错误码
+    #[must_use]
+    pub fn failed_online(path: &str, error: &NeteaseError) -> Self {
+        Self { error_code: Some(error.code().to_string()), ..Self::failed(path, error) }
+    }
+}
+
+/// `lyrics-batch-item` 事件
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LyricsBatchItemEvent {
+    pub task_id: String,
+    pub completed: usize,
+    pub total: usize,
+    pub item: LyricsBatchItem,
+}
+
+/// 批量下载报告
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct LyricsBatchReport {
+    pub matched: Vec<LyricsBatchItem>,
+    /// 已有歌词而跳过的文件
+    pub skipped: Vec<LyricsBatchItem>,
+    /// 需要手动选择的文件
+    pub low_confidence: Vec<LyricsBatchItem>,
+    /// 纯音乐
+    pub instrumental: Vec<LyricsBatchItem>,
+    pub failed: Vec<LyricsBatchItem>,
+    /// 是否被取消（报告只包含取消前处理过的文件）
+    pub cancelled: bool,
+}
+
+impl LyricsBatchReport {
+    pub fn push(&mut self, item: LyricsBatchItem) {
+        match item.status {
+            LyricsBatchStatus::Matched => self.matched.push(item),
+            LyricsBatchStatus::AlreadyHad => self.skipped.push(item),
+            LyricsBatchStatus::LowConfidence => self.low_confidence.push(item),
+            LyricsBatchStatus::Instrumental => self.instrumental.push(item),
+            LyricsBatchStatus::Failed => self.failed.push(item),
+        }
+    }
+}
+
+/// 音轨是否已有歌词：找到同名歌词文件或标签中有歌词
+#[must_use]
+pub fn has_local_lyrics(path: &str, subfolders: &[String], options: &MetadataOptions) -> bool {
+    find_lyrics_file_internal(path, subfolders, options).best.is_some()
+        || get_embedded_lyrics_internal(path).ok().flatten().is_some_and(|lyrics| !lyrics.text.trim().is_empty())
+}
+
+/// 要保存的歌词文本：有翻译或罗马音时合并为多行 LRC
+#[must_use]
+pub fn lyrics_text(lyrics: &LyricsData) -> String {
+    if lyrics.tlyric.is_empty() && lyrics.romalrc.is_empty() {
+        lyrics.lrc.clone()
+    } else {
+        merge_lyrics(&lyrics.lrc, &lyrics.tlyric, &lyrics.romalrc).lrc
+    }
+}
+
+/// 按保存位置保存歌词；只保存到缓存时什么也不做（获取时已写入缓存）
+pub fn save_lyrics(path: &str, text: &str, target: LyricsSaveTarget) -> Result<(), String> {
+    match target {
+        LyricsSaveTarget::LrcFile => {
+            let lrc_path = Path::new(path).with_extension("lrc");
+            write_lyrics_file_internal(&lrc_path.to_string_lossy(), text, false, None)
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        }
+        LyricsSaveTarget::CacheOnly => Ok(()),
+        LyricsSaveTarget::Embed => write_embedded_lyrics_internal(path, text, !lrc_to_sync_entries(text).is_empty()),
+    }
+}
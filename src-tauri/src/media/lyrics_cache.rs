@@ -0,0 +1,170 @@
+//! 在线歌词缓存模块
+//!
+//! 下载的歌词按来源和歌曲 ID 保存为 JSON 文件（`<来源>-<歌曲 ID>.json`），记录获取时间。
+//! 未超过有效期的缓存直接使用；网络请求失败时退回过期的缓存，离线时也能显示听过的歌曲的歌词。
+//! 自动匹配的歌曲通过歌词匹配缓存（文件路径 -> 歌曲 ID）找到这里的条目。
+
+use super::netease::{LyricsData, LyricsKind};
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::fs;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::tr;
+
+/// 缓存条目
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CachedLyrics {
+    /// 获取时间（Unix 秒）
+    fetched_at: u64,
+    lyrics: LyricsData,
+}
+
+/// 歌词缓存统计
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct LyricsCacheStats {
+    pub entries: usize,
+    /// 已超过有效期的条目数
+    pub expired: usize,
+    pub total_bytes: u64,
+    /// 最早、最晚的获取时间（Unix 秒）
+    pub oldest_fetched_at: Option<u64>,
+    pub newest_fetched_at: Option<u64>,
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
+
+/// 在线歌词磁盘缓存
+pub struct LyricsCache {
+    dir: PathBuf,
+    /// 有效期（秒），`None` 表示永不过期
+    ttl_secs: Option<u64>,
+}
+
+impl LyricsCache {
+    /// `ttl_days` 为 0 时缓存永不过期
+    #[must_use]
+    pub fn new(dir: PathBuf, ttl_days: u32) -> Self {
+        let ttl_secs = (ttl_days > 0).then(|| u64::from(ttl_days) * 24 * 60 * 60);
+        Self { dir, ttl_secs }
+    }
+
+    fn entry_path(&self, provider: &str, song_id: &str) -> PathBuf {
+        // 歌曲 ID 会拼进文件名，去掉路径分隔符等字符
+        let id: String = song_id.chars().filter(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_')).collect();
+        self.dir.join(format!("{provider}-{id}.json"))
+    }
+
+    fn is_fresh(&self, fetched_at: u64) -> bool {
+        self.ttl_secs.is_none_or(|ttl| unix_now().saturating_sub(fetched_at) < ttl)
+    }
+
+    /// 是否有该歌曲未过期的缓存
+    #[must_use]
+    pub fn has_fresh(&self, provider: &str, song_id: &str) -> bool {
+        read_entry(&self.entry_path(provider, song_id)).is_some_and(|entry| self.is_fresh(entry.fetched_at))
+    }
+
+    /// 先写临时文件再重命名，避免留下不完整的条目
+    fn write(&self, provider: &str, song_id: &str, lyrics: &LyricsData) -> Result<(), String> {
+        let entry = CachedLyrics { fetched_at: unix_now(), lyrics: lyrics.clone() };
+        let content = serde_json::to_string(&entry).map_err(|e| tr!("lyrics.cacheSerializeFailed", detail = e))?;
+        let path = self.entry_path(provider, song_id);
+        let temp_path = path.with_extension("json.tmp");
+        fs::write(&temp_path, content)
+            .and_then(|()| fs::rename(&temp_path, &path))
+            .map_err(|e| tr!("lyrics.cacheWriteFailed", detail = e))
+    }
+
+    /// 获取歌词：缓存未过期时直接返回，否则调用 `fetch` 下载并写入缓存
+    ///
+    /// `force_refresh` 时跳过缓存；下载失败但有过期的缓存时返回过期的缓存。
+    pub async fn get_or_fetch<F, Fut, E>(
+        &self,
+        provider: &str,
+        song_id: &str,
+        force_refresh: bool,
+        fetch: F,
+    ) -> Result<LyricsData, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<LyricsData, E>>,
+        E: Display,
+    {
+        let cached = read_entry(&self.entry_path(provider, song_id));
+        if !force_refresh
+            && let Some(entry) = &cached
+            && self.is_fresh(entry.fetched_at)
+        {
+            return Ok(entry.lyrics.clone());
+        }
+
+        match fetch().await {
+            Ok(lyrics) => {
+                if let Err(e) = self.write(provider, song_id, &lyrics) {
+                    eprintln!("Failed to save lyrics cache: {e}");
+                }
+                Ok(lyrics)
+            }
+            Err(e) => match cached {
+                Some(entry) => {
+                    eprintln!("Failed to refresh lyrics for {provider} song {song_id}, using cached copy: {e}");
+                    Ok(entry.lyrics)
+                }
+                None => Err(e),
+            },
+        }
+    }
+
+    fn entry_files(&self) -> Vec<PathBuf> {
+        fs::read_dir(&self.dir)
+            .map(|entries| {
+                entries
+                    .filter_map(Result::ok)
+                    .map(|entry| entry.path())
+                    .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// 统计缓存条目数、占用空间和获取时间范围
+    #[must_use]
+    pub fn stats(&self) -> LyricsCacheStats {
+        let mut stats = LyricsCacheStats::default();
+        for path in self.entry_files() {
+            let Some(entry) = read_entry(&path) else {
+                continue;
+            };
+            stats.entries += 1;
+            stats.total_bytes += fs::metadata(&path).map_or(0, |m| m.len());
+            if !self.is_fresh(entry.fetched_at) {
+                stats.expired += 1;
+            }
+            let fetched_at = entry.fetched_at;
+            stats.oldest_fetched_at = Some(stats.oldest_fetched_at.map_or(fetched_at, |t| t.min(fetched_at)));
+            stats.newest_fetched_at = Some(stats.newest_fetched_at.map_or(fetched_at, |t| t.max(fetched_at)));
+        }
+        stats
+    }
+
+    /// 删除所有缓存条目，返回删除的数量
+    pub fn clear(&self) -> usize {
+        self.entry_files().into_iter().filter(|path| fs::remove_file(path).is_ok()).count()
+    }
+}
+
+fn read_entry(path: &Path) -> Option<CachedLyrics> {
+    let mut entry: CachedLyrics = serde_json::from_str(&fs::read_to_string(path).ok()?).ok()?;
+    // 旧版条目没有记录类型（读作 `Synced`），按内容重新判断
+    if entry.lyrics.kind == LyricsKind::Synced {
+        entry.lyrics = entry.lyrics.classified();
+    }
+    entry.lyrics.word_level = !entry.lyrics.words.is_empty();
+    Some(entry)
+}
@@ -0,0 +1,174 @@
+//! 外部歌词文件查找模块
+//!
+//! 在音频文件所在目录及配置的歌词子目录（如 `Lyrics/`）中查找歌词文件，依次按以下规则匹配：
+//! 与音频文件名（不含扩展名）完全相同；忽略大小写、标点和空白后相同；与标签中的标题模糊匹配。
+//! `.txt` 文件只有包含 LRC 时间标签时才被接受。
+
+use super::encoding::decode_text;
+use super::lyrics::lrc_to_sync_entries;
+use super::metadata::MetadataOptions;
+use super::metadata_cache::cached_track_metadata;
+use super::text::{edit_similarity, search_key};
+use crate::paths::{long_path, resolved_key};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// 歌词文件扩展名，按优先级排列
+const LYRICS_EXTENSIONS: &[&str] = &["lrc", "ass", "srt", "txt"];
+
+/// 检查 `.txt` 文件是否为 LRC 歌词时最多读取的字节数
+const TXT_SNIFF_BYTES: u64 = 64 * 1024;
+
+/// 标题模糊匹配的最低相似度
+const MIN_TITLE_SIMILARITY: f64 = 0.8;
+
+/// 匹配可信度，从高到低排列
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum LyricsMatchConfidence {
+    /// 文件名与音频文件完全相同
+    Exact,
+    /// 忽略大小写、标点和空白后相同
+    Normalized,
+    /// 与标签标题模糊匹配
+    Title,
+}
+
+/// 候选歌词文件
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LyricsCandidate {
+    pub path: String,
+    pub confidence: LyricsMatchConfidence,
+}
+
+/// 歌词文件查找结果
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct LyricsFileMatch {
+    /// 最佳匹配，没有候选时为 `None`
+    pub best: Option<LyricsCandidate>,
+    /// 其余候选，按可信度从高到低排列
+    pub candidates: Vec<LyricsCandidate>,
+}
+
+/// 比较用的键：去除变音符号并转为小写，只保留字母和数字
+fn match_key(value: &str) -> String {
+    search_key(value).chars().filter(|c| c.is_alphanumeric()).collect()
+}
+
+/// 去掉文件名开头的音轨号，如 `01 - Title`、`01. Title`、`1-01 Title`
+fn strip_track_number(stem: &str) -> &str {
+    let rest = stem.trim_start_matches(|c: char| c.is_ascii_digit() || matches!(c, '-' | '.' | '_' | ' '));
+    if rest.is_empty() { stem } else { rest }
+}
+
+/// 文件名是否与标题模糊匹配：去掉音轨号后相似，或包含完整标题（如 `Artist - Title`）
+fn matches_title(stem: &str, title_key: &str) -> bool {
+    if title_key.is_empty() {
+        return false;
+    }
+    let stem_key = match_key(strip_track_number(stem));
+    // 过短的标题容易误匹配，只接受相似度判断
+    (title_key.chars().count() >= 3 && stem_key.contains(title_key))
+        || edit_similarity(&stem_key, title_key) >= MIN_TITLE_SIMILARITY
+}
+
+/// `.txt` 文件是否包含 LRC 时间标签（只读取文件开头）
+#[must_use]
+pub fn is_lrc_text(path: &Path) -> bool {
+    let mut bytes = Vec::new();
+    if File::open(long_path(path)).and_then(|file| file.take(TXT_SNIFF_BYTES).read_to_end(&mut bytes)).is_err() {
+        return false;
+    }
+    !lrc_to_sync_entries(&decode_text(&bytes).text).is_empty()
+}
+
+fn lyrics_extension(path: &Path) -> Option<usize> {
+    let ext = path.extension()?.to_str()?;
+    LYRICS_EXTENSIONS.iter().position(|candidate| candidate.eq_ignore_ascii_case(ext))
+}
+
+/// 路径的扩展名是否为歌词文件（lrc、ass、srt、txt）
+#[must_use]
+pub fn is_lyrics_file(path: &Path) -> bool {
+    lyrics_extension(path).is_some()
+}
+
+/// 要查找的目录：音频所在目录和其中存在的歌词子目录，大小写不敏感的文件系统上重复的目录只保留一个
+fn search_dirs(dir: &Path, subfolders: &[String]) -> Vec<PathBuf> {
+    let mut seen = HashSet::new();
+    std::iter::once(dir.to_path_buf())
+        .chain(subfolders.iter().filter(|name| !name.trim().is_empty()).map(|name| dir.join(name.trim())))
+        .filter(|candidate| candidate.is_dir())
+        .filter(|candidate| seen.insert(resolved_key(&candidate.to_string_lossy())))
+        .collect()
+}
+
+/// 查找音频文件对应的歌词文件
+///
+/// 同一可信度下音频所在目录优先于子目录，扩展名按 lrc、ass、srt、txt 的顺序优先。
+/// 只有仍有未按文件名匹配的文件时才读取标签标题。
+#[must_use]
+pub fn find_lyrics_file_internal(audio_path: &str, subfolders: &[String], options: &MetadataOptions) -> LyricsFileMatch {
+    let audio = Path::new(audio_path);
+    let (Some(dir), Some(stem)) = (audio.parent(), audio.file_stem().map(|stem| stem.to_string_lossy().to_string()))
+    else {
+        return LyricsFileMatch::default();
+    };
+    let stem_key = match_key(&stem);
+
+    // (目录序号, 扩展名序号, 路径, 文件名)
+    let mut files: Vec<(usize, usize, PathBuf, String)> = Vec::new();
+    for (dir_index, search_dir) in search_dirs(dir, subfolders).iter().enumerate() {
+        let Ok(entries) = fs::read_dir(long_path(search_dir)) else {
+            continue;
+        };
+        for entry in entries.filter_map(Result::ok) {
+            let path = search_dir.join(entry.file_name());
+            let (Some(ext_index), Some(file_stem)) = (lyrics_extension(&path), path.file_stem()) else {
+                continue;
+            };
+            if path.is_file() {
+                let file_stem = file_stem.to_string_lossy().to_string();
+                files.push((dir_index, ext_index, path, file_stem));
+            }
+        }
+    }
+
+    let mut title_key: Option<String> = None;
+    let mut matched: Vec<(LyricsMatchConfidence, usize, usize, PathBuf)> = Vec::new();
+    for (dir_index, ext_index, path, file_stem) in files {
+        let confidence = if file_stem == stem {
+            LyricsMatchConfidence::Exact
+        } else if !stem_key.is_empty() && match_key(&file_stem) == stem_key {
+            LyricsMatchConfidence::Normalized
+        } else {
+            let title = title_key.get_or_insert_with(|| {
+                cached_track_metadata(audio_path, options)
+                    .ok()
+                    .and_then(|track| track.title)
+                    .map(|title| match_key(&title))
+                    .unwrap_or_default()
+            });
+            if !matches_title(&file_stem, title) {
+                continue;
+            }
+            LyricsMatchConfidence::Title
+        };
+        if LYRICS_EXTENSIONS[ext_index] == "txt" && !is_lrc_text(&path) {
+            continue;
+        }
+        matched.push((confidence, dir_index, ext_index, path));
+    }
+
+    matched.sort();
+    let mut candidates = matched.into_iter().map(|(confidence, _, _, path)| LyricsCandidate {
+        path: path.to_string_lossy().to_string(),
+        confidence,
+    });
+    LyricsFileMatch { best: candidates.next(), candidates: candidates.collect() }
+}
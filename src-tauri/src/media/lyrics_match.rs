@@ -0,0 +1,625 @@
+//! 歌词自动匹配模块
+//!
+//! 根据音轨的标题、艺术家、专辑和时长在在线来源中搜索，按标题、艺术家、专辑的编辑距离相似度和时长差给候选打分，
+//! 各部分得分随结果返回，便于界面解释匹配原因。
+//! 搜索结果的时长和专辑不够准确时，得分最高的几个候选会先用来源的歌曲详情补全再重新打分。
+//! 来源按歌词设置中的顺序依次尝试（默认先网易云音乐、后 QQ 音乐），某个来源的最佳候选达到置信度阈值时
+//! 直接获取歌词，都没有时返回所有来源排好序的候选列表交给用户选择。
+//! 开启卡拉 OK 模式时，可信候选的歌词没有逐字时间就继续尝试后面的来源，都没有逐字歌词时采用第一个可信候选。
+//! 批量下载指定了在线专辑时，先按曲号和标题把音轨对应到专辑曲目，对应不上的再单独搜索。
+//! 选中的来源和歌曲 ID 按文件路径缓存，再次获取时跳过搜索；歌词本身通过在线歌词缓存获取。
+//! 匹配到纯音乐时缓存中记下该文件已确定为纯音乐，之后不再请求歌词。
+
+use super::lyrics_cache::LyricsCache;
+use super::metadata::TrackMetadata;
+use super::lyrics_provider::{fetch_cached, LyricsProvider, LyricsProviderRegistry, ProviderSong, DEFAULT_PROVIDER};
+use super::netease::{AlbumTrack, LyricLayers, LyricsData, LyricsKind, NeteaseError, SearchSongResult};
+use super::text::{edit_similarity, is_cjk, search_key};
+use crate::paths::canonical_key;
+use crate::tr;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 自动采用候选所需的最低得分（满分 1.0）
+pub const CONFIDENCE_THRESHOLD: f64 = 0.6;
+/// 时长差超过该值（秒）的候选只在没有更好的候选时才考虑
+pub const DURATION_TOLERANCE_SECS: f64 = 5.0;
+/// 每次搜索的结果数
+const SEARCH_LIMIT: u32 = 10;
+/// 用歌曲详情补全信息的候选数
+const DETAIL_CANDIDATES: usize = 3;
+/// 匹配结果中附带的次高候选数
+const RUNNERS_UP: usize = 3;
+/// 按曲号对应到专辑曲目时要求的最低标题相似度（时长相近时不要求）
+const ALBUM_TRACK_TITLE_SIMILARITY: f64 = 0.5;
+
+/// 缓存的匹配结果
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LyricsMatch {
+    /// 旧版缓存条目没有记录来源，均来自网易云音乐
+    #[serde(default = "default_provider")]
+    pub provider: String,
+    pub song_id: String,
+    pub score: f64,
+    /// 匹配到的歌曲是纯音乐，不再请求歌词
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub instrumental: bool,
+}
+
+impl LyricsMatch {
+    #[must_use]
+    pub fn new(provider: String, song_id: String, score: f64, lyrics: &LyricsData) -> Self {
+        Self { provider, song_id, score, instrumental: lyrics.kind == LyricsKind::Instrumental }
+    }
+}
+
+fn default_provider() -> String {
+    DEFAULT_PROVIDER.to_string()
+}
+
+/// 文件路径 -> 匹配到的歌曲
+pub struct LyricsMatchCache {
+    path: PathBuf,
+    entries: HashMap<String, LyricsMatch>,
+}
+
+impl LyricsMatchCache {
+    /// 从缓存目录加载，文件不存在或损坏时返回空缓存
+    #[must_use]
+    pub fn load(cache_dir: &Path) -> Self {
+        let path = cache_dir.join("lyrics_matches.json");
+        let entries = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self { path, entries }
+    }
+
+    #[must_use]
+    pub fn get(&self, track_path: &str) -> Option<&LyricsMatch> {
+        self.entries.get(&canonical_key(track_path))
+    }
+
+    pub fn insert(&mut self, track_path: &str, found: LyricsMatch) {
+        self.entries.insert(canonical_key(track_path), found);
+    }
+
+    pub fn remove(&mut self, track_path: &str) -> bool {
+        self.entries.remove(&canonical_key(track_path)).is_some()
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let content = serde_json::to_string(&self.entries).map_err(|e| tr!("lyrics.matchCacheSerializeFailed", detail = e))?;
+        let temp_path = self.path.with_extension("json.tmp");
+        fs::write(&temp_path, content)
+            .and_then(|()| fs::rename(&temp_path, &self.path))
+            .map_err(|e| tr!("lyrics.matchCacheWriteFailed", detail = e))
+    }
+}
+
+/// 参与匹配的音轨信息
+#[derive(Debug, Clone, Default)]
+pub struct TrackQuery {
+    pub title: String,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    /// 时长（秒）
+    pub duration: Option<f64>,
+    pub track_number: Option<u32>,
+    pub disc_number: Option<u32>,
+}
+
+impl TrackQuery {
+    /// 从音轨元数据构建，标题回退为文件名时去掉扩展名
+    #[must_use]
+    pub fn from_metadata(metadata: &TrackMetadata) -> Self {
+        let title = match metadata.title.as_deref() {
+            Some(title) if title == metadata.name => {
+                Path::new(title).file_stem().map_or_else(|| title.to_string(), |s| s.to_string_lossy().to_string())
+            }
+            Some(title) => title.to_string(),
+            None => String::new(),
+        };
+        Self {
+            title,
+            artist: metadata.artist.clone().filter(|a| !a.trim().is_empty()),
+            album: metadata.album.clone().filter(|a| !a.trim().is_empty()),
+            duration: metadata.duration,
+            track_number: metadata.track_number,
+            disc_number: metadata.disc_number,
+        }
+    }
+}
+
+/// 候选得分的各个部分，便于在界面中解释为什么选中某个候选
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchScore {
+    /// 标题相似度（0 到 1）
+    pub title: f64,
+    /// 艺术家相似度，音轨没有艺术家时为 `None`
+    pub artist: Option<f64>,
+    /// 专辑相似度，音轨或候选没有专辑时为 `None`
+    pub album: Option<f64>,
+    /// 与音轨的时长差（秒），任一方没有时长时为 `None`
+    pub duration_diff: Option<f64>,
+    /// 加权后的总分（满分 1.0）
+    pub total: f64,
+}
+
+/// 打过分的候选
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchCandidate {
+    #[serde(flatten)]
+    pub song: SearchSongResult,
+    /// 候选所在的来源
+    pub provider: String,
+    pub score: MatchScore,
+}
+
+/// 自动匹配结果
+#[allow(clippy::large_enum_variant)] // 只作为命令结果返回一次，不会大量存放
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum AutoMatchOutcome {
+    /// 已自动匹配并获取歌词
+    #[serde(rename_all = "camelCase")]
+    Matched {
+        lyrics: LyricsData,
+        provider: String,
+        song_id: String,
+        score: f64,
+        /// 得分的各个部分，使用缓存的歌曲 ID 时为 `None`
+        match_score: Option<MatchScore>,
+        /// 匹配到的歌曲，使用缓存的歌曲 ID 时为 `None`
+        song: Option<SearchSongResult>,
+        /// 同一来源中得分次高的几个候选
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        runners_up: Vec<MatchCandidate>,
+        /// 是否使用了缓存的歌曲 ID（没有搜索）
+        cached: bool,
+    },
+    /// 没有足够可信的候选，按得分从高到低返回（包含所有来源的候选）
+    Candidates { candidates: Vec<MatchCandidate> },
+    /// 搜索没有结果
+    NoMatch,
+}
+
+impl AutoMatchOutcome {
+    /// 把歌词时间偏移应用到匹配到的歌词上
+    pub fn apply_offset(&mut self, offset_ms: i64) {
+        if let Self::Matched { lyrics, .. } = self {
+            *lyrics = std::mem::take(lyrics).with_offset(offset_ms);
+        }
+    }
+
+    /// 去掉匹配结果中的次高候选
+    pub fn clear_runners_up(&mut self) {
+        if let Self::Matched { runners_up, .. } = self {
+            runners_up.clear();
+        }
+    }
+}
+
+/// 去掉括号中的附加说明（如 "(Live)"、"（伴奏）"），只保留字母和数字
+fn normalize_title(value: &str) -> String {
+    let mut depth = 0usize;
+    let mut result = String::new();
+    for c in search_key(value).chars() {
+        match c {
+            '(' | '（' | '[' | '【' => depth += 1,
+            ')' | '）' | ']' | '】' => depth = depth.saturating_sub(1),
+            _ if depth == 0 && c.is_alphanumeric() => result.push(c),
+            _ => {}
+        }
+    }
+    result
+}
+
+/// 参与比较的标题写法：同时含中日韩文字和拉丁字母的标题（如 "晴天 Sunny Day"）还分别取两部分
+fn title_variants(value: &str) -> Vec<String> {
+    let normalized = normalize_title(value);
+    let (cjk, other): (String, String) = normalized.chars().partition(|c| is_cjk(*c));
+    if cjk.is_empty() || other.is_empty() { vec![normalized] } else { vec![normalized, cjk, other] }
+}
+
+/// 拆分多位艺术家
+fn split_artists(value: &str) -> Vec<String> {
+    let mut names: Vec<String> = search_key(value)
+        .replace(" feat. ", "/")
+        .replace(" feat ", "/")
+        .replace(" ft. ", "/")
+        .split(['/', '&', ',', '、', ';', '；'])
+        .map(|name| name.chars().filter(|c| c.is_alphanumeric()).collect::<String>())
+        .filter(|name| !name.is_empty())
+        .collect();
+    names.dedup();
+    names
+}
+
+/// 标题相似度：各写法之间编辑距离相似度的最大值
+fn title_similarity(a: &str, b: &str) -> f64 {
+    let (a, b) = (title_variants(a), title_variants(b));
+    a.iter()
+        .flat_map(|a| b.iter().map(move |b| (a, b)))
+        .filter(|(a, b)| !a.is_empty() && !b.is_empty())
+        .map(|(a, b)| edit_similarity(a, b))
+        .fold(0.0, f64::max)
+}
+
+/// 一组艺术家中每位与另一组最相近者的平均相似度
+fn artist_coverage(names: &[String], others: &[String]) -> f64 {
+    let total: f64 = names
+        .iter()
+        .map(|name| {
+            others
+                .iter()
+                .map(|other| {
+                    if name.contains(other.as_str()) || other.contains(name.as_str()) {
+                        1.0
+                    } else {
+                        edit_similarity(name, other)
+                    }
+                })
+                .fold(0.0, f64::max)
+        })
+        .sum();
+    total / names.len() as f64
+}
+
+/// 艺术家相似度：双向覆盖率的平均，只差一位合作艺术家（feat.）时仍有较高得分
+fn artist_similarity(track: &str, candidate: &str) -> f64 {
+    let (track, candidate) = (split_artists(track), split_artists(candidate));
+    if track.is_empty() || candidate.is_empty() {
+        return 0.0;
+    }
+    f64::midpoint(artist_coverage(&track, &candidate), artist_coverage(&candidate, &track))
+}
+
+/// 给候选打分：标题占 0.5，艺术家占 0.3，时长占 0.2，专辑占 0.1；音轨缺少某项时按其余部分折算
+#[must_use]
+pub fn score_candidate(query: &TrackQuery, song: &SearchSongResult) -> MatchScore {
+    let title = title_similarity(&query.title, &song.name);
+    let artist = query.artist.as_deref().map(|artist| artist_similarity(artist, &song.artist));
+    // 来源没有返回专辑时不计入，避免拉低得分
+    let album = query
+        .album
+        .as_deref()
+        .filter(|_| !song.album.is_empty())
+        .map(|album| title_similarity(album, &song.album));
+    let duration_diff = query
+        .duration
+        .filter(|_| song.duration > 0)
+        .map(|duration| (duration - song.duration as f64 / 1000.0).abs());
+    let duration = query
+        .duration
+        .map(|_| duration_diff.map_or(0.0, |diff| (1.0 - diff / (DURATION_TOLERANCE_SECS * 2.0)).max(0.0)));
+
+    let parts = [(Some(title), 0.5), (artist, 0.3), (duration, 0.2), (album, 0.1)];
+    let (total, weight) = parts
+        .iter()
+        .filter_map(|(value, weight)| value.map(|value| (value * weight, *weight)))
+        .fold((0.0, 0.0), |(total, sum), (value, weight)| (total + value, sum + weight));
+    MatchScore { title, artist, album, duration_diff, total: total / weight }
+}
+
+/// 给搜索结果打分并排序；有时长相近的候选时，时长差超过容差的候选被排除
+#[must_use]
+pub fn rank_candidates(query: &TrackQuery, songs: Vec<ProviderSong>) -> Vec<MatchCandidate> {
+    let mut candidates: Vec<MatchCandidate> = songs
+        .into_iter()
+        .map(|ProviderSong { provider, song }| MatchCandidate { score: score_candidate(query, &song), song, provider })
+        .collect();
+
+    let within = |c: &MatchCandidate| c.score.duration_diff.is_none_or(|diff| diff <= DURATION_TOLERANCE_SECS);
+    if candidates.iter().any(within) {
+        candidates.retain(within);
+    }
+    candidates.sort_by(|a, b| b.score.total.total_cmp(&a.score.total));
+    candidates
+}
+
+/// 先用"标题 艺术家"搜索，没有结果时只用标题再搜一次
+async fn search_track(provider: &dyn LyricsProvider, query: &TrackQuery) -> Result<Vec<ProviderSong>, NeteaseError> {
+    let songs = match &query.artist {
+        Some(artist) => provider.search(&format!("{} {artist}", query.title), SEARCH_LIMIT, 0).await?,
+        None => Vec::new(),
+    };
+    if songs.is_empty() { provider.search(&query.title, SEARCH_LIMIT, 0).await } else { Ok(songs) }
+}
+
+/// 用歌曲详情补全得分最高的几个候选并重新排序，获取详情失败时保留原来的结果
+async fn refine_candidates(
+    provider: &dyn LyricsProvider,
+    query: &TrackQuery,
+    candidates: Vec<MatchCandidate>,
+) -> Vec<MatchCandidate> {
+    let mut songs: Vec<ProviderSong> =
+        candidates.into_iter().map(|c| ProviderSong { provider: c.provider, song: c.song }).collect();
+    let count = songs.len().min(DETAIL_CANDIDATES);
+    let mut top: Vec<SearchSongResult> = songs[..count].iter().map(|s| s.song.clone()).collect();
+    match provider.refine(&mut top).await {
+        Ok(()) => {
+            for (target, song) in songs.iter_mut().zip(top) {
+                target.song = song;
+            }
+        }
+        Err(e) => eprintln!("Failed to fetch {} song details: {e}", provider.name()),
+    }
+    rank_candidates(query, songs)
+}
+
+/// 记录自动采用的候选，生成匹配结果
+fn record_match(
+    track_path: &str,
+    cache: &mut LyricsMatchCache,
+    best: MatchCandidate,
+    ranked: Vec<MatchCandidate>,
+    lyrics: LyricsData,
+) -> AutoMatchOutcome {
+    let score = best.score.total;
+    cache.insert(track_path, LyricsMatch::new(best.provider.clone(), best.song.id.clone(), score, &lyrics));
+    if let Err(e) = cache.save() {
+        eprintln!("Failed to save lyrics match cache: {e}");
+    }
+    AutoMatchOutcome::Matched {
+        lyrics,
+        provider: best.provider,
+        song_id: best.song.id.clone(),
+        score,
+        match_score: Some(best.score),
+        song: Some(best.song),
+        runners_up: ranked.into_iter().take(RUNNERS_UP).collect(),
+        cached: false,
+    }
+}
+
+/// 自动匹配音轨的歌词
+///
+/// 缓存中有该文件的歌曲时直接获取歌词（已确定为纯音乐时不再请求）；否则按优先级在启用的来源中依次搜索，
+/// 某个来源出错时继续尝试下一个，所有来源都出错时返回第一个错误。
+/// `layers.karaoke` 时优先采用带逐字时间的歌词。
+pub async fn auto_match_lyrics(
+    track_path: &str,
+    query: &TrackQuery,
+    providers: &LyricsProviderRegistry,
+    layers: LyricLayers,
+    cache: &mut LyricsMatchCache,
+    lyrics_cache: &LyricsCache,
+) -> Result<AutoMatchOutcome, NeteaseError> {
+    if let Some(found) = cache.get(track_path).cloned()
+        && let Some(provider) = providers.get(&found.provider)
+    {
+        let lyrics = if found.instrumental {
+            LyricsData::default().with_kind(LyricsKind::Instrumental)
+        } else {
+            fetch_cached(provider.as_ref(), &found.song_id, false, lyrics_cache).await?.with_layers(layers)
+        };
+        return Ok(AutoMatchOutcome::Matched {
+            lyrics,
+            provider: found.provider,
+            song_id: found.song_id,
+            score: found.score,
+            match_score: None,
+            song: None,
+            runners_up: Vec::new(),
+            cached: true,
+        });
+    }
+
+    if query.title.trim().is_empty() {
+        return Ok(AutoMatchOutcome::NoMatch);
+    }
+
+    let mut candidates = Vec::new();
+    let mut first_error = None;
+    // 卡拉 OK 模式下没有逐字时间的可信候选，后面的来源都没有逐字歌词时采用
+    let mut fallback = None;
+    for provider in providers.enabled() {
+        let songs = match search_track(provider.as_ref(), query).await {
+            Ok(songs) => songs,
+            Err(e) => {
+                first_error.get_or_insert(e);
+                continue;
+            }
+        };
+        let mut ranked = refine_candidates(provider.as_ref(), query, rank_candidates(query, songs)).await;
+        if ranked.first().is_none_or(|best| best.score.total < CONFIDENCE_THRESHOLD) {
+            candidates.append(&mut ranked);
+            continue;
+        }
+
+        let best = ranked.remove(0);
+        let lyrics = fetch_cached(provider.as_ref(), &best.song.id, false, lyrics_cache).await?.with_layers(layers);
+        if layers.karaoke && !lyrics.word_level && lyrics.kind != LyricsKind::Instrumental {
+            fallback.get_or_insert((best, ranked, lyrics));
+            continue;
+        }
+        return Ok(record_match(track_path, cache, best, ranked, lyrics));
+    }
+
+    if let Some((best, ranked, lyrics)) = fallback {
+        return Ok(record_match(track_path, cache, best, ranked, lyrics));
+    }
+    if candidates.is_empty() {
+        return first_error.map_or(Ok(AutoMatchOutcome::NoMatch), Err);
+    }
+    candidates.sort_by(|a, b| b.score.total.total_cmp(&a.score.total));
+    Ok(AutoMatchOutcome::Candidates { candidates })
+}
+
+/// 把音轨对应到在线专辑中的曲目
+///
+/// 曲号（和碟号）相同且标题相近或时长相近时直接采用，通用标题（如 "Intro"）也能对应正确；
+/// 否则取得分最高且达到置信度阈值的曲目。
+#[must_use]
+pub fn match_album_track<'a>(query: &TrackQuery, tracks: &'a [AlbumTrack]) -> Option<(&'a AlbumTrack, MatchScore)> {
+    let scored = tracks.iter().map(|track| (track, score_candidate(query, &track.song)));
+    let by_position = query.track_number.and_then(|number| {
+        scored.clone().find(|(track, score)| {
+            track.track_number == Some(number)
+                && query.disc_number.zip(track.disc_number).is_none_or(|(a, b)| a == b)
+                && (score.title >= ALBUM_TRACK_TITLE_SIMILARITY
+                    || score.duration_diff.is_some_and(|diff| diff <= DURATION_TOLERANCE_SECS))
+        })
+    });
+    by_position.or_else(|| {
+        scored
+            .filter(|(_, score)| score.total >= CONFIDENCE_THRESHOLD)
+            .max_by(|(_, a), (_, b)| a.total.total_cmp(&b.total))
+    })
+}
+
+/// 先在指定的网易云音乐专辑中对应曲目，对应不上时按普通自动匹配搜索
+pub async fn auto_match_in_album(
+    track_path: &str,
+    query: &TrackQuery,
+    album: &[AlbumTrack],
+    providers: &LyricsProviderRegistry,
+    layers: LyricLayers,
+    cache: &mut LyricsMatchCache,
+    lyrics_cache: &LyricsCache,
+) -> Result<AutoMatchOutcome, NeteaseError> {
+    let Some((track, score)) = match_album_track(query, album) else {
+        return auto_match_lyrics(track_path, query, providers, layers, cache, lyrics_cache).await;
+    };
+    let provider = providers.get(DEFAULT_PROVIDER).ok_or_else(|| NeteaseError::NotFound(DEFAULT_PROVIDER.into()))?;
+    let song_id = track.song.id.clone();
+    let lyrics = fetch_cached(provider.as_ref(), &song_id, false, lyrics_cache).await?.with_layers(layers);
+    let provider = provider.name().to_string();
+    cache.insert(track_path, LyricsMatch::new(provider.clone(), song_id.clone(), score.total, &lyrics));
+    if let Err(e) = cache.save() {
+        eprintln!("Failed to save lyrics match cache: {e}");
+    }
+    Ok(AutoMatchOutcome::Matched {
+        lyrics,
+        provider,
+        song_id,
+        score: score.total,
+        match_score: Some(score),
+        song: Some(track.song.clone()),
+        runners_up: Vec::new(),
+        cached: false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn song(id: &str, name: &str, artist: &str, album: &str, duration_ms: i64) -> SearchSongResult {
+        SearchSongResult {
+            id: id.to_string(),
+            name: name.to_string(),
+            artist: artist.to_string(),
+            album: album.to_string(),
+            album_id: None,
+            cover_url: None,
+            duration: duration_ms,
+        }
+    }
+
+    fn query(title: &str, artist: &str, album: Option<&str>, duration: f64) -> TrackQuery {
+        TrackQuery {
+            title: title.to_string(),
+            artist: Some(artist.to_string()),
+            album: album.map(ToString::to_string),
+            duration: Some(duration),
+            ..TrackQuery::default()
+        }
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9, "{actual} != {expected}");
+    }
+
+    /// 各部分的权重：标题 0.5、艺术家 0.3、时长 0.2、专辑 0.1，按音轨具有的部分折算
+    #[test]
+    fn scores_are_weighted_by_component() {
+        let track = query("Yellow", "Coldplay", Some("Parachutes"), 269.0);
+        let exact = score_candidate(&track, &song("1", "Yellow", "Coldplay", "Parachutes", 269_000));
+        assert_eq!(
+            exact,
+            MatchScore { title: 1.0, artist: Some(1.0), album: Some(1.0), duration_diff: Some(0.0), total: 1.0 }
+        );
+
+        let other_artist = score_candidate(&track, &song("2", "Yellow", "zzz", "Parachutes", 269_000));
+        assert_close(other_artist.total, 0.8 / 1.1);
+
+        // 时长差 5 秒时时长部分得 0.5，候选没有专辑时专辑不计入
+        let late = score_candidate(&track, &song("3", "Yellow", "Coldplay", "", 274_000));
+        assert_eq!(late.album, None);
+        assert_close(late.total, 0.5 + 0.3 + 0.2 * 0.5);
+
+        // 候选没有时长时时长部分得 0
+        let no_duration = score_candidate(&track, &song("4", "Yellow", "Coldplay", "", 0));
+        assert_eq!(no_duration.duration_diff, None);
+        assert_close(no_duration.total, 0.8);
+
+        let title_only = TrackQuery { title: "Yellow".to_string(), ..TrackQuery::default() };
+        let score = score_candidate(&title_only, &song("5", "Yellow", "zzz", "abc", 1_000));
+        assert_eq!((score.artist, score.album, score.duration_diff), (None, None, None));
+        assert_close(score.total, 1.0);
+    }
+
+    #[test]
+    fn tricky_candidates_pick_the_intended_winner() {
+        let cases = [
+            (
+                "featuring artists",
+                query("晴天", "周杰伦", None, 269.0),
+                [song("cover", "晴天", "魏如萱", "", 270_000), song("feat", "晴天", "周杰伦/杨瑞代", "", 269_500)],
+                "feat",
+            ),
+            (
+                "(Live) suffix with a studio track",
+                query("Yellow", "Coldplay", None, 269.0),
+                [
+                    song("live", "Yellow (Live)", "Coldplay", "", 300_000),
+                    song("studio", "Yellow", "Coldplay", "", 268_000),
+                ],
+                "studio",
+            ),
+            (
+                "(Live) suffix with a live track",
+                query("Yellow (Live)", "Coldplay", None, 300.0),
+                [
+                    song("studio", "Yellow", "Coldplay", "", 268_000),
+                    song("live", "Yellow (Live)", "Coldplay", "", 301_000),
+                ],
+                "live",
+            ),
+            (
+                "CJK and Latin title",
+                query("晴天 Sunny Day", "周杰伦", None, 269.0),
+                [song("latin", "Sunny Day", "Hey Violet", "", 200_000), song("cjk", "晴天", "周杰伦", "", 269_000)],
+                "cjk",
+            ),
+            (
+                "duration off by 3 seconds",
+                query("Home", "Michael Bublé", None, 225.0),
+                [song("other", "Home", "Daughtry", "", 225_000), song("late", "Home", "Michael Buble", "", 228_000)],
+                "late",
+            ),
+            (
+                "album bonus",
+                query("晴天", "周杰伦", Some("叶惠美"), 269.0),
+                [
+                    song("hits", "晴天", "周杰伦", "Greatest Hits", 269_000),
+                    song("album", "晴天", "周杰伦", "叶惠美", 269_000),
+                ],
+                "album",
+            ),
+        ];
+        for (name, track, songs, winner) in cases {
+            let songs = songs.into_iter().map(|song| ProviderSong { provider: DEFAULT_PROVIDER.to_string(), song });
+            let ranked = rank_candidates(&track, songs.collect());
+            assert_eq!(ranked[0].song.id, winner, "{name}");
+            assert!(ranked[0].score.total >= CONFIDENCE_THRESHOLD, "{name}");
+        }
+    }
+}
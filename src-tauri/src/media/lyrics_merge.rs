@@ -0,0 +1,222 @@
+//! 歌词合并模块
+//!
+//! 网易云音乐的原文歌词（lrc）、翻译歌词（tlyric）和罗马音（romalrc）各自带时间轴，行数和时间不一定完全一致。
+//! 翻译和罗马音分别按时间对齐到原文：先找时间完全相同的原文行，再找 500 毫秒以内最近的、还没有对应行的原文行。
+//! 没有对应行的原文只保留原文，对不上原文的翻译或罗马音行被丢弃。逐字时间标记会被去掉，只保留行级时间。
+
+use super::lyrics::{lrc_to_sync_entries, sync_entries_to_lrc};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// 翻译或罗马音行与原文行的最大时间差（毫秒）
+const ALIGN_TOLERANCE_MS: u32 = 500;
+
+/// 合并后的一行歌词
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct MergedLyricLine {
+    /// 时间（毫秒）
+    pub time: u32,
+    pub original: String,
+    pub translation: Option<String>,
+    pub romanization: Option<String>,
+}
+
+/// 合并结果
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MergedLyrics {
+    pub lines: Vec<MergedLyricLine>,
+    /// 多行 LRC：每个时间先写原文，之后依次写同一时间的罗马音和翻译
+    pub lrc: String,
+}
+
+/// 去掉逐字时间标记：`<mm:ss.xx>` 和 `(开始,时长)` / `(开始,时长,0)`，合并多余空白
+fn strip_word_timing(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let close = match c {
+            '<' => Some('>'),
+            '(' => Some(')'),
+            _ => None,
+        };
+        if let Some(close) = close
+            && let Some(end) = rest.find(close)
+        {
+            let inner = &rest[1..end];
+            let is_timing = if c == '<' {
+                inner.contains(':') && inner.chars().all(|ch| ch.is_ascii_digit() || matches!(ch, ':' | '.'))
+            } else {
+                inner.contains(',') && inner.chars().all(|ch| ch.is_ascii_digit() || ch == ',')
+            };
+            if is_timing {
+                rest = &rest[end + 1..];
+                continue;
+            }
+        }
+        result.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    result.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// 解析要对齐的歌词层，去掉逐字时间标记和空行
+fn layer_entries(lrc: &str) -> Vec<(u32, String)> {
+    lrc_to_sync_entries(lrc)
+        .into_iter()
+        .map(|(time, text)| (time, strip_word_timing(&text)))
+        .filter(|(_, text)| !text.is_empty())
+        .collect()
+}
+
+/// 把翻译或罗马音对齐到原文行，返回每个原文行对应的文本
+fn align(original: &[(u32, String)], layer: &[(u32, String)]) -> Vec<Option<String>> {
+    let mut aligned: Vec<Option<String>> = vec![None; original.len()];
+    let mut by_time: HashMap<u32, Vec<usize>> = HashMap::new();
+    for (index, (time, text)) in original.iter().enumerate() {
+        if !text.trim().is_empty() {
+            by_time.entry(*time).or_default().push(index);
+        }
+    }
+
+    // 第一轮：时间完全相同
+    let mut pending = Vec::new();
+    for (time, text) in layer {
+        let slot = by_time.get(time).and_then(|indices| indices.iter().copied().find(|&i| aligned[i].is_none()));
+        match slot {
+            Some(index) => aligned[index] = Some(text.clone()),
+            None => pending.push((*time, text)),
+        }
+    }
+
+    // 第二轮：容差以内最近的空闲原文行
+    for (time, text) in pending {
+        let start = original.partition_point(|(t, _)| *t < time.saturating_sub(ALIGN_TOLERANCE_MS));
+        let nearest = original[start..]
+            .iter()
+            .enumerate()
+            .take_while(|(_, (t, _))| *t <= time.saturating_add(ALIGN_TOLERANCE_MS))
+            .filter(|(offset, (_, line))| aligned[start + offset].is_none() && !line.trim().is_empty())
+            .min_by_key(|(_, (t, _))| t.abs_diff(time))
+            .map(|(offset, _)| start + offset);
+        if let Some(index) = nearest {
+            aligned[index] = Some(text.clone());
+        }
+    }
+    aligned
+}
+
+/// 按时间合并原文、翻译和罗马音歌词，`translation`、`romanization` 为空时不合并该层
+///
+/// 与原文相同的翻译或罗马音行不计入。
+#[must_use]
+pub fn merge_lyrics(original: &str, translation: &str, romanization: &str) -> MergedLyrics {
+    let original: Vec<(u32, String)> =
+        lrc_to_sync_entries(original).into_iter().map(|(time, text)| (time, strip_word_timing(&text))).collect();
+    let translations = align(&original, &layer_entries(translation));
+    let romanizations = align(&original, &layer_entries(romanization));
+
+    let lines: Vec<MergedLyricLine> = original
+        .into_iter()
+        .zip(translations.into_iter().zip(romanizations))
+        .map(|((time, original), (translation, romanization))| MergedLyricLine {
+            time,
+            translation: translation.filter(|t| *t != original),
+            romanization: romanization.filter(|r| *r != original),
+            original,
+        })
+        .collect();
+
+    let entries: Vec<(u32, String)> = lines
+        .iter()
+        .flat_map(|line| {
+            std::iter::once(Some(line.original.clone()))
+                .chain([line.romanization.clone(), line.translation.clone()])
+                .flatten()
+                .map(|text| (line.time, text))
+        })
+        .collect();
+    MergedLyrics { lrc: sync_entries_to_lrc(&entries), lines }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ORIGINAL: &str = "[00:01.00]君の名前を\n[00:05.00]呼んでいた\n[00:09.00]夜明けまで\n[00:13.00]ずっと";
+
+    fn pairs(merged: &MergedLyrics) -> Vec<(u32, &str, Option<&str>)> {
+        merged.lines.iter().map(|line| (line.time, line.original.as_str(), line.translation.as_deref())).collect()
+    }
+
+    #[test]
+    fn exact_times_pair_each_line() {
+        let translation = "[00:01.00]你的名字\n[00:05.00]我一直在呼唤\n[00:09.00]直到黎明\n[00:13.00]一直";
+        let merged = merge_lyrics(ORIGINAL, translation, "");
+        assert_eq!(
+            pairs(&merged),
+            [
+                (1_000, "君の名前を", Some("你的名字")),
+                (5_000, "呼んでいた", Some("我一直在呼唤")),
+                (9_000, "夜明けまで", Some("直到黎明")),
+                (13_000, "ずっと", Some("一直")),
+            ]
+        );
+        assert!(merged.lines.iter().all(|line| line.romanization.is_none()));
+    }
+
+    #[test]
+    fn shifted_translation_lines_align_within_tolerance() {
+        // 翻译整体晚 300 毫秒，第三行早 450 毫秒，最后一行偏差超过容差
+        let translation = "[00:01.30]你的名字\n[00:05.30]我一直在呼唤\n[00:08.55]直到黎明\n[00:13.60]一直";
+        let merged = merge_lyrics(ORIGINAL, translation, "");
+        assert_eq!(
+            pairs(&merged),
+            [
+                (1_000, "君の名前を", Some("你的名字")),
+                (5_000, "呼んでいた", Some("我一直在呼唤")),
+                (9_000, "夜明けまで", Some("直到黎明")),
+                (13_000, "ずっと", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn shorter_translation_leaves_the_remaining_lines_untranslated() {
+        let translation = "[00:01.00]你的名字\n[00:09.00]直到黎明";
+        let merged = merge_lyrics(ORIGINAL, translation, "");
+        assert_eq!(
+            pairs(&merged),
+            [
+                (1_000, "君の名前を", Some("你的名字")),
+                (5_000, "呼んでいた", None),
+                (9_000, "夜明けまで", Some("直到黎明")),
+                (13_000, "ずっと", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_shifted_line_does_not_take_an_already_paired_original() {
+        // 第二行翻译离第一行原文更近，但第一行已经有完全对上的翻译，只能落在 5 秒的原文上
+        let original = "[00:01.00]一\n[00:01.40]二\n[00:05.00]三";
+        let translation = "[00:01.00]one\n[00:01.10]two";
+        let merged = merge_lyrics(original, translation, "");
+        assert_eq!(pairs(&merged), [(1_000, "一", Some("one")), (1_400, "二", Some("two")), (5_000, "三", None)]);
+    }
+
+    #[test]
+    fn romanization_and_word_timing_are_merged_into_lrc() {
+        let original = "[00:01.00]<00:01.00>君の<00:01.50>名前\n[00:05.00]Hello";
+        let romanization = "[00:01.00](1000,500,0)kimi no (1500,400,0)namae\n[00:05.00]Hello";
+        let translation = "[00:01.00]你的名字";
+        let merged = merge_lyrics(original, translation, romanization);
+
+        assert_eq!(merged.lines[0].original, "君の名前");
+        assert_eq!(merged.lines[0].romanization.as_deref(), Some("kimi no namae"));
+        // 与原文相同的罗马音不计入
+        assert_eq!(merged.lines[1].romanization, None);
+        assert_eq!(merged.lrc, "[00:01.00]君の名前\n[00:01.00]kimi no namae\n[00:01.00]你的名字\n[00:05.00]Hello\n");
+    }
+}
@@ -0,0 +1,246 @@
+//! 在线歌词来源
+//!
+//! 每个在线来源（网易云音乐、QQ 音乐、酷狗音乐）实现 `LyricsProvider`，自动匹配、批量下载和通用的搜索、获取命令
+//! 只通过该 trait 访问来源。`LyricsProviderRegistry` 按歌词设置中的顺序保存启用的来源，修改设置后重新排序。
+
+use super::kugou::KugouProvider;
+use super::lyrics_cache::LyricsCache;
+use super::netease::{LyricsData, NeteaseError, NeteaseProvider, SearchSongResult};
+use super::qqmusic::QqMusicProvider;
+use serde::Serialize;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, RwLock};
+
+/// 来源方法返回的 future
+pub type ProviderFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, NeteaseError>> + Send + 'a>>;
+
+/// 歌词设置中没有可用来源时使用的来源
+pub const DEFAULT_PROVIDER: &str = "netease";
+
+/// 搜索结果中的歌曲，附带所在的来源
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderSong {
+    pub provider: String,
+    #[serde(flatten)]
+    pub song: SearchSongResult,
+}
+
+/// 在线歌词来源
+pub trait LyricsProvider: Send + Sync {
+    /// 来源名称，同时用于配置、歌词缓存文件名和匹配缓存
+    fn name(&self) -> &'static str;
+
+    /// 当前是否可以使用
+    fn is_available(&self) -> bool;
+
+    /// 搜索歌曲
+    fn search<'a>(&'a self, query: &'a str, limit: u32, offset: u32) -> ProviderFuture<'a, Vec<ProviderSong>>;
+
+    /// 获取歌词，歌曲 ID 为该来源搜索结果中的 ID
+    fn get_lyrics<'a>(&'a self, song_id: &'a str) -> ProviderFuture<'a, LyricsData>;
+
+    /// 用歌曲详情补充搜索结果中的精确时长和专辑信息，来源没有详情接口时不做处理
+    fn refine<'a>(&'a self, _songs: &'a mut [SearchSongResult]) -> ProviderFuture<'a, ()> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+/// 给搜索结果加上来源名称
+#[must_use]
+pub fn tag_songs(provider: &str, songs: Vec<SearchSongResult>) -> Vec<ProviderSong> {
+    songs.into_iter().map(|song| ProviderSong { provider: provider.to_string(), song }).collect()
+}
+
+/// 通过在线歌词缓存获取歌词，`force_refresh` 时跳过未过期的缓存
+pub async fn fetch_cached(
+    provider: &dyn LyricsProvider,
+    song_id: &str,
+    force_refresh: bool,
+    cache: &LyricsCache,
+) -> Result<LyricsData, NeteaseError> {
+    cache.get_or_fetch(provider.name(), song_id, force_refresh, || provider.get_lyrics(song_id)).await
+}
+
+/// 按名称创建已知的来源
+fn known_provider(name: &str) -> Option<Arc<dyn LyricsProvider>> {
+    match name {
+        "netease" => Some(Arc::new(NeteaseProvider)),
+        "qqmusic" => Some(Arc::new(QqMusicProvider)),
+        "kugou" => Some(Arc::new(KugouProvider)),
+        _ => None,
+    }
+}
+
+/// 按优先级排列的启用来源
+pub struct LyricsProviderRegistry {
+    providers: RwLock<Vec<Arc<dyn LyricsProvider>>>,
+}
+
+impl LyricsProviderRegistry {
+    /// 按配置中的顺序创建，未知或重复的名称被忽略
+    #[must_use]
+    pub fn new(order: &[String]) -> Self {
+        Self { providers: RwLock::new(ordered_providers(order)) }
+    }
+
+    /// 修改歌词设置后重新排序
+    pub fn set_order(&self, order: &[String]) {
+        *self.providers.write().unwrap() = ordered_providers(order);
+    }
+
+    /// 当前可用的来源，按优先级排列
+    #[must_use]
+    pub fn enabled(&self) -> Vec<Arc<dyn LyricsProvider>> {
+        self.providers.read().unwrap().iter().filter(|provider| provider.is_available()).cloned().collect()
+    }
+
+    /// 按名称查找来源；未启用的已知来源也能找到（用于读取以前匹配的结果）
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<Arc<dyn LyricsProvider>> {
+        let enabled = self.providers.read().unwrap().iter().find(|provider| provider.name() == name).cloned();
+        enabled.or_else(|| known_provider(name))
+    }
+}
+
+fn ordered_providers(order: &[String]) -> Vec<Arc<dyn LyricsProvider>> {
+    let mut providers: Vec<Arc<dyn LyricsProvider>> = Vec::new();
+    for name in order {
+        if providers.iter().all(|provider| provider.name() != name.as_str())
+            && let Some(provider) = known_provider(name)
+        {
+            providers.push(provider);
+        }
+    }
+    if providers.is_empty() {
+        providers.extend(known_provider(DEFAULT_PROVIDER));
+    }
+    providers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::media::lyrics_match::{auto_match_lyrics, AutoMatchOutcome, LyricsMatchCache, TrackQuery};
+    use crate::media::netease::LyricLayers;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tempfile::TempDir;
+
+    /// 模拟的歌词来源：`fail` 时搜索返回网络错误，否则返回一首固定的歌曲
+    struct MockProvider {
+        name: &'static str,
+        fail: bool,
+        available: bool,
+        searches: AtomicUsize,
+        fetches: AtomicUsize,
+    }
+
+    impl MockProvider {
+        const fn new(name: &'static str, fail: bool) -> Self {
+            Self { name, fail, available: true, searches: AtomicUsize::new(0), fetches: AtomicUsize::new(0) }
+        }
+    }
+
+    impl LyricsProvider for MockProvider {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn is_available(&self) -> bool {
+            self.available
+        }
+
+        fn search<'a>(&'a self, _query: &'a str, _limit: u32, _offset: u32) -> ProviderFuture<'a, Vec<ProviderSong>> {
+            self.searches.fetch_add(1, Ordering::SeqCst);
+            Box::pin(async move {
+                if self.fail {
+                    return Err(NeteaseError::Network(format!("{} is down", self.name)));
+                }
+                let song = SearchSongResult {
+                    id: format!("{}-1", self.name),
+                    name: "Yellow".to_string(),
+                    artist: "Coldplay".to_string(),
+                    album: "Parachutes".to_string(),
+                    album_id: None,
+                    cover_url: None,
+                    duration: 269_000,
+                };
+                Ok(tag_songs(self.name, vec![song]))
+            })
+        }
+
+        fn get_lyrics<'a>(&'a self, song_id: &'a str) -> ProviderFuture<'a, LyricsData> {
+            self.fetches.fetch_add(1, Ordering::SeqCst);
+            Box::pin(async move { Ok(LyricsData { lrc: format!("[00:01.00]{song_id}"), ..LyricsData::default() }) })
+        }
+    }
+
+    fn registry(providers: &[&Arc<MockProvider>]) -> LyricsProviderRegistry {
+        let providers = providers.iter().map(|&provider| Arc::clone(provider) as Arc<dyn LyricsProvider>).collect();
+        LyricsProviderRegistry { providers: RwLock::new(providers) }
+    }
+
+    async fn auto_match(providers: &LyricsProviderRegistry) -> Result<AutoMatchOutcome, NeteaseError> {
+        let dir = TempDir::new().unwrap();
+        let query = TrackQuery {
+            title: "Yellow".to_string(),
+            artist: Some("Coldplay".to_string()),
+            duration: Some(269.0),
+            ..TrackQuery::default()
+        };
+        let layers = LyricLayers { translation: false, romanization: false, karaoke: false };
+        let mut cache = LyricsMatchCache::load(dir.path());
+        let lyrics_cache = LyricsCache::new(dir.path().join("lyrics"), 30);
+        auto_match_lyrics("C:\\Music\\Yellow.flac", &query, providers, layers, &mut cache, &lyrics_cache).await
+    }
+
+    #[tokio::test]
+    async fn primary_provider_is_used_when_it_works() {
+        let primary = Arc::new(MockProvider::new("primary", false));
+        let secondary = Arc::new(MockProvider::new("secondary", false));
+
+        let outcome = auto_match(&registry(&[&primary, &secondary])).await.unwrap();
+        let AutoMatchOutcome::Matched { provider, song_id, lyrics, .. } = outcome else {
+            panic!("expected a match, got {outcome:?}");
+        };
+        assert_eq!((provider.as_str(), song_id.as_str()), ("primary", "primary-1"));
+        assert_eq!(lyrics.lrc, "[00:01.00]primary-1");
+        assert_eq!(secondary.searches.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn primary_failure_falls_back_to_the_next_provider() {
+        let primary = Arc::new(MockProvider::new("primary", true));
+        let secondary = Arc::new(MockProvider::new("secondary", false));
+
+        let outcome = auto_match(&registry(&[&primary, &secondary])).await.unwrap();
+        let AutoMatchOutcome::Matched { provider, lyrics, .. } = outcome else {
+            panic!("expected a match, got {outcome:?}");
+        };
+        assert_eq!(provider, "secondary");
+        assert_eq!(lyrics.lrc, "[00:01.00]secondary-1");
+        assert_eq!(primary.searches.load(Ordering::SeqCst), 1);
+        assert_eq!(primary.fetches.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn unavailable_providers_are_skipped() {
+        let offline = Arc::new(MockProvider { available: false, ..MockProvider::new("offline", false) });
+        let secondary = Arc::new(MockProvider::new("secondary", false));
+
+        let outcome = auto_match(&registry(&[&offline, &secondary])).await.unwrap();
+        assert!(matches!(outcome, AutoMatchOutcome::Matched { ref provider, .. } if provider == "secondary"));
+        assert_eq!(offline.searches.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn all_providers_failing_returns_the_first_error() {
+        let primary = Arc::new(MockProvider::new("primary", true));
+        let secondary = Arc::new(MockProvider::new("secondary", true));
+
+        let error = auto_match(&registry(&[&primary, &secondary])).await.unwrap_err();
+        assert!(matches!(&error, NeteaseError::Network(message) if message == "primary is down"), "{error}");
+        assert_eq!(secondary.searches.load(Ordering::SeqCst), 1);
+    }
+}
@@ -2,29 +2,97 @@
 //!
 //! 提供音轨元数据结构和处理函数。
 
+use super::archive::{read_entry_metadata, split_archive_path};
+use super::cover::{
+    cached_cover_file, folder_cover_data_url, folder_cover_path, sniff_image_extension, store_cover_file, CoverSource,
+};
+use super::custom_tags::{read_custom_tags, CustomTag};
+use super::dsf;
+use super::encoding::{reinterpret_latin1, repair_mojibake};
+use super::lame::{read_mp3_info, Mp3Info};
+use super::tag_writer::parse_picture_type;
+use super::text::{normalize_optional, normalize_text};
+use crate::config::AppConfig;
+use crate::paths::long_path;
+use crate::tr;
 use base64::{engine::general_purpose, Engine as _};
 use lofty::prelude::{Accessor, AudioFile, TaggedFileExt};
+use lofty::config::ParseOptions;
+use lofty::file::{FileType, TaggedFile};
+use lofty::picture::{Picture, PictureType};
 use lofty::probe::Probe;
-use serde::Serialize;
-use std::fs;
-use std::path::Path;
+use lofty::tag::{ItemKey, Tag};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
 
 /// 单个音轨的元数据
-#[derive(Debug, Serialize, Default, Clone)]
-#[serde(rename_all = "camelCase")]
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase", default)]
 pub struct TrackMetadata {
     pub path: String,
     pub name: String,
     pub title: Option<String>,
     pub artist: Option<String>,
+    /// 拆分后的艺术家列表，`artist` 保持原样
+    pub artists: Vec<String>,
     pub album: Option<String>,
+    pub album_artist: Option<String>,
+    /// 排序用艺术家名（TSOP / ARTISTSORT / soar）
+    pub artist_sort: Option<String>,
+    /// 排序用专辑艺术家名（TSO2 / ALBUMARTISTSORT / soaa）
+    pub album_artist_sort: Option<String>,
+    /// 排序用专辑名（TSOA / ALBUMSORT / soal）
+    pub album_sort: Option<String>,
+    pub genre: Option<String>,
+    pub year: Option<u32>,
+    pub track_number: Option<u32>,
+    pub track_total: Option<u32>,
+    pub disc_number: Option<u32>,
+    /// 国际标准录音代码（TSRC / ISRC）
+    pub isrc: Option<String>,
+    /// 唱片公司（TPUB / LABEL）
+    pub label: Option<String>,
+    /// 目录编号（TXXX:CATALOGNUMBER / CATALOGNUMBER）
+    pub catalog_number: Option<String>,
     pub duration: Option<f64>,
     pub cover: Option<String>,
+    /// 是否有可用封面（扫描时不读取封面数据，只给出该提示）
+    pub has_cover: bool,
+    /// 封面来源：内嵌、目录图片或没有封面
+    pub cover_source: CoverSource,
     pub bitrate: Option<u32>,
     pub sample_rate: Option<u32>,
     pub channels: Option<u8>,
     pub bit_depth: Option<u8>,
     pub format: Option<String>,
+    /// 编码器（来自 TSSE / ENCODER / ©too 等字段，MP3 缺失时取 LAME 头）
+    pub encoder: Option<String>,
+    /// 实际探测到的容器类型，可能与扩展名不一致
+    pub container: Option<String>,
+    /// MP3 的 Xing/LAME 头信息
+    pub mp3_info: Option<Mp3Info>,
+    /// 标签文本疑似为 GBK/Big5 等编码被当作 Latin-1 存储的乱码
+    pub encoding_suspect: bool,
+    /// 乱码修复建议，原始字段保持不变
+    pub encoding_repair: Option<EncodingRepair>,
+    /// 文件大小（字节）
+    pub file_size: Option<u64>,
+    /// 标签或文件头解析失败时的错误信息，此时其余字段可能只来自文件名
+    pub tag_error: Option<String>,
+}
+
+/// 乱码标签的修复建议
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct EncodingRepair {
+    /// 推测的原始编码（如 "GBK"）
+    pub encoding: String,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub album_artist: Option<String>,
 }
 
 impl TrackMetadata {
@@ -67,6 +135,51 @@ impl TrackMetadata {
     pub const fn with_channels(mut self, channels: Option<u8>) -> Self { self.channels = channels; self }
 }
 
+/// 元数据读取选项
+#[derive(Debug, Clone)]
+pub struct MetadataOptions {
+    /// 用于拆分多艺术家字段的分隔符
+    pub artist_separators: Vec<String>,
+    /// 是否读取并编码封面数据，目录扫描时默认关闭
+    pub include_covers: bool,
+    /// 目录封面的候选文件名
+    pub cover_file_names: Vec<String>,
+}
+
+impl Default for MetadataOptions {
+    fn default() -> Self {
+        Self::from_config(&AppConfig::default())
+    }
+}
+
+impl MetadataOptions {
+    #[must_use]
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self {
+            artist_separators: config.metadata.artist_separators.clone(),
+            include_covers: false,
+            cover_file_names: config.metadata.cover_file_names.clone(),
+        }
+    }
+
+    /// 设置是否读取封面数据
+    #[must_use]
+    pub const fn with_covers(mut self, include_covers: bool) -> Self {
+        self.include_covers = include_covers;
+        self
+    }
+}
+
+/// 扩展元数据，额外包含自定义字段
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackMetadataFull {
+    #[serde(flatten)]
+    pub metadata: TrackMetadata,
+    /// TXXX / 未映射的 Vorbis 注释 / MP4 自由格式字段，按文件中的顺序排列
+    pub custom_tags: Vec<CustomTag>,
+}
+
 /// 包含多个音轨的播放列表
 #[derive(Debug, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -118,14 +231,331 @@ impl Playlist {
     }
 }
 
-/// 获取音轨的元数据信息（内部函数）
-pub fn get_track_metadata_internal(path: &str) -> Result<TrackMetadata, String> {
+/// 按分隔符拆分艺术家字符串
+///
+/// 分隔符匹配不区分 ASCII 大小写，拆分结果去除首尾空白并去重。
+#[must_use]
+pub fn split_artists(artist: &str, separators: &[String]) -> Vec<String> {
+    let mut parts = vec![artist.to_string()];
+
+    for separator in separators.iter().filter(|s| !s.is_empty()) {
+        let separator_lower = separator.to_ascii_lowercase();
+        parts = parts
+            .into_iter()
+            .flat_map(|part| {
+                let lower = part.to_ascii_lowercase();
+                let mut pieces = Vec::new();
+                let mut start = 0;
+                for (idx, _) in lower.match_indices(&separator_lower) {
+                    pieces.push(part[start..idx].to_string());
+                    start = idx + separator_lower.len();
+                }
+                pieces.push(part[start..].to_string());
+                pieces
+            })
+            .collect();
+    }
+
+    let mut artists: Vec<String> = Vec::new();
+    for part in parts {
+        let trimmed = part.trim();
+        if !trimmed.is_empty() && !artists.iter().any(|a| a == trimmed) {
+            artists.push(trimmed.to_string());
+        }
+    }
+    artists
+}
+
+/// 从标签中读取艺术家列表
+///
+/// 优先使用真正的多值字段（如多个 Vorbis ARTIST），否则拆分单个字符串。
+fn read_artists(tag: &Tag, artist: Option<&str>, separators: &[String]) -> Vec<String> {
+    let values: Vec<&str> = tag.get_strings(&ItemKey::TrackArtist).collect();
+    if values.len() > 1 {
+        return values.into_iter().map(|v| v.trim().to_string()).filter(|v| !v.is_empty()).collect();
+    }
+
+    artist.map(|a| split_artists(a, separators)).unwrap_or_default()
+}
+
+/// 将图片编码为 data URL
+fn cover_data_url(picture: &Picture) -> String {
+    let mime_type = picture.mime_type().map_or("image/jpeg", lofty::picture::MimeType::as_str);
+    format!("data:{mime_type};base64,{}", general_purpose::STANDARD.encode(picture.data()))
+}
+
+/// 读取音轨的封面，返回 data URL
+///
+/// 没有嵌入封面时回退到所在目录中的封面图片。
+pub fn get_track_cover_internal(path: &str, options: &MetadataOptions) -> Result<Option<String>, String> {
+    if !has_extension(Path::new(path), "dsf") {
+        let tagged_file = Probe::open(long_path(path))
+            .map_err(|e| tr!("file.openFailed", detail = e))?
+            .read()
+            .map_err(|e| tr!("file.readFailed", detail = e))?;
+
+        if let Some(picture) = tagged_file.primary_tag().and_then(|tag| tag.pictures().first()) {
+            return Ok(Some(cover_data_url(picture)));
+        }
+    }
+
+    folder_cover_path(Path::new(path), &options.cover_file_names)
+        .map(|image| folder_cover_data_url(&image))
+        .transpose()
+}
+
+/// 读取音轨封面的原始图片数据，没有嵌入封面时回退到所在目录中的封面图片
+pub fn track_cover_data(path: &str, options: &MetadataOptions) -> Result<Option<Vec<u8>>, String> {
+    if !has_extension(Path::new(path), "dsf") {
+        let tagged_file = Probe::open(long_path(path))
+            .map_err(|e| tr!("file.openFailed", detail = e))?
+            .read()
+            .map_err(|e| tr!("file.readFailed", detail = e))?;
+
+        if let Some(picture) = tagged_file.primary_tag().and_then(|tag| tag.pictures().first()) {
+            return Ok(Some(picture.data().to_vec()));
+        }
+    }
+
+    folder_cover_path(Path::new(path), &options.cover_file_names)
+        .map(|image| fs::read(long_path(&image)).map_err(|e| tr!("cover.readFailed", detail = e)))
+        .transpose()
+}
+
+/// 音轨封面的图片文件，供系统媒体控制等需要文件路径的地方使用
+///
+/// 嵌入的封面写入 `cache_dir`（按路径和修改时间命名，文件修改后重新写入），没有嵌入封面时返回目录中的封面图片。
+pub fn track_cover_file(path: &str, options: &MetadataOptions, cache_dir: &Path) -> Result<Option<PathBuf>, String> {
+    if !has_extension(Path::new(path), "dsf") {
+        let tagged_file = Probe::open(long_path(path))
+            .map_err(|e| tr!("file.openFailed", detail = e))?
+            .read()
+            .map_err(|e| tr!("file.readFailed", detail = e))?;
+
+        if let Some(picture) = tagged_file.primary_tag().and_then(|tag| tag.pictures().first()) {
+            let modified = fs::metadata(long_path(path)).and_then(|meta| meta.modified()).ok();
+            let hash = format!("{:x}", Sha256::digest(format!("{path}|{modified:?}").as_bytes()));
+            let key = format!("track-{}", &hash[..32]);
+            if let Some(cached) = cached_cover_file(cache_dir, &key) {
+                return Ok(Some(cached));
+            }
+            return store_cover_file(cache_dir, &key, picture.data()).map(Some);
+        }
+    }
+
+    Ok(folder_cover_path(Path::new(path), &options.cover_file_names))
+}
+
+/// 没有嵌入封面时使用目录中的封面图片
+fn apply_folder_cover(metadata: &mut TrackMetadata, path: &str, options: &MetadataOptions) {
+    if metadata.has_cover {
+        metadata.cover_source = CoverSource::Embedded;
+        return;
+    }
+
+    let Some(image) = folder_cover_path(Path::new(path), &options.cover_file_names) else {
+        return;
+    };
+    metadata.has_cover = true;
+    metadata.cover_source = CoverSource::Folder;
+    if options.include_covers {
+        match folder_cover_data_url(&image) {
+            Ok(data_url) => metadata.cover = Some(data_url),
+            Err(e) => eprintln!("Failed to read folder cover '{}': {e}", image.display()),
+        }
+    }
+}
+
+/// 统一整理元数据：规范化所有文本字段，标题缺失时使用文件名
+fn finalize_metadata(mut metadata: TrackMetadata) -> TrackMetadata {
+    metadata.title = normalize_optional(metadata.title);
+    metadata.artist = normalize_optional(metadata.artist);
+    metadata.album = normalize_optional(metadata.album);
+    metadata.album_artist = normalize_optional(metadata.album_artist);
+    metadata.artist_sort = normalize_optional(metadata.artist_sort);
+    metadata.album_artist_sort = normalize_optional(metadata.album_artist_sort);
+    metadata.album_sort = normalize_optional(metadata.album_sort);
+    metadata.encoder = normalize_optional(metadata.encoder);
+    metadata.genre = normalize_optional(metadata.genre);
+    metadata.isrc = normalize_optional(metadata.isrc);
+    metadata.label = normalize_optional(metadata.label);
+    metadata.catalog_number = normalize_optional(metadata.catalog_number);
+    metadata.artists = metadata
+        .artists
+        .iter()
+        .map(|a| normalize_text(a))
+        .filter(|a| !a.is_empty())
+        .collect();
+    metadata.name = normalize_text(&metadata.name);
+
+    metadata.encoding_repair = detect_encoding_repair(&metadata);
+    metadata.encoding_suspect = metadata.encoding_repair.is_some();
+
+    if metadata.title.is_none() {
+        metadata.title = Some(metadata.name.clone());
+    }
+
+    metadata
+}
+
+/// 检测标签乱码并生成修复建议
+///
+/// 将所有可疑字段拼接后统一判断编码，避免不同字段被猜成不同编码。
+fn detect_encoding_repair(metadata: &TrackMetadata) -> Option<EncodingRepair> {
+    let fields = [&metadata.title, &metadata.artist, &metadata.album, &metadata.album_artist];
+    let combined = fields
+        .iter()
+        .filter_map(|field| field.as_deref())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let encoding = repair_mojibake(&combined)?.encoding;
+
+    let repair = |field: &Option<String>| {
+        field
+            .as_deref()
+            .map(|value| reinterpret_latin1(value, encoding).unwrap_or_else(|_| value.to_string()))
+    };
+
+    Some(EncodingRepair {
+        encoding: encoding.to_string(),
+        title: repair(&metadata.title),
+        artist: repair(&metadata.artist),
+        album: repair(&metadata.album),
+        album_artist: repair(&metadata.album_artist),
+    })
+}
+
+/// 根据探测到的文件类型给出容器名称
+const fn container_name(file_type: FileType) -> &'static str {
+    match file_type {
+        FileType::Aac => "ADTS",
+        FileType::Aiff => "AIFF",
+        FileType::Ape => "APE",
+        FileType::Flac => "FLAC",
+        FileType::Mpeg => "MPEG",
+        FileType::Mp4 => "MP4",
+        FileType::Mpc => "Musepack",
+        FileType::Opus | FileType::Vorbis | FileType::Speex => "Ogg",
+        FileType::Wav => "RIFF/WAVE",
+        FileType::WavPack => "WavPack",
+        _ => "Unknown",
+    }
+}
+
+/// 检查文件扩展名（不区分大小写）
+fn has_extension(path: &Path, extension: &str) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case(extension))
+}
+
+/// 获取 DSF 文件的元数据（lofty 不支持该格式）
+fn get_dsf_metadata(path: &str, options: &MetadataOptions) -> Result<TrackMetadata, String> {
     let file_path = Path::new(path);
+    let file_size = fs::metadata(long_path(file_path)).map_err(|e| tr!("file.openFailed", detail = e))?.len();
+    let info = match dsf::read_dsf_info(file_path) {
+        Ok(info) => info,
+        Err(e) => return Ok(fallback_metadata(path, file_size, e)),
+    };
+    let duration = info.duration_secs();
 
-    let tagged_file = Probe::open(file_path)
+    let mut metadata = TrackMetadata {
+        path: path.replace('/', "\\"),
+        name: file_path.file_name().unwrap_or_default().to_string_lossy().to_string(),
+        duration: if duration > 0.0 { Some(duration) } else { None },
+        bitrate: Some(info.bitrate_kbps()),
+        sample_rate: Some(info.sample_rate),
+        channels: Some(info.channels),
+        bit_depth: Some(1),
+        format: Some("DSF".to_string()),
+        container: Some("DSF".to_string()),
+        file_size: Some(file_size),
+        ..Default::default()
+    };
+
+    match dsf::read_dsf_tags(file_path, &info) {
+        Ok(Some(tags)) => {
+            metadata.artists = tags
+                .artist
+                .as_deref()
+                .map(|a| split_artists(a, &options.artist_separators))
+                .unwrap_or_default();
+            metadata.title = tags.title;
+            metadata.artist = tags.artist;
+            metadata.album = tags.album;
+            metadata.album_artist = tags.album_artist;
+        }
+        Ok(None) => {}
+        Err(e) => metadata.tag_error = Some(e),
+    }
+
+    apply_folder_cover(&mut metadata, path, options);
+    Ok(finalize_metadata(metadata))
+}
+
+/// 读取文件，`read_tags` 为 `false` 时只解析音频属性
+fn read_with_tags(path: &Path, read_tags: bool) -> Result<TaggedFile, String> {
+    Probe::open(long_path(path))
         .map_err(|e| e.to_string())?
+        .options(ParseOptions::new().read_tags(read_tags))
         .read()
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| e.to_string())
+}
+
+/// 文件无法解析时只根据文件名、扩展名和大小生成元数据
+fn fallback_metadata(path: &str, file_size: u64, error: String) -> TrackMetadata {
+    let file_path = Path::new(path);
+    finalize_metadata(TrackMetadata {
+        path: path.replace('/', "\\"),
+        name: file_path.file_name().unwrap_or_default().to_string_lossy().to_string(),
+        format: file_path.extension().and_then(|ext| ext.to_str()).map(str::to_uppercase),
+        file_size: Some(file_size),
+        tag_error: Some(error),
+        ..Default::default()
+    })
+}
+
+/// 获取音轨的元数据信息（内部函数，包含封面）
+pub fn get_track_metadata_internal(path: &str) -> Result<TrackMetadata, String> {
+    get_track_metadata_with_options(path, &MetadataOptions::default().with_covers(true))
+}
+
+/// 获取包含自定义字段的扩展元数据
+pub fn get_track_metadata_full_internal(path: &str, options: &MetadataOptions) -> Result<TrackMetadataFull, String> {
+    let metadata = get_track_metadata_with_options(path, options)?;
+    let custom_tags = if has_extension(Path::new(path), "dsf") {
+        Vec::new()
+    } else {
+        read_custom_tags(Path::new(path))?
+    };
+    Ok(TrackMetadataFull { metadata, custom_tags })
+}
+
+/// 按指定选项获取音轨的元数据信息
+pub fn get_track_metadata_with_options(path: &str, options: &MetadataOptions) -> Result<TrackMetadata, String> {
+    if split_archive_path(path).is_some() {
+        return read_entry_metadata(path, options);
+    }
+    let file_path = Path::new(path);
+
+    if has_extension(file_path, "dsf") {
+        return get_dsf_metadata(path, options);
+    }
+
+    // 只有文件本身无法打开时才返回错误
+    let file_size = File::open(long_path(file_path))
+        .and_then(|file| file.metadata())
+        .map_err(|e| tr!("file.openFailed", detail = e))?
+        .len();
+
+    // 标签损坏时跳过标签只读取音频属性，仍然失败时退回到文件名
+    let (tagged_file, tag_error) = match read_with_tags(file_path, true) {
+        Ok(tagged_file) => (tagged_file, None),
+        Err(tag_error) => match read_with_tags(file_path, false) {
+            Ok(tagged_file) => (tagged_file, Some(tag_error)),
+            Err(_) => return Ok(fallback_metadata(path, file_size, tag_error)),
+        },
+    };
 
     let properties = tagged_file.properties();
     let duration = properties.duration().as_secs_f64();
@@ -145,76 +575,585 @@ pub fn get_track_metadata_internal(path: &str) -> Result<TrackMetadata, String>
         channels: properties.channels(),
         bit_depth: properties.bit_depth(),
         format,
+        container: Some(container_name(tagged_file.file_type()).to_string()),
+        file_size: Some(file_size),
+        tag_error,
         ..Default::default()
     };
 
+    if tagged_file.file_type() == FileType::Mpeg {
+        metadata.mp3_info = read_mp3_info(file_path)
+            .map_err(|e| eprintln!("Failed to read MP3 header for '{path}': {e}"))
+            .ok()
+            .flatten();
+    }
+
     if let Some(tag) = tagged_file.primary_tag() {
         metadata.title = tag.title().map(|s| s.to_string());
         metadata.artist = tag.artist().map(|s| s.to_string());
+        metadata.artists = read_artists(tag, metadata.artist.as_deref(), &options.artist_separators);
         metadata.album = tag.album().map(|s| s.to_string());
+        metadata.album_artist = tag.get_string(&ItemKey::AlbumArtist).map(str::to_string);
+        metadata.artist_sort = tag.get_string(&ItemKey::TrackArtistSortOrder).map(str::to_string);
+        metadata.album_artist_sort = tag.get_string(&ItemKey::AlbumArtistSortOrder).map(str::to_string);
+        metadata.album_sort = tag.get_string(&ItemKey::AlbumTitleSortOrder).map(str::to_string);
+        metadata.genre = tag.genre().map(|s| s.to_string());
+        metadata.year = tag.year();
+        metadata.track_number = tag.track();
+        metadata.track_total = tag.track_total();
+        metadata.disc_number = tag.disk();
+        metadata.isrc = tag.get_string(&ItemKey::Isrc).map(str::to_string);
+        metadata.label = tag.get_string(&ItemKey::Label).map(str::to_string);
+        metadata.catalog_number = tag.get_string(&ItemKey::CatalogNumber).map(str::to_string);
 
-        if let Some(picture) = tag.pictures().first() {
-            let mime_type = picture.mime_type().map_or("image/jpeg", lofty::picture::MimeType::as_str);
-            let data = picture.data();
-            metadata.cover = Some(format!("data:{mime_type};base64,{}", general_purpose::STANDARD.encode(data)));
+        metadata.encoder = tag
+            .get_string(&ItemKey::EncoderSoftware)
+            .or_else(|| tag.get_string(&ItemKey::EncoderSettings))
+            .map(str::to_string);
+        metadata.has_cover = !tag.pictures().is_empty();
+        if options.include_covers {
+            metadata.cover = tag.pictures().first().map(cover_data_url);
         }
     }
 
-    if metadata.title.is_none() || metadata.title.as_deref() == Some("") {
-        metadata.title = Some(metadata.name.clone());
+    if metadata.encoder.is_none() {
+        metadata.encoder = metadata.mp3_info.as_ref().and_then(|info| info.encoder.clone());
     }
 
-    Ok(metadata)
+    apply_folder_cover(&mut metadata, path, options);
+    Ok(finalize_metadata(metadata))
 }
 
 
+/// 输出路径的扩展名与图片实际格式不一致时的处理方式
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum CoverFormatMismatch {
+    /// 把扩展名改成实际格式
+    #[default]
+    FixExtension,
+    /// 转码为扩展名对应的格式
+    Transcode,
+}
+
+/// 提取封面的选项
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ExtractCoverOptions {
+    pub on_mismatch: CoverFormatMismatch,
+    /// 要提取的图片类型（如 "front"、"back"），默认优先正面封面
+    pub picture_type: Option<String>,
+    /// 目标位置已有相同内容的文件时不再写入
+    pub skip_identical: bool,
+}
+
+/// 统一图片扩展名写法
+fn normalize_image_extension(ext: &str) -> String {
+    match ext.to_ascii_lowercase().as_str() {
+        "jpeg" | "jpe" | "jfif" => "jpg".to_string(),
+        "tif" => "tiff".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// 确定图片的实际格式：优先按文件头判断，无法识别时使用标签中的 MIME 类型
+fn actual_image_extension(picture: &Picture) -> &'static str {
+    sniff_image_extension(picture.data()).unwrap_or_else(|| {
+        match picture.mime_type().map(lofty::picture::MimeType::as_str) {
+            Some("image/png") => "png",
+            Some("image/gif") => "gif",
+            Some("image/webp") => "webp",
+            Some("image/bmp") => "bmp",
+            _ => "jpg",
+        }
+    })
+}
+
+/// 将图片转码为指定扩展名对应的格式
+fn transcode_image(data: &[u8], extension: &str) -> Result<Vec<u8>, String> {
+    let format = image::ImageFormat::from_extension(extension)
+        .ok_or_else(|| tr!("cover.convertUnsupported", format = extension))?;
+    let mut decoded = image::load_from_memory(data).map_err(|e| tr!("cover.decodeFailed", detail = e))?;
+    if format == image::ImageFormat::Jpeg {
+        // JPEG 不支持透明通道
+        decoded = image::DynamicImage::ImageRgb8(decoded.to_rgb8());
+    }
+
+    let mut output = Vec::new();
+    decoded
+        .write_to(&mut std::io::Cursor::new(&mut output), format)
+        .map_err(|e| tr!("cover.convertFailed", detail = e))?;
+    Ok(output)
+}
+
 /// 提取音频文件的封面并保存到指定路径
-pub fn extract_cover_internal(audio_path: &str, output_path: &str) -> Result<String, String> {
+///
+/// 图片格式以文件头为准。输出路径的扩展名不一致时按 `on_mismatch` 修正扩展名或转码，
+/// 返回最终写入的路径。
+pub fn extract_cover_internal(
+    audio_path: &str,
+    output_path: &str,
+    options: &ExtractCoverOptions,
+) -> Result<String, String> {
     let file_path = Path::new(audio_path);
 
-    let tagged_file = Probe::open(file_path)
-        .map_err(|e| format!("无法打开文件: {e}"))?
+    let tagged_file = Probe::open(long_path(file_path))
+        .map_err(|e| tr!("file.openFailed", detail = e))?
         .read()
-        .map_err(|e| format!("无法读取文件: {e}"))?;
+        .map_err(|e| tr!("file.readFailed", detail = e))?;
 
     let tag = tagged_file
         .primary_tag()
-        .ok_or_else(|| "文件没有标签信息".to_string())?;
-
-    let picture = tag
-        .pictures()
-        .first()
-        .ok_or_else(|| "文件没有封面图片".to_string())?;
+        .ok_or_else(|| tr!("tags.noTags"))?;
 
-    let data = picture.data();
-    
-    // 根据 MIME 类型确定文件扩展名
-    let extension = match picture.mime_type().map(lofty::picture::MimeType::as_str) {
-        Some("image/png") => "png",
-        Some("image/gif") => "gif",
-        Some("image/webp") => "webp",
-        Some("image/bmp") => "bmp",
-        _ => "jpg", // 默认为 jpg
+    let pictures = tag.pictures();
+    let picture = match options.picture_type.as_deref() {
+        Some(name) => {
+            let picture_type = parse_picture_type(name)?;
+            pictures
+                .iter()
+                .find(|p| p.pic_type() == picture_type)
+                .ok_or_else(|| tr!("cover.noPictureOfType", name = name))?
+        }
+        None => pictures
+            .iter()
+            .find(|p| p.pic_type() == PictureType::CoverFront)
+            .or_else(|| pictures.first())
+            .ok_or_else(|| tr!("cover.noCover"))?,
     };
 
-    // 处理输出路径
+    let actual = actual_image_extension(picture);
     let output = Path::new(output_path);
-    let final_path = if output.extension().is_none() {
-        // 如果没有扩展名，添加正确的扩展名
-        output.with_extension(extension)
-    } else {
-        output.to_path_buf()
+    let requested = output.extension().map(|ext| normalize_image_extension(&ext.to_string_lossy()));
+
+    let (final_path, data) = match requested {
+        Some(ext) if ext == actual => (output.to_path_buf(), picture.data().to_vec()),
+        Some(ext) if options.on_mismatch == CoverFormatMismatch::Transcode => {
+            (output.to_path_buf(), transcode_image(picture.data(), &ext)?)
+        }
+        _ => (output.with_extension(actual), picture.data().to_vec()),
     };
 
+    if options.skip_identical && fs::read(long_path(&final_path)).is_ok_and(|existing| existing == data) {
+        return Ok(final_path.to_string_lossy().to_string());
+    }
+
     // 确保父目录存在
     if let Some(parent) = final_path.parent() {
-        fs::create_dir_all(parent)
-            .map_err(|e| format!("无法创建目录: {e}"))?;
+        fs::create_dir_all(long_path(parent))
+            .map_err(|e| tr!("file.createDirFailed", detail = e))?;
     }
 
-    // 写入文件
-    fs::write(&final_path, data)
-        .map_err(|e| format!("无法写入文件: {e}"))?;
+    fs::write(long_path(&final_path), data)
+        .map_err(|e| tr!("file.writeFailed", detail = e))?;
 
     Ok(final_path.to_string_lossy().to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// FLAC 元数据块：1 字节类型（最高位表示最后一块）+ 24 位长度 + 内容
+    fn flac_block(block_type: u8, last: bool, content: &[u8]) -> Vec<u8> {
+        let len = u32::try_from(content.len()).unwrap().to_be_bytes();
+        let mut block = vec![block_type | if last { 0x80 } else { 0 }, len[1], len[2], len[3]];
+        block.extend_from_slice(content);
+        block
+    }
+
+    /// 10 秒、44.1 kHz、双声道、16 位的 FLAC，`comment` 为 VORBIS_COMMENT 块的内容
+    fn write_flac(dir: &TempDir, name: &str, comment: &[u8]) -> String {
+        write_flac_with_cover(dir, name, comment, None)
+    }
+
+    /// PICTURE 块：500x500 的 JPEG 封面，`data` 为图片内容
+    fn picture_block(data: &[u8]) -> Vec<u8> {
+        let mut content = Vec::new();
+        content.extend(3u32.to_be_bytes());
+        content.extend(10u32.to_be_bytes());
+        content.extend(b"image/jpeg");
+        content.extend(0u32.to_be_bytes());
+        for value in [500u32, 500, 24, 0] {
+            content.extend(value.to_be_bytes());
+        }
+        content.extend(u32::try_from(data.len()).unwrap().to_be_bytes());
+        content.extend(data);
+        flac_block(6, true, &content)
+    }
+
+    fn write_flac_with_cover(dir: &TempDir, name: &str, comment: &[u8], cover: Option<&[u8]>) -> String {
+        let mut stream_info = Vec::new();
+        stream_info.extend(4096u16.to_be_bytes());
+        stream_info.extend(4096u16.to_be_bytes());
+        stream_info.extend([0; 6]);
+        let packed: u64 = (44_100 << 44) | (1 << 41) | (15 << 36) | 0x6_BAA8;
+        stream_info.extend(packed.to_be_bytes());
+        stream_info.extend([0; 16]);
+
+        let mut bytes = b"fLaC".to_vec();
+        bytes.extend(flac_block(0, false, &stream_info));
+        bytes.extend(flac_block(4, cover.is_none(), comment));
+        if let Some(cover) = cover {
+            bytes.extend(picture_block(cover));
+        }
+        bytes.extend([0; 1024]);
+
+        let path = dir.path().join(name);
+        fs::write(&path, bytes).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    fn vorbis_comment(fields: &[&str]) -> Vec<u8> {
+        let mut content = Vec::new();
+        content.extend(6u32.to_le_bytes());
+        content.extend(b"vendor");
+        content.extend(u32::try_from(fields.len()).unwrap().to_le_bytes());
+        for field in fields {
+            content.extend(u32::try_from(field.len()).unwrap().to_le_bytes());
+            content.extend(field.as_bytes());
+        }
+        content
+    }
+
+    fn read(path: &str) -> TrackMetadata {
+        get_track_metadata_with_options(path, &MetadataOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn reads_tags_and_properties_of_an_intact_file() {
+        let dir = TempDir::new().unwrap();
+        let path = write_flac(&dir, "intact.flac", &vorbis_comment(&["TITLE=晴天", "ARTIST=周杰伦"]));
+
+        let metadata = read(&path);
+        assert_eq!(metadata.tag_error, None);
+        assert_eq!(metadata.title.as_deref(), Some("晴天"));
+        assert_eq!(metadata.artist.as_deref(), Some("周杰伦"));
+        assert_eq!(metadata.sample_rate, Some(44_100));
+        assert_eq!(metadata.duration, Some(10.0));
+    }
+
+    /// macOS 上写入的 NFD 标签与 NFC 标签读取后相同，旧软件留下的空字符和末尾空白被去掉
+    #[test]
+    fn tag_text_is_normalized_to_nfc() {
+        let dir = TempDir::new().unwrap();
+        let nfd = write_flac(&dir, "nfd.flac", &vorbis_comment(&["ARTIST=Bjo\u{308}rk", "ALBUM=Homoge\u{301}nic"]));
+        let nfc = write_flac(&dir, "nfc.flac", &vorbis_comment(&["ARTIST=Björk\0\0", "ALBUM=Homogénic  "]));
+
+        let (nfd, nfc) = (read(&nfd), read(&nfc));
+        assert_eq!(nfd.artist.as_deref(), Some("Björk"));
+        assert_eq!(nfd.artist, nfc.artist);
+        assert_eq!(nfd.artists, nfc.artists);
+        assert_eq!(nfd.album.as_deref(), Some("Homogénic"));
+        assert_eq!(nfd.album, nfc.album);
+    }
+
+    /// 标签区域损坏（vendor 长度超出块大小）时跳过标签，仍然给出音频属性，标题取文件名
+    #[test]
+    fn skips_corrupted_tags_but_keeps_audio_properties() {
+        let dir = TempDir::new().unwrap();
+        let mut comment = vorbis_comment(&["TITLE=晴天"]);
+        comment[..4].copy_from_slice(&u32::MAX.to_le_bytes());
+        let path = write_flac(&dir, "corrupt-tags.flac", &comment);
+
+        let metadata = read(&path);
+        assert!(metadata.tag_error.is_some());
+        assert_eq!(metadata.title.as_deref(), Some("corrupt-tags.flac"));
+        assert_eq!(metadata.duration, Some(10.0));
+        assert_eq!((metadata.sample_rate, metadata.channels, metadata.bit_depth), (Some(44_100), Some(2), Some(16)));
+        assert_eq!(metadata.container.as_deref(), Some(container_name(FileType::Flac)));
+    }
+
+    /// 音频本身也无法解析时只根据文件名生成元数据
+    #[test]
+    fn falls_back_to_file_name_when_probing_fails() {
+        let dir = TempDir::new().unwrap();
+        for (name, content) in [("02 Garbage.flac", &b"not audio"[..]), ("empty.mp3", &b""[..])] {
+            let path = dir.path().join(name);
+            fs::write(&path, content).unwrap();
+
+            let metadata = read(&path.to_string_lossy());
+            assert!(metadata.tag_error.is_some(), "{name}");
+            assert_eq!(metadata.name, name);
+            assert_eq!(metadata.title.as_deref(), Some(name));
+            assert_eq!(metadata.duration, None);
+            assert_eq!(metadata.container, None);
+            assert_eq!(metadata.file_size, Some(content.len() as u64));
+        }
+        assert_eq!(read(&dir.path().join("empty.mp3").to_string_lossy()).format.as_deref(), Some("MP3"));
+    }
+
+    #[test]
+    fn missing_files_are_an_error() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("missing.flac");
+        assert!(get_track_metadata_with_options(&path.to_string_lossy(), &MetadataOptions::default()).is_err());
+    }
+
+    #[test]
+    fn covers_are_only_encoded_when_requested() {
+        let dir = TempDir::new().unwrap();
+        let cover = [0xFF, 0xD8, 0xFF, 0xE0, 0, 0x10];
+        let path = write_flac_with_cover(&dir, "cover.flac", &vorbis_comment(&["TITLE=晴天"]), Some(&cover));
+
+        let scanned = read(&path);
+        assert!(scanned.has_cover);
+        assert_eq!(scanned.cover, None);
+
+        let options = MetadataOptions::default().with_covers(true);
+        let with_cover = get_track_metadata_with_options(&path, &options).unwrap();
+        assert_eq!(with_cover.cover.as_deref(), Some("data:image/jpeg;base64,/9j/4AAQ"));
+    }
+
+    /// 扫描时跳过封面编码的耗时对比，手动运行：
+    /// `cargo test --release -- --ignored scan_timing --nocapture`
+    #[test]
+    #[ignore = "timing measurement"]
+    fn scan_timing_with_and_without_covers() {
+        const TRACKS: usize = 200;
+        let dir = TempDir::new().unwrap();
+        let mut cover = vec![0xFF, 0xD8, 0xFF, 0xE0];
+        cover.resize(512 * 1024, 0x55);
+        let comment = vorbis_comment(&["TITLE=晴天", "ARTIST=周杰伦"]);
+        let paths: Vec<String> =
+            (0..TRACKS).map(|i| write_flac_with_cover(&dir, &format!("{i:03}.flac"), &comment, Some(&cover))).collect();
+
+        let scan = |include_covers: bool| {
+            let options = MetadataOptions::default().with_covers(include_covers);
+            let started = std::time::Instant::now();
+            for path in &paths {
+                get_track_metadata_with_options(path, &options).unwrap();
+            }
+            started.elapsed()
+        };
+        // 先读一遍，让文件进入系统缓存
+        scan(false);
+        let (with_covers, without_covers) = (scan(true), scan(false));
+        println!("{TRACKS} tracks with 512 KiB covers: {with_covers:?} with covers, {without_covers:?} without");
+        assert!(without_covers < with_covers);
+    }
+
+    /// 1 秒、8 kHz、单声道、16 位的静音 AIFF
+    fn aiff_fixture() -> Vec<u8> {
+        let frames: u32 = 8_000;
+        let mut comm = Vec::new();
+        comm.extend(1u16.to_be_bytes());
+        comm.extend(frames.to_be_bytes());
+        comm.extend(16u16.to_be_bytes());
+        // 80 位扩展精度的 8000.0
+        comm.extend([0x40, 0x0B, 0xFA, 0, 0, 0, 0, 0, 0, 0]);
+
+        let mut ssnd = vec![0; 8];
+        ssnd.resize(8 + frames as usize * 2, 0);
+
+        let mut form = b"AIFF".to_vec();
+        for (id, content) in [(b"COMM", comm), (b"SSND", ssnd)] {
+            form.extend(id);
+            form.extend(u32::try_from(content.len()).unwrap().to_be_bytes());
+            form.extend(content);
+        }
+        let mut bytes = b"FORM".to_vec();
+        bytes.extend(u32::try_from(form.len()).unwrap().to_be_bytes());
+        bytes.extend(form);
+        bytes
+    }
+
+    /// 1 秒、44.1 kHz、双声道、16 位的 Monkey's Audio（3.99 版文件头）
+    fn ape_fixture() -> Vec<u8> {
+        let mut bytes = b"MAC ".to_vec();
+        bytes.extend(3990u16.to_le_bytes());
+        bytes.extend(0u16.to_le_bytes());
+        for value in [52u32, 24, 0, 0, 1024, 0, 0] {
+            bytes.extend(value.to_le_bytes());
+        }
+        bytes.extend([0; 16]);
+        bytes.extend(2000u16.to_le_bytes());
+        bytes.extend(0u16.to_le_bytes());
+        for value in [73_728u32 * 4, 44_100, 1] {
+            bytes.extend(value.to_le_bytes());
+        }
+        bytes.extend(16u16.to_le_bytes());
+        bytes.extend(2u16.to_le_bytes());
+        bytes.extend(44_100u32.to_le_bytes());
+        bytes.extend([0; 1024]);
+        bytes
+    }
+
+    /// 1 秒、44.1 kHz、双声道、16 位的 WavPack，只有一个块头
+    fn wavpack_fixture() -> Vec<u8> {
+        // 采样率表中 44.1 kHz 的序号为 9；首块和尾块标志；每个采样 2 字节
+        let flags: u32 = (9 << 23) | 0x1000 | 0x800 | 1;
+        let mut bytes = b"wvpk".to_vec();
+        bytes.extend(24u32.to_le_bytes());
+        bytes.extend(0x410u16.to_le_bytes());
+        bytes.extend([0, 0]);
+        for value in [44_100u32, 0, 44_100, flags, 0] {
+            bytes.extend(value.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Ogg 页的 CRC（多项式 0x04C11DB7，不反转）
+    fn ogg_crc(page: &[u8]) -> u32 {
+        page.iter().fold(0u32, |crc, byte| {
+            (0..8).fold(crc ^ (u32::from(*byte) << 24), |crc, _| {
+                if crc & 0x8000_0000 == 0 { crc << 1 } else { (crc << 1) ^ 0x04C1_1DB7 }
+            })
+        })
+    }
+
+    fn ogg_page(header_type: u8, granule: u64, sequence: u32, packets: &[&[u8]]) -> Vec<u8> {
+        let mut page = b"OggS".to_vec();
+        page.extend([0, header_type]);
+        page.extend(granule.to_le_bytes());
+        page.extend(1u32.to_le_bytes());
+        page.extend(sequence.to_le_bytes());
+        page.extend([0; 4]);
+        let mut lacing = Vec::new();
+        for packet in packets {
+            lacing.extend(vec![255; packet.len() / 255]);
+            lacing.push(u8::try_from(packet.len() % 255).unwrap());
+        }
+        page.push(u8::try_from(lacing.len()).unwrap());
+        page.extend(lacing);
+        page.extend(packets.concat());
+        let crc = ogg_crc(&page);
+        page[22..26].copy_from_slice(&crc.to_le_bytes());
+        page
+    }
+
+    /// 1 秒、48 kHz、双声道的 Opus，`comment` 为 OpusTags 中的 Vorbis 注释
+    fn opus_fixture(comment: &[u8]) -> Vec<u8> {
+        const PRE_SKIP: u16 = 312;
+        let mut head = b"OpusHead".to_vec();
+        head.extend([1, 2]);
+        head.extend(PRE_SKIP.to_le_bytes());
+        head.extend(48_000u32.to_le_bytes());
+        head.extend([0, 0, 0]);
+        let mut tags = b"OpusTags".to_vec();
+        tags.extend(comment);
+
+        let mut bytes = ogg_page(0x02, 0, 0, &[&head]);
+        bytes.extend(ogg_page(0, 0, 1, &[&tags]));
+        // 50 个 20 ms 的空 CELT 帧（解码为静音）
+        bytes.extend(ogg_page(0x04, 48_000 + u64::from(PRE_SKIP), 2, &[&[0xFC][..]; 50]));
+        bytes
+    }
+
+    /// 1 秒、2.8224 MHz、双声道的 DSF，末尾附带 `tag`
+    fn dsf_fixture(tag: &id3::Tag) -> Vec<u8> {
+        const DATA_LEN: u64 = 2 * 4096;
+        let mut id3 = Vec::new();
+        tag.write_to(&mut id3, id3::Version::Id3v24).unwrap();
+        let metadata_offset = 28 + 52 + 12 + DATA_LEN;
+
+        let mut bytes = b"DSD ".to_vec();
+        for value in [28, metadata_offset + id3.len() as u64, metadata_offset] {
+            bytes.extend(value.to_le_bytes());
+        }
+        bytes.extend(b"fmt ");
+        bytes.extend(52u64.to_le_bytes());
+        for value in [1u32, 0, 2, 2, 2_822_400, 1] {
+            bytes.extend(value.to_le_bytes());
+        }
+        bytes.extend(2_822_400u64.to_le_bytes());
+        bytes.extend(4096u32.to_le_bytes());
+        bytes.extend(0u32.to_le_bytes());
+        bytes.extend(b"data");
+        bytes.extend((12 + DATA_LEN).to_le_bytes());
+        bytes.resize(bytes.len() + 2 * 4096, 0x69);
+        bytes.extend(id3);
+        bytes
+    }
+
+    /// 用 lofty 写入格式对应的主标签
+    fn tag_with_lofty(path: &str) {
+        use lofty::config::WriteOptions;
+        use lofty::tag::TagExt;
+
+        let tagged_file = Probe::open(path).unwrap().read().unwrap();
+        let mut tag = Tag::new(tagged_file.primary_tag_type());
+        tag.set_title("夜曲".to_string());
+        tag.set_artist("周杰伦".to_string());
+        tag.set_album("十一月的萧邦".to_string());
+        tag.save_to_path(path, WriteOptions::default()).unwrap();
+    }
+
+    fn write_fixture(dir: &TempDir, name: &str, bytes: &[u8]) -> String {
+        let path = dir.path().join(name);
+        fs::write(&path, bytes).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn reads_tags_and_properties_of_each_added_format() {
+        let dir = TempDir::new().unwrap();
+        let comment = vorbis_comment(&["TITLE=夜曲", "ARTIST=周杰伦", "ALBUM=十一月的萧邦"]);
+        let mut id3 = id3::Tag::new();
+        id3::TagLike::set_title(&mut id3, "夜曲");
+        id3::TagLike::set_artist(&mut id3, "周杰伦");
+        id3::TagLike::set_album(&mut id3, "十一月的萧邦");
+
+        let opus = write_fixture(&dir, "夜曲.opus", &opus_fixture(&comment));
+        let dsf = write_fixture(&dir, "夜曲.dsf", &dsf_fixture(&id3));
+        let lofty_tagged = [
+            (write_fixture(&dir, "夜曲.ape", &ape_fixture()), 44_100, 2),
+            (write_fixture(&dir, "夜曲.wv", &wavpack_fixture()), 44_100, 2),
+            (write_fixture(&dir, "夜曲.aiff", &aiff_fixture()), 8_000, 1),
+        ];
+        for (path, _, _) in &lofty_tagged {
+            tag_with_lofty(path);
+        }
+
+        let expected = [(opus, 48_000, 2), (dsf, 2_822_400, 2)].into_iter().chain(lofty_tagged);
+        for (path, sample_rate, channels) in expected {
+            let metadata = read(&path);
+            assert_eq!(metadata.tag_error, None, "{path}");
+            assert_eq!(metadata.title.as_deref(), Some("夜曲"), "{path}");
+            assert_eq!(metadata.artist.as_deref(), Some("周杰伦"), "{path}");
+            assert_eq!(metadata.album.as_deref(), Some("十一月的萧邦"), "{path}");
+            assert_eq!(metadata.sample_rate, Some(sample_rate), "{path}");
+            assert_eq!(metadata.channels, Some(channels), "{path}");
+            let duration = metadata.duration.unwrap_or_default();
+            assert!((duration - 1.0).abs() < 0.01, "{path}: {duration}");
+        }
+
+        let scanned = crate::media::filesystem::collect_audio_paths(&[dir.path().to_string_lossy().to_string()]);
+        assert_eq!(scanned.len(), 5);
+    }
+
+    #[test]
+    fn aiff_and_opus_decode() {
+        use crate::audio::decoder::{ensure_decodable, SymphoniaDecoder};
+
+        let dir = TempDir::new().unwrap();
+        let aiff = write_fixture(&dir, "silence.aiff", &aiff_fixture());
+        let opus = write_fixture(&dir, "silence.opus", &opus_fixture(&vorbis_comment(&[])));
+        // 1 秒的单声道 8 kHz 和双声道 48 kHz
+        for (path, samples) in [(aiff, 8_000), (opus, 2 * 48_000)] {
+            ensure_decodable(&path).unwrap();
+            let mut decoder = SymphoniaDecoder::new(&path).unwrap();
+            decoder.prefill_buffer().unwrap();
+            assert_eq!(decoder.count(), samples, "{path}");
+        }
+    }
+
+    #[test]
+    fn ape_wavpack_and_dsf_are_read_but_rejected_for_playback() {
+        use crate::audio::decoder::ensure_decodable;
+        use crate::error::AppError;
+
+        let dir = TempDir::new().unwrap();
+        let files = [
+            (write_fixture(&dir, "a.ape", &ape_fixture()), "APE"),
+            (write_fixture(&dir, "a.wv", &wavpack_fixture()), "WV"),
+            (write_fixture(&dir, "a.dsf", &dsf_fixture(&id3::Tag::new())), "DSF"),
+        ];
+        for (path, format) in files {
+            assert_eq!(read(&path).tag_error, None, "{path}");
+            let error = ensure_decodable(&path).unwrap_err();
+            assert!(matches!(&error, AppError::UnsupportedFormat(f) if f == format), "{path}: {error}");
+        }
+    }
+}
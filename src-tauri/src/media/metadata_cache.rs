@@ -0,0 +1,227 @@
+//! 元数据缓存模块
+//!
+//! 按路径缓存不含封面的元数据，文件大小或修改时间变化后自动失效。缓存以 `canonical_key` 为键，
+//! 同一文件的不同路径写法共用一条缓存。
+//! 目录扫描、导出和批量读取都经由这里，单个文件可通过 `refresh_track` 强制重新读取。
+//! 缓存可保存到磁盘（cache/metadata/tracks.json），下次启动后增量扫描据此判断哪些文件变化。
+
+use super::metadata::{get_track_metadata_with_options, MetadataOptions, TrackMetadata};
+use crate::error::{AppError, AppResult};
+use crate::paths::canonical_key;
+use crate::AppState;
+use crate::tr;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::SystemTime;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// 单个文件元数据更新事件名称，负载为新的 `TrackMetadata`
+pub const TRACK_METADATA_CHANGED_EVENT: &str = "track-metadata-changed";
+/// 文件丢失事件名称
+pub const TRACK_MISSING_EVENT: &str = "track-missing";
+
+/// 文件丢失事件负载
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackMissingEvent {
+    pub path: String,
+}
+
+/// 缓存文件名
+const CACHE_FILE: &str = "tracks.json";
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CacheEntry {
+    /// 写入缓存时使用的路径（旧版本的缓存文件没有此项，加载时由键补上）
+    #[serde(default)]
+    path: String,
+    modified: Option<SystemTime>,
+    size: u64,
+    metadata: TrackMetadata,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MetadataCache {
+    /// 生成缓存时使用的选项，选项变化后整个缓存失效
+    options_key: String,
+    /// `canonical_key` -> 缓存项
+    entries: HashMap<String, CacheEntry>,
+}
+
+static METADATA_CACHE: LazyLock<Mutex<MetadataCache>> = LazyLock::new(|| Mutex::new(MetadataCache::default()));
+
+/// 影响元数据结果的选项摘要
+fn options_key(options: &MetadataOptions) -> String {
+    format!("{}\u{1}{}", options.artist_separators.join("\u{0}"), options.cover_file_names.join("\u{0}"))
+}
+
+fn file_stamp(path: &str) -> Option<(Option<SystemTime>, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok(), metadata.len()))
+}
+
+/// 读取元数据，优先使用缓存
+///
+/// 需要封面数据时直接读取文件，不经过缓存。
+pub fn cached_track_metadata(path: &str, options: &MetadataOptions) -> Result<TrackMetadata, String> {
+    if options.include_covers {
+        return get_track_metadata_with_options(path, options);
+    }
+
+    let key = options_key(options);
+    let stamp = file_stamp(path);
+    {
+        let mut cache = METADATA_CACHE.lock().unwrap();
+        if cache.options_key != key {
+            cache.options_key = key;
+            cache.entries.clear();
+        }
+        if let (Some(entry), Some((modified, size))) = (cache.entries.get(&canonical_key(path)), stamp) {
+            if entry.modified == modified && entry.size == size {
+                return Ok(entry.metadata.clone());
+            }
+        }
+    }
+
+    let metadata = get_track_metadata_with_options(path, options)?;
+    store(path, stamp, &metadata);
+    Ok(metadata)
+}
+
+fn store(path: &str, stamp: Option<(Option<SystemTime>, u64)>, metadata: &TrackMetadata) {
+    let Some((modified, size)) = stamp else {
+        return;
+    };
+    let mut metadata = metadata.clone();
+    metadata.cover = None;
+    METADATA_CACHE
+        .lock()
+        .unwrap()
+        .entries
+        .insert(canonical_key(path), CacheEntry { path: path.to_string(), modified, size, metadata });
+}
+
+/// 从缓存中移除文件
+pub fn invalidate(path: &str) {
+    METADATA_CACHE.lock().unwrap().entries.remove(&canonical_key(path));
+}
+
+/// 文件在缓存中的状态：`None` 表示未缓存，`Some(true)` 表示大小和修改时间都未变化
+#[must_use]
+pub fn is_fresh(path: &str) -> Option<bool> {
+    let stamp = file_stamp(path);
+    let cache = METADATA_CACHE.lock().unwrap();
+    let entry = cache.entries.get(&canonical_key(path))?;
+    Some(stamp.is_some_and(|(modified, size)| entry.modified == modified && entry.size == size))
+}
+
+/// 不做校验地读取缓存中的元数据（用于已删除或已移动的文件）
+#[must_use]
+pub fn peek(path: &str) -> Option<TrackMetadata> {
+    METADATA_CACHE.lock().unwrap().entries.get(&canonical_key(path)).map(|entry| entry.metadata.clone())
+}
+
+/// 缓存中位于目录下的所有文件
+#[must_use]
+pub fn cached_paths_under(dir: &Path) -> Vec<String> {
+    let mut prefix = canonical_key(&dir.to_string_lossy());
+    if !prefix.ends_with('/') {
+        prefix.push('/');
+    }
+
+    METADATA_CACHE
+        .lock()
+        .unwrap()
+        .entries
+        .iter()
+        .filter(|(key, _)| key.starts_with(&prefix))
+        .map(|(_, entry)| entry.path.clone())
+        .collect()
+}
+
+/// 从缓存中移除目录下的所有文件，返回被移除的路径
+pub fn remove_under(dir: &Path) -> Vec<String> {
+    let removed = cached_paths_under(dir);
+    let mut cache = METADATA_CACHE.lock().unwrap();
+    for path in &removed {
+        cache.entries.remove(&canonical_key(path));
+    }
+    removed
+}
+
+/// 从磁盘加载缓存，文件不存在或损坏时保持为空
+///
+/// 旧版本的缓存以原始路径为键，加载时统一改为 `canonical_key`，同一文件有多条缓存时只保留一条。
+pub fn load_persisted(cache_dir: &Path) {
+    let loaded: Option<MetadataCache> = fs::read_to_string(cache_dir.join(CACHE_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok());
+    if let Some(mut loaded) = loaded {
+        loaded.entries = loaded
+            .entries
+            .into_iter()
+            .map(|(key, mut entry)| {
+                if entry.path.is_empty() {
+                    entry.path = key;
+                }
+                (canonical_key(&entry.path), entry)
+            })
+            .collect();
+        *METADATA_CACHE.lock().unwrap() = loaded;
+    }
+}
+
+/// 将缓存保存到磁盘
+pub fn persist(cache_dir: &Path) -> Result<(), String> {
+    let content = serde_json::to_string(&*METADATA_CACHE.lock().unwrap())
+        .map_err(|e| tr!("metadata.cacheSerializeFailed", detail = e))?;
+    let path = cache_dir.join(CACHE_FILE);
+    let temp_path = cache_dir.join(format!("{CACHE_FILE}.tmp"));
+    fs::write(&temp_path, content)
+        .and_then(|()| fs::rename(&temp_path, &path))
+        .map_err(|e| tr!("metadata.cacheWriteFailed", detail = e))
+}
+
+/// 跳过缓存重新读取文件，并用结果更新缓存
+///
+/// 文件不存在时移除缓存并返回 `AppError::FileNotFound`。
+pub fn refresh_track(path: &str, options: &MetadataOptions) -> AppResult<TrackMetadata> {
+    if !Path::new(path).is_file() {
+        invalidate(path);
+        return Err(AppError::FileNotFound(path.to_string()));
+    }
+
+    let stamp = file_stamp(path);
+    let metadata = get_track_metadata_with_options(path, options)?;
+    store(path, stamp, &metadata);
+    Ok(metadata)
+}
+
+/// 重新读取文件并通知前端
+///
+/// 成功时发送 `track-metadata-changed`，文件丢失时发送 `track-missing`；曲库数据库中的记录同步更新或删除。
+pub fn refresh_and_notify(app: &AppHandle, path: &str, options: &MetadataOptions) -> AppResult<TrackMetadata> {
+    let library = app.try_state::<AppState>().map(|state| Arc::clone(&state.library));
+    match refresh_track(path, options) {
+        Ok(metadata) => {
+            if let Some(Err(e)) = library.map(|db| db.update_track(&metadata)) {
+                eprintln!("Failed to update library database: {e}");
+            }
+            let _ = app.emit(TRACK_METADATA_CHANGED_EVENT, &metadata);
+            Ok(metadata)
+        }
+        Err(AppError::FileNotFound(missing)) => {
+            if let Some(Err(e)) = library.map(|db| db.remove_tracks(std::slice::from_ref(&missing))) {
+                eprintln!("Failed to update library database: {e}");
+            }
+            let _ = app.emit(TRACK_MISSING_EVENT, TrackMissingEvent { path: missing.clone() });
+            Err(AppError::FileNotFound(missing))
+        }
+        Err(e) => Err(e),
+    }
+}
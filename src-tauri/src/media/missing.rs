@@ -0,0 +1,378 @@
+//! 丢失文件检查模块
+//!
+//! 检查曲库数据库以及前端传入的播放列表、播放队列中记录的路径是否仍然存在，
+//! 按引用来源分组报告，并可一次性清除对已丢失文件的引用。
+//! 曲库中位于网络共享上的根目录无法访问，或根目录被标记为离线（如拔出的移动硬盘）时，
+//! 其中的文件单独列为离线，不会被当作已删除清除。
+
+use super::filesystem::check_file_exists_internal;
+use super::metadata_cache::invalidate;
+use super::network::{network_path_classifier, probe_dir, with_timeout, PROBE_TIMEOUT};
+use crate::error::{AppError, AppResult};
+use crate::library::LibraryDb;
+use crate::paths::canonical_key;
+use crate::tasks::CancelToken;
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{LazyLock, Mutex};
+use std::time::Duration;
+
+/// 丢失文件清除事件名称，前端据此从播放队列等处移除条目
+pub const MISSING_FILES_PRUNED_EVENT: &str = "missing-files-pruned";
+
+/// 曲库数据库作为引用来源时的名称
+pub const LIBRARY_SOURCE: &str = "library";
+
+/// 每批检查的路径数，每批完成后报告一次进度
+const CHECK_BATCH_SIZE: usize = 256;
+
+/// 批量检查网络路径时使用的线程数
+const NETWORK_CHECK_THREADS: usize = 8;
+
+/// 网络路径专用的线程池，阻塞的检查不会占满全局线程池
+static NETWORK_POOL: LazyLock<Option<ThreadPool>> = LazyLock::new(|| {
+    ThreadPoolBuilder::new()
+        .num_threads(NETWORK_CHECK_THREADS)
+        .thread_name(|index| format!("network-check-{index}"))
+        .build()
+        .ok()
+});
+
+/// 批量检查时单个文件的状态
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FileStatus {
+    Exists,
+    Missing,
+    /// 所在网络共享无法访问或根目录离线，文件是否存在未知
+    Offline,
+}
+
+/// 一组来自同一来源的路径（如 `queue`、`playlist:收藏`）
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PathReferences {
+    pub source: String,
+    pub paths: Vec<String>,
+}
+
+/// 找不到的文件及引用它的来源
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MissingFile {
+    pub path: String,
+    pub sources: Vec<String>,
+}
+
+/// 丢失文件报告
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct MissingFilesReport {
+    /// 检查过的不同路径数
+    pub checked: usize,
+    pub missing: Vec<MissingFile>,
+    /// 当前离线的根目录
+    pub offline_roots: Vec<String>,
+    /// 位于离线根目录中而没有检查的文件，以及所在网络共享检查超时的文件
+    pub offline: Vec<MissingFile>,
+}
+
+/// 清除结果
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PruneResult {
+    pub removed: Vec<String>,
+    /// 文件已经重新出现而保留的路径
+    pub restored: Vec<String>,
+    /// 所在根目录离线而保留的路径
+    pub offline: Vec<String>,
+}
+
+/// 曲库中当前离线的根目录：被标记为离线的根目录，以及无法访问的网络根目录
+fn offline_roots(db: &LibraryDb) -> AppResult<Vec<String>> {
+    let mut roots: Vec<String> = db.offline_roots()?.into_iter().map(|(root, _)| root).collect();
+    for root in db.network_roots()? {
+        if !roots.contains(&root) && matches!(probe_dir(Path::new(&root)), Err(AppError::NetworkUnavailable(_))) {
+            roots.push(root);
+        }
+    }
+    Ok(roots)
+}
+
+/// 路径是否位于 `roots` 中的某个目录下，`roots` 为 `canonical_key` 的结果
+fn is_under_any(path: &str, roots: &[String]) -> bool {
+    let key = canonical_key(path);
+    roots.iter().any(|root| key.starts_with(root.as_str()) && key[root.len()..].starts_with('/'))
+}
+
+fn root_keys(roots: &[String]) -> Vec<String> {
+    roots.iter().map(|root| canonical_key(root)).collect()
+}
+
+/// 检查单个网络路径，超过 `timeout` 后把所在目录记为离线，同一目录中的其他文件不再逐个等待
+fn check_network_path(
+    path: &str,
+    offline: &Mutex<Vec<String>>,
+    exists: fn(&str) -> bool,
+    timeout: Duration,
+) -> FileStatus {
+    if is_under_any(path, &offline.lock().unwrap()) {
+        return FileStatus::Offline;
+    }
+    let owned = path.to_string();
+    match with_timeout(timeout, move || exists(&owned)) {
+        Some(true) => FileStatus::Exists,
+        Some(false) => FileStatus::Missing,
+        None => {
+            if let Some(parent) = Path::new(path).parent() {
+                offline.lock().unwrap().extend(root_keys(&[parent.to_string_lossy().to_string()]));
+            }
+            FileStatus::Offline
+        }
+    }
+}
+
+/// 批量检查文件是否存在，结果与 `paths` 一一对应
+///
+/// 位于曲库中已知离线的根目录下的路径直接返回 `Offline`；其他网络路径在专用线程池中并行检查，
+/// 每个最多等待 `PROBE_TIMEOUT`。本地路径不受网络路径影响。
+pub fn check_files_exist_internal(db: &LibraryDb, paths: &[String]) -> Vec<FileStatus> {
+    let offline = Mutex::new(root_keys(&offline_roots(db).unwrap_or_default()));
+    let is_network = network_path_classifier();
+    let (network, local): (Vec<usize>, Vec<usize>) =
+        (0..paths.len()).partition(|&index| is_network(Path::new(&paths[index])));
+
+    let mut statuses = vec![FileStatus::Missing; paths.len()];
+    let local_statuses: Vec<FileStatus> = local
+        .par_iter()
+        .map(|&index| {
+            let path = &paths[index];
+            if is_under_any(path, &offline.lock().unwrap()) {
+                FileStatus::Offline
+            } else if check_file_exists_internal(path) {
+                FileStatus::Exists
+            } else {
+                FileStatus::Missing
+            }
+        })
+        .collect();
+    let check_network = || -> Vec<FileStatus> {
+        network
+            .par_iter()
+            .map(|&index| check_network_path(&paths[index], &offline, check_file_exists_internal, PROBE_TIMEOUT))
+            .collect()
+    };
+    let network_statuses = match NETWORK_POOL.as_ref() {
+        Some(pool) => pool.install(check_network),
+        None => check_network(),
+    };
+
+    for (index, status) in local.into_iter().zip(local_statuses).chain(network.into_iter().zip(network_statuses)) {
+        statuses[index] = status;
+    }
+    statuses
+}
+
+/// 分批检查的结果
+#[derive(Debug, Default)]
+pub struct PathCheck {
+    /// 不存在的路径
+    pub missing: Vec<String>,
+    /// 所在网络共享检查超时的路径，是否存在未知
+    pub offline: Vec<String>,
+}
+
+/// 分批检查路径是否存在，返回不存在和无法确认的路径
+///
+/// 网络路径在专用线程池中检查，每个最多等待 `PROBE_TIMEOUT`；超时的路径及同一目录中的其他文件记为离线，
+/// 不算作丢失。`on_progress(completed, total)` 在每批完成后调用。
+pub fn find_missing_paths(
+    paths: &[String],
+    cancel: &CancelToken,
+    on_progress: impl Fn(usize, usize) + Sync,
+) -> AppResult<PathCheck> {
+    check_paths(paths, cancel, on_progress, &network_path_classifier(), check_file_exists_internal, PROBE_TIMEOUT)
+}
+
+fn check_paths(
+    paths: &[String],
+    cancel: &CancelToken,
+    on_progress: impl Fn(usize, usize) + Sync,
+    is_network: &(impl Fn(&Path) -> bool + Sync),
+    exists: fn(&str) -> bool,
+    timeout: Duration,
+) -> AppResult<PathCheck> {
+    let (network, local): (Vec<&String>, Vec<&String>) = paths.iter().partition(|path| is_network(Path::new(path)));
+    let completed = AtomicUsize::new(0);
+    let on_batch = |count: usize| on_progress(completed.fetch_add(count, Ordering::Relaxed) + count, paths.len());
+
+    let local_status = |path: &str| if exists(path) { FileStatus::Exists } else { FileStatus::Missing };
+    let mut results = check_in_batches(&local, cancel, local_status, on_batch);
+    let offline = Mutex::new(Vec::new());
+    let check_network = || {
+        check_in_batches(&network, cancel, |path| check_network_path(path, &offline, exists, timeout), on_batch)
+    };
+    results.extend(match NETWORK_POOL.as_ref() {
+        Some(pool) => pool.install(check_network),
+        None => check_network(),
+    });
+    cancel.check()?;
+
+    let mut check = PathCheck::default();
+    for (path, status) in results {
+        match status {
+            FileStatus::Missing => check.missing.push(path),
+            FileStatus::Offline => check.offline.push(path),
+            FileStatus::Exists => {}
+        }
+    }
+    Ok(check)
+}
+
+/// 按批并行检查，只返回不存在或无法确认的路径；取消后剩余的批次不再检查
+fn check_in_batches(
+    paths: &[&String],
+    cancel: &CancelToken,
+    status: impl Fn(&str) -> FileStatus + Sync,
+    on_batch: impl Fn(usize) + Sync,
+) -> Vec<(String, FileStatus)> {
+    paths
+        .par_chunks(CHECK_BATCH_SIZE)
+        .flat_map_iter(|batch| {
+            let results: Vec<(String, FileStatus)> = if cancel.is_cancelled() {
+                Vec::new()
+            } else {
+                batch
+                    .iter()
+                    .map(|path| ((*path).clone(), status(path)))
+                    .filter(|(_, status)| *status != FileStatus::Exists)
+                    .collect()
+            };
+            on_batch(batch.len());
+            results
+        })
+        .collect()
+}
+
+/// 检查曲库和 `references` 中的所有路径
+pub fn find_missing_files_internal(
+    db: &LibraryDb,
+    references: &[PathReferences],
+    cancel: &CancelToken,
+    on_progress: impl Fn(usize, usize) + Sync,
+) -> AppResult<MissingFilesReport> {
+    // 路径键 -> (路径, 来源)
+    let mut known: HashMap<String, (String, Vec<String>)> = HashMap::new();
+    let mut add = |path: String, source: &str| {
+        let (_, sources) = known.entry(canonical_key(&path)).or_insert_with(|| (path, Vec::new()));
+        if !sources.iter().any(|s| s == source) {
+            sources.push(source.to_string());
+        }
+    };
+    for path in db.all_paths()? {
+        add(path, LIBRARY_SOURCE);
+    }
+    for reference in references {
+        for path in &reference.paths {
+            add(path.clone(), &reference.source);
+        }
+    }
+
+    let offline_roots = offline_roots(db)?;
+    let offline_keys = root_keys(&offline_roots);
+    let (offline, paths): (Vec<String>, Vec<String>) =
+        known.values().map(|(path, _)| path.clone()).partition(|path| is_under_any(path, &offline_keys));
+    let check = find_missing_paths(&paths, cancel, on_progress)?;
+
+    let mut report = MissingFilesReport {
+        checked: paths.len(),
+        missing: Vec::with_capacity(check.missing.len()),
+        offline_roots,
+        offline: Vec::with_capacity(offline.len() + check.offline.len()),
+    };
+    for path in check.missing {
+        if let Some((path, sources)) = known.remove(&canonical_key(&path)) {
+            report.missing.push(MissingFile { path, sources });
+        }
+    }
+    for path in offline.into_iter().chain(check.offline) {
+        if let Some((path, sources)) = known.remove(&canonical_key(&path)) {
+            report.offline.push(MissingFile { path, sources });
+        }
+    }
+    report.missing.sort_by(|a, b| a.path.cmp(&b.path));
+    report.offline.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(report)
+}
+
+/// 从曲库数据库和元数据缓存中清除丢失的文件
+///
+/// 清除前再次检查，期间重新出现的文件以及所在网络共享无法访问的文件会保留。数据库中的删除在同一个事务中完成。
+pub fn prune_missing_internal(db: &LibraryDb, paths: Vec<String>) -> AppResult<PruneResult> {
+    let offline_keys = root_keys(&offline_roots(db)?);
+    let (offline, paths): (Vec<String>, Vec<String>) =
+        paths.into_iter().partition(|path| is_under_any(path, &offline_keys));
+    let (restored, removed): (Vec<String>, Vec<String>) =
+        paths.into_iter().partition(|path| check_file_exists_internal(path));
+    db.remove_tracks(&removed)?;
+    for path in &removed {
+        invalidate(path);
+    }
+    Ok(PruneResult { removed, restored, offline })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    /// 名称中带 `hang` 的文件模拟断开的网络共享，检查会一直阻塞
+    fn slow_exists(path: &str) -> bool {
+        if path.contains("hang") {
+            thread::sleep(Duration::from_secs(5));
+        }
+        path.contains("present")
+    }
+
+    #[test]
+    fn network_timeouts_are_offline_not_missing() {
+        let paths: Vec<String> = [
+            "/local/present.flac",
+            "/local/gone.flac",
+            "/share/ok/present.flac",
+            "/share/ok/gone.flac",
+            "/share/down/hang-1.flac",
+            "/share/down/hang-2.flac",
+        ]
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+        let is_network = |path: &Path| path.starts_with("/share");
+        let progress = Mutex::new(Vec::new());
+
+        let started = std::time::Instant::now();
+        let check = check_paths(
+            &paths,
+            &CancelToken::default(),
+            |completed, total| progress.lock().unwrap().push((completed, total)),
+            &is_network,
+            slow_exists,
+            Duration::from_millis(100),
+        )
+        .unwrap();
+
+        let sorted = |mut paths: Vec<String>| {
+            paths.sort();
+            paths
+        };
+        assert_eq!(sorted(check.missing), ["/local/gone.flac", "/share/ok/gone.flac"]);
+        assert_eq!(sorted(check.offline), ["/share/down/hang-1.flac", "/share/down/hang-2.flac"]);
+        // 第一个超时后同一目录中的文件不再等待
+        assert!(started.elapsed() < Duration::from_secs(2), "{:?}", started.elapsed());
+        assert_eq!(progress.lock().unwrap().iter().map(|(completed, _)| *completed).max(), Some(paths.len()));
+    }
+}
@@ -2,12 +2,67 @@
 //!
 //! 提供文件系统操作和音频元数据处理功能。
 
+pub mod archive;
+pub mod clipboard;
 pub mod commands;
+pub mod cover;
+pub mod cover_embed;
+pub mod cue;
+pub mod custom_tags;
+pub mod dropped;
+pub mod dsf;
+pub mod duplicates;
+pub mod encoding;
+pub mod export;
+pub mod file_properties;
 pub mod filesystem;
+pub mod fingerprint;
+pub mod folder_stats;
+pub mod folder_tree;
+pub mod hashing;
 pub mod http_client;
+pub mod import;
+pub mod integrity;
+pub mod krc;
+pub mod kugou;
+pub mod lame;
+pub mod lastfm;
+pub mod listenbrainz;
+pub mod lrc_file;
+pub mod lyrics;
+pub mod lyrics_batch;
+pub mod lyrics_cache;
+pub mod lyrics_finder;
+pub mod lyrics_match;
+pub mod lyrics_merge;
+pub mod lyrics_provider;
 pub mod metadata;
+pub mod metadata_cache;
+pub mod missing;
+pub mod musicbrainz;
 pub mod netease;
+pub mod netease_limiter;
+pub mod network;
+pub mod organize;
+pub mod playlist_file;
+pub mod qqmusic;
+pub mod rating;
+pub mod relink;
+pub mod rescan;
+pub mod roots;
+pub mod scan;
+pub mod scan_checkpoint;
+pub mod scan_filter;
+pub mod scrobble;
+pub mod sorting;
+pub mod tag_writer;
+pub mod text;
+pub mod trash;
+pub mod volumes;
+pub mod watcher;
+pub mod word_lyrics;
+pub mod yrc;
 
 // 重新导出常用类型
 pub use filesystem::{get_audio_files_from_dir, read_dir, AUDIO_EXTENSIONS};
-pub use metadata::{Playlist, TrackMetadata};
+pub use metadata::{EncodingRepair, MetadataOptions, Playlist, TrackMetadata, TrackMetadataFull};
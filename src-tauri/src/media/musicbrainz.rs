@@ -0,0 +1,528 @@
+//! AcoustID / MusicBrainz 查询模块
+//!
+//! 先用声学指纹查询 AcoustID，无结果时按现有标题和艺术家搜索 MusicBrainz，
+//! 返回排序后的候选录音。这里只负责查询，写入标签由调用方显式触发。
+
+use super::tag_writer::TagChanges;
+use crate::media::http_client::{get_client, is_offline};
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, USER_AGENT};
+use reqwest::{Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+const ACOUSTID_LOOKUP_URL: &str = "https://api.acoustid.org/v2/lookup";
+const MUSICBRAINZ_RECORDING_URL: &str = "https://musicbrainz.org/ws/2/recording";
+
+/// MusicBrainz 要求每秒最多一个请求
+const MUSICBRAINZ_MIN_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 上一次 MusicBrainz 请求的时间
+static LAST_MUSICBRAINZ_REQUEST: LazyLock<Mutex<Option<Instant>>> = LazyLock::new(|| Mutex::new(None));
+
+/// 候选录音
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct LookupCandidate {
+    /// 来源："acoustid" 或 "musicbrainz"
+    pub source: String,
+    /// 匹配得分（0-1）
+    pub score: f64,
+    pub title: String,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub album_artist: Option<String>,
+    pub release_date: Option<String>,
+    pub track_number: Option<u32>,
+    pub track_total: Option<u32>,
+    pub disc_number: Option<u32>,
+    pub recording_id: String,
+    pub release_id: Option<String>,
+    pub release_group_id: Option<String>,
+    pub artist_id: Option<String>,
+    pub track_id: Option<String>,
+}
+
+impl LookupCandidate {
+    /// 转换为标签修改内容（包含 MusicBrainz ID）
+    #[must_use]
+    pub fn to_tag_changes(&self) -> TagChanges {
+        TagChanges {
+            title: Some(self.title.clone()),
+            artist: self.artist.clone(),
+            album: self.album.clone(),
+            album_artist: self.album_artist.clone(),
+            year: self
+                .release_date
+                .as_deref()
+                .and_then(|date| date.get(..4))
+                .map(str::to_string),
+            track_number: self.track_number.map(|n| n.to_string()),
+            disc_number: self.disc_number.map(|n| n.to_string()),
+            musicbrainz_recording_id: Some(self.recording_id.clone()),
+            musicbrainz_release_id: self.release_id.clone(),
+            musicbrainz_release_group_id: self.release_group_id.clone(),
+            musicbrainz_artist_id: self.artist_id.clone(),
+            musicbrainz_track_id: self.track_id.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+/// 查询结果
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum LookupOutcome {
+    /// 找到候选录音（按得分从高到低排列）
+    Matches { candidates: Vec<LookupCandidate> },
+    /// 没有匹配结果
+    NoMatch,
+    /// 服务端限流
+    #[serde(rename_all = "camelCase")]
+    RateLimited { retry_after_secs: Option<u64> },
+    /// 网络或服务错误
+    NetworkError { message: String },
+    /// 离线模式已开启，没有查询
+    Offline,
+}
+
+/// 查询过程中的错误
+#[derive(Debug)]
+pub enum LookupError {
+    Network(String),
+    RateLimited(Option<u64>),
+    Offline,
+}
+
+impl From<LookupError> for LookupOutcome {
+    fn from(err: LookupError) -> Self {
+        match err {
+            LookupError::Network(message) => Self::NetworkError { message },
+            LookupError::RateLimited(retry_after_secs) => Self::RateLimited { retry_after_secs },
+            LookupError::Offline => Self::Offline,
+        }
+    }
+}
+
+fn build_headers() -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(ACCEPT, HeaderValue::from_static("application/json"));
+    headers.insert(
+        USER_AGENT,
+        HeaderValue::from_static(concat!(
+            "MercurialPlayer/",
+            env!("CARGO_PKG_VERSION"),
+            " ( https://github.com/JDBeWL/MerPlayer )"
+        )),
+    );
+    headers
+}
+
+/// 检查 HTTP 状态，429/503 视为限流
+async fn read_body(response: Response) -> Result<String, LookupError> {
+    let status = response.status();
+    if status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::SERVICE_UNAVAILABLE {
+        let retry_after = response
+            .headers()
+            .get("retry-after")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse().ok());
+        return Err(LookupError::RateLimited(retry_after));
+    }
+
+    let body = response
+        .text()
+        .await
+        .map_err(|e| LookupError::Network(format!("Read response failed: {e}")))?;
+    if !status.is_success() {
+        return Err(LookupError::Network(format!("HTTP error: {status}")));
+    }
+    Ok(body)
+}
+
+// ============ AcoustID ============
+
+#[derive(Debug, Deserialize)]
+struct AcoustIdResponse {
+    status: String,
+    #[serde(default)]
+    results: Vec<AcoustIdResult>,
+    error: Option<AcoustIdError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AcoustIdError {
+    #[serde(default)]
+    code: i32,
+    #[serde(default)]
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AcoustIdResult {
+    score: f64,
+    #[serde(default)]
+    recordings: Vec<AcoustIdRecording>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AcoustIdRecording {
+    id: String,
+    title: Option<String>,
+    #[serde(default)]
+    artists: Vec<AcoustIdArtist>,
+    #[serde(default)]
+    releases: Vec<AcoustIdRelease>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AcoustIdArtist {
+    id: String,
+    name: String,
+    joinphrase: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AcoustIdRelease {
+    id: String,
+    title: Option<String>,
+    releasegroup_id: Option<String>,
+    date: Option<AcoustIdDate>,
+    #[serde(default)]
+    artists: Vec<AcoustIdArtist>,
+    #[serde(default)]
+    mediums: Vec<AcoustIdMedium>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AcoustIdDate {
+    year: Option<u32>,
+    month: Option<u32>,
+    day: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AcoustIdMedium {
+    position: Option<u32>,
+    track_count: Option<u32>,
+    #[serde(default)]
+    tracks: Vec<AcoustIdTrack>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AcoustIdTrack {
+    id: Option<String>,
+    position: Option<u32>,
+}
+
+fn join_artists(artists: &[AcoustIdArtist]) -> Option<String> {
+    if artists.is_empty() {
+        return None;
+    }
+    Some(
+        artists
+            .iter()
+            .flat_map(|a| [a.name.as_str(), a.joinphrase.as_deref().unwrap_or_default()])
+            .collect(),
+    )
+}
+
+fn format_date(date: &AcoustIdDate) -> Option<String> {
+    match (date.year, date.month, date.day) {
+        (Some(y), Some(m), Some(d)) => Some(format!("{y:04}-{m:02}-{d:02}")),
+        (Some(y), Some(m), None) => Some(format!("{y:04}-{m:02}")),
+        (Some(y), None, _) => Some(format!("{y:04}")),
+        _ => None,
+    }
+}
+
+/// 使用声学指纹查询 AcoustID
+pub async fn lookup_acoustid(
+    api_key: &str,
+    fingerprint: &str,
+    duration: f64,
+) -> Result<Vec<LookupCandidate>, LookupError> {
+    if is_offline() {
+        return Err(LookupError::Offline);
+    }
+    let duration = (duration.round() as u64).to_string();
+    let params = [
+        ("client", api_key),
+        ("format", "json"),
+        ("meta", "recordings releases tracks"),
+        ("duration", duration.as_str()),
+        ("fingerprint", fingerprint),
+    ];
+
+    let response = get_client()
+        .post(ACOUSTID_LOOKUP_URL)
+        .headers(build_headers())
+        .form(&params)
+        .send()
+        .await
+        .map_err(|e| LookupError::Network(format!("Request failed: {e}")))?;
+    let body = read_body(response).await?;
+
+    let data: AcoustIdResponse =
+        serde_json::from_str(&body).map_err(|e| LookupError::Network(format!("Parse response failed: {e}")))?;
+    if data.status != "ok" {
+        let error = data.error.unwrap_or(AcoustIdError { code: 0, message: "unknown error".to_string() });
+        // 错误码 14 表示请求过于频繁
+        if error.code == 14 {
+            return Err(LookupError::RateLimited(None));
+        }
+        return Err(LookupError::Network(format!("AcoustID error {}: {}", error.code, error.message)));
+    }
+
+    let mut candidates = Vec::new();
+    for result in data.results {
+        for recording in result.recordings {
+            let Some(title) = recording.title else {
+                continue;
+            };
+            let artist = join_artists(&recording.artists);
+            let artist_id = recording.artists.first().map(|a| a.id.clone());
+
+            if recording.releases.is_empty() {
+                candidates.push(LookupCandidate {
+                    source: "acoustid".to_string(),
+                    score: result.score,
+                    title: title.clone(),
+                    artist: artist.clone(),
+                    artist_id: artist_id.clone(),
+                    recording_id: recording.id.clone(),
+                    ..Default::default()
+                });
+                continue;
+            }
+
+            for release in &recording.releases {
+                let medium = release.mediums.iter().find(|m| !m.tracks.is_empty());
+                let track = medium.and_then(|m| m.tracks.first());
+                candidates.push(LookupCandidate {
+                    source: "acoustid".to_string(),
+                    score: result.score,
+                    title: title.clone(),
+                    artist: artist.clone(),
+                    album: release.title.clone(),
+                    album_artist: join_artists(&release.artists),
+                    release_date: release.date.as_ref().and_then(format_date),
+                    track_number: track.and_then(|t| t.position),
+                    track_total: medium.and_then(|m| m.track_count),
+                    disc_number: medium.and_then(|m| m.position),
+                    recording_id: recording.id.clone(),
+                    release_id: Some(release.id.clone()),
+                    release_group_id: release.releasegroup_id.clone(),
+                    artist_id: artist_id.clone(),
+                    track_id: track.and_then(|t| t.id.clone()),
+                });
+            }
+        }
+    }
+
+    Ok(candidates)
+}
+
+// ============ MusicBrainz ============
+
+#[derive(Debug, Deserialize)]
+struct RecordingSearchResponse {
+    #[serde(default)]
+    recordings: Vec<MbRecording>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MbRecording {
+    id: String,
+    #[serde(default)]
+    score: f64,
+    title: String,
+    #[serde(default, rename = "artist-credit")]
+    artist_credit: Vec<MbArtistCredit>,
+    #[serde(default)]
+    releases: Vec<MbRelease>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MbArtistCredit {
+    name: String,
+    joinphrase: Option<String>,
+    artist: Option<MbArtist>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MbArtist {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct MbRelease {
+    id: String,
+    title: Option<String>,
+    date: Option<String>,
+    #[serde(rename = "release-group")]
+    release_group: Option<MbReleaseGroup>,
+    #[serde(default, rename = "artist-credit")]
+    artist_credit: Vec<MbArtistCredit>,
+    #[serde(default)]
+    media: Vec<MbMedium>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MbReleaseGroup {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct MbMedium {
+    position: Option<u32>,
+    #[serde(rename = "track-count")]
+    track_count: Option<u32>,
+    #[serde(default)]
+    track: Vec<MbTrack>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MbTrack {
+    id: Option<String>,
+    number: Option<String>,
+}
+
+fn join_credits(credits: &[MbArtistCredit]) -> Option<String> {
+    if credits.is_empty() {
+        return None;
+    }
+    Some(
+        credits
+            .iter()
+            .flat_map(|c| [c.name.as_str(), c.joinphrase.as_deref().unwrap_or_default()])
+            .collect(),
+    )
+}
+
+/// 转义 Lucene 查询中的特殊字符
+fn escape_query(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// 等待至距上一次请求满 1 秒
+async fn wait_for_musicbrainz_slot() {
+    let mut last = LAST_MUSICBRAINZ_REQUEST.lock().await;
+    if let Some(remaining) = last.and_then(|previous| MUSICBRAINZ_MIN_INTERVAL.checked_sub(previous.elapsed())) {
+        tokio::time::sleep(remaining).await;
+    }
+    *last = Some(Instant::now());
+}
+
+/// 按标题和艺术家搜索 MusicBrainz 录音
+pub async fn search_musicbrainz(title: &str, artist: Option<&str>) -> Result<Vec<LookupCandidate>, LookupError> {
+    let mut query = format!("recording:\"{}\"", escape_query(title));
+    if let Some(artist) = artist.filter(|a| !a.trim().is_empty()) {
+        let _ = write!(query, " AND artist:\"{}\"", escape_query(artist));
+    }
+
+    wait_for_musicbrainz_slot().await;
+    if is_offline() {
+        return Err(LookupError::Offline);
+    }
+
+    let response = get_client()
+        .get(MUSICBRAINZ_RECORDING_URL)
+        .headers(build_headers())
+        .query(&[("query", query.as_str()), ("fmt", "json"), ("limit", "10")])
+        .send()
+        .await
+        .map_err(|e| LookupError::Network(format!("Request failed: {e}")))?;
+    let body = read_body(response).await?;
+
+    let data: RecordingSearchResponse =
+        serde_json::from_str(&body).map_err(|e| LookupError::Network(format!("Parse response failed: {e}")))?;
+
+    let mut candidates = Vec::new();
+    for recording in data.recordings {
+        let artist = join_credits(&recording.artist_credit);
+        let artist_id = recording
+            .artist_credit
+            .first()
+            .and_then(|c| c.artist.as_ref())
+            .map(|a| a.id.clone());
+        let score = recording.score / 100.0;
+
+        let base = LookupCandidate {
+            source: "musicbrainz".to_string(),
+            score,
+            title: recording.title.clone(),
+            artist,
+            artist_id,
+            recording_id: recording.id.clone(),
+            ..Default::default()
+        };
+
+        if recording.releases.is_empty() {
+            candidates.push(base);
+            continue;
+        }
+
+        for release in &recording.releases {
+            let medium = release.media.first();
+            let track = medium.and_then(|m| m.track.first());
+            candidates.push(LookupCandidate {
+                album: release.title.clone(),
+                album_artist: join_credits(&release.artist_credit),
+                release_date: release.date.clone().filter(|d| !d.is_empty()),
+                track_number: track.and_then(|t| t.number.as_deref()).and_then(|n| n.parse().ok()),
+                track_total: medium.and_then(|m| m.track_count),
+                disc_number: medium.and_then(|m| m.position),
+                release_id: Some(release.id.clone()),
+                release_group_id: release.release_group.as_ref().map(|g| g.id.clone()),
+                track_id: track.and_then(|t| t.id.clone()),
+                ..base.clone()
+            });
+        }
+    }
+
+    Ok(candidates)
+}
+
+/// 按得分排序并去掉重复的录音/发行组合
+pub fn rank_candidates(mut candidates: Vec<LookupCandidate>) -> Vec<LookupCandidate> {
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+    let mut seen = std::collections::HashSet::new();
+    candidates.retain(|c| seen.insert((c.recording_id.clone(), c.release_id.clone())));
+    candidates
+}
+
+/// 查询音轨信息
+///
+/// 配置了 AcoustID API Key 且指纹可用时先按指纹查询，无匹配时再按标题/艺术家搜索 MusicBrainz。
+pub async fn lookup_recording(
+    api_key: &str,
+    fingerprint: Option<(&str, f64)>,
+    title: Option<&str>,
+    artist: Option<&str>,
+) -> LookupOutcome {
+    if is_offline() {
+        return LookupOutcome::Offline;
+    }
+    if let Some((fingerprint, duration)) = fingerprint.filter(|_| !api_key.trim().is_empty()) {
+        match lookup_acoustid(api_key.trim(), fingerprint, duration).await {
+            Ok(candidates) if !candidates.is_empty() => {
+                return LookupOutcome::Matches { candidates: rank_candidates(candidates) };
+            }
+            Ok(_) => {}
+            Err(e) => return e.into(),
+        }
+    }
+
+    let Some(title) = title.filter(|t| !t.trim().is_empty()) else {
+        return LookupOutcome::NoMatch;
+    };
+
+    match search_musicbrainz(title, artist).await {
+        Ok(candidates) if !candidates.is_empty() => LookupOutcome::Matches { candidates: rank_candidates(candidates) },
+        Ok(_) => LookupOutcome::NoMatch,
+        Err(e) => e.into(),
+    }
+}
@@ -1,10 +1,163 @@
 //! 网易云音乐歌词 API
 //!
-//! 提供从网易云音乐搜索和获取歌词的功能
+//! 提供从网易云音乐搜索和获取歌词、专辑封面的功能。
+//! 请求超时、连接失败和 5xx 响应最多重试两次（间隔 0.5 秒、1 秒），错误按类型区分后返回前端。
+//! 所有请求经过共用的限流队列，收到限流或风控响应后暂停之后的请求；离线模式下不发出请求，直接返回 `Offline`。
+//! 接口地址、User-Agent、附加请求头和 Cookie 可在设置中修改；接口类型为自建的 NeteaseCloudMusicApi 时，
+//! 请求路径随之改变，响应中字段名不同的地方（如歌曲详情的 `ar` / `artists`）用别名兼容。
+//! 获取歌词时同时请求 YRC 逐字歌词，有逐字歌词的歌曲在结果中附带逐字时间。
 
-use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, ACCEPT_LANGUAGE, CONTENT_TYPE, REFERER, USER_AGENT};
+use reqwest::header::{
+    HeaderMap, HeaderName, HeaderValue, ACCEPT, ACCEPT_LANGUAGE, CONTENT_TYPE, COOKIE, REFERER, RETRY_AFTER, USER_AGENT,
+};
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::ser::{SerializeStruct, Serializer};
 use serde::{Deserialize, Serialize};
-use crate::media::http_client::get_client;
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::path::Path;
+use std::sync::{LazyLock, RwLock};
+use std::time::{Duration, Instant};
+use crate::config::{LyricsConfig, NeteaseApiFlavor, NeteaseConfig};
+use crate::media::cover::{cached_cover_file, folder_cover_data_url, sniff_image_extension, store_cover_file};
+use crate::media::http_client::{get_netease_client, is_offline, uses_proxy};
+use crate::media::lyrics::{lrc_to_sync_entries, shift_lrc};
+use crate::media::lyrics_provider::{tag_songs, LyricsProvider, ProviderFuture, ProviderSong};
+use crate::media::netease_limiter::{acquire, throttle};
+use crate::media::word_lyrics::WordTimedLine;
+use crate::media::yrc::parse_yrc;
+use crate::tr;
+
+/// 失败后最多重试的次数
+const MAX_RETRIES: u32 = 2;
+/// 第一次重试前的等待时间，之后每次翻倍
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+/// 没有设置接口地址时使用的地址
+const DEFAULT_API_BASE_URL: &str = "https://music.163.com";
+
+/// 检查接口设置时搜索的关键词
+const TEST_SEARCH_KEYWORD: &str = "晴天";
+
+/// 当前使用的接口设置
+struct ApiSettings {
+    /// 不带末尾 `/` 的接口地址
+    base_url: String,
+    flavor: NeteaseApiFlavor,
+    headers: HeaderMap,
+}
+
+static API_SETTINGS: LazyLock<RwLock<ApiSettings>> = LazyLock::new(|| {
+    RwLock::new(ApiSettings {
+        base_url: DEFAULT_API_BASE_URL.to_string(),
+        flavor: NeteaseApiFlavor::Web,
+        headers: default_headers(),
+    })
+});
+
+/// 在线请求的错误，所有在线歌词来源（网易云音乐、QQ 音乐、酷狗音乐）共用
+///
+/// 附带的文本说明出错的接口（如 `song/lyric 123`）和状态码，不包含完整的响应内容。
+/// 序列化为 `{ code, message }`，`code` 是前端可以匹配的稳定字符串：
+///
+/// | code           | 含义                                                     |
+/// |----------------|----------------------------------------------------------|
+/// | `TIMEOUT`      | 重试后仍然超时                                           |
+/// | `RATE_LIMITED` | 请求过于频繁（限流队列已暂停），可能带 `retryAfterSecs` |
+/// | `BLOCKED`      | 地区限制或被风控拒绝                                     |
+/// | `NOT_FOUND`    | 歌曲、专辑或封面不存在                                   |
+/// | `PRIVATE`      | 需要登录或没有权限（如私密歌单）                         |
+/// | `PARSE`        | 响应不是预期的格式                                       |
+/// | `NETWORK`      | 其他网络错误                                             |
+/// | `PROXY`        | 经过设置的代理连接失败                                   |
+/// | `CACHE`        | 读写本地缓存失败                                         |
+/// | `OFFLINE_MODE` | 离线模式已开启，没有发出请求                             |
+#[derive(Debug)]
+pub enum NeteaseError {
+    /// 重试后仍然超时
+    Timeout(String),
+    /// 请求过于频繁，附带服务端建议的等待秒数
+    RateLimited(Option<u64>),
+    /// 资源在当前地区不可用，或请求被服务端拒绝
+    Blocked(String),
+    /// 歌曲或专辑不存在，或没有封面
+    NotFound(String),
+    /// 需要登录或没有权限访问（如私密歌单）
+    Private(String),
+    /// 响应不是预期的格式
+    Parse(String),
+    /// 网络请求失败
+    Network(String),
+    /// 设置了代理时连接失败（代理不可用或拒绝连接）
+    Proxy(String),
+    /// 读写本地缓存失败
+    Cache(String),
+    /// 离线模式已开启，没有发出请求
+    Offline,
+}
+
+impl fmt::Display for NeteaseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Timeout(what) => f.write_str(&tr!("online.timeout", detail = what)),
+            Self::RateLimited(Some(secs)) => f.write_str(&tr!("online.rateLimitedRetry", seconds = secs)),
+            Self::RateLimited(None) => f.write_str(&tr!("online.rateLimited")),
+            Self::Blocked(what) => f.write_str(&tr!("online.blocked", detail = what)),
+            Self::NotFound(what) => f.write_str(&tr!("online.notFound", detail = what)),
+            Self::Private(what) => f.write_str(&tr!("online.private", detail = what)),
+            Self::Parse(err) => f.write_str(&tr!("online.parse", detail = err)),
+            Self::Network(err) => f.write_str(&tr!("online.network", detail = err)),
+            Self::Proxy(err) => f.write_str(&tr!("online.proxy", detail = err)),
+            Self::Cache(err) => f.write_str(&tr!("online.cache", detail = err)),
+            Self::Offline => f.write_str(&tr!("network.offline")),
+        }
+    }
+}
+
+impl NeteaseError {
+    /// 稳定的错误码，供前端区分错误类型
+    #[must_use]
+    pub const fn code(&self) -> &'static str {
+        match self {
+            Self::Timeout(_) => "TIMEOUT",
+            Self::RateLimited(_) => "RATE_LIMITED",
+            Self::Blocked(_) => "BLOCKED",
+            Self::NotFound(_) => "NOT_FOUND",
+            Self::Private(_) => "PRIVATE",
+            Self::Parse(_) => "PARSE",
+            Self::Network(_) => "NETWORK",
+            Self::Proxy(_) => "PROXY",
+            Self::Cache(_) => "CACHE",
+            Self::Offline => "OFFLINE_MODE",
+        }
+    }
+}
+
+/// 与 `AppError` 一样序列化为 `{ code, message }`，限流时带上 `retryAfterSecs`
+impl Serialize for NeteaseError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let retry_after = match self {
+            Self::RateLimited(secs) => *secs,
+            _ => None,
+        };
+        let mut state = serializer.serialize_struct("NeteaseError", if retry_after.is_some() { 3 } else { 2 })?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        if let Some(secs) = retry_after {
+            state.serialize_field("retryAfterSecs", &secs)?;
+        }
+        state.end()
+    }
+}
+
+impl std::error::Error for NeteaseError {}
+
+impl From<NeteaseError> for String {
+    fn from(err: NeteaseError) -> Self {
+        err.to_string()
+    }
+}
 
 /// 搜索结果中的歌曲信息
 #[derive(Debug, Serialize, Deserialize, Default)]
@@ -13,19 +166,35 @@ pub struct ArtistInfo {
     pub name: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Default)]
 pub struct AlbumInfo {
+    #[serde(default)]
+    pub id: i64,
     #[serde(default)]
     pub name: String,
+    /// 专辑封面地址
+    #[serde(default, rename = "picUrl")]
+    pub pic_url: Option<String>,
+    /// 发行时间（Unix 毫秒），只有歌曲详情接口返回
+    #[serde(default, rename = "publishTime")]
+    pub publish_time: Option<i64>,
 }
 
 /// 歌词响应
 #[derive(Debug, Deserialize)]
 struct LyricResponse {
     code: i32,
+    /// 纯音乐
+    #[serde(default)]
+    nolyric: bool,
+    /// 还没有收录歌词
+    #[serde(default)]
+    uncollected: bool,
     lrc: Option<LyricContent>,
     tlyric: Option<LyricContent>,
     romalrc: Option<LyricContent>,
+    /// 逐字歌词
+    yrc: Option<LyricContent>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -33,24 +202,248 @@ struct LyricContent {
     lyric: Option<String>,
 }
 
-/// 返回给前端的歌词数据
-#[derive(Debug, Serialize)]
+/// 歌词类型
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum LyricsKind {
+    /// 带时间轴的歌词
+    #[default]
+    Synced,
+    /// 没有时间轴的纯文本歌词
+    PlainText,
+    /// 纯音乐，没有歌词
+    Instrumental,
+    /// 来源没有这首歌的歌词
+    NotFound,
+}
+
+/// 作词、作曲等署名行，如 `作曲 : 周杰伦`
+fn is_credit_line(line: &str) -> bool {
+    line.split_once([':', '：']).is_some_and(|(name, _)| name.trim().chars().count() <= 6)
+}
+
+/// 来源用于纯音乐的占位歌词，如 "纯音乐，请欣赏"、"此歌曲为没有填词的纯音乐，请您欣赏"
+fn is_instrumental_marker(line: &str) -> bool {
+    (line.contains("纯音乐") && line.contains("欣赏")) || line.trim().eq_ignore_ascii_case("instrumental")
+}
+
+impl LyricsKind {
+    /// 按歌词内容判断类型：空歌词为 `NotFound`，只有占位行（和署名行）的为 `Instrumental`
+    #[must_use]
+    pub fn detect(lrc: &str) -> Self {
+        let entries = lrc_to_sync_entries(lrc);
+        let lines: Vec<&str> = if entries.is_empty() {
+            lrc.lines().map(str::trim).filter(|line| !line.starts_with('[')).collect()
+        } else {
+            entries.iter().map(|(_, text)| text.trim()).collect()
+        };
+        let lyric_lines: Vec<&str> =
+            lines.into_iter().filter(|line| !line.is_empty() && !is_credit_line(line)).collect();
+
+        if lyric_lines.is_empty() {
+            Self::NotFound
+        } else if lyric_lines.len() <= 2 && lyric_lines.iter().any(|line| is_instrumental_marker(line)) {
+            Self::Instrumental
+        } else if entries.is_empty() {
+            Self::PlainText
+        } else {
+            Self::Synced
+        }
+    }
+}
+
+/// 返回给前端的歌词数据，没有或未请求的翻译、罗马音不出现在结果中
+///
+/// 来源提供逐字时间（如酷狗 KRC）时 `words` 带逐字歌词，`lrc` 仍是展平后的行级歌词。
+/// `kind` 为纯音乐或没有歌词时 `lrc` 为空，界面据此显示 "纯音乐" 而不是空白的歌词。
+/// 为某个音轨返回时，该音轨的歌词时间偏移已应用到所有时间上，`offsetMs` 为应用的偏移。
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct LyricsData {
+    /// 旧版缓存条目没有记录类型，读取缓存时按内容重新判断
+    #[serde(default)]
+    pub kind: LyricsKind,
     pub lrc: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub tlyric: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub romalrc: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub words: Vec<WordTimedLine>,
+    /// 是否带逐字时间（`words` 不为空）
+    #[serde(default, rename = "wordLevel")]
+    pub word_level: bool,
+    /// 已应用的歌词时间偏移（毫秒），正数表示歌词推后
+    #[serde(default, rename = "offsetMs")]
+    pub offset_ms: i64,
+}
+
+/// 除原文外需要返回的歌词层
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub struct LyricLayers {
+    #[serde(default)]
+    pub translation: bool,
+    #[serde(default)]
+    pub romanization: bool,
+    /// 卡拉 OK 模式：自动匹配时优先选择带逐字时间的歌词
+    #[serde(default)]
+    pub karaoke: bool,
+}
+
+impl LyricLayers {
+    /// 按歌词设置中的翻译、罗马音和卡拉 OK 模式偏好
+    #[must_use]
+    pub const fn from_config(config: &LyricsConfig) -> Self {
+        Self {
+            translation: config.prefer_translation,
+            romanization: config.prefer_romanization,
+            karaoke: config.karaoke_mode,
+        }
+    }
+}
+
+impl LyricsData {
+    /// 设置逐字歌词
+    #[must_use]
+    pub fn with_words(self, words: Vec<WordTimedLine>) -> Self {
+        Self { word_level: !words.is_empty(), words, ..self }
+    }
+
+    /// 按内容判断类型，纯音乐和没有歌词时清空占位文本
+    #[must_use]
+    pub fn classified(self) -> Self {
+        let kind = LyricsKind::detect(&self.lrc);
+        self.with_kind(kind)
+    }
+
+    /// 设置类型，纯音乐和没有歌词时清空占位文本
+    #[must_use]
+    pub fn with_kind(self, kind: LyricsKind) -> Self {
+        if matches!(kind, LyricsKind::Instrumental | LyricsKind::NotFound) {
+            Self { kind, ..Self::default() }
+        } else {
+            Self { kind, ..self }
+        }
+    }
+
+    /// 把歌词时间偏移应用到原文、翻译、罗马音和逐字歌词上，已应用的偏移不会重复计算
+    #[must_use]
+    pub fn with_offset(mut self, offset_ms: i64) -> Self {
+        let delta = offset_ms - self.offset_ms;
+        if delta != 0 {
+            self.lrc = shift_lrc(&self.lrc, delta);
+            self.tlyric = shift_lrc(&self.tlyric, delta);
+            self.romalrc = shift_lrc(&self.romalrc, delta);
+            for line in &mut self.words {
+                line.shift(delta);
+            }
+        }
+        self.offset_ms = offset_ms;
+        self
+    }
+
+    /// 去掉不需要的歌词层
+    #[must_use]
+    pub fn with_layers(mut self, layers: LyricLayers) -> Self {
+        if !layers.translation {
+            self.tlyric.clear();
+        }
+        if !layers.romanization {
+            self.romalrc.clear();
+        }
+        self
+    }
 }
 
 /// 返回给前端的搜索结果
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
 pub struct SearchSongResult {
     pub id: String,
     pub name: String,
     pub artist: String,
     pub album: String,
+    /// 专辑 ID，没有专辑信息时为 `None`
+    pub album_id: Option<String>,
+    /// 专辑封面地址（原图尺寸）
+    pub cover_url: Option<String>,
     pub duration: i64,
 }
 
+/// 下载的封面
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CoverImage {
+    /// 缓存中的图片文件路径
+    pub path: String,
+    pub data_url: String,
+    /// 是否直接取自缓存，没有发起网络请求
+    pub cached: bool,
+}
+
+/// 歌曲详情 API 响应结构
+#[derive(Debug, Deserialize)]
+struct SongDetailResponse {
+    code: i32,
+    #[serde(default)]
+    songs: Vec<SongDetail>,
+}
+
+/// 网页接口返回 `artists` / `album` / `duration`，NeteaseCloudMusicApi 返回 `ar` / `al` / `dt`
+#[derive(Debug, Deserialize)]
+struct SongDetail {
+    id: i64,
+    #[serde(default)]
+    name: String,
+    /// 时长（毫秒）
+    #[serde(default, alias = "dt")]
+    duration: i64,
+    #[serde(default, alias = "ar")]
+    artists: Vec<ArtistInfo>,
+    #[serde(alias = "al")]
+    album: Option<AlbumInfo>,
+}
+
+/// 返回给前端的歌曲详情
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SongDetailInfo {
+    pub id: String,
+    pub name: String,
+    pub artists: Vec<String>,
+    pub album: String,
+    pub album_id: Option<String>,
+    /// 专辑封面地址（原图尺寸）
+    pub cover_url: Option<String>,
+    /// 精确时长（毫秒）
+    pub duration: i64,
+    /// 专辑发行时间（Unix 毫秒）
+    pub publish_time: Option<i64>,
+}
+
+impl From<SongDetail> for SongDetailInfo {
+    fn from(song: SongDetail) -> Self {
+        let album = song.album.unwrap_or_default();
+        Self {
+            id: song.id.to_string(),
+            name: song.name,
+            artists: song.artists.into_iter().map(|a| a.name).filter(|name| !name.is_empty()).collect(),
+            album: album.name,
+            album_id: (album.id > 0).then(|| album.id.to_string()),
+            cover_url: album.pic_url.filter(|url| !url.is_empty()),
+            duration: song.duration,
+            publish_time: album.publish_time.filter(|time| *time > 0),
+        }
+    }
+}
+
+/// 专辑 API 响应结构
+#[derive(Debug, Deserialize)]
+struct AlbumResponse {
+    code: i32,
+    album: Option<AlbumInfo>,
+}
+
 /// CloudSearch API 响应结构
 #[derive(Debug, Deserialize)]
 struct CloudSearchResponse {
@@ -74,6 +467,157 @@ struct CloudSearchSong {
     dt: i64,
 }
 
+/// 专辑搜索（cloudsearch type=10）响应结构
+#[derive(Debug, Deserialize)]
+struct AlbumSearchResponse {
+    code: i32,
+    result: Option<AlbumSearchList>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AlbumSearchList {
+    albums: Option<Vec<AlbumSearchItem>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AlbumSearchItem {
+    id: i64,
+    name: String,
+    #[serde(default)]
+    artists: Vec<ArtistInfo>,
+    /// 曲目数
+    #[serde(default)]
+    size: u32,
+    #[serde(default, rename = "publishTime")]
+    publish_time: Option<i64>,
+    #[serde(default, rename = "picUrl")]
+    pic_url: Option<String>,
+}
+
+/// 专辑曲目（api/v1/album）响应结构
+#[derive(Debug, Deserialize)]
+struct AlbumTracksResponse {
+    code: i32,
+    album: Option<AlbumInfo>,
+    #[serde(default)]
+    songs: Vec<AlbumSong>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AlbumSong {
+    id: i64,
+    name: String,
+    #[serde(default)]
+    ar: Vec<ArtistInfo>,
+    #[serde(default)]
+    dt: i64,
+    /// 曲号
+    #[serde(default)]
+    no: u32,
+    /// 碟号，如 "01"
+    #[serde(default)]
+    cd: Option<String>,
+}
+
+/// 返回给前端的专辑搜索结果
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AlbumSearchResult {
+    pub id: String,
+    pub name: String,
+    pub artist: String,
+    pub track_count: u32,
+    pub publish_year: Option<i32>,
+    /// 专辑封面地址（原图尺寸）
+    pub cover_url: Option<String>,
+}
+
+/// 专辑中的一首曲目
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AlbumTrack {
+    #[serde(flatten)]
+    pub song: SearchSongResult,
+    /// 曲号，接口没有返回时为 `None`
+    pub track_number: Option<u32>,
+    pub disc_number: Option<u32>,
+}
+
+/// 歌单详情（api/v6/playlist/detail）响应结构
+#[derive(Debug, Deserialize)]
+struct PlaylistResponse {
+    code: i32,
+    playlist: Option<PlaylistInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PlaylistInfo {
+    #[serde(default)]
+    name: String,
+    creator: Option<PlaylistCreator>,
+    /// 全部歌曲的 ID；`tracks` 只包含前几首，不使用
+    #[serde(default)]
+    track_ids: Vec<PlaylistTrackId>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PlaylistCreator {
+    #[serde(default)]
+    nickname: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PlaylistTrackId {
+    id: i64,
+}
+
+/// 歌单中的一首歌曲
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct OnlinePlaylistEntry {
+    pub id: String,
+    pub title: String,
+    #[serde(default)]
+    pub artist: String,
+    #[serde(default)]
+    pub album: String,
+    /// 时长（毫秒）
+    #[serde(default)]
+    pub duration: i64,
+}
+
+/// 网易云音乐歌单
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct OnlinePlaylist {
+    pub id: String,
+    pub name: String,
+    /// 创建者昵称
+    pub creator: Option<String>,
+    /// 按歌单顺序排列的歌曲
+    pub entries: Vec<OnlinePlaylistEntry>,
+    /// 歌单中有但取不到详情的歌曲数（已下架等）
+    pub unavailable: usize,
+}
+
+/// 发行时间（Unix 毫秒）对应的年份；网易云音乐按北京时间记录，先换算到 UTC+8 避免元旦前后差一年
+fn year_from_millis(millis: i64) -> Option<i32> {
+    if millis <= 0 {
+        return None;
+    }
+    let days = (millis + 8 * 3_600_000).div_euclid(86_400_000);
+    // 按公历从 1970-01-01 起的天数推算年份
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let year = year_of_era + era * 400 + i64::from(month_index >= 10);
+    i32::try_from(year).ok()
+}
+
 /// 安全截取字符串前 N 个字符（避免在多字节字符中间截断）
 fn safe_truncate(s: &str, max_chars: usize) -> &str {
     match s.char_indices().nth(max_chars) {
@@ -82,8 +626,8 @@ fn safe_truncate(s: &str, max_chars: usize) -> &str {
     }
 }
 
-/// 构建请求头 - 模拟浏览器访问网易云音乐网页
-fn build_headers() -> HeaderMap {
+/// 默认请求头 - 模拟浏览器访问网易云音乐网页
+fn default_headers() -> HeaderMap {
     let mut headers = HeaderMap::new();
     
     headers.insert(ACCEPT, HeaderValue::from_static("*/*"));
@@ -100,88 +644,743 @@ fn build_headers() -> HeaderMap {
     headers
 }
 
-/// 搜索歌曲 - 使用 Web API
-pub async fn search_songs(keyword: &str, limit: u32, offset: u32) -> Result<Vec<SearchSongResult>, String> {
-    let client = get_client();
-    
-    // 使用 cloudsearch API（更稳定）
-    let url = "https://music.163.com/api/cloudsearch/pc";
-    
-    let params = [
-        ("s", keyword),
-        ("type", "1"),
-        ("limit", &limit.to_string()),
-        ("offset", &offset.to_string()),
-    ];
-
-    let response = client
-        .post(url)
-        .headers(build_headers())
-        .form(&params)
-        .send()
-        .await
-        .map_err(|e| format!("Request failed: {e}"))?;
-
-    let status = response.status();
-    let response_text = response.text().await.map_err(|e| format!("Read response failed: {e}"))?;
-    
+/// 当前设置下的请求头
+fn build_headers() -> HeaderMap {
+    API_SETTINGS.read().unwrap().headers.clone()
+}
+
+/// 当前设置下的请求地址：按接口类型选择网页接口路径或 NeteaseCloudMusicApi 路径
+fn api_url(web_path: &str, cloud_path: &str) -> String {
+    let settings = API_SETTINGS.read().unwrap();
+    match settings.flavor {
+        NeteaseApiFlavor::Web => format!("{}{web_path}", settings.base_url),
+        NeteaseApiFlavor::CloudMusicApi => format!("{}{cloud_path}", settings.base_url),
+    }
+}
+
+fn api_flavor() -> NeteaseApiFlavor {
+    API_SETTINGS.read().unwrap().flavor
+}
+
+fn header_value(value: &str, name: &str) -> Result<HeaderValue, String> {
+    HeaderValue::from_str(value.trim()).map_err(|_| tr!("netease.invalidHeaderValue", name = name, value = value))
+}
+
+/// 检查并应用接口设置，返回整理后的设置（去掉首尾空白和地址末尾的 `/`）
+///
+/// 接口地址只支持 http 和 https；请求头名称和值必须是合法的 HTTP 头。之后的请求立即使用新的设置。
+pub fn apply_api_config(config: &NeteaseConfig) -> Result<NeteaseConfig, String> {
+    let base_url = config.api_base_url.trim().trim_end_matches('/').to_string();
+    if !(base_url.is_empty() || base_url.starts_with("https://") || base_url.starts_with("http://")) {
+        return Err(tr!("netease.invalidBaseUrl", url = base_url));
+    }
+
+    let mut headers = default_headers();
+    if !config.user_agent.trim().is_empty() {
+        headers.insert(USER_AGENT, header_value(&config.user_agent, "User-Agent")?);
+    }
+    if !config.cookie.trim().is_empty() {
+        headers.insert(COOKIE, header_value(&config.cookie, "Cookie")?);
+    }
+    let mut extra_headers = HashMap::new();
+    for (name, value) in &config.extra_headers {
+        let name = name.trim();
+        if name.is_empty() {
+            continue;
+        }
+        let header = HeaderName::from_bytes(name.as_bytes()).map_err(|_| tr!("netease.invalidHeaderName", name = name))?;
+        headers.insert(header, header_value(value, name)?);
+        extra_headers.insert(name.to_string(), value.trim().to_string());
+    }
+
+    *API_SETTINGS.write().unwrap() = ApiSettings {
+        base_url: if base_url.is_empty() { DEFAULT_API_BASE_URL.to_string() } else { base_url.clone() },
+        flavor: config.api_flavor,
+        headers,
+    };
+    Ok(NeteaseConfig {
+        api_base_url: base_url,
+        api_flavor: config.api_flavor,
+        user_agent: config.user_agent.trim().to_string(),
+        extra_headers,
+        cookie: config.cookie.trim().to_string(),
+    })
+}
+
+/// 网络错误是否值得重试：超时、连接失败或连接被重置
+fn is_transient(err: &reqwest::Error) -> bool {
+    err.is_timeout() || err.is_connect() || err.is_request()
+}
+
+fn request_error(err: &reqwest::Error, what: &str) -> NeteaseError {
+    if err.is_timeout() {
+        NeteaseError::Timeout(what.to_string())
+    } else if err.is_connect() && uses_proxy() {
+        NeteaseError::Proxy(format!("{what}: {err}"))
+    } else {
+        NeteaseError::Network(format!("{what}: {err}"))
+    }
+}
+
+/// 发送请求，超时、连接失败和 5xx 响应按指数退避重试
+async fn send(
+    what: &str,
+    headers: &HeaderMap,
+    build: impl Fn(&Client) -> RequestBuilder,
+) -> Result<Response, NeteaseError> {
+    let mut attempt = 0;
+    loop {
+        if is_offline() {
+            return Err(NeteaseError::Offline);
+        }
+        acquire().await;
+        // 在限流队列中等待期间也可能切换到离线模式
+        if is_offline() {
+            return Err(NeteaseError::Offline);
+        }
+        let result = build(&get_netease_client()).headers(headers.clone()).send().await;
+        let retry = match &result {
+            Ok(response) => response.status().is_server_error(),
+            Err(err) => is_transient(err),
+        };
+        if !retry || attempt >= MAX_RETRIES {
+            return result.map_err(|err| request_error(&err, what));
+        }
+        tokio::time::sleep(RETRY_BASE_DELAY * 2u32.pow(attempt)).await;
+        attempt += 1;
+    }
+}
+
+/// 按 HTTP 状态和 API 返回码区分错误
+///
+/// 429 及 405/-447（操作频繁）视为限流，403 及 -460/-462（风控、地区限制）视为被屏蔽，404 视为不存在，
+/// 401 及 20001（需要登录，如私密歌单）视为没有权限。
+fn check_api_status(status: StatusCode, code: Option<i32>, what: &str) -> Result<(), NeteaseError> {
+    if matches!(code, Some(405 | -447)) {
+        return Err(NeteaseError::RateLimited(None));
+    }
+    if status == StatusCode::UNAUTHORIZED || matches!(code, Some(401 | 20001)) {
+        return Err(NeteaseError::Private(what.to_string()));
+    }
+    if status == StatusCode::FORBIDDEN || matches!(code, Some(403 | -460 | -462)) {
+        return Err(NeteaseError::Blocked(what.to_string()));
+    }
+    if status == StatusCode::NOT_FOUND || matches!(code, Some(404 | -404)) {
+        return Err(NeteaseError::NotFound(what.to_string()));
+    }
     if !status.is_success() {
-        return Err(format!("HTTP error: {status} - {response_text}"));
+        return Err(NeteaseError::Network(format!("{what}: HTTP {status}")));
+    }
+    match code {
+        Some(code) if code != 200 => Err(NeteaseError::Network(format!("{what}: API code {code}"))),
+        _ => Ok(()),
+    }
+}
+
+/// 检查响应的 HTTP 状态，429 时读取 Retry-After
+fn check_response(response: &Response, what: &str) -> Result<(), NeteaseError> {
+    if response.status() == StatusCode::TOO_MANY_REQUESTS {
+        let retry_after =
+            response.headers().get(RETRY_AFTER).and_then(|v| v.to_str().ok()).and_then(|v| v.parse().ok());
+        return Err(NeteaseError::RateLimited(retry_after));
     }
+    check_api_status(response.status(), None, what)
+}
+
+/// 限流或风控时暂停请求队列，限流错误改为附带预计的等待时间
+fn throttle_on(err: NeteaseError) -> NeteaseError {
+    match err {
+        NeteaseError::RateLimited(retry_after) => NeteaseError::RateLimited(Some(throttle(retry_after, false))),
+        NeteaseError::Blocked(_) => {
+            throttle(None, true);
+            err
+        }
+        _ => err,
+    }
+}
+
+/// 请求 JSON 接口并解析，只检查 HTTP 状态（其他在线歌词来源共用）
+///
+/// 与网易云音乐接口共用客户端、重试策略和限流队列。
+pub(super) async fn fetch_json<T: DeserializeOwned>(
+    what: &str,
+    headers: &HeaderMap,
+    build: impl Fn(&Client) -> RequestBuilder,
+) -> Result<T, NeteaseError> {
+    let result = async {
+        let response = send(what, headers, build).await?;
+        check_response(&response, what)?;
+        let text = response.text().await.map_err(|err| request_error(&err, what))?;
 
-    let data: CloudSearchResponse = serde_json::from_str(&response_text)
-        .map_err(|e| format!("Parse response failed: {e} - Response: {}", safe_truncate(&response_text, 200)))?;
+        serde_json::from_str(&text)
+            .map_err(|e| NeteaseError::Parse(format!("{what}: {e} - Response: {}", safe_truncate(&text, 200))))
+    };
+    result.await.map_err(throttle_on)
+}
+
+/// 请求网易云音乐 JSON 接口并解析，检查响应中的 `code`
+async fn request_json<T: DeserializeOwned>(
+    what: &str,
+    build: impl Fn(&Client) -> RequestBuilder,
+    code_of: impl Fn(&T) -> i32,
+) -> Result<T, NeteaseError> {
+    let data: T = fetch_json(what, &build_headers(), build).await?;
+    check_api_status(StatusCode::OK, Some(code_of(&data)), what).map_err(throttle_on)?;
+    Ok(data)
+}
 
-    if data.code != 200 {
-        return Err(format!("API error: code {}", data.code));
+/// 调用 cloudsearch 接口，`kind` 为 1（单曲）或 10（专辑）
+///
+/// 网页接口用 POST 表单，NeteaseCloudMusicApi 用 GET 查询参数，两者的响应结构相同。
+async fn cloudsearch<T: DeserializeOwned>(
+    what: &str,
+    keyword: &str,
+    kind: &str,
+    limit: u32,
+    offset: u32,
+    code_of: impl Fn(&T) -> i32,
+) -> Result<T, NeteaseError> {
+    let url = api_url("/api/cloudsearch/pc", "/cloudsearch");
+    let limit = limit.to_string();
+    let offset = offset.to_string();
+    let flavor = api_flavor();
+    let keyword_param = if flavor == NeteaseApiFlavor::Web { "s" } else { "keywords" };
+    let params = [(keyword_param, keyword), ("type", kind), ("limit", limit.as_str()), ("offset", offset.as_str())];
+
+    match flavor {
+        NeteaseApiFlavor::Web => request_json(what, |client| client.post(&url).form(&params), code_of).await,
+        NeteaseApiFlavor::CloudMusicApi => request_json(what, |client| client.get(&url).query(&params), code_of).await,
     }
+}
+
+/// 搜索歌曲，使用 cloudsearch 接口（更稳定）
+pub async fn search_songs(keyword: &str, limit: u32, offset: u32) -> Result<Vec<SearchSongResult>, NeteaseError> {
+    let data: CloudSearchResponse =
+        cloudsearch("cloudsearch", keyword, "1", limit, offset, |d: &CloudSearchResponse| d.code).await?;
 
     let songs: Vec<SearchSongResult> = data.result
         .and_then(|r| r.songs)
         .unwrap_or_default()
         .into_iter()
-        .map(|s| SearchSongResult {
-            id: s.id.to_string(),
-            name: s.name,
-            artist: s.ar.iter().map(|a| a.name.clone()).collect::<Vec<_>>().join("/"),
-            album: s.al.map(|a| a.name).unwrap_or_default(),
-            duration: s.dt,
+        .map(|s| {
+            let album = s.al.unwrap_or_default();
+            SearchSongResult {
+                id: s.id.to_string(),
+                name: s.name,
+                artist: s.ar.iter().map(|a| a.name.clone()).collect::<Vec<_>>().join("/"),
+                album: album.name,
+                album_id: (album.id > 0).then(|| album.id.to_string()),
+                cover_url: album.pic_url.filter(|url| !url.is_empty()),
+                duration: s.dt,
+            }
         })
         .collect();
 
     Ok(songs)
 }
 
-/// 获取歌词 - 使用 Web API
-pub async fn get_lyrics(song_id: &str) -> Result<LyricsData, String> {
-    let client = get_client();
-    
-    let url = format!("https://music.163.com/api/song/lyric?id={song_id}&lv=-1&tv=-1&rv=-1&kv=-1");
+/// 获取歌词
+pub async fn get_lyrics(song_id: &str) -> Result<LyricsData, NeteaseError> {
+    let what = format!("song/lyric {song_id}");
+    let url = api_url(
+        &format!("/api/song/lyric/v1?id={song_id}&lv=-1&tv=-1&rv=-1&kv=-1&yv=-1"),
+        &format!("/lyric/new?id={song_id}"),
+    );
 
-    let response = client
-        .get(&url)
-        .headers(build_headers())
-        .send()
-        .await
-        .map_err(|e| format!("Request failed: {e}"))?;
+    let data: LyricResponse = request_json(&what, |client| client.get(&url), |d: &LyricResponse| d.code).await?;
 
-    let status = response.status();
-    let response_text = response.text().await.map_err(|e| format!("Read response failed: {e}"))?;
-    
-    if !status.is_success() {
-        return Err(format!("HTTP error: {status} - {response_text}"));
+    let lyrics = LyricsData {
+        lrc: data.lrc.and_then(|l| l.lyric).unwrap_or_default(),
+        tlyric: data.tlyric.and_then(|l| l.lyric).unwrap_or_default(),
+        romalrc: data.romalrc.and_then(|l| l.lyric).unwrap_or_default(),
+        ..Default::default()
     }
+    .with_words(data.yrc.and_then(|l| l.lyric).map(|yrc| parse_yrc(&yrc)).unwrap_or_default());
+    Ok(if data.nolyric {
+        lyrics.with_kind(LyricsKind::Instrumental)
+    } else if data.uncollected {
+        lyrics.with_kind(LyricsKind::NotFound)
+    } else {
+        lyrics.classified()
+    })
+}
 
-    let data: LyricResponse = serde_json::from_str(&response_text)
-        .map_err(|e| format!("Parse response failed: {e} - Response: {}", safe_truncate(&response_text, 200)))?;
+/// 歌曲详情接口每次最多查询的歌曲数
+const SONG_DETAIL_BATCH: usize = 50;
 
-    if data.code != 200 {
-        return Err(format!("API error: code {}", data.code));
+/// 批量获取歌曲详情，超过 50 首时分批请求
+///
+/// 结果按 `song_ids` 的顺序排列，不存在的歌曲不出现在结果中；歌曲 ID 必须是数字。
+pub async fn get_song_details(song_ids: &[String]) -> Result<Vec<SongDetailInfo>, NeteaseError> {
+    if let Some(id) = song_ids.iter().find(|id| id.is_empty() || !id.bytes().all(|b| b.is_ascii_digit())) {
+        return Err(NeteaseError::NotFound(format!("song/detail {id}")));
     }
 
-    Ok(LyricsData {
-        lrc: data.lrc.and_then(|l| l.lyric).unwrap_or_default(),
-        tlyric: data.tlyric.and_then(|l| l.lyric).unwrap_or_default(),
-        romalrc: data.romalrc.and_then(|l| l.lyric).unwrap_or_default(),
+    let mut details = Vec::with_capacity(song_ids.len());
+    for chunk in song_ids.chunks(SONG_DETAIL_BATCH) {
+        let what = format!("song/detail ({} songs)", chunk.len());
+        let ids = chunk.join(",");
+        let url = api_url(&format!("/api/song/detail/?ids=[{ids}]"), &format!("/song/detail?ids={ids}"));
+        let data: SongDetailResponse =
+            request_json(&what, |client| client.get(&url), |d: &SongDetailResponse| d.code).await?;
+        let mut found: Vec<SongDetailInfo> = data.songs.into_iter().map(SongDetailInfo::from).collect();
+        for id in chunk {
+            if let Some(index) = found.iter().position(|song| song.id == *id) {
+                details.push(found.swap_remove(index));
+            }
+        }
+    }
+    Ok(details)
+}
+
+/// 搜索专辑
+pub async fn search_albums(keyword: &str, limit: u32) -> Result<Vec<AlbumSearchResult>, NeteaseError> {
+    let data: AlbumSearchResponse =
+        cloudsearch("cloudsearch album", keyword, "10", limit, 0, |d: &AlbumSearchResponse| d.code).await?;
+
+    let albums = data
+        .result
+        .and_then(|r| r.albums)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|a| AlbumSearchResult {
+            id: a.id.to_string(),
+            name: a.name,
+            artist: a.artists.iter().map(|artist| artist.name.clone()).collect::<Vec<_>>().join("/"),
+            track_count: a.size,
+            publish_year: a.publish_time.and_then(year_from_millis),
+            cover_url: a.pic_url.filter(|url| !url.is_empty()),
+        })
+        .collect();
+    Ok(albums)
+}
+
+/// 获取专辑的曲目列表，按专辑中的顺序排列
+pub async fn get_album_tracks(album_id: &str) -> Result<Vec<AlbumTrack>, NeteaseError> {
+    let what = format!("album tracks {album_id}");
+    if album_id.is_empty() || !album_id.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(NeteaseError::NotFound(what));
+    }
+    let url = api_url(&format!("/api/v1/album/{album_id}"), &format!("/album?id={album_id}"));
+    let data: AlbumTracksResponse =
+        request_json(&what, |client| client.get(&url), |d: &AlbumTracksResponse| d.code).await?;
+    if data.songs.is_empty() {
+        return Err(NeteaseError::NotFound(what));
+    }
+
+    let album = data.album.unwrap_or_default();
+    let cover_url = album.pic_url.filter(|url| !url.is_empty());
+    let tracks: Vec<AlbumTrack> = data
+        .songs
+        .into_iter()
+        .map(|s| AlbumTrack {
+            song: SearchSongResult {
+                id: s.id.to_string(),
+                name: s.name,
+                artist: s.ar.iter().map(|a| a.name.clone()).collect::<Vec<_>>().join("/"),
+                album: album.name.clone(),
+                album_id: Some(album_id.to_string()),
+                cover_url: cover_url.clone(),
+                duration: s.dt,
+            },
+            track_number: (s.no > 0).then_some(s.no),
+            disc_number: s.cd.and_then(|cd| cd.trim().parse().ok()).filter(|disc| *disc > 0),
+        })
+        .collect();
+    Ok(tracks)
+}
+
+/// 从歌单 ID、歌单链接或分享文本中取出歌单 ID
+///
+/// 支持 `music.163.com/#/playlist?id=…`、`y.music.163.com/m/playlist?id=…&userid=…` 和
+/// `music.163.com/playlist/…/` 等写法。
+#[must_use]
+pub fn parse_playlist_id(input: &str) -> Option<String> {
+    let input = input.trim();
+    let leading_digits = |s: &str| {
+        let digits: String = s.chars().take_while(char::is_ascii_digit).collect();
+        (!digits.is_empty()).then_some(digits)
+    };
+    if !input.is_empty() && input.bytes().all(|b| b.is_ascii_digit()) {
+        return Some(input.to_string());
+    }
+
+    let rest = &input[input.find("playlist")? + "playlist".len()..];
+    if let Some(path) = rest.strip_prefix('/') {
+        return leading_digits(path);
+    }
+    rest.split(['?', '&', '#']).find_map(|part| part.strip_prefix("id=")).and_then(leading_digits)
+}
+
+/// 获取歌单的全部歌曲
+///
+/// 歌单详情只返回歌曲 ID，歌曲信息按每批 50 首查询歌曲详情。私密歌单或需要登录的歌单返回 `Private`
+/// （接口返回 401 或 20001），地区限制返回 `Blocked`。
+pub async fn get_playlist(id_or_url: &str) -> Result<OnlinePlaylist, NeteaseError> {
+    let id = parse_playlist_id(id_or_url)
+        .ok_or_else(|| NeteaseError::NotFound(format!("playlist {}", safe_truncate(id_or_url.trim(), 100))))?;
+    let what = format!("playlist {id}");
+    let url = api_url(&format!("/api/v6/playlist/detail?id={id}&n=0"), &format!("/playlist/detail?id={id}"));
+
+    let data: PlaylistResponse = request_json(&what, |client| client.get(&url), |d: &PlaylistResponse| d.code).await?;
+    let playlist = data.playlist.ok_or_else(|| NeteaseError::NotFound(what.clone()))?;
+
+    let ids: Vec<String> = playlist.track_ids.iter().map(|track| track.id.to_string()).collect();
+    let entries: Vec<OnlinePlaylistEntry> = get_song_details(&ids)
+        .await?
+        .into_iter()
+        .map(|song| OnlinePlaylistEntry {
+            id: song.id,
+            title: song.name,
+            artist: song.artists.join("/"),
+            album: song.album,
+            duration: song.duration,
+        })
+        .collect();
+
+    Ok(OnlinePlaylist {
+        id,
+        name: playlist.name,
+        creator: playlist.creator.map(|creator| creator.nickname).filter(|name| !name.is_empty()),
+        unavailable: ids.len() - entries.len(),
+        entries,
     })
 }
+
+/// 接口设置的检查结果
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiConnectionTest {
+    pub success: bool,
+    /// 收到响应所用的时间（毫秒）
+    pub latency_ms: u64,
+    /// 测试搜索返回的歌曲数
+    pub results: usize,
+    pub error: Option<NeteaseError>,
+}
+
+/// 用一次只要一条结果的搜索检查当前的接口设置（地址、接口类型、请求头）
+pub async fn test_api_connection() -> ApiConnectionTest {
+    let started = Instant::now();
+    let result = search_songs(TEST_SEARCH_KEYWORD, 1, 0).await;
+    let latency_ms = u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX);
+    match result {
+        Ok(songs) => ApiConnectionTest { success: true, latency_ms, results: songs.len(), error: None },
+        Err(e) => ApiConnectionTest { success: false, latency_ms, results: 0, error: Some(e) },
+    }
+}
+
+/// 网易云音乐歌词来源
+pub struct NeteaseProvider;
+
+impl LyricsProvider for NeteaseProvider {
+    fn name(&self) -> &'static str {
+        "netease"
+    }
+
+    fn is_available(&self) -> bool {
+        true
+    }
+
+    fn search<'a>(&'a self, query: &'a str, limit: u32, offset: u32) -> ProviderFuture<'a, Vec<ProviderSong>> {
+        Box::pin(async move { Ok(tag_songs(self.name(), search_songs(query, limit, offset).await?)) })
+    }
+
+    fn get_lyrics<'a>(&'a self, song_id: &'a str) -> ProviderFuture<'a, LyricsData> {
+        Box::pin(get_lyrics(song_id))
+    }
+
+    fn refine<'a>(&'a self, songs: &'a mut [SearchSongResult]) -> ProviderFuture<'a, ()> {
+        Box::pin(async move {
+            let ids: Vec<String> = songs.iter().map(|song| song.id.clone()).collect();
+            for detail in get_song_details(&ids).await? {
+                if let Some(song) = songs.iter_mut().find(|song| song.id == detail.id) {
+                    if detail.duration > 0 {
+                        song.duration = detail.duration;
+                    }
+                    if !detail.album.is_empty() {
+                        song.album = detail.album;
+                        song.album_id = detail.album_id;
+                    }
+                    song.cover_url = detail.cover_url.or(song.cover_url.take());
+                }
+            }
+            Ok(())
+        })
+    }
+}
+
+/// 查询歌曲所属专辑的封面地址
+async fn song_cover_url(song_id: &str) -> Result<String, NeteaseError> {
+    get_song_details(&[song_id.to_string()])
+        .await?
+        .into_iter()
+        .next()
+        .and_then(|song| song.cover_url)
+        .ok_or_else(|| NeteaseError::NotFound(format!("song/detail {song_id}")))
+}
+
+/// 查询专辑封面地址
+async fn album_cover_url(album_id: &str) -> Result<String, NeteaseError> {
+    let what = format!("album {album_id}");
+    let url = api_url(&format!("/api/album/{album_id}"), &format!("/album?id={album_id}"));
+    let data: AlbumResponse = request_json(&what, |client| client.get(&url), |d: &AlbumResponse| d.code).await?;
+    data.album.and_then(|album| album.pic_url).filter(|url| !url.is_empty()).ok_or(NeteaseError::NotFound(what))
+}
+
+/// 下载封面图片，`size` 为 0 时下载原图，否则由服务端缩放为 `size` x `size`
+async fn download_cover(pic_url: &str, size: u32, what: &str) -> Result<Vec<u8>, NeteaseError> {
+    let url = if size == 0 {
+        pic_url.to_string()
+    } else {
+        let separator = if pic_url.contains('?') { '&' } else { '?' };
+        format!("{pic_url}{separator}param={size}y{size}")
+    };
+    let what = format!("cover {what}");
+    let response = send(&what, &build_headers(), |client| client.get(&url)).await?;
+    check_response(&response, &what).map_err(throttle_on)?;
+    let data = response.bytes().await.map_err(|err| request_error(&err, &what))?;
+    Ok(data.to_vec())
+}
+
+/// 获取歌曲或专辑的封面，优先按专辑 ID 查询
+///
+/// 图片保存在封面磁盘缓存中，同一来源和尺寸只下载一次。
+pub async fn get_cover(
+    song_id: Option<&str>,
+    album_id: Option<&str>,
+    size: u32,
+    cache_dir: &Path,
+) -> Result<CoverImage, NeteaseError> {
+    let (kind, id) = match (album_id, song_id) {
+        (Some(id), _) => ("album", id),
+        (None, Some(id)) => ("song", id),
+        (None, None) => return Err(NeteaseError::NotFound("no song or album id".to_string())),
+    };
+    // ID 会拼进请求地址和缓存文件名，只接受数字
+    if id.is_empty() || !id.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(NeteaseError::NotFound(format!("{kind} {id}")));
+    }
+
+    let what = format!("{kind} {id}");
+    let key = if size == 0 { format!("netease-{kind}-{id}") } else { format!("netease-{kind}-{id}-{size}") };
+    cached_or_download(&key, &what, cache_dir, async {
+        let pic_url = if kind == "album" { album_cover_url(id).await? } else { song_cover_url(id).await? };
+        download_cover(&pic_url, size, &what).await
+    })
+    .await
+}
+
+/// 按图片地址下载封面（如其他来源搜索结果中的封面地址），`key` 为封面缓存中的文件名
+pub async fn get_cover_from_url(url: &str, key: &str, cache_dir: &Path) -> Result<CoverImage, NeteaseError> {
+    if !(url.starts_with("https://") || url.starts_with("http://")) {
+        return Err(NeteaseError::NotFound(format!("cover url {url}")));
+    }
+    cached_or_download(key, url, cache_dir, download_cover(url, 0, url)).await
+}
+
+/// 缓存中没有 `key` 对应的图片时下载并保存
+async fn cached_or_download(
+    key: &str,
+    what: &str,
+    cache_dir: &Path,
+    download: impl Future<Output = Result<Vec<u8>, NeteaseError>>,
+) -> Result<CoverImage, NeteaseError> {
+    let (path, cached) = if let Some(path) = cached_cover_file(cache_dir, key) {
+        (path, true)
+    } else {
+        let data = download.await?;
+        if sniff_image_extension(&data).is_none() {
+            return Err(NeteaseError::Parse(format!("{what}: cover is not an image")));
+        }
+        let path = store_cover_file(cache_dir, key, &data).map_err(NeteaseError::Cache)?;
+        (path, false)
+    };
+
+    let data_url = folder_cover_data_url(&path).map_err(NeteaseError::Cache)?;
+    Ok(CoverImage { path: path.to_string_lossy().to_string(), data_url, cached })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// 测试请求的超时，代替客户端默认的 10 秒
+    const TEST_TIMEOUT: Duration = Duration::from_millis(300);
+
+    /// 模拟服务器对一个连接的处理
+    enum Reply {
+        /// 返回响应后关闭连接
+        Respond(String),
+        /// 读取请求后直接关闭连接
+        Reset,
+        /// 一直不响应
+        Hang,
+    }
+
+    fn respond(status: &str, extra_headers: &str, body: &str) -> Reply {
+        let len = body.len();
+        Reply::Respond(format!(
+            "HTTP/1.1 {status}\r\nContent-Length: {len}\r\nConnection: close\r\n{extra_headers}\r\n{body}"
+        ))
+    }
+
+    fn read_request(stream: &mut TcpStream) {
+        let mut request = Vec::new();
+        let mut buf = [0; 1024];
+        while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+            match stream.read(&mut buf) {
+                Ok(0) | Err(_) => return,
+                Ok(n) => request.extend_from_slice(&buf[..n]),
+            }
+        }
+    }
+
+    /// 本地模拟服务器，按顺序用 `replies` 处理每个连接；返回地址和收到的请求数
+    fn mock_server(replies: Vec<Reply>) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/api", listener.local_addr().unwrap());
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&hits);
+        std::thread::spawn(move || {
+            // 保持挂起的连接不关闭，直到服务线程结束
+            #[allow(clippy::collection_is_never_read)]
+            let mut hanging = Vec::new();
+            for (stream, reply) in listener.incoming().zip(replies) {
+                let mut stream = stream.unwrap();
+                read_request(&mut stream);
+                counter.fetch_add(1, Ordering::SeqCst);
+                match reply {
+                    Reply::Respond(response) => {
+                        let _ = stream.write_all(response.as_bytes());
+                    }
+                    Reply::Reset => drop(stream),
+                    Reply::Hang => hanging.push(stream),
+                }
+            }
+        });
+        (url, hits)
+    }
+
+    fn get(url: &str) -> impl Fn(&Client) -> RequestBuilder + '_ {
+        move |client: &Client| client.get(url).timeout(TEST_TIMEOUT)
+    }
+
+    /// 发送请求并检查 HTTP 状态，不经过 `throttle_on`，避免暂停其他测试共用的限流队列
+    async fn status_of(url: &str) -> Result<(), NeteaseError> {
+        let response = send("test", &HeaderMap::new(), get(url)).await?;
+        check_response(&response, "test")
+    }
+
+    #[tokio::test]
+    async fn server_errors_are_retried_with_backoff() {
+        let ok = respond("200 OK", "", r#"{"code":200}"#);
+        let (url, hits) = mock_server(vec![respond("503 Service Unavailable", "", ""), Reply::Reset, ok]);
+        let started = Instant::now();
+
+        let data: serde_json::Value = fetch_json("test", &HeaderMap::new(), get(&url)).await.unwrap();
+        assert_eq!(data["code"], 200);
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+        assert!(started.elapsed() >= RETRY_BASE_DELAY * 3);
+    }
+
+    #[tokio::test]
+    async fn retries_stop_after_max_retries() {
+        let (url, hits) = mock_server((0..4).map(|_| respond("502 Bad Gateway", "", "")).collect());
+
+        let error = status_of(&url).await.unwrap_err();
+        assert!(matches!(&error, NeteaseError::Network(message) if message.contains("HTTP 502")), "{error}");
+        // 第一次请求加上 MAX_RETRIES 次重试
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn hanging_requests_time_out() {
+        let (url, hits) = mock_server(vec![Reply::Hang, Reply::Hang, Reply::Hang]);
+
+        let error = status_of(&url).await.unwrap_err();
+        assert!(matches!(&error, NeteaseError::Timeout(what) if what == "test"), "{error}");
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn refused_connections_are_network_errors() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/api", listener.local_addr().unwrap());
+        drop(listener);
+
+        let error = status_of(&url).await.unwrap_err();
+        assert!(matches!(&error, NeteaseError::Network(message) if message.starts_with("test: ")), "{error}");
+    }
+
+    #[tokio::test]
+    async fn too_many_requests_are_rate_limited() {
+        let (url, hits) = mock_server(vec![respond("429 Too Many Requests", "Retry-After: 7\r\n", "")]);
+
+        let error = status_of(&url).await.unwrap_err();
+        assert!(matches!(error, NeteaseError::RateLimited(Some(7))), "{error}");
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn forbidden_responses_are_blocked() {
+        let (url, _) = mock_server(vec![respond("403 Forbidden", "", "")]);
+
+        let error = status_of(&url).await.unwrap_err();
+        assert!(matches!(error, NeteaseError::Blocked(_)), "{error}");
+    }
+
+    #[tokio::test]
+    async fn missing_resources_are_not_found() {
+        let (url, _) = mock_server(vec![respond("404 Not Found", "", "")]);
+
+        let result: Result<serde_json::Value, _> = fetch_json("song/lyric 1", &HeaderMap::new(), get(&url)).await;
+        assert!(matches!(result, Err(NeteaseError::NotFound(what)) if what == "song/lyric 1"));
+    }
+
+    #[tokio::test]
+    async fn unexpected_json_is_a_parse_error_with_context() {
+        let (url, _) = mock_server(vec![respond("200 OK", "", r#"{"code": "#)]);
+
+        let result: Result<LyricResponse, _> = fetch_json("song/lyric 1", &HeaderMap::new(), get(&url)).await;
+        let Err(NeteaseError::Parse(message)) = result else {
+            panic!("expected a parse error");
+        };
+        assert!(message.starts_with("song/lyric 1: "), "{message}");
+        assert!(message.ends_with(r#"Response: {"code": "#), "{message}");
+    }
+
+    #[test]
+    fn api_codes_map_to_error_types() {
+        let cases: [(StatusCode, Option<i32>, &str); 9] = [
+            (StatusCode::OK, Some(-447), "RATE_LIMITED"),
+            (StatusCode::OK, Some(405), "RATE_LIMITED"),
+            (StatusCode::OK, Some(-460), "BLOCKED"),
+            (StatusCode::OK, Some(-462), "BLOCKED"),
+            (StatusCode::FORBIDDEN, None, "BLOCKED"),
+            (StatusCode::OK, Some(-404), "NOT_FOUND"),
+            (StatusCode::OK, Some(20001), "PRIVATE"),
+            (StatusCode::UNAUTHORIZED, None, "PRIVATE"),
+            (StatusCode::OK, Some(-1), "NETWORK"),
+        ];
+        for (status, code, expected) in cases {
+            assert_eq!(check_api_status(status, code, "test").unwrap_err().code(), expected, "{status} {code:?}");
+        }
+        assert!(check_api_status(StatusCode::OK, Some(200), "test").is_ok());
+        assert!(check_api_status(StatusCode::OK, None, "test").is_ok());
+    }
+
+    #[test]
+    fn errors_serialize_with_code_and_message() {
+        let json = serde_json::to_value(NeteaseError::Timeout("song/lyric 1".to_string())).unwrap();
+        assert_eq!(json, serde_json::json!({ "code": "TIMEOUT", "message": "Request timed out: song/lyric 1" }));
+        let json = serde_json::to_value(NeteaseError::RateLimited(Some(30))).unwrap();
+        assert_eq!(json["code"], "RATE_LIMITED");
+        assert_eq!(json["retryAfterSecs"], 30);
+        assert!(serde_json::to_value(NeteaseError::RateLimited(None)).unwrap().get("retryAfterSecs").is_none());
+    }
+}
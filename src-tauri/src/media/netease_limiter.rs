@@ -0,0 +1,218 @@
+//! 网易云音乐请求限流
+//!
+//! 所有网易云音乐请求共用一个令牌桶（默认每秒 2 个，最多积攒 3 个），排队的请求按先后顺序依次取令牌。
+//! 桶中有令牌时请求立即发出，单次交互请求不会被延迟；需要等待时额外加上一点随机抖动，避免请求间隔过于规律。
+//! 收到限流或风控响应后暂停整个队列一段时间，并向前端发送 `netease-throttled` 事件。
+
+use serde::Serialize;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::{LazyLock, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+/// 暂停请求时发送的事件
+pub const NETEASE_THROTTLED_EVENT: &str = "netease-throttled";
+
+/// 默认每秒请求数
+pub const DEFAULT_REQUESTS_PER_SECOND: f64 = 2.0;
+/// 令牌桶容量：空闲后允许连续发出的请求数
+const BURST: f64 = 3.0;
+/// 等待令牌时附加的最大随机抖动（毫秒）
+const MAX_JITTER_MS: u64 = 150;
+/// 限流响应没有给出等待时间时的暂停时间
+const DEFAULT_RATE_LIMIT_COOLDOWN: Duration = Duration::from_secs(30);
+/// 被风控屏蔽时的暂停时间
+const BLOCKED_COOLDOWN: Duration = Duration::from_mins(1);
+
+/// 发送给前端的限流事件
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ThrottledEvent {
+    /// 预计恢复请求前的等待秒数
+    pub retry_after_secs: u64,
+    /// 是否因风控屏蔽而暂停（否则为请求过于频繁）
+    pub blocked: bool,
+}
+
+struct Bucket {
+    tokens: f64,
+    requests_per_second: f64,
+    last_refill: Instant,
+    /// 暂停到该时间为止
+    cooldown_until: Option<Instant>,
+}
+
+impl Bucket {
+    fn new(now: Instant) -> Self {
+        Self { tokens: BURST, requests_per_second: DEFAULT_REQUESTS_PER_SECOND, last_refill: now, cooldown_until: None }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.requests_per_second).min(BURST);
+        self.last_refill = now;
+    }
+
+    /// 取一个令牌；没有令牌或正在暂停时返回需要等待的时间
+    fn take(&mut self, now: Instant) -> Option<Duration> {
+        if let Some(until) = self.cooldown_until {
+            if until > now {
+                return Some(until - now);
+            }
+            self.cooldown_until = None;
+        }
+        self.refill(now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            return None;
+        }
+        Some(Duration::from_secs_f64((1.0 - self.tokens) / self.requests_per_second) + jitter())
+    }
+
+    /// 暂停到 `now + cooldown` 并清空令牌，已有更晚的暂停时保留它；返回暂停结束的时间
+    ///
+    /// 令牌从暂停结束时才开始重新积攒，恢复后不会立即连续发出一批请求。
+    fn pause(&mut self, now: Instant, cooldown: Duration) -> Instant {
+        let until = self.cooldown_until.filter(|current| *current > now + cooldown).unwrap_or(now + cooldown);
+        self.cooldown_until = Some(until);
+        self.tokens = 0.0;
+        self.last_refill = until;
+        until
+    }
+}
+
+static BUCKET: LazyLock<Mutex<Bucket>> = LazyLock::new(|| Mutex::new(Bucket::new(Instant::now())));
+
+/// 排队顺序：tokio 的互斥锁按请求先后交给等待者
+static QUEUE: LazyLock<tokio::sync::Mutex<()>> = LazyLock::new(|| tokio::sync::Mutex::new(()));
+
+/// 用于发送限流事件
+static APP_HANDLE: OnceLock<AppHandle> = OnceLock::new();
+
+fn jitter() -> Duration {
+    let random = RandomState::new().build_hasher().finish();
+    Duration::from_millis(random % (MAX_JITTER_MS + 1))
+}
+
+/// 记录应用句柄，之后的限流事件发送到前端
+pub fn init(app: &AppHandle) {
+    let _ = APP_HANDLE.set(app.clone());
+}
+
+/// 设置每秒请求数，限制在 0.1 ~ 20 之间
+pub fn set_rate(requests_per_second: f64) {
+    let rate = if requests_per_second.is_finite() {
+        requests_per_second.clamp(0.1, 20.0)
+    } else {
+        DEFAULT_REQUESTS_PER_SECOND
+    };
+    let mut bucket = BUCKET.lock().unwrap();
+    bucket.refill(Instant::now());
+    bucket.requests_per_second = rate;
+}
+
+/// 等待轮到当前请求并取得令牌
+pub async fn acquire() {
+    let _turn = QUEUE.lock().await;
+    loop {
+        let wait = BUCKET.lock().unwrap().take(Instant::now());
+        match wait {
+            Some(wait) => tokio::time::sleep(wait).await,
+            None => return,
+        }
+    }
+}
+
+/// 收到限流或风控响应后暂停队列，返回预计的等待秒数
+///
+/// `retry_after` 为服务端建议的等待秒数，没有时按默认时间暂停；暂停期间再次触发时只会延长暂停。
+pub fn throttle(retry_after: Option<u64>, blocked: bool) -> u64 {
+    let cooldown = match retry_after {
+        Some(secs) => Duration::from_secs(secs.max(1)),
+        None if blocked => BLOCKED_COOLDOWN,
+        None => DEFAULT_RATE_LIMIT_COOLDOWN,
+    };
+    let now = Instant::now();
+    let until = BUCKET.lock().unwrap().pause(now, cooldown);
+
+    let retry_after_secs = (until - now).as_secs_f64().ceil() as u64;
+    if let Some(app) = APP_HANDLE.get() {
+        let _ = app.emit(NETEASE_THROTTLED_EVENT, ThrottledEvent { retry_after_secs, blocked });
+    }
+    retry_after_secs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JITTER: Duration = Duration::from_millis(MAX_JITTER_MS);
+
+    fn assert_wait(wait: Option<Duration>, base: Duration) {
+        let wait = wait.expect("expected to wait");
+        assert!(wait >= base && wait <= base + JITTER, "{wait:?} not within jitter of {base:?}");
+    }
+
+    #[test]
+    fn a_full_bucket_allows_a_burst_then_waits_for_the_next_token() {
+        let start = Instant::now();
+        let mut bucket = Bucket::new(start);
+        for _ in 0..3 {
+            assert_eq!(bucket.take(start), None);
+        }
+        // 2 个/秒：下一个令牌在 0.5 秒后
+        assert_wait(bucket.take(start), Duration::from_millis(500));
+        assert_wait(bucket.take(start + Duration::from_millis(200)), Duration::from_millis(300));
+    }
+
+    #[test]
+    fn tokens_refill_over_time_up_to_the_burst() {
+        let start = Instant::now();
+        let mut bucket = Bucket::new(start);
+        for _ in 0..3 {
+            bucket.take(start);
+        }
+
+        let later = start + Duration::from_millis(500);
+        assert_eq!(bucket.take(later), None);
+        assert!(bucket.take(later).is_some());
+
+        // 空闲很久之后最多积攒 3 个令牌
+        let idle = later + Duration::from_mins(1);
+        for _ in 0..3 {
+            assert_eq!(bucket.take(idle), None);
+        }
+        assert!(bucket.take(idle).is_some());
+    }
+
+    #[test]
+    fn the_wait_follows_the_configured_rate() {
+        let start = Instant::now();
+        let mut bucket = Bucket::new(start);
+        bucket.requests_per_second = 0.5;
+        for _ in 0..3 {
+            bucket.take(start);
+        }
+        assert_wait(bucket.take(start), Duration::from_secs(2));
+        assert_wait(bucket.take(start + Duration::from_millis(1500)), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn pausing_empties_the_bucket_and_only_extends_the_cooldown() {
+        let start = Instant::now();
+        let mut bucket = Bucket::new(start);
+        let until = bucket.pause(start, Duration::from_secs(30));
+        assert_eq!(until, start + Duration::from_secs(30));
+        assert_eq!(bucket.take(start + Duration::from_secs(10)), Some(Duration::from_secs(20)));
+
+        // 较短的暂停不会提前结束当前暂停
+        assert_eq!(bucket.pause(start + Duration::from_secs(10), Duration::from_secs(5)), until);
+        assert_eq!(bucket.pause(start, Duration::from_mins(1)), start + Duration::from_mins(1));
+
+        // 暂停结束后令牌从零开始重新积攒
+        let resumed = start + Duration::from_mins(1);
+        assert_wait(bucket.take(resumed), Duration::from_millis(500));
+        assert_eq!(bucket.take(resumed + Duration::from_millis(500)), None);
+    }
+}
@@ -0,0 +1,145 @@
+//! 网络共享模块
+//!
+//! 判断路径是否位于网络共享上，并为可能无限期阻塞的文件操作加上超时。
+//! 休眠或断开的 SMB/NFS 共享会让 `read_dir`、`metadata` 等调用长时间不返回，
+//! 扫描网络目录时这些调用在单独的线程中执行，超时后调用方放弃等待并继续处理其他文件。
+
+use crate::error::{AppError, AppResult};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+/// 扫描网络目录时单个文件操作的默认超时（秒）
+pub const DEFAULT_NETWORK_TIMEOUT_SECS: u64 = 10;
+
+/// 检查网络目录是否可访问时的最长等待时间
+pub const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// 网络文件系统类型
+#[cfg(unix)]
+const NETWORK_FILESYSTEMS: &[&str] = &[
+    "cifs", "smb3", "smbfs", "nfs", "nfs4", "afpfs", "webdav", "fuse.sshfs", "fuse.rclone", "9p", "afs", "davfs",
+    "fuse.davfs2",
+];
+
+/// 在单独的线程中执行 `operation`，超过 `timeout` 未完成时返回 `None`
+///
+/// 阻塞的系统调用无法中断，超时后线程会在调用返回时自行退出，结果被丢弃。
+pub fn with_timeout<T, F>(timeout: Duration, operation: F) -> Option<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = sender.send(operation());
+    });
+    receiver.recv_timeout(timeout).ok()
+}
+
+/// `/proc/mounts` 中的挂载点用八进制转义空白字符
+#[cfg(all(unix, not(target_os = "macos")))]
+fn unescape_mount_point(mount: &str) -> String {
+    mount.replace("\\040", " ").replace("\\011", "\t").replace("\\012", "\n").replace("\\134", "\\")
+}
+
+/// 挂载表：(挂载点, 文件系统类型)
+#[cfg(all(unix, not(target_os = "macos")))]
+fn mount_table() -> Vec<(String, String)> {
+    let Ok(mounts) = fs::read_to_string("/proc/mounts") else {
+        return Vec::new();
+    };
+    mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let mount = unescape_mount_point(fields.nth(1)?);
+            Some((mount, fields.next()?.to_string()))
+        })
+        .collect()
+}
+
+/// 挂载表：(挂载点, 文件系统类型)，解析 `mount` 命令的输出，如 `//user@nas/music on /Volumes/music (smbfs, ...)`
+#[cfg(target_os = "macos")]
+fn mount_table() -> Vec<(String, String)> {
+    let Ok(output) = std::process::Command::new("/sbin/mount").output() else {
+        return Vec::new();
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let (_, rest) = line.split_once(" on ")?;
+            let (mount, options) = rest.rsplit_once(" (")?;
+            let fs_type = options.split([',', ')']).next()?.trim();
+            Some((mount.to_string(), fs_type.to_string()))
+        })
+        .collect()
+}
+
+/// 路径是否位于网络共享上
+///
+/// 只按路径和挂载表判断，不访问路径本身，因此不会被断开的共享阻塞。
+/// Windows 上只识别 UNC 路径（`\\server\share`、`\\?\UNC\server\share`）。
+#[cfg(windows)]
+#[must_use]
+pub fn is_network_path(path: &Path) -> bool {
+    let path = path.to_string_lossy().replace('/', "\\");
+    match path.strip_prefix(r"\\?\") {
+        Some(rest) => rest.to_uppercase().starts_with("UNC\\"),
+        None => path.starts_with(r"\\"),
+    }
+}
+
+/// 路径是否位于网络共享上
+///
+/// 只按路径和挂载表判断，不访问路径本身，因此不会被断开的共享阻塞；经由符号链接进入共享的路径不会被识别。
+#[cfg(unix)]
+#[must_use]
+pub fn is_network_path(path: &Path) -> bool {
+    is_network_in(&mount_table(), path)
+}
+
+#[cfg(unix)]
+fn is_network_in(mounts: &[(String, String)], path: &Path) -> bool {
+    let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    mounts
+        .iter()
+        .filter(|(mount, _)| path.starts_with(mount))
+        .max_by_key(|(mount, _)| mount.len())
+        .is_some_and(|(_, fs_type)| NETWORK_FILESYSTEMS.contains(&fs_type.as_str()))
+}
+
+/// 返回判断路径是否位于网络共享上的函数，挂载表只读取一次，用于批量判断
+#[cfg(unix)]
+pub fn network_path_classifier() -> impl Fn(&Path) -> bool + Sync {
+    let mounts = mount_table();
+    move |path| is_network_in(&mounts, path)
+}
+
+/// 返回判断路径是否位于网络共享上的函数，用于批量判断
+#[cfg(windows)]
+pub fn network_path_classifier() -> impl Fn(&Path) -> bool + Sync {
+    is_network_path
+}
+
+/// 检查目录是否可访问
+///
+/// 网络路径最多等待 `PROBE_TIMEOUT`，超时或共享无法连接时返回 `NetworkUnavailable`；
+/// 路径不存在或不是目录时返回 `InvalidPath`。
+pub fn probe_dir(path: &Path) -> AppResult<()> {
+    let display = path.to_string_lossy().to_string();
+    if !is_network_path(path) {
+        return if path.is_dir() { Ok(()) } else { Err(AppError::InvalidPath(display)) };
+    }
+
+    let owned = path.to_path_buf();
+    match with_timeout(PROBE_TIMEOUT, move || fs::metadata(owned).map(|metadata| metadata.is_dir())) {
+        Some(Ok(true)) => Ok(()),
+        Some(Ok(false)) => Err(AppError::InvalidPath(display)),
+        Some(Err(e)) if e.kind() == ErrorKind::NotFound => Err(AppError::InvalidPath(display)),
+        Some(Err(_)) | None => Err(AppError::NetworkUnavailable(display)),
+    }
+}
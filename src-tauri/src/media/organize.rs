@@ -0,0 +1,722 @@
+//! 按标签整理文件模块
+//!
+//! 根据 `{album_artist}/{album}/{track:02} - {title}` 这类模板计算新路径并移动文件，
+//! 也提供按指定目标路径移动单个或多个文件的功能。
+
+use super::metadata::{get_track_metadata_with_options, MetadataOptions, TrackMetadata};
+use super::metadata_cache::invalidate;
+use super::watcher::mark_self_write;
+use crate::error::{AppError, AppResult};
+use crate::paths::{canonical_key, long_path, same_path};
+use crate::library::LibraryDb;
+use crate::tr;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, Read};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+
+/// 路径变更事件名称，前端据此更新队列和缓存
+pub const TRACK_PATHS_CHANGED_EVENT: &str = "track-paths-changed";
+
+/// 默认的整理模板
+pub const DEFAULT_ORGANIZE_PATTERN: &str = "{album_artist}/{album}/{track:02} - {title}";
+
+/// 跨分区移动后校验内容时每次读取的字节数
+const VERIFY_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Windows 保留的设备名（不区分大小写，带扩展名时同样保留）
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "CONIN$", "CONOUT$", "COM0", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6",
+    "COM7", "COM8", "COM9", "COM¹", "COM²", "COM³", "LPT0", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6",
+    "LPT7", "LPT8", "LPT9", "LPT¹", "LPT²", "LPT³",
+];
+
+/// 生成的单级文件名的最大字节数，给扩展名和重名时的序号留出余量（多数文件系统上限为 255）
+const MAX_NAME_BYTES: usize = 200;
+
+/// 单个文件的移动
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FileMove {
+    pub from: String,
+    pub to: String,
+}
+
+/// 无法移动的文件
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FileConflict {
+    pub from: String,
+    pub to: Option<String>,
+    /// 原因："exists"、"duplicate"、"playing" 或错误信息
+    pub reason: String,
+}
+
+/// 整理结果
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct OrganizeResult {
+    pub dry_run: bool,
+    /// 计划（或已完成）的移动
+    pub moves: Vec<FileMove>,
+    /// 因冲突或错误未移动的文件
+    pub conflicts: Vec<FileConflict>,
+}
+
+/// 清理文件名中的非法字符
+///
+/// Windows 下替换 `<>:"/\|?*` 和控制字符、去掉末尾的点和空格，并避开保留设备名；
+/// 其他平台只替换 `/` 和空字符。过长的名称按字符边界截断到 `MAX_NAME_BYTES` 字节以内。
+#[must_use]
+pub fn sanitize_file_name(name: &str) -> String {
+    let windows = cfg!(windows);
+    let mut cleaned: String = name
+        .chars()
+        .map(|c| {
+            let illegal = c == '/' || c == '\0' || (windows && (c.is_control() || "<>:\"\\|?*".contains(c)));
+            if illegal { '_' } else { c }
+        })
+        .collect();
+
+    if cleaned.len() > MAX_NAME_BYTES {
+        let end = (0..=MAX_NAME_BYTES).rev().find(|&i| cleaned.is_char_boundary(i)).unwrap_or(0);
+        cleaned.truncate(end);
+    }
+
+    if windows {
+        cleaned = cleaned.trim_end_matches(['.', ' ']).to_string();
+        let stem = cleaned.split('.').next().unwrap_or_default().trim_end();
+        if RESERVED_NAMES.iter().any(|r| r.eq_ignore_ascii_case(stem)) {
+            cleaned.insert(stem.len(), '_');
+        }
+    }
+
+    let cleaned = cleaned.trim().to_string();
+    if cleaned.is_empty() || cleaned == "." || cleaned == ".." {
+        "_".to_string()
+    } else {
+        cleaned
+    }
+}
+
+/// 取得占位符对应的值
+fn placeholder_value(metadata: &TrackMetadata, name: &str) -> Option<String> {
+    match name {
+        "title" => metadata.title.clone(),
+        "artist" => metadata.artist.clone(),
+        "album" => metadata.album.clone(),
+        "album_artist" => metadata.album_artist.clone().or_else(|| metadata.artist.clone()),
+        "genre" => metadata.genre.clone(),
+        "year" => metadata.year.map(|v| v.to_string()),
+        "track" => metadata.track_number.map(|v| v.to_string()),
+        "track_total" => metadata.track_total.map(|v| v.to_string()),
+        "disc" => metadata.disc_number.map(|v| v.to_string()),
+        "format" => metadata.format.clone(),
+        _ => None,
+    }
+}
+
+/// 替换文本中的占位符，`escape` 处理每个标签值
+///
+/// 占位符格式为 `{name}` 或 `{name:02}`（数字补零），缺失的字段以 "Unknown" 代替；
+/// `pattern` 是完整的模板，只用于错误信息。
+fn render_placeholders(
+    text: &str,
+    pattern: &str,
+    metadata: &TrackMetadata,
+    escape: impl Fn(&str) -> String,
+) -> Result<String, String> {
+    let mut rendered = String::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('}') else {
+            return Err(tr!("organize.unclosedPlaceholder", pattern = pattern));
+        };
+        let placeholder = &rest[start + 1..start + end];
+        let (name, width) = match placeholder.split_once(':') {
+            Some((name, spec)) => {
+                let width: usize = spec.parse().map_err(|_| tr!("organize.invalidPlaceholder", placeholder = format!("{{{placeholder}}}")))?;
+                (name, Some(width))
+            }
+            None => (placeholder, None),
+        };
+
+        let value = placeholder_value(metadata, name);
+        if value.is_none() && !is_known_placeholder(name) {
+            return Err(tr!("organize.unknownPlaceholder", placeholder = format!("{{{name}}}")));
+        }
+        let value = match (value, width) {
+            (Some(v), Some(width)) if v.chars().all(|c| c.is_ascii_digit()) => format!("{v:0>width$}"),
+            (Some(v), _) => v,
+            (None, _) => tr!("common.unknown"),
+        };
+        rendered.push_str(&escape(&value));
+        rest = &rest[start + end + 1..];
+    }
+    rendered.push_str(rest);
+    Ok(rendered)
+}
+
+/// 根据模板渲染一行文字（如复制音轨信息时的 `{artist} - {title}`），占位符与整理文件的模板相同
+pub fn render_template(template: &str, metadata: &TrackMetadata) -> Result<String, String> {
+    if template.trim().is_empty() {
+        return Err(tr!("organize.emptyPattern"));
+    }
+    render_placeholders(template, template, metadata, str::to_string)
+}
+
+/// 根据模板渲染相对路径（不含扩展名）
+///
+/// 占位符的格式见 `render_placeholders`。标签值中的斜杠会被替换，只有模板中的 `/` 才会产生目录层级。
+pub fn render_pattern(pattern: &str, metadata: &TrackMetadata) -> Result<PathBuf, String> {
+    let mut segments = Vec::new();
+
+    for raw_segment in pattern.split(['/', '\\']).filter(|s| !s.is_empty()) {
+        let segment = render_placeholders(raw_segment, pattern, metadata, |value| value.replace(['/', '\\'], "_"))?;
+        segments.push(sanitize_file_name(&segment));
+    }
+
+    if segments.is_empty() {
+        return Err(tr!("organize.emptyPattern"));
+    }
+    Ok(segments.iter().collect())
+}
+
+fn is_known_placeholder(name: &str) -> bool {
+    matches!(
+        name,
+        "title" | "artist" | "album" | "album_artist" | "genre" | "year" | "track" | "track_total" | "disc" | "format"
+    )
+}
+
+/// 按模板计算文件在 `base` 下的目标路径，保留源文件的扩展名
+pub fn target_path(base: &Path, pattern: &str, metadata: &TrackMetadata, source: &Path) -> Result<PathBuf, String> {
+    let mut relative = render_pattern(pattern, metadata)?;
+    if let Some(ext) = source.extension() {
+        let name = relative.file_name().unwrap_or_default().to_string_lossy().to_string();
+        relative.set_file_name(format!("{name}.{}", ext.to_string_lossy()));
+    }
+    if relative.components().any(|c| !matches!(c, Component::Normal(_))) {
+        return Err(tr!("organize.invalidPath", path = relative.display()));
+    }
+    Ok(base.join(relative))
+}
+
+/// 找到文件所属的音乐目录，没有时使用文件所在目录
+fn base_directory(path: &Path, music_directories: &[String]) -> PathBuf {
+    music_directories
+        .iter()
+        .map(Path::new)
+        .filter(|root| path.starts_with(root))
+        .max_by_key(|root| root.components().count())
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| path.parent().map(Path::to_path_buf).unwrap_or_default())
+}
+
+/// 逐块比较两个文件的内容
+fn same_contents(a: &Path, b: &Path) -> io::Result<bool> {
+    let (a, b) = (long_path(a), long_path(b));
+    if fs::metadata(&a)?.len() != fs::metadata(&b)?.len() {
+        return Ok(false);
+    }
+    let (mut a, mut b) = (BufReader::new(File::open(a)?), BufReader::new(File::open(b)?));
+    let (mut buf_a, mut buf_b) = (vec![0u8; VERIFY_CHUNK_SIZE], vec![0u8; VERIFY_CHUNK_SIZE]);
+    loop {
+        let read = a.read(&mut buf_a)?;
+        if read == 0 {
+            return Ok(true);
+        }
+        b.read_exact(&mut buf_b[..read])?;
+        if buf_a[..read] != buf_b[..read] {
+            return Ok(false);
+        }
+    }
+}
+
+/// 两个路径是否指向同一个文件（如不区分大小写的文件系统上只改变大小写的重命名）
+#[cfg(unix)]
+fn same_file(a: &Path, b: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    match (fs::metadata(long_path(a)), fs::metadata(long_path(b))) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+        _ => false,
+    }
+}
+
+/// 两个路径是否指向同一个文件；Windows 上 `fs::canonicalize` 返回文件在磁盘上的实际路径（包括大小写）
+#[cfg(not(unix))]
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(long_path(a)), fs::canonicalize(long_path(b))) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// 复制到新文件并保留权限，目标已存在时返回 `AlreadyExists`，不会覆盖
+fn copy_new(from: &Path, to: &Path) -> io::Result<()> {
+    let mut source = File::open(long_path(from))?;
+    let permissions = source.metadata()?.permissions();
+    let mut target = OpenOptions::new().write(true).create_new(true).open(long_path(to))?;
+    let copied = io::copy(&mut source, &mut target)
+        .and_then(|_| target.sync_all())
+        .and_then(|()| fs::set_permissions(long_path(to), permissions));
+    if copied.is_err() {
+        drop(target);
+        let _ = fs::remove_file(long_path(to));
+    }
+    copied
+}
+
+/// 复制文件并校验内容，自动创建目标目录；目标已存在时返回 `AlreadyExists`，校验失败时删除不完整的副本
+pub fn copy_file_verified(from: &Path, to: &Path) -> io::Result<()> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(long_path(parent))?;
+    }
+    mark_self_write(to);
+    copy_new(from, to)?;
+    if !same_contents(from, to).unwrap_or(false) {
+        let _ = fs::remove_file(long_path(to));
+        return Err(io::Error::other(tr!("organize.copyMismatch")));
+    }
+    Ok(())
+}
+
+/// 移动文件，自动创建目标目录；目标已存在时返回 `AlreadyExists`，不会覆盖
+///
+/// 先在目标位置建立硬链接再删除源文件，目标已存在时建立链接失败，检查和移动之间新出现的文件也不会被覆盖。
+/// 跨分区或文件系统不支持硬链接（如 FAT、部分网络共享）时退回到复制、校验内容、再删除源文件；
+/// 校验失败时删除不完整的副本并保留源文件。目标就是源文件本身（只改变大小写）时直接重命名。
+pub fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(long_path(parent))?;
+    }
+    mark_self_write(from);
+    mark_self_write(to);
+    if same_file(from, to) {
+        return fs::rename(long_path(from), long_path(to));
+    }
+    match fs::hard_link(long_path(from), long_path(to)) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => return Err(e),
+        Err(_) => copy_file_verified(from, to)?,
+    }
+    // 源文件无法删除（如被其他程序占用）时撤销，避免同一文件出现在两处
+    fs::remove_file(long_path(from)).inspect_err(|_| {
+        let _ = fs::remove_file(long_path(to));
+    })
+}
+
+/// 移动失败时的冲突原因：目标在检查之后才出现时仍按 `exists` 报告
+fn move_error_reason(e: &io::Error) -> String {
+    if e.kind() == io::ErrorKind::AlreadyExists {
+        "exists".to_string()
+    } else {
+        tr!("file.moveFailed", detail = e)
+    }
+}
+
+/// 批量移动的结果
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct MoveFilesResult {
+    pub moved: Vec<FileMove>,
+    /// 因冲突或错误未移动的文件
+    pub conflicts: Vec<FileConflict>,
+}
+
+/// `path` 是否为正在播放的文件
+fn is_playing(current_path: &Mutex<Option<String>>, path: &str) -> bool {
+    current_path.lock().unwrap().as_deref().is_some_and(|current| same_path(current, path))
+}
+
+/// 移动单个文件前的检查，返回冲突原因
+fn move_conflict(file_move: &FileMove) -> Option<&'static str> {
+    let (from, to) = (long_path(&file_move.from), long_path(&file_move.to));
+    if !from.is_file() {
+        Some("missing")
+    } else if to.exists() && !same_file(&from, &to) {
+        // 只改变大小写的重命名在不区分大小写的文件系统上目标"已存在"，但指向源文件本身，允许进行
+        Some("exists")
+    } else {
+        None
+    }
+}
+
+/// 移动（或重命名）文件，并更新曲库数据库和元数据缓存
+///
+/// 目标已存在的文件不会被覆盖，以 `exists` 冲突报告；多个文件指向同一目标时只移动第一个。
+/// 正在播放的文件在 Windows 上无法重命名，报告 `playing`；其他平台上照常移动，
+/// 并把 `current_path` 指向新路径，已打开的文件句柄不受影响，播放不会中断。
+/// 数据库中的路径在同一个事务中更新。
+pub fn move_files_internal(
+    db: &LibraryDb,
+    moves: Vec<FileMove>,
+    current_path: &Mutex<Option<String>>,
+) -> AppResult<MoveFilesResult> {
+    let mut result = MoveFilesResult::default();
+    let mut claimed: HashSet<String> = HashSet::new();
+
+    for file_move in moves {
+        if file_move.from == file_move.to {
+            continue;
+        }
+        let playing = is_playing(current_path, &file_move.from);
+
+        let reason = if playing && cfg!(windows) {
+            Some("playing")
+        } else if !claimed.insert(canonical_key(&file_move.to)) {
+            Some("duplicate")
+        } else {
+            move_conflict(&file_move)
+        };
+        if let Some(reason) = reason {
+            let reason = reason.to_string();
+            result.conflicts.push(FileConflict { from: file_move.from, to: Some(file_move.to), reason });
+            continue;
+        }
+
+        if let Err(e) = move_file(Path::new(&file_move.from), Path::new(&file_move.to)) {
+            let reason = move_error_reason(&e);
+            result.conflicts.push(FileConflict { from: file_move.from, to: Some(file_move.to), reason });
+            continue;
+        }
+
+        if playing {
+            *current_path.lock().unwrap() = Some(file_move.to.clone());
+        }
+        invalidate(&file_move.from);
+        result.moved.push(file_move);
+    }
+
+    db.move_tracks(&result.moved)?;
+    Ok(result)
+}
+
+/// 按模板整理文件
+///
+/// `dry_run` 为 `true` 时只返回计划；否则通过 [`move_files_internal`] 移动文件，
+/// 同时更新曲库数据库和元数据缓存，已存在的目标文件不会被覆盖。
+/// 和实际移动一致，只有 Windows 上正在播放的文件报告 `playing`。
+pub fn organize_files_internal(
+    db: &LibraryDb,
+    paths: &[String],
+    pattern: &str,
+    dry_run: bool,
+    music_directories: &[String],
+    options: &MetadataOptions,
+    current_path: &Mutex<Option<String>>,
+) -> AppResult<OrganizeResult> {
+    let mut result = OrganizeResult { dry_run, ..Default::default() };
+    // `canonical_key` 只在 Windows 上忽略大小写，区分大小写的文件系统上 `Artist/x` 和 `artist/x` 是两个目标
+    let mut claimed: HashSet<String> = HashSet::new();
+    let mut plan = Vec::new();
+
+    for path in paths {
+        let source = Path::new(path);
+        let metadata = match get_track_metadata_with_options(path, options) {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                result.conflicts.push(FileConflict { from: path.clone(), to: None, reason: e });
+                continue;
+            }
+        };
+
+        let target = target_path(&base_directory(source, music_directories), pattern, &metadata, source)
+            .map_err(AppError::Other)?;
+        let target_str = target.to_string_lossy().to_string();
+
+        if same_path(path, &target_str) {
+            continue;
+        }
+
+        let reason = if cfg!(windows) && is_playing(current_path, path) {
+            Some("playing")
+        } else if !claimed.insert(canonical_key(&target_str)) {
+            Some("duplicate")
+        } else if target.exists() && !same_file(source, &target) {
+            Some("exists")
+        } else {
+            None
+        };
+        if let Some(reason) = reason {
+            result.conflicts.push(FileConflict { from: path.clone(), to: Some(target_str), reason: reason.to_string() });
+            continue;
+        }
+
+        plan.push(FileMove { from: path.clone(), to: target_str });
+    }
+
+    if dry_run {
+        result.moves = plan;
+    } else {
+        let moved = move_files_internal(db, plan, current_path)?;
+        result.moves = moved.moved;
+        result.conflicts.extend(moved.conflicts);
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(dir: &TempDir, name: &str, content: &str) -> PathBuf {
+        let path = dir.path().join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn file_move(from: &Path, to: &Path) -> FileMove {
+        FileMove { from: from.to_string_lossy().to_string(), to: to.to_string_lossy().to_string() }
+    }
+
+    #[test]
+    fn move_file_does_not_overwrite_existing_target() {
+        let dir = TempDir::new().unwrap();
+        let from = write(&dir, "a.flac", "source");
+        let to = write(&dir, "b.flac", "existing");
+
+        let error = move_file(&from, &to).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(move_error_reason(&error), "exists");
+        assert_eq!(fs::read_to_string(&to).unwrap(), "existing");
+        assert_eq!(fs::read_to_string(&from).unwrap(), "source");
+    }
+
+    #[test]
+    fn move_file_creates_target_directories() {
+        let dir = TempDir::new().unwrap();
+        let from = write(&dir, "a.flac", "source");
+        let to = dir.path().join("Artist").join("Album").join("a.flac");
+
+        move_file(&from, &to).unwrap();
+        assert!(!from.exists());
+        assert_eq!(fs::read_to_string(&to).unwrap(), "source");
+    }
+
+    #[test]
+    fn copy_file_verified_does_not_overwrite_existing_target() {
+        let dir = TempDir::new().unwrap();
+        let from = write(&dir, "a.flac", "source");
+        let to = write(&dir, "copy/a.flac", "existing");
+
+        assert_eq!(copy_file_verified(&from, &to).unwrap_err().kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(fs::read_to_string(&to).unwrap(), "existing");
+
+        let fresh = dir.path().join("copy").join("b.flac");
+        copy_file_verified(&from, &fresh).unwrap();
+        assert_eq!(fs::read_to_string(&fresh).unwrap(), "source");
+        assert!(from.is_file());
+    }
+
+    #[test]
+    fn move_conflict_reports_missing_source_and_existing_target() {
+        let dir = TempDir::new().unwrap();
+        let from = write(&dir, "a.flac", "source");
+        let to = write(&dir, "b.flac", "other");
+
+        assert_eq!(move_conflict(&file_move(&dir.path().join("missing.flac"), &to)), Some("missing"));
+        assert_eq!(move_conflict(&file_move(&from, &to)), Some("exists"));
+        assert_eq!(move_conflict(&file_move(&from, &dir.path().join("c.flac"))), None);
+    }
+
+    /// 区分大小写的文件系统上只有大小写不同的是另一个文件，不能被覆盖
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn case_only_difference_to_another_file_is_a_conflict() {
+        let dir = TempDir::new().unwrap();
+        let from = write(&dir, "Song.flac", "source");
+        let to = write(&dir, "song.flac", "other");
+        if same_file(&from, &to) {
+            // 临时目录在不区分大小写的文件系统上
+            return;
+        }
+
+        assert_eq!(move_conflict(&file_move(&from, &to)), Some("exists"));
+        assert_eq!(move_file(&from, &to).unwrap_err().kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(fs::read_to_string(&to).unwrap(), "other");
+    }
+
+    /// 目标是源文件本身（另一个名字指向同一文件）时不算冲突
+    #[cfg(unix)]
+    #[test]
+    fn target_that_is_the_source_itself_is_not_a_conflict() {
+        let dir = TempDir::new().unwrap();
+        let from = write(&dir, "a.flac", "source");
+        let to = dir.path().join("b.flac");
+        if fs::hard_link(&from, &to).is_err() {
+            return;
+        }
+
+        assert!(same_file(&from, &to));
+        assert_eq!(move_conflict(&file_move(&from, &to)), None);
+    }
+
+    fn tagged(artist: &str, title: &str) -> TrackMetadata {
+        TrackMetadata { artist: Some(artist.to_string()), title: Some(title.to_string()), ..Default::default() }
+    }
+
+    fn library(root: &Path) -> LibraryDb {
+        LibraryDb::open(&root.join("library.db")).unwrap()
+    }
+
+    fn organize_in(db: &LibraryDb, paths: &[&Path], root: &Path, dry_run: bool) -> OrganizeResult {
+        let paths: Vec<String> = paths.iter().map(|p| p.to_string_lossy().to_string()).collect();
+        let roots = [root.to_string_lossy().to_string()];
+        let options = MetadataOptions::default();
+        organize_files_internal(db, &paths, "{album}/{title}", dry_run, &roots, &options, &Mutex::new(None)).unwrap()
+    }
+
+    fn organize(paths: &[&Path], root: &Path, dry_run: bool) -> OrganizeResult {
+        organize_in(&library(root), paths, root, dry_run)
+    }
+
+    #[test]
+    fn slashes_in_tags_do_not_create_directories() {
+        let path = render_pattern("{artist}/{title}", &tagged("AC/DC", "Back\\In Black")).unwrap();
+        assert_eq!(path, Path::new("AC_DC").join("Back_In Black"));
+    }
+
+    #[test]
+    fn numeric_placeholders_are_zero_padded() {
+        let metadata = TrackMetadata { track_number: Some(3), title: Some("Song".to_string()), ..Default::default() };
+        assert_eq!(render_pattern("{track:02} - {title}", &metadata).unwrap(), PathBuf::from("03 - Song"));
+    }
+
+    #[test]
+    fn invalid_patterns_are_rejected() {
+        let metadata = tagged("Artist", "Song");
+        assert!(render_pattern("{lyricist}", &metadata).is_err());
+        assert!(render_pattern("{title", &metadata).is_err());
+        assert!(render_pattern("{track:x}", &metadata).is_err());
+        assert!(render_pattern("//", &metadata).is_err());
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn reserved_device_names_are_renamed() {
+        assert_eq!(sanitize_file_name("CON"), "CON_");
+        assert_eq!(sanitize_file_name("con.flac"), "con_.flac");
+        assert_eq!(sanitize_file_name("Lpt1 "), "Lpt1_");
+        assert_eq!(sanitize_file_name("Console"), "Console");
+        assert_eq!(sanitize_file_name("a<b>:c?.."), "a_b__c_");
+        assert_eq!(render_pattern("{artist}/{title}", &tagged("NUL", "CON")).unwrap(), Path::new("NUL_").join("CON_"));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn reserved_names_with_extensions_and_superscripts_are_renamed() {
+        assert_eq!(sanitize_file_name("COM¹"), "COM¹_");
+        assert_eq!(sanitize_file_name("conin$.txt"), "conin$_.txt");
+        assert_eq!(sanitize_file_name("nul.tar.gz"), "nul_.tar.gz");
+        assert_eq!(sanitize_file_name("AUX .mp3"), "AUX_ .mp3");
+        assert_eq!(sanitize_file_name("COM10"), "COM10");
+        // 截断后残留的末尾点同样去掉
+        assert_eq!(sanitize_file_name(&format!("{}....", "a".repeat(199))), "a".repeat(199));
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn reserved_device_names_are_kept_elsewhere() {
+        assert_eq!(sanitize_file_name("CON"), "CON");
+        assert_eq!(sanitize_file_name("a<b>:c?"), "a<b>:c?");
+        assert_eq!(sanitize_file_name("a/b\0c"), "a_b_c");
+    }
+
+    #[test]
+    fn empty_and_dot_names_are_replaced() {
+        assert_eq!(sanitize_file_name(""), "_");
+        assert_eq!(sanitize_file_name(".."), "_");
+        assert_eq!(sanitize_file_name("  "), "_");
+    }
+
+    #[test]
+    fn long_names_are_truncated_on_a_char_boundary() {
+        let name = sanitize_file_name(&"歌".repeat(100));
+        assert!(name.len() <= MAX_NAME_BYTES);
+        assert_eq!(name, "歌".repeat(MAX_NAME_BYTES / 3));
+    }
+
+    #[test]
+    fn organize_reports_duplicate_targets() {
+        let dir = TempDir::new().unwrap();
+        // 无法读取标签的文件都落到同一个 Unknown/Unknown 目标
+        let a = write(&dir, "a/x.flac", "not audio");
+        let b = write(&dir, "b/x.flac", "not audio");
+
+        let result = organize(&[&a, &b], dir.path(), true);
+        assert_eq!(result.moves.len(), 1);
+        assert_eq!(result.moves[0].from, a.to_string_lossy());
+        assert_eq!(result.conflicts.len(), 1);
+        assert_eq!(result.conflicts[0].from, b.to_string_lossy());
+        assert_eq!(result.conflicts[0].reason, "duplicate");
+        assert!(a.is_file() && b.is_file());
+    }
+
+    #[test]
+    fn organize_claims_targets_case_sensitively_except_on_windows() {
+        let dir = TempDir::new().unwrap();
+        // 扩展名保留原样，两个目标只有大小写不同
+        let a = write(&dir, "a/x.flac", "not audio");
+        let b = write(&dir, "b/x.FLAC", "not audio");
+
+        let result = organize(&[&a, &b], dir.path(), true);
+        if cfg!(windows) {
+            assert_eq!(result.moves.len(), 1);
+            assert_eq!(result.conflicts[0].reason, "duplicate");
+        } else {
+            assert_eq!(result.moves.len(), 2);
+            assert!(result.conflicts.is_empty());
+        }
+    }
+
+    #[test]
+    fn organize_moves_files_and_never_overwrites_existing_targets() {
+        let dir = TempDir::new().unwrap();
+        // 无法读取标签时标题取文件名，同名文件落到同一个目标
+        let a = write(&dir, "a/x.flac", "first");
+        let b = write(&dir, "b/x.flac", "second");
+
+        let result = organize(&[&a], dir.path(), false);
+        assert_eq!(result.moves.len(), 1);
+        let target = PathBuf::from(&result.moves[0].to);
+        assert!(!a.exists());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "first");
+
+        let result = organize(&[&b], dir.path(), false);
+        assert!(result.moves.is_empty());
+        assert_eq!(result.conflicts[0].reason, "exists");
+        assert_eq!(fs::read_to_string(&target).unwrap(), "first");
+        assert_eq!(fs::read_to_string(&b).unwrap(), "second");
+    }
+
+    #[test]
+    fn organize_updates_the_library_and_metadata_cache() {
+        let dir = TempDir::new().unwrap();
+        let db = library(dir.path());
+        let source = write(&dir, "incoming/x.flac", "not audio");
+        let source_str = source.to_string_lossy().to_string();
+        let cached = crate::media::metadata_cache::cached_track_metadata(&source_str, &MetadataOptions::default());
+        db.upsert_tracks(&[cached.unwrap()]).unwrap();
+        assert!(crate::media::metadata_cache::peek(&source_str).is_some());
+
+        let plan = organize_in(&db, &[&source], dir.path(), true);
+        assert_eq!(plan.moves.len(), 1);
+        assert!(source.is_file());
+        assert!(db.get_track(&source_str).unwrap().is_some());
+
+        let result = organize_in(&db, &[&source], dir.path(), false);
+        assert_eq!(result.moves.len(), 1);
+        let target = &result.moves[0].to;
+        assert!(!source.exists() && Path::new(target).is_file());
+        assert!(db.get_track(&source_str).unwrap().is_none());
+        assert!(db.get_track(target).unwrap().is_some());
+        assert!(crate::media::metadata_cache::peek(&source_str).is_none());
+    }
+}
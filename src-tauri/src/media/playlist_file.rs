@@ -0,0 +1,820 @@
+//! 播放列表文件导入导出模块
+//!
+//! 读写其他播放器使用的播放列表文件（M3U/M3U8、PLS、XSPF）。格式优先根据内容识别，
+//! 无法识别时使用扩展名。相对路径相对播放列表所在目录解析，找不到的文件保留在结果中并单独列出，
+//! 网络地址作为流媒体条目保留。
+
+use super::encoding::decode_text;
+use super::filesystem::check_file_exists_internal;
+use super::metadata::{MetadataOptions, Playlist, TrackMetadata};
+use super::metadata_cache::cached_track_metadata;
+use crate::tr;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf, MAIN_SEPARATOR_STR};
+
+/// 播放列表文件格式
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PlaylistFormat {
+    /// M3U / M3U8（扩展 M3U）
+    M3u,
+    /// Winamp PLS（INI 格式）
+    Pls,
+    /// XML Shareable Playlist Format
+    Xspf,
+}
+
+impl PlaylistFormat {
+    /// 根据扩展名判断格式
+    #[must_use]
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "m3u" | "m3u8" => Some(Self::M3u),
+            "pls" => Some(Self::Pls),
+            "xspf" => Some(Self::Xspf),
+            _ => None,
+        }
+    }
+
+    /// 根据文件开头的内容识别格式
+    #[must_use]
+    pub fn sniff(content: &str) -> Option<Self> {
+        let head: String = content.trim_start_matches('\u{feff}').trim_start().chars().take(512).collect();
+        let head = head.to_lowercase();
+        if head.starts_with("[playlist]") {
+            Some(Self::Pls)
+        } else if head.starts_with("<?xml") || head.starts_with("<playlist") {
+            head.contains("<playlist").then_some(Self::Xspf)
+        } else if head.starts_with("#extm3u") || head.starts_with("#extinf") {
+            Some(Self::M3u)
+        } else {
+            None
+        }
+    }
+}
+
+/// 播放列表文件中的一个条目（尚未解析路径）
+#[derive(Debug, Clone, Default)]
+pub struct PlaylistEntry {
+    /// 文件中原始书写的位置
+    pub location: String,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    /// 时长（秒）
+    pub duration: Option<f64>,
+}
+
+/// 解析后的播放列表文件内容
+#[derive(Debug, Clone, Default)]
+pub struct ParsedPlaylist {
+    /// 文件中声明的名称（`#PLAYLIST:`、XSPF 的 `<title>`）
+    pub name: Option<String>,
+    pub entries: Vec<PlaylistEntry>,
+}
+
+/// 导入结果
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportedPlaylist {
+    /// 按原顺序排列的全部条目，找不到的文件和流媒体只包含播放列表中记录的信息
+    pub playlist: Playlist,
+    /// 找不到的文件（解析后的路径）
+    pub missing: Vec<String>,
+    /// 网络地址条目
+    pub streams: Vec<String>,
+    pub format: PlaylistFormat,
+    /// 检测到的文本编码
+    pub encoding: String,
+}
+
+/// 导出结果
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaylistExportResult {
+    pub output_path: String,
+    pub format: PlaylistFormat,
+    /// 写出的条目数
+    pub exported: usize,
+}
+
+/// 解析后的条目位置
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EntryLocation {
+    File(PathBuf),
+    Stream(String),
+}
+
+/// 解码 URI 中的百分号编码（按 UTF-8 解释）
+#[must_use]
+pub fn percent_decode(value: &str) -> String {
+    String::from_utf8_lossy(&urlencoding::decode_binary(value.as_bytes())).into_owned()
+}
+
+/// 按 URI 规则逐段编码路径，保留 `/` 分隔符
+fn percent_encode_path(path: &str) -> String {
+    path.split('/').map(|segment| urlencoding::encode(segment).into_owned()).collect::<Vec<_>>().join("/")
+}
+
+/// 是否为带协议的地址（`http://`、`file://` 等）
+fn has_scheme(location: &str) -> bool {
+    location.split_once("://").is_some_and(|(scheme, _)| {
+        !scheme.is_empty() && scheme.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    })
+}
+
+fn is_file_url(location: &str) -> bool {
+    location.get(..7).is_some_and(|scheme| scheme.eq_ignore_ascii_case("file://")) && location.len() > 7
+}
+
+/// `file://` 地址转换为本地路径
+fn file_url_to_path(url: &str) -> PathBuf {
+    let rest = &url[7..];
+    // file://localhost/path 与 file:///path 等价
+    let rest = rest.strip_prefix("localhost").unwrap_or(rest);
+    let decoded = percent_decode(rest);
+    let bytes = decoded.as_bytes();
+    if bytes.len() >= 3 && bytes[0] == b'/' && bytes[1].is_ascii_alphabetic() && bytes[2] == b':' {
+        // file:///C:/Music -> C:/Music
+        PathBuf::from(&decoded[1..])
+    } else if decoded.starts_with('/') {
+        PathBuf::from(decoded)
+    } else {
+        // file://server/share -> //server/share
+        PathBuf::from(format!("//{decoded}"))
+    }
+}
+
+/// 本地绝对路径转换为 `file://` 地址
+pub fn path_to_file_url(path: &str) -> String {
+    let normalized = path.replace('\\', "/");
+    let bytes = normalized.as_bytes();
+    if bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' {
+        // 盘符中的冒号不编码
+        format!("file:///{}{}", &normalized[..2], percent_encode_path(&normalized[2..]))
+    } else if let Some(unc) = normalized.strip_prefix("//") {
+        format!("file://{}", percent_encode_path(unc))
+    } else {
+        format!("file://{}", percent_encode_path(&normalized))
+    }
+}
+
+/// 是否为 Windows 风格的绝对路径（`C:\`、`\\server\share`），在其他平台上也按绝对路径处理
+fn is_windows_absolute(location: &str) -> bool {
+    let bytes = location.as_bytes();
+    (bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':') || location.starts_with("\\\\")
+}
+
+fn is_absolute(location: &str) -> bool {
+    is_windows_absolute(location) || location.starts_with('/') || Path::new(location).is_absolute()
+}
+
+/// 相对播放列表所在目录解析条目位置并统一路径分隔符
+#[must_use]
+pub fn resolve_location(base_dir: &Path, location: &str) -> EntryLocation {
+    let location = location.trim();
+    if is_file_url(location) {
+        return EntryLocation::File(file_url_to_path(location));
+    }
+    if has_scheme(location) {
+        return EntryLocation::Stream(location.to_string());
+    }
+    if is_absolute(location) {
+        return EntryLocation::File(PathBuf::from(location));
+    }
+    let relative: PathBuf = location.split(['/', '\\']).filter(|s| !s.is_empty() && *s != ".").collect();
+    EntryLocation::File(base_dir.join(relative))
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+/// 解析 `#EXTINF:时长 属性,艺术家 - 标题`
+fn parse_extinf(value: &str) -> PlaylistEntry {
+    // 属性值中可能含有逗号，只在引号外寻找分隔逗号
+    let mut quoted = false;
+    let comma = value.char_indices().find_map(|(index, c)| match c {
+        '"' => {
+            quoted = !quoted;
+            None
+        }
+        ',' if !quoted => Some(index),
+        _ => None,
+    });
+    let (info, display) = match comma {
+        Some(index) => (&value[..index], value[index + 1..].trim()),
+        None => (value, ""),
+    };
+
+    let duration = info
+        .split_whitespace()
+        .next()
+        .and_then(|d| d.parse::<f64>().ok())
+        .filter(|d| *d > 0.0);
+    let (artist, title) = match display.split_once(" - ") {
+        Some((artist, title)) if !artist.trim().is_empty() => (non_empty(artist), non_empty(title)),
+        _ => (None, non_empty(display)),
+    };
+
+    PlaylistEntry { title, artist, duration, ..Default::default() }
+}
+
+/// 解析 M3U 文本内容
+#[must_use]
+pub fn parse_m3u(content: &str) -> ParsedPlaylist {
+    let mut parsed = ParsedPlaylist::default();
+    let mut pending: Option<PlaylistEntry> = None;
+
+    for line in content.lines() {
+        let line = line.trim().trim_start_matches('\u{feff}');
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(value) = line.strip_prefix("#EXTINF:") {
+            pending = Some(parse_extinf(value));
+        } else if let Some(name) = line.strip_prefix("#PLAYLIST:") {
+            parsed.name = non_empty(name);
+        } else if line.starts_with('#') {
+            // #EXTM3U、#EXTVLCOPT 等其他指令
+        } else {
+            let mut entry = pending.take().unwrap_or_default();
+            entry.location = line.to_string();
+            parsed.entries.push(entry);
+        }
+    }
+    parsed
+}
+
+/// 解析 PLS 文本内容
+///
+/// 条目由 `File1=`、`Title1=`、`Length1=` 组成，键不区分大小写，按编号排序。
+#[must_use]
+pub fn parse_pls(content: &str) -> ParsedPlaylist {
+    let mut indexed: Vec<(u32, PlaylistEntry)> = Vec::new();
+
+    for line in content.lines() {
+        let Some((key, value)) = line.trim().split_once('=') else {
+            continue;
+        };
+        let key = key.trim().to_lowercase();
+        let (field, number) = key.split_at(key.find(|c: char| c.is_ascii_digit()).unwrap_or(key.len()));
+        let Ok(number) = number.parse::<u32>() else {
+            continue;
+        };
+
+        let position = indexed.iter().position(|(n, _)| *n == number).unwrap_or_else(|| {
+            indexed.push((number, PlaylistEntry::default()));
+            indexed.len() - 1
+        });
+        let entry = &mut indexed[position].1;
+        match field {
+            "file" => entry.location = value.trim().to_string(),
+            "title" => entry.title = non_empty(value),
+            // 流媒体的长度为 -1
+            "length" => entry.duration = value.trim().parse::<f64>().ok().filter(|d| *d > 0.0),
+            _ => {}
+        }
+    }
+
+    indexed.sort_by_key(|(number, _)| *number);
+    ParsedPlaylist {
+        name: None,
+        entries: indexed.into_iter().map(|(_, entry)| entry).filter(|e| !e.location.is_empty()).collect(),
+    }
+}
+
+/// 解析 XSPF 文档
+///
+/// 没有协议的 `<location>` 是相对 URI，先解码百分号编码再按相对路径处理。
+pub fn parse_xspf(content: &str) -> Result<ParsedPlaylist, String> {
+    let mut reader = Reader::from_str(content);
+    reader.config_mut().trim_text(true);
+
+    let mut parsed = ParsedPlaylist::default();
+    let mut stack: Vec<String> = Vec::new();
+    let mut current: Option<PlaylistEntry> = None;
+
+    loop {
+        let event = reader.read_event().map_err(|e| tr!("playlist.xspfParseFailed", detail = e))?;
+        let text = match &event {
+            Event::Start(start) => {
+                let name = String::from_utf8_lossy(start.local_name().as_ref()).to_lowercase();
+                if name == "track" && stack.last().is_some_and(|parent| parent == "tracklist") {
+                    current = Some(PlaylistEntry::default());
+                }
+                stack.push(name);
+                continue;
+            }
+            Event::End(_) => {
+                if stack.pop().as_deref() == Some("track") {
+                    if let Some(entry) = current.take().filter(|e| !e.location.is_empty()) {
+                        parsed.entries.push(entry);
+                    }
+                }
+                continue;
+            }
+            Event::Text(text) => text.unescape().map_err(|e| tr!("playlist.xspfParseFailed", detail = e))?.into_owned(),
+            Event::CData(data) => String::from_utf8_lossy(data.as_ref()).into_owned(),
+            Event::Eof => break,
+            _ => continue,
+        };
+
+        let element = stack.last().map_or("", String::as_str);
+        let parent = stack.len().checked_sub(2).map_or("", |i| stack[i].as_str());
+        match (&mut current, parent, element) {
+            (Some(entry), "track", "location") if entry.location.is_empty() => {
+                let location = text.trim();
+                entry.location = if has_scheme(location) { location.to_string() } else { percent_decode(location) };
+            }
+            (Some(entry), "track", "title") => entry.title = non_empty(&text),
+            (Some(entry), "track", "creator") => entry.artist = non_empty(&text),
+            (Some(entry), "track", "album") => entry.album = non_empty(&text),
+            // XSPF 的时长单位是毫秒
+            (Some(entry), "track", "duration") => {
+                entry.duration = text.trim().parse::<f64>().ok().filter(|d| *d > 0.0).map(|ms| ms / 1000.0);
+            }
+            (None, "playlist", "title") => parsed.name = non_empty(&text),
+            _ => {}
+        }
+    }
+    Ok(parsed)
+}
+
+/// 为找不到的文件或流媒体生成只含播放列表信息的条目
+fn placeholder_track(path: String, name: String, entry: &PlaylistEntry) -> TrackMetadata {
+    TrackMetadata {
+        path,
+        name,
+        title: entry.title.clone(),
+        artist: entry.artist.clone(),
+        artists: entry.artist.iter().cloned().collect(),
+        album: entry.album.clone(),
+        duration: entry.duration,
+        ..Default::default()
+    }
+}
+
+/// 解析条目路径、检查文件是否存在并读取元数据
+#[must_use]
+pub fn resolve_entries(
+    name: String,
+    parsed: &ParsedPlaylist,
+    base_dir: &Path,
+    options: &MetadataOptions,
+    format: PlaylistFormat,
+    encoding: &str,
+) -> ImportedPlaylist {
+    let mut imported = ImportedPlaylist {
+        playlist: Playlist::new(parsed.name.clone().unwrap_or(name)),
+        missing: Vec::new(),
+        streams: Vec::new(),
+        format,
+        encoding: encoding.to_string(),
+    };
+
+    for entry in &parsed.entries {
+        match resolve_location(base_dir, &entry.location) {
+            EntryLocation::Stream(url) => {
+                let name = entry.title.clone().unwrap_or_else(|| url.clone());
+                imported.playlist.add_track(placeholder_track(url.clone(), name, entry));
+                imported.streams.push(url);
+            }
+            EntryLocation::File(path) => {
+                let path = path.to_string_lossy().to_string();
+                let file_name = Path::new(&path.replace('\\', "/"))
+                    .file_name()
+                    .map_or_else(|| path.clone(), |n| n.to_string_lossy().to_string());
+
+                if check_file_exists_internal(&path) {
+                    let track = cached_track_metadata(&path, options).unwrap_or_else(|e| {
+                        eprintln!("Failed to get metadata for file '{path}': {e}");
+                        placeholder_track(path.replace('/', "\\"), file_name, entry)
+                    });
+                    imported.playlist.add_track(track);
+                } else {
+                    imported.playlist.add_track(placeholder_track(path.replace('/', "\\"), file_name, entry));
+                    imported.missing.push(path);
+                }
+            }
+        }
+    }
+    imported
+}
+
+/// 导入播放列表文件
+///
+/// `.m3u8` 和 `.xspf` 按 UTF-8 读取；`.m3u`、`.pls` 自动检测编码（GBK、Latin-1 等）。
+pub fn import_playlist_internal(path: &str, options: &MetadataOptions) -> Result<ImportedPlaylist, String> {
+    let file_path = Path::new(path);
+    let bytes = fs::read(file_path).map_err(|e| tr!("playlist.readFailed", detail = e))?;
+
+    let is_utf8_only = file_path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("m3u8") || ext.eq_ignore_ascii_case("xspf"));
+    let (text, encoding) = if is_utf8_only {
+        (String::from_utf8_lossy(&bytes).into_owned(), "UTF-8")
+    } else {
+        let decoded = decode_text(&bytes);
+        (decoded.text, decoded.encoding)
+    };
+
+    let format = PlaylistFormat::sniff(&text)
+        .or_else(|| PlaylistFormat::from_path(file_path))
+        .ok_or_else(|| tr!("playlist.unsupportedFormat", path = path))?;
+    let parsed = match format {
+        PlaylistFormat::M3u => parse_m3u(&text),
+        PlaylistFormat::Pls => parse_pls(&text),
+        PlaylistFormat::Xspf => parse_xspf(text.trim_start_matches('\u{feff}'))?,
+    };
+    let name = file_path
+        .file_stem()
+        .map_or_else(|| "Playlist".to_string(), |s| s.to_string_lossy().to_string());
+    let base_dir = file_path.parent().unwrap_or_else(|| Path::new("."));
+    Ok(resolve_entries(name, &parsed, base_dir, options, format, encoding))
+}
+
+/// 要写出的条目
+#[derive(Default)]
+struct ExportEntry {
+    /// 写入文件的位置（本地路径或网络地址）
+    location: String,
+    is_stream: bool,
+    title: Option<String>,
+    artist: Option<String>,
+    album: Option<String>,
+    duration: Option<f64>,
+}
+
+/// 计算写入文件的本地路径，启用 `relative` 且文件位于播放列表目录下时写相对路径
+fn export_location(path: &str, base_dir: &Path, relative: bool) -> String {
+    let native = path.replace(['/', '\\'], MAIN_SEPARATOR_STR);
+    if relative {
+        if let Ok(stripped) = Path::new(&native).strip_prefix(base_dir) {
+            return stripped.to_string_lossy().to_string();
+        }
+    }
+    native
+}
+
+fn seconds(duration: Option<f64>) -> i64 {
+    duration.map_or(-1, |d| d.round() as i64)
+}
+
+fn write_m3u(name: &str, entries: &[ExportEntry]) -> String {
+    let mut out = String::from("#EXTM3U\n");
+    let _ = writeln!(out, "#PLAYLIST:{name}");
+    for entry in entries {
+        let display = match (&entry.artist, &entry.title) {
+            (Some(artist), Some(title)) => format!("{artist} - {title}"),
+            (None, Some(title)) => title.clone(),
+            _ => String::new(),
+        };
+        let _ = writeln!(out, "#EXTINF:{},{display}", seconds(entry.duration));
+        let _ = writeln!(out, "{}", entry.location);
+    }
+    out
+}
+
+fn write_pls(entries: &[ExportEntry]) -> String {
+    let mut out = String::from("[playlist]\n");
+    for (index, entry) in entries.iter().enumerate() {
+        let number = index + 1;
+        let _ = writeln!(out, "File{number}={}", entry.location);
+        if let Some(title) = &entry.title {
+            let _ = writeln!(out, "Title{number}={title}");
+        }
+        let _ = writeln!(out, "Length{number}={}", seconds(entry.duration));
+    }
+    let _ = writeln!(out, "NumberOfEntries={}", entries.len());
+    out.push_str("Version=2\n");
+    out
+}
+
+/// 写出 XSPF，本地绝对路径写为 `file://` 地址，相对路径写为相对 URI，非 ASCII 字符按 UTF-8 百分号编码
+fn write_xspf(name: &str, entries: &[ExportEntry]) -> String {
+    let escape = |value: &str| quick_xml::escape::escape(value).into_owned();
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<playlist version=\"1\" xmlns=\"http://xspf.org/ns/0/\">\n");
+    let _ = writeln!(out, "  <title>{}</title>", escape(name));
+    out.push_str("  <trackList>\n");
+    for entry in entries {
+        let location = if entry.is_stream {
+            entry.location.clone()
+        } else if is_absolute(&entry.location) {
+            path_to_file_url(&entry.location)
+        } else {
+            percent_encode_path(&entry.location.replace('\\', "/"))
+        };
+        out.push_str("    <track>\n");
+        let _ = writeln!(out, "      <location>{}</location>", escape(&location));
+        for (element, value) in [("title", &entry.title), ("creator", &entry.artist), ("album", &entry.album)] {
+            if let Some(value) = value {
+                let _ = writeln!(out, "      <{element}>{}</{element}>", escape(value));
+            }
+        }
+        if let Some(duration) = entry.duration {
+            let _ = writeln!(out, "      <duration>{}</duration>", (duration * 1000.0).round() as u64);
+        }
+        out.push_str("    </track>\n");
+    }
+    out.push_str("  </trackList>\n</playlist>\n");
+    out
+}
+
+/// 导出播放列表文件
+///
+/// 未指定 `format` 时根据输出文件扩展名判断。能读取到的文件会写入标题、艺术家和时长，网络地址原样写出。
+pub fn export_playlist_internal(
+    output_path: &str,
+    name: Option<&str>,
+    paths: &[String],
+    format: Option<PlaylistFormat>,
+    relative: bool,
+    options: &MetadataOptions,
+) -> Result<PlaylistExportResult, String> {
+    let output = Path::new(output_path);
+    let format = format
+        .or_else(|| PlaylistFormat::from_path(output))
+        .ok_or_else(|| tr!("playlist.unknownExtension", path = output_path))?;
+    let base_dir = output.parent().unwrap_or_else(|| Path::new("."));
+    let name = name.map_or_else(
+        || output.file_stem().map_or_else(String::new, |s| s.to_string_lossy().to_string()),
+        str::to_string,
+    );
+
+    let entries: Vec<ExportEntry> = paths
+        .iter()
+        .map(|path| {
+            if has_scheme(path) && !is_file_url(path) {
+                return ExportEntry { location: path.clone(), is_stream: true, ..Default::default() };
+            }
+            let local =
+                if is_file_url(path) { file_url_to_path(path).to_string_lossy().to_string() } else { path.clone() };
+            let track = check_file_exists_internal(&local)
+                .then(|| cached_track_metadata(&local, options).ok())
+                .flatten()
+                .unwrap_or_default();
+            ExportEntry {
+                location: export_location(&local, base_dir, relative),
+                is_stream: false,
+                title: track.title,
+                artist: track.artist,
+                album: track.album,
+                duration: track.duration,
+            }
+        })
+        .collect();
+
+    let content = match format {
+        PlaylistFormat::M3u => write_m3u(&name, &entries),
+        PlaylistFormat::Pls => write_pls(&entries),
+        PlaylistFormat::Xspf => write_xspf(&name, &entries),
+    };
+    fs::write(output, content).map_err(|e| tr!("playlist.writeFailed", detail = e))?;
+
+    Ok(PlaylistExportResult { output_path: output_path.to_string(), format, exported: entries.len() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::media::encoding::encode_with;
+    use tempfile::TempDir;
+
+    /// foobar2000 保存的 M3U8：UTF-8 BOM、CRLF、`\` 分隔的相对路径
+    const FOOBAR2000_M3U8: &str = "\u{feff}#EXTM3U\r\n\
+        #EXTINF:269,周杰伦 - 晴天\r\n\
+        ..\\Music\\周杰伦\\叶惠美\\03 晴天.flac\r\n\
+        #EXTINF:339,周杰伦 - 以父之名\r\n\
+        ..\\Music\\周杰伦\\叶惠美\\01 以父之名.flac\r\n\
+        #EXTINF:223,周杰伦 - 稻香\r\n\
+        ..\\Music\\周杰伦\\魔杰座\\稻香.flac\r\n";
+
+    /// VLC 保存的 M3U：`file://` 地址、`#EXTVLCOPT` 选项、流媒体条目，`$URL` 在测试中替换
+    const VLC_M3U: &str = "#EXTM3U\n\
+        #EXTINF:269,周杰伦 - 晴天\n\
+        #EXTVLCOPT:start-time=12\n\
+        $URL\n\
+        #EXTINF:-1,Hit FM\n\
+        http://stream.example.com/hitfm.mp3\n\
+        #EXTINF:339,以父之名\n\
+        ../Music/周杰伦/叶惠美/01 以父之名.flac\n";
+
+    /// MusicBee 在中文 Windows 上导出的 M3U：系统代码页（GBK）、CRLF、相对路径和绝对路径混用
+    const MUSICBEE_M3U: &str = "#EXTM3U\r\n\
+        #EXTINF:269,周杰伦 - 晴天\r\n\
+        Music\\周杰伦\\叶惠美\\03 晴天.flac\r\n\
+        #EXTINF:251,周杰伦 - 七里香\r\n\
+        C:\\Users\\Public\\Music\\周杰伦\\七里香\\七里香.flac\r\n\
+        #EXTINF:223,周杰伦 - 简单爱\r\n\
+        Music\\周杰伦\\范特西\\简单爱.flac\r\n";
+
+    /// 曲库目录：`Music/周杰伦/叶惠美/` 下有两首（无法读取标签的）音轨
+    fn library() -> (TempDir, PathBuf) {
+        let dir = TempDir::new().unwrap();
+        let album = dir.path().join("Music").join("周杰伦").join("叶惠美");
+        fs::create_dir_all(&album).unwrap();
+        fs::create_dir_all(dir.path().join("Playlists")).unwrap();
+        for name in ["03 晴天.flac", "01 以父之名.flac"] {
+            fs::write(album.join(name), b"not audio").unwrap();
+        }
+        (dir, album)
+    }
+
+    fn import(path: &Path) -> ImportedPlaylist {
+        import_playlist_internal(&path.to_string_lossy(), &MetadataOptions::default()).unwrap()
+    }
+
+    fn extinf(entry: &PlaylistEntry) -> (Option<&str>, Option<&str>, Option<f64>) {
+        (entry.artist.as_deref(), entry.title.as_deref(), entry.duration)
+    }
+
+    #[test]
+    fn parses_extinf_lines() {
+        let parsed = parse_m3u(FOOBAR2000_M3U8);
+        assert_eq!(parsed.entries.len(), 3);
+        assert_eq!(extinf(&parsed.entries[0]), (Some("周杰伦"), Some("晴天"), Some(269.0)));
+        assert_eq!(parsed.entries[0].location, "..\\Music\\周杰伦\\叶惠美\\03 晴天.flac");
+
+        let parsed = parse_m3u(&VLC_M3U.replace("$URL", "file:///a.flac"));
+        assert_eq!(parsed.entries.len(), 3);
+        assert_eq!(extinf(&parsed.entries[1]), (None, Some("Hit FM"), None));
+        assert_eq!(extinf(&parsed.entries[2]), (None, Some("以父之名"), Some(339.0)));
+    }
+
+    #[test]
+    fn parses_extinf_attributes_and_playlist_name() {
+        let content = "#EXTM3U\n#PLAYLIST: 晴天合集 \n\
+            #EXTINF:-1 tvg-name=\"A, B\" group-title=\"Radio\",Artist - Live, Vol. 2\nhttp://a\n\
+            #EXTINF:180,\nb.mp3\nc.mp3\n";
+        let parsed = parse_m3u(content);
+        assert_eq!(parsed.name.as_deref(), Some("晴天合集"));
+        assert_eq!(extinf(&parsed.entries[0]), (Some("Artist"), Some("Live, Vol. 2"), None));
+        assert_eq!(extinf(&parsed.entries[1]), (None, None, Some(180.0)));
+        // 没有 #EXTINF 的条目只有位置
+        assert_eq!(extinf(&parsed.entries[2]), (None, None, None));
+        assert_eq!(parsed.entries[2].location, "c.mp3");
+    }
+
+    #[test]
+    fn imports_foobar2000_playlists_relative_to_the_playlist() {
+        let (dir, _) = library();
+        let playlists = dir.path().join("Playlists");
+        let playlist = playlists.join("foobar2000.m3u8");
+        fs::write(&playlist, FOOBAR2000_M3U8).unwrap();
+
+        let imported = import(&playlist);
+        assert_eq!(imported.format, PlaylistFormat::M3u);
+        assert_eq!(imported.encoding, "UTF-8");
+        assert_eq!(imported.playlist.name, "foobar2000");
+        let names: Vec<&str> = imported.playlist.files.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["03 晴天.flac", "01 以父之名.flac", "稻香.flac"]);
+
+        let missing = playlists.join("..").join("Music").join("周杰伦").join("魔杰座").join("稻香.flac");
+        assert_eq!(imported.missing, [missing.to_string_lossy()]);
+        // 找不到的文件保留播放列表中的信息
+        assert_eq!(imported.playlist.files[2].title.as_deref(), Some("稻香"));
+        assert_eq!(imported.playlist.files[2].duration, Some(223.0));
+    }
+
+    #[test]
+    fn imports_vlc_playlists_with_file_urls_and_streams() {
+        let (dir, album) = library();
+        let url = path_to_file_url(&album.join("03 晴天.flac").to_string_lossy());
+        let playlist = dir.path().join("Playlists").join("vlc.m3u");
+        fs::write(&playlist, VLC_M3U.replace("$URL", &url)).unwrap();
+
+        let imported = import(&playlist);
+        assert!(imported.missing.is_empty(), "{:?}", imported.missing);
+        assert_eq!(imported.streams, ["http://stream.example.com/hitfm.mp3"]);
+        let files = &imported.playlist.files;
+        assert_eq!(files.len(), 3);
+        assert_eq!(files[0].name, "03 晴天.flac");
+        assert_eq!((files[1].name.as_str(), files[1].title.as_deref()), ("Hit FM", Some("Hit FM")));
+        assert_eq!(files[2].name, "01 以父之名.flac");
+    }
+
+    #[test]
+    fn detects_gbk_musicbee_playlists_and_flags_missing_entries() {
+        let (dir, _) = library();
+        let playlist = dir.path().join("musicbee.m3u");
+        fs::write(&playlist, encode_with(MUSICBEE_M3U, "GBK", false).unwrap()).unwrap();
+
+        let imported = import(&playlist);
+        assert_eq!(imported.encoding, "GBK");
+        let files = &imported.playlist.files;
+        assert_eq!(files.len(), 3);
+        assert_eq!(files[0].name, "03 晴天.flac");
+        assert_eq!((files[1].artist.as_deref(), files[1].title.as_deref()), (Some("周杰伦"), Some("七里香")));
+        assert_eq!(files[1].duration, Some(251.0));
+
+        let missing_relative = dir.path().join("Music").join("周杰伦").join("范特西").join("简单爱.flac");
+        let missing_absolute = "C:\\Users\\Public\\Music\\周杰伦\\七里香\\七里香.flac";
+        assert_eq!(imported.missing.len(), 2, "{:?}", imported.missing);
+        assert!(imported.missing.iter().any(|path| path == missing_absolute));
+        assert!(imported.missing.iter().any(|path| Path::new(path) == missing_relative));
+    }
+
+    #[test]
+    fn resolves_relative_absolute_and_stream_locations() {
+        let base = Path::new("/music/playlists");
+        assert_eq!(resolve_location(base, "./a/b.flac"), EntryLocation::File(base.join("a").join("b.flac")));
+        assert_eq!(resolve_location(base, "a\\\\b.flac"), EntryLocation::File(base.join("a").join("b.flac")));
+        for absolute in ["D:\\Music\\b.flac", "\\\\nas\\music\\b.flac", "/srv/music/b.flac"] {
+            assert_eq!(resolve_location(base, absolute), EntryLocation::File(PathBuf::from(absolute)));
+        }
+        assert_eq!(resolve_location(base, " rtsp://host/live "), EntryLocation::Stream("rtsp://host/live".to_string()));
+    }
+
+    fn round_trip(path: &str) -> EntryLocation {
+        resolve_location(Path::new("/unused"), &path_to_file_url(path))
+    }
+
+    #[test]
+    fn file_urls_percent_encode_cjk_as_utf8() {
+        let url = path_to_file_url("/home/用户/音乐/晴天.flac");
+        assert_eq!(url, "file:///home/%E7%94%A8%E6%88%B7/%E9%9F%B3%E4%B9%90/%E6%99%B4%E5%A4%A9.flac");
+        assert_eq!(round_trip("/home/用户/音乐/晴天.flac"), EntryLocation::File(PathBuf::from("/home/用户/音乐/晴天.flac")));
+
+        // 盘符中的冒号不编码，反斜杠统一为 `/`
+        let url = path_to_file_url("C:\\Music\\周杰伦\\晴天.flac");
+        assert_eq!(url, "file:///C:/Music/%E5%91%A8%E6%9D%B0%E4%BC%A6/%E6%99%B4%E5%A4%A9.flac");
+        assert_eq!(round_trip("C:\\Music\\周杰伦\\晴天.flac"), EntryLocation::File(PathBuf::from("C:/Music/周杰伦/晴天.flac")));
+
+        let url = path_to_file_url("\\\\nas\\music\\歌.flac");
+        assert_eq!(url, "file://nas/music/%E6%AD%8C.flac");
+        assert_eq!(round_trip("\\\\nas\\music\\歌.flac"), EntryLocation::File(PathBuf::from("//nas/music/歌.flac")));
+    }
+
+    #[test]
+    fn file_urls_round_trip_reserved_characters() {
+        let path = "/music/AC DC/#1 (Live) 100%?.flac";
+        let url = path_to_file_url(path);
+        assert!(url.contains("AC%20DC") && url.contains("%231") && url.contains("100%25%3F"), "{url}");
+        assert_eq!(round_trip(path), EntryLocation::File(PathBuf::from(path)));
+
+        let location = resolve_location(Path::new("/unused"), "file://localhost/home/%E7%94%A8%E6%88%B7.flac");
+        assert_eq!(location, EntryLocation::File(PathBuf::from("/home/用户.flac")));
+    }
+
+    #[test]
+    fn parses_xspf_tracks() {
+        let content = r#"<?xml version="1.0" encoding="UTF-8"?>
+<playlist version="1" xmlns="http://xspf.org/ns/0/">
+  <title>周杰伦 &amp; 朋友</title>
+  <trackList>
+    <track>
+      <location>%E5%91%A8%E6%9D%B0%E4%BC%A6/%E6%99%B4%E5%A4%A9.flac</location>
+      <title>晴天</title>
+      <creator>周杰伦</creator>
+      <album>叶惠美</album>
+      <duration>269000</duration>
+    </track>
+    <track><title>没有位置的条目被忽略</title></track>
+    <track><location>http://stream.example.com/a%20b</location></track>
+  </trackList>
+</playlist>"#;
+        let parsed = parse_xspf(content).unwrap();
+        assert_eq!(parsed.name.as_deref(), Some("周杰伦 & 朋友"));
+        assert_eq!(parsed.entries.len(), 2);
+        assert_eq!(parsed.entries[0].location, "周杰伦/晴天.flac");
+        assert_eq!(extinf(&parsed.entries[0]), (Some("周杰伦"), Some("晴天"), Some(269.0)));
+        assert_eq!(parsed.entries[0].album.as_deref(), Some("叶惠美"));
+        // 带协议的地址保持原样
+        assert_eq!(parsed.entries[1].location, "http://stream.example.com/a%20b");
+    }
+
+    #[test]
+    fn xspf_export_and_import_round_trip_cjk_paths() {
+        let (dir, album) = library();
+        let relative = album.join("03 晴天.flac").to_string_lossy().to_string();
+        let outside = TempDir::new().unwrap();
+        let absolute = outside.path().join("歌 #1.flac");
+        fs::write(&absolute, b"not audio").unwrap();
+        let absolute = absolute.to_string_lossy().to_string();
+
+        let output = dir.path().join("列表.xspf");
+        let paths = [relative, absolute];
+        let options = MetadataOptions::default();
+        export_playlist_internal(&output.to_string_lossy(), None, &paths, None, true, &options).unwrap();
+
+        let content = fs::read_to_string(&output).unwrap();
+        assert!(content.contains("<title>列表</title>"));
+        assert!(content.contains("<location>Music/%E5%91%A8%E6%9D%B0%E4%BC%A6/"), "{content}");
+        assert!(content.contains("<location>file://"));
+        assert!(!content.contains("<location>Music/周杰伦"));
+
+        let imported = import(&output);
+        assert_eq!(imported.format, PlaylistFormat::Xspf);
+        assert!(imported.missing.is_empty(), "{:?}", imported.missing);
+        let names: Vec<&str> = imported.playlist.files.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["03 晴天.flac", "歌 #1.flac"]);
+    }
+}
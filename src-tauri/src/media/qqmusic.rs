@@ -0,0 +1,188 @@
+//! QQ 音乐歌词 API
+//!
+//! 提供从 QQ 音乐搜索歌曲和获取歌词的功能，结果与网易云音乐接口的结构相同。
+//! 歌曲 ID 使用 songmid；歌词接口返回 base64 编码的 LRC 文本。
+//! 请求与网易云音乐共用 HTTP 客户端、重试策略和限流队列，错误同样以 `NeteaseError` 返回。
+
+use super::lyrics_provider::{tag_songs, LyricsProvider, ProviderFuture, ProviderSong};
+use super::netease::{fetch_json, LyricsData, NeteaseError, SearchSongResult};
+use base64::{engine::general_purpose, Engine as _};
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, ORIGIN, REFERER, USER_AGENT};
+use serde::Deserialize;
+
+const SEARCH_URL: &str = "https://c.y.qq.com/soso/fcgi-bin/client_search_cp";
+const LYRIC_URL: &str = "https://c.y.qq.com/lyric/fcgi-bin/fcg_query_lyric_new.fcg";
+/// 歌词不存在时的返回码
+const LYRIC_NOT_FOUND: i32 = -1901;
+
+/// 搜索接口响应结构
+#[derive(Debug, Deserialize)]
+struct SearchResponse {
+    code: i32,
+    data: Option<SearchData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchData {
+    song: Option<SearchSongList>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchSongList {
+    #[serde(default)]
+    list: Vec<SearchSong>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchSong {
+    mid: String,
+    name: String,
+    #[serde(default)]
+    singer: Vec<Singer>,
+    album: Option<Album>,
+    /// 时长（秒）
+    #[serde(default)]
+    interval: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct Singer {
+    #[serde(default)]
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Album {
+    #[serde(default)]
+    mid: String,
+    #[serde(default)]
+    name: String,
+}
+
+/// 歌词接口响应结构
+#[derive(Debug, Deserialize)]
+struct LyricResponse {
+    #[serde(default)]
+    retcode: i32,
+    #[serde(default)]
+    lyric: String,
+    #[serde(default)]
+    trans: String,
+}
+
+/// 构建请求头 - 搜索和歌词接口都会检查 Referer
+fn build_headers(referer: &'static str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(ACCEPT, HeaderValue::from_static("application/json, text/plain, */*"));
+    headers.insert(REFERER, HeaderValue::from_static(referer));
+    headers.insert(ORIGIN, HeaderValue::from_static("https://y.qq.com"));
+    headers.insert(
+        USER_AGENT,
+        HeaderValue::from_static(
+            "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36",
+        ),
+    );
+    headers
+}
+
+fn check_code(code: i32, what: &str) -> Result<(), NeteaseError> {
+    if code == 0 { Ok(()) } else { Err(NeteaseError::Network(format!("{what}: API code {code}"))) }
+}
+
+/// 解码 base64 编码的歌词，空字符串保持为空
+fn decode_lyric(encoded: &str, what: &str) -> Result<String, NeteaseError> {
+    if encoded.trim().is_empty() {
+        return Ok(String::new());
+    }
+    let bytes = general_purpose::STANDARD
+        .decode(encoded.trim())
+        .map_err(|e| NeteaseError::Parse(format!("{what}: invalid base64 lyric: {e}")))?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// 专辑封面地址（原图尺寸）
+fn album_cover_url(album_mid: &str) -> String {
+    format!("https://y.gtimg.cn/music/photo_new/T002R800x800M000{album_mid}.jpg")
+}
+
+/// 搜索歌曲，`offset` 按 `limit` 换算为页码
+pub async fn search_songs(keyword: &str, limit: u32, offset: u32) -> Result<Vec<SearchSongResult>, NeteaseError> {
+    let limit = limit.max(1);
+    let page = (offset / limit + 1).to_string();
+    let limit = limit.to_string();
+    let params = [
+        ("w", keyword),
+        ("p", page.as_str()),
+        ("n", limit.as_str()),
+        ("format", "json"),
+        ("new_json", "1"),
+        ("cr", "1"),
+    ];
+
+    let headers = build_headers("https://y.qq.com/");
+    let data: SearchResponse =
+        fetch_json("qqmusic search", &headers, |client| client.get(SEARCH_URL).query(&params)).await?;
+    check_code(data.code, "qqmusic search")?;
+
+    let songs = data
+        .data
+        .and_then(|d| d.song)
+        .map(|s| s.list)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|s| {
+            let album = s.album.filter(|album| !album.mid.is_empty());
+            SearchSongResult {
+                id: s.mid,
+                name: s.name,
+                artist: s.singer.iter().map(|a| a.name.clone()).collect::<Vec<_>>().join("/"),
+                album: album.as_ref().map(|a| a.name.clone()).unwrap_or_default(),
+                album_id: album.as_ref().map(|a| a.mid.clone()),
+                cover_url: album.as_ref().map(|a| album_cover_url(&a.mid)),
+                duration: s.interval * 1000,
+            }
+        })
+        .collect();
+    Ok(songs)
+}
+
+/// 获取歌词和翻译（QQ 音乐不提供罗马音）
+pub async fn get_lyrics(song_mid: &str) -> Result<LyricsData, NeteaseError> {
+    let what = format!("qqmusic lyric {song_mid}");
+    let params = [("songmid", song_mid), ("format", "json"), ("nobase64", "0"), ("g_tk", "5381")];
+
+    let headers = build_headers("https://y.qq.com/portal/player.html");
+    let data: LyricResponse = fetch_json(&what, &headers, |client| client.get(LYRIC_URL).query(&params)).await?;
+    if data.retcode == LYRIC_NOT_FOUND {
+        return Err(NeteaseError::NotFound(what));
+    }
+    check_code(data.retcode, &what)?;
+
+    Ok(LyricsData {
+        lrc: decode_lyric(&data.lyric, &what)?,
+        tlyric: decode_lyric(&data.trans, &what)?,
+        ..Default::default()
+    }
+    .classified())
+}
+
+/// QQ 音乐歌词来源
+pub struct QqMusicProvider;
+
+impl LyricsProvider for QqMusicProvider {
+    fn name(&self) -> &'static str {
+        "qqmusic"
+    }
+
+    fn is_available(&self) -> bool {
+        true
+    }
+
+    fn search<'a>(&'a self, query: &'a str, limit: u32, offset: u32) -> ProviderFuture<'a, Vec<ProviderSong>> {
+        Box::pin(async move { Ok(tag_songs(self.name(), search_songs(query, limit, offset).await?)) })
+    }
+
+    fn get_lyrics<'a>(&'a self, song_id: &'a str) -> ProviderFuture<'a, LyricsData> {
+        Box::pin(get_lyrics(song_id))
+    }
+}
@@ -0,0 +1,191 @@
+//! 评分读写模块
+//!
+//! 评分统一使用 1-5 星。MP3 写入 ID3v2 POPM 帧（Windows Media Player 的 0-255 映射），
+//! 其他格式写入 RATING 字段（0-100，每星 20 分）。
+
+use super::tag_writer::{
+    is_mp3, primary_tag_or_insert, read_id3_or_default, read_tagged_file, save_id3_atomic, save_tagged_file_atomic,
+};
+use crate::paths::long_path;
+use crate::tr;
+use id3::TagLike;
+use lofty::file::TaggedFileExt;
+use lofty::tag::{ItemKey, ItemValue, TagItem, TagType};
+use std::path::Path;
+
+/// POPM 帧中 MerPlayer 使用的邮箱标识
+const POPM_USER: &str = "MerPlayer";
+/// MP4 中存放评分的自由格式原子
+const MP4_RATING_KEY: &str = "----:com.apple.iTunes:RATING";
+
+/// 星级转换为 POPM 评分值
+const fn stars_to_popm(stars: u8) -> u8 {
+    match stars {
+        1 => 1,
+        2 => 64,
+        3 => 128,
+        4 => 196,
+        _ => 255,
+    }
+}
+
+/// POPM 评分值转换为星级，0 表示未评分
+const fn popm_to_stars(value: u8) -> Option<u8> {
+    match value {
+        0 => None,
+        1..=31 => Some(1),
+        32..=95 => Some(2),
+        96..=159 => Some(3),
+        160..=223 => Some(4),
+        _ => Some(5),
+    }
+}
+
+/// 0-100 的评分转换为星级
+fn percent_to_stars(value: &str) -> Option<u8> {
+    let value: u32 = value.trim().parse().ok()?;
+    match value {
+        0 => None,
+        // 部分软件直接写入 1-5
+        1..=5 => Some(value as u8),
+        _ => Some(value.div_ceil(20).min(5) as u8),
+    }
+}
+
+/// 各类标签中存放评分的字段
+///
+/// lofty 把 Vorbis 注释的 RATING 读作 `Popularimeter`；APE、ID3v2（TXXX）等没有对应映射的标签使用未知键，
+/// 只能通过 `insert_unchecked` 写入。
+fn rating_key(tag_type: TagType) -> ItemKey {
+    match tag_type {
+        TagType::Mp4Ilst => ItemKey::Unknown(MP4_RATING_KEY.to_string()),
+        TagType::VorbisComments => ItemKey::Popularimeter,
+        _ => ItemKey::Unknown("RATING".to_string()),
+    }
+}
+
+/// 读取音轨评分（1-5 星），未评分时返回 `None`
+#[must_use]
+pub fn read_rating(path: &str) -> Option<u8> {
+    let file_path = Path::new(path);
+    if is_mp3(file_path) {
+        let tag = id3::Tag::read_from_path(long_path(file_path)).ok()?;
+        return tag
+            .frames()
+            .filter_map(|frame| frame.content().popularimeter())
+            .find_map(|popm| popm_to_stars(popm.rating));
+    }
+
+    let tagged_file = read_tagged_file(file_path).ok()?;
+    tagged_file
+        .tags()
+        .iter()
+        .find_map(|tag| tag.get_string(&rating_key(tag.tag_type())))
+        .and_then(percent_to_stars)
+}
+
+/// 写入音轨评分
+///
+/// `stars` 为 `None` 或 0 时移除评分，大于 5 时按 5 星处理。
+pub fn write_rating(path: &str, stars: Option<u8>) -> Result<(), String> {
+    let stars = stars.filter(|s| *s > 0).map(|s| s.min(5));
+    let file_path = Path::new(path);
+    if is_mp3(file_path) {
+        write_popm(file_path, stars)
+    } else {
+        write_rating_field(file_path, stars)
+    }
+}
+
+/// 写入 MP3 的 POPM 帧，同样先写临时文件再替换
+fn write_popm(path: &Path, stars: Option<u8>) -> Result<(), String> {
+    let mut tag = read_id3_or_default(path)?;
+
+    tag.remove("POPM");
+    if let Some(stars) = stars {
+        tag.add_frame(id3::frame::Popularimeter {
+            user: POPM_USER.to_string(),
+            rating: stars_to_popm(stars),
+            counter: 0,
+        });
+    }
+
+    save_id3_atomic(&tag, path)
+}
+
+/// 写入其他格式的 RATING 字段
+fn write_rating_field(path: &Path, stars: Option<u8>) -> Result<(), String> {
+    let mut tagged_file = read_tagged_file(path)?;
+    let tag_type = tagged_file.primary_tag_type();
+    if tag_type == TagType::Id3v1 {
+        return Err(tr!("tags.ratingUnsupported"));
+    }
+    let key = rating_key(tag_type);
+    let tag = primary_tag_or_insert(&mut tagged_file);
+
+    tag.remove_key(&key);
+    if let Some(stars) = stars {
+        tag.insert_unchecked(TagItem::new(key, ItemValue::Text((u32::from(stars) * 20).to_string())));
+    }
+
+    save_tagged_file_atomic(&tagged_file, path)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lofty::config::WriteOptions;
+    use lofty::ogg::VorbisComments;
+    use lofty::tag::TagExt;
+    use std::fs;
+    use tempfile::TempDir;
+
+    /// 只有 STREAMINFO 和 PADDING 块的 FLAC（10 秒、44.1 kHz、双声道、16 位）
+    fn write_flac(dir: &TempDir) -> String {
+        let mut bytes = b"fLaC".to_vec();
+        bytes.extend([0, 0, 0, 34]);
+        bytes.extend(4096u16.to_be_bytes());
+        bytes.extend(4096u16.to_be_bytes());
+        bytes.extend([0; 6]);
+        let packed: u64 = (44_100 << 44) | (1 << 41) | (15 << 36) | 0x6_BAA8;
+        bytes.extend(packed.to_be_bytes());
+        bytes.extend([0; 16]);
+        bytes.extend([0x81, 0, 0x04, 0]);
+        bytes.extend([0; 1024]);
+        let path = dir.path().join("track.flac");
+        fs::write(&path, bytes).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn ratings_round_trip_through_flac() {
+        let dir = TempDir::new().unwrap();
+        let path = write_flac(&dir);
+        assert_eq!(read_rating(&path), None);
+
+        write_rating(&path, Some(4)).unwrap();
+        assert_eq!(read_rating(&path), Some(4));
+        write_rating(&path, Some(9)).unwrap();
+        assert_eq!(read_rating(&path), Some(5));
+
+        let tagged_file = read_tagged_file(Path::new(&path)).unwrap();
+        let tag = tagged_file.primary_tag().unwrap();
+        assert_eq!(tag.get_strings(&ItemKey::Popularimeter).collect::<Vec<_>>(), ["100"]);
+
+        write_rating(&path, None).unwrap();
+        assert_eq!(read_rating(&path), None);
+    }
+
+    /// 其他软件写入的 Vorbis RATING 字段
+    #[test]
+    fn vorbis_rating_fields_are_read() {
+        let dir = TempDir::new().unwrap();
+        let path = write_flac(&dir);
+        let mut vorbis = VorbisComments::default();
+        vorbis.push("RATING".to_string(), "60".to_string());
+        vorbis.save_to_path(&path, WriteOptions::default()).unwrap();
+
+        assert_eq!(read_rating(&path), Some(3));
+    }
+}
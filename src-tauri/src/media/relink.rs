@@ -0,0 +1,229 @@
+//! 丢失文件重新定位模块
+//!
+//! 文件夹整理后，播放列表和曲库中的旧路径会失效。这里在给定目录中为每个丢失的文件寻找候选：
+//! 先按文件名匹配，找不到时再按曲库或缓存中记录的（时长 ± 2 秒、标题、艺术家）匹配。
+//! 只有唯一候选时才给出建议，多个候选交给用户选择。
+
+use super::filesystem::{check_file_exists_internal, collect_audio_paths_filtered};
+use super::metadata::{MetadataOptions, TrackMetadata};
+use super::metadata_cache::{cached_track_metadata, invalidate, peek};
+use super::organize::{FileConflict, FileMove};
+use super::scan_filter::ScanFilter;
+use super::text::search_key;
+use crate::error::AppResult;
+use crate::library::LibraryDb;
+use crate::tasks::CancelToken;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// 按元数据匹配时允许的时长误差（秒）
+const DURATION_TOLERANCE: f64 = 2.0;
+
+/// 候选的匹配方式
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum RelinkMatch {
+    FileName,
+    Metadata,
+}
+
+/// 单个候选文件
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RelinkCandidate {
+    pub path: String,
+    /// 0 到 1 之间的置信度
+    pub confidence: f32,
+    pub matched_by: RelinkMatch,
+}
+
+/// 一个丢失文件的重新定位建议
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RelinkProposal {
+    pub missing: String,
+    /// 按置信度从高到低排列
+    pub candidates: Vec<RelinkCandidate>,
+    /// 只有唯一候选时给出，多个候选需要用户选择
+    pub suggested: Option<String>,
+}
+
+/// 应用重新定位的结果
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RelinkApplyResult {
+    pub applied: Vec<FileMove>,
+    /// 新路径不存在而跳过的映射
+    pub skipped: Vec<FileConflict>,
+}
+
+fn file_name(path: &str) -> String {
+    Path::new(&path.replace('\\', "/"))
+        .file_name()
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+fn parent_name(path: &str) -> String {
+    Path::new(&path.replace('\\', "/"))
+        .parent()
+        .and_then(Path::file_name)
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+fn extension(path: &str) -> String {
+    Path::new(path).extension().map(|ext| ext.to_string_lossy().to_lowercase()).unwrap_or_default()
+}
+
+/// 用于比较的标题（没有标题时使用文件名）
+fn title_key(track: &TrackMetadata) -> String {
+    search_key(track.title.as_deref().unwrap_or(&track.name)).trim().to_string()
+}
+
+fn artist_key(track: &TrackMetadata) -> Option<String> {
+    track.artist.as_deref().map(|artist| search_key(artist).trim().to_string()).filter(|a| !a.is_empty())
+}
+
+fn durations_match(a: &TrackMetadata, b: &TrackMetadata) -> bool {
+    matches!((a.duration, b.duration), (Some(a), Some(b)) if (a - b).abs() <= DURATION_TOLERANCE)
+}
+
+/// 按元数据比较，不匹配时返回 `None`
+///
+/// 标题和时长必须一致；艺术家一致时置信度更高，任一方缺少艺术家时不作为否定条件。
+fn metadata_score(old: &TrackMetadata, candidate: &TrackMetadata) -> Option<f32> {
+    if !durations_match(old, candidate) || title_key(old).is_empty() || title_key(old) != title_key(candidate) {
+        return None;
+    }
+    let mut score = 0.5;
+    match (artist_key(old), artist_key(candidate)) {
+        (Some(a), Some(b)) if a == b => score += 0.3,
+        (Some(_), Some(_)) => return None,
+        _ => {}
+    }
+    if extension(&old.path) == extension(&candidate.path) {
+        score += 0.1;
+    }
+    Some(score)
+}
+
+/// 丢失文件原来的元数据：优先取曲库数据库，其次取元数据缓存
+fn known_metadata(db: &LibraryDb, path: &str) -> Option<TrackMetadata> {
+    db.get_track(path)
+        .map_err(|e| eprintln!("Failed to read library track '{path}': {e}"))
+        .ok()
+        .flatten()
+        .or_else(|| peek(path))
+}
+
+/// 为丢失的文件在 `search_roots` 中寻找候选
+///
+/// `on_progress(completed, total, path)` 在读取候选文件元数据时调用。
+pub fn relink_missing_tracks_internal(
+    db: &LibraryDb,
+    missing_paths: &[String],
+    search_roots: &[String],
+    filter: &ScanFilter,
+    options: &MetadataOptions,
+    cancel: &CancelToken,
+    on_progress: impl Fn(usize, usize, &str) + Sync,
+) -> AppResult<Vec<RelinkProposal>> {
+    let files = collect_audio_paths_filtered(search_roots, filter);
+    cancel.check()?;
+
+    let mut by_name: HashMap<String, Vec<&String>> = HashMap::new();
+    for path in &files {
+        by_name.entry(file_name(path)).or_default().push(path);
+    }
+
+    let known: Vec<Option<TrackMetadata>> = missing_paths.iter().map(|path| known_metadata(db, path)).collect();
+
+    // 只有存在按文件名找不到候选、但记录了元数据的条目时，才读取全部文件的元数据
+    let needs_metadata = missing_paths
+        .iter()
+        .zip(&known)
+        .any(|(path, old)| old.is_some() && !by_name.contains_key(&file_name(path)));
+    let library: Vec<TrackMetadata> = if needs_metadata {
+        let completed = AtomicUsize::new(0);
+        let tracks = files
+            .par_iter()
+            .filter_map(|path| {
+                if cancel.is_cancelled() {
+                    return None;
+                }
+                let track = cached_track_metadata(path, options).ok();
+                on_progress(completed.fetch_add(1, Ordering::Relaxed) + 1, files.len(), path);
+                track
+            })
+            .collect();
+        cancel.check()?;
+        tracks
+    } else {
+        Vec::new()
+    };
+
+    let mut proposals = Vec::with_capacity(missing_paths.len());
+    for (missing, old) in missing_paths.iter().zip(&known) {
+        cancel.check()?;
+        let mut candidates: Vec<RelinkCandidate> = by_name
+            .get(&file_name(missing))
+            .into_iter()
+            .flatten()
+            .map(|path| {
+                let mut confidence = 0.6;
+                if parent_name(missing) == parent_name(path) {
+                    confidence += 0.2;
+                }
+                let metadata_matches = old.as_ref().is_some_and(|old| {
+                    cached_track_metadata(path, options).is_ok_and(|track| metadata_score(old, &track).is_some())
+                });
+                if metadata_matches {
+                    confidence += 0.2;
+                }
+                RelinkCandidate { path: (*path).clone(), confidence, matched_by: RelinkMatch::FileName }
+            })
+            .collect();
+
+        if candidates.is_empty() {
+            if let Some(old) = old {
+                candidates = library
+                    .iter()
+                    .filter_map(|track| {
+                        metadata_score(old, track).map(|confidence| RelinkCandidate {
+                            path: track.path.clone(),
+                            confidence,
+                            matched_by: RelinkMatch::Metadata,
+                        })
+                    })
+                    .collect();
+            }
+        }
+
+        candidates.sort_by(|a, b| b.confidence.total_cmp(&a.confidence).then_with(|| a.path.cmp(&b.path)));
+        let suggested = (candidates.len() == 1).then(|| candidates[0].path.clone());
+        proposals.push(RelinkProposal { missing: missing.clone(), candidates, suggested });
+    }
+    Ok(proposals)
+}
+
+/// 应用用户确认的映射，更新曲库数据库和元数据缓存
+///
+/// 新路径不存在的映射会被跳过。播放队列、播放统计等前端数据由调用方通过路径变更事件通知更新。
+pub fn apply_relink_internal(db: &LibraryDb, mappings: Vec<FileMove>) -> AppResult<RelinkApplyResult> {
+    let mut result = RelinkApplyResult::default();
+    for mapping in mappings {
+        if check_file_exists_internal(&mapping.to) {
+            invalidate(&mapping.from);
+            result.applied.push(mapping);
+        } else {
+            let reason = "missing".to_string();
+            result.skipped.push(FileConflict { from: mapping.from, to: Some(mapping.to), reason });
+        }
+    }
+    db.move_tracks(&result.applied)?;
+    Ok(result)
+}
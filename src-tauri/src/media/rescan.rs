@@ -0,0 +1,221 @@
+//! 增量重新扫描模块
+//!
+//! 对比文件大小和修改时间与元数据缓存，只重新读取新增或变化的文件。
+//! 缓存中有而目录中已找不到的文件视为删除；能与新文件配对（哈希或大小 + 时长一致）的视为移动。
+
+use super::filesystem::collect_audio_paths_filtered;
+use super::hashing::{HashCache, HashKind};
+use super::metadata::{MetadataOptions, TrackMetadata};
+use super::metadata_cache::{
+    cached_paths_under, invalidate, is_fresh, peek, refresh_track, TRACK_METADATA_CHANGED_EVENT,
+};
+use super::organize::{FileMove, TRACK_PATHS_CHANGED_EVENT};
+use super::scan_filter::ScanFilter;
+use super::watcher::{
+    LibraryFilesEvent, LIBRARY_FILES_ADDED_EVENT, LIBRARY_FILES_MODIFIED_EVENT, LIBRARY_FILES_REMOVED_EVENT,
+};
+use crate::error::AppResult;
+use crate::library::LibraryDb;
+use crate::system::power;
+use crate::tasks::CancelToken;
+use rayon::prelude::*;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tauri::{AppHandle, Emitter};
+
+/// 判断为同一文件时允许的时长误差（秒）
+const DURATION_TOLERANCE: f64 = 0.05;
+
+/// 增量扫描结果摘要
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RescanSummary {
+    pub added: usize,
+    pub updated: usize,
+    pub removed: usize,
+    pub unchanged: usize,
+    /// 在曲库内移动（或重命名）的文件数
+    pub moved: usize,
+}
+
+/// 增量扫描发现的变化（路径与传入目录的写法一致）
+#[derive(Debug, Default)]
+pub struct RescanChanges {
+    /// 新增的文件及其元数据
+    pub added: Vec<(String, TrackMetadata)>,
+    /// 变化的文件及其新的元数据
+    pub updated: Vec<(String, TrackMetadata)>,
+    pub removed: Vec<String>,
+    pub moved: Vec<FileMove>,
+    pub unchanged: usize,
+}
+
+impl RescanChanges {
+    #[must_use]
+    pub fn summary(&self) -> RescanSummary {
+        RescanSummary {
+            added: self.added.len(),
+            updated: self.updated.len(),
+            removed: self.removed.len(),
+            unchanged: self.unchanged,
+            moved: self.moved.len(),
+        }
+    }
+
+    /// 发送逐文件事件
+    ///
+    /// 新增和删除分别发送 `library-files-added` / `library-files-removed`，变化的文件发送
+    /// `library-files-modified` 和各自的 `track-metadata-changed`，移动的文件发送 `track-paths-changed`。
+    pub fn emit(&self, app: &AppHandle) {
+        if !self.added.is_empty() {
+            let paths = self.added.iter().map(|(path, _)| path.clone()).collect();
+            let _ = app.emit(LIBRARY_FILES_ADDED_EVENT, LibraryFilesEvent { paths });
+        }
+        if !self.removed.is_empty() {
+            let _ = app.emit(LIBRARY_FILES_REMOVED_EVENT, LibraryFilesEvent { paths: self.removed.clone() });
+        }
+        if !self.updated.is_empty() {
+            for (_, track) in &self.updated {
+                let _ = app.emit(TRACK_METADATA_CHANGED_EVENT, track);
+            }
+            let paths = self.updated.iter().map(|(path, _)| path.clone()).collect();
+            let _ = app.emit(LIBRARY_FILES_MODIFIED_EVENT, LibraryFilesEvent { paths });
+        }
+        if !self.moved.is_empty() {
+            let _ = app.emit(TRACK_PATHS_CHANGED_EVENT, &self.moved);
+        }
+    }
+
+    /// 把变化写入曲库数据库
+    pub fn apply_to(&self, db: &LibraryDb) -> AppResult<()> {
+        db.move_tracks(&self.moved)?;
+        db.remove_tracks(&self.removed)?;
+        let tracks: Vec<TrackMetadata> =
+            self.added.iter().chain(&self.updated).map(|(_, track)| track.clone()).collect();
+        db.upsert_tracks(&tracks)
+    }
+}
+
+fn same_duration(a: Option<f64>, b: Option<f64>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => (a - b).abs() < DURATION_TOLERANCE,
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+/// 为已删除的文件在新增文件中寻找移动后的位置
+///
+/// 候选文件需要大小和时长一致；旧文件有缓存的哈希时按哈希确认，否则只在唯一候选时配对。
+fn match_moved(
+    old_path: &str,
+    added: &[(String, TrackMetadata)],
+    taken: &HashSet<usize>,
+    hashes: &mut HashCache,
+    cancel: &CancelToken,
+) -> Option<usize> {
+    let old = peek(old_path)?;
+    old.file_size?;
+
+    let candidates: Vec<usize> = added
+        .iter()
+        .enumerate()
+        .filter(|(index, (_, new))| {
+            !taken.contains(index) && new.file_size == old.file_size && same_duration(new.duration, old.duration)
+        })
+        .map(|(index, _)| index)
+        .collect();
+
+    match hashes.cached(old_path, HashKind::File).map(str::to_string) {
+        Some(old_hash) => candidates.into_iter().find(|&index| {
+            !cancel.is_cancelled()
+                && hashes
+                    .get_or_compute(&added[index].0, HashKind::File, cancel)
+                    .is_ok_and(|hash| hash == old_hash)
+        }),
+        None if candidates.len() == 1 => candidates.first().copied(),
+        None => None,
+    }
+}
+
+/// 增量重新扫描目录
+///
+/// 未变化的文件直接使用缓存，新增和变化的文件在并行线程池中重新读取，每读取一个文件调用一次
+/// `on_progress(completed, total, path)`。不存在的目录（如未挂载的移动硬盘）会被跳过，
+/// 其中的文件不会被当作删除；被 `filter` 排除的文件视为已删除。
+pub fn rescan_library_internal(
+    paths: &[String],
+    options: &MetadataOptions,
+    filter: &ScanFilter,
+    hashes: &mut HashCache,
+    cancel: &CancelToken,
+    on_progress: impl Fn(usize, usize, &str) + Sync,
+) -> AppResult<RescanChanges> {
+    let roots: Vec<String> = paths.iter().filter(|path| Path::new(path).is_dir()).cloned().collect();
+    let current = collect_audio_paths_filtered(&roots, filter);
+    cancel.check()?;
+
+    let mut changes = RescanChanges::default();
+    let mut new_paths = Vec::new();
+    let mut changed_paths = Vec::new();
+    for path in &current {
+        match is_fresh(path) {
+            None => new_paths.push(path.clone()),
+            Some(false) => changed_paths.push(path.clone()),
+            Some(true) => changes.unchanged += 1,
+        }
+    }
+
+    let current_set: HashSet<&String> = current.iter().collect();
+    let mut removed: Vec<String> = roots
+        .iter()
+        .flat_map(|root| cached_paths_under(Path::new(root)))
+        .filter(|path| !current_set.contains(path))
+        .collect();
+    removed.sort();
+    removed.dedup();
+
+    let total = new_paths.len() + changed_paths.len();
+    let completed = AtomicUsize::new(0);
+    let read = |path: &String| -> Option<(String, TrackMetadata)> {
+        if cancel.is_cancelled() {
+            return None;
+        }
+        let result = refresh_track(path, options)
+            .map_err(|e| eprintln!("Failed to get metadata for file '{path}': {e}"))
+            .ok();
+        on_progress(completed.fetch_add(1, Ordering::Relaxed), total, path);
+        result.map(|metadata| (path.clone(), metadata))
+    };
+    let (added, updated): (Vec<(String, TrackMetadata)>, _) = power::run_background(|| {
+        (new_paths.par_iter().filter_map(read).collect(), changed_paths.par_iter().filter_map(read).collect())
+    });
+    changes.updated = updated;
+    cancel.check()?;
+
+    let mut taken = HashSet::new();
+    for old_path in removed {
+        cancel.check()?;
+        match match_moved(&old_path, &added, &taken, hashes, cancel) {
+            Some(index) => {
+                taken.insert(index);
+                changes.moved.push(FileMove { from: old_path.clone(), to: added[index].0.clone() });
+            }
+            None => changes.removed.push(old_path.clone()),
+        }
+        invalidate(&old_path);
+    }
+    cancel.check()?;
+
+    changes.added = added
+        .into_iter()
+        .enumerate()
+        .filter(|(index, _)| !taken.contains(index))
+        .map(|(_, entry)| entry)
+        .collect();
+
+    on_progress(total, total, "");
+    Ok(changes)
+}
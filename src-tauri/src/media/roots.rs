@@ -0,0 +1,233 @@
+//! 曲库根目录状态模块
+//!
+//! 定期检查配置的音乐目录是否可访问。目录所在的驱动器被拔出（或网络共享断开）时把根目录标记为离线并发送
+//! `library-root-offline`，其中的曲目保留在曲库中，只是标记为不可用；目录重新出现后发送 `library-root-online`，
+//! 并在后台只对该目录做一次增量重新扫描。目录监视器出错或发现目录丢失时会立即触发一次检查。
+
+use super::hashing::HashCache;
+use super::metadata::MetadataOptions;
+use super::metadata_cache::persist;
+use super::network::{is_network_path, probe_dir};
+use super::rescan::rescan_library_internal;
+use super::scan_filter::ScanFilter;
+use super::volumes::is_removable_path;
+use crate::error::AppResult;
+use crate::library::LibraryDb;
+use crate::paths::canonical_key;
+use crate::tasks::{TaskProgressEvent, TASK_PROGRESS_EVENT};
+use crate::AppState;
+use crossbeam_channel::{Receiver, Sender};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::{LazyLock, Mutex};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// 根目录离线事件名称
+pub const LIBRARY_ROOT_OFFLINE_EVENT: &str = "library-root-offline";
+/// 根目录恢复在线事件名称
+pub const LIBRARY_ROOT_ONLINE_EVENT: &str = "library-root-online";
+
+/// 根目录恢复后重新扫描的任务 ID 前缀，后接根目录路径
+pub const ROOT_RESCAN_TASK_PREFIX: &str = "rescan-root:";
+
+/// 定期检查根目录的间隔
+const ROOT_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// 音乐目录的状态
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryRootStatus {
+    pub path: String,
+    pub online: bool,
+    /// 是否位于可移动或外接的卷上
+    pub removable: bool,
+    /// 是否位于网络共享上
+    pub network: bool,
+    /// 是否已扫描进曲库
+    pub indexed: bool,
+    /// 曲库中该目录下的曲目数
+    pub track_count: usize,
+    /// 被标记为离线的时间（Unix 秒），在线或尚未标记时为 `None`
+    pub offline_since: Option<i64>,
+}
+
+/// 根目录离线或恢复事件负载
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryRootEvent {
+    pub path: String,
+    pub removable: bool,
+    /// 曲库中该目录下的曲目数
+    pub track_count: usize,
+}
+
+/// 上次检查时各根目录（`canonical_key`）是否在线
+static ROOT_ONLINE: LazyLock<Mutex<HashMap<String, bool>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// 立即检查的请求
+static CHECK_REQUESTS: LazyLock<(Sender<()>, Receiver<()>)> = LazyLock::new(crossbeam_channel::unbounded);
+
+fn is_online(root: &str) -> bool {
+    probe_dir(Path::new(root)).is_ok()
+}
+
+/// 获取各音乐目录的状态
+///
+/// 网络目录最多等待 `PROBE_TIMEOUT`；可移动卷的判断结果会被缓存，驱动器拔出后仍能报告。
+pub fn roots_status_internal(db: &LibraryDb, directories: &[String]) -> AppResult<Vec<LibraryRootStatus>> {
+    let marked: HashMap<String, i64> =
+        db.offline_roots()?.into_iter().map(|(root, since)| (canonical_key(&root), since)).collect();
+    directories
+        .iter()
+        .map(|dir| {
+            let path = Path::new(dir);
+            let online = is_online(dir);
+            Ok(LibraryRootStatus {
+                path: dir.clone(),
+                online,
+                removable: is_removable_path(path),
+                network: is_network_path(path),
+                indexed: db.is_indexed(dir)?,
+                track_count: db.count_under(dir)?,
+                offline_since: if online { None } else { marked.get(&canonical_key(dir)).copied() },
+            })
+        })
+        .collect()
+}
+
+fn root_event(db: &LibraryDb, root: &str) -> LibraryRootEvent {
+    LibraryRootEvent {
+        path: root.to_string(),
+        removable: is_removable_path(Path::new(root)),
+        track_count: db.count_under(root).unwrap_or(0),
+    }
+}
+
+fn mark_root_offline(app: &AppHandle, state: &AppState, root: &str) {
+    if let Err(e) = state.library.set_root_offline(root, true) {
+        eprintln!("Failed to mark library root '{root}' offline: {e}");
+    }
+    let _ = app.emit(LIBRARY_ROOT_OFFLINE_EVENT, root_event(&state.library, root));
+}
+
+fn mark_root_online(app: &AppHandle, state: &AppState, root: &str) {
+    let indexed = state.library.set_root_offline(root, false).unwrap_or_else(|e| {
+        eprintln!("Failed to mark library root '{root}' online: {e}");
+        false
+    });
+    let _ = app.emit(LIBRARY_ROOT_ONLINE_EVENT, root_event(&state.library, root));
+    if indexed {
+        rescan_root(app, state, root);
+    }
+}
+
+/// 在后台对恢复的根目录做一次增量重新扫描，过程中发送 `task-progress`，可通过 `cancel_task` 取消
+fn rescan_root(app: &AppHandle, state: &AppState, root: &str) {
+    let config = match state.config_manager.load_config() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Failed to rescan library root '{root}': {e}");
+            return;
+        }
+    };
+    let dirs = state
+        .config_manager
+        .get_cache_directory("hash")
+        .and_then(|hash| state.config_manager.get_cache_directory("metadata").map(|metadata| (hash, metadata)));
+    let (hash_dir, metadata_dir) = match dirs {
+        Ok(dirs) => dirs,
+        Err(e) => {
+            eprintln!("Failed to rescan library root '{root}': {e}");
+            return;
+        }
+    };
+
+    let options = MetadataOptions::from_config(&config);
+    let filter = ScanFilter::from_config(&config);
+    let app = app.clone();
+    let root = root.to_string();
+    let spawned = thread::Builder::new().name("root-rescan".to_string()).spawn(move || {
+        let state = app.state::<AppState>();
+        let task_id = format!("{ROOT_RESCAN_TASK_PREFIX}{root}");
+        let token = state.tasks.start(&task_id);
+        let mut hashes = HashCache::load(&hash_dir);
+        let on_progress = |completed: usize, total: usize, path: &str| {
+            let _ = app.emit(TASK_PROGRESS_EVENT, TaskProgressEvent {
+                task_id: task_id.clone(),
+                kind: "rescan".to_string(),
+                completed,
+                total,
+                current: (!path.is_empty()).then(|| path.to_string()),
+            });
+        };
+
+        let result =
+            rescan_library_internal(std::slice::from_ref(&root), &options, &filter, &mut hashes, &token, on_progress);
+        state.tasks.finish(&task_id, &token);
+        if let Err(e) = hashes.save() {
+            eprintln!("Failed to save hash cache: {e}");
+        }
+        if let Err(e) = persist(&metadata_dir) {
+            eprintln!("Failed to save metadata cache: {e}");
+        }
+        match result {
+            Ok(changes) => {
+                if let Err(e) = changes.apply_to(&state.library) {
+                    eprintln!("Failed to update library database: {e}");
+                }
+                changes.emit(&app);
+            }
+            Err(e) => eprintln!("Failed to rescan library root '{root}': {e}"),
+        }
+    });
+    if let Err(e) = spawned {
+        eprintln!("Failed to start library root rescan: {e}");
+    }
+}
+
+/// 检查各音乐目录，在线状态变化时更新曲库并发送事件
+///
+/// 首次检查时以曲库中记录的离线标记作为之前的状态，因此程序关闭期间重新连接的驱动器也会被重新扫描。
+fn check_library_roots(app: &AppHandle) {
+    let Some(state) = app.try_state::<AppState>() else {
+        return;
+    };
+    let directories = state.config_manager.load_config().map(|config| config.music_directories).unwrap_or_default();
+    let marked: HashSet<String> =
+        state.library.offline_roots().unwrap_or_default().into_iter().map(|(root, _)| canonical_key(&root)).collect();
+
+    for root in &directories {
+        let key = canonical_key(root);
+        let online = is_online(root);
+        let previous = ROOT_ONLINE.lock().unwrap().insert(key.clone(), online);
+        match (previous.unwrap_or(!marked.contains(&key)), online) {
+            (true, false) => mark_root_offline(app, &state, root),
+            (false, true) => mark_root_online(app, &state, root),
+            _ => {}
+        }
+    }
+}
+
+/// 请求立即检查根目录，如目录监视器报告错误或发现目录丢失时
+pub fn request_root_check() {
+    let _ = CHECK_REQUESTS.0.send(());
+}
+
+/// 启动检查根目录的后台线程，启动后立即检查一次，之后每隔 `ROOT_CHECK_INTERVAL` 或收到请求时检查
+pub fn start_root_monitor(app: &AppHandle) {
+    let app = app.clone();
+    let spawned = thread::Builder::new().name("library-roots".to_string()).spawn(move || {
+        loop {
+            check_library_roots(&app);
+            let _ = CHECK_REQUESTS.1.recv_timeout(ROOT_CHECK_INTERVAL);
+            // 合并检查期间积压的请求
+            while CHECK_REQUESTS.1.try_recv().is_ok() {}
+        }
+    });
+    if let Err(e) = spawned {
+        eprintln!("Failed to start library root monitor: {e}");
+    }
+}
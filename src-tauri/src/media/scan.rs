@@ -0,0 +1,255 @@
+//! 异步目录扫描模块
+//!
+//! 扫描在后台线程中进行，命令立即返回扫描 ID。进度约每 250 ms 通过 `scan-progress` 发送，
+//! 结束后发送带播放列表的 `scan-complete`；取消时发送带部分结果的 `scan-cancelled`。
+//! 遇到符号链接循环等问题时发送 `scan-warning`，扫描继续进行。
+//! 同一时间只允许一个扫描。
+//!
+//! 完成和取消事件带有扫描报告（文件数、被排除的条目数、读取失败的文件及原因、按扩展名的统计），
+//! 最近一次的报告保存在数据目录中（last_scan_report.json），重启后仍可通过 `get_last_scan_report` 查看。
+
+use super::filesystem::{ScanFailure, ScanProgress, ScanWarning};
+use super::metadata::Playlist;
+use crate::error::{AppError, AppResult};
+use crate::tasks::{CancelToken, TaskRegistry};
+use crate::tr;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter};
+
+/// 扫描进度事件名称
+pub const SCAN_PROGRESS_EVENT: &str = "scan-progress";
+/// 扫描完成事件名称
+pub const SCAN_COMPLETE_EVENT: &str = "scan-complete";
+/// 扫描取消事件名称
+pub const SCAN_CANCELLED_EVENT: &str = "scan-cancelled";
+/// 扫描失败事件名称
+pub const SCAN_FAILED_EVENT: &str = "scan-failed";
+/// 扫描警告事件名称（如符号链接循环）
+pub const SCAN_WARNING_EVENT: &str = "scan-warning";
+
+/// 扫描任务 ID 的前缀，也用于判断是否已有扫描在运行
+pub const SCAN_TASK_PREFIX: &str = "scan-";
+
+/// 进度事件的发送间隔
+const SCAN_PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// 扫描报告文件名
+const REPORT_FILE: &str = "last_scan_report.json";
+
+static NEXT_SCAN_ID: AtomicU64 = AtomicU64::new(1);
+
+/// 保存扫描报告的目录，启动时设置，未设置时不保存
+static REPORT_DIR: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// 最近一次扫描的报告，未扫描过时从磁盘读取
+static LAST_REPORT: Mutex<Option<ScanReport>> = Mutex::new(None);
+
+/// 扫描进度事件负载
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanProgressEvent {
+    pub scan_id: String,
+    /// 已发现的音频文件数
+    pub discovered: usize,
+    /// 已读取元数据的文件数
+    pub processed: usize,
+    pub current: Option<String>,
+    /// 已用时间（毫秒）
+    pub elapsed_ms: u64,
+}
+
+/// 扫描报告
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanReport {
+    pub scan_id: String,
+    /// 结束时间（Unix 秒）
+    pub finished_at: i64,
+    pub elapsed_ms: u64,
+    /// 扫描是否被取消（取消时只包含已处理部分的统计）
+    pub cancelled: bool,
+    /// 遍历到的文件总数（包括非音频文件）
+    pub files_seen: usize,
+    /// 发现的音频文件数
+    pub audio_files: usize,
+    /// 成功读取并加入结果的曲目数
+    pub added: usize,
+    /// 被排除规则或隐藏文件规则跳过的文件和目录数
+    pub excluded: usize,
+    /// 无法读取元数据的文件及原因
+    pub failed: Vec<ScanFailure>,
+    /// 扩展名（小写）-> 音频文件数
+    pub formats: BTreeMap<String, usize>,
+}
+
+/// 扫描完成或取消事件负载
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanResultEvent {
+    pub scan_id: String,
+    pub playlists: Vec<Playlist>,
+    pub elapsed_ms: u64,
+    pub report: ScanReport,
+}
+
+/// 扫描失败事件负载
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanFailedEvent {
+    pub scan_id: String,
+    pub message: String,
+}
+
+/// 扫描警告事件负载，同步扫描命令发送时 `scan_id` 为空
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanWarningEvent {
+    pub scan_id: Option<String>,
+    pub path: String,
+    pub message: String,
+}
+
+/// 发送扫描过程中积累的警告
+pub fn emit_warnings(app: &AppHandle, scan_id: Option<&str>, progress: &ScanProgress) {
+    for ScanWarning { path, message } in progress.take_warnings() {
+        let _ = app.emit(SCAN_WARNING_EVENT, ScanWarningEvent {
+            scan_id: scan_id.map(str::to_string),
+            path,
+            message,
+        });
+    }
+}
+
+fn elapsed_ms(started: Instant) -> u64 {
+    u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX)
+}
+
+/// 设置保存扫描报告的目录
+pub fn set_report_directory(dir: PathBuf) {
+    *REPORT_DIR.write().unwrap() = Some(dir);
+}
+
+fn build_report(
+    scan_id: &str,
+    progress: &ScanProgress,
+    playlists: &[Playlist],
+    started: Instant,
+    cancelled: bool,
+) -> ScanReport {
+    ScanReport {
+        scan_id: scan_id.to_string(),
+        finished_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| i64::try_from(d.as_secs()).unwrap_or(i64::MAX)),
+        elapsed_ms: elapsed_ms(started),
+        cancelled,
+        files_seen: progress.files_seen(),
+        audio_files: progress.discovered(),
+        added: playlists.iter().map(Playlist::track_count).sum(),
+        excluded: progress.excluded(),
+        failed: progress.failures(),
+        formats: progress.formats(),
+    }
+}
+
+/// 记录最近一次的扫描报告并保存到磁盘，保存失败时只记录日志
+fn save_report(report: &ScanReport) {
+    *LAST_REPORT.lock().unwrap() = Some(report.clone());
+    let Some(dir) = REPORT_DIR.read().unwrap().clone() else {
+        return;
+    };
+    let temp_path = dir.join(format!("{REPORT_FILE}.tmp"));
+    let saved = serde_json::to_string(report).map_err(|e| e.to_string()).and_then(|content| {
+        fs::write(&temp_path, content)
+            .and_then(|()| fs::rename(&temp_path, dir.join(REPORT_FILE)))
+            .map_err(|e| e.to_string())
+    });
+    if let Err(e) = saved {
+        eprintln!("Failed to save scan report: {e}");
+    }
+}
+
+/// 最近一次扫描的报告，本次运行还没有扫描时读取上次保存的报告
+#[must_use]
+pub fn last_scan_report() -> Option<ScanReport> {
+    let mut last = LAST_REPORT.lock().unwrap();
+    if last.is_none() {
+        let dir = REPORT_DIR.read().unwrap().clone()?;
+        *last = fs::read_to_string(dir.join(REPORT_FILE))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok());
+    }
+    last.clone()
+}
+
+fn emit_progress(app: &AppHandle, scan_id: &str, progress: &ScanProgress, started: Instant) {
+    emit_warnings(app, Some(scan_id), progress);
+    let _ = app.emit(SCAN_PROGRESS_EVENT, ScanProgressEvent {
+        scan_id: scan_id.to_string(),
+        discovered: progress.discovered(),
+        processed: progress.processed(),
+        current: progress.current(),
+        elapsed_ms: elapsed_ms(started),
+    });
+}
+
+/// 在后台启动扫描并返回扫描 ID
+///
+/// 已有扫描在运行时返回错误。`scan` 在阻塞线程池中执行，需要定期检查取消令牌，
+/// 取消后返回已得到的部分结果。
+pub fn start_scan<F>(app: AppHandle, tasks: Arc<TaskRegistry>, scan: F) -> AppResult<String>
+where
+    F: FnOnce(&ScanProgress, &CancelToken) -> Result<Vec<Playlist>, String> + Send + 'static,
+{
+    let scan_id = format!("{SCAN_TASK_PREFIX}{}", NEXT_SCAN_ID.fetch_add(1, Ordering::Relaxed));
+    let token = tasks
+        .start_exclusive(&scan_id, SCAN_TASK_PREFIX)
+        .ok_or_else(|| AppError::Other(tr!("scan.alreadyRunning")))?;
+
+    let id = scan_id.clone();
+    tauri::async_runtime::spawn(async move {
+        let started = Instant::now();
+        let progress = Arc::new(ScanProgress::default());
+        let mut job = {
+            let progress = Arc::clone(&progress);
+            let token = token.clone();
+            tauri::async_runtime::spawn_blocking(move || scan(&progress, &token))
+        };
+
+        let mut ticker = tokio::time::interval(SCAN_PROGRESS_INTERVAL);
+        let result = loop {
+            tokio::select! {
+                result = &mut job => break result,
+                _ = ticker.tick() => emit_progress(&app, &id, &progress, started),
+            }
+        };
+        tasks.finish(&id, &token);
+        emit_progress(&app, &id, &progress, started);
+
+        match result {
+            Ok(Ok(playlists)) => {
+                let cancelled = token.is_cancelled();
+                let report = build_report(&id, &progress, &playlists, started, cancelled);
+                save_report(&report);
+                let event = if cancelled { SCAN_CANCELLED_EVENT } else { SCAN_COMPLETE_EVENT };
+                let elapsed_ms = report.elapsed_ms;
+                let _ = app.emit(event, ScanResultEvent { scan_id: id, playlists, elapsed_ms, report });
+            }
+            Ok(Err(message)) => {
+                let _ = app.emit(SCAN_FAILED_EVENT, ScanFailedEvent { scan_id: id, message });
+            }
+            Err(e) => {
+                let message = tr!("tasks.crashed", detail = e);
+                let _ = app.emit(SCAN_FAILED_EVENT, ScanFailedEvent { scan_id: id, message });
+            }
+        }
+    });
+
+    Ok(scan_id)
+}
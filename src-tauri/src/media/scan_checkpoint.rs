@@ -0,0 +1,172 @@
+//! 扫描检查点模块
+//!
+//! 后台扫描曲库时定期把进度写入数据目录（scan_checkpoint.json），同时保存元数据缓存。
+//! 扫描被中断（如关闭程序）后，下次可通过 `resume_pending_scan` 继续：重新遍历时已缓存且未变化的
+//! 文件直接使用缓存，只有尚未读取的文件需要解析。扫描完成或取消后检查点被删除；
+//! 扫描目录或过滤规则变化后旧的检查点失效。
+
+use super::filesystem::ScanProgress;
+use super::metadata_cache::persist;
+use crate::config::AppConfig;
+use crate::paths::canonical_key;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::RwLock;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// 检查点文件名
+const CHECKPOINT_FILE: &str = "scan_checkpoint.json";
+
+/// 写入检查点的间隔
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// 保存检查点的目录，启动时设置，未设置时不保存
+static CHECKPOINT_DIR: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// 未完成的扫描
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanCheckpoint {
+    /// 扫描的目录
+    pub roots: Vec<String>,
+    pub include_covers: bool,
+    /// 影响扫描结果的配置摘要，变化后检查点失效
+    filter_key: String,
+    /// 已完整扫描的目录
+    pub completed_roots: Vec<String>,
+    /// 已发现的音频文件数
+    pub discovered: usize,
+    /// 已读取元数据的文件数
+    pub processed: usize,
+    /// 最后写入时间（Unix 秒）
+    pub updated_at: i64,
+}
+
+/// 设置保存检查点的目录
+pub fn set_checkpoint_directory(dir: PathBuf) {
+    *CHECKPOINT_DIR.write().unwrap() = Some(dir);
+}
+
+fn checkpoint_path() -> Option<PathBuf> {
+    CHECKPOINT_DIR.read().unwrap().as_ref().map(|dir| dir.join(CHECKPOINT_FILE))
+}
+
+/// 扫描结果取决于的配置：过滤规则、扩展名和遍历方式
+fn filter_key(config: &AppConfig) -> String {
+    let scan = &config.directory_scan;
+    serde_json::json!([
+        scan.exclude_patterns,
+        scan.folder_blacklist,
+        scan.skip_hidden,
+        scan.hidden_allowlist,
+        scan.follow_symlinks,
+        scan.audio_extensions,
+        scan.scan_archives,
+        scan.enable_subdirectory_scan,
+        scan.max_depth,
+        config.playlist.folder_based_playlists,
+    ])
+    .to_string()
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| i64::try_from(d.as_secs()).unwrap_or(i64::MAX))
+}
+
+impl ScanCheckpoint {
+    /// 为即将开始的扫描创建检查点
+    #[must_use]
+    pub fn new(roots: &[String], include_covers: bool, config: &AppConfig) -> Self {
+        Self {
+            roots: roots.to_vec(),
+            include_covers,
+            filter_key: filter_key(config),
+            completed_roots: Vec::new(),
+            discovered: 0,
+            processed: 0,
+            updated_at: unix_now(),
+        }
+    }
+
+    /// 检查点是否仍适用于当前配置：扫描目录仍是配置中的音乐目录，过滤规则没有变化
+    #[must_use]
+    pub fn is_valid_for(&self, config: &AppConfig) -> bool {
+        let configured: Vec<String> = config.music_directories.iter().map(|dir| canonical_key(dir)).collect();
+        self.filter_key == filter_key(config)
+            && !self.roots.is_empty()
+            && self.roots.iter().all(|root| configured.contains(&canonical_key(root)))
+    }
+
+    fn update(&mut self, progress: &ScanProgress) {
+        self.completed_roots = progress.completed_roots();
+        self.discovered = progress.discovered();
+        self.processed = progress.processed();
+        self.updated_at = unix_now();
+    }
+
+    fn save(&self) {
+        let Some(path) = checkpoint_path() else {
+            return;
+        };
+        let temp_path = path.with_extension("json.tmp");
+        let saved = serde_json::to_string(self).map_err(|e| e.to_string()).and_then(|content| {
+            fs::write(&temp_path, content).and_then(|()| fs::rename(&temp_path, &path)).map_err(|e| e.to_string())
+        });
+        if let Err(e) = saved {
+            eprintln!("Failed to save scan checkpoint: {e}");
+        }
+    }
+}
+
+/// 读取未完成扫描的检查点
+#[must_use]
+pub fn load_checkpoint() -> Option<ScanCheckpoint> {
+    let content = fs::read_to_string(checkpoint_path()?).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// 删除检查点
+pub fn clear_checkpoint() {
+    if let Some(path) = checkpoint_path()
+        && let Err(e) = fs::remove_file(&path)
+        && e.kind() != std::io::ErrorKind::NotFound
+    {
+        eprintln!("Failed to remove scan checkpoint: {e}");
+    }
+}
+
+/// 执行 `scan`，期间每隔 `CHECKPOINT_INTERVAL` 写入检查点并保存元数据缓存
+///
+/// `scan` 返回后检查点被删除（无论完成还是取消）；`scan` 返回 `Err` 时保留检查点以便继续。
+pub fn with_checkpoints<T, E>(
+    mut checkpoint: ScanCheckpoint,
+    progress: &ScanProgress,
+    cache_dir: &Path,
+    scan: impl FnOnce() -> Result<T, E>,
+) -> Result<T, E> {
+    checkpoint.save();
+    let (stop, stopped) = mpsc::channel::<()>();
+    let result = thread::scope(|scope| {
+        scope.spawn(move || {
+            while stopped.recv_timeout(CHECKPOINT_INTERVAL) == Err(RecvTimeoutError::Timeout) {
+                if let Err(e) = persist(cache_dir) {
+                    eprintln!("Failed to save metadata cache: {e}");
+                }
+                checkpoint.update(progress);
+                checkpoint.save();
+            }
+        });
+        let result = scan();
+        drop(stop);
+        result
+    });
+    if result.is_ok() {
+        clear_checkpoint();
+    }
+    result
+}
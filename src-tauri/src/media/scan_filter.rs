@@ -0,0 +1,250 @@
+//! 扫描过滤模块
+//!
+//! 决定目录遍历时跳过哪些目录和文件。被排除的目录不会被进入（通过 `filter_entry` 剪枝），
+//! 包含 `.nomedia` 或 `.merplayer-ignore` 标记文件的目录同样整体跳过。
+//! 启用 `skip_hidden` 时还会跳过隐藏文件（点开头的名称、Windows 的隐藏/系统属性）和回收站等目录。
+
+use super::network::DEFAULT_NETWORK_TIMEOUT_SECS;
+use crate::config::AppConfig;
+use crate::tr;
+use globset::{Glob, GlobBuilder, GlobSet, GlobSetBuilder};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use walkdir::DirEntry;
+
+/// 放在目录中即可让扫描跳过该目录的标记文件
+pub const IGNORE_MARKERS: &[&str] = &[".nomedia", ".merplayer-ignore"];
+
+/// 常见的系统和垃圾目录，`skip_hidden` 启用时跳过（不区分大小写）
+pub const JUNK_DIRECTORIES: &[&str] = &[
+    "$recycle.bin",
+    "recycler",
+    "system volume information",
+    "lost+found",
+    "__macosx",
+    ".appledouble",
+    ".spotlight-v100",
+    ".fseventsd",
+    ".trashes",
+    ".trash",
+];
+
+/// Windows 文件属性：隐藏
+#[cfg(windows)]
+const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+/// Windows 文件属性：系统
+#[cfg(windows)]
+const FILE_ATTRIBUTE_SYSTEM: u32 = 0x4;
+
+/// 是否带有 Windows 隐藏或系统属性
+#[cfg(windows)]
+fn has_hidden_attribute(entry: &DirEntry) -> bool {
+    use std::os::windows::fs::MetadataExt;
+    entry
+        .metadata()
+        .is_ok_and(|metadata| metadata.file_attributes() & (FILE_ATTRIBUTE_HIDDEN | FILE_ATTRIBUTE_SYSTEM) != 0)
+}
+
+#[cfg(not(windows))]
+fn has_hidden_attribute(_entry: &DirEntry) -> bool {
+    false
+}
+
+/// 编译单个排除模式（忽略大小写，`*` 可以跨越路径分隔符）
+fn compile(pattern: &str) -> Result<Glob, String> {
+    GlobBuilder::new(&pattern.trim().replace('\\', "/"))
+        .case_insensitive(true)
+        .backslash_escape(false)
+        .build()
+        .map_err(|e| tr!("scanFilter.invalidPattern", pattern = pattern, detail = e))
+}
+
+/// 统一为 `/` 分隔，便于同一模式在各平台匹配
+fn normalized(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+/// 目录中是否有忽略标记文件
+#[must_use]
+pub fn has_ignore_marker(dir: &Path) -> bool {
+    IGNORE_MARKERS.iter().any(|marker| dir.join(marker).is_file())
+}
+
+/// 扫描过滤规则
+#[derive(Debug, Clone)]
+pub struct ScanFilter {
+    exclude: GlobSet,
+    skip_hidden: bool,
+    /// 不视为隐藏的名称（小写）
+    hidden_allowlist: Vec<String>,
+    follow_symlinks: bool,
+    /// 网络目录中单个文件操作的超时
+    network_timeout: Duration,
+    /// 把 ZIP 压缩包视为音频文件的集合
+    scan_archives: bool,
+}
+
+impl Default for ScanFilter {
+    fn default() -> Self {
+        Self {
+            exclude: GlobSet::empty(),
+            skip_hidden: true,
+            hidden_allowlist: Vec::new(),
+            follow_symlinks: false,
+            network_timeout: Duration::from_secs(DEFAULT_NETWORK_TIMEOUT_SECS),
+            scan_archives: false,
+        }
+    }
+}
+
+impl ScanFilter {
+    /// 由排除模式构建，任一模式无效时返回错误
+    pub fn new(patterns: &[String]) -> Result<Self, String> {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in patterns.iter().filter(|p| !p.trim().is_empty()) {
+            builder.add(compile(pattern)?);
+        }
+        let exclude = builder.build().map_err(|e| tr!("scanFilter.buildFailed", detail = e))?;
+        Ok(Self { exclude, ..Self::default() })
+    }
+
+    /// 根据配置构建，包括 `exclude_patterns` 和 `folder_blacklist`；无效的模式会被忽略
+    #[must_use]
+    pub fn from_config(config: &AppConfig) -> Self {
+        let scan = &config.directory_scan;
+        let mut builder = GlobSetBuilder::new();
+        for pattern in scan.exclude_patterns.iter().chain(&scan.folder_blacklist) {
+            if pattern.trim().is_empty() {
+                continue;
+            }
+            match compile(pattern) {
+                Ok(glob) => {
+                    builder.add(glob);
+                }
+                Err(e) => eprintln!("{e}"),
+            }
+        }
+        let exclude = builder.build().unwrap_or_else(|e| {
+            eprintln!("Failed to build exclude patterns: {e}");
+            GlobSet::empty()
+        });
+        Self {
+            exclude,
+            skip_hidden: scan.skip_hidden,
+            hidden_allowlist: scan.hidden_allowlist.iter().map(|name| name.trim().to_lowercase()).collect(),
+            follow_symlinks: scan.follow_symlinks,
+            network_timeout: Duration::from_secs(scan.network_timeout_secs.max(1)),
+            scan_archives: scan.scan_archives,
+        }
+    }
+
+    /// 遍历时是否进入符号链接指向的目录
+    #[must_use]
+    pub fn follow_symlinks(&self) -> bool {
+        self.follow_symlinks
+    }
+
+    /// 扫描网络目录时单个文件操作的超时
+    #[must_use]
+    pub fn network_timeout(&self) -> Duration {
+        self.network_timeout
+    }
+
+    /// 遍历时是否收集 ZIP 压缩包
+    #[must_use]
+    pub fn scan_archives(&self) -> bool {
+        self.scan_archives
+    }
+
+    /// 名称是否为隐藏文件或垃圾目录（不检查文件属性）
+    #[must_use]
+    pub fn is_hidden_name(&self, name: &str) -> bool {
+        if !self.skip_hidden {
+            return false;
+        }
+        let lower = name.to_lowercase();
+        if self.hidden_allowlist.contains(&lower) {
+            return false;
+        }
+        // macOS 资源分叉文件（._*）、.Trash-1000 等都以点开头
+        lower.starts_with('.') || JUNK_DIRECTORIES.contains(&lower.as_str())
+    }
+
+    fn is_allowlisted(&self, path: &Path) -> bool {
+        path.file_name()
+            .is_some_and(|name| self.hidden_allowlist.contains(&name.to_string_lossy().to_lowercase()))
+    }
+
+    fn is_hidden(&self, path: &Path) -> bool {
+        path.file_name().is_some_and(|name| self.is_hidden_name(&name.to_string_lossy()))
+    }
+
+    /// 路径的名称或完整路径是否匹配排除模式
+    #[must_use]
+    pub fn is_excluded(&self, path: &Path) -> bool {
+        if self.exclude.is_empty() {
+            return false;
+        }
+        path.file_name().is_some_and(|name| self.exclude.is_match(name.to_string_lossy().as_ref()))
+            || self.exclude.is_match(normalized(path))
+    }
+
+    /// 单个路径是否应被排除：路径本身或它与扫描根目录之间的任一目录被过滤，或所在目录有忽略标记
+    ///
+    /// 用于不经过目录遍历得到的路径（如监视器报告的新文件）。`roots` 为扫描根目录，
+    /// 根目录及其上级不参与判断。
+    #[must_use]
+    pub fn excludes_file(&self, roots: &[PathBuf], path: &Path) -> bool {
+        let root = roots
+            .iter()
+            .filter(|root| path.starts_with(root))
+            .max_by_key(|root| root.as_os_str().len());
+        path.ancestors()
+            .take_while(|ancestor| root.is_none_or(|root| ancestor != root))
+            .any(|ancestor| self.is_excluded(ancestor) || self.is_hidden(ancestor))
+            || path.parent().is_some_and(has_ignore_marker)
+    }
+
+    /// 遍历时是否保留该条目，用于 `WalkDir::filter_entry`
+    ///
+    /// 扫描根目录本身不受排除模式和隐藏规则影响，但有忽略标记时同样跳过。
+    #[must_use]
+    pub fn allows(&self, entry: &DirEntry) -> bool {
+        if entry.depth() > 0 {
+            if self.is_excluded(entry.path()) {
+                return false;
+            }
+            let hidden_attribute =
+                self.skip_hidden && has_hidden_attribute(entry) && !self.is_allowlisted(entry.path());
+            if self.is_hidden(entry.path()) || hidden_attribute {
+                return false;
+            }
+        }
+        !(entry.file_type().is_dir() && has_ignore_marker(entry.path()))
+    }
+}
+
+/// 排除模式预览结果
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ExcludePatternTest {
+    /// 是否会被排除
+    pub matches: bool,
+    /// 模式匹配了名称（最后一级目录或文件名）
+    pub matches_name: bool,
+    /// 模式匹配了完整路径
+    pub matches_path: bool,
+}
+
+/// 检查单个排除模式是否匹配示例路径，供设置界面实时预览
+pub fn test_exclude_pattern_internal(pattern: &str, sample_path: &str) -> Result<ExcludePatternTest, String> {
+    let matcher = compile(pattern)?.compile_matcher();
+    let sample = sample_path.replace('\\', "/");
+    let path = Path::new(&sample);
+    let matches_name = path
+        .file_name()
+        .is_some_and(|name| matcher.is_match(name.to_string_lossy().as_ref()));
+    let matches_path = matcher.is_match(normalized(path));
+    Ok(ExcludePatternTest { matches: matches_name || matches_path, matches_name, matches_path })
+}
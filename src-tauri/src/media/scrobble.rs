@@ -0,0 +1,499 @@
+//! 播放记录（scrobble）
+//!
+//! 根据播放事件统计每首音轨实际听了多久：只累计正常播放推进的位置，跳转和暂停期间不计入。
+//! 换音轨、停止或播放结束时，听过的时长达到音轨的一半或 4 分钟（取较小者）就记为一次播放，
+//! 短于 30 秒或缺少艺术家、标题的音轨不记录。开始播放时发送“正在播放”。
+//!
+//! 统计只进行一次，结果交给设置中已登录的每个服务（Last.fm、ListenBrainz）。每个服务有自己的队列文件，
+//! 播放记录先写入队列再提交，因网络问题提交失败时留在队列中，下次提交或程序启动时按批重试；
+//! 被服务拒绝的记录直接丢弃，避免一直重试。提交失败不会提示，原因可以通过 [`status`] 查询。
+
+use super::lastfm::{self, Credentials, LastfmError};
+use super::listenbrainz::{self, ListenBrainzError};
+use super::metadata::{MetadataOptions, TrackMetadata};
+use super::metadata_cache::cached_track_metadata;
+use super::tag_writer::read_tagged_file;
+use crate::audio::playback::{
+    PlaybackPositionEvent, PlaybackStateEvent, PLAYBACK_POSITION_EVENT, PLAYBACK_STATE_EVENT, TRACK_ENDED_EVENT,
+};
+use crate::config::AppConfig;
+use crate::AppState;
+use lofty::file::TaggedFileExt;
+use lofty::tag::ItemKey;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Listener, Manager};
+
+/// 短于这个时长的音轨不记录（秒）
+const MIN_DURATION_SECS: f64 = 30.0;
+/// 听过这么久就记录，不论音轨多长（秒）
+const MAX_THRESHOLD_SECS: f64 = 240.0;
+/// 两次位置更新之间超过这个差值视为跳转，不计入收听时长（秒）
+const MAX_POSITION_STEP: f32 = 1.0;
+
+/// 一次播放记录
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ScrobbleTrack {
+    pub artist: String,
+    pub title: String,
+    pub album: Option<String>,
+    pub album_artist: Option<String>,
+    pub track_number: Option<u32>,
+    pub duration_secs: u32,
+    /// 开始播放的时间（Unix 时间戳，秒）
+    pub started_at: u64,
+    /// 标签中的 MusicBrainz ID
+    #[serde(default)]
+    pub recording_mbid: Option<String>,
+    #[serde(default)]
+    pub release_mbid: Option<String>,
+    #[serde(default)]
+    pub track_mbid: Option<String>,
+    #[serde(default)]
+    pub artist_mbids: Vec<String>,
+}
+
+impl ScrobbleTrack {
+    /// 音轨信息不满足记录条件（太短、缺少艺术家或标题）时返回 `None`
+    #[must_use]
+    pub fn from_metadata(metadata: &TrackMetadata, started_at: u64) -> Option<Self> {
+        let duration = metadata.duration.filter(|duration| *duration >= MIN_DURATION_SECS)?;
+        let non_empty =
+            |value: &Option<String>| value.as_deref().map(str::trim).filter(|v| !v.is_empty()).map(str::to_string);
+        let artist = non_empty(&metadata.artist).or_else(|| {
+            let joined = metadata.artists.join(", ");
+            (!joined.trim().is_empty()).then_some(joined)
+        })?;
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let duration_secs = duration.round() as u32;
+        Some(Self {
+            artist,
+            title: non_empty(&metadata.title)?,
+            album: non_empty(&metadata.album),
+            album_artist: non_empty(&metadata.album_artist),
+            track_number: metadata.track_number,
+            duration_secs,
+            started_at,
+            recording_mbid: None,
+            release_mbid: None,
+            track_mbid: None,
+            artist_mbids: Vec::new(),
+        })
+    }
+
+    /// 从文件标签中读取 MusicBrainz ID，读取失败时保持为空
+    fn read_musicbrainz_ids(&mut self, path: &str) {
+        let Ok(file) = read_tagged_file(Path::new(path)) else { return };
+        let find = |key: &ItemKey| {
+            file.tags().iter().find_map(|tag| tag.get_string(key)).map(str::trim).filter(|id| !id.is_empty())
+        };
+        self.recording_mbid = find(&ItemKey::MusicBrainzRecordingId).map(str::to_string);
+        self.release_mbid = find(&ItemKey::MusicBrainzReleaseId).map(str::to_string);
+        self.track_mbid = find(&ItemKey::MusicBrainzTrackId).map(str::to_string);
+        // 多位艺术家可能分成多个字段，也可能写在同一个字段中用分隔符隔开
+        let artist_ids = file.tags().iter().find_map(|tag| {
+            let values: Vec<&str> = tag.get_strings(&ItemKey::MusicBrainzArtistId).collect();
+            (!values.is_empty()).then_some(values)
+        });
+        self.artist_mbids = artist_ids
+            .unwrap_or_default()
+            .iter()
+            .flat_map(|value| value.split([';', '/', ',']))
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .map(str::to_string)
+            .collect();
+    }
+
+    /// 需要听多久才记录（秒）
+    #[must_use]
+    pub fn threshold_secs(&self) -> f64 {
+        (f64::from(self.duration_secs) / 2.0).min(MAX_THRESHOLD_SECS)
+    }
+}
+
+/// 提交播放记录的服务
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Service {
+    Lastfm,
+    ListenBrainz,
+}
+
+/// 提交失败的原因
+struct Failure {
+    message: String,
+    retryable: bool,
+}
+
+impl From<LastfmError> for Failure {
+    fn from(err: LastfmError) -> Self {
+        Self { retryable: err.is_retryable(), message: err.to_string() }
+    }
+}
+
+impl From<ListenBrainzError> for Failure {
+    fn from(err: ListenBrainzError) -> Self {
+        Self { retryable: err.is_retryable(), message: err.to_string() }
+    }
+}
+
+/// Last.fm 的 API 账号和会话密钥，未登录或关闭了提交时为 `None`
+fn lastfm_session(config: &AppConfig) -> Option<(Credentials, String)> {
+    let lastfm = &config.lastfm;
+    if !lastfm.enabled || lastfm.api_key.is_empty() || lastfm.api_secret.is_empty() || lastfm.session_key.is_empty() {
+        return None;
+    }
+    let credentials = Credentials { api_key: lastfm.api_key.clone(), api_secret: lastfm.api_secret.clone() };
+    Some((credentials, lastfm.session_key.clone()))
+}
+
+/// ListenBrainz 的用户令牌，未填写或关闭了提交时为 `None`
+fn listenbrainz_token(config: &AppConfig) -> Option<String> {
+    let listenbrainz = &config.listenbrainz;
+    (listenbrainz.enabled && !listenbrainz.token.is_empty()).then(|| listenbrainz.token.clone())
+}
+
+impl Service {
+    const ALL: [Self; 2] = [Self::Lastfm, Self::ListenBrainz];
+
+    const fn index(self) -> usize {
+        self as usize
+    }
+
+    /// 队列文件名（Last.fm 沿用最初的文件名）
+    const fn queue_file(self) -> &'static str {
+        match self {
+            Self::Lastfm => "scrobble_queue.json",
+            Self::ListenBrainz => "listenbrainz_queue.json",
+        }
+    }
+
+    const fn max_batch(self) -> usize {
+        match self {
+            Self::Lastfm => lastfm::MAX_BATCH,
+            Self::ListenBrainz => listenbrainz::MAX_BATCH,
+        }
+    }
+
+    fn is_configured(self, config: &AppConfig) -> bool {
+        match self {
+            Self::Lastfm => lastfm_session(config).is_some(),
+            Self::ListenBrainz => listenbrainz_token(config).is_some(),
+        }
+    }
+
+    fn username(self, config: &AppConfig) -> Option<String> {
+        let name = match self {
+            Self::Lastfm => &config.lastfm.username,
+            Self::ListenBrainz => &config.listenbrainz.username,
+        };
+        Some(name.clone()).filter(|name| !name.is_empty())
+    }
+
+    /// 提交一批播放记录，没有登录时什么也不做
+    async fn submit(self, config: &AppConfig, tracks: &[ScrobbleTrack]) -> Result<(), Failure> {
+        match self {
+            Self::Lastfm => {
+                let Some((credentials, session_key)) = lastfm_session(config) else { return Ok(()) };
+                Ok(lastfm::scrobble(&credentials, &session_key, tracks).await?)
+            }
+            Self::ListenBrainz => {
+                let Some(token) = listenbrainz_token(config) else { return Ok(()) };
+                Ok(listenbrainz::submit_listens(&token, tracks).await?)
+            }
+        }
+    }
+
+    async fn now_playing(self, config: &AppConfig, track: &ScrobbleTrack) -> Result<(), Failure> {
+        match self {
+            Self::Lastfm => {
+                let Some((credentials, session_key)) = lastfm_session(config) else { return Ok(()) };
+                Ok(lastfm::update_now_playing(&credentials, &session_key, track).await?)
+            }
+            Self::ListenBrainz => {
+                let Some(token) = listenbrainz_token(config) else { return Ok(()) };
+                Ok(listenbrainz::playing_now(&token, track).await?)
+            }
+        }
+    }
+}
+
+/// 一个服务的提交状态
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceStatus {
+    /// 已登录且开启了提交
+    pub enabled: bool,
+    pub username: Option<String>,
+    /// 队列中等待提交的记录数
+    pub pending: usize,
+    /// 最近一次提交失败的原因，之后提交成功时清空
+    pub last_error: Option<String>,
+    /// 最近一次提交成功的时间（Unix 时间戳，秒）
+    pub last_scrobbled_at: Option<u64>,
+}
+
+/// `get_scrobble_status` 的结果
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ScrobbleStatus {
+    pub lastfm: ServiceStatus,
+    pub listenbrainz: ServiceStatus,
+}
+
+/// 正在播放的音轨
+struct Listening {
+    path: String,
+    /// 读取标签前以及不满足记录条件时为 `None`
+    track: Option<ScrobbleTrack>,
+    /// 实际听过的时长（秒）
+    listened: f32,
+    /// 上一次的播放位置，暂停和跳转后清空
+    last_position: Option<f32>,
+    is_playing: bool,
+    now_playing_sent: bool,
+}
+
+/// 一个服务等待提交的记录，与队列文件保持一致
+struct Queue {
+    path: Option<PathBuf>,
+    tracks: Vec<ScrobbleTrack>,
+    last_error: Option<String>,
+    last_scrobbled_at: Option<u64>,
+}
+
+impl Queue {
+    const EMPTY: Self = Self { path: None, tracks: Vec::new(), last_error: None, last_scrobbled_at: None };
+
+    fn save(&self) {
+        let Some(path) = &self.path else { return };
+        let result = if self.tracks.is_empty() {
+            std::fs::remove_file(path)
+                .or_else(|e| if e.kind() == std::io::ErrorKind::NotFound { Ok(()) } else { Err(e) })
+        } else {
+            serde_json::to_string(&self.tracks)
+                .map_err(std::io::Error::other)
+                .and_then(|content| std::fs::write(path, content))
+        };
+        if let Err(e) = result {
+            eprintln!("Failed to save scrobble queue: {e}");
+        }
+    }
+}
+
+static LISTENING: Mutex<Option<Listening>> = Mutex::new(None);
+static QUEUES: Mutex<[Queue; 2]> = Mutex::new([Queue::EMPTY, Queue::EMPTY]);
+/// 每个服务同一时间只进行一次提交
+static FLUSHING: LazyLock<[tokio::sync::Mutex<()>; 2]> =
+    LazyLock::new(|| [tokio::sync::Mutex::new(()), tokio::sync::Mutex::new(())]);
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default()
+}
+
+fn load_config(app: &AppHandle) -> AppConfig {
+    app.state::<AppState>().config_manager.load_config().unwrap_or_default()
+}
+
+fn load_queue(path: &Path) -> Vec<ScrobbleTrack> {
+    std::fs::read_to_string(path).ok().and_then(|content| serde_json::from_str(&content).ok()).unwrap_or_default()
+}
+
+/// 按批提交一个服务队列中的记录，遇到网络问题时停止，留待下次重试
+async fn flush(app: &AppHandle, service: Service) {
+    let _guard = FLUSHING[service.index()].lock().await;
+    let config = load_config(app);
+    if !service.is_configured(&config) {
+        return;
+    }
+    loop {
+        let batch: Vec<ScrobbleTrack> =
+            QUEUES.lock().unwrap()[service.index()].tracks.iter().take(service.max_batch()).cloned().collect();
+        if batch.is_empty() {
+            return;
+        }
+        let result = service.submit(&config, &batch).await;
+        let mut queues = QUEUES.lock().unwrap();
+        let queue = &mut queues[service.index()];
+        match result {
+            Ok(()) => {
+                queue.last_error = None;
+                queue.last_scrobbled_at = Some(now());
+            }
+            Err(failure) => {
+                if !failure.retryable {
+                    eprintln!("{service:?} rejected {} scrobbles: {}", batch.len(), failure.message);
+                }
+                queue.last_error = Some(failure.message);
+                if failure.retryable {
+                    return;
+                }
+            }
+        }
+        let submitted = batch.len().min(queue.tracks.len());
+        queue.tracks.drain(..submitted);
+        queue.save();
+    }
+}
+
+fn spawn_flush(app: &AppHandle, service: Service) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move { flush(&app, service).await });
+}
+
+fn send_now_playing(app: &AppHandle, track: ScrobbleTrack) {
+    let config = load_config(app);
+    for service in Service::ALL.into_iter().filter(|service| service.is_configured(&config)) {
+        let (config, track) = (config.clone(), track.clone());
+        tauri::async_runtime::spawn(async move {
+            if let Err(failure) = service.now_playing(&config, &track).await {
+                eprintln!("Failed to send now playing to {service:?}: {}", failure.message);
+            }
+        });
+    }
+}
+
+/// 结束当前音轨的统计，听够时间的加入各服务的队列并提交
+fn finish(app: &AppHandle, listening: Option<Listening>) {
+    let Some(Listening { track: Some(track), listened, .. }) = listening else { return };
+    if f64::from(listened) < track.threshold_secs() {
+        return;
+    }
+    let config = load_config(app);
+    let services: Vec<Service> = Service::ALL.into_iter().filter(|service| service.is_configured(&config)).collect();
+    {
+        let mut queues = QUEUES.lock().unwrap();
+        for service in &services {
+            let queue = &mut queues[service.index()];
+            queue.tracks.push(track.clone());
+            queue.save();
+        }
+    }
+    for service in services {
+        spawn_flush(app, service);
+    }
+}
+
+/// 在后台读取新音轨的标签，仍在播放该音轨时填入记录信息
+fn load_track(app: &AppHandle, path: String, started_at: u64) {
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let options = MetadataOptions::from_config(&load_config(&app));
+        let Ok(metadata) = cached_track_metadata(&path, &options) else { return };
+        let Some(mut track) = ScrobbleTrack::from_metadata(&metadata, started_at) else { return };
+        track.read_musicbrainz_ids(&path);
+        let now_playing = {
+            let mut listening = LISTENING.lock().unwrap();
+            let Some(current) = listening.as_mut().filter(|current| current.path == path) else { return };
+            current.track = Some(track.clone());
+            current.now_playing_sent = current.is_playing;
+            current.is_playing
+        };
+        if now_playing {
+            send_now_playing(&app, track);
+        }
+    });
+}
+
+fn on_state(app: &AppHandle, state: PlaybackStateEvent) {
+    let mut listening = LISTENING.lock().unwrap();
+    let same_track = listening.as_ref().zip(state.path.as_ref()).is_some_and(|(current, path)| &current.path == path);
+    if !same_track {
+        let finished = listening.take();
+        if let Some(path) = state.path {
+            *listening = Some(Listening {
+                path: path.clone(),
+                track: None,
+                listened: 0.0,
+                last_position: state.position_secs,
+                is_playing: state.is_playing,
+                now_playing_sent: false,
+            });
+            load_track(app, path, now());
+        }
+        drop(listening);
+        finish(app, finished);
+        return;
+    }
+    let Some(current) = listening.as_mut() else { return };
+    current.is_playing = state.is_playing;
+    current.last_position = if state.is_playing { state.position_secs } else { None };
+    if state.is_playing
+        && !current.now_playing_sent
+        && let Some(track) = current.track.clone()
+    {
+        current.now_playing_sent = true;
+        drop(listening);
+        send_now_playing(app, track);
+    }
+}
+
+fn on_position(position: f32) {
+    let mut listening = LISTENING.lock().unwrap();
+    let Some(current) = listening.as_mut().filter(|current| current.is_playing) else { return };
+    if let Some(last) = current.last_position {
+        let step = position - last;
+        if step > 0.0 && step <= MAX_POSITION_STEP {
+            current.listened += step;
+        }
+    }
+    current.last_position = Some(position);
+}
+
+/// 各服务当前的提交状态
+#[must_use]
+pub fn status(app: &AppHandle) -> ScrobbleStatus {
+    let config = load_config(app);
+    let queues = QUEUES.lock().unwrap();
+    let service_status = |service: Service| {
+        let queue = &queues[service.index()];
+        ServiceStatus {
+            enabled: service.is_configured(&config),
+            username: service.username(&config),
+            pending: queue.tracks.len(),
+            last_error: queue.last_error.clone(),
+            last_scrobbled_at: queue.last_scrobbled_at,
+        }
+    };
+    ScrobbleStatus { lastfm: service_status(Service::Lastfm), listenbrainz: service_status(Service::ListenBrainz) }
+}
+
+/// 登录完成后提交积压的记录
+pub fn retry(app: &AppHandle) {
+    for service in Service::ALL {
+        spawn_flush(app, service);
+    }
+}
+
+/// 启动时读取队列文件、提交积压的记录并开始监听播放事件
+pub fn start(app: &AppHandle) {
+    if let Ok(dir) = app.state::<AppState>().config_manager.get_data_directory() {
+        let mut queues = QUEUES.lock().unwrap();
+        for service in Service::ALL {
+            let path = dir.join(service.queue_file());
+            queues[service.index()].tracks = load_queue(&path);
+            queues[service.index()].path = Some(path);
+        }
+    }
+    retry(app);
+
+    let handle = app.clone();
+    app.listen(PLAYBACK_STATE_EVENT, move |event| {
+        if let Ok(state) = serde_json::from_str::<PlaybackStateEvent>(event.payload()) {
+            on_state(&handle, state);
+        }
+    });
+    app.listen(PLAYBACK_POSITION_EVENT, |event| {
+        if let Ok(position) = serde_json::from_str::<PlaybackPositionEvent>(event.payload()) {
+            on_position(position.position);
+        }
+    });
+    let handle = app.clone();
+    app.listen(TRACK_ENDED_EVENT, move |_| {
+        let finished = LISTENING.lock().unwrap().take();
+        finish(&handle, finished);
+    });
+}
@@ -0,0 +1,484 @@
+//! 音轨排序模块
+//!
+//! 优先使用排序标签（ARTISTSORT 等），缺失时去掉前导冠词后再比较。
+//! 文本按自然顺序比较：忽略大小写和变音符号，嵌入的数字按数值比较。
+
+use super::filesystem::existing_path;
+use super::metadata::TrackMetadata;
+use super::text::{grouping_key, search_key};
+use crate::config::AppConfig;
+use crate::i18n::language_code;
+use crate::paths::canonical_key;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// 排序上下文
+#[derive(Debug, Clone, Default)]
+pub struct SortContext {
+    /// 需要忽略的前导冠词
+    pub articles: Vec<String>,
+    /// 曲库记录的加入时间（Unix 秒），键为 `canonical_key` 写法的路径
+    pub added_at: HashMap<String, i64>,
+}
+
+impl SortContext {
+    /// 根据配置构建排序上下文
+    ///
+    /// 英文冠词始终生效，当前界面语言（跟随系统时为系统语言）的冠词列表会追加在后面。
+    #[must_use]
+    pub fn from_config(config: &AppConfig) -> Self {
+        let sort_articles = &config.metadata.sort_articles;
+        let mut articles = sort_articles.get("en").cloned().unwrap_or_default();
+        let language = language_code(&config.general.language);
+        if language != "en" {
+            if let Some(extra) = sort_articles.get(&language) {
+                articles.extend(extra.iter().cloned());
+            }
+        }
+        Self { articles, added_at: HashMap::new() }
+    }
+}
+
+/// 去掉字符串开头的冠词（"The Beatles" -> "Beatles"）
+///
+/// 以撇号结尾的冠词（如法语 "L'"）无需后跟空白。
+#[must_use]
+pub fn strip_leading_article<'a>(value: &'a str, articles: &[String]) -> &'a str {
+    let trimmed = value.trim_start();
+
+    for article in articles.iter().filter(|a| !a.is_empty()) {
+        let Some(prefix) = trimmed.get(..article.len()) else {
+            continue;
+        };
+        if !prefix.eq_ignore_ascii_case(article) {
+            continue;
+        }
+
+        let rest = &trimmed[article.len()..];
+        if article.ends_with('\'') {
+            if !rest.is_empty() {
+                return rest;
+            }
+        } else if rest.starts_with(char::is_whitespace) {
+            let rest = rest.trim_start();
+            if !rest.is_empty() {
+                return rest;
+            }
+        }
+    }
+
+    trimmed
+}
+
+/// 生成排序键：有排序标签时直接使用，否则去掉冠词
+fn sort_key(sort_name: Option<&str>, display_name: Option<&str>, context: &SortContext) -> String {
+    match sort_name.filter(|s| !s.trim().is_empty()) {
+        Some(sort_name) => grouping_key(sort_name),
+        None => grouping_key(strip_leading_article(display_name.unwrap_or_default(), &context.articles)),
+    }
+}
+
+/// 艺术家排序键
+#[must_use]
+pub fn artist_sort_key(track: &TrackMetadata, context: &SortContext) -> String {
+    sort_key(track.artist_sort.as_deref(), track.artist.as_deref(), context)
+}
+
+/// 专辑艺术家排序键（缺失时回退到艺术家）
+#[must_use]
+pub fn album_artist_sort_key(track: &TrackMetadata, context: &SortContext) -> String {
+    if track.album_artist_sort.is_some() || track.album_artist.is_some() {
+        sort_key(track.album_artist_sort.as_deref(), track.album_artist.as_deref(), context)
+    } else {
+        artist_sort_key(track, context)
+    }
+}
+
+/// 专辑排序键
+#[must_use]
+pub fn album_sort_key(track: &TrackMetadata, context: &SortContext) -> String {
+    sort_key(track.album_sort.as_deref(), track.album.as_deref(), context)
+}
+
+/// 排序字段
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum SortField {
+    Title,
+    /// 专辑艺术家（缺失时为艺术家），同一艺术家内按专辑、碟号和音轨号
+    Artist,
+    /// 专辑，同一专辑内按碟号和音轨号
+    Album,
+    /// 碟号和音轨号
+    TrackNumber,
+    Year,
+    Duration,
+    #[default]
+    Path,
+    /// 文件修改时间
+    DateModified,
+    /// 加入曲库的时间，取自曲库数据库；不在曲库中的文件按修改时间排序
+    DateAdded,
+}
+
+/// 排序方向
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SortDirection {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// 排序方式
+///
+/// 主键按 `direction` 排序，`secondary` 始终升序，最后按路径的自然顺序保证结果稳定。
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SortSpec {
+    pub field: SortField,
+    pub direction: SortDirection,
+    pub secondary: Option<SortField>,
+}
+
+impl SortSpec {
+    /// 主排序或次排序是否使用 `field`
+    #[must_use]
+    pub fn uses(self, field: SortField) -> bool {
+        self.field == field || self.secondary == Some(field)
+    }
+}
+
+/// 自然排序的比较键
+///
+/// 忽略大小写和变音符号，字符串中的数字按数值比较（"Track 2" 排在 "Track 10" 之前）。
+/// 完全相同的键再按原始字符串区分，保证全序。
+///
+/// 中日韩文字只按码位比较，不做拼音、笔画或假名读音等区域化排序；
+/// 依赖区域排序规则需要引入 ICU 排序数据，目前不在支持范围内。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NaturalKey {
+    folded: String,
+    original: String,
+}
+
+impl NaturalKey {
+    #[must_use]
+    pub fn new(value: &str) -> Self {
+        Self { folded: search_key(value), original: value.to_string() }
+    }
+}
+
+impl Ord for NaturalKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        compare_natural(&self.folded, &other.folded).then_with(|| self.original.cmp(&other.original))
+    }
+}
+
+impl PartialOrd for NaturalKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// 取出连续的数字
+fn take_digits(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> String {
+    let mut digits = String::new();
+    while let Some(c) = chars.next_if(char::is_ascii_digit) {
+        digits.push(c);
+    }
+    digits
+}
+
+/// 逐段比较：数字段按数值，其余按字符
+fn compare_natural(a: &str, b: &str) -> Ordering {
+    let mut chars_a = a.chars().peekable();
+    let mut chars_b = b.chars().peekable();
+    loop {
+        match (chars_a.peek().copied(), chars_b.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let digits_a = take_digits(&mut chars_a);
+                let digits_b = take_digits(&mut chars_b);
+                let value_a = digits_a.trim_start_matches('0');
+                let value_b = digits_b.trim_start_matches('0');
+                let ordering = value_a
+                    .len()
+                    .cmp(&value_b.len())
+                    .then_with(|| value_a.cmp(value_b))
+                    // 数值相同时，前导零少的在前（"1" < "01"）
+                    .then_with(|| digits_a.len().cmp(&digits_b.len()));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(x), Some(y)) => {
+                if x != y {
+                    return x.cmp(&y);
+                }
+                chars_a.next();
+                chars_b.next();
+            }
+        }
+    }
+}
+
+/// 自然顺序比较两个字符串
+#[must_use]
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    NaturalKey::new(a).cmp(&NaturalKey::new(b))
+}
+
+/// 单个排序字段的值
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum SortValue {
+    Number(Option<u64>),
+    Text(NaturalKey),
+}
+
+/// 带方向的排序键
+#[derive(Debug, PartialEq, Eq)]
+struct Directed {
+    values: Vec<SortValue>,
+    descending: bool,
+}
+
+impl Ord for Directed {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let ordering = self.values.cmp(&other.values);
+        if self.descending { ordering.reverse() } else { ordering }
+    }
+}
+
+impl PartialOrd for Directed {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// 文件修改时间（Unix 秒）
+///
+/// `path` 是 `TrackMetadata.path` 的写法，需要先找到磁盘上实际的路径。
+fn modified_secs(path: &str) -> Option<u64> {
+    std::fs::metadata(existing_path(path)?)
+        .ok()?
+        .modified()
+        .ok()?
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()
+        .map(|d| d.as_secs())
+}
+
+fn field_values(track: &TrackMetadata, field: SortField, context: &SortContext) -> Vec<SortValue> {
+    let number = |value: Option<u32>| SortValue::Number(value.map(u64::from));
+    match field {
+        SortField::Title => vec![SortValue::Text(NaturalKey::new(track.title.as_deref().unwrap_or(&track.name)))],
+        SortField::Artist => vec![
+            SortValue::Text(NaturalKey::new(&album_artist_sort_key(track, context))),
+            SortValue::Text(NaturalKey::new(&album_sort_key(track, context))),
+            number(track.disc_number),
+            number(track.track_number),
+        ],
+        SortField::Album => vec![
+            SortValue::Text(NaturalKey::new(&album_sort_key(track, context))),
+            number(track.disc_number),
+            number(track.track_number),
+        ],
+        SortField::TrackNumber => vec![number(track.disc_number), number(track.track_number)],
+        SortField::Year => vec![number(track.year)],
+        SortField::Duration => vec![SortValue::Number(track.duration.map(|d| (d.max(0.0) * 1000.0) as u64))],
+        SortField::Path => vec![SortValue::Text(NaturalKey::new(&track.path))],
+        SortField::DateModified => vec![SortValue::Number(modified_secs(&track.path))],
+        SortField::DateAdded => {
+            let added_at = context.added_at.get(&canonical_key(&track.path)).and_then(|&secs| u64::try_from(secs).ok());
+            vec![SortValue::Number(added_at.or_else(|| modified_secs(&track.path)))]
+        }
+    }
+}
+
+/// 按排序方式排序音轨列表
+pub fn sort_tracks(tracks: &mut [TrackMetadata], spec: &SortSpec, context: &SortContext) {
+    tracks.sort_by_cached_key(|track| {
+        (
+            Directed {
+                values: field_values(track, spec.field, context),
+                descending: spec.direction == SortDirection::Desc,
+            },
+            spec.secondary.map(|field| field_values(track, field, context)),
+            NaturalKey::new(&track.path),
+        )
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::path::Path;
+    use std::time::{Duration, UNIX_EPOCH};
+    use tempfile::TempDir;
+
+    fn track(path: &Path) -> TrackMetadata {
+        TrackMetadata {
+            path: path.to_string_lossy().replace('/', "\\"),
+            name: path.file_name().unwrap().to_string_lossy().into_owned(),
+            ..Default::default()
+        }
+    }
+
+    fn names(tracks: &[TrackMetadata]) -> Vec<&str> {
+        tracks.iter().map(|track| track.name.as_str()).collect()
+    }
+
+    fn sorted(values: &[&str]) -> Vec<String> {
+        let mut values: Vec<String> = values.iter().map(ToString::to_string).collect();
+        values.sort_by(|a, b| natural_cmp(a, b));
+        values
+    }
+
+    #[test]
+    fn numbers_compare_by_value() {
+        assert_eq!(natural_cmp("Track 2", "Track 10"), Ordering::Less);
+        assert_eq!(natural_cmp("Track 10", "Track 9"), Ordering::Greater);
+        assert_eq!(natural_cmp("Disc 1 Track 10", "Disc 2 Track 1"), Ordering::Less);
+        assert_eq!(
+            sorted(&["Track 100", "Track 10", "Track 9", "Track 1"]),
+            ["Track 1", "Track 9", "Track 10", "Track 100"]
+        );
+    }
+
+    #[test]
+    fn leading_zeros_only_break_ties() {
+        assert_eq!(natural_cmp("Track 02", "Track 3"), Ordering::Less);
+        assert_eq!(natural_cmp("Track 010", "Track 9"), Ordering::Greater);
+        // 数值相同时前导零少的在前
+        assert_eq!(natural_cmp("Track 2", "Track 02"), Ordering::Less);
+        assert_eq!(natural_cmp("0", "00"), Ordering::Less);
+        assert_eq!(
+            sorted(&["Track 10", "track 2", "Track 1a", "Track 02", "Track 1"]),
+            ["Track 1", "Track 1a", "track 2", "Track 02", "Track 10"]
+        );
+    }
+
+    #[test]
+    fn case_is_ignored_but_order_stays_total() {
+        assert_eq!(natural_cmp("abc", "ABD"), Ordering::Less);
+        assert_eq!(natural_cmp("Abc", "abc"), Ordering::Less);
+        assert_eq!(natural_cmp("abc", "abc"), Ordering::Equal);
+    }
+
+    #[test]
+    fn accents_are_ignored() {
+        // 按码位比较时 "É" 排在所有 ASCII 字母之后
+        assert_eq!(natural_cmp("Émile", "Eric"), Ordering::Less);
+        assert_eq!(natural_cmp("Ångström", "Bach"), Ordering::Less);
+        assert_eq!(natural_cmp("Beyonce", "Beyoncé"), Ordering::Less);
+        assert_eq!(sorted(&["Zoë", "Über", "Ölafur", "Apple"]), ["Apple", "Ölafur", "Über", "Zoë"]);
+    }
+
+    #[test]
+    fn nfd_and_nfc_forms_sort_together() {
+        let nfd = "Beyonce\u{301} 2";
+        assert_eq!(natural_cmp(nfd, "Beyoncé 10"), Ordering::Less);
+        assert_eq!(natural_cmp("Beyoncé 10", nfd), Ordering::Greater);
+    }
+
+    #[test]
+    fn cjk_compares_by_code_point_with_numbers_by_value() {
+        assert_eq!(natural_cmp("第2章", "第10章"), Ordering::Less);
+        assert_eq!(natural_cmp("あ", "ア"), Ordering::Less);
+        assert_eq!(natural_cmp("一", "二"), Ordering::Less);
+        assert_eq!(sorted(&["晴天 10", "晴天 2", "七里香"]), ["七里香", "晴天 2", "晴天 10"]);
+    }
+
+    #[test]
+    fn strips_leading_articles() {
+        let articles: Vec<String> = ["The", "A", "L'"].iter().map(ToString::to_string).collect();
+        assert_eq!(strip_leading_article("The Beatles", &articles), "Beatles");
+        assert_eq!(strip_leading_article("  the   Who", &articles), "Who");
+        assert_eq!(strip_leading_article("L'Arc~en~Ciel", &articles), "Arc~en~Ciel");
+        assert_eq!(strip_leading_article("Theater", &articles), "Theater");
+        assert_eq!(strip_leading_article("The", &articles), "The");
+    }
+
+    #[test]
+    fn date_modified_stats_the_file_on_disk() {
+        let dir = TempDir::new().unwrap();
+        let older = dir.path().join("older.flac");
+        let newer = dir.path().join("newer.flac");
+        for (path, secs) in [(&older, 1_000), (&newer, 2_000)] {
+            File::create(path).unwrap().set_modified(UNIX_EPOCH + Duration::from_secs(secs)).unwrap();
+        }
+
+        let mut tracks = vec![track(&newer), track(&older)];
+        let spec = SortSpec { field: SortField::DateModified, ..SortSpec::default() };
+        sort_tracks(&mut tracks, &spec, &SortContext::default());
+        assert_eq!(names(&tracks), ["older.flac", "newer.flac"]);
+
+        let spec = SortSpec { direction: SortDirection::Desc, ..spec };
+        sort_tracks(&mut tracks, &spec, &SortContext::default());
+        assert_eq!(names(&tracks), ["newer.flac", "older.flac"]);
+    }
+
+    #[test]
+    fn date_added_uses_library_times_and_falls_back_to_modified_time() {
+        let dir = TempDir::new().unwrap();
+        let paths: Vec<_> = ["a.flac", "b.flac", "c.flac"].iter().map(|name| dir.path().join(name)).collect();
+        for (path, secs) in paths.iter().zip([3_000, 1_000, 2_500]) {
+            File::create(path).unwrap().set_modified(UNIX_EPOCH + Duration::from_secs(secs)).unwrap();
+        }
+        let mut tracks: Vec<_> = paths.iter().map(|path| track(path)).collect();
+
+        // a 和 b 在曲库中，加入时间与修改时间的顺序相反；c 不在曲库中
+        let mut context = SortContext::default();
+        context.added_at.insert(canonical_key(&tracks[0].path), 1_500);
+        context.added_at.insert(canonical_key(&tracks[1].path), 2_000);
+
+        let spec = SortSpec { field: SortField::DateAdded, ..SortSpec::default() };
+        assert!(spec.uses(SortField::DateAdded));
+        sort_tracks(&mut tracks, &spec, &context);
+        assert_eq!(names(&tracks), ["a.flac", "b.flac", "c.flac"]);
+    }
+
+    #[test]
+    fn artist_order_uses_sort_tags_and_ignores_articles() {
+        let entry = |title: &str, artist: &str, album: &str, track_number: u32| TrackMetadata {
+            path: format!("C:\\Music\\{title}.flac"),
+            name: format!("{title}.flac"),
+            title: Some(title.to_string()),
+            artist: Some(artist.to_string()),
+            album: Some(album.to_string()),
+            track_number: Some(track_number),
+            ..Default::default()
+        };
+        let mut tracks = vec![
+            // ARTISTSORT 让中文艺术家按拼音排在最后
+            TrackMetadata { artist_sort: Some("Zhou Jielun".to_string()), ..entry("晴天", "周杰伦", "叶惠美", 1) },
+            // 合辑中的曲目按专辑艺术家（及其 ALBUMARTISTSORT）排序，而不是曲目艺术家
+            TrackMetadata {
+                album_artist: Some("Various Artists".to_string()),
+                album_artist_sort: Some("Compilations".to_string()),
+                ..entry("Hello", "Adele", "Now 92", 1)
+            },
+            entry("Come Together", "The Beatles", "Abbey Road", 1),
+            entry("Something", "The Beatles", "Abbey Road", 2),
+            entry("Dream On", "Aerosmith", "Aerosmith", 1),
+            entry("Let It Be", "The Beatles", "Let It Be", 6),
+            entry("Bohemian Rhapsody", "Queen", "A Night at the Opera", 11),
+        ];
+        tracks.reverse();
+
+        let context = SortContext { articles: vec!["The".to_string(), "A".to_string()], ..SortContext::default() };
+        let spec = SortSpec { field: SortField::Artist, ..SortSpec::default() };
+        sort_tracks(&mut tracks, &spec, &context);
+
+        let titles: Vec<_> = tracks.iter().map(|track| track.title.as_deref().unwrap()).collect();
+        assert_eq!(
+            titles,
+            ["Dream On", "Come Together", "Something", "Let It Be", "Hello", "Bohemian Rhapsody", "晴天"]
+        );
+    }
+}
@@ -0,0 +1,924 @@
+//! 标签写入模块
+//!
+//! 提供音频文件标签的修改功能，所有写入都先落到临时文件再原子替换。
+
+use super::custom_tags::{apply_custom_tags, write_custom_tags_id3};
+use super::encoding::reinterpret_latin1;
+use super::metadata::{get_track_metadata_internal, TrackMetadata};
+use super::rating::{read_rating, write_rating};
+use super::watcher::mark_self_write;
+use crate::paths::{long_path, same_path};
+use crate::tasks::CancelToken;
+use crate::AppState;
+use crate::tr;
+use lofty::config::WriteOptions;
+use lofty::file::{AudioFile, TaggedFile, TaggedFileExt};
+use lofty::error::{ErrorKind, LoftyError};
+use lofty::picture::{Picture, PictureType};
+use lofty::probe::Probe;
+use lofty::tag::{Accessor, ItemKey, ItemValue, Tag, TagItem, TagType};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 移除封面的结果
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoveCoverResult {
+    /// 被移除的图片数量
+    pub removed_count: usize,
+    /// 文件缩小的字节数
+    pub bytes_reclaimed: u64,
+    /// 更新后的元数据
+    pub metadata: TrackMetadata,
+}
+
+/// 标签修改内容
+///
+/// 字段为 `None` 表示不修改，为空字符串表示删除该字段。
+#[derive(Debug, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TagChanges {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub album_artist: Option<String>,
+    pub artist_sort: Option<String>,
+    pub album_artist_sort: Option<String>,
+    pub album_sort: Option<String>,
+    pub genre: Option<String>,
+    pub year: Option<String>,
+    pub track_number: Option<String>,
+    pub disc_number: Option<String>,
+    pub musicbrainz_recording_id: Option<String>,
+    pub musicbrainz_release_id: Option<String>,
+    pub musicbrainz_release_group_id: Option<String>,
+    pub musicbrainz_artist_id: Option<String>,
+    pub musicbrainz_track_id: Option<String>,
+    /// ISRC，写入前校验格式（允许连字符）
+    pub isrc: Option<String>,
+    pub label: Option<String>,
+    pub catalog_number: Option<String>,
+    /// 自定义字段（TXXX / Vorbis 注释 / MP4 自由格式），值为空字符串表示删除
+    pub custom_tags: Option<HashMap<String, String>>,
+}
+
+/// 标签写入错误
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "kind", content = "message", rename_all = "camelCase")]
+pub enum TagWriteError {
+    /// 文件不存在
+    NotFound(String),
+    /// 文件只读或没有写入权限
+    ReadOnly(String),
+    /// 文件正在播放
+    CurrentlyPlaying(String),
+    /// 格式或标签类型不支持写入
+    UnsupportedTag(String),
+    /// 磁盘空间不足（严重错误，批量写入会就此停止）
+    DiskFull(String),
+    /// 字段内容无效
+    Invalid(String),
+    /// 其他 IO 错误
+    Io(String),
+    /// 因之前的严重错误而未处理
+    Skipped(String),
+}
+
+impl fmt::Display for TagWriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound(path) => f.write_str(&tr!("tags.notFound", path = path)),
+            Self::ReadOnly(path) => f.write_str(&tr!("tags.readOnly", path = path)),
+            Self::CurrentlyPlaying(_) => f.write_str(&tr!("tags.currentlyPlaying")),
+            Self::UnsupportedTag(msg) | Self::DiskFull(msg) | Self::Invalid(msg) | Self::Io(msg) => {
+                write!(f, "{msg}")
+            }
+            Self::Skipped(path) => f.write_str(&tr!("tags.skipped", path = path)),
+        }
+    }
+}
+
+impl TagWriteError {
+    /// 是否为需要中止批量操作的严重错误
+    #[must_use]
+    pub const fn is_catastrophic(&self) -> bool {
+        matches!(self, Self::DiskFull(_))
+    }
+
+    /// `message` 是已经按当前语言生成的完整错误信息，错误类型按 `err` 区分
+    fn from_io(message: String, err: &std::io::Error) -> Self {
+        match err.kind() {
+            std::io::ErrorKind::StorageFull => Self::DiskFull(message),
+            std::io::ErrorKind::PermissionDenied | std::io::ErrorKind::ReadOnlyFilesystem => Self::ReadOnly(message),
+            std::io::ErrorKind::NotFound => Self::NotFound(message),
+            _ => Self::Io(message),
+        }
+    }
+
+    fn from_lofty(message: String, err: &LoftyError) -> Self {
+        match err.kind() {
+            ErrorKind::Io(io) => Self::from_io(message, io),
+            _ => Self::UnsupportedTag(message),
+        }
+    }
+}
+
+/// 封面修改方式
+#[derive(Debug, Clone)]
+pub enum CoverChange {
+    /// 替换正面封面
+    Set(Picture),
+    /// 移除正面封面
+    Remove,
+}
+
+/// 解析图片类型名称
+///
+/// 支持 `front` / `coverFront` 这类名称，也支持 ID3 APIC 的数字编号。
+pub fn parse_picture_type(name: &str) -> Result<PictureType, String> {
+    if let Ok(code) = name.trim().parse::<u8>() {
+        return Ok(PictureType::from_u8(code));
+    }
+
+    let normalized: String = name
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+
+    let picture_type = match normalized.as_str() {
+        "other" => PictureType::Other,
+        "icon" => PictureType::Icon,
+        "othericon" => PictureType::OtherIcon,
+        "front" | "cover" | "coverfront" => PictureType::CoverFront,
+        "back" | "coverback" => PictureType::CoverBack,
+        "leaflet" => PictureType::Leaflet,
+        "media" => PictureType::Media,
+        "leadartist" => PictureType::LeadArtist,
+        "artist" => PictureType::Artist,
+        "conductor" => PictureType::Conductor,
+        "band" => PictureType::Band,
+        "composer" => PictureType::Composer,
+        "lyricist" => PictureType::Lyricist,
+        "recordinglocation" => PictureType::RecordingLocation,
+        "duringrecording" => PictureType::DuringRecording,
+        "duringperformance" => PictureType::DuringPerformance,
+        "screencapture" => PictureType::ScreenCapture,
+        "brightfish" => PictureType::BrightFish,
+        "illustration" => PictureType::Illustration,
+        "bandlogo" => PictureType::BandLogo,
+        "publisherlogo" => PictureType::PublisherLogo,
+        _ => return Err(tr!("tags.unknownPictureType", name = name)),
+    };
+
+    Ok(picture_type)
+}
+
+/// 判断是否为 MP3 文件（按扩展名）
+#[must_use]
+pub fn is_mp3(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("mp3"))
+}
+
+/// 判断路径是否为当前正在播放的文件
+pub fn is_current_track(state: &AppState, path: &str) -> bool {
+    state
+        .player
+        .current_path
+        .lock()
+        .unwrap()
+        .as_deref()
+        .is_some_and(|current| same_path(current, path))
+}
+
+/// 读取音频文件（包含标签）
+pub fn read_tagged_file(path: &Path) -> Result<TaggedFile, String> {
+    read_tagged_file_checked(path).map_err(|e| e.to_string())
+}
+
+fn read_tagged_file_checked(path: &Path) -> Result<TaggedFile, TagWriteError> {
+    Probe::open(long_path(path))
+        .map_err(|e| TagWriteError::from_lofty(tr!("file.openFailed", detail = e), &e))?
+        .read()
+        .map_err(|e| TagWriteError::from_lofty(tr!("file.readFailed", detail = e), &e))
+}
+
+/// 获取文件中所有标签的类型
+pub fn tag_types(tagged_file: &TaggedFile) -> Vec<TagType> {
+    tagged_file.tags().iter().map(Tag::tag_type).collect()
+}
+
+/// 获取主标签，不存在时按文件格式创建
+pub fn primary_tag_or_insert(tagged_file: &mut TaggedFile) -> &mut Tag {
+    if tagged_file.primary_tag().is_none() {
+        let tag_type = tagged_file.primary_tag_type();
+        tagged_file.insert_tag(Tag::new(tag_type));
+    }
+    tagged_file.primary_tag_mut().expect("primary tag was just inserted")
+}
+
+/// 设置或删除文本字段
+fn apply_text(tag: &mut Tag, key: ItemKey, value: Option<&String>) {
+    match value.map(|v| v.trim()) {
+        None => {}
+        Some("") => tag.remove_key(&key),
+        Some(v) => {
+            tag.insert_text(key, v.to_string());
+        }
+    }
+}
+
+/// 解析数值字段（如 "3" 或 "3/12"）
+fn parse_number(field: &str, value: &str) -> Result<u32, String> {
+    value
+        .split('/')
+        .next()
+        .and_then(|n| n.trim().parse().ok())
+        .ok_or_else(|| tr!("tags.invalidNumber", field = field, value = value))
+}
+
+/// 校验并规范化 ISRC（如 "US-S1Z-99-00001" -> "USS1Z9900001"）
+///
+/// 格式为 2 位国家代码 + 3 位登记者代码 + 2 位年份 + 5 位编号。
+pub fn normalize_isrc(value: &str) -> Result<String, String> {
+    let isrc: String = value
+        .chars()
+        .filter(|c| *c != '-' && !c.is_whitespace())
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    let bytes = isrc.as_bytes();
+
+    let valid = bytes.len() == 12
+        && bytes[0..2].iter().all(u8::is_ascii_uppercase)
+        && bytes[2..5].iter().all(u8::is_ascii_alphanumeric)
+        && bytes[5..12].iter().all(u8::is_ascii_digit);
+    if valid {
+        Ok(isrc)
+    } else {
+        Err(tr!("tags.invalidIsrc", value = value))
+    }
+}
+
+/// 将修改内容应用到标签上
+pub fn apply_tag_changes(tag: &mut Tag, changes: &TagChanges) -> Result<(), String> {
+    match changes.title.as_deref().map(str::trim) {
+        None => {}
+        Some("") => tag.remove_title(),
+        Some(v) => tag.set_title(v.to_string()),
+    }
+    match changes.artist.as_deref().map(str::trim) {
+        None => {}
+        Some("") => tag.remove_artist(),
+        Some(v) => tag.set_artist(v.to_string()),
+    }
+    match changes.album.as_deref().map(str::trim) {
+        None => {}
+        Some("") => tag.remove_album(),
+        Some(v) => tag.set_album(v.to_string()),
+    }
+    match changes.genre.as_deref().map(str::trim) {
+        None => {}
+        Some("") => tag.remove_genre(),
+        Some(v) => tag.set_genre(v.to_string()),
+    }
+    match changes.year.as_deref().map(str::trim) {
+        None => {}
+        Some("") => tag.remove_year(),
+        Some(v) => tag.set_year(parse_number("year", v)?),
+    }
+    match changes.track_number.as_deref().map(str::trim) {
+        None => {}
+        Some("") => tag.remove_track(),
+        Some(v) => tag.set_track(parse_number("trackNumber", v)?),
+    }
+    match changes.disc_number.as_deref().map(str::trim) {
+        None => {}
+        Some("") => tag.remove_disk(),
+        Some(v) => tag.set_disk(parse_number("discNumber", v)?),
+    }
+
+    apply_text(tag, ItemKey::AlbumArtist, changes.album_artist.as_ref());
+    apply_text(tag, ItemKey::TrackArtistSortOrder, changes.artist_sort.as_ref());
+    apply_text(tag, ItemKey::AlbumArtistSortOrder, changes.album_artist_sort.as_ref());
+    apply_text(tag, ItemKey::AlbumTitleSortOrder, changes.album_sort.as_ref());
+    apply_text(tag, ItemKey::MusicBrainzRecordingId, changes.musicbrainz_recording_id.as_ref());
+    apply_text(tag, ItemKey::MusicBrainzReleaseId, changes.musicbrainz_release_id.as_ref());
+    apply_text(tag, ItemKey::MusicBrainzReleaseGroupId, changes.musicbrainz_release_group_id.as_ref());
+    apply_text(tag, ItemKey::MusicBrainzArtistId, changes.musicbrainz_artist_id.as_ref());
+    apply_text(tag, ItemKey::MusicBrainzTrackId, changes.musicbrainz_track_id.as_ref());
+    apply_text(tag, ItemKey::Label, changes.label.as_ref());
+    apply_text(tag, ItemKey::CatalogNumber, changes.catalog_number.as_ref());
+    match changes.isrc.as_deref().map(str::trim) {
+        None => {}
+        Some("") => tag.remove_key(&ItemKey::Isrc),
+        Some(v) => {
+            tag.insert_text(ItemKey::Isrc, normalize_isrc(v)?);
+        }
+    }
+
+    Ok(())
+}
+
+/// 应用封面修改
+fn apply_cover(tag: &mut Tag, cover: &CoverChange) {
+    tag.remove_picture_type(PictureType::CoverFront);
+    if let CoverChange::Set(picture) = cover {
+        tag.push_picture(picture.clone());
+    }
+}
+
+/// 从图片文件创建正面封面
+pub fn load_cover_picture(image_path: &str) -> Result<Picture, String> {
+    let data = fs::read(image_path).map_err(|e| tr!("tags.imageReadFailed", detail = e))?;
+    let mut picture =
+        Picture::from_reader(&mut data.as_slice()).map_err(|e| tr!("tags.imageFormatUnknown", detail = e))?;
+    picture.set_pic_type(PictureType::CoverFront);
+    Ok(picture)
+}
+
+/// 写入音轨标签（返回带类型的错误）
+pub fn write_tags_checked(
+    path: &str,
+    changes: &TagChanges,
+    cover: Option<&CoverChange>,
+) -> Result<TrackMetadata, TagWriteError> {
+    let file_path = Path::new(path);
+    let file_info = fs::metadata(long_path(file_path)).map_err(|e| TagWriteError::from_io(tr!("file.metadataFailed", detail = e), &e))?;
+    if file_info.permissions().readonly() {
+        return Err(TagWriteError::ReadOnly(path.to_string()));
+    }
+
+    // MP3 的 TXXX 由 id3 crate 写入，其他格式随主标签一起保存
+    let mp3 = is_mp3(file_path);
+    let mut tagged_file = read_tagged_file_checked(file_path)?;
+    let tag = primary_tag_or_insert(&mut tagged_file);
+    apply_tag_changes(tag, changes).map_err(TagWriteError::Invalid)?;
+    if let Some(cover) = cover {
+        apply_cover(tag, cover);
+    }
+    if let Some(custom) = changes.custom_tags.as_ref().filter(|_| !mp3) {
+        apply_custom_tags(tag, custom).map_err(TagWriteError::UnsupportedTag)?;
+    }
+    save_atomic_checked(&tagged_file, file_path, &[])?;
+
+    if let Some(custom) = changes.custom_tags.as_ref().filter(|_| mp3) {
+        write_custom_tags_id3(file_path, custom).map_err(TagWriteError::Io)?;
+    }
+
+    get_track_metadata_internal(path).map_err(TagWriteError::Io)
+}
+
+/// 写入音轨标签并返回更新后的元数据
+pub fn write_track_tags_internal(path: &str, changes: &TagChanges) -> Result<TrackMetadata, String> {
+    write_tags_checked(path, changes, None).map_err(|e| e.to_string())
+}
+
+/// 生成同目录下的临时文件路径
+pub fn temp_path_for(path: &Path) -> PathBuf {
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!(".{file_name}.merplayer-tmp"))
+}
+
+/// 原子保存标签
+///
+/// 先复制原文件到临时文件并在其上写入标签，成功后再替换原文件，
+/// 避免写入中途崩溃导致音频文件损坏。
+pub fn save_tagged_file_atomic(tagged_file: &TaggedFile, path: &Path) -> Result<(), String> {
+    save_atomic_checked(tagged_file, path, &[]).map_err(|e| e.to_string())
+}
+
+/// `removed` 中的标签类型同时从文件中删除：`TaggedFile::remove` 只移除内存中的标签，
+/// 保存时不会删除文件中已有的同类标签，需要在替换原文件之前从临时文件中删除。
+fn save_atomic_checked(tagged_file: &TaggedFile, path: &Path, removed: &[TagType]) -> Result<(), TagWriteError> {
+    // 临时文件名比原文件长，原路径接近长度上限时同样需要扩展长度形式
+    let temp_path = long_path(temp_path_for(path));
+    mark_self_write(path);
+
+    fs::copy(long_path(path), &temp_path).map_err(|e| {
+        let _ = fs::remove_file(&temp_path);
+        TagWriteError::from_io(tr!("file.tempFailed", detail = e), &e)
+    })?;
+
+    if let Err(e) = tagged_file.save_to_path(&temp_path, WriteOptions::default()) {
+        let _ = fs::remove_file(&temp_path);
+        return Err(TagWriteError::from_lofty(tr!("tags.writeFailed", detail = e), &e));
+    }
+
+    for tag_type in removed {
+        if let Err(e) = tag_type.remove_from_path(&temp_path) {
+            let _ = fs::remove_file(&temp_path);
+            return Err(TagWriteError::from_lofty(tr!("tags.removeFailed", detail = e), &e));
+        }
+    }
+
+    fs::rename(&temp_path, long_path(path)).map_err(|e| {
+        let _ = fs::remove_file(&temp_path);
+        TagWriteError::from_io(tr!("file.replaceFailed", detail = e), &e)
+    })?;
+    mark_self_write(path);
+    Ok(())
+}
+
+/// 原子保存 id3 crate 的标签
+///
+/// lofty 不支持的 ID3v2 帧（如 POPM、SYLT）通过 id3 crate 写入，同样先写临时文件再替换。
+pub fn save_id3_atomic(tag: &id3::Tag, path: &Path) -> Result<(), String> {
+    let temp_path = long_path(temp_path_for(path));
+    mark_self_write(path);
+    let result = fs::copy(long_path(path), &temp_path)
+        .map_err(|e| tr!("file.tempFailed", detail = e))
+        .and_then(|_| {
+            tag.write_to_path(&temp_path, tag.version())
+                .map_err(|e| tr!("tags.writeFailed", detail = e))
+        })
+        .and_then(|()| fs::rename(&temp_path, long_path(path)).map_err(|e| tr!("file.replaceFailed", detail = e)));
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    mark_self_write(path);
+    result
+}
+
+/// 读取 MP3 的 ID3v2 标签，没有标签时返回空标签
+pub fn read_id3_or_default(path: &Path) -> Result<id3::Tag, String> {
+    match id3::Tag::read_from_path(long_path(path)) {
+        Ok(tag) => Ok(tag),
+        Err(e) if matches!(e.kind, id3::ErrorKind::NoTag) => Ok(id3::Tag::new()),
+        Err(e) => Err(tr!("tags.id3ReadFailed", detail = e)),
+    }
+}
+
+/// 移除音频文件中嵌入的封面
+///
+/// `picture_type` 为 `None` 时移除全部图片，否则只移除指定类型。
+pub fn remove_track_cover_internal(
+    audio_path: &str,
+    picture_type: Option<PictureType>,
+) -> Result<RemoveCoverResult, String> {
+    let file_path = Path::new(audio_path);
+    let size_before = fs::metadata(long_path(file_path))
+        .map_err(|e| tr!("file.metadataFailed", detail = e))?
+        .len();
+
+    let mut tagged_file = read_tagged_file(file_path)?;
+
+    let mut removed_count = 0;
+    for tag_type in tag_types(&tagged_file) {
+        let Some(tag) = tagged_file.tag_mut(tag_type) else {
+            continue;
+        };
+
+        let before = tag.pictures().len();
+        match picture_type {
+            Some(picture_type) => tag.remove_picture_type(picture_type),
+            None => {
+                while !tag.pictures().is_empty() {
+                    tag.remove_picture(0);
+                }
+            }
+        }
+        removed_count += before - tag.pictures().len();
+    }
+
+    if removed_count > 0 {
+        save_tagged_file_atomic(&tagged_file, file_path)?;
+    }
+
+    let size_after = fs::metadata(long_path(file_path))
+        .map_err(|e| tr!("file.metadataFailed", detail = e))?
+        .len();
+
+    Ok(RemoveCoverResult {
+        removed_count,
+        bytes_reclaimed: size_before.saturating_sub(size_after),
+        metadata: get_track_metadata_internal(audio_path)?,
+    })
+}
+
+/// 修复 ID3 标签中的乱码
+///
+/// 按用户确认的编码重新解释所有 Latin-1 文本字段，写回为 UTF-8 的 ID3v2.4 标签。
+/// ID3v1 无法保存 UTF-8，修复后会被移除。
+pub fn fix_tag_encoding_internal(path: &str, encoding: &str) -> Result<TrackMetadata, String> {
+    let file_path = Path::new(path);
+    let mut tagged_file = read_tagged_file(file_path)?;
+
+    let mut tag = tagged_file
+        .tag(TagType::Id3v2)
+        .or_else(|| tagged_file.tag(TagType::Id3v1))
+        .cloned()
+        .ok_or_else(|| tr!("tags.noId3Tag"))?;
+    tag.re_map(TagType::Id3v2);
+
+    let repaired: Vec<(ItemKey, String)> = tag
+        .items()
+        .filter_map(|item| match item.value() {
+            ItemValue::Text(text) => reinterpret_latin1(text, encoding)
+                .ok()
+                .filter(|fixed| fixed != text)
+                .map(|fixed| (item.key().clone(), fixed)),
+            _ => None,
+        })
+        .collect();
+
+    if repaired.is_empty() {
+        return Err(tr!("tags.nothingToRepair", encoding = encoding));
+    }
+
+    for (key, value) in repaired {
+        tag.insert_text(key, value);
+    }
+
+    tagged_file.insert_tag(tag);
+    // 转换为 ID3v2 后删除原来的 ID3v1 标签，否则只读 ID3v1 的播放器仍会显示乱码
+    tagged_file.remove(TagType::Id3v1);
+    save_atomic_checked(&tagged_file, file_path, &[TagType::Id3v1]).map_err(|e| e.to_string())?;
+
+    get_track_metadata_internal(path)
+}
+
+/// 专辑级别的共享字段
+///
+/// 与 `TagChanges` 相同：`None` 表示不修改，空字符串表示删除。
+/// `cover` 为图片文件路径，空字符串表示移除正面封面。
+#[derive(Debug, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AlbumChanges {
+    pub album: Option<String>,
+    pub album_artist: Option<String>,
+    pub year: Option<String>,
+    pub genre: Option<String>,
+    pub disc_number: Option<String>,
+    pub cover: Option<String>,
+}
+
+impl AlbumChanges {
+    #[must_use]
+    pub fn to_tag_changes(&self) -> TagChanges {
+        TagChanges {
+            album: self.album.clone(),
+            album_artist: self.album_artist.clone(),
+            year: self.year.clone(),
+            genre: self.genre.clone(),
+            disc_number: self.disc_number.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// 解析封面修改，图片只读取一次
+    pub fn cover_change(&self) -> Result<Option<CoverChange>, String> {
+        match self.cover.as_deref().map(str::trim) {
+            None => Ok(None),
+            Some("") => Ok(Some(CoverChange::Remove)),
+            Some(image_path) => load_cover_picture(image_path).map(|p| Some(CoverChange::Set(p))),
+        }
+    }
+}
+
+/// 批量写入中单个文件的结果
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AlbumWriteResult {
+    pub path: String,
+    pub metadata: Option<TrackMetadata>,
+    pub error: Option<TagWriteError>,
+}
+
+/// 将专辑共享字段写入多个文件
+///
+/// 单个文件失败不影响其他文件；遇到磁盘空间不足等严重错误或任务被取消时停止，
+/// 剩余文件标记为 `Skipped`。`is_playing` 用于跳过正在播放的文件。
+pub fn write_album_metadata_internal(
+    paths: &[String],
+    changes: &AlbumChanges,
+    is_playing: impl Fn(&str) -> bool,
+    cancel: &CancelToken,
+    on_progress: impl FnMut(usize, &str),
+) -> Result<Vec<AlbumWriteResult>, String> {
+    let cover = changes.cover_change()?;
+    Ok(write_files_checked(paths, &changes.to_tag_changes(), cover.as_ref(), is_playing, cancel, on_progress))
+}
+
+/// 把相同的标签修改和封面写入多个文件，规则与 `write_album_metadata_internal` 相同
+pub fn write_files_checked(
+    paths: &[String],
+    tag_changes: &TagChanges,
+    cover: Option<&CoverChange>,
+    is_playing: impl Fn(&str) -> bool,
+    cancel: &CancelToken,
+    mut on_progress: impl FnMut(usize, &str),
+) -> Vec<AlbumWriteResult> {
+    let mut results = Vec::with_capacity(paths.len());
+    let mut stopped = false;
+
+    for (index, path) in paths.iter().enumerate() {
+        if stopped || cancel.is_cancelled() {
+            results.push(AlbumWriteResult {
+                path: path.clone(),
+                metadata: None,
+                error: Some(TagWriteError::Skipped(path.clone())),
+            });
+            continue;
+        }
+
+        on_progress(index, path);
+
+        let result = if is_playing(path) {
+            Err(TagWriteError::CurrentlyPlaying(path.clone()))
+        } else {
+            write_tags_checked(path, tag_changes, cover)
+        };
+
+        match result {
+            Ok(metadata) => results.push(AlbumWriteResult { path: path.clone(), metadata: Some(metadata), error: None }),
+            Err(error) => {
+                stopped = error.is_catastrophic();
+                results.push(AlbumWriteResult { path: path.clone(), metadata: None, error: Some(error) });
+            }
+        }
+    }
+
+    results
+}
+
+/// 可按名称选择复制的字段
+const COPYABLE_FIELDS: &[(&str, ItemKey)] = &[
+    ("title", ItemKey::TrackTitle),
+    ("artist", ItemKey::TrackArtist),
+    ("album", ItemKey::AlbumTitle),
+    ("albumArtist", ItemKey::AlbumArtist),
+    ("genre", ItemKey::Genre),
+    ("year", ItemKey::Year),
+    ("recordingDate", ItemKey::RecordingDate),
+    ("trackNumber", ItemKey::TrackNumber),
+    ("trackTotal", ItemKey::TrackTotal),
+    ("discNumber", ItemKey::DiscNumber),
+    ("discTotal", ItemKey::DiscTotal),
+    ("composer", ItemKey::Composer),
+    ("lyricist", ItemKey::Lyricist),
+    ("comment", ItemKey::Comment),
+    ("lyrics", ItemKey::Lyrics),
+    ("titleSort", ItemKey::TrackTitleSortOrder),
+    ("artistSort", ItemKey::TrackArtistSortOrder),
+    ("albumSort", ItemKey::AlbumTitleSortOrder),
+    ("albumArtistSort", ItemKey::AlbumArtistSortOrder),
+    ("bpm", ItemKey::Bpm),
+    ("isrc", ItemKey::Isrc),
+    ("label", ItemKey::Label),
+    ("catalogNumber", ItemKey::CatalogNumber),
+    ("musicbrainzRecordingId", ItemKey::MusicBrainzRecordingId),
+    ("musicbrainzReleaseId", ItemKey::MusicBrainzReleaseId),
+    ("musicbrainzArtistId", ItemKey::MusicBrainzArtistId),
+    ("rating", ItemKey::Popularimeter),
+];
+
+/// 图片字段名称
+const PICTURES_FIELD: &str = "pictures";
+
+/// 复制标签的结果
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CopyTagsResult {
+    /// 已复制的字段
+    pub copied: Vec<String>,
+    /// 源文件中没有的字段
+    pub skipped: Vec<String>,
+    /// 目标格式无法表示的字段
+    pub unsupported: Vec<String>,
+    /// 目标文件更新后的元数据
+    pub metadata: TrackMetadata,
+}
+
+/// 字段的显示名称
+fn field_name(key: &ItemKey) -> String {
+    COPYABLE_FIELDS
+        .iter()
+        .find(|(_, k)| k == key)
+        .map_or_else(
+            || match key {
+                ItemKey::Unknown(name) => name.clone(),
+                other => format!("{other:?}"),
+            },
+            |(name, _)| (*name).to_string(),
+        )
+}
+
+/// 将源文件的标签复制到目标文件
+///
+/// `fields` 为 `None` 时复制全部字段（包括图片、歌词和评分）。lofty 的通用标签会在
+/// ID3/Vorbis/MP4 字段名之间自动转换，目标格式没有对应字段的会列入 `unsupported`。
+/// 评分在各格式中的字段和取值范围不同（POPM、RATING），按星级读取后用 [`write_rating`] 写入。
+/// 只覆盖选中的字段，目标文件的其他字段保持不变。
+pub fn copy_tags_internal(
+    source_path: &str,
+    dest_path: &str,
+    fields: Option<&[String]>,
+) -> Result<CopyTagsResult, String> {
+    let source_file = read_tagged_file(Path::new(source_path))?;
+    let source_tag = source_file
+        .primary_tag()
+        .or_else(|| source_file.first_tag())
+        .ok_or_else(|| tr!("tags.sourceHasNoTags"))?;
+
+    let (keys, include_pictures): (Vec<ItemKey>, bool) = match fields {
+        None => {
+            let mut keys: Vec<ItemKey> = Vec::new();
+            for item in source_tag.items() {
+                if !keys.contains(item.key()) {
+                    keys.push(item.key().clone());
+                }
+            }
+            if !keys.contains(&ItemKey::Popularimeter) {
+                keys.push(ItemKey::Popularimeter);
+            }
+            (keys, true)
+        }
+        Some(fields) => {
+            let mut keys = Vec::new();
+            for field in fields {
+                if field == PICTURES_FIELD {
+                    continue;
+                }
+                let key = COPYABLE_FIELDS
+                    .iter()
+                    .find(|(name, _)| name.eq_ignore_ascii_case(field))
+                    .map(|(_, key)| key.clone())
+                    .ok_or_else(|| tr!("tags.unknownField", field = field))?;
+                keys.push(key);
+            }
+            (keys, fields.iter().any(|f| f == PICTURES_FIELD))
+        }
+    };
+
+    let dest_file_path = Path::new(dest_path);
+    let mut dest_file = read_tagged_file(dest_file_path)?;
+    let dest_tag = primary_tag_or_insert(&mut dest_file);
+    let dest_type = dest_tag.tag_type();
+
+    let mut copied = Vec::new();
+    let mut skipped = Vec::new();
+    let mut unsupported = Vec::new();
+    let mut rating = None;
+
+    for key in keys {
+        let name = field_name(&key);
+        if key == ItemKey::Popularimeter {
+            match read_rating(source_path) {
+                Some(stars) => rating = Some(stars),
+                None => skipped.push(name),
+            }
+            continue;
+        }
+        let items: Vec<TagItem> = source_tag.get_items(&key).cloned().collect();
+        if items.is_empty() {
+            skipped.push(name);
+            continue;
+        }
+        if key.map_key(dest_type, true).is_none() {
+            unsupported.push(name);
+            continue;
+        }
+
+        dest_tag.remove_key(&key);
+        let mut any_written = false;
+        for item in items {
+            any_written |= dest_tag.push(item);
+        }
+        if any_written { copied.push(name) } else { unsupported.push(name) }
+    }
+
+    if include_pictures {
+        let pictures = source_tag.pictures();
+        if pictures.is_empty() {
+            skipped.push(PICTURES_FIELD.to_string());
+        } else if dest_type == TagType::Id3v1 {
+            unsupported.push(PICTURES_FIELD.to_string());
+        } else {
+            while !dest_tag.pictures().is_empty() {
+                dest_tag.remove_picture(0);
+            }
+            for picture in pictures {
+                dest_tag.push_picture(picture.clone());
+            }
+            copied.push(PICTURES_FIELD.to_string());
+        }
+    }
+
+    if !copied.is_empty() {
+        save_tagged_file_atomic(&dest_file, dest_file_path)?;
+    }
+    if let Some(stars) = rating {
+        let name = field_name(&ItemKey::Popularimeter);
+        if write_rating(dest_path, Some(stars)).is_ok() { copied.push(name) } else { unsupported.push(name) }
+    }
+
+    Ok(CopyTagsResult {
+        copied,
+        skipped,
+        unsupported,
+        metadata: get_track_metadata_internal(dest_path)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// 按 Latin-1 存储的 GBK 字节，补齐到 ID3v1 字段的固定长度
+    fn gbk_field(text: &str, len: usize) -> Vec<u8> {
+        let (bytes, _, _) = encoding_rs::GBK.encode(text);
+        let mut field = bytes.into_owned();
+        field.resize(len, 0);
+        field
+    }
+
+    /// 只有 ID3v1 标签的 MP3：若干帧 MPEG-1 Layer III（128 kbps、44.1 kHz）加 128 字节的 ID3v1
+    fn write_id3v1_mp3(dir: &TempDir) -> PathBuf {
+        let mut frame = vec![0xFF, 0xFB, 0x90, 0x00];
+        frame.resize(417, 0);
+        let mut bytes = frame.repeat(20);
+
+        bytes.extend_from_slice(b"TAG");
+        bytes.extend(gbk_field("晴天", 30));
+        bytes.extend(gbk_field("周杰伦", 30));
+        bytes.extend(gbk_field("叶惠美", 30));
+        bytes.extend_from_slice(b"2003");
+        bytes.extend([0; 30]);
+        bytes.push(255);
+
+        let path = dir.path().join("track.mp3");
+        fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    #[test]
+    fn fix_tag_encoding_moves_text_to_id3v2_and_strips_id3v1() {
+        let dir = TempDir::new().unwrap();
+        let path = write_id3v1_mp3(&dir);
+
+        let metadata = fix_tag_encoding_internal(&path.to_string_lossy(), "GBK").unwrap();
+        assert_eq!(metadata.title.as_deref(), Some("晴天"));
+        assert_eq!(metadata.artist.as_deref(), Some("周杰伦"));
+
+        let bytes = fs::read(&path).unwrap();
+        assert!(!bytes[bytes.len() - 128..].starts_with(b"TAG"), "ID3v1 标签仍在文件末尾");
+
+        let tagged_file = read_tagged_file(&path).unwrap();
+        assert!(tagged_file.tag(TagType::Id3v1).is_none());
+        let tag = tagged_file.tag(TagType::Id3v2).unwrap();
+        assert_eq!(tag.album().as_deref(), Some("叶惠美"));
+    }
+
+    #[test]
+    fn fix_tag_encoding_rejects_text_that_is_not_mojibake() {
+        let dir = TempDir::new().unwrap();
+        let path = write_id3v1_mp3(&dir);
+        let path = path.to_string_lossy();
+        fix_tag_encoding_internal(&path, "GBK").unwrap();
+
+        // 已经修复过的标签不是 Latin-1 乱码，没有可修复的字段
+        assert!(fix_tag_encoding_internal(&path, "GBK").is_err());
+    }
+
+    /// 只有 STREAMINFO 和 PADDING 块的 FLAC（10 秒、44.1 kHz、双声道、16 位）
+    fn write_flac(dir: &TempDir) -> PathBuf {
+        let mut bytes = b"fLaC".to_vec();
+        bytes.extend([0, 0, 0, 34]);
+        bytes.extend(4096u16.to_be_bytes());
+        bytes.extend(4096u16.to_be_bytes());
+        bytes.extend([0; 6]);
+        let packed: u64 = (44_100 << 44) | (1 << 41) | (15 << 36) | 0x6_BAA8;
+        bytes.extend(packed.to_be_bytes());
+        bytes.extend([0; 16]);
+        bytes.extend([0x81, 0, 0x04, 0]);
+        bytes.extend([0; 1024]);
+        let path = dir.path().join("track.flac");
+        fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    #[test]
+    fn copy_tags_converts_the_rating_between_formats() {
+        let dir = TempDir::new().unwrap();
+        let source = write_flac(&dir).to_string_lossy().to_string();
+        let dest = write_id3v1_mp3(&dir).to_string_lossy().to_string();
+        write_rating(&source, Some(4)).unwrap();
+
+        let result = copy_tags_internal(&source, &dest, Some(&["rating".to_string()])).unwrap();
+        assert_eq!(result.copied, ["rating"]);
+        assert_eq!(read_rating(&dest), Some(4));
+
+        // 源文件没有评分时跳过
+        write_rating(&source, None).unwrap();
+        let result = copy_tags_internal(&source, &dest, Some(&["rating".to_string()])).unwrap();
+        assert_eq!(result.skipped, ["rating"]);
+        assert_eq!(read_rating(&dest), Some(4));
+    }
+}
@@ -0,0 +1,112 @@
+//! 标签文本规范化模块
+//!
+//! 统一处理 NFC 规范化、空字符和多余空白，保证分组与搜索键一致；另提供模糊比较用的相似度。
+
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// 规范化标签文本
+///
+/// 转换为 NFC 形式，去除旧版 ID3v1 留下的空字符和末尾空白。
+#[must_use]
+pub fn normalize_text(value: &str) -> String {
+    let cleaned: String = value.chars().filter(|c| *c != '\0').collect();
+    cleaned.trim_end().nfc().collect()
+}
+
+/// 规范化可选文本，结果为空时返回 `None`
+#[must_use]
+pub fn normalize_optional(value: Option<String>) -> Option<String> {
+    value.map(|v| normalize_text(&v)).filter(|v| !v.is_empty())
+}
+
+/// 生成用于分组和比较的键（NFC + 小写）
+#[must_use]
+pub fn grouping_key(value: &str) -> String {
+    normalize_text(value).trim().to_lowercase()
+}
+
+/// 生成用于搜索的键（去除变音符号 + 小写）
+///
+/// “Beyoncé” 与 “beyonce” 得到相同的键；中日韩文字保持不变。
+#[must_use]
+pub fn search_key(value: &str) -> String {
+    normalize_text(value)
+        .nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .collect::<String>()
+        .to_lowercase()
+}
+
+/// 中日韩文字（假名、汉字、谚文）
+#[must_use]
+pub const fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30ff}' | '\u{3400}'..='\u{4dbf}' | '\u{4e00}'..='\u{9fff}'
+        | '\u{ac00}'..='\u{d7af}' | '\u{f900}'..='\u{faff}' | '\u{ff66}'..='\u{ff9f}')
+}
+
+/// 按字符计算的编辑距离相似度（0 到 1），两个空字符串视为相同
+#[must_use]
+pub fn edit_similarity(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != cb);
+            current[j + 1] = (previous[j] + cost).min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    1.0 - previous[b.len()] as f64 / longest as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// (NFD, NFC) 形式的同一文本
+    const PAIRS: &[(&str, &str)] = &[
+        ("Bjo\u{308}rk", "Björk"),
+        ("Beyonce\u{301}", "Beyoncé"),
+        ("Sigur Ro\u{301}s", "Sigur Rós"),
+        ("Mo\u{308}tley Cru\u{308}e", "Mötley Crüe"),
+        ("\u{1100}\u{1161}\u{11a8}", "\u{ac01}"),
+        ("か\u{3099}ゆ", "がゆ"),
+    ];
+
+    #[test]
+    fn nfd_and_nfc_text_share_grouping_keys() {
+        for (nfd, nfc) in PAIRS {
+            assert_ne!(nfd, nfc);
+            assert_eq!(normalize_text(nfd), *nfc);
+            assert_eq!(grouping_key(nfd), grouping_key(nfc), "{nfc}");
+            assert_eq!(search_key(nfd), search_key(nfc), "{nfc}");
+        }
+        assert_eq!(grouping_key("BJO\u{308}RK "), grouping_key("björk"));
+    }
+
+    #[test]
+    fn search_keys_ignore_diacritics_but_keep_cjk() {
+        assert_eq!(search_key("Beyoncé"), "beyonce");
+        assert_eq!(search_key("Bjo\u{308}rk"), "bjork");
+        assert_eq!(search_key("周杰伦"), "周杰伦");
+        // 浊音符号属于组合字符，与不带浊音的假名得到相同的键
+        assert_eq!(search_key("が"), search_key("か"));
+    }
+
+    #[test]
+    fn null_bytes_and_trailing_whitespace_are_removed() {
+        assert_eq!(normalize_text("Title\0\0\0"), "Title");
+        assert_eq!(normalize_text("Ti\0tle  \t"), "Title");
+        assert_eq!(normalize_text("  Intro"), "  Intro");
+        assert_eq!(normalize_optional(Some("\0\0 ".to_string())), None);
+        assert_eq!(normalize_optional(Some("Bjo\u{308}rk".to_string())).as_deref(), Some("Björk"));
+    }
+}
@@ -0,0 +1,239 @@
+//! 回收站模块
+//!
+//! 通过系统回收站删除文件，不直接永久删除。删除成功的文件会从曲库数据库和元数据缓存中移除，
+//! 前端根据 `files-deleted` 事件清理播放队列、播放统计等引用。
+//! 文件所在位置不支持回收站时（如网络共享）返回 `TrashUnsupported`，永久删除需要单独调用。
+
+use super::filesystem::existing_path;
+use super::metadata_cache::invalidate;
+use super::watcher::mark_self_write;
+use crate::error::{AppError, AppResult};
+use crate::library::LibraryDb;
+use crate::tr;
+use serde::Serialize;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+/// 文件被删除（移到回收站或永久删除）后发出的事件名称
+pub const FILES_DELETED_EVENT: &str = "files-deleted";
+
+/// 删除事件负载
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FilesDeletedEvent {
+    pub paths: Vec<String>,
+    /// 是否为此停止了当前播放
+    pub stopped_playback: bool,
+}
+
+/// 文件未被删除的原因
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum DeleteFailureReason {
+    /// 正在播放，调用方没有要求停止播放
+    Playing,
+    /// 文件不存在
+    NotFound,
+    /// 移到回收站或删除时出错，详见 `message`
+    Error,
+}
+
+/// 未被删除的文件
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteFailure {
+    pub path: String,
+    pub reason: DeleteFailureReason,
+    /// 错误信息，只在 `reason` 为 `error` 时给出
+    pub message: Option<String>,
+}
+
+impl DeleteFailure {
+    #[must_use]
+    pub const fn new(path: String, reason: DeleteFailureReason) -> Self {
+        Self { path, reason, message: None }
+    }
+
+    #[must_use]
+    pub const fn error(path: String, message: String) -> Self {
+        Self { path, reason: DeleteFailureReason::Error, message: Some(message) }
+    }
+}
+
+/// 删除结果
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteFilesResult {
+    pub deleted: Vec<String>,
+    pub failed: Vec<DeleteFailure>,
+    pub stopped_playback: bool,
+}
+
+/// 文件所在位置是否有回收站
+///
+/// Windows 上 UNC 路径中的文件会被直接永久删除，Linux 上网络文件系统没有回收站。
+#[cfg(not(target_os = "macos"))]
+fn supports_trash(path: &Path) -> bool {
+    !super::network::is_network_path(&path.canonicalize().unwrap_or_else(|_| path.to_path_buf()))
+}
+
+/// 文件所在位置是否有回收站（不支持时系统会返回错误，不会直接删除）
+#[cfg(target_os = "macos")]
+fn supports_trash(_path: &Path) -> bool {
+    true
+}
+
+/// 把文件移到系统回收站
+pub fn move_to_trash(path: &str) -> Result<(), String> {
+    if !supports_trash(Path::new(path)) {
+        return Err(tr!("trash.unsupported"));
+    }
+    mark_self_write(Path::new(path));
+    trash::delete(path).map_err(|e| tr!("trash.failed", detail = e))
+}
+
+/// 从曲库数据库和元数据缓存中移除已删除的文件
+fn forget_files(db: &LibraryDb, paths: &[String]) -> AppResult<()> {
+    db.remove_tracks(paths)?;
+    for path in paths {
+        invalidate(path);
+    }
+    Ok(())
+}
+
+/// 把 `paths` 移到回收站，逐个文件报告结果
+///
+/// 有任一文件所在位置不支持回收站时不处理任何文件，返回 `TrashUnsupported`。
+/// `is_playing` 为真的文件会被跳过，调用方应先停止播放。
+pub fn trash_files_internal(
+    db: &LibraryDb,
+    paths: Vec<String>,
+    is_playing: impl Fn(&str) -> bool,
+) -> AppResult<DeleteFilesResult> {
+    let unsupported: Vec<&str> = paths
+        .iter()
+        .map(String::as_str)
+        .filter(|path| !supports_trash(Path::new(&existing_path(path).unwrap_or_else(|| (*path).to_string()))))
+        .collect();
+    if !unsupported.is_empty() {
+        return Err(AppError::TrashUnsupported(unsupported.join(", ")));
+    }
+
+    let mut result = DeleteFilesResult::default();
+    for path in paths {
+        if is_playing(&path) {
+            result.failed.push(DeleteFailure::new(path, DeleteFailureReason::Playing));
+        } else if let Some(found) = existing_path(&path) {
+            // 删除确认存在的写法，`path` 可能是使用另一种分隔符的曲库路径
+            match move_to_trash(&found) {
+                Ok(()) => result.deleted.push(path),
+                Err(message) => result.failed.push(DeleteFailure::error(path, message)),
+            }
+        } else {
+            result.failed.push(DeleteFailure::new(path, DeleteFailureReason::NotFound));
+        }
+    }
+
+    forget_files(db, &result.deleted)?;
+    Ok(result)
+}
+
+/// 永久删除 `paths`，只应在用户明确确认后调用
+///
+/// 不经过回收站，无法恢复。`is_playing` 为真的文件会被跳过。
+pub fn delete_files_permanently_internal(
+    db: &LibraryDb,
+    paths: Vec<String>,
+    is_playing: impl Fn(&str) -> bool,
+) -> AppResult<DeleteFilesResult> {
+    let mut result = DeleteFilesResult::default();
+    for path in paths {
+        if is_playing(&path) {
+            result.failed.push(DeleteFailure::new(path, DeleteFailureReason::Playing));
+            continue;
+        }
+        let target = existing_path(&path).unwrap_or_else(|| path.clone());
+        mark_self_write(Path::new(&target));
+        match fs::remove_file(&target) {
+            Ok(()) => result.deleted.push(path),
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                result.failed.push(DeleteFailure::new(path, DeleteFailureReason::NotFound));
+            }
+            Err(e) => result.failed.push(DeleteFailure::error(path, tr!("trash.deleteFailed", detail = e))),
+        }
+    }
+
+    forget_files(db, &result.deleted)?;
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn library(dir: &TempDir) -> LibraryDb {
+        LibraryDb::open(&dir.path().join("library.db")).unwrap()
+    }
+
+    #[test]
+    fn failures_carry_a_typed_reason() {
+        let dir = TempDir::new().unwrap();
+        let db = library(&dir);
+        let path = |name: &str| dir.path().join(name).to_string_lossy().to_string();
+        fs::write(path("playing.flac"), b"").unwrap();
+        fs::write(path("other.flac"), b"").unwrap();
+
+        let paths = vec![path("playing.flac"), path("missing.flac"), path("other.flac")];
+        let result = delete_files_permanently_internal(&db, paths, |p| p.ends_with("playing.flac")).unwrap();
+        assert_eq!(result.deleted, [path("other.flac")]);
+        let reasons: Vec<_> = result.failed.iter().map(|f| (f.path.clone(), f.reason, f.message.clone())).collect();
+        assert_eq!(
+            reasons,
+            [
+                (path("playing.flac"), DeleteFailureReason::Playing, None),
+                (path("missing.flac"), DeleteFailureReason::NotFound, None),
+            ]
+        );
+        assert!(Path::new(&path("playing.flac")).exists());
+    }
+
+    #[test]
+    fn files_that_cannot_be_trashed_are_reported_before_touching_the_trash() {
+        let dir = TempDir::new().unwrap();
+        let db = library(&dir);
+        let playing = dir.path().join("playing.flac").to_string_lossy().to_string();
+        fs::write(&playing, b"").unwrap();
+        let missing = dir.path().join("missing.flac").to_string_lossy().to_string();
+
+        let result = trash_files_internal(&db, vec![playing.clone(), missing], |p| p == playing).unwrap();
+        assert!(result.deleted.is_empty());
+        let reasons: Vec<_> = result.failed.iter().map(|f| f.reason).collect();
+        assert_eq!(reasons, [DeleteFailureReason::Playing, DeleteFailureReason::NotFound]);
+    }
+
+    #[test]
+    fn failures_serialize_with_camel_case_reasons() {
+        let failure = DeleteFailure::error("/a.flac".to_string(), "denied".to_string());
+        let json = serde_json::to_value(&failure).unwrap();
+        assert_eq!(json, serde_json::json!({ "path": "/a.flac", "reason": "error", "message": "denied" }));
+        let failure = DeleteFailure::new("/b.flac".to_string(), DeleteFailureReason::NotFound);
+        let json = serde_json::to_value(&failure).unwrap();
+        assert_eq!(json["reason"], "notFound");
+    }
+
+    #[test]
+    fn library_paths_with_the_other_separator_are_deleted() {
+        let dir = TempDir::new().unwrap();
+        let db = library(&dir);
+        let file = dir.path().join("song.flac");
+        fs::write(&file, b"").unwrap();
+        let key = file.to_string_lossy().replace('/', "\\");
+
+        let result = delete_files_permanently_internal(&db, vec![key.clone()], |_| false).unwrap();
+        assert_eq!(result.deleted, [key]);
+        assert!(result.failed.is_empty());
+        assert!(!file.exists());
+    }
+}
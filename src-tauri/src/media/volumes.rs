@@ -0,0 +1,148 @@
+//! 卷信息模块
+//!
+//! 判断路径是否位于可移动或外接的卷上（U 盘、移动硬盘、SD 卡），用于在曲库目录离线时区分
+//! "驱动器被拔出"和"目录被删除"。结果按卷缓存，同一卷只查询一次。
+
+use crate::paths::canonical_key;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{LazyLock, Mutex};
+
+/// 卷或查询过的路径（`canonical_key`）-> 是否可移动
+static REMOVABLE_CACHE: LazyLock<Mutex<HashMap<String, bool>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// 路径所在的卷：Windows 上为盘符（如 `E:`），其他平台为挂载点
+#[cfg(windows)]
+fn volume_of(path: &Path) -> Option<String> {
+    let raw = path.to_string_lossy();
+    let mut chars = raw.chars();
+    match (chars.next(), chars.next()) {
+        (Some(letter), Some(':')) if letter.is_ascii_alphabetic() => Some(format!("{}:", letter.to_ascii_uppercase())),
+        _ => None,
+    }
+}
+
+/// 查询卷的总线类型和驱动器类型，USB、SD 等总线或可移动驱动器视为可移动
+#[cfg(windows)]
+fn query_removable(volume: &str) -> bool {
+    use std::os::windows::process::CommandExt;
+    /// 不为子进程创建控制台窗口
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    let letter = volume.trim_end_matches(':');
+    let script = format!(
+        "$v = Get-Volume -DriveLetter {letter}; $d = Get-Partition -DriveLetter {letter} | Get-Disk; \
+         \"$($v.DriveType)|$($d.BusType)\""
+    );
+    let Ok(output) = std::process::Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", &script])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+    else {
+        return false;
+    };
+    let text = String::from_utf8_lossy(&output.stdout).to_lowercase();
+    let (drive_type, bus_type) = text.trim().split_once('|').unwrap_or((text.trim(), ""));
+    drive_type == "removable" || matches!(bus_type, "usb" | "sd" | "mmc" | "1394")
+}
+
+/// 路径所在的挂载点和设备
+#[cfg(all(unix, not(target_os = "macos")))]
+fn mount_of(path: &Path) -> Option<(String, String)> {
+    let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    let mounts = std::fs::read_to_string("/proc/mounts").ok()?;
+    mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let device = fields.next()?.to_string();
+            let mount = fields.next()?.replace("\\040", " ").replace("\\011", "\t").replace("\\134", "\\");
+            path.starts_with(&mount).then_some((mount, device))
+        })
+        .max_by_key(|(mount, _)| mount.len())
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn volume_of(path: &Path) -> Option<String> {
+    mount_of(path).map(|(mount, _)| mount)
+}
+
+/// 通过 sysfs 判断：设备标记为可移动，或挂在 USB / MMC 总线上（外接 SSD 通常不标记为可移动）
+#[cfg(all(unix, not(target_os = "macos")))]
+fn query_removable(volume: &str) -> bool {
+    let Some((_, device)) = mount_of(Path::new(volume)) else {
+        return false;
+    };
+    let Some(name) = device.strip_prefix("/dev/").and_then(|name| name.rsplit('/').next()) else {
+        return false;
+    };
+    let Ok(sys_path) = std::fs::canonicalize(format!("/sys/class/block/{name}")) else {
+        return false;
+    };
+    let sys = sys_path.to_string_lossy();
+    if sys.contains("/usb") || sys.contains("/mmc") {
+        return true;
+    }
+    // 分区的 removable 标记在上级的磁盘设备上
+    [sys_path.as_path(), sys_path.parent().unwrap_or(&sys_path)]
+        .iter()
+        .any(|dir| std::fs::read_to_string(dir.join("removable")).is_ok_and(|value| value.trim() == "1"))
+}
+
+/// `/Volumes` 下的卷为启动盘以外的卷
+#[cfg(target_os = "macos")]
+fn volume_of(path: &Path) -> Option<String> {
+    let mut components = path.components();
+    let (Some(_), Some(volumes), Some(name)) = (components.next(), components.next(), components.next()) else {
+        return None;
+    };
+    (volumes.as_os_str() == "Volumes").then(|| format!("/Volumes/{}", name.as_os_str().to_string_lossy()))
+}
+
+/// 解析 `diskutil info` 的输出：可移动介质，或不是内置设备
+#[cfg(target_os = "macos")]
+fn query_removable(volume: &str) -> bool {
+    let Ok(output) = std::process::Command::new("/usr/sbin/diskutil").args(["info", volume]).output() else {
+        return false;
+    };
+    String::from_utf8_lossy(&output.stdout).lines().any(|line| {
+        let Some((key, value)) = line.split_once(':') else {
+            return false;
+        };
+        let value = value.trim();
+        match key.trim() {
+            "Removable Media" => value.eq_ignore_ascii_case("Removable"),
+            "Device Location" => value.eq_ignore_ascii_case("External"),
+            "Internal" => value.eq_ignore_ascii_case("No"),
+            _ => false,
+        }
+    })
+}
+
+fn cached(key: &str) -> Option<bool> {
+    REMOVABLE_CACHE.lock().unwrap().get(key).copied()
+}
+
+/// 路径是否位于可移动或外接的卷上
+///
+/// 驱动器被拔出后使用路径在线时查询到的结果，从未在线过时返回 `false`；网络共享不算可移动卷。
+#[must_use]
+pub fn is_removable_path(path: &Path) -> bool {
+    let path_key = canonical_key(&path.to_string_lossy());
+    if let Some(removable) = cached(&path_key) {
+        return removable;
+    }
+    // 已拔出的驱动器上的路径会被归到其他卷（如根目录），结果不可靠，不缓存
+    if !path.exists() {
+        return false;
+    }
+    let Some(volume) = volume_of(path) else {
+        return false;
+    };
+    let volume_key = canonical_key(&volume);
+    let removable = cached(&volume_key).unwrap_or_else(|| query_removable(&volume));
+    let mut cache = REMOVABLE_CACHE.lock().unwrap();
+    cache.insert(volume_key, removable);
+    cache.insert(path_key, removable);
+    removable
+}
@@ -0,0 +1,463 @@
+//! 曲库目录监视模块
+//!
+//! 递归监视音乐目录，把短时间内的大量文件事件合并后以 `library-files-*` 事件发给前端；使用电池时合并的时间更长。
+//! 目录消失（如拔出移动硬盘）时发送 `watch-lost` 并定期重试，恢复后发送 `watch-restored`；
+//! 同时请求立即检查曲库根目录（见 `roots`），由它标记离线的曲目并在恢复后重新扫描。
+//! 当前曲目的歌词文件被其他程序修改时发送 `lyrics-file-changed`，前端据此重新加载歌词。
+//! 本程序自己写入的文件（标签编辑、歌词保存）会在一段时间内被忽略。
+
+use super::filesystem::{collect_audio_paths_filtered, has_audio_extension};
+use super::lyrics_finder::is_lyrics_file;
+use super::metadata::MetadataOptions;
+use super::metadata_cache::{cached_track_metadata, invalidate, refresh_and_notify, remove_under};
+use super::roots::request_root_check;
+use super::scan_filter::ScanFilter;
+use crate::config::manager::AppConfig;
+use crate::paths::canonical_key;
+use crate::system::power;
+use crate::AppState;
+use crate::tr;
+use crossbeam_channel::{Receiver, RecvTimeoutError};
+use notify::event::{ModifyKind, RenameMode};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// 新增音频文件事件名称
+pub const LIBRARY_FILES_ADDED_EVENT: &str = "library-files-added";
+/// 音频文件被删除或移出事件名称
+pub const LIBRARY_FILES_REMOVED_EVENT: &str = "library-files-removed";
+/// 音频文件内容变化事件名称
+pub const LIBRARY_FILES_MODIFIED_EVENT: &str = "library-files-modified";
+/// 监视目录丢失事件名称
+pub const WATCH_LOST_EVENT: &str = "watch-lost";
+/// 监视目录恢复事件名称
+pub const WATCH_RESTORED_EVENT: &str = "watch-restored";
+/// 当前曲目的歌词文件被外部修改事件名称
+pub const LYRICS_FILE_CHANGED_EVENT: &str = "lyrics-file-changed";
+
+/// 最后一个事件之后等待多久再发送
+const DEBOUNCE: Duration = Duration::from_millis(1500);
+/// 持续有事件时最长合并多久
+const MAX_BATCH: Duration = Duration::from_secs(10);
+/// 使用电池减少后台工作时的 [`DEBOUNCE`]
+const LOW_POWER_DEBOUNCE: Duration = Duration::from_secs(10);
+/// 使用电池减少后台工作时的 [`MAX_BATCH`]
+const LOW_POWER_MAX_BATCH: Duration = Duration::from_mins(1);
+/// 检查停止标志的间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// 检查丢失目录的间隔
+const RETRY_INTERVAL: Duration = Duration::from_secs(5);
+/// 自身写入后忽略该文件事件的时长
+const SELF_WRITE_WINDOW: Duration = Duration::from_secs(5);
+
+/// 文件变化事件负载
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryFilesEvent {
+    pub paths: Vec<String>,
+}
+
+/// 目录丢失或恢复事件负载
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchDirectoryEvent {
+    pub path: String,
+}
+
+/// 歌词文件变化事件负载
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LyricsFileChangedEvent {
+    /// 当前播放的曲目
+    pub track_path: String,
+    /// 新增、修改或删除的歌词文件
+    pub paths: Vec<String>,
+}
+
+/// 最近由本程序写入的文件 -> 写入时间
+static SELF_WRITES: LazyLock<Mutex<HashMap<String, Instant>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn self_write_key(path: &Path) -> String {
+    canonical_key(&path.to_string_lossy())
+}
+
+/// 记录本程序即将（或刚刚）写入的文件，监视器在短时间内忽略它的事件
+pub fn mark_self_write(path: &Path) {
+    let mut writes = SELF_WRITES.lock().unwrap();
+    writes.retain(|_, time| time.elapsed() < SELF_WRITE_WINDOW);
+    writes.insert(self_write_key(path), Instant::now());
+}
+
+fn is_self_write(path: &Path) -> bool {
+    SELF_WRITES
+        .lock()
+        .unwrap()
+        .get(&self_write_key(path))
+        .is_some_and(|time| time.elapsed() < SELF_WRITE_WINDOW)
+}
+
+/// 尚未发送的变化
+#[derive(Default)]
+struct PendingChanges {
+    added: BTreeSet<PathBuf>,
+    removed: BTreeSet<PathBuf>,
+    modified: BTreeSet<PathBuf>,
+    first: Option<Instant>,
+    last: Option<Instant>,
+}
+
+impl PendingChanges {
+    fn add(&mut self, path: PathBuf) {
+        // 先删除后创建（其他程序的原子保存）视为修改
+        if self.removed.remove(&path) {
+            self.modified.insert(path);
+        } else {
+            self.added.insert(path);
+        }
+    }
+
+    fn remove(&mut self, path: PathBuf) {
+        self.modified.remove(&path);
+        // 本批次中新增又删除的文件不需要通知
+        if !self.added.remove(&path) {
+            self.removed.insert(path);
+        }
+    }
+
+    fn modify(&mut self, path: PathBuf) {
+        if !self.added.contains(&path) {
+            self.modified.insert(path);
+        }
+    }
+
+    fn record(&mut self, event: Event) {
+        let paths: Vec<PathBuf> = event.paths.into_iter().filter(|p| !is_self_write(p)).collect();
+        if paths.is_empty() {
+            return;
+        }
+
+        match event.kind {
+            EventKind::Create(_) => paths.into_iter().for_each(|p| self.add(p)),
+            EventKind::Remove(_) => paths.into_iter().for_each(|p| self.remove(p)),
+            EventKind::Modify(ModifyKind::Name(RenameMode::From)) => paths.into_iter().for_each(|p| self.remove(p)),
+            EventKind::Modify(ModifyKind::Name(RenameMode::To)) => paths.into_iter().for_each(|p| self.add(p)),
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if paths.len() == 2 => {
+                let mut paths = paths.into_iter();
+                self.remove(paths.next().unwrap());
+                self.add(paths.next().unwrap());
+            }
+            // 无法区分方向的重命名按文件是否存在判断
+            EventKind::Modify(ModifyKind::Name(_)) => {
+                for path in paths {
+                    if path.exists() {
+                        self.add(path);
+                    } else {
+                        self.remove(path);
+                    }
+                }
+            }
+            EventKind::Modify(_) => paths.into_iter().for_each(|p| self.modify(p)),
+            _ => return,
+        }
+
+        let now = Instant::now();
+        self.first.get_or_insert(now);
+        self.last = Some(now);
+    }
+
+    fn is_ready(&self) -> bool {
+        let (debounce, max_batch) = if power::is_reducing_background_work() {
+            (LOW_POWER_DEBOUNCE, LOW_POWER_MAX_BATCH)
+        } else {
+            (DEBOUNCE, MAX_BATCH)
+        };
+        match (self.first, self.last) {
+            (Some(first), Some(last)) => last.elapsed() >= debounce || first.elapsed() >= max_batch,
+            _ => false,
+        }
+    }
+}
+
+fn path_string(path: &Path) -> String {
+    path.to_string_lossy().to_string()
+}
+
+/// 过滤出音频文件并发送事件
+///
+/// 新增的目录会被展开为其中的音频文件；删除的目录从元数据缓存中找出原有的音频文件。
+/// 被 `filter` 排除的新文件和变化会被忽略。曲库数据库随之更新。
+fn flush(
+    app: &AppHandle,
+    changes: PendingChanges,
+    roots: &[PathBuf],
+    options: &MetadataOptions,
+    filter: &ScanFilter,
+) {
+    let library = app.try_state::<AppState>().map(|state| Arc::clone(&state.library));
+
+    let mut added: Vec<String> = Vec::new();
+    for path in &changes.added {
+        if filter.excludes_file(roots, path) {
+            continue;
+        }
+        if path.is_dir() {
+            added.extend(collect_audio_paths_filtered(&[path_string(path)], filter));
+        } else if path.is_file() && has_audio_extension(path) {
+            added.push(path_string(path));
+        }
+    }
+    added.sort();
+    added.dedup();
+
+    let mut removed: Vec<String> = Vec::new();
+    for path in &changes.removed {
+        if has_audio_extension(path) {
+            let path = path_string(path);
+            invalidate(&path);
+            removed.push(path);
+        } else {
+            removed.extend(remove_under(path));
+            if let Some(Err(e)) = library.as_ref().map(|db| db.remove_under(&path_string(path))) {
+                eprintln!("Failed to update library database: {e}");
+            }
+        }
+    }
+
+    if let Some(db) = &library {
+        let tracks: Vec<_> = added.iter().filter_map(|path| cached_track_metadata(path, options).ok()).collect();
+        if let Err(e) = db.remove_tracks(&removed).and_then(|()| db.upsert_tracks(&tracks)) {
+            eprintln!("Failed to update library database: {e}");
+        }
+    }
+
+    let modified: Vec<String> = changes
+        .modified
+        .iter()
+        .filter(|path| path.is_file() && has_audio_extension(path) && !filter.excludes_file(roots, path))
+        .map(|path| path_string(path))
+        .collect();
+    for path in &modified {
+        let _ = refresh_and_notify(app, path, options);
+    }
+
+    notify_lyrics_changed(app, &changes);
+
+    for (event, paths) in [
+        (LIBRARY_FILES_ADDED_EVENT, added),
+        (LIBRARY_FILES_REMOVED_EVENT, removed),
+        (LIBRARY_FILES_MODIFIED_EVENT, modified),
+    ] {
+        if !paths.is_empty() {
+            let _ = app.emit(event, LibraryFilesEvent { paths });
+        }
+    }
+}
+
+/// 当前曲目目录（或其中的歌词子目录）中的歌词文件变化时发送 `lyrics-file-changed`
+fn notify_lyrics_changed(app: &AppHandle, changes: &PendingChanges) {
+    let Some(state) = app.try_state::<AppState>() else {
+        return;
+    };
+    let Some(track_path) = state.player.current_path.lock().unwrap().clone() else {
+        return;
+    };
+    let Some(track_dir) = Path::new(&track_path).parent().map(|dir| canonical_key(&dir.to_string_lossy())) else {
+        return;
+    };
+    let in_track_dir = |dir: Option<&Path>| dir.is_some_and(|dir| canonical_key(&dir.to_string_lossy()) == track_dir);
+
+    let paths: Vec<String> = changes
+        .added
+        .iter()
+        .chain(&changes.modified)
+        .chain(&changes.removed)
+        .filter(|path| is_lyrics_file(path))
+        .filter(|path| {
+            let parent = path.parent();
+            in_track_dir(parent) || in_track_dir(parent.and_then(Path::parent))
+        })
+        .map(|path| path_string(path))
+        .collect();
+    if !paths.is_empty() {
+        let _ = app.emit(LYRICS_FILE_CHANGED_EVENT, LyricsFileChangedEvent { track_path, paths });
+    }
+}
+
+/// 检查监视目录是否还在，丢失的目录在重新出现后恢复监视
+fn check_roots(app: &AppHandle, watcher: &mut RecommendedWatcher, roots: &[PathBuf], lost: &mut HashSet<PathBuf>) {
+    for root in roots {
+        let exists = root.is_dir();
+        if lost.contains(root) {
+            if exists && watcher.watch(root, RecursiveMode::Recursive).is_ok() {
+                lost.remove(root);
+                let _ = app.emit(WATCH_RESTORED_EVENT, WatchDirectoryEvent { path: path_string(root) });
+                request_root_check();
+            }
+        } else if !exists {
+            let _ = watcher.unwatch(root);
+            lost.insert(root.clone());
+            let _ = app.emit(WATCH_LOST_EVENT, WatchDirectoryEvent { path: path_string(root) });
+            request_root_check();
+        }
+    }
+}
+
+fn run(
+    app: &AppHandle,
+    mut watcher: RecommendedWatcher,
+    events: &Receiver<notify::Result<Event>>,
+    roots: &[PathBuf],
+    mut lost: HashSet<PathBuf>,
+    options: &MetadataOptions,
+    filter: &ScanFilter,
+    stop: &AtomicBool,
+) {
+    let mut pending = PendingChanges::default();
+    let mut last_check = Instant::now();
+    let mut check_now = false;
+
+    while !stop.load(Ordering::Relaxed) {
+        match events.recv_timeout(POLL_INTERVAL) {
+            Ok(Ok(event)) => pending.record(event),
+            Ok(Err(e)) => {
+                // 监视的目录所在驱动器被拔出时通常先收到错误，立即检查而不是等到下次定期检查
+                eprintln!("Library watcher error: {e}");
+                request_root_check();
+                check_now = true;
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+
+        if pending.is_ready() {
+            flush(app, std::mem::take(&mut pending), roots, options, filter);
+        }
+        if check_now || last_check.elapsed() >= RETRY_INTERVAL {
+            check_roots(app, &mut watcher, roots, &mut lost);
+            last_check = Instant::now();
+            check_now = false;
+        }
+    }
+}
+
+/// 按设置开始、更新或停止监视音乐目录
+///
+/// 关闭目录监视或没有音乐目录时停止监视；正在监视时换成设置中的目录列表，否则开始监视。
+pub fn apply_config(app: &AppHandle, config: &AppConfig) {
+    let state = app.state::<AppState>();
+    let watcher = &state.library_watcher;
+    let result = if !config.directory_scan.watch_directories || config.music_directories.is_empty() {
+        watcher.stop();
+        Ok(())
+    } else if watcher.is_running() {
+        watcher.set_directories(config.music_directories.clone())
+    } else {
+        let options = MetadataOptions::from_config(config);
+        watcher.start(app.clone(), config.music_directories.clone(), options, ScanFilter::from_config(config))
+    };
+    if let Err(e) = result {
+        eprintln!("Failed to start library watcher: {e}");
+    }
+}
+
+/// 正在进行的监视
+struct WatchSession {
+    app: AppHandle,
+    directories: Vec<String>,
+    options: MetadataOptions,
+    filter: ScanFilter,
+    stop: Arc<AtomicBool>,
+}
+
+/// 曲库目录监视器，保存在 `AppState` 中
+#[derive(Default)]
+pub struct LibraryWatcher {
+    session: Mutex<Option<WatchSession>>,
+}
+
+impl LibraryWatcher {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 开始监视目录，已在监视时先停止旧的监视
+    ///
+    /// 启动时不存在的目录会立即发送 `watch-lost`，之后按间隔重试。
+    pub fn start(
+        &self,
+        app: AppHandle,
+        directories: Vec<String>,
+        options: MetadataOptions,
+        filter: ScanFilter,
+    ) -> Result<(), String> {
+        self.stop();
+
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let mut watcher = notify::recommended_watcher(move |event| {
+            let _ = sender.send(event);
+        })
+        .map_err(|e| tr!("watcher.createFailed", detail = e))?;
+
+        let roots: Vec<PathBuf> = directories.iter().map(PathBuf::from).collect();
+        let mut lost = HashSet::new();
+        for root in &roots {
+            if watcher.watch(root, RecursiveMode::Recursive).is_err() {
+                lost.insert(root.clone());
+                let _ = app.emit(WATCH_LOST_EVENT, WatchDirectoryEvent { path: path_string(root) });
+            }
+        }
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+        let thread_app = app.clone();
+        let thread_options = options.clone();
+        let thread_filter = filter.clone();
+        thread::Builder::new()
+            .name("library-watcher".to_string())
+            .spawn(move || {
+                run(&thread_app, watcher, &receiver, &roots, lost, &thread_options, &thread_filter, &thread_stop);
+            })
+            .map_err(|e| tr!("watcher.threadFailed", detail = e))?;
+
+        *self.session.lock().unwrap() = Some(WatchSession { app, directories, options, filter, stop });
+        Ok(())
+    }
+
+    /// 停止监视，未在监视时什么也不做
+    pub fn stop(&self) {
+        let session = self.session.lock().unwrap().take();
+        if let Some(session) = session {
+            session.stop.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// 是否正在监视
+    #[must_use]
+    pub fn is_running(&self) -> bool {
+        self.session.lock().unwrap().is_some()
+    }
+
+    /// 正在监视时换成新的目录列表，未在监视时什么也不做
+    pub fn set_directories(&self, directories: Vec<String>) -> Result<(), String> {
+        let session = self
+            .session
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|s| (s.app.clone(), s.directories.clone(), s.options.clone(), s.filter.clone()));
+        match session {
+            Some((app, current, options, filter)) if current != directories => {
+                self.start(app, directories, options, filter)
+            }
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,52 @@
+//! 逐字歌词
+//!
+//! 酷狗 KRC 等格式为每个字（词）记录开始时间和时长，可以逐字高亮。
+//! 这里是与来源无关的结构，时间均为从歌曲开始的绝对毫秒数；保存为 .lrc 文件时展平为行级 LRC。
+
+use super::lyrics::{shift_time, sync_entries_to_lrc};
+use serde::{Deserialize, Serialize};
+
+/// 一个字（词）
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct LyricWord {
+    /// 开始时间（毫秒）
+    pub start: u32,
+    /// 时长（毫秒）
+    pub duration: u32,
+    pub text: String,
+}
+
+/// 带逐字时间的一行歌词
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct WordTimedLine {
+    /// 开始时间（毫秒）
+    pub start: u32,
+    /// 时长（毫秒）
+    pub duration: u32,
+    pub words: Vec<LyricWord>,
+}
+
+impl WordTimedLine {
+    /// 整行文本
+    #[must_use]
+    pub fn text(&self) -> String {
+        self.words.iter().map(|word| word.text.as_str()).collect()
+    }
+
+    /// 按偏移（毫秒）平移整行和每个字的开始时间
+    pub fn shift(&mut self, offset_ms: i64) {
+        self.start = shift_time(self.start, offset_ms);
+        for word in &mut self.words {
+            word.start = shift_time(word.start, offset_ms);
+        }
+    }
+}
+
+/// 展平为行级 LRC，丢弃逐字时间
+#[must_use]
+pub fn word_lines_to_lrc(lines: &[WordTimedLine]) -> String {
+    let entries: Vec<(u32, String)> = lines.iter().map(|line| (line.start, line.text())).collect();
+    sync_entries_to_lrc(&entries)
+}
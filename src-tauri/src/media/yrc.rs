@@ -0,0 +1,49 @@
+//! 网易云音乐 YRC 逐字歌词解析
+//!
+//! 每行形如 `[行开始,行时长](字开始,字时长,0)字...`，与 KRC 不同，字的开始时间是从歌曲开始的绝对毫秒数。
+//! 正文前通常有若干行 `{"t":0,"c":[{"tx":"作词: "},...]}` 形式的 JSON 元信息（作词、作曲等），解析时跳过。
+
+use super::word_lyrics::{LyricWord, WordTimedLine};
+
+/// 解析 `a,b` 或 `a,b,c` 形式的时间，返回前两个数
+fn parse_pair(value: &str) -> Option<(u32, u32)> {
+    let mut parts = value.split(',');
+    let first = parts.next()?.trim().parse().ok()?;
+    let second = parts.next()?.trim().parse().ok()?;
+    Some((first, second))
+}
+
+/// 解析一行 `[开始,时长](开始,时长,0)字...`
+fn parse_line(line: &str) -> Option<WordTimedLine> {
+    let inner = line.strip_prefix('[')?;
+    let end = inner.find(']')?;
+    let (start, duration) = parse_pair(&inner[..end])?;
+
+    let mut words: Vec<LyricWord> = Vec::new();
+    let mut rest = &inner[end + 1..];
+    while let Some(open) = rest.find('(') {
+        let Some(close) = rest[open..].find(')').map(|i| open + i) else { break };
+        // 歌词文本本身带括号时（如 "(Live)"），括号内不是时间，作为上一个字的文本
+        let Some((word_start, word_duration)) = parse_pair(&rest[open + 1..close]) else {
+            let text_end = rest[close..].find('(').map_or(rest.len(), |i| close + i);
+            if let Some(last) = words.last_mut() {
+                last.text.push_str(&rest[..text_end]);
+            }
+            rest = &rest[text_end..];
+            continue;
+        };
+        rest = &rest[close + 1..];
+        let text_end = rest.find('(').unwrap_or(rest.len());
+        words.push(LyricWord { start: word_start, duration: word_duration, text: rest[..text_end].to_string() });
+        rest = &rest[text_end..];
+    }
+    (!words.is_empty()).then_some(WordTimedLine { start, duration, words })
+}
+
+/// 解析 YRC 歌词文本，JSON 元信息行和无法识别的行被忽略
+#[must_use]
+pub fn parse_yrc(text: &str) -> Vec<WordTimedLine> {
+    let mut lines: Vec<WordTimedLine> = text.lines().map(str::trim).filter_map(parse_line).collect();
+    lines.sort_by_key(|line| line.start);
+    lines
+}
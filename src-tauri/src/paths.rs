@@ -0,0 +1,236 @@
+//! 路径处理模块
+//!
+//! Windows 上超过 MAX_PATH（260 个字符）的路径需要使用 `\\?\` 扩展长度形式，否则部分 API
+//! 会返回难以理解的 IO 错误。打开音频、封面、歌词文件以及移动文件的入口都先经过 `long_path` 转换；
+//! 转换后的路径只用于访问文件，保存、比较和显示仍使用原路径。
+//!
+//! 同一文件的路径可能以多种写法出现（文件对话框返回的路径、`TrackMetadata.path` 的反斜杠写法、
+//! 前端传回的路径），比较路径和作为映射键时统一使用 `canonical_key`。
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 超过该长度的路径转换为扩展长度形式（创建目录时的上限为 248 个字符）
+#[cfg(windows)]
+const LONG_PATH_THRESHOLD: usize = 248;
+
+/// 返回可以直接用于文件操作的路径
+///
+/// Windows 上较长的绝对路径会被规范化（统一分隔符、处理 `.` 和 `..`）后加上 `\\?\` 前缀，
+/// UNC 路径转换为 `\\?\UNC\server\share` 形式；已是扩展长度形式的路径和其他平台上的路径原样返回。
+#[cfg(windows)]
+pub fn long_path(path: impl AsRef<Path>) -> PathBuf {
+    let path = path.as_ref();
+    let raw = path.as_os_str().to_string_lossy();
+    if raw.starts_with(r"\\?\") || raw.starts_with(r"\\.\") || raw.len() < LONG_PATH_THRESHOLD {
+        return path.to_path_buf();
+    }
+    // 扩展长度路径不会被系统规范化，必须先得到完整的绝对路径
+    let Ok(absolute) = std::path::absolute(path) else {
+        return path.to_path_buf();
+    };
+    let absolute = absolute.to_string_lossy().replace('/', "\\");
+    match absolute.strip_prefix(r"\\") {
+        Some(unc) => PathBuf::from(format!(r"\\?\UNC\{unc}")),
+        None => PathBuf::from(format!(r"\\?\{absolute}")),
+    }
+}
+
+/// 返回可以直接用于文件操作的路径
+#[cfg(not(windows))]
+pub fn long_path(path: impl AsRef<Path>) -> PathBuf {
+    path.as_ref().to_path_buf()
+}
+
+/// 去掉 `\\?\` 扩展长度前缀，得到用于显示和保存的路径（如 `fs::canonicalize` 的结果）
+///
+/// `\\?\UNC\server\share` 还原为 `\\server\share`；其他路径原样返回。
+pub fn display_path(path: impl AsRef<Path>) -> PathBuf {
+    let path = path.as_ref();
+    let raw = path.to_string_lossy();
+    if let Some(unc) = raw.strip_prefix(r"\\?\UNC\") {
+        return PathBuf::from(format!(r"\\{unc}"));
+    }
+    raw.strip_prefix(r"\\?\").map_or_else(|| path.to_path_buf(), PathBuf::from)
+}
+
+/// 比较路径和作为映射键时使用的写法
+///
+/// 去掉 `\\?\` 前缀，分隔符统一为 `/`，合并重复的分隔符并去掉 `.` 和末尾的分隔符，盘符转为小写；
+/// UNC 路径保留开头的 `//`。只有 Windows 上整个路径转为小写：Linux 和区分大小写的 macOS 卷上
+/// `Song.flac` 与 `song.flac` 是两个文件，不能共用一个键。只按文本转换，不访问文件系统。
+#[must_use]
+pub fn canonical_key(path: &str) -> String {
+    let display = display_path(path);
+    let raw = display.to_string_lossy().replace('\\', "/");
+    let lead = if raw.starts_with("//") {
+        "//"
+    } else if raw.starts_with('/') {
+        "/"
+    } else {
+        ""
+    };
+    let components: Vec<&str> = raw.split('/').filter(|part| !part.is_empty() && *part != ".").collect();
+    fold_case(format!("{lead}{}", components.join("/")))
+}
+
+/// Windows 的文件名不区分大小写，整个键转为小写
+#[cfg(windows)]
+fn fold_case(key: String) -> String {
+    key.to_lowercase()
+}
+
+/// 其他平台只把盘符（`C:`）转为小写
+#[cfg(not(windows))]
+fn fold_case(mut key: String) -> String {
+    if key.as_bytes().get(1) == Some(&b':') && key.as_bytes()[0].is_ascii_alphabetic() {
+        key[..1].make_ascii_lowercase();
+    }
+    key
+}
+
+/// 与 `canonical_key` 相同，但文件存在时先解析符号链接和 `..`（`fs::canonicalize`），
+/// 用于判断不同写法的路径是否指向同一目录；文件不存在或无法访问时按文本转换
+#[must_use]
+pub fn resolved_key(path: &str) -> String {
+    fs::canonicalize(long_path(path)).map_or_else(
+        |_| canonical_key(path),
+        |resolved| canonical_key(&display_path(resolved).to_string_lossy()),
+    )
+}
+
+/// 两个路径是否指向同一文件（按 `canonical_key` 比较）
+#[must_use]
+pub fn same_path(a: &str, b: &str) -> bool {
+    canonical_key(a) == canonical_key(b)
+}
+
+/// `path` 是否为 `root` 本身或位于其中（按 `canonical_key` 比较）
+#[must_use]
+pub fn is_within(path: &str, root: &str) -> bool {
+    let (path, root) = (canonical_key(path), canonical_key(root));
+    if root.is_empty() {
+        return false;
+    }
+    path == root || path.strip_prefix(&root).is_some_and(|rest| root.ends_with('/') || rest.starts_with('/'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn canonical_key_normalizes_separators_and_prefixes() {
+        // 期望值的大小写随平台变化
+        assert_eq!(canonical_key(r"\\?\C:\Music\\Album\.\"), fold_case("c:/Music/Album".to_string()));
+        assert_eq!(canonical_key(r"\\?\UNC\server\share\a.flac"), "//server/share/a.flac");
+        assert_eq!(canonical_key("/home/me//Music/"), fold_case("/home/me/Music".to_string()));
+    }
+
+    #[test]
+    fn canonical_key_lowercases_drive_letter() {
+        assert!(canonical_key("D:/Music").starts_with("d:/"));
+        assert!(same_path("D:\\Music\\a.flac", "d:/Music/a.flac"));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn canonical_key_folds_case_on_windows() {
+        assert!(same_path(r"C:\Music\Song.flac", "c:/music/song.FLAC"));
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn canonical_key_keeps_case_elsewhere() {
+        assert!(!same_path("/music/Song.flac", "/music/song.flac"));
+        assert_eq!(canonical_key("/music/Song.flac"), "/music/Song.flac");
+    }
+
+    #[test]
+    fn is_within_requires_component_boundary() {
+        assert!(is_within("/music/album/a.flac", "/music/album"));
+        assert!(is_within("/music/album", "/music/album/"));
+        assert!(!is_within("/music/album2/a.flac", "/music/album"));
+        assert!(!is_within("/music/a.flac", ""));
+    }
+
+    #[test]
+    fn display_path_strips_extended_prefixes() {
+        assert_eq!(display_path(r"\\?\C:\Music\a.flac"), PathBuf::from(r"C:\Music\a.flac"));
+        assert_eq!(display_path(r"\\?\UNC\nas\music\a.flac"), PathBuf::from(r"\\nas\music\a.flac"));
+        assert_eq!(display_path("/music/a.flac"), PathBuf::from("/music/a.flac"));
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn long_path_is_unchanged_elsewhere() {
+        let long = format!("/music/{}", "a".repeat(400));
+        assert_eq!(long_path(&long), PathBuf::from(&long));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn long_path_adds_the_extended_prefix_to_long_paths() {
+        let name = "a".repeat(300);
+        assert_eq!(long_path(r"C:\Music\a.flac"), PathBuf::from(r"C:\Music\a.flac"));
+        // 扩展长度形式不会被系统规范化，`/` 和 `.` 需要事先处理
+        assert_eq!(long_path(format!(r"C:\Music/./{name}")), PathBuf::from(format!(r"\\?\C:\Music\{name}")));
+        assert_eq!(long_path(format!(r"\\nas\music\{name}")), PathBuf::from(format!(r"\\?\UNC\nas\music\{name}")));
+        let prefixed = format!(r"\\?\C:\{name}");
+        assert_eq!(long_path(&prefixed), PathBuf::from(&prefixed));
+        let original = format!(r"C:\Music\{name}");
+        assert_eq!(display_path(long_path(&original)), PathBuf::from(&original));
+    }
+
+    /// 1 秒、8 kHz、单声道、16 位的静音 WAV
+    #[cfg(windows)]
+    fn silent_wav() -> Vec<u8> {
+        let data_len: u32 = 16_000;
+        let mut wav = Vec::new();
+        wav.extend(b"RIFF");
+        wav.extend((36 + data_len).to_le_bytes());
+        wav.extend(b"WAVEfmt ");
+        wav.extend(16u32.to_le_bytes());
+        wav.extend(1u16.to_le_bytes());
+        wav.extend(1u16.to_le_bytes());
+        wav.extend(8_000u32.to_le_bytes());
+        wav.extend(16_000u32.to_le_bytes());
+        wav.extend(2u16.to_le_bytes());
+        wav.extend(16u16.to_le_bytes());
+        wav.extend(b"data");
+        wav.extend(data_len.to_le_bytes());
+        wav.resize(wav.len() + data_len as usize, 0);
+        wav
+    }
+
+    /// 完整路径超过 300 个字符的音轨可以被扫描、读取元数据和打开播放
+    #[cfg(windows)]
+    #[test]
+    fn files_under_long_paths_can_be_scanned_read_and_played() {
+        use crate::audio::decoder::SymphoniaDecoder;
+        use crate::media::filesystem::collect_audio_paths;
+        use crate::media::metadata::{get_track_metadata_with_options, MetadataOptions};
+
+        let root = tempfile::TempDir::new().unwrap();
+        let mut dir = root.path().to_path_buf();
+        while dir.as_os_str().len() < 300 {
+            dir.push("Symphony No. 9 in D minor, Op. 125 - Choral");
+        }
+        fs::create_dir_all(long_path(&dir)).unwrap();
+        let file = dir.join("01 Allegro ma non troppo.wav");
+        fs::write(long_path(&file), silent_wav()).unwrap();
+        let path = file.to_string_lossy().to_string();
+        assert!(path.len() > 300);
+
+        let scanned = collect_audio_paths(&[root.path().to_string_lossy().to_string()]);
+        assert_eq!(scanned.len(), 1);
+        assert!(same_path(&scanned[0], &path));
+
+        let metadata = get_track_metadata_with_options(&path, &MetadataOptions::default()).unwrap();
+        assert_eq!(metadata.tag_error, None);
+        assert_eq!(metadata.sample_rate, Some(8_000));
+
+        let mut decoder = SymphoniaDecoder::new(&path).unwrap();
+        decoder.prefill_buffer().unwrap();
+    }
+}
@@ -0,0 +1,614 @@
+//! 开机启动
+//!
+//! 登录时启动程序的启动项按平台写在不同位置：
+//!
+//! - Windows：注册表 `HKCU\Software\Microsoft\Windows\CurrentVersion\Run` 中的值
+//! - macOS：`~/Library/LaunchAgents` 中的 LaunchAgent plist
+//! - Linux：XDG 自动启动目录（`~/.config/autostart`）中的 `.desktop` 文件
+//!
+//! 启动项指向当前的程序，可以带 `--minimized` 参数：程序没有托盘图标，带此参数启动时主窗口最小化，
+//! 不抢占焦点，再次启动程序时由单实例恢复窗口。开关状态以启动项本身为准，不另存配置。
+//! 程序被移动或更新到其他位置后启动项会指向旧的路径，读取状态时报告为需要修复，重新写入即可。
+
+use serde::Serialize;
+use std::io;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+/// 最小化启动的命令行参数
+pub const MINIMIZED_ARG: &str = "--minimized";
+
+/// 启动项的内容
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Entry {
+    program: PathBuf,
+    minimized: bool,
+}
+
+/// `get_autostart_status` 的结果
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AutostartStatus {
+    /// 当前平台是否支持开机启动
+    pub supported: bool,
+    /// 启动项是否存在
+    pub enabled: bool,
+    /// 是否最小化启动
+    pub minimized: bool,
+    /// 启动项指向的程序
+    pub registered_path: Option<String>,
+    /// 当前程序的路径
+    pub current_path: Option<String>,
+    /// 启动项指向的不是当前程序（程序被移动或更新后），需要重新写入
+    pub stale: bool,
+}
+
+fn invalid_path(path: &Path) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("Program path is not valid UTF-8: {}", path.display()))
+}
+
+#[cfg(windows)]
+mod platform {
+    use super::{invalid_path, Entry, MINIMIZED_ARG};
+    use std::io;
+    use std::path::PathBuf;
+    use winreg::enums::{HKEY_CURRENT_USER, KEY_READ, KEY_SET_VALUE};
+    use winreg::RegKey;
+
+    const RUN_KEY: &str = r"Software\Microsoft\Windows\CurrentVersion\Run";
+    const VALUE_NAME: &str = "Mercurial Player";
+
+    /// 启动项的命令行；程序路径总是加引号，安装目录中有空格时也能正确解析（Windows 路径中不能有引号）
+    fn command_line(entry: &Entry) -> io::Result<String> {
+        let program = entry.program.to_str().ok_or_else(|| invalid_path(&entry.program))?;
+        let mut command = format!("\"{program}\"");
+        if entry.minimized {
+            command.push(' ');
+            command.push_str(MINIMIZED_ARG);
+        }
+        Ok(command)
+    }
+
+    /// 解析命令行：带引号的程序路径取到下一个引号，不带引号的取到第一个空格
+    fn parse_command_line(command: &str) -> Option<Entry> {
+        let command = command.trim();
+        let (program, rest) = match command.strip_prefix('"') {
+            Some(quoted) => {
+                let end = quoted.find('"')?;
+                (&quoted[..end], &quoted[end + 1..])
+            }
+            None => command.split_once(' ').unwrap_or((command, "")),
+        };
+        if program.is_empty() {
+            return None;
+        }
+        Some(Entry {
+            program: PathBuf::from(program),
+            minimized: rest.split_whitespace().any(|arg| arg == MINIMIZED_ARG),
+        })
+    }
+
+    pub fn read() -> io::Result<Option<Entry>> {
+        let run = RegKey::predef(HKEY_CURRENT_USER).open_subkey_with_flags(RUN_KEY, KEY_READ)?;
+        match run.get_value::<String, _>(VALUE_NAME) {
+            Ok(command) => Ok(parse_command_line(&command)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn write(entry: &Entry) -> io::Result<()> {
+        let (run, _) = RegKey::predef(HKEY_CURRENT_USER).create_subkey(RUN_KEY)?;
+        run.set_value(VALUE_NAME, &command_line(entry)?)
+    }
+
+    pub fn remove() -> io::Result<()> {
+        let run = RegKey::predef(HKEY_CURRENT_USER).open_subkey_with_flags(RUN_KEY, KEY_SET_VALUE)?;
+        match run.delete_value(VALUE_NAME) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn command_lines_round_trip() {
+            let programs = [
+                r"C:\Program Files\Mercurial Player\mercurial-player.exe",
+                r"D:\音乐 软件\Mercurial Player (x64)\播放器.exe",
+                r"C:\Users\Zoë\AppData\Local\Programs\mercurial-player.exe",
+            ];
+            for program in programs {
+                for minimized in [false, true] {
+                    let entry = Entry { program: PathBuf::from(program), minimized };
+                    let command = command_line(&entry).unwrap();
+                    assert!(command.starts_with(&format!("\"{program}\"")), "{command}");
+                    assert_eq!(parse_command_line(&command), Some(entry));
+                }
+            }
+        }
+
+        #[test]
+        fn command_lines_written_by_other_tools_are_parsed() {
+            let entry = parse_command_line(r"C:\Apps\player.exe --minimized").unwrap();
+            assert_eq!(entry.program, PathBuf::from(r"C:\Apps\player.exe"));
+            assert!(entry.minimized);
+            let entry = parse_command_line(r#"  "C:\Program Files\player.exe"--minimized "#).unwrap();
+            assert_eq!(entry.program, PathBuf::from(r"C:\Program Files\player.exe"));
+            assert!(entry.minimized);
+            assert_eq!(parse_command_line(r#""C:\Program Files\player.exe"#), None);
+            assert_eq!(parse_command_line(r#""""#), None);
+            assert_eq!(parse_command_line(""), None);
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::{invalid_path, Entry, MINIMIZED_ARG};
+    use std::io;
+    use std::path::PathBuf;
+
+    /// LaunchAgent 的标识，与程序的 identifier 相同
+    const LABEL: &str = "com.jdbewl.mercurial-player";
+
+    fn plist_path() -> io::Result<PathBuf> {
+        let home =
+            dirs::home_dir().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Home directory not found"))?;
+        Ok(home.join("Library").join("LaunchAgents").join(format!("{LABEL}.plist")))
+    }
+
+    /// 转义 XML 文本中的特殊字符
+    fn escape_xml(text: &str) -> String {
+        let mut escaped = String::with_capacity(text.len());
+        for c in text.chars() {
+            match c {
+                '&' => escaped.push_str("&amp;"),
+                '<' => escaped.push_str("&lt;"),
+                '>' => escaped.push_str("&gt;"),
+                '"' => escaped.push_str("&quot;"),
+                '\'' => escaped.push_str("&apos;"),
+                _ => escaped.push(c),
+            }
+        }
+        escaped
+    }
+
+    /// 还原 XML 文本，包括 `&#...;` 形式的字符引用（其他工具改写 plist 时可能使用）
+    fn unescape_xml(text: &str) -> String {
+        let mut unescaped = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(start) = rest.find('&') {
+            unescaped.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let Some(end) = rest.find(';') else { break };
+            let decoded = match &rest[1..end] {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                reference => reference
+                    .strip_prefix('#')
+                    .and_then(|number| match number.strip_prefix('x') {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                        None => number.parse().ok(),
+                    })
+                    .and_then(char::from_u32),
+            };
+            match decoded {
+                Some(c) => {
+                    unescaped.push(c);
+                    rest = &rest[end + 1..];
+                }
+                None => {
+                    unescaped.push('&');
+                    rest = &rest[1..];
+                }
+            }
+        }
+        unescaped.push_str(rest);
+        unescaped
+    }
+
+    fn plist(entry: &Entry) -> io::Result<String> {
+        let program = entry.program.to_str().ok_or_else(|| invalid_path(&entry.program))?;
+        let mut arguments = format!("        <string>{}</string>\n", escape_xml(program));
+        if entry.minimized {
+            arguments.push_str(&format!("        <string>{MINIMIZED_ARG}</string>\n"));
+        }
+        Ok(format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{LABEL}</string>
+    <key>ProgramArguments</key>
+    <array>
+{arguments}    </array>
+    <key>RunAtLoad</key>
+    <true/>
+</dict>
+</plist>
+"#
+        ))
+    }
+
+    /// 读取 `ProgramArguments` 数组：第一项是程序路径，其余是参数
+    fn parse_plist(content: &str) -> Option<Entry> {
+        let after_key = &content[content.find("<key>ProgramArguments</key>")?..];
+        let array = &after_key[after_key.find("<array>")?..];
+        let array = &array[..array.find("</array>")?];
+        let mut arguments = array
+            .split("<string>")
+            .skip(1)
+            .filter_map(|item| item.split_once("</string>").map(|(value, _)| unescape_xml(value)));
+        let program = arguments.next().filter(|program| !program.is_empty())?;
+        Some(Entry { program: PathBuf::from(program), minimized: arguments.any(|arg| arg == MINIMIZED_ARG) })
+    }
+
+    pub fn read() -> io::Result<Option<Entry>> {
+        match std::fs::read_to_string(plist_path()?) {
+            Ok(content) => Ok(parse_plist(&content)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn write(entry: &Entry) -> io::Result<()> {
+        let path = plist_path()?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, plist(entry)?)
+    }
+
+    pub fn remove() -> io::Result<()> {
+        match std::fs::remove_file(plist_path()?) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn xml_escaping_round_trips() {
+            let text = r#"/Applications/Tom & Jerry's <"Player">/メルクリウス"#;
+            let escaped = escape_xml(text);
+            assert_eq!(escaped, "/Applications/Tom &amp; Jerry&apos;s &lt;&quot;Player&quot;&gt;/メルクリウス");
+            assert_eq!(unescape_xml(&escaped), text);
+        }
+
+        #[test]
+        fn character_references_are_decoded() {
+            assert_eq!(unescape_xml("A&#38;B&#x26;C&#x30e1;"), "A&B&Cメ");
+            // 无法识别的引用和单独的 `&` 原样保留
+            assert_eq!(unescape_xml("a &unknown; b & c"), "a &unknown; b & c");
+            assert_eq!(unescape_xml("a &#xZZ; &"), "a &#xZZ; &");
+        }
+
+        #[test]
+        fn plists_round_trip() {
+            let programs = [
+                "/Applications/Mercurial Player.app/Contents/MacOS/mercurial-player",
+                "/Users/zoë/Applications/音乐 & 播放器.app/Contents/MacOS/mercurial-player",
+            ];
+            for program in programs {
+                for minimized in [false, true] {
+                    let entry = Entry { program: PathBuf::from(program), minimized };
+                    assert_eq!(parse_plist(&plist(&entry).unwrap()), Some(entry));
+                }
+            }
+        }
+
+        #[test]
+        fn plists_without_arguments_are_ignored() {
+            assert_eq!(parse_plist("<plist><dict><key>Label</key></dict></plist>"), None);
+            let empty = "<key>ProgramArguments</key>\n<array>\n<string></string>\n</array>";
+            assert_eq!(parse_plist(empty), None);
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::{invalid_path, Entry, MINIMIZED_ARG};
+    use std::io;
+    use std::path::PathBuf;
+
+    const FILE_NAME: &str = "mercurial-player.desktop";
+    /// `Exec` 中需要加引号的字符（Desktop Entry 规范中的保留字符）
+    const RESERVED: &str = " \t\n\"'\\><~|&;$*?#()`";
+
+    fn desktop_path() -> io::Result<PathBuf> {
+        let config =
+            dirs::config_dir().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Config directory not found"))?;
+        Ok(config.join("autostart").join(FILE_NAME))
+    }
+
+    /// `Exec` 中的一个参数：含保留字符时加双引号，引号内的 `"`、`` ` ``、`$`、`\` 前加反斜杠；
+    /// `%` 写成 `%%`，避免被当作字段代码
+    fn quote_arg(arg: &str) -> String {
+        let arg = arg.replace('%', "%%");
+        if !arg.is_empty() && !arg.chars().any(|c| RESERVED.contains(c)) {
+            return arg;
+        }
+        let mut quoted = String::with_capacity(arg.len() + 2);
+        quoted.push('"');
+        for c in arg.chars() {
+            if matches!(c, '"' | '`' | '$' | '\\') {
+                quoted.push('\\');
+            }
+            quoted.push(c);
+        }
+        quoted.push('"');
+        quoted
+    }
+
+    /// 写入文件的字符串值还要再转义一次反斜杠和换行
+    fn escape_value(value: &str) -> String {
+        value.replace('\\', "\\\\").replace('\n', "\\n").replace('\t', "\\t").replace('\r', "\\r")
+    }
+
+    fn unescape_value(value: &str) -> String {
+        let mut unescaped = String::with_capacity(value.len());
+        let mut chars = value.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                unescaped.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('s') => unescaped.push(' '),
+                Some('n') => unescaped.push('\n'),
+                Some('t') => unescaped.push('\t'),
+                Some('r') => unescaped.push('\r'),
+                Some(other) => unescaped.push(other),
+                None => unescaped.push('\\'),
+            }
+        }
+        unescaped
+    }
+
+    /// `Exec` 的值（已还原字符串转义）拆分为参数，去掉 `%f` 等字段代码
+    fn split_exec(exec: &str) -> Vec<String> {
+        let mut args = Vec::new();
+        let mut current: Option<String> = None;
+        let mut chars = exec.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => {
+                    let arg = current.get_or_insert_with(String::new);
+                    while let Some(c) = chars.next() {
+                        match c {
+                            '"' => break,
+                            '\\' => arg.extend(chars.next()),
+                            _ => arg.push(c),
+                        }
+                    }
+                }
+                ' ' | '\t' => args.extend(current.take()),
+                _ => current.get_or_insert_with(String::new).push(c),
+            }
+        }
+        args.extend(current);
+        args.into_iter()
+            .filter(|arg| !(arg.len() == 2 && arg.starts_with('%') && arg != "%%"))
+            .map(|arg| arg.replace("%%", "%"))
+            .collect()
+    }
+
+    fn exec_line(entry: &Entry) -> io::Result<String> {
+        let program = entry.program.to_str().ok_or_else(|| invalid_path(&entry.program))?;
+        let mut exec = quote_arg(program);
+        if entry.minimized {
+            exec.push(' ');
+            exec.push_str(MINIMIZED_ARG);
+        }
+        Ok(escape_value(&exec))
+    }
+
+    fn desktop_file(entry: &Entry) -> io::Result<String> {
+        Ok(format!(
+            "[Desktop Entry]\nType=Application\nName=Mercurial Player\nExec={}\nTerminal=false\n\
+             X-GNOME-Autostart-enabled=true\n",
+            exec_line(entry)?
+        ))
+    }
+
+    /// 读取 `[Desktop Entry]` 中的 `Exec`；`Hidden=true` 表示用户在桌面环境中关闭了启动项
+    fn parse_desktop_file(content: &str) -> Option<Entry> {
+        let mut in_entry = false;
+        let mut exec = None;
+        for line in content.lines().map(str::trim) {
+            if line.starts_with('[') {
+                in_entry = line == "[Desktop Entry]";
+                continue;
+            }
+            if !in_entry {
+                continue;
+            }
+            if let Some((key, value)) = line.split_once('=') {
+                match key.trim() {
+                    "Exec" => exec = Some(unescape_value(value.trim())),
+                    "Hidden" if value.trim() == "true" => return None,
+                    _ => {}
+                }
+            }
+        }
+        let mut args = split_exec(&exec?).into_iter();
+        let program = args.next()?;
+        Some(Entry { program: PathBuf::from(program), minimized: args.any(|arg| arg == MINIMIZED_ARG) })
+    }
+
+    pub fn read() -> io::Result<Option<Entry>> {
+        match std::fs::read_to_string(desktop_path()?) {
+            Ok(content) => Ok(parse_desktop_file(&content)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn write(entry: &Entry) -> io::Result<()> {
+        let path = desktop_path()?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, desktop_file(entry)?)
+    }
+
+    pub fn remove() -> io::Result<()> {
+        match std::fs::remove_file(desktop_path()?) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn exec_arguments_round_trip() {
+            let args = [
+                "/usr/bin/mercurial-player",
+                "/opt/Mercurial Player/mercurial-player",
+                "/home/用户/应用 程序/播放器.AppImage",
+                "/home/me/100% \"loud\" $HOME `x` \\ 'q'/player",
+                "/home/me/100%/player",
+            ];
+            for arg in args {
+                let quoted = quote_arg(arg);
+                assert_eq!(split_exec(&quoted), [arg], "{quoted}");
+            }
+            assert_eq!(quote_arg("/usr/bin/player"), "/usr/bin/player");
+            assert_eq!(quote_arg("/opt/My Player/player"), "\"/opt/My Player/player\"");
+        }
+
+        #[test]
+        fn desktop_files_round_trip() {
+            let programs = ["/opt/Mercurial Player/mercurial-player", r"/home/zoë/音乐\播放器/player.AppImage"];
+            for program in programs {
+                for minimized in [false, true] {
+                    let entry = Entry { program: PathBuf::from(program), minimized };
+                    assert_eq!(parse_desktop_file(&desktop_file(&entry).unwrap()), Some(entry));
+                }
+            }
+        }
+
+        #[test]
+        fn exec_lines_written_by_other_tools_are_parsed() {
+            let content = "[Desktop Entry]\nExec=\"/opt/My Player/player\" %U --minimized\n\
+                           [Desktop Action New]\nExec=/usr/bin/other\n";
+            let entry = parse_desktop_file(content).unwrap();
+            assert_eq!(entry.program, PathBuf::from("/opt/My Player/player"));
+            assert!(entry.minimized);
+            assert_eq!(parse_desktop_file("[Desktop Entry]\nExec=/usr/bin/player\nHidden=true\n"), None);
+            assert_eq!(parse_desktop_file("[Desktop Entry]\nName=Player\n"), None);
+        }
+    }
+}
+
+#[cfg(not(any(windows, target_os = "macos", target_os = "linux")))]
+#[allow(clippy::unnecessary_wraps)] // 与其他平台的签名保持一致
+mod platform {
+    use super::Entry;
+    use std::io;
+
+    fn unsupported() -> io::Error {
+        io::Error::new(io::ErrorKind::Unsupported, "Autostart is not supported on this platform")
+    }
+
+    pub fn read() -> io::Result<Option<Entry>> {
+        Ok(None)
+    }
+
+    pub fn write(_entry: &Entry) -> io::Result<()> {
+        Err(unsupported())
+    }
+
+    pub fn remove() -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// 启动项应指向的程序：AppImage 运行时可执行文件在每次挂载的临时目录中，改用 AppImage 文件本身
+fn current_program() -> io::Result<PathBuf> {
+    #[cfg(target_os = "linux")]
+    {
+        if let Some(appimage) = std::env::var_os("APPIMAGE").filter(|path| !path.is_empty()) {
+            return Ok(PathBuf::from(appimage));
+        }
+    }
+    std::env::current_exe()
+}
+
+/// 启动项是否指向当前程序；文件不存在时视为旧路径
+fn same_program(registered: &Path, current: &Path) -> bool {
+    match (registered.canonicalize(), current.canonicalize()) {
+        (Ok(registered), Ok(current)) => registered == current,
+        (Err(_), _) => false,
+        (Ok(_), Err(_)) => registered == current,
+    }
+}
+
+/// 启动项的当前状态
+pub fn status() -> io::Result<AutostartStatus> {
+    let supported = cfg!(any(windows, target_os = "macos", target_os = "linux"));
+    let current = current_program().ok();
+    let entry = platform::read()?;
+    Ok(AutostartStatus {
+        supported,
+        enabled: entry.is_some(),
+        minimized: entry.as_ref().is_some_and(|entry| entry.minimized),
+        registered_path: entry.as_ref().map(|entry| entry.program.to_string_lossy().to_string()),
+        current_path: current.as_ref().map(|path| path.to_string_lossy().to_string()),
+        stale: entry.as_ref().is_some_and(|entry| current.as_ref().is_none_or(|c| !same_program(&entry.program, c))),
+    })
+}
+
+/// 写入或删除启动项，返回写入后的状态；已有的启动项指向旧路径时写入即修复
+pub fn set(enabled: bool, minimized: bool) -> io::Result<AutostartStatus> {
+    if enabled {
+        platform::write(&Entry { program: current_program()?, minimized })?;
+    } else {
+        platform::remove()?;
+    }
+    status()
+}
+
+/// 启动参数中是否要求最小化启动
+#[must_use]
+pub fn is_minimized_launch(args: &[String]) -> bool {
+    args.iter().skip(1).any(|arg| arg == MINIMIZED_ARG)
+}
+
+/// 最小化启动时把主窗口最小化
+pub fn apply_launch_args(app: &AppHandle, args: &[String]) {
+    if is_minimized_launch(args)
+        && let Some(window) = app.get_webview_window("main")
+    {
+        let _ = window.minimize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn minimized_flag_is_read_from_arguments() {
+        let args = |list: &[&str]| list.iter().map(ToString::to_string).collect::<Vec<_>>();
+        assert!(is_minimized_launch(&args(&["player", "--minimized"])));
+        assert!(!is_minimized_launch(&args(&["player", "/music/a.flac"])));
+        // 第一个参数是程序本身
+        assert!(!is_minimized_launch(&args(&["--minimized"])));
+    }
+}
@@ -2,8 +2,17 @@
 //!
 //! 包含系统信息获取和窗口管理功能。
 
-use std::collections::HashMap;
-use tauri::{command, AppHandle, LogicalSize, Manager, Size};
+use super::autostart::{self, AutostartStatus};
+use super::diagnostics::{self, SystemInfo};
+use super::file_manager;
+use super::launch::{take_pending, OpenFilesEvent};
+use super::power::{self, PowerStatus};
+use super::theme::{system_accent_color, system_theme, SystemAccentColor, SystemTheme};
+use crate::error::AppResult;
+use crate::tasks::{run_blocking, TaskStatus};
+use crate::AppState;
+use tauri::{command, AppHandle, LogicalSize, Manager, Size, State};
+use tauri_plugin_clipboard_manager::ClipboardExt;
 
 /// 迷你模式窗口尺寸
 const MINI_SIZE: LogicalSize<f64> = LogicalSize { width: 300.0, height: 100.0 };
@@ -12,20 +21,18 @@ const DEFAULT_SIZE: LogicalSize<f64> = LogicalSize { width: 1250.0, height: 720.
 /// 最小窗口尺寸
 const MIN_SIZE: LogicalSize<f64> = LogicalSize { width: 1200.0, height: 700.0 };
 
-/// 获取系统信息
+/// 获取系统信息：程序版本、音频输出、内存、数据目录、权限、语言和主题，供“关于”页面显示
 #[command]
-pub fn get_system_info() -> Result<HashMap<String, String>, String> {
-    let mut info = HashMap::new();
-
-    info.insert("os".to_string(), std::env::consts::OS.to_string());
-    info.insert("arch".to_string(), std::env::consts::ARCH.to_string());
-    info.insert("family".to_string(), std::env::consts::FAMILY.to_string());
-
-    if let Some(music_dir) = dirs::audio_dir() {
-        info.insert("music_dir".to_string(), music_dir.to_string_lossy().to_string());
-    }
+pub async fn get_system_info(app: AppHandle) -> AppResult<SystemInfo> {
+    diagnostics::collect(&app).await
+}
 
-    Ok(info)
+/// 把系统信息以文本形式复制到剪贴板，附在问题报告中；返回复制的文本
+#[command]
+pub async fn copy_diagnostics_to_clipboard(app: AppHandle) -> AppResult<String> {
+    let text = diagnostics::collect(&app).await?.to_string();
+    app.clipboard().write_text(text.clone()).map_err(|e| e.to_string())?;
+    Ok(text)
 }
 
 /// 获取系统可用的字体列表
@@ -50,6 +57,77 @@ pub fn get_system_fonts() -> Result<Vec<String>, String> {
     Ok(fonts)
 }
 
+/// 取消正在运行的后台任务
+///
+/// 返回任务是否存在。
+#[command]
+pub fn cancel_task(state: State<AppState>, task_id: String) -> bool {
+    state.tasks.cancel(&task_id)
+}
+
+/// 正在运行的任务的状态（是否因使用电池暂停），任务不存在或已结束时返回 `None`
+#[command]
+pub fn get_task_status(state: State<AppState>, task_id: String) -> Option<TaskStatus> {
+    state.tasks.status(&task_id)
+}
+
+/// 所有正在运行的任务的状态
+#[command]
+pub fn get_running_tasks(state: State<AppState>) -> Vec<TaskStatus> {
+    state.tasks.statuses()
+}
+
+/// 取走启动参数中要打开的音轨，前端的播放器初始化完成后调用；之后再调用返回空列表
+#[command]
+pub async fn take_launch_files(app: AppHandle) -> AppResult<OpenFilesEvent> {
+    run_blocking(move || Ok(take_pending(&app))).await
+}
+
+/// 系统的深色/浅色设置：读取失败或系统没有偏好时为 `unknown`，当前平台无法读取时为 `unsupported`
+#[command]
+pub async fn get_system_theme(app: AppHandle) -> SystemTheme {
+    system_theme(&app).await
+}
+
+/// 系统强调色（`#rrggbb`），供前端生成配色；当前平台没有强调色时为 `unsupported`
+#[command]
+pub async fn get_system_accent_color(app: AppHandle) -> SystemAccentColor {
+    system_accent_color(&app).await
+}
+
+/// 开机启动的状态：启动项是否存在、是否最小化启动，以及是否指向旧的程序路径（需要修复）
+#[command]
+pub async fn get_autostart_status() -> AppResult<AutostartStatus> {
+    run_blocking(|| Ok(autostart::status()?)).await
+}
+
+/// 开启或关闭开机启动，返回写入后的状态；开启时总是指向当前程序，启动项指向旧路径时也用它修复
+#[command]
+pub async fn set_autostart(enabled: bool, minimized: bool) -> AppResult<AutostartStatus> {
+    run_blocking(move || Ok(autostart::set(enabled, minimized)?)).await
+}
+
+/// 电源状态：交流电还是电池、剩余电量，以及是否正在减少后台工作
+#[command]
+pub async fn get_power_status() -> AppResult<PowerStatus> {
+    run_blocking(|| Ok(power::current_status())).await
+}
+
+/// 在系统文件管理器中显示文件并选中（Linux 上文件管理器不支持时打开所在目录）；文件不存在时返回 `FileNotFound` 错误
+#[command]
+pub async fn show_in_file_manager(path: String) -> AppResult<()> {
+    file_manager::show_in_file_manager(&path).await
+}
+
+/// 把主窗口带到前台（最小化或隐藏时恢复）
+pub fn raise_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
 /// 设置迷你模式
 #[command]
 pub async fn set_mini_mode(app_handle: AppHandle, enable: bool) -> Result<(), String> {
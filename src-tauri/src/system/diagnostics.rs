@@ -0,0 +1,204 @@
+//! 诊断信息
+//!
+//! 汇总程序版本、音频输出、内存、数据目录、权限、语言和主题等信息，供“关于”页面显示，也可以复制为文本
+//! 附在问题报告中。文本版固定使用英文，方便维护者阅读。
+
+use super::theme::{system_theme, SystemTheme};
+use crate::error::AppResult;
+use crate::i18n::{self, Language};
+use crate::tasks::run_blocking;
+use crate::AppState;
+use serde::Serialize;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind};
+use tauri::{AppHandle, Manager};
+use walkdir::WalkDir;
+
+/// 构建时的 Git 提交（见 `build.rs`），不在 Git 仓库中构建时为 `unknown`
+const BUILD_HASH: &str = match option_env!("MERCURIAL_BUILD_HASH") {
+    Some(hash) => hash,
+    None => "unknown",
+};
+
+/// 音频输出方式
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum OutputMode {
+    /// 经系统混音器输出
+    Shared,
+    /// WASAPI 独占模式
+    Exclusive,
+}
+
+/// 诊断信息
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemInfo {
+    pub os: String,
+    /// 系统的名称和版本（如 `Windows 11 (26100)`），读取失败时为空
+    pub os_version: Option<String>,
+    pub arch: String,
+    pub family: String,
+    pub music_dir: Option<String>,
+    pub app_version: String,
+    /// 构建时的 Git 提交
+    pub build_hash: String,
+    /// 使用的 cpal 音频后端（如 `WASAPI`、`ALSA`、`CoreAudio`）
+    pub audio_host: String,
+    /// 当前的输出设备
+    pub output_device: String,
+    pub output_mode: OutputMode,
+    /// 系统内存总量（字节）
+    pub total_memory: u64,
+    /// 系统可用内存（字节）
+    pub available_memory: u64,
+    /// 本进程占用的物理内存（字节）
+    pub process_memory: Option<u64>,
+    /// 程序数据目录（配置、数据和缓存所在的目录）
+    pub app_data_dir: String,
+    /// 程序数据目录中所有文件的大小之和（字节）
+    pub app_data_size: u64,
+    /// 是否以管理员（root）权限运行，当前平台无法判断时为空
+    pub elevated: Option<bool>,
+    /// 系统语言（BCP-47 标签）
+    pub locale: String,
+    /// 后端文字使用的语言
+    pub language: Language,
+    pub theme: SystemTheme,
+}
+
+#[cfg(windows)]
+#[allow(unsafe_code)]
+fn is_elevated(_system: &System, _pid: Option<sysinfo::Pid>) -> Option<bool> {
+    // 只读取当前进程令牌的成员信息
+    Some(unsafe { windows::Win32::UI::Shell::IsUserAnAdmin() }.as_bool())
+}
+
+#[cfg(unix)]
+fn is_elevated(system: &System, pid: Option<sysinfo::Pid>) -> Option<bool> {
+    let uid = system.process(pid?)?.effective_user_id()?;
+    Some(**uid == 0)
+}
+
+#[cfg(not(any(windows, unix)))]
+fn is_elevated(_system: &System, _pid: Option<sysinfo::Pid>) -> Option<bool> {
+    None
+}
+
+/// 目录中所有文件的大小之和，无法读取的文件忽略
+fn directory_size(dir: &Path) -> u64 {
+    WalkDir::new(dir)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| entry.metadata().ok())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+fn collect_blocking(app: &AppHandle, theme: SystemTheme) -> SystemInfo {
+    let state = app.state::<AppState>();
+    let output_device = state.player.current_device_name.lock().unwrap().clone();
+    let output_mode =
+        if *state.player.exclusive_mode.lock().unwrap() { OutputMode::Exclusive } else { OutputMode::Shared };
+    let app_data_dir = Path::new(state.config_manager.get_config_directory())
+        .parent()
+        .map_or_else(|| PathBuf::from("."), Path::to_path_buf);
+
+    let mut system = System::new();
+    system.refresh_memory();
+    let pid = sysinfo::get_current_pid().ok();
+    if let Some(pid) = pid {
+        system.refresh_processes_specifics(
+            ProcessesToUpdate::Some(&[pid]),
+            true,
+            ProcessRefreshKind::nothing().with_memory().with_user(UpdateKind::OnlyIfNotSet),
+        );
+    }
+
+    SystemInfo {
+        os: std::env::consts::OS.to_string(),
+        os_version: System::long_os_version(),
+        arch: std::env::consts::ARCH.to_string(),
+        family: std::env::consts::FAMILY.to_string(),
+        music_dir: dirs::audio_dir().map(|dir| dir.to_string_lossy().to_string()),
+        app_version: app.package_info().version.to_string(),
+        build_hash: BUILD_HASH.to_string(),
+        audio_host: cpal::default_host().id().name().to_string(),
+        output_device,
+        output_mode,
+        total_memory: system.total_memory(),
+        available_memory: system.available_memory(),
+        process_memory: pid.and_then(|pid| system.process(pid)).map(sysinfo::Process::memory),
+        app_data_size: directory_size(&app_data_dir),
+        app_data_dir: app_data_dir.to_string_lossy().to_string(),
+        elevated: is_elevated(&system, pid),
+        locale: i18n::system_locale(),
+        language: i18n::current(),
+        theme,
+    }
+}
+
+/// 读取诊断信息；统计数据目录大小需要遍历目录，在阻塞线程池中执行
+pub async fn collect(app: &AppHandle) -> AppResult<SystemInfo> {
+    let theme = system_theme(app).await;
+    let app = app.clone();
+    run_blocking(move || Ok(collect_blocking(&app, theme))).await
+}
+
+/// 以 1024 为进制显示字节数（如 `1.5 GiB`）
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    #[allow(clippy::cast_precision_loss)]
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 { format!("{bytes} B") } else { format!("{value:.1} {}", UNITS[unit]) }
+}
+
+impl fmt::Display for SystemInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let unknown = "unknown";
+        writeln!(f, "Mercurial Player {} ({})", self.app_version, self.build_hash)?;
+        writeln!(
+            f,
+            "OS: {} ({}, {}, {})",
+            self.os_version.as_deref().unwrap_or(unknown),
+            self.os,
+            self.family,
+            self.arch
+        )?;
+        let mode = match self.output_mode {
+            OutputMode::Shared => "shared",
+            OutputMode::Exclusive => "exclusive",
+        };
+        writeln!(f, "Audio: {} / {} ({mode})", self.audio_host, self.output_device)?;
+        writeln!(
+            f,
+            "Memory: {} available of {}, process {}",
+            format_bytes(self.available_memory),
+            format_bytes(self.total_memory),
+            self.process_memory.map_or_else(|| unknown.to_string(), format_bytes)
+        )?;
+        writeln!(f, "Data directory: {} ({})", self.app_data_dir, format_bytes(self.app_data_size))?;
+        writeln!(f, "Music directory: {}", self.music_dir.as_deref().unwrap_or(unknown))?;
+        let elevated = match self.elevated {
+            Some(true) => "yes",
+            Some(false) => "no",
+            None => unknown,
+        };
+        writeln!(f, "Elevated: {elevated}")?;
+        writeln!(f, "Locale: {} (interface {})", self.locale, self.language.tag())?;
+        let theme = match self.theme {
+            SystemTheme::Dark => "dark",
+            SystemTheme::Light => "light",
+            SystemTheme::Unknown => unknown,
+            SystemTheme::Unsupported => "unsupported",
+        };
+        write!(f, "Theme: {theme}")
+    }
+}
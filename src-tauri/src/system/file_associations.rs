@@ -0,0 +1,166 @@
+//! “打开方式”中的文件关联
+//!
+//! 设置中开启后，把支持的音频扩展名和播放列表扩展名登记到系统的“打开方式”列表中，不会抢占其他程序的
+//! 默认打开方式；关闭后删除登记。只写当前用户的配置，不需要管理员权限。
+//!
+//! - Windows：`HKEY_CURRENT_USER\Software\Classes` 下的 ProgID 和各扩展名的 `OpenWithProgids`
+//! - Linux：`~/.local/share/applications` 中一个不在菜单中显示的 `.desktop` 文件
+//! - macOS：只能在应用包的 `Info.plist` 中声明，这里不做处理
+
+use crate::media::filesystem::supported_extensions;
+
+/// 除音频扩展名外一并登记的播放列表扩展名
+const PLAYLIST_EXTENSIONS: &[&str] = &["m3u", "m3u8", "pls", "xspf"];
+
+/// 需要登记的全部扩展名
+fn extensions() -> Vec<String> {
+    let mut extensions = supported_extensions();
+    extensions.extend(PLAYLIST_EXTENSIONS.iter().map(ToString::to_string));
+    extensions
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::io;
+    use std::path::Path;
+    use winreg::enums::HKEY_CURRENT_USER;
+    use winreg::RegKey;
+
+    const PROG_ID: &str = "MercurialPlayer.AudioFile";
+    const CLASSES: &str = r"Software\Classes";
+
+    pub fn register(exe: &Path, extensions: &[String]) -> io::Result<()> {
+        let (classes, _) = RegKey::predef(HKEY_CURRENT_USER).create_subkey(CLASSES)?;
+        let (prog_id, _) = classes.create_subkey(PROG_ID)?;
+        prog_id.set_value("", &"Mercurial Player")?;
+        let (command, _) = prog_id.create_subkey(r"shell\open\command")?;
+        command.set_value("", &format!("\"{}\" \"%1\"", exe.display()))?;
+        for extension in extensions {
+            let (open_with, _) = classes.create_subkey(format!(r".{extension}\OpenWithProgids"))?;
+            open_with.set_value(PROG_ID, &"")?;
+        }
+        Ok(())
+    }
+
+    pub fn unregister(extensions: &[String]) -> io::Result<()> {
+        let (classes, _) = RegKey::predef(HKEY_CURRENT_USER).create_subkey(CLASSES)?;
+        if classes.open_subkey(PROG_ID).is_err() {
+            return Ok(());
+        }
+        classes.delete_subkey_all(PROG_ID)?;
+        for extension in extensions {
+            let key = format!(r".{extension}\OpenWithProgids");
+            if let Ok((open_with, _)) = classes.create_subkey(&key) {
+                let _ = open_with.delete_value(PROG_ID);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::fs;
+    use std::io;
+    use std::path::{Path, PathBuf};
+    use std::process::Command;
+
+    const DESKTOP_FILE: &str = "mercurial-player-open-with.desktop";
+
+    /// 扩展名对应的 MIME 类型，没有对应类型的扩展名不登记
+    fn mime_type(extension: &str) -> Option<&'static str> {
+        Some(match extension {
+            "mp3" => "audio/mpeg",
+            "flac" => "audio/flac",
+            "wav" => "audio/x-wav",
+            "ogg" | "oga" => "audio/ogg",
+            "opus" => "audio/x-opus+ogg",
+            "m4a" | "m4b" | "alac" => "audio/mp4",
+            "aac" => "audio/aac",
+            "ape" => "audio/x-ape",
+            "wv" => "audio/x-wavpack",
+            "aiff" | "aif" | "aifc" => "audio/x-aiff",
+            "wma" => "audio/x-ms-wma",
+            "dsf" => "audio/x-dsf",
+            "dff" => "audio/x-dff",
+            "mka" => "audio/x-matroska",
+            "m3u" | "m3u8" => "audio/x-mpegurl",
+            "pls" => "audio/x-scpls",
+            "xspf" => "application/xspf+xml",
+            _ => return None,
+        })
+    }
+
+    fn applications_dir() -> io::Result<PathBuf> {
+        dirs::data_dir()
+            .map(|dir| dir.join("applications"))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "data directory not found"))
+    }
+
+    /// 刷新 MIME 类型缓存，缺少该工具时文件管理器稍后也会自己读取
+    fn update_database(dir: &Path) {
+        let _ = Command::new("update-desktop-database").arg(dir).status();
+    }
+
+    pub fn register(exe: &Path, extensions: &[String]) -> io::Result<()> {
+        let mut mime_types: Vec<&str> = extensions.iter().filter_map(|ext| mime_type(ext)).collect();
+        mime_types.sort_unstable();
+        mime_types.dedup();
+        let content = format!(
+            "[Desktop Entry]\nType=Application\nName=Mercurial Player\nExec=\"{}\" %F\nMimeType={};\n\
+             NoDisplay=true\nTerminal=false\n",
+            exe.display(),
+            mime_types.join(";")
+        );
+        let dir = applications_dir()?;
+        let path = dir.join(DESKTOP_FILE);
+        if fs::read_to_string(&path).is_ok_and(|existing| existing == content) {
+            return Ok(());
+        }
+        fs::create_dir_all(&dir)?;
+        fs::write(&path, content)?;
+        update_database(&dir);
+        Ok(())
+    }
+
+    pub fn unregister(_extensions: &[String]) -> io::Result<()> {
+        let dir = applications_dir()?;
+        let path = dir.join(DESKTOP_FILE);
+        if !path.exists() {
+            return Ok(());
+        }
+        fs::remove_file(path)?;
+        update_database(&dir);
+        Ok(())
+    }
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+#[allow(clippy::unnecessary_wraps)] // 与其他平台的签名保持一致
+mod platform {
+    use std::io;
+    use std::path::Path;
+
+    pub fn register(_exe: &Path, _extensions: &[String]) -> io::Result<()> {
+        Ok(())
+    }
+
+    pub fn unregister(_extensions: &[String]) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// 按设置登记或删除文件关联，在后台线程中进行
+pub fn apply(enabled: bool) {
+    std::thread::spawn(move || {
+        let extensions = extensions();
+        let result = if enabled {
+            std::env::current_exe().and_then(|exe| platform::register(&exe, &extensions))
+        } else {
+            platform::unregister(&extensions)
+        };
+        if let Err(e) = result {
+            eprintln!("Failed to update file associations: {e}");
+        }
+    });
+}
@@ -0,0 +1,97 @@
+//! 在系统文件管理器中显示文件
+//!
+//! Windows 打开资源管理器并选中文件（`explorer /select,`），macOS 在访达中显示（`open -R`），
+//! Linux 通过 freedesktop 的 `org.freedesktop.FileManager1` D-Bus 接口选中文件，文件管理器不提供该接口时
+//! 用 `xdg-open` 打开所在目录。压缩包中的音轨显示压缩包本身。
+//!
+//! 路径以参数列表传给进程，不经过 shell；只有资源管理器自己解析命令行，需要单独加引号（见 `reveal`）。
+
+use crate::error::{AppError, AppResult};
+use crate::media::archive::split_archive_path;
+use crate::paths::{display_path, long_path};
+use crate::tasks::run_blocking;
+use crate::tr;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// 找到要显示的文件：压缩包中的音轨换成压缩包，解析为完整的绝对路径；文件不存在时返回错误
+fn resolve(path: &str) -> AppResult<PathBuf> {
+    let file = split_archive_path(path).map_or(path, |(archive, _)| archive);
+    fs::canonicalize(long_path(file)).map(display_path).map_err(|e| match e.kind() {
+        ErrorKind::NotFound => AppError::FileNotFound(path.to_string()),
+        _ => AppError::Io(e),
+    })
+}
+
+fn spawn(command: &mut Command) -> AppResult<()> {
+    command.spawn().map(drop).map_err(|e| AppError::Other(tr!("fileManager.openFailed", detail = e)))
+}
+
+/// 资源管理器按逗号分隔 `/select` 的参数，整个路径需要放在引号中；Windows 路径中不会出现引号
+#[cfg(windows)]
+fn reveal(file: &Path) -> AppResult<()> {
+    use std::os::windows::process::CommandExt;
+    spawn(Command::new("explorer").raw_arg(format!("/select,\"{}\"", file.display())))
+}
+
+#[cfg(target_os = "macos")]
+fn reveal(file: &Path) -> AppResult<()> {
+    spawn(Command::new("open").arg("-R").arg(file))
+}
+
+/// 本地文件的 `file://` URI，逐段百分号编码（路径不一定是 UTF-8）
+#[cfg(target_os = "linux")]
+fn file_uri(file: &Path) -> String {
+    use std::os::unix::ffi::OsStrExt;
+    let encoded: Vec<_> =
+        file.as_os_str().as_bytes().split(|&b| b == b'/').map(|segment| urlencoding::encode_binary(segment)).collect();
+    format!("file://{}", encoded.join("/"))
+}
+
+/// 请求文件管理器选中文件
+#[cfg(target_os = "linux")]
+async fn show_items(file: &Path) -> zbus::Result<()> {
+    let connection = zbus::Connection::session().await?;
+    connection
+        .call_method(
+            Some("org.freedesktop.FileManager1"),
+            "/org/freedesktop/FileManager1",
+            Some("org.freedesktop.FileManager1"),
+            "ShowItems",
+            &(vec![file_uri(file)], ""),
+        )
+        .await?;
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+async fn reveal(file: &Path) -> AppResult<()> {
+    if show_items(file).await.is_ok() {
+        return Ok(());
+    }
+    let dir = file.parent().unwrap_or(file);
+    spawn(Command::new("xdg-open").arg(dir))
+}
+
+#[cfg(not(any(windows, target_os = "macos", target_os = "linux")))]
+fn reveal(_file: &Path) -> AppResult<()> {
+    Err(AppError::Other(tr!("fileManager.revealUnsupported")))
+}
+
+/// 在系统文件管理器中显示文件（压缩包中的音轨显示压缩包）；文件不存在时返回 `FileNotFound`
+///
+/// 解析路径需要访问文件系统（网络共享可能很慢），在阻塞线程池中执行。
+pub async fn show_in_file_manager(path: &str) -> AppResult<()> {
+    let path = path.to_string();
+    let file = run_blocking(move || resolve(&path)).await?;
+    #[cfg(target_os = "linux")]
+    {
+        reveal(&file).await
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        reveal(&file)
+    }
+}
@@ -0,0 +1,141 @@
+//! 启动参数中的文件
+//!
+//! 命令行（或文件关联、“打开方式”）传入的路径按类型处理：音频文件直接加入播放队列，播放列表文件
+//! 经播放列表导入读取其中能找到的音轨，目录扫描其中的音频文件。首次启动时参数先暂存，前端的播放器
+//! 初始化完成后用 `take_launch_files` 取走并开始播放；之后收到的路径（单实例插件转发的参数、macOS 的
+//! 打开文件请求）以 `open-files` 事件发送。两种情况都交给前端的同一个函数处理，行为一致。
+
+use crate::media::filesystem::collect_audio_paths;
+use crate::media::metadata::{MetadataOptions, TrackMetadata};
+use crate::media::metadata_cache::cached_track_metadata;
+use crate::media::playlist_file::{import_playlist_internal, PlaylistFormat};
+use crate::AppState;
+use serde::Serialize;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// 程序已在运行时打开文件的事件，前端把音轨加入播放队列并播放第一首
+pub const OPEN_FILES_EVENT: &str = "open-files";
+
+/// 启动参数和前端初始化前收到的路径，尚未被前端取走
+static PENDING: Mutex<Vec<String>> = Mutex::new(Vec::new());
+/// 前端是否已取走暂存的路径；此前收到的路径也先暂存，避免前端还没开始监听时丢失
+static TAKEN: AtomicBool = AtomicBool::new(false);
+
+/// 要打开的音轨，按参数的顺序排列
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenFilesEvent {
+    pub tracks: Vec<TrackMetadata>,
+}
+
+/// 命令行参数中的路径：跳过程序路径和选项，相对路径按 `cwd` 解析
+#[must_use]
+pub fn paths_from_args(args: &[String], cwd: &Path) -> Vec<String> {
+    args.iter()
+        .skip(1)
+        .filter(|arg| !arg.starts_with('-'))
+        .map(|arg| cwd.join(arg).to_string_lossy().to_string())
+        .collect()
+}
+
+/// 暂存本进程的启动参数，供前端初始化后取走
+pub fn set_pending(args: &[String]) {
+    let Ok(cwd) = std::env::current_dir() else { return };
+    PENDING.lock().unwrap().extend(paths_from_args(args, &cwd));
+}
+
+/// 读取音轨信息，无法读取标签时只包含文件名
+fn track_metadata(path: String, options: &MetadataOptions) -> TrackMetadata {
+    cached_track_metadata(&path, options).unwrap_or_else(|_| {
+        let name = Path::new(&path).file_name().map(|n| n.to_string_lossy().to_string());
+        TrackMetadata::new(path.clone(), name.unwrap_or_default())
+    })
+}
+
+/// 播放列表中能找到的音轨，找不到的文件和网络地址跳过
+fn playlist_tracks(path: &str, options: &MetadataOptions) -> Vec<TrackMetadata> {
+    match import_playlist_internal(path, options) {
+        Ok(imported) => imported
+            .playlist
+            .files
+            .into_iter()
+            .filter(|track| !imported.missing.contains(&track.path) && !imported.streams.contains(&track.path))
+            .collect(),
+        Err(e) => {
+            eprintln!("Failed to open playlist {path}: {e}");
+            Vec::new()
+        }
+    }
+}
+
+/// 把参数中的路径解析为要播放的音轨（会读取文件，应在阻塞线程中调用）
+#[must_use]
+pub fn resolve_tracks(inputs: &[String], options: &MetadataOptions) -> Vec<TrackMetadata> {
+    let mut tracks = Vec::new();
+    for input in inputs {
+        let path = Path::new(input);
+        if path.is_file() && PlaylistFormat::from_path(path).is_some() {
+            tracks.extend(playlist_tracks(input, options));
+        } else {
+            let paths = collect_audio_paths(std::slice::from_ref(input));
+            tracks.extend(paths.into_iter().map(|path| track_metadata(path, options)));
+        }
+    }
+    tracks
+}
+
+fn metadata_options(app: &AppHandle) -> MetadataOptions {
+    app.state::<AppState>()
+        .config_manager
+        .load_config()
+        .map(|config| MetadataOptions::from_config(&config))
+        .unwrap_or_default()
+}
+
+/// 取走首次启动时传入的文件并读取音轨信息，之后再调用返回空列表
+pub fn take_pending(app: &AppHandle) -> OpenFilesEvent {
+    let inputs = {
+        let mut pending = PENDING.lock().unwrap();
+        TAKEN.store(true, Ordering::Relaxed);
+        std::mem::take(&mut *pending)
+    };
+    if inputs.is_empty() {
+        return OpenFilesEvent::default();
+    }
+    OpenFilesEvent { tracks: resolve_tracks(&inputs, &metadata_options(app)) }
+}
+
+/// 在后台解析路径后发送 `open-files` 事件；前端还没取走启动参数时并入暂存的路径
+pub fn open_files(app: &AppHandle, inputs: Vec<String>) {
+    if inputs.is_empty() {
+        return;
+    }
+    {
+        let mut pending = PENDING.lock().unwrap();
+        if !TAKEN.load(Ordering::Relaxed) {
+            pending.extend(inputs);
+            return;
+        }
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let tracks = resolve_tracks(&inputs, &metadata_options(&app));
+        if !tracks.is_empty() {
+            let _ = app.emit(OPEN_FILES_EVENT, OpenFilesEvent { tracks });
+        }
+    });
+}
+
+/// macOS 通过“打开方式”或拖到程序图标上打开的文件
+#[cfg(target_os = "macos")]
+pub fn open_urls(app: &AppHandle, urls: &[tauri::Url]) {
+    let inputs = urls
+        .iter()
+        .filter_map(|url| url.to_file_path().ok())
+        .map(|path| path.to_string_lossy().to_string())
+        .collect();
+    open_files(app, inputs);
+}
@@ -0,0 +1,120 @@
+//! 全局媒体键
+//!
+//! 窗口最小化或没有焦点时，键盘上的播放/暂停、停止、上一首和下一首键也能控制播放。按键与系统媒体控件一样
+//! 以 `media-control` 事件转发给前端的播放器，同时发送 `media-key` 事件，前端据此让对应的按钮闪一下。
+//!
+//! 注册为全局快捷键后其他程序就收不到这些按键。设置中可以关闭媒体键，或者只在播放时注册：暂停、停止或
+//! 播放结束后立即注销，按键交还给其他播放器。
+
+use super::media_session::{send_control, MediaControl};
+use crate::audio::playback::{PlaybackStateEvent, PLAYBACK_STATE_EVENT, TRACK_ENDED_EVENT};
+use crate::config::manager::GeneralConfig;
+use crate::AppState;
+use std::sync::Mutex;
+use tauri::plugin::TauriPlugin;
+use tauri::{AppHandle, Emitter, Listener, Manager, Wry};
+use tauri_plugin_global_shortcut::{Code, GlobalShortcutExt, Shortcut, ShortcutEvent, ShortcutState};
+
+/// 按下媒体键时发给前端的事件，负载与 `media-control` 相同
+pub const MEDIA_KEY_EVENT: &str = "media-key";
+
+/// 接管的媒体键
+const MEDIA_KEYS: [Code; 4] = [Code::MediaPlayPause, Code::MediaStop, Code::MediaTrackNext, Code::MediaTrackPrevious];
+
+/// 媒体键的设置和注册状态
+#[derive(Debug, Clone, Copy)]
+struct Keys {
+    enabled: bool,
+    only_when_playing: bool,
+    is_playing: bool,
+    /// 当前是否已注册为全局快捷键
+    registered: bool,
+}
+
+impl Keys {
+    /// 按设置和播放状态是否应该接管媒体键
+    const fn wanted(self) -> bool {
+        self.enabled && (self.is_playing || !self.only_when_playing)
+    }
+}
+
+static KEYS: Mutex<Keys> =
+    Mutex::new(Keys { enabled: false, only_when_playing: true, is_playing: false, registered: false });
+
+const fn control_for(key: Code) -> Option<MediaControl> {
+    match key {
+        Code::MediaPlayPause => Some(MediaControl::PlayPause),
+        Code::MediaStop => Some(MediaControl::Stop),
+        Code::MediaTrackNext => Some(MediaControl::Next),
+        Code::MediaTrackPrevious => Some(MediaControl::Previous),
+        _ => None,
+    }
+}
+
+/// 按键按下时转发给前端，松开时忽略
+fn handle(app: &AppHandle, shortcut: &Shortcut, event: ShortcutEvent) {
+    if event.state() != ShortcutState::Pressed {
+        return;
+    }
+    let Some(control) = control_for(shortcut.key) else { return };
+    let _ = app.emit(MEDIA_KEY_EVENT, control);
+    send_control(app, control);
+}
+
+/// 全局快捷键插件，按键交给本模块处理
+#[must_use]
+pub fn plugin() -> TauriPlugin<Wry> {
+    tauri_plugin_global_shortcut::Builder::new().with_handler(handle).build()
+}
+
+/// 按需要注册或注销媒体键；某个键已被其他程序占用时跳过该键
+fn sync(app: &AppHandle, keys: &mut Keys) {
+    let wanted = keys.wanted();
+    if wanted == keys.registered {
+        return;
+    }
+    keys.registered = wanted;
+    let shortcuts = app.global_shortcut();
+    for key in MEDIA_KEYS {
+        let shortcut = Shortcut::new(None, key);
+        let result = if wanted {
+            shortcuts.register(shortcut)
+        } else if shortcuts.is_registered(shortcut) {
+            shortcuts.unregister(shortcut)
+        } else {
+            Ok(())
+        };
+        if let Err(e) = result {
+            eprintln!("Failed to update media key {key:?}: {e}");
+        }
+    }
+}
+
+fn set_playing(app: &AppHandle, is_playing: bool) {
+    let mut keys = KEYS.lock().unwrap();
+    keys.is_playing = is_playing;
+    sync(app, &mut keys);
+}
+
+/// 应用媒体键设置，保存或重置设置后调用
+pub fn apply_config(app: &AppHandle, general: &GeneralConfig) {
+    let mut keys = KEYS.lock().unwrap();
+    keys.enabled = general.global_media_keys;
+    keys.only_when_playing = general.media_keys_only_when_playing;
+    sync(app, &mut keys);
+}
+
+/// 启动时按设置注册媒体键，并跟随播放状态注册或注销
+pub fn start(app: &AppHandle) {
+    let handle = app.clone();
+    app.listen(PLAYBACK_STATE_EVENT, move |event| {
+        if let Ok(state) = serde_json::from_str::<PlaybackStateEvent>(event.payload()) {
+            set_playing(&handle, state.is_playing);
+        }
+    });
+    let handle = app.clone();
+    app.listen(TRACK_ENDED_EVENT, move |_| set_playing(&handle, false));
+
+    let config = app.state::<AppState>().config_manager.load_config().unwrap_or_default();
+    apply_config(app, &config.general);
+}
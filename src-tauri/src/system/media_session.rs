@@ -0,0 +1,130 @@
+//! 系统媒体控件共用的部分
+//!
+//! Linux 的 MPRIS、Windows 的系统媒体传输控件（SMTC）和 macOS 的正在播放都显示同样的音轨信息，控制请求也都以
+//! `media-control` 事件转发给前端的播放器：播放队列由前端管理，由它调用播放命令可以让前端的播放状态、队列与
+//! 系统控件保持一致。全局媒体键也通过这里转发，同一次按键可能同时从媒体键和系统控件送达，只处理一次。
+
+use crate::media::metadata::{track_cover_file, MetadataOptions};
+use crate::media::metadata_cache::cached_track_metadata;
+use crate::AppState;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// 发给前端播放器的控制事件
+pub const MEDIA_CONTROL_EVENT: &str = "media-control";
+/// 同一按键在这段时间内重复送达时只转发一次
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// 上一次转发的按键及其时间
+static LAST_BUTTON: Mutex<Option<(MediaControl, Instant)>> = Mutex::new(None);
+
+/// `media-control` 事件，`action` 为控制类型
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(tag = "action", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum MediaControl {
+    Play,
+    Pause,
+    PlayPause,
+    Stop,
+    Next,
+    Previous,
+    /// 跳转到指定位置
+    Seek { position_secs: f64 },
+    /// 设置音量（0.0 - 1.0）
+    SetVolume { volume: f64 },
+}
+
+impl MediaControl {
+    /// 去抖时对应的按键：播放、暂停和切换是同一个键（媒体键发送切换，系统控件按当前状态发送播放或暂停），
+    /// 跳转和音量不是按键，不去抖
+    const fn button(self) -> Option<Self> {
+        match self {
+            Self::Play | Self::Pause | Self::PlayPause => Some(Self::PlayPause),
+            Self::Stop | Self::Next | Self::Previous => Some(self),
+            Self::Seek { .. } | Self::SetVolume { .. } => None,
+        }
+    }
+}
+
+/// 把控制请求转发给前端，同一按键在去抖时间内重复送达时忽略
+pub fn send_control(app: &AppHandle, control: MediaControl) {
+    if let Some(button) = control.button() {
+        let now = Instant::now();
+        let mut last = LAST_BUTTON.lock().unwrap();
+        if last.is_some_and(|(previous, at)| previous == button && now.duration_since(at) < DEBOUNCE) {
+            return;
+        }
+        *last = Some((button, now));
+    }
+    let _ = app.emit(MEDIA_CONTROL_EVENT, control);
+}
+
+/// 系统媒体控件中显示的音轨信息
+#[derive(Debug, Clone)]
+pub struct NowPlaying {
+    pub path: String,
+    /// 标题，没有标题标签时为文件名
+    pub title: String,
+    pub artists: Vec<String>,
+    pub album: Option<String>,
+    pub album_artist: Option<String>,
+    pub track_number: Option<u32>,
+    /// 时长（秒）
+    pub duration: Option<f64>,
+    /// 封面图片文件
+    pub cover: Option<PathBuf>,
+}
+
+impl NowPlaying {
+    /// 只有路径的音轨，元数据读取完成前使用
+    #[must_use]
+    pub fn new(path: &str) -> Self {
+        let title = Path::new(path).file_stem().map_or_else(|| path.to_string(), |s| s.to_string_lossy().to_string());
+        Self {
+            path: path.to_string(),
+            title,
+            artists: Vec::new(),
+            album: None,
+            album_artist: None,
+            track_number: None,
+            duration: None,
+            cover: None,
+        }
+    }
+
+    /// 读取标签和封面（嵌入的封面写入封面缓存目录），会读取文件，应在阻塞线程中调用
+    #[must_use]
+    pub fn load(app: &AppHandle, path: &str) -> Self {
+        let mut track = Self::new(path);
+        let state = app.state::<AppState>();
+        let options = state
+            .config_manager
+            .load_config()
+            .map(|config| MetadataOptions::from_config(&config))
+            .unwrap_or_default();
+        if let Ok(metadata) = cached_track_metadata(path, &options) {
+            if let Some(title) = metadata.title.filter(|title| !title.trim().is_empty()) {
+                track.title = title;
+            }
+            track.artists =
+                if metadata.artists.is_empty() { metadata.artist.into_iter().collect() } else { metadata.artists };
+            track.album = metadata.album;
+            track.album_artist = metadata.album_artist;
+            track.track_number = metadata.track_number;
+            track.duration = metadata.duration;
+        }
+        if let Ok(cache_dir) = state.config_manager.get_cache_directory("covers") {
+            track.cover = track_cover_file(path, &options, &cache_dir).ok().flatten();
+        }
+        track
+    }
+
+    /// 艺术家列表合并为一个字符串
+    #[must_use]
+    pub fn artist(&self) -> String {
+        self.artists.join(" / ")
+    }
+}
@@ -1,8 +1,29 @@
 //! 系统模块
 //!
-//! 提供系统信息获取和窗口管理功能。
+//! 提供诊断信息获取和窗口管理功能，接入系统媒体控件（Linux 的 MPRIS、Windows 的 SMTC、macOS 的正在播放），
+//! 在 Windows 任务栏按钮上显示播放进度和控制按钮，响应全局媒体键，换音轨时显示通知，保证只运行一个实例，
+//! 打开启动参数中的文件，在文件管理器中显示文件，管理开机启动，读取系统主题和强调色，并在使用电池时减少后台工作。
 
+pub mod autostart;
 pub mod commands;
+pub mod diagnostics;
+pub mod file_associations;
+pub mod file_manager;
+pub mod launch;
+pub mod media_keys;
+pub mod media_session;
+pub mod notifications;
+pub mod power;
+#[cfg(target_os = "linux")]
+pub mod mpris;
+#[cfg(target_os = "macos")]
+pub mod nowplaying;
+pub mod single_instance;
+#[cfg(windows)]
+pub mod smtc;
+#[cfg(windows)]
+pub mod taskbar;
+pub mod theme;
 
 // 重新导出命令
 pub use commands::{
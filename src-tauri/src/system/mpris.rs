@@ -0,0 +1,511 @@
+//! Linux 系统媒体控制（MPRIS）
+//!
+//! 在会话总线上注册 `org.mpris.MediaPlayer2` 和 `org.mpris.MediaPlayer2.Player` 接口，桌面的媒体控件、
+//! GNOME 声音菜单和 `playerctl` 通过它显示正在播放的音轨并控制播放。
+//! 状态来自后端的 `playback-state`、`playback-position` 和 `track-ended` 事件，每次变化都发送属性变化信号，
+//! 跳转时发送 `Seeked`。控制请求以 `media-control` 事件转发给前端的播放器执行。
+
+use crate::audio::playback::{
+    PlaybackPositionEvent, PlaybackStateEvent, PLAYBACK_POSITION_EVENT, PLAYBACK_STATE_EVENT, TRACK_ENDED_EVENT,
+};
+use super::media_session::{send_control, MediaControl, NowPlaying};
+use crate::media::playlist_file::path_to_file_url;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tauri::{AppHandle, Listener};
+use zbus::zvariant::{ObjectPath, OwnedValue, Value};
+use zbus::{fdo, interface, Connection, SignalContext};
+
+/// 注册的总线名称
+const BUS_NAME: &str = "org.mpris.MediaPlayer2.mercurial_player";
+/// MPRIS 规定的对象路径
+const OBJECT_PATH: &str = "/org/mpris/MediaPlayer2";
+/// 音轨 ID 的路径前缀
+const TRACK_ID_PREFIX: &str = "/com/jdbewl/mercurial_player/track";
+/// 没有音轨时使用的 ID
+const NO_TRACK: &str = "/org/mpris/MediaPlayer2/TrackList/NoTrack";
+
+/// 正在播放的音轨
+#[derive(Debug, Clone)]
+struct SessionTrack {
+    id: String,
+    info: NowPlaying,
+}
+
+impl SessionTrack {
+    fn new(info: NowPlaying) -> Self {
+        let hash = format!("{:x}", Sha256::digest(info.path.as_bytes()));
+        Self { id: format!("{TRACK_ID_PREFIX}/{}", &hash[..16]), info }
+    }
+
+    /// 时长（微秒）
+    fn length_us(&self) -> Option<i64> {
+        self.info.duration.map(|secs| (secs * 1_000_000.0) as i64)
+    }
+
+    /// `Metadata` 属性
+    fn metadata(&self) -> HashMap<String, OwnedValue> {
+        let info = &self.info;
+        let mut values: Vec<(&str, Value<'static>)> = vec![
+            ("xesam:title", Value::from(info.title.clone())),
+            ("xesam:url", Value::from(path_to_file_url(&info.path))),
+        ];
+        if let Ok(id) = ObjectPath::try_from(self.id.clone()) {
+            values.push(("mpris:trackid", Value::from(id)));
+        }
+        if !info.artists.is_empty() {
+            values.push(("xesam:artist", Value::from(info.artists.clone())));
+        }
+        if let Some(album) = &info.album {
+            values.push(("xesam:album", Value::from(album.clone())));
+        }
+        if let Some(album_artist) = &info.album_artist {
+            values.push(("xesam:albumArtist", Value::from(vec![album_artist.clone()])));
+        }
+        if let Some(number) = info.track_number.and_then(|number| i32::try_from(number).ok()) {
+            values.push(("xesam:trackNumber", Value::from(number)));
+        }
+        if let Some(length) = self.length_us() {
+            values.push(("mpris:length", Value::from(length)));
+        }
+        if let Some(cover) = &info.cover {
+            values.push(("mpris:artUrl", Value::from(path_to_file_url(&cover.to_string_lossy()))));
+        }
+        values
+            .into_iter()
+            .filter_map(|(key, value)| Some((key.to_string(), value.try_to_owned().ok()?)))
+            .collect()
+    }
+}
+
+/// 当前的播放状态
+#[derive(Debug)]
+struct Session {
+    track: Option<SessionTrack>,
+    is_playing: bool,
+    /// 音轨已播放完毕
+    ended: bool,
+    volume: f64,
+    /// 最近一次得知的播放位置（秒）及其时间，播放中的位置由此推算
+    position_secs: f64,
+    position_at: Instant,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self {
+            track: None,
+            is_playing: false,
+            ended: false,
+            volume: 1.0,
+            position_secs: 0.0,
+            position_at: Instant::now(),
+        }
+    }
+}
+
+/// 一次状态变化中需要发送信号的部分
+#[derive(Debug, Default, Clone, Copy)]
+struct Changes {
+    track: bool,
+    status: bool,
+    volume: bool,
+    /// 跳转后的位置（微秒）
+    seeked: Option<i64>,
+}
+
+impl Session {
+    fn set_position(&mut self, secs: f64) {
+        self.position_secs = secs.max(0.0);
+        self.position_at = Instant::now();
+    }
+
+    /// 当前播放位置（微秒）
+    fn position_us(&self) -> i64 {
+        let mut secs = self.position_secs;
+        if self.is_playing {
+            secs += self.position_at.elapsed().as_secs_f64();
+        }
+        let position = (secs * 1_000_000.0) as i64;
+        match self.track.as_ref().and_then(SessionTrack::length_us) {
+            Some(length) => position.min(length),
+            None => position,
+        }
+    }
+
+    fn playback_status(&self) -> &'static str {
+        match (&self.track, self.is_playing) {
+            (None, _) => "Stopped",
+            _ if self.ended => "Stopped",
+            (Some(_), true) => "Playing",
+            (Some(_), false) => "Paused",
+        }
+    }
+
+    /// 应用 `playback-state` 事件
+    fn apply(&mut self, event: &PlaybackStateEvent) -> Changes {
+        let status = self.playback_status();
+        let mut changes = Changes::default();
+
+        if event.path.as_deref() != self.track.as_ref().map(|track| track.info.path.as_str()) {
+            self.track = event.path.as_deref().map(|path| SessionTrack::new(NowPlaying::new(path)));
+            changes.track = true;
+        }
+        // 播放位置先按旧状态推算到现在，再切换播放状态
+        let position = self.position_us() as f64 / 1_000_000.0;
+        self.set_position(event.position_secs.map_or(position, f64::from));
+        self.is_playing = event.is_playing;
+        if event.position_secs.is_some() || event.is_playing {
+            self.ended = false;
+        }
+        if event.seeked {
+            changes.seeked = Some(self.position_us());
+        }
+
+        let volume = f64::from(event.volume);
+        if (volume - self.volume).abs() > f64::EPSILON {
+            self.volume = volume;
+            changes.volume = true;
+        }
+        changes.status = changes.track || status != self.playback_status();
+        changes
+    }
+}
+
+type SharedSession = Arc<Mutex<Session>>;
+
+/// 已注册的会话总线连接，退出时注销
+static CONNECTION: Mutex<Option<Connection>> = Mutex::new(None);
+
+/// `org.mpris.MediaPlayer2`
+struct RootInterface {
+    app: AppHandle,
+}
+
+#[interface(name = "org.mpris.MediaPlayer2")]
+impl RootInterface {
+    fn raise(&self) {
+        super::commands::raise_main_window(&self.app);
+    }
+
+    fn quit(&self) {
+        self.app.exit(0);
+    }
+
+    #[zbus(property)]
+    fn can_quit(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_raise(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn has_track_list(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn identity(&self) -> String {
+        self.app.package_info().name.clone()
+    }
+
+    #[zbus(property)]
+    fn supported_uri_schemes(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    #[zbus(property)]
+    fn supported_mime_types(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+/// `org.mpris.MediaPlayer2.Player`
+struct PlayerInterface {
+    app: AppHandle,
+    session: SharedSession,
+}
+
+impl PlayerInterface {
+    fn send(&self, control: MediaControl) {
+        send_control(&self.app, control);
+    }
+
+    fn has_track(&self) -> bool {
+        self.session.lock().unwrap().track.is_some()
+    }
+}
+
+#[interface(name = "org.mpris.MediaPlayer2.Player")]
+impl PlayerInterface {
+    fn next(&self) {
+        self.send(MediaControl::Next);
+    }
+
+    fn previous(&self) {
+        self.send(MediaControl::Previous);
+    }
+
+    fn pause(&self) {
+        self.send(MediaControl::Pause);
+    }
+
+    fn play_pause(&self) {
+        self.send(MediaControl::PlayPause);
+    }
+
+    fn stop(&self) {
+        self.send(MediaControl::Stop);
+    }
+
+    fn play(&self) {
+        self.send(MediaControl::Play);
+    }
+
+    /// 相对当前位置跳转（微秒），跳过结尾时播放下一首
+    fn seek(&self, offset: i64) {
+        let (position, length) = {
+            let session = self.session.lock().unwrap();
+            let Some(track) = &session.track else { return };
+            (session.position_us(), track.length_us())
+        };
+        let target = position.saturating_add(offset).max(0);
+        if length.is_some_and(|length| target > length) {
+            self.send(MediaControl::Next);
+        } else {
+            self.send(MediaControl::Seek { position_secs: target as f64 / 1_000_000.0 });
+        }
+    }
+
+    /// 跳转到指定位置（微秒），音轨 ID 不是当前音轨或位置超出范围时忽略
+    fn set_position(&self, track_id: ObjectPath<'_>, position: i64) {
+        {
+            let session = self.session.lock().unwrap();
+            let Some(track) = &session.track else { return };
+            let out_of_range = position < 0 || track.length_us().is_some_and(|length| position > length);
+            if track_id.as_str() != track.id || out_of_range {
+                return;
+            }
+        }
+        self.send(MediaControl::Seek { position_secs: position as f64 / 1_000_000.0 });
+    }
+
+    fn open_uri(&self, _uri: String) -> fdo::Result<()> {
+        Err(fdo::Error::NotSupported("OpenUri is not supported".to_string()))
+    }
+
+    #[zbus(signal)]
+    async fn seeked(ctxt: &SignalContext<'_>, position: i64) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn playback_status(&self) -> String {
+        self.session.lock().unwrap().playback_status().to_string()
+    }
+
+    #[zbus(property)]
+    fn rate(&self) -> f64 {
+        1.0
+    }
+
+    /// 不支持变速，忽略设置
+    #[zbus(property)]
+    fn set_rate(&self, _rate: f64) {}
+
+    #[zbus(property)]
+    fn minimum_rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn maximum_rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn metadata(&self) -> HashMap<String, OwnedValue> {
+        let session = self.session.lock().unwrap();
+        match &session.track {
+            Some(track) => track.metadata(),
+            None => ObjectPath::try_from(NO_TRACK)
+                .ok()
+                .and_then(|id| Value::from(id).try_to_owned().ok())
+                .map(|id| HashMap::from([("mpris:trackid".to_string(), id)]))
+                .unwrap_or_default(),
+        }
+    }
+
+    #[zbus(property)]
+    fn volume(&self) -> f64 {
+        self.session.lock().unwrap().volume
+    }
+
+    #[zbus(property)]
+    fn set_volume(&self, volume: f64) {
+        self.send(MediaControl::SetVolume { volume: volume.clamp(0.0, 1.0) });
+    }
+
+    /// 播放位置变化不发送信号，跳转时发送 `Seeked`
+    #[zbus(property(emits_changed_signal = "false"))]
+    fn position(&self) -> i64 {
+        self.session.lock().unwrap().position_us()
+    }
+
+    #[zbus(property)]
+    fn can_go_next(&self) -> bool {
+        self.has_track()
+    }
+
+    #[zbus(property)]
+    fn can_go_previous(&self) -> bool {
+        self.has_track()
+    }
+
+    #[zbus(property)]
+    fn can_play(&self) -> bool {
+        self.has_track()
+    }
+
+    #[zbus(property)]
+    fn can_pause(&self) -> bool {
+        self.has_track()
+    }
+
+    #[zbus(property)]
+    fn can_seek(&self) -> bool {
+        self.session.lock().unwrap().track.as_ref().is_some_and(|track| track.info.duration.is_some())
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn can_control(&self) -> bool {
+        true
+    }
+}
+
+/// 发送属性变化和 `Seeked` 信号
+async fn notify(connection: &Connection, changes: Changes) -> zbus::Result<()> {
+    let iface_ref = connection.object_server().interface::<_, PlayerInterface>(OBJECT_PATH).await?;
+    let iface = iface_ref.get().await;
+    let ctxt = iface_ref.signal_context();
+    if changes.track {
+        iface.metadata_changed(ctxt).await?;
+        iface.can_go_next_changed(ctxt).await?;
+        iface.can_go_previous_changed(ctxt).await?;
+        iface.can_play_changed(ctxt).await?;
+        iface.can_pause_changed(ctxt).await?;
+        iface.can_seek_changed(ctxt).await?;
+    }
+    if changes.status {
+        iface.playback_status_changed(ctxt).await?;
+    }
+    if changes.volume {
+        iface.volume_changed(ctxt).await?;
+    }
+    if let Some(position) = changes.seeked {
+        PlayerInterface::seeked(ctxt, position).await?;
+    }
+    Ok(())
+}
+
+/// 换了音轨时在后台读取元数据，读取完成且仍是同一音轨时更新
+async fn load_track(
+    app: AppHandle,
+    connection: &Connection,
+    session: &SharedSession,
+    path: String,
+) -> zbus::Result<()> {
+    let loader_path = path.clone();
+    let Ok(info) = tauri::async_runtime::spawn_blocking(move || NowPlaying::load(&app, &loader_path)).await else {
+        return Ok(());
+    };
+    {
+        let mut session = session.lock().unwrap();
+        match &mut session.track {
+            Some(current) if current.info.path == path => current.info = info,
+            _ => return Ok(()),
+        }
+    }
+    notify(connection, Changes { track: true, ..Changes::default() }).await
+}
+
+/// 监听后端的播放事件
+fn listen_playback(app: &AppHandle, connection: &Connection, session: &SharedSession) {
+    let (handle, conn, shared) = (app.clone(), connection.clone(), Arc::clone(session));
+    app.listen(PLAYBACK_STATE_EVENT, move |event| {
+        let Ok(state) = serde_json::from_str::<PlaybackStateEvent>(event.payload()) else { return };
+        let changes = shared.lock().unwrap().apply(&state);
+        let (app, connection, session) = (handle.clone(), conn.clone(), Arc::clone(&shared));
+        tauri::async_runtime::spawn(async move {
+            let mut result = notify(&connection, changes).await;
+            if changes.track
+                && let Some(path) = state.path
+            {
+                result = result.and(load_track(app, &connection, &session, path).await);
+            }
+            if let Err(e) = result {
+                eprintln!("Failed to update MPRIS state: {e}");
+            }
+        });
+    });
+
+    let shared = Arc::clone(session);
+    app.listen(PLAYBACK_POSITION_EVENT, move |event| {
+        let Ok(position) = serde_json::from_str::<PlaybackPositionEvent>(event.payload()) else { return };
+        let mut session = shared.lock().unwrap();
+        if session.is_playing {
+            session.set_position(f64::from(position.position));
+        }
+    });
+
+    let (conn, shared) = (connection.clone(), Arc::clone(session));
+    app.listen(TRACK_ENDED_EVENT, move |_| {
+        {
+            let mut session = shared.lock().unwrap();
+            let position = session.position_us() as f64 / 1_000_000.0;
+            session.set_position(position);
+            session.is_playing = false;
+            session.ended = true;
+        }
+        let connection = conn.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = notify(&connection, Changes { status: true, ..Changes::default() }).await {
+                eprintln!("Failed to update MPRIS state: {e}");
+            }
+        });
+    });
+}
+
+async fn serve(app: AppHandle) -> zbus::Result<()> {
+    let session = SharedSession::default();
+    let connection = zbus::connection::Builder::session()?
+        .name(BUS_NAME)?
+        .serve_at(OBJECT_PATH, RootInterface { app: app.clone() })?
+        .serve_at(OBJECT_PATH, PlayerInterface { app: app.clone(), session: Arc::clone(&session) })?
+        .build()
+        .await?;
+    listen_playback(&app, &connection, &session);
+    *CONNECTION.lock().unwrap() = Some(connection);
+    Ok(())
+}
+
+/// 启动时注册 MPRIS 服务；没有会话总线（如在无桌面环境中运行）时只打印错误
+pub fn start(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = serve(app).await {
+            eprintln!("Failed to register MPRIS service: {e}");
+        }
+    });
+}
+
+/// 退出时注销总线名称并关闭连接
+pub fn stop() {
+    let Some(connection) = CONNECTION.lock().unwrap().take() else { return };
+    tauri::async_runtime::block_on(async move {
+        let _ = connection.release_name(BUS_NAME).await;
+    });
+}
@@ -0,0 +1,101 @@
+//! 换音轨通知
+//!
+//! 开始播放另一首音轨时显示一条系统通知，包含标题、艺术家、专辑和封面。通知接口需要图片文件的路径，
+//! 嵌入的封面先写入封面缓存目录（见 [`NowPlaying::load`]）。可以设置为只在窗口没有焦点或最小化时显示。
+//!
+//! 换音轨后等待一小段时间，期间又换了音轨就不再显示，快速切歌时不会连续弹出通知；程序启动后不久的
+//! 自动播放（如打开启动参数中的文件）也不显示。
+
+use super::media_session::NowPlaying;
+use crate::audio::playback::{PlaybackStateEvent, PLAYBACK_STATE_EVENT};
+use crate::AppState;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Listener, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+/// 换音轨后等待多久再显示，期间再次换音轨则跳过
+const SETTLE_DELAY: Duration = Duration::from_millis(1500);
+/// 启动后这段时间内的换音轨不显示通知
+const STARTUP_GRACE: Duration = Duration::from_secs(5);
+
+/// 最近一次开始播放的音轨
+static LAST_PATH: Mutex<Option<String>> = Mutex::new(None);
+/// 每次换音轨加一，等待结束时仍相同才显示
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// 主窗口是否在前台（有焦点且没有最小化）
+fn window_focused(app: &AppHandle) -> bool {
+    app.get_webview_window("main")
+        .is_some_and(|window| window.is_focused().unwrap_or(false) && !window.is_minimized().unwrap_or(false))
+}
+
+/// 按设置显示通知（会读取文件，应在阻塞线程中调用）
+fn show(app: &AppHandle, path: &str, generation: u64) {
+    let config = app.state::<AppState>().config_manager.load_config().unwrap_or_default();
+    if !config.general.track_notifications || (config.general.notify_only_when_unfocused && window_focused(app)) {
+        return;
+    }
+    let track = NowPlaying::load(app, path);
+    if GENERATION.load(Ordering::Relaxed) != generation {
+        return;
+    }
+
+    let mut body = track.artist();
+    if let Some(album) = track.album.as_deref().filter(|album| !album.is_empty()) {
+        if !body.is_empty() {
+            body.push_str(" — ");
+        }
+        body.push_str(album);
+    }
+    let mut notification = app.notification().builder().title(&track.title);
+    if !body.is_empty() {
+        notification = notification.body(body);
+    }
+    if let Some(cover) = &track.cover {
+        notification = notification.icon(cover.to_string_lossy());
+    }
+    if let Err(e) = notification.show() {
+        eprintln!("Failed to show track notification: {e}");
+    }
+}
+
+/// 换音轨后等待，期间没有再换音轨时显示通知
+fn schedule(app: &AppHandle, path: String, generation: u64) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(SETTLE_DELAY).await;
+        if GENERATION.load(Ordering::Relaxed) != generation {
+            return;
+        }
+        let _ = tauri::async_runtime::spawn_blocking(move || show(&app, &path, generation)).await;
+    });
+}
+
+/// 启动时开始监听播放状态
+pub fn start(app: &AppHandle) {
+    let started = Instant::now();
+    let handle = app.clone();
+    app.listen(PLAYBACK_STATE_EVENT, move |event| {
+        let Ok(state) = serde_json::from_str::<PlaybackStateEvent>(event.payload()) else { return };
+        let Some(path) = state.path else {
+            *LAST_PATH.lock().unwrap() = None;
+            return;
+        };
+        if !state.is_playing {
+            return;
+        }
+        {
+            let mut last = LAST_PATH.lock().unwrap();
+            if last.as_deref() == Some(path.as_str()) {
+                return;
+            }
+            *last = Some(path.clone());
+        }
+        let generation = GENERATION.fetch_add(1, Ordering::Relaxed) + 1;
+        if started.elapsed() >= STARTUP_GRACE {
+            schedule(&handle, path, generation);
+        }
+    });
+}
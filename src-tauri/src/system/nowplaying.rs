@@ -0,0 +1,270 @@
+//! macOS 正在播放（MPNowPlayingInfoCenter）
+//!
+//! 控制中心的“正在播放”、触控栏和耳机按键通过它显示正在播放的音轨并控制播放。标题、艺术家、专辑、封面和时长
+//! 在换音轨时发布；已播放时间和播放速率（播放中为 1，暂停为 0）在开始播放、暂停、继续和跳转时重新发布，
+//! 系统据此推算进度条，不会与实际位置偏离。`MPRemoteCommandCenter` 的播放、暂停、切换、上一首、下一首和
+//! 拖动进度条以 `media-control` 事件转发给前端的播放器执行。
+//!
+//! AppKit 要求这些对象只在主线程使用，所有调用都经 `run_on_main_thread` 转到主线程。
+//! 调用 Objective-C 框架需要 `unsafe`，除系统主题的 macOS 部分、Windows 的任务栏按钮、管理员权限检查和
+//! 通话检测外，本模块是整个程序中唯一允许 `unsafe` 的地方。
+#![allow(unsafe_code)]
+
+use super::media_session::{send_control, MediaControl, NowPlaying};
+use crate::audio::playback::{
+    PlaybackPositionEvent, PlaybackStateEvent, PLAYBACK_POSITION_EVENT, PLAYBACK_STATE_EVENT, TRACK_ENDED_EVENT,
+};
+use block2::RcBlock;
+use objc2::rc::Retained;
+use objc2::runtime::AnyObject;
+use objc2::{ClassType, Message};
+use objc2_app_kit::NSImage;
+use objc2_foundation::{CGSize, MainThreadMarker, NSMutableDictionary, NSNumber, NSString};
+use objc2_media_player::{
+    MPChangePlaybackPositionCommandEvent, MPMediaItemArtwork, MPMediaItemPropertyAlbumTitle, MPMediaItemPropertyArtist,
+    MPMediaItemPropertyArtwork, MPMediaItemPropertyPlaybackDuration, MPMediaItemPropertyTitle, MPNowPlayingInfoCenter,
+    MPNowPlayingInfoPropertyElapsedPlaybackTime, MPNowPlayingInfoPropertyPlaybackRate, MPNowPlayingPlaybackState,
+    MPRemoteCommand, MPRemoteCommandCenter, MPRemoteCommandEvent, MPRemoteCommandHandlerStatus,
+};
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::ptr::NonNull;
+use std::sync::Mutex;
+use tauri::{AppHandle, Listener};
+
+/// 当前的播放状态，由事件监听线程更新，在主线程发布
+#[derive(Debug, Clone)]
+struct Session {
+    track: Option<NowPlaying>,
+    is_playing: bool,
+    ended: bool,
+    /// 最近一次得知的播放位置（秒）
+    position_secs: f64,
+}
+
+impl Session {
+    const EMPTY: Self = Self { track: None, is_playing: false, ended: false, position_secs: 0.0 };
+}
+
+static SESSION: Mutex<Session> = Mutex::new(Session::EMPTY);
+
+thread_local! {
+    /// 当前封面，同一音轨只创建一次（只在主线程使用）
+    static ARTWORK: RefCell<Option<(PathBuf, Retained<MPMediaItemArtwork>)>> = const { RefCell::new(None) };
+}
+
+/// 任何 Objective-C 对象都可以作为字典的值
+fn as_object<T: Message>(value: Retained<T>) -> Retained<AnyObject> {
+    // SAFETY: 所有 Objective-C 对象都是 AnyObject
+    unsafe { Retained::cast(value) }
+}
+
+/// 读取封面图片，失败时返回 `None`
+fn artwork(path: &Path) -> Option<Retained<MPMediaItemArtwork>> {
+    let file = NSString::from_str(&path.to_string_lossy());
+    // SAFETY: 在主线程调用，图片读取失败时返回 nil
+    let image = unsafe { NSImage::initWithContentsOfFile(NSImage::alloc(), &file) }?;
+    // SAFETY: image 是有效的 NSImage
+    let size = unsafe { image.size() };
+    let handler = RcBlock::new(move |_size: CGSize| NonNull::from(&*image));
+    // SAFETY: 处理函数返回的图片由 block 持有，在 artwork 存在期间一直有效
+    Some(unsafe { MPMediaItemArtwork::initWithBoundsSize_requestHandler(MPMediaItemArtwork::alloc(), size, &handler) })
+}
+
+/// 封面，同一图片复用上次创建的对象
+fn cached_artwork(path: &Path) -> Option<Retained<MPMediaItemArtwork>> {
+    ARTWORK.with_borrow_mut(|cached| {
+        if let Some((cached_path, artwork)) = cached.as_ref()
+            && cached_path == path
+        {
+            return Some(artwork.clone());
+        }
+        let created = artwork(path)?;
+        *cached = Some((path.to_path_buf(), created.clone()));
+        Some(created)
+    })
+}
+
+/// 发布当前状态（主线程）
+fn publish(session: &Session) {
+    let Some(track) = &session.track else {
+        clear();
+        return;
+    };
+    let mut info = NSMutableDictionary::<NSString, AnyObject>::new();
+    let rate = if session.is_playing { 1.0 } else { 0.0 };
+    // SAFETY: 键是 MediaPlayer 框架导出的常量；在主线程调用
+    unsafe {
+        info.insert_id(MPMediaItemPropertyTitle, as_object(NSString::from_str(&track.title)));
+        if !track.artists.is_empty() {
+            info.insert_id(MPMediaItemPropertyArtist, as_object(NSString::from_str(&track.artist())));
+        }
+        if let Some(album) = &track.album {
+            info.insert_id(MPMediaItemPropertyAlbumTitle, as_object(NSString::from_str(album)));
+        }
+        if let Some(duration) = track.duration {
+            info.insert_id(MPMediaItemPropertyPlaybackDuration, as_object(NSNumber::new_f64(duration)));
+        }
+        if let Some(artwork) = track.cover.as_deref().and_then(cached_artwork) {
+            info.insert_id(MPMediaItemPropertyArtwork, as_object(artwork));
+        }
+        let elapsed = NSNumber::new_f64(session.position_secs);
+        info.insert_id(MPNowPlayingInfoPropertyElapsedPlaybackTime, as_object(elapsed));
+        info.insert_id(MPNowPlayingInfoPropertyPlaybackRate, as_object(NSNumber::new_f64(rate)));
+
+        let center = MPNowPlayingInfoCenter::defaultCenter();
+        center.setNowPlayingInfo(Some(&info));
+        center.setPlaybackState(if session.ended {
+            MPNowPlayingPlaybackState::Stopped
+        } else if session.is_playing {
+            MPNowPlayingPlaybackState::Playing
+        } else {
+            MPNowPlayingPlaybackState::Paused
+        });
+    }
+}
+
+/// 清空正在播放的信息（主线程）
+fn clear() {
+    ARTWORK.with_borrow_mut(Option::take);
+    // SAFETY: 在主线程调用
+    unsafe {
+        let center = MPNowPlayingInfoCenter::defaultCenter();
+        center.setNowPlayingInfo(None);
+        center.setPlaybackState(MPNowPlayingPlaybackState::Stopped);
+    }
+}
+
+/// 把当前状态交给主线程发布
+fn publish_on_main(app: &AppHandle) {
+    let session = SESSION.lock().unwrap().clone();
+    let _ = app.run_on_main_thread(move || publish(&session));
+}
+
+/// 为远程命令注册处理函数（主线程）
+fn on_command(command: &MPRemoteCommand, app: &AppHandle, control: MediaControl) {
+    let handle = app.clone();
+    let handler = RcBlock::new(move |_event: NonNull<MPRemoteCommandEvent>| {
+        send_control(&handle, control);
+        MPRemoteCommandHandlerStatus::Success
+    });
+    // SAFETY: 在主线程调用，命令中心持有处理函数
+    unsafe {
+        command.setEnabled(true);
+        let _ = command.addTargetWithHandler(&handler);
+    }
+}
+
+/// 注册遥控命令（必须在主线程）
+fn install_handlers(app: &AppHandle) {
+    if MainThreadMarker::new().is_none() {
+        eprintln!("Now Playing handlers must be installed on the main thread");
+        return;
+    }
+    // SAFETY: 已确认在主线程
+    let center = unsafe { MPRemoteCommandCenter::sharedCommandCenter() };
+    // SAFETY: 同上，这些方法只返回命令中心持有的命令对象
+    unsafe {
+        on_command(&center.playCommand(), app, MediaControl::Play);
+        on_command(&center.pauseCommand(), app, MediaControl::Pause);
+        on_command(&center.togglePlayPauseCommand(), app, MediaControl::PlayPause);
+        on_command(&center.stopCommand(), app, MediaControl::Stop);
+        on_command(&center.nextTrackCommand(), app, MediaControl::Next);
+        on_command(&center.previousTrackCommand(), app, MediaControl::Previous);
+    }
+
+    let handle = app.clone();
+    let handler = RcBlock::new(move |event: NonNull<MPRemoteCommandEvent>| {
+        // SAFETY: changePlaybackPositionCommand 的事件总是 MPChangePlaybackPositionCommandEvent
+        let position = unsafe { event.cast::<MPChangePlaybackPositionCommandEvent>().as_ref().positionTime() };
+        send_control(&handle, MediaControl::Seek { position_secs: position.max(0.0) });
+        MPRemoteCommandHandlerStatus::Success
+    });
+    // SAFETY: 在主线程调用，命令中心持有处理函数
+    unsafe {
+        let command = center.changePlaybackPositionCommand();
+        command.setEnabled(true);
+        let _ = command.addTargetWithHandler(&handler);
+    }
+}
+
+/// 换了音轨时在后台读取元数据，读取完成且仍是同一音轨时重新发布
+fn load_track(app: &AppHandle, path: String) {
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let info = NowPlaying::load(&app, &path);
+        {
+            let mut session = SESSION.lock().unwrap();
+            match &mut session.track {
+                Some(current) if current.path == path => *current = info,
+                _ => return,
+            }
+        }
+        publish_on_main(&app);
+    });
+}
+
+/// 监听后端的播放事件
+fn listen_playback(app: &AppHandle) {
+    let handle = app.clone();
+    app.listen(PLAYBACK_STATE_EVENT, move |event| {
+        let Ok(state) = serde_json::from_str::<PlaybackStateEvent>(event.payload()) else { return };
+        let new_track = {
+            let mut session = SESSION.lock().unwrap();
+            let changed = state.path.as_deref() != session.track.as_ref().map(|track| track.path.as_str());
+            if changed {
+                session.track = state.path.as_deref().map(NowPlaying::new);
+            }
+            if let Some(position) = state.position_secs {
+                session.position_secs = f64::from(position);
+            }
+            session.is_playing = state.is_playing;
+            if state.position_secs.is_some() || state.is_playing {
+                session.ended = false;
+            }
+            changed.then_some(state.path).flatten()
+        };
+        // 暂停、继续（速率变化）和跳转都重新发布已播放时间
+        publish_on_main(&handle);
+        if let Some(path) = new_track {
+            load_track(&handle, path);
+        }
+    });
+
+    // 只记录位置，系统按已播放时间和速率自行推算进度
+    app.listen(PLAYBACK_POSITION_EVENT, move |event| {
+        let Ok(position) = serde_json::from_str::<PlaybackPositionEvent>(event.payload()) else { return };
+        let mut session = SESSION.lock().unwrap();
+        if session.is_playing {
+            session.position_secs = f64::from(position.position);
+        }
+    });
+
+    let handle = app.clone();
+    app.listen(TRACK_ENDED_EVENT, move |_| {
+        {
+            let mut session = SESSION.lock().unwrap();
+            session.is_playing = false;
+            session.ended = true;
+        }
+        publish_on_main(&handle);
+    });
+}
+
+/// 启动时在主线程注册遥控命令并开始监听播放事件
+pub fn start(app: &AppHandle) {
+    let handle = app.clone();
+    if let Err(e) = app.run_on_main_thread(move || install_handlers(&handle)) {
+        eprintln!("Failed to install Now Playing handlers: {e}");
+        return;
+    }
+    listen_playback(app);
+}
+
+/// 退出时清空正在播放的信息（在主线程的事件循环中调用）
+pub fn stop() {
+    *SESSION.lock().unwrap() = Session::EMPTY;
+    if MainThreadMarker::new().is_some() {
+        clear();
+    }
+}
@@ -0,0 +1,169 @@
+//! 电源状态
+//!
+//! 定时读取电源是交流电还是电池以及剩余电量，变化时发送 `power-status-changed` 事件。设置中开启
+//! `power.reduceBackgroundWorkOnBattery` 时，使用电池期间减少后台工作：扫描只用一个线程读取元数据，
+//! 指纹等批量任务暂停（接上电源后自动继续），目录监视合并事件的时间更长。
+//!
+//! 没有电池的设备视为使用交流电；无法读取电源状态的平台为 `unknown`，不减少后台工作。
+
+use crate::config::PowerConfig;
+use crate::tasks::{CancelToken, TaskStatus, TASK_STATUS_EVENT};
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{LazyLock, Mutex};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+/// 电源状态变化的事件
+pub const POWER_STATUS_CHANGED_EVENT: &str = "power-status-changed";
+
+/// 重新读取电源状态的间隔
+const POLL_INTERVAL: Duration = Duration::from_secs(15);
+/// 暂停的任务检查是否可以继续的间隔
+const RESUME_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 电源类型
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum PowerSource {
+    /// 交流电（包括没有电池的设备）
+    Ac,
+    Battery,
+    /// 当前平台无法读取
+    Unknown,
+}
+
+/// 电源状态，也是 `power-status-changed` 事件的负载
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PowerStatus {
+    pub source: PowerSource,
+    /// 剩余电量（0–100），没有电池时为空
+    pub percentage: Option<u8>,
+    /// 是否正在减少后台工作
+    pub reducing_background_work: bool,
+}
+
+/// 最近一次读取的电源类型和剩余电量
+static READING: Mutex<(PowerSource, Option<u8>)> = Mutex::new((PowerSource::Unknown, None));
+/// 最近一次读取时是否使用电池
+static ON_BATTERY: AtomicBool = AtomicBool::new(false);
+/// 设置中的 `power.reduceBackgroundWorkOnBattery`
+static REDUCE_ON_BATTERY: AtomicBool = AtomicBool::new(true);
+
+/// 省电时扫描使用的单线程线程池
+static LOW_POWER_POOL: LazyLock<Option<ThreadPool>> = LazyLock::new(|| {
+    ThreadPoolBuilder::new()
+        .num_threads(1)
+        .thread_name(|_| "low-power-worker".to_string())
+        .build()
+        .map_err(|e| eprintln!("Failed to create low-power worker pool: {e}"))
+        .ok()
+});
+
+/// 读取电源类型和剩余电量；有电池在放电时视为使用电池，多块电池按总能量计算电量
+fn read() -> (PowerSource, Option<u8>) {
+    let Ok(manager) = starship_battery::Manager::new() else {
+        return (PowerSource::Unknown, None);
+    };
+    let Ok(batteries) = manager.batteries() else {
+        return (PowerSource::Unknown, None);
+    };
+    let batteries: Vec<_> = batteries.filter_map(Result::ok).collect();
+    if batteries.is_empty() {
+        return (PowerSource::Ac, None);
+    }
+    let discharging = batteries.iter().any(|battery| battery.state() == starship_battery::State::Discharging);
+    let energy: f32 = batteries.iter().map(|battery| battery.energy().value).sum();
+    let full: f32 = batteries.iter().map(|battery| battery.energy_full().value).sum();
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let percentage = (full > 0.0).then(|| (energy / full * 100.0).round().clamp(0.0, 100.0) as u8);
+    (if discharging { PowerSource::Battery } else { PowerSource::Ac }, percentage)
+}
+
+/// 重新读取电源状态，返回状态是否有变化
+fn refresh() -> bool {
+    let reading = read();
+    ON_BATTERY.store(reading.0 == PowerSource::Battery, Ordering::Relaxed);
+    let mut last = READING.lock().unwrap();
+    let changed = *last != reading;
+    *last = reading;
+    changed
+}
+
+/// 是否在使用电池且开启了减少后台工作
+#[must_use]
+pub fn is_reducing_background_work() -> bool {
+    REDUCE_ON_BATTERY.load(Ordering::Relaxed) && ON_BATTERY.load(Ordering::Relaxed)
+}
+
+/// 最近一次读取的电源状态
+#[must_use]
+pub fn status() -> PowerStatus {
+    let (source, percentage) = *READING.lock().unwrap();
+    PowerStatus { source, percentage, reducing_background_work: is_reducing_background_work() }
+}
+
+/// 立即重新读取电源状态（会读取系统电池信息，需在阻塞线程池中调用）
+#[must_use]
+pub fn current_status() -> PowerStatus {
+    refresh();
+    status()
+}
+
+/// 应用设置中的电源选项，是否减少后台工作因此变化时发送事件
+pub fn apply_config(app: &AppHandle, config: &PowerConfig) {
+    let reducing = is_reducing_background_work();
+    REDUCE_ON_BATTERY.store(config.reduce_background_work_on_battery, Ordering::Relaxed);
+    if is_reducing_background_work() != reducing {
+        let _ = app.emit(POWER_STATUS_CHANGED_EVENT, status());
+    }
+}
+
+/// 执行并行的后台工作（其中的 rayon 并行迭代）；减少后台工作时只用一个线程
+pub fn run_background<R: Send>(op: impl FnOnce() -> R + Send) -> R {
+    match LOW_POWER_POOL.as_ref() {
+        Some(pool) if is_reducing_background_work() => pool.install(op),
+        _ => op(),
+    }
+}
+
+fn emit_task_status(app: &AppHandle, task_id: &str, token: &CancelToken, paused: bool) {
+    if token.set_paused(paused) {
+        let _ = app.emit(TASK_STATUS_EVENT, TaskStatus { task_id: task_id.to_string(), paused });
+    }
+}
+
+/// 批量任务在处理下一项之前调用：减少后台工作时把任务标记为暂停并等待，接上电源、关闭选项或任务被取消后返回
+pub fn pause_while_on_battery(app: &AppHandle, task_id: &str, token: &CancelToken) {
+    if !is_reducing_background_work() {
+        return;
+    }
+    emit_task_status(app, task_id, token, true);
+    while is_reducing_background_work() && !token.is_cancelled() {
+        thread::sleep(RESUME_CHECK_INTERVAL);
+    }
+    emit_task_status(app, task_id, token, false);
+}
+
+/// 启动时开始定时读取电源状态，变化时发送事件
+pub fn start(app: &AppHandle) {
+    let app = app.clone();
+    let spawned = thread::Builder::new().name("power-monitor".to_string()).spawn(move || {
+        refresh();
+        if READING.lock().unwrap().0 == PowerSource::Unknown {
+            return;
+        }
+        loop {
+            thread::sleep(POLL_INTERVAL);
+            if refresh() {
+                let _ = app.emit(POWER_STATUS_CHANGED_EVENT, status());
+            }
+        }
+    });
+    if let Err(e) = spawned {
+        eprintln!("Failed to start power monitor: {e}");
+    }
+}
@@ -0,0 +1,24 @@
+//! 单实例
+//!
+//! 程序已经运行时再次启动（如在文件管理器中双击音频文件），新的进程把命令行参数和工作目录转发给
+//! 正在运行的实例后立即退出，不会出现两个音频管线同时播放。正在运行的实例把主窗口带到前台，
+//! 参数中的文件按首次启动时相同的规则打开（见 [`super::launch`]）。
+//!
+//! 相对路径按第二个进程的工作目录解析，与它在命令行中的含义一致。
+
+use super::launch::{open_files, paths_from_args};
+use std::path::Path;
+use tauri::plugin::TauriPlugin;
+use tauri::{AppHandle, Wry};
+
+/// 处理另一个进程转发来的启动参数
+fn on_second_instance(app: &AppHandle, args: Vec<String>, cwd: String) {
+    super::commands::raise_main_window(app);
+    open_files(app, paths_from_args(&args, Path::new(&cwd)));
+}
+
+/// 单实例插件，必须在其他插件之前注册
+#[must_use]
+pub fn plugin() -> TauriPlugin<Wry> {
+    tauri_plugin_single_instance::init(on_second_instance)
+}
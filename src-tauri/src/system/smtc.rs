@@ -0,0 +1,277 @@
+//! Windows 系统媒体传输控件（SMTC）
+//!
+//! 音量浮层中的媒体卡片和键盘媒体键通过它显示正在播放的音轨并控制播放。桌面程序没有 UWP 窗口，
+//! 这里通过一个不播放任何内容的 `MediaPlayer` 取得控件，并关闭它的命令管理器，避免它自己响应按钮。
+//! 控件与音频输出无关，切换输出设备后继续工作；所有调用都在专用线程中进行。
+//!
+//! 换音轨时更新标题、艺术家、专辑和封面，播放状态和时间轴随 `playback-state`、`playback-position`
+//! 事件更新，浮层中的进度条因此可以拖动；停止播放后清空显示并关闭控件。按钮和拖动进度条以
+//! `media-control` 事件转发给前端的播放器执行。
+
+use super::media_session::{send_control, MediaControl, NowPlaying};
+use crate::audio::playback::{
+    PlaybackPositionEvent, PlaybackStateEvent, PLAYBACK_POSITION_EVENT, PLAYBACK_STATE_EVENT, TRACK_ENDED_EVENT,
+};
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use std::sync::Mutex;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Listener};
+use windows::core::{Result as WinResult, HSTRING};
+use windows::Foundation::{TimeSpan, TypedEventHandler};
+use windows::Media::Playback::MediaPlayer;
+use windows::Media::{
+    MediaPlaybackStatus, MediaPlaybackType, PlaybackPositionChangeRequestedEventArgs, SystemMediaTransportControls,
+    SystemMediaTransportControlsButton, SystemMediaTransportControlsButtonPressedEventArgs,
+    SystemMediaTransportControlsTimelineProperties,
+};
+use windows::Storage::StorageFile;
+use windows::Storage::Streams::RandomAccessStreamReference;
+
+/// `TimeSpan` 每秒的计数（100 纳秒为单位）
+const TICKS_PER_SEC: f64 = 10_000_000.0;
+/// 播放中更新时间轴的最小间隔
+const TIMELINE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 发给控件线程的更新
+enum Update {
+    State(Box<PlaybackStateEvent>),
+    Position(f32),
+    Ended,
+    Shutdown,
+}
+
+/// 控件线程及其发送端，退出时关闭
+static SERVICE: Mutex<Option<(Sender<Update>, JoinHandle<()>)>> = Mutex::new(None);
+
+/// 当前的播放状态
+struct Session {
+    path: Option<String>,
+    is_playing: bool,
+    ended: bool,
+    duration: Option<f64>,
+    /// 最近一次得知的播放位置（秒）
+    position_secs: f64,
+    /// 上次更新时间轴的时间
+    timeline_at: Option<Instant>,
+}
+
+fn time_span(secs: f64) -> TimeSpan {
+    TimeSpan { Duration: (secs.max(0.0) * TICKS_PER_SEC) as i64 }
+}
+
+/// 把按钮和拖动进度条转发给前端
+fn register_handlers(app: &AppHandle, controls: &SystemMediaTransportControls) -> WinResult<()> {
+    let handle = app.clone();
+    controls.ButtonPressed(&TypedEventHandler::new(
+        move |_, args: &Option<SystemMediaTransportControlsButtonPressedEventArgs>| {
+            let Some(args) = args else { return Ok(()) };
+            let control = match args.Button()? {
+                SystemMediaTransportControlsButton::Play => MediaControl::Play,
+                SystemMediaTransportControlsButton::Pause => MediaControl::Pause,
+                SystemMediaTransportControlsButton::Stop => MediaControl::Stop,
+                SystemMediaTransportControlsButton::Next => MediaControl::Next,
+                SystemMediaTransportControlsButton::Previous => MediaControl::Previous,
+                _ => return Ok(()),
+            };
+            send_control(&handle, control);
+            Ok(())
+        },
+    ))?;
+
+    let handle = app.clone();
+    controls.PlaybackPositionChangeRequested(&TypedEventHandler::new(
+        move |_, args: &Option<PlaybackPositionChangeRequestedEventArgs>| {
+            let Some(args) = args else { return Ok(()) };
+            let position = args.RequestedPlaybackPosition()?.Duration as f64 / TICKS_PER_SEC;
+            send_control(&handle, MediaControl::Seek { position_secs: position.max(0.0) });
+            Ok(())
+        },
+    ))?;
+    Ok(())
+}
+
+/// 显示音轨信息和封面
+fn show_track(controls: &SystemMediaTransportControls, track: &NowPlaying) -> WinResult<()> {
+    let updater = controls.DisplayUpdater()?;
+    updater.ClearAll()?;
+    updater.SetType(MediaPlaybackType::Music)?;
+    let music = updater.MusicProperties()?;
+    music.SetTitle(&HSTRING::from(track.title.as_str()))?;
+    music.SetArtist(&HSTRING::from(track.artist()))?;
+    if let Some(album) = &track.album {
+        music.SetAlbumTitle(&HSTRING::from(album.as_str()))?;
+    }
+    if let Some(album_artist) = &track.album_artist {
+        music.SetAlbumArtist(&HSTRING::from(album_artist.as_str()))?;
+    }
+    if let Some(number) = track.track_number {
+        music.SetTrackNumber(number)?;
+    }
+    if let Some(cover) = &track.cover {
+        // 封面读取失败时只显示文字信息
+        let thumbnail = StorageFile::GetFileFromPathAsync(&HSTRING::from(cover.to_string_lossy().as_ref()))
+            .and_then(|operation| operation.get())
+            .and_then(|file| RandomAccessStreamReference::CreateFromFile(&file));
+        if let Ok(thumbnail) = thumbnail {
+            updater.SetThumbnail(&thumbnail)?;
+        }
+    }
+    updater.Update()
+}
+
+/// 清空显示并关闭控件
+fn clear(controls: &SystemMediaTransportControls) -> WinResult<()> {
+    let updater = controls.DisplayUpdater()?;
+    updater.ClearAll()?;
+    updater.Update()?;
+    controls.SetPlaybackStatus(MediaPlaybackStatus::Closed)?;
+    controls.SetIsEnabled(false)
+}
+
+impl Session {
+    fn status(&self) -> MediaPlaybackStatus {
+        if self.path.is_none() {
+            MediaPlaybackStatus::Closed
+        } else if self.ended {
+            MediaPlaybackStatus::Stopped
+        } else if self.is_playing {
+            MediaPlaybackStatus::Playing
+        } else {
+            MediaPlaybackStatus::Paused
+        }
+    }
+
+    /// 更新时间轴，使浮层中的进度条与播放位置一致
+    fn update_timeline(&mut self, controls: &SystemMediaTransportControls) -> WinResult<()> {
+        self.timeline_at = Some(Instant::now());
+        let Some(duration) = self.duration else { return Ok(()) };
+        let timeline = SystemMediaTransportControlsTimelineProperties::new()?;
+        timeline.SetStartTime(time_span(0.0))?;
+        timeline.SetEndTime(time_span(duration))?;
+        timeline.SetMinSeekTime(time_span(0.0))?;
+        timeline.SetMaxSeekTime(time_span(duration))?;
+        timeline.SetPosition(time_span(self.position_secs.min(duration)))?;
+        controls.UpdateTimelineProperties(&timeline)
+    }
+
+    fn apply(&mut self, app: &AppHandle, controls: &SystemMediaTransportControls, update: Update) -> WinResult<()> {
+        match update {
+            Update::State(event) => {
+                if event.path != self.path {
+                    self.path.clone_from(&event.path);
+                    self.duration = None;
+                    match &event.path {
+                        Some(path) => {
+                            let track = NowPlaying::load(app, path);
+                            self.duration = track.duration;
+                            controls.SetIsEnabled(true)?;
+                            show_track(controls, &track)?;
+                        }
+                        None => return clear(controls),
+                    }
+                }
+                self.is_playing = event.is_playing;
+                if let Some(position) = event.position_secs {
+                    self.position_secs = f64::from(position);
+                }
+                if event.position_secs.is_some() || event.is_playing {
+                    self.ended = false;
+                }
+                controls.SetPlaybackStatus(self.status())?;
+                self.update_timeline(controls)
+            }
+            Update::Position(position) => {
+                if !self.is_playing {
+                    return Ok(());
+                }
+                self.position_secs = f64::from(position);
+                if self.timeline_at.is_none_or(|at| at.elapsed() >= TIMELINE_INTERVAL) {
+                    self.update_timeline(controls)?;
+                }
+                Ok(())
+            }
+            Update::Ended => {
+                self.is_playing = false;
+                self.ended = true;
+                if let Some(duration) = self.duration {
+                    self.position_secs = duration;
+                }
+                controls.SetPlaybackStatus(self.status())?;
+                self.update_timeline(controls)
+            }
+            Update::Shutdown => Ok(()),
+        }
+    }
+}
+
+/// 控件线程：创建控件后按顺序处理更新，收到 `Shutdown` 时清空并退出
+fn run(app: &AppHandle, updates: &Receiver<Update>) -> WinResult<()> {
+    let player = MediaPlayer::new()?;
+    player.CommandManager()?.SetIsEnabled(false)?;
+    let controls = player.SystemMediaTransportControls()?;
+    controls.SetIsPlayEnabled(true)?;
+    controls.SetIsPauseEnabled(true)?;
+    controls.SetIsStopEnabled(true)?;
+    controls.SetIsNextEnabled(true)?;
+    controls.SetIsPreviousEnabled(true)?;
+    controls.SetIsEnabled(false)?;
+    register_handlers(app, &controls)?;
+
+    let mut session =
+        Session { path: None, is_playing: false, ended: false, duration: None, position_secs: 0.0, timeline_at: None };
+    for update in updates {
+        if matches!(update, Update::Shutdown) {
+            break;
+        }
+        if let Err(e) = session.apply(app, &controls, update) {
+            eprintln!("Failed to update media controls: {e}");
+        }
+    }
+    clear(&controls)
+}
+
+/// 把后端的播放事件转发给控件线程
+fn listen_playback(app: &AppHandle, sender: &Sender<Update>) {
+    let tx = sender.clone();
+    app.listen(PLAYBACK_STATE_EVENT, move |event| {
+        if let Ok(state) = serde_json::from_str::<PlaybackStateEvent>(event.payload()) {
+            let _ = tx.send(Update::State(Box::new(state)));
+        }
+    });
+    let tx = sender.clone();
+    app.listen(PLAYBACK_POSITION_EVENT, move |event| {
+        if let Ok(position) = serde_json::from_str::<PlaybackPositionEvent>(event.payload()) {
+            let _ = tx.send(Update::Position(position.position));
+        }
+    });
+    let tx = sender.clone();
+    app.listen(TRACK_ENDED_EVENT, move |_| {
+        let _ = tx.send(Update::Ended);
+    });
+}
+
+/// 启动时创建控件线程
+pub fn start(app: &AppHandle) {
+    let (sender, updates) = unbounded();
+    let handle = app.clone();
+    let thread = std::thread::Builder::new().name("media-controls".to_string()).spawn(move || {
+        if let Err(e) = run(&handle, &updates) {
+            eprintln!("Failed to set up media controls: {e}");
+        }
+    });
+    match thread {
+        Ok(thread) => {
+            listen_playback(app, &sender);
+            *SERVICE.lock().unwrap() = Some((sender, thread));
+        }
+        Err(e) => eprintln!("Failed to start media controls thread: {e}"),
+    }
+}
+
+/// 退出时清空控件并等待线程结束
+pub fn stop() {
+    let Some((sender, thread)) = SERVICE.lock().unwrap().take() else { return };
+    let _ = sender.send(Update::Shutdown);
+    let _ = thread.join();
+}
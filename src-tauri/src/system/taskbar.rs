@@ -0,0 +1,492 @@
+//! Windows 任务栏按钮
+//!
+//! 通过 `ITaskbarList3` 在任务栏按钮上显示当前音轨的播放进度（播放中为正常状态，暂停时为暂停状态，
+//! 停止后清除），并在缩略图下方添加上一首、播放/暂停和下一首按钮，播放/暂停按钮的图标随播放状态切换。
+//! 进度随 `playback-state`、`playback-position` 事件更新，播放中最多每秒更新一次；按钮的提示文字随界面语言
+//! （`language-changed` 事件）切换。
+//!
+//! 这些调用必须在创建窗口的线程（主线程）中进行，事件监听中取得的状态通过 `run_on_main_thread` 交给主线程。
+//! 按钮被点击时系统向窗口发送 `WM_COMMAND`，这里通过窗口子类化接收，以 `media-control` 事件转发给前端的
+//! 播放器执行。资源管理器重启后任务栏按钮会重新创建，收到 `TaskbarButtonCreated` 时重新添加按钮。
+//! 调用 COM 和窗口接口需要 `unsafe`，因此本模块允许 `unsafe`。
+#![allow(unsafe_code)]
+
+use super::media_session::{send_control, MediaControl};
+use crate::audio::playback::{
+    PlaybackPositionEvent, PlaybackStateEvent, PLAYBACK_POSITION_EVENT, PLAYBACK_STATE_EVENT, TRACK_ENDED_EVENT,
+};
+use crate::i18n::LANGUAGE_CHANGED_EVENT;
+use crate::media::metadata::MetadataOptions;
+use crate::media::metadata_cache::cached_track_metadata;
+use crate::tr;
+use crate::AppState;
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Listener, Manager};
+use windows::core::{w, Result as WinResult};
+use windows::Win32::Foundation::{HWND, LPARAM, LRESULT, TRUE, WPARAM};
+use windows::Win32::Graphics::Gdi::{CreateBitmap, DeleteObject};
+use windows::Win32::System::Com::{CoCreateInstance, CLSCTX_INPROC_SERVER};
+use windows::Win32::UI::Shell::{
+    DefSubclassProc, ITaskbarList3, RemoveWindowSubclass, SetWindowSubclass, TaskbarList, TBPF_NOPROGRESS,
+    TBPF_NORMAL, TBPF_PAUSED, THBF_ENABLED, THBN_CLICKED, THB_FLAGS, THB_ICON, THB_TOOLTIP, THUMBBUTTON,
+};
+use windows::Win32::UI::WindowsAndMessaging::{
+    CreateIconIndirect, DestroyIcon, RegisterWindowMessageW, HICON, ICONINFO, WM_COMMAND,
+};
+
+/// 播放中更新进度的最小间隔
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+/// 窗口子类的标识
+const SUBCLASS_ID: usize = 1;
+/// 按钮图标的边长（像素）
+const ICON_SIZE: u16 = 16;
+/// 绘制图标时每个像素在每个方向上的采样数，用于抗锯齿
+const SAMPLES: u16 = 4;
+
+/// 当前的播放状态，由事件监听更新
+struct Playback {
+    path: Option<String>,
+    is_playing: bool,
+    ended: bool,
+    duration: Option<f64>,
+    /// 最近一次得知的播放位置（秒）
+    position_secs: f64,
+    /// 上次交给主线程显示的时间
+    shown_at: Option<Instant>,
+}
+
+static PLAYBACK: Mutex<Playback> = Mutex::new(Playback {
+    path: None,
+    is_playing: false,
+    ended: false,
+    duration: None,
+    position_secs: 0.0,
+    shown_at: None,
+});
+
+/// 窗口过程中转发按钮点击用
+static APP: OnceLock<AppHandle> = OnceLock::new();
+/// `TaskbarButtonCreated` 消息的编号
+static TASKBAR_CREATED: AtomicU32 = AtomicU32::new(0);
+
+thread_local! {
+    /// 任务栏接口只在主线程中使用
+    static TASKBAR: RefCell<Option<Taskbar>> = const { RefCell::new(None) };
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Stopped,
+    Playing,
+    Paused,
+}
+
+/// 交给主线程显示的内容
+#[derive(Debug, Clone, Copy)]
+struct Progress {
+    status: Status,
+    position_secs: f64,
+    duration: Option<f64>,
+}
+
+impl Playback {
+    const fn progress(&self) -> Progress {
+        let status = if self.path.is_none() || self.ended {
+            Status::Stopped
+        } else if self.is_playing {
+            Status::Playing
+        } else {
+            Status::Paused
+        };
+        Progress { status, position_secs: self.position_secs, duration: self.duration }
+    }
+}
+
+/// 缩略图工具栏上的按钮
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Button {
+    Previous,
+    PlayPause,
+    Next,
+}
+
+impl Button {
+    const ALL: [Self; 3] = [Self::Previous, Self::PlayPause, Self::Next];
+
+    const fn id(self) -> u32 {
+        match self {
+            Self::Previous => 0,
+            Self::PlayPause => 1,
+            Self::Next => 2,
+        }
+    }
+
+    fn from_id(id: usize) -> Option<Self> {
+        Self::ALL.into_iter().find(|button| button.id() as usize == id)
+    }
+
+    const fn control(self) -> MediaControl {
+        match self {
+            Self::Previous => MediaControl::Previous,
+            Self::PlayPause => MediaControl::PlayPause,
+            Self::Next => MediaControl::Next,
+        }
+    }
+}
+
+/// 按钮图标的形状，以 16 × 16 的坐标描述
+#[derive(Debug, Clone, Copy)]
+enum Glyph {
+    Previous,
+    Play,
+    Pause,
+    Next,
+}
+
+/// 点是否在三角形内（含边上）
+fn in_triangle([a, b, c]: [(f32, f32); 3], x: f32, y: f32) -> bool {
+    let side = |(x1, y1): (f32, f32), (x2, y2): (f32, f32)| (x2 - x1) * (y - y1) - (y2 - y1) * (x - x1);
+    let sides = [side(a, b), side(b, c), side(c, a)];
+    sides.iter().all(|side| *side >= 0.0) || sides.iter().all(|side| *side <= 0.0)
+}
+
+impl Glyph {
+    fn contains(self, x: f32, y: f32) -> bool {
+        let bar = |left: f32, right: f32| (left..right).contains(&x) && (3.0..13.0).contains(&y);
+        match self {
+            Self::Previous => bar(3.5, 5.5) || in_triangle([(13.0, 3.0), (5.5, 8.0), (13.0, 13.0)], x, y),
+            Self::Play => in_triangle([(4.5, 2.5), (13.0, 8.0), (4.5, 13.5)], x, y),
+            Self::Pause => bar(4.0, 7.0) || bar(9.0, 12.0),
+            Self::Next => in_triangle([(3.0, 3.0), (10.5, 8.0), (3.0, 13.0)], x, y) || bar(10.5, 12.5),
+        }
+    }
+
+    /// 白色图形的 32 位 ARGB 像素，按行从上到下排列
+    fn pixels(self) -> Vec<u32> {
+        let step = 1.0 / f32::from(SAMPLES);
+        let mut pixels = Vec::with_capacity(usize::from(ICON_SIZE) * usize::from(ICON_SIZE));
+        for row in 0..ICON_SIZE {
+            for column in 0..ICON_SIZE {
+                let mut covered = 0_u32;
+                for sample_y in 0..SAMPLES {
+                    for sample_x in 0..SAMPLES {
+                        let x = f32::from(column) + (f32::from(sample_x) + 0.5) * step;
+                        let y = f32::from(row) + (f32::from(sample_y) + 0.5) * step;
+                        covered += u32::from(self.contains(x, y));
+                    }
+                }
+                let alpha = covered * 255 / u32::from(SAMPLES * SAMPLES);
+                pixels.push((alpha << 24) | 0x00FF_FFFF);
+            }
+        }
+        pixels
+    }
+
+    fn create_icon(self) -> WinResult<HICON> {
+        let pixels = self.pixels();
+        // 单色掩码每行 2 字节；颜色位图带透明度时掩码不起作用，全部为 0
+        let mask_bits = vec![0_u8; usize::from(ICON_SIZE) * usize::from(ICON_SIZE) / 8];
+        let size = i32::from(ICON_SIZE);
+        unsafe {
+            let color = CreateBitmap(size, size, 1, 32, Some(pixels.as_ptr().cast()));
+            let mask = CreateBitmap(size, size, 1, 1, Some(mask_bits.as_ptr().cast()));
+            let info = ICONINFO { fIcon: TRUE, xHotspot: 0, yHotspot: 0, hbmMask: mask, hbmColor: color };
+            let icon = CreateIconIndirect(&info);
+            let _ = DeleteObject(color);
+            let _ = DeleteObject(mask);
+            icon
+        }
+    }
+}
+
+/// 按钮图标，释放时销毁
+struct Icons {
+    previous: HICON,
+    play: HICON,
+    pause: HICON,
+    next: HICON,
+}
+
+impl Icons {
+    fn new() -> WinResult<Self> {
+        Ok(Self {
+            previous: Glyph::Previous.create_icon()?,
+            play: Glyph::Play.create_icon()?,
+            pause: Glyph::Pause.create_icon()?,
+            next: Glyph::Next.create_icon()?,
+        })
+    }
+}
+
+impl Drop for Icons {
+    fn drop(&mut self) {
+        for icon in [self.previous, self.play, self.pause, self.next] {
+            let _ = unsafe { DestroyIcon(icon) };
+        }
+    }
+}
+
+/// 按钮的提示文字（`szTip` 最多 260 个字符，含结尾的 0）
+fn tooltip(text: &str) -> [u16; 260] {
+    let mut tip = [0_u16; 260];
+    for (slot, unit) in tip.iter_mut().zip(text.encode_utf16().take(259)) {
+        *slot = unit;
+    }
+    tip
+}
+
+/// 按界面语言取上一首、播放、暂停和下一首的提示文字
+fn labels() -> [String; 4] {
+    [tr!("taskbar.previous"), tr!("taskbar.play"), tr!("taskbar.pause"), tr!("taskbar.next")]
+}
+
+/// 主窗口的任务栏按钮
+struct Taskbar {
+    list: ITaskbarList3,
+    hwnd: HWND,
+    icons: Icons,
+    /// 已添加的按钮中播放/暂停按钮是否显示暂停图标；还没有添加按钮时为 `None`
+    buttons: Option<bool>,
+}
+
+impl Taskbar {
+    fn new(hwnd: HWND) -> WinResult<Self> {
+        let list: ITaskbarList3 = unsafe { CoCreateInstance(&TaskbarList, None, CLSCTX_INPROC_SERVER)? };
+        unsafe { list.HrInit()? };
+        Ok(Self { list, hwnd, icons: Icons::new()?, buttons: None })
+    }
+
+    /// 添加或更新按钮；同一个任务栏按钮上只能添加一次，之后只能更新
+    fn update_buttons(&mut self, is_playing: bool) -> WinResult<()> {
+        let [previous, play, pause, next] = labels();
+        let buttons = Button::ALL.map(|button| {
+            let (icon, label) = match button {
+                Button::Previous => (self.icons.previous, &previous),
+                Button::PlayPause if is_playing => (self.icons.pause, &pause),
+                Button::PlayPause => (self.icons.play, &play),
+                Button::Next => (self.icons.next, &next),
+            };
+            THUMBBUTTON {
+                dwMask: THB_ICON | THB_TOOLTIP | THB_FLAGS,
+                iId: button.id(),
+                hIcon: icon,
+                szTip: tooltip(label),
+                dwFlags: THBF_ENABLED,
+                ..Default::default()
+            }
+        });
+        unsafe {
+            if self.buttons.is_some() {
+                self.list.ThumbBarUpdateButtons(self.hwnd, &buttons)?;
+            } else {
+                self.list.ThumbBarAddButtons(self.hwnd, &buttons)?;
+            }
+        }
+        self.buttons = Some(is_playing);
+        Ok(())
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn show(&mut self, progress: Progress) -> WinResult<()> {
+        let is_playing = progress.status == Status::Playing;
+        if self.buttons != Some(is_playing) {
+            self.update_buttons(is_playing)?;
+        }
+        let duration = progress.duration.filter(|duration| *duration > 0.0);
+        unsafe {
+            match (progress.status, duration) {
+                (Status::Stopped, _) | (_, None) => self.list.SetProgressState(self.hwnd, TBPF_NOPROGRESS),
+                (status, Some(duration)) => {
+                    let state = if status == Status::Playing { TBPF_NORMAL } else { TBPF_PAUSED };
+                    self.list.SetProgressState(self.hwnd, state)?;
+                    let completed = (progress.position_secs.clamp(0.0, duration) * 1000.0) as u64;
+                    self.list.SetProgressValue(self.hwnd, completed, (duration * 1000.0) as u64)
+                }
+            }
+        }
+    }
+}
+
+/// 在主线程中显示；窗口过程正在使用任务栏时跳过
+fn show(progress: Progress) {
+    TASKBAR.with(|cell| {
+        let Ok(mut taskbar) = cell.try_borrow_mut() else { return };
+        if let Some(taskbar) = taskbar.as_mut()
+            && let Err(e) = taskbar.show(progress)
+        {
+            eprintln!("Failed to update taskbar button: {e}");
+        }
+    });
+}
+
+/// 界面语言变化后在主线程中更新已添加按钮的提示文字
+fn relabel() {
+    TASKBAR.with(|cell| {
+        let Ok(mut taskbar) = cell.try_borrow_mut() else { return };
+        if let Some(taskbar) = taskbar.as_mut()
+            && let Some(is_playing) = taskbar.buttons
+            && let Err(e) = taskbar.update_buttons(is_playing)
+        {
+            eprintln!("Failed to update taskbar button: {e}");
+        }
+    });
+}
+
+/// 把当前的播放状态交给主线程显示
+fn refresh(app: &AppHandle) {
+    let progress = {
+        let mut playback = PLAYBACK.lock().unwrap();
+        playback.shown_at = Some(Instant::now());
+        playback.progress()
+    };
+    let _ = app.run_on_main_thread(move || show(progress));
+}
+
+/// 主窗口的子类过程：转发缩略图按钮的点击，任务栏按钮重新创建后重新添加按钮
+unsafe extern "system" fn subclass_proc(
+    hwnd: HWND,
+    message: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+    _id: usize,
+    _data: usize,
+) -> LRESULT {
+    if message == WM_COMMAND && (wparam.0 >> 16) & 0xFFFF == THBN_CLICKED as usize {
+        if let Some(app) = APP.get()
+            && let Some(button) = Button::from_id(wparam.0 & 0xFFFF)
+        {
+            send_control(app, button.control());
+        }
+        return LRESULT(0);
+    }
+    let taskbar_created = TASKBAR_CREATED.load(Ordering::Relaxed);
+    if taskbar_created != 0 && message == taskbar_created {
+        TASKBAR.with(|cell| {
+            if let Ok(mut taskbar) = cell.try_borrow_mut()
+                && let Some(taskbar) = taskbar.as_mut()
+            {
+                taskbar.buttons = None;
+            }
+        });
+        let progress = PLAYBACK.lock().unwrap().progress();
+        show(progress);
+    }
+    unsafe { DefSubclassProc(hwnd, message, wparam, lparam) }
+}
+
+/// 在主线程中创建任务栏接口并子类化主窗口
+fn install(app: &AppHandle) -> WinResult<()> {
+    let Some(window) = app.get_webview_window("main") else { return Ok(()) };
+    let Ok(raw) = window.hwnd() else { return Ok(()) };
+    let hwnd = HWND(raw.0);
+    let taskbar = Taskbar::new(hwnd)?;
+    unsafe {
+        TASKBAR_CREATED.store(RegisterWindowMessageW(w!("TaskbarButtonCreated")), Ordering::Relaxed);
+        if !SetWindowSubclass(hwnd, Some(subclass_proc), SUBCLASS_ID, 0).as_bool() {
+            return Err(windows::core::Error::from_win32());
+        }
+    }
+    TASKBAR.with(|cell| *cell.borrow_mut() = Some(taskbar));
+    // 任务栏按钮可能已经创建，此时不会再收到 `TaskbarButtonCreated`
+    let progress = PLAYBACK.lock().unwrap().progress();
+    show(progress);
+    Ok(())
+}
+
+/// 在后台读取新音轨的时长，仍在播放该音轨时更新进度
+fn load_duration(app: &AppHandle, path: String) {
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let config = app.state::<AppState>().config_manager.load_config().unwrap_or_default();
+        let Ok(metadata) = cached_track_metadata(&path, &MetadataOptions::from_config(&config)) else { return };
+        {
+            let mut playback = PLAYBACK.lock().unwrap();
+            if playback.path.as_deref() != Some(path.as_str()) {
+                return;
+            }
+            playback.duration = metadata.duration;
+        }
+        refresh(&app);
+    });
+}
+
+/// 根据后端的播放事件更新状态
+fn listen_playback(app: &AppHandle) {
+    let handle = app.clone();
+    app.listen(PLAYBACK_STATE_EVENT, move |event| {
+        let Ok(state) = serde_json::from_str::<PlaybackStateEvent>(event.payload()) else { return };
+        {
+            let mut playback = PLAYBACK.lock().unwrap();
+            if state.path != playback.path {
+                playback.path.clone_from(&state.path);
+                playback.duration = None;
+                playback.position_secs = 0.0;
+                if let Some(path) = state.path {
+                    load_duration(&handle, path);
+                }
+            }
+            playback.is_playing = state.is_playing;
+            if let Some(position) = state.position_secs {
+                playback.position_secs = f64::from(position);
+            }
+            if state.position_secs.is_some() || state.is_playing {
+                playback.ended = false;
+            }
+        }
+        refresh(&handle);
+    });
+    let handle = app.clone();
+    app.listen(PLAYBACK_POSITION_EVENT, move |event| {
+        let Ok(position) = serde_json::from_str::<PlaybackPositionEvent>(event.payload()) else { return };
+        {
+            let mut playback = PLAYBACK.lock().unwrap();
+            if !playback.is_playing {
+                return;
+            }
+            playback.position_secs = f64::from(position.position);
+            if playback.shown_at.is_some_and(|at| at.elapsed() < PROGRESS_INTERVAL) {
+                return;
+            }
+        }
+        refresh(&handle);
+    });
+    let handle = app.clone();
+    app.listen(TRACK_ENDED_EVENT, move |_| {
+        {
+            let mut playback = PLAYBACK.lock().unwrap();
+            playback.is_playing = false;
+            playback.ended = true;
+        }
+        refresh(&handle);
+    });
+    let handle = app.clone();
+    app.listen(LANGUAGE_CHANGED_EVENT, move |_| {
+        let _ = handle.run_on_main_thread(relabel);
+    });
+}
+
+/// 启动时在主线程中添加按钮，并开始监听播放状态
+pub fn start(app: &AppHandle) {
+    let _ = APP.set(app.clone());
+    let handle = app.clone();
+    let installed = app.run_on_main_thread(move || {
+        if let Err(e) = install(&handle) {
+            eprintln!("Failed to set up taskbar buttons: {e}");
+        }
+    });
+    match installed {
+        Ok(()) => listen_playback(app),
+        Err(e) => eprintln!("Failed to set up taskbar buttons: {e}"),
+    }
+}
+
+/// 退出时（在主线程中）移除窗口子类并销毁图标
+pub fn stop() {
+    TASKBAR.with(|cell| {
+        let Some(taskbar) = cell.borrow_mut().take() else { return };
+        let _ = unsafe { RemoveWindowSubclass(taskbar.hwnd, Some(subclass_proc), SUBCLASS_ID) };
+    });
+}
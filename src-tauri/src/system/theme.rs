@@ -0,0 +1,281 @@
+//! 系统主题
+//!
+//! 读取操作系统的深色/浅色设置和强调色，供前端的“跟随系统”主题和 MD3 配色使用：
+//!
+//! - Windows：注册表 `Themes\Personalize` 的 `AppsUseLightTheme` 和 `DWM` 的 `AccentColor`
+//! - macOS：应用的 `NSAppearance` 和 `NSColor.controlAccentColor`（只能在主线程读取）
+//! - Linux：桌面门户（xdg-desktop-portal）`org.freedesktop.appearance` 中的 `color-scheme` 和 `accent-color`
+//!
+//! 其他平台返回 `unsupported`。程序运行期间定时重新读取，设置变化时发送 `system-theme-changed` 事件。
+//! 各平台的变化通知机制各不相同（注册表通知、分布式通知、门户信号），定时读取的开销很小，行为也一致。
+
+use serde::Serialize;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+/// 系统主题变化的事件
+pub const SYSTEM_THEME_CHANGED_EVENT: &str = "system-theme-changed";
+
+/// 重新读取系统设置的间隔
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// 系统的深色/浅色设置
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SystemTheme {
+    Dark,
+    Light,
+    /// 读取失败，或系统没有偏好（如 Linux 的 `color-scheme` 为 0）
+    Unknown,
+    /// 当前平台无法读取
+    Unsupported,
+}
+
+/// 系统强调色
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum SystemAccentColor {
+    /// `#rrggbb` 格式的颜色
+    Color { hex: String },
+    /// 读取失败，或用户没有设置
+    Unknown,
+    /// 当前平台没有强调色
+    Unsupported,
+}
+
+impl SystemAccentColor {
+    /// 由 0–1 之间的 RGB 分量构造，超出范围时视为未设置
+    #[cfg_attr(not(any(target_os = "macos", target_os = "linux")), allow(dead_code))]
+    fn from_unit_rgb(red: f64, green: f64, blue: f64) -> Self {
+        if ![red, green, blue].iter().all(|channel| (0.0..=1.0).contains(channel)) {
+            return Self::Unknown;
+        }
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let [r, g, b] = [red, green, blue].map(|channel| (channel * 255.0).round() as u8);
+        Self::Color { hex: format!("#{r:02x}{g:02x}{b:02x}") }
+    }
+}
+
+/// `system-theme-changed` 事件
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemThemeChangedEvent {
+    pub theme: SystemTheme,
+    pub accent_color: SystemAccentColor,
+}
+
+#[cfg(windows)]
+mod platform {
+    use super::{SystemAccentColor, SystemTheme};
+    use tauri::AppHandle;
+    use winreg::enums::HKEY_CURRENT_USER;
+    use winreg::RegKey;
+
+    const PERSONALIZE: &str = r"Software\Microsoft\Windows\CurrentVersion\Themes\Personalize";
+    const DWM: &str = r"Software\Microsoft\Windows\DWM";
+
+    fn read_dword(path: &str, name: &str) -> Option<u32> {
+        RegKey::predef(HKEY_CURRENT_USER).open_subkey(path).ok()?.get_value(name).ok()
+    }
+
+    #[allow(clippy::unused_async)] // 与其他平台的签名保持一致
+    pub async fn theme(_app: &AppHandle) -> SystemTheme {
+        match read_dword(PERSONALIZE, "AppsUseLightTheme") {
+            Some(0) => SystemTheme::Dark,
+            Some(_) => SystemTheme::Light,
+            None => SystemTheme::Unknown,
+        }
+    }
+
+    #[allow(clippy::unused_async)]
+    pub async fn accent_color(_app: &AppHandle) -> SystemAccentColor {
+        // 按 0xAABBGGRR 存储
+        read_dword(DWM, "AccentColor").map_or(SystemAccentColor::Unknown, |abgr| {
+            let [r, g, b, _] = abgr.to_le_bytes();
+            SystemAccentColor::Color { hex: format!("#{r:02x}{g:02x}{b:02x}") }
+        })
+    }
+}
+
+#[cfg(target_os = "macos")]
+#[allow(unsafe_code)]
+mod platform {
+    use super::{SystemAccentColor, SystemTheme};
+    use objc2_app_kit::{
+        NSAppearanceCustomization, NSAppearanceNameAqua, NSAppearanceNameDarkAqua, NSApplication, NSColor, NSColorSpace,
+    };
+    use objc2_foundation::{MainThreadMarker, NSArray};
+    use tauri::AppHandle;
+
+    /// 在主线程执行并取回结果；已在主线程时直接执行
+    async fn on_main_thread<T: Send + 'static>(
+        app: &AppHandle,
+        read: impl FnOnce(MainThreadMarker) -> T + Send + 'static,
+    ) -> Option<T> {
+        if let Some(mtm) = MainThreadMarker::new() {
+            return Some(read(mtm));
+        }
+        let (sender, receiver) = tokio::sync::oneshot::channel();
+        app.run_on_main_thread(move || {
+            if let Some(mtm) = MainThreadMarker::new() {
+                let _ = sender.send(read(mtm));
+            }
+        })
+        .ok()?;
+        receiver.await.ok()
+    }
+
+    fn read_theme(mtm: MainThreadMarker) -> SystemTheme {
+        // Objective-C 调用；外观名称是框架导出的常量
+        let best = unsafe {
+            let appearance = NSApplication::sharedApplication(mtm).effectiveAppearance();
+            let names = NSArray::from_slice(&[NSAppearanceNameAqua, NSAppearanceNameDarkAqua]);
+            appearance
+                .bestMatchFromAppearancesWithNames(&names)
+                .map(|name| name.isEqualToString(NSAppearanceNameDarkAqua))
+        };
+        match best {
+            Some(true) => SystemTheme::Dark,
+            Some(false) => SystemTheme::Light,
+            None => SystemTheme::Unknown,
+        }
+    }
+
+    fn read_accent_color(_mtm: MainThreadMarker) -> SystemAccentColor {
+        // Objective-C 调用；强调色可能是动态颜色，先转换到 sRGB 再取分量
+        let components = unsafe {
+            NSColor::controlAccentColor()
+                .colorUsingColorSpace(&NSColorSpace::sRGBColorSpace())
+                .map(|color| (color.redComponent(), color.greenComponent(), color.blueComponent()))
+        };
+        components.map_or(SystemAccentColor::Unknown, |(r, g, b)| SystemAccentColor::from_unit_rgb(r, g, b))
+    }
+
+    pub async fn theme(app: &AppHandle) -> SystemTheme {
+        on_main_thread(app, read_theme).await.unwrap_or(SystemTheme::Unknown)
+    }
+
+    pub async fn accent_color(app: &AppHandle) -> SystemAccentColor {
+        on_main_thread(app, read_accent_color).await.unwrap_or(SystemAccentColor::Unknown)
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::{SystemAccentColor, SystemTheme};
+    use std::sync::OnceLock;
+    use tauri::AppHandle;
+    use zbus::zvariant::{OwnedValue, Value};
+    use zbus::{Connection, Message};
+
+    const PORTAL_SERVICE: &str = "org.freedesktop.portal.Desktop";
+    const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
+    const SETTINGS_INTERFACE: &str = "org.freedesktop.portal.Settings";
+    const APPEARANCE_NAMESPACE: &str = "org.freedesktop.appearance";
+
+    /// 会话总线连接，首次读取时建立
+    static CONNECTION: OnceLock<Connection> = OnceLock::new();
+
+    async fn connection() -> Option<&'static Connection> {
+        if let Some(connection) = CONNECTION.get() {
+            return Some(connection);
+        }
+        let connection = Connection::session().await.ok()?;
+        Some(CONNECTION.get_or_init(|| connection))
+    }
+
+    async fn call(connection: &Connection, method: &str, key: &str) -> zbus::Result<Message> {
+        connection
+            .call_method(
+                Some(PORTAL_SERVICE),
+                PORTAL_PATH,
+                Some(SETTINGS_INTERFACE),
+                method,
+                &(APPEARANCE_NAMESPACE, key),
+            )
+            .await
+    }
+
+    /// 读取门户中的外观设置；旧版本的门户没有 `ReadOne`，`Read` 返回的值外面多包一层
+    async fn read_setting(key: &str) -> Option<OwnedValue> {
+        let connection = connection().await?;
+        let reply = match call(connection, "ReadOne", key).await {
+            Ok(reply) => reply,
+            Err(_) => call(connection, "Read", key).await.ok()?,
+        };
+        reply.body().deserialize::<OwnedValue>().ok()
+    }
+
+    fn theme_from_value(value: &Value<'_>) -> SystemTheme {
+        match value {
+            Value::Value(inner) => theme_from_value(inner),
+            Value::U32(1) => SystemTheme::Dark,
+            Value::U32(2) => SystemTheme::Light,
+            _ => SystemTheme::Unknown,
+        }
+    }
+
+    fn accent_from_value(value: &Value<'_>) -> SystemAccentColor {
+        match value {
+            Value::Value(inner) => accent_from_value(inner),
+            Value::Structure(structure) => match structure.fields() {
+                [Value::F64(r), Value::F64(g), Value::F64(b)] => SystemAccentColor::from_unit_rgb(*r, *g, *b),
+                _ => SystemAccentColor::Unknown,
+            },
+            _ => SystemAccentColor::Unknown,
+        }
+    }
+
+    pub async fn theme(_app: &AppHandle) -> SystemTheme {
+        read_setting("color-scheme").await.map_or(SystemTheme::Unknown, |value| theme_from_value(&value))
+    }
+
+    pub async fn accent_color(_app: &AppHandle) -> SystemAccentColor {
+        read_setting("accent-color").await.map_or(SystemAccentColor::Unknown, |value| accent_from_value(&value))
+    }
+}
+
+#[cfg(not(any(windows, target_os = "macos", target_os = "linux")))]
+#[allow(clippy::unused_async)] // 与其他平台的签名保持一致
+mod platform {
+    use super::{SystemAccentColor, SystemTheme};
+    use tauri::AppHandle;
+
+    pub async fn theme(_app: &AppHandle) -> SystemTheme {
+        SystemTheme::Unsupported
+    }
+
+    pub async fn accent_color(_app: &AppHandle) -> SystemAccentColor {
+        SystemAccentColor::Unsupported
+    }
+}
+
+/// 系统的深色/浅色设置
+pub async fn system_theme(app: &AppHandle) -> SystemTheme {
+    platform::theme(app).await
+}
+
+/// 系统强调色
+pub async fn system_accent_color(app: &AppHandle) -> SystemAccentColor {
+    platform::accent_color(app).await
+}
+
+/// 启动时开始定时读取系统设置，变化时发送事件
+pub fn start(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut last = (system_theme(&app).await, system_accent_color(&app).await);
+        if last == (SystemTheme::Unsupported, SystemAccentColor::Unsupported) {
+            return;
+        }
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            let current = (system_theme(&app).await, system_accent_color(&app).await);
+            if current != last {
+                let (theme, accent_color) = current.clone();
+                let _ = app.emit(SYSTEM_THEME_CHANGED_EVENT, SystemThemeChangedEvent { theme, accent_color });
+                last = current;
+            }
+        }
+    });
+}
@@ -0,0 +1,162 @@
+//! 后台任务模块
+//!
+//! 管理可取消的长时间任务，如指纹计算、批量写入标签和目录扫描。批量任务在使用电池时可能暂停
+//! （见 `system::power`），暂停状态可以通过任务状态查询。
+
+use crate::error::{AppError, AppResult};
+use crate::tr;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// 任务进度事件名称
+pub const TASK_PROGRESS_EVENT: &str = "task-progress";
+/// 任务暂停或继续事件名称
+pub const TASK_STATUS_EVENT: &str = "task-status";
+
+/// 任务进度事件
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskProgressEvent {
+    pub task_id: String,
+    /// 任务类型（如 "fingerprint"、"write-tags"）
+    pub kind: String,
+    pub completed: usize,
+    pub total: usize,
+    /// 当前处理的文件
+    pub current: Option<String>,
+}
+
+/// 任务状态，也是 `task-status` 事件的负载
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskStatus {
+    pub task_id: String,
+    /// 使用电池期间为省电暂停，接上电源后自动继续
+    pub paused: bool,
+}
+
+/// 取消令牌，同时记录任务是否暂停
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+    paused: Arc<AtomicBool>,
+}
+
+impl CancelToken {
+    /// 是否已被取消
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// 是否已暂停
+    #[must_use]
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    /// 标记任务暂停或继续，返回状态是否有变化
+    pub fn set_paused(&self, paused: bool) -> bool {
+        self.paused.swap(paused, Ordering::Relaxed) != paused
+    }
+
+    /// 已取消时返回 `AppError::Cancelled`
+    pub fn check(&self) -> AppResult<()> {
+        if self.is_cancelled() {
+            Err(AppError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+/// 正在运行的任务表
+#[derive(Debug, Default)]
+pub struct TaskRegistry {
+    tasks: Mutex<HashMap<String, CancelToken>>,
+}
+
+impl TaskRegistry {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 登记任务并返回取消令牌；同名任务已存在时会先取消旧任务
+    pub fn start(&self, task_id: &str) -> CancelToken {
+        let token = CancelToken::default();
+        let previous = self.tasks.lock().unwrap().insert(task_id.to_string(), token.clone());
+        if let Some(previous) = previous {
+            previous.cancel();
+        }
+        token
+    }
+
+    /// 登记任务，已有同前缀的任务在运行时返回 `None`（用于不允许并发的任务）
+    pub fn start_exclusive(&self, task_id: &str, prefix: &str) -> Option<CancelToken> {
+        let mut tasks = self.tasks.lock().unwrap();
+        if tasks.keys().any(|id| id.starts_with(prefix)) {
+            return None;
+        }
+        let token = CancelToken::default();
+        tasks.insert(task_id.to_string(), token.clone());
+        Some(token)
+    }
+
+    /// 取消任务，任务不存在时返回 `false`
+    pub fn cancel(&self, task_id: &str) -> bool {
+        self.tasks
+            .lock()
+            .unwrap()
+            .get(task_id)
+            .map(CancelToken::cancel)
+            .is_some()
+    }
+
+    /// 正在运行的任务的状态，任务不存在时返回 `None`
+    pub fn status(&self, task_id: &str) -> Option<TaskStatus> {
+        self.tasks
+            .lock()
+            .unwrap()
+            .get(task_id)
+            .map(|token| TaskStatus { task_id: task_id.to_string(), paused: token.is_paused() })
+    }
+
+    /// 所有正在运行的任务的状态
+    pub fn statuses(&self) -> Vec<TaskStatus> {
+        let mut statuses: Vec<TaskStatus> = self
+            .tasks
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(task_id, token)| TaskStatus { task_id: task_id.clone(), paused: token.is_paused() })
+            .collect();
+        statuses.sort_by(|a, b| a.task_id.cmp(&b.task_id));
+        statuses
+    }
+
+    /// 任务结束后移除登记
+    pub fn finish(&self, task_id: &str, token: &CancelToken) {
+        let mut tasks = self.tasks.lock().unwrap();
+        if tasks.get(task_id).is_some_and(|current| Arc::ptr_eq(&current.cancelled, &token.cancelled)) {
+            tasks.remove(task_id);
+        }
+    }
+}
+
+/// 在阻塞线程池中运行任务，避免占用 IPC 线程
+pub async fn run_blocking<T, F>(task: F) -> AppResult<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> AppResult<T> + Send + 'static,
+{
+    tauri::async_runtime::spawn_blocking(task)
+        .await
+        .map_err(|e| AppError::Other(tr!("tasks.crashed", detail = e)))?
+}
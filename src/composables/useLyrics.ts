@@ -5,8 +5,9 @@ import { FileUtils } from '@/utils/fileUtils'
 import { neteaseApi } from '@/utils/neteaseApi'
 import { LyricsParser } from '@/utils/lyricsParser'
 import { invoke } from '@tauri-apps/api/core'
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
 import logger from '@/utils/logger'
-import type { LyricLine, Track } from '@/types'
+import type { EmbeddedLyrics, LyricLine, LyricsFileChangedEvent, Track } from '@/types'
 
 /**
  * 简单的 LRU 缓存，用于在线歌词
@@ -50,6 +51,8 @@ const onlineLyricsCache = new LyricsLRUCache(50)
 
 // 模块级别的共享状态，确保所有 useLyrics 实例共享同一个 lyricsSource
 const sharedLyricsSource = ref<'local' | 'online'>('local')
+// 在线来源确认当前歌曲是纯音乐
+const sharedInstrumental = ref(false)
 
 export function useLyrics() {
   const playerStore = usePlayerStore()
@@ -60,6 +63,7 @@ export function useLyrics() {
   // 使用共享的 lyricsSource
   const lyricsSource: Ref<'local' | 'online'> = sharedLyricsSource
   const onlineLyricsError = ref<string | null>(null)
+  const instrumental: Ref<boolean> = sharedInstrumental
 
   const fetchOnlineLyrics = async (track: Track | null): Promise<string | null> => {
     if (!track) return null
@@ -69,13 +73,15 @@ export function useLyrics() {
       const duration = track.duration ? track.duration * 1000 : 0
       logger.debug('Fetching online lyrics for: ' + title + ' - ' + artist)
       const lyricsData = await neteaseApi.searchAndGetLyrics(title, artist, duration)
+      instrumental.value = lyricsData?.kind === 'instrumental'
       if (!lyricsData || !lyricsData.lrc) {
         logger.debug('No online lyrics found')
         return null
       }
       let lrcContent = lyricsData.lrc
-      if (configStore.lyrics?.preferTranslation && lyricsData.tlyric) {
-        lrcContent = neteaseApi.mergeLyrics(lyricsData.lrc, lyricsData.tlyric)
+      // 后端只返回设置中开启的翻译、罗马音
+      if (lyricsData.tlyric || lyricsData.romalrc) {
+        lrcContent = await neteaseApi.mergeLyrics(lyricsData.lrc, lyricsData.tlyric, lyricsData.romalrc)
       }
       return lrcContent
     } catch (error) {
@@ -100,14 +106,33 @@ export function useLyrics() {
     }
   }
 
+  // 没有歌词文件时读取文件标签中内嵌的歌词
+  const loadEmbeddedLyrics = async (trackPath: string): Promise<boolean> => {
+    try {
+      const embedded = await invoke<EmbeddedLyrics | null>('get_embedded_lyrics', { path: trackPath })
+      if (!embedded?.text) return false
+      const parsed = await LyricsParser.parseAsync(embedded.text, embedded.synced ? 'lrc' : 'auto')
+      if (!parsed.length) return false
+      lyrics.value = parsed
+      playerStore.lyrics = parsed
+      return true
+    } catch (error) {
+      logger.debug('No embedded lyrics:', error)
+      return false
+    }
+  }
+
   const loadLyrics = async (trackPath: string | undefined): Promise<void> => {
     if (!trackPath) { 
       lyrics.value = []
       playerStore.lyrics = null
       lyricsSource.value = 'local'
       onlineLyricsError.value = null
+      instrumental.value = false
       return
     }
+
+    void playerStore.loadLyricsOffset(trackPath)
     
     // 先检查缓存中是否有这首歌的在线歌词
     const cached = onlineLyricsCache.get(trackPath)
@@ -125,15 +150,17 @@ export function useLyrics() {
     playerStore.lyrics = null
     lyricsSource.value = 'local'
     onlineLyricsError.value = null
+    instrumental.value = false
     try {
       const lyricsPath = await FileUtils.findLyricsFile(trackPath)
       if (lyricsPath) {
         const content = await FileUtils.readFile(lyricsPath)
-        const ext = FileUtils.getFileExtension(lyricsPath) as 'lrc' | 'ass' | 'srt'
         // 使用统一的异步解析器
-        lyrics.value = await LyricsParser.parseAsync(content, ext)
+        lyrics.value = await LyricsParser.parseAsync(content, FileUtils.getLyricsFormat(lyricsPath))
         playerStore.lyrics = lyrics.value
         lyricsSource.value = 'local'
+      } else if (await loadEmbeddedLyrics(trackPath)) {
+        lyricsSource.value = 'local'
       } else if (configStore.lyrics?.enableOnlineFetch) {
         logger.debug('No local lyrics found, trying online fetch...')
         const track = playerStore.currentTrack
@@ -212,6 +239,20 @@ export function useLyrics() {
 
   const stopWatchTrack = watch(() => playerStore.currentTrack?.path, loadLyrics, { immediate: true })
 
+  // 歌词文件被外部编辑器修改时重新加载
+  let lyricsFileUnlisten: UnlistenFn | null = null
+  let disposed = false
+  listen<LyricsFileChangedEvent>('lyrics-file-changed', (event) => {
+    const trackPath = playerStore.currentTrack?.path
+    if (!trackPath || !FileUtils.pathsEqual(trackPath, event.payload.trackPath)) return
+    logger.debug('Lyrics file changed externally:', event.payload.paths)
+    onlineLyricsCache.delete(trackPath)
+    loadLyrics(trackPath)
+  }).then((unlisten) => {
+    if (disposed) unlisten()
+    else lyricsFileUnlisten = unlisten
+  })
+
   // activeIndex 更新逻辑 - 使用节流避免高频更新
   let lastActiveIndexUpdate = 0
   const ACTIVE_INDEX_THROTTLE = 100 // 每 100ms 更新一次
@@ -260,6 +301,9 @@ export function useLyrics() {
   const cleanup = (): void => {
     stopWatchTrack()
     stopWatchEffect()
+    disposed = true
+    lyricsFileUnlisten?.()
+    lyricsFileUnlisten = null
   }
 
   return {
@@ -268,6 +312,7 @@ export function useLyrics() {
     activeIndex,
     lyricsSource,
     onlineLyricsError,
+    instrumental,
     fetchAndSaveLyrics,
     loadLyrics,
     cleanup
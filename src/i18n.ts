@@ -1,5 +1,6 @@
 import { createI18n } from 'vue-i18n'
 import type { Ref } from 'vue'
+import { invoke } from '@tauri-apps/api/core'
 
 // 导入翻译文件
 import zh from './locales/zh.json'
@@ -27,6 +28,23 @@ export const setLocale = (locale: string): void => {
   (i18n.global.locale as Ref<string>).value = locale
 }
 
+// 设置中跟随系统语言的值
+export const SYSTEM_LANGUAGE = 'system'
+
+// 将设置中的语言转换为界面语言：跟随系统时按系统语言选择，中文以外的语言使用英文
+export const resolveLocale = async (language: string): Promise<string> => {
+  const tag = !language || language === SYSTEM_LANGUAGE
+    ? await invoke<string>('get_system_locale').catch(() => 'zh')
+    : language
+  return tag.toLowerCase().startsWith('zh') ? 'zh' : 'en'
+}
+
+// 按设置切换界面语言，同时切换后端生成的文字（错误信息、任务栏按钮等）的语言
+export const applyLanguage = async (language: string): Promise<void> => {
+  setLocale(await resolveLocale(language))
+  await invoke('set_language', { tag: language || SYSTEM_LANGUAGE })
+}
+
 // 导出获取当前语言的方法
 export const getCurrentLocale = (): string => {
   return (i18n.global.locale as Ref<string>).value
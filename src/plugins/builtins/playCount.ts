@@ -3,7 +3,14 @@
  * 记录每首歌曲的播放次数和播放历史
  */
 
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
 import { PluginPermission, type PluginAPI, type BuiltinPluginDefinition, type Track } from '../pluginManager'
+import FileUtils from '../../utils/fileUtils'
+
+/**
+ * 存储数据的版本：1 起播放次数和播放历史都以 `FileUtils.pathKey` 为键
+ */
+const DATA_VERSION = 1
 
 interface PlayCountData {
   playCounts: Record<string, number>
@@ -51,6 +58,9 @@ export const playCountPlugin: BuiltinPluginDefinition = {
     let playStartTime: number | null = null
     let hasRecordedCurrentTrack = false
     let pollingInterval: ReturnType<typeof setInterval> | null = null
+    let pathsChangedUnlisten: UnlistenFn | null = null
+    let missingPrunedUnlisten: UnlistenFn | null = null
+    let filesDeletedUnlisten: UnlistenFn | null = null
 
     const loadData = (): PlayCountData => {
       return {
@@ -66,11 +76,29 @@ export const playCountPlugin: BuiltinPluginDefinition = {
       api.storage.set('totalPlayTime', data.totalPlayTime)
     }
 
+    /**
+     * 把旧版本以原始路径保存的播放次数和历史记录改为以 `pathKey` 为键，同一文件的次数合并
+     */
+    const migrateData = (): void => {
+      if (api.storage.get<number>('dataVersion', 0) >= DATA_VERSION) return
+
+      const data = loadData()
+      const playCounts: Record<string, number> = {}
+      for (const [path, count] of Object.entries(data.playCounts)) {
+        const key = FileUtils.pathKey(path)
+        playCounts[key] = (playCounts[key] || 0) + count
+      }
+      data.playCounts = playCounts
+      data.playHistory = data.playHistory.map(entry => ({ ...entry, path: FileUtils.pathKey(entry.path) }))
+      saveData(data)
+      api.storage.set('dataVersion', DATA_VERSION)
+    }
+
     const recordPlayCount = (track: Track): void => {
       if (!track || !track.path) return
 
       const data = loadData()
-      const trackPath = track.path
+      const trackPath = FileUtils.pathKey(track.path)
 
       data.playCounts[trackPath] = (data.playCounts[trackPath] || 0) + 1
 
@@ -134,6 +162,48 @@ export const playCountPlugin: BuiltinPluginDefinition = {
       }
     }
 
+    /**
+     * 文件被整理或重新定位后，把播放次数和历史记录迁移到新路径
+     */
+    const applyPathChanges = (changes: { from: string; to: string }[]): void => {
+      if (changes.length === 0) return
+
+      const mapping = new Map(changes.map(c => [FileUtils.pathKey(c.from), FileUtils.pathKey(c.to)]))
+      const data = loadData()
+
+      for (const [path, count] of Object.entries(data.playCounts)) {
+        const to = mapping.get(FileUtils.pathKey(path))
+        if (to && !FileUtils.pathsEqual(to, path)) {
+          data.playCounts[to] = (data.playCounts[to] || 0) + count
+          delete data.playCounts[path]
+        }
+      }
+      data.playHistory = data.playHistory.map(entry => {
+        const to = mapping.get(FileUtils.pathKey(entry.path))
+        return to ? { ...entry, path: to } : entry
+      })
+      saveData(data)
+    }
+
+    /**
+     * 丢失的文件被清除后删除它们的播放次数，播放历史保留；文件被删除时同时移除历史记录
+     */
+    const removePlayCounts = (paths: string[], removeHistory: boolean = false): void => {
+      if (paths.length === 0) return
+
+      const removed = new Set(paths.map(p => FileUtils.pathKey(p)))
+      const data = loadData()
+      for (const path of Object.keys(data.playCounts)) {
+        if (removed.has(FileUtils.pathKey(path))) {
+          delete data.playCounts[path]
+        }
+      }
+      if (removeHistory) {
+        data.playHistory = data.playHistory.filter(entry => !removed.has(FileUtils.pathKey(entry.path)))
+      }
+      saveData(data)
+    }
+
     const pollPlayerState = async (): Promise<void> => {
       try {
         const state = api.player.getState()
@@ -146,6 +216,7 @@ export const playCountPlugin: BuiltinPluginDefinition = {
     return {
       async activate(): Promise<void> {
         api.log.info('播放统计插件已激活')
+        migrateData()
 
         api.events.on('player:trackChanged', (data) => {
           const { track, isPlaying } = data as { track: Track | null; isPlaying: boolean }
@@ -157,6 +228,20 @@ export const playCountPlugin: BuiltinPluginDefinition = {
           handleTrackChange(track, isPlaying)
         })
 
+        try {
+          pathsChangedUnlisten = await listen<{ from: string; to: string }[]>('track-paths-changed', (event) => {
+            applyPathChanges(event.payload || [])
+          })
+          missingPrunedUnlisten = await listen<string[]>('missing-files-pruned', (event) => {
+            removePlayCounts(event.payload || [])
+          })
+          filesDeletedUnlisten = await listen<{ paths: string[] }>('files-deleted', (event) => {
+            removePlayCounts(event.payload?.paths || [], true)
+          })
+        } catch (err) {
+          api.log.error('Failed to setup path change listeners:', err)
+        }
+
         await pollPlayerState()
         pollingInterval = setInterval(pollPlayerState, 5000)
       },
@@ -170,6 +255,19 @@ export const playCountPlugin: BuiltinPluginDefinition = {
           clearInterval(pollingInterval)
           pollingInterval = null
         }
+
+        if (pathsChangedUnlisten) {
+          pathsChangedUnlisten()
+          pathsChangedUnlisten = null
+        }
+        if (missingPrunedUnlisten) {
+          missingPrunedUnlisten()
+          missingPrunedUnlisten = null
+        }
+        if (filesDeletedUnlisten) {
+          filesDeletedUnlisten()
+          filesDeletedUnlisten = null
+        }
         
         lastTrackPath = null
         playStartTime = null
@@ -179,7 +277,7 @@ export const playCountPlugin: BuiltinPluginDefinition = {
 
       getPlayCount(trackPath: string): number {
         const data = loadData()
-        return data.playCounts[trackPath] || 0
+        return data.playCounts[FileUtils.pathKey(trackPath)] || 0
       },
 
       getAllPlayCounts(): Record<string, number> {
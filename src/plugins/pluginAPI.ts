@@ -125,8 +125,7 @@ export function createPluginAPI(
             const lyricsPath = await FileUtils.findLyricsFile(store.currentTrack.path)
             if (lyricsPath) {
               const content = await FileUtils.readFile(lyricsPath)
-              const ext = FileUtils.getFileExtension(lyricsPath) as 'lrc' | 'ass' | 'srt' | 'vtt' | 'txt'
-              const parsed = await LyricsParser.parseAsync(content, ext as 'lrc' | 'ass' | 'srt' | 'auto')
+              const parsed = await LyricsParser.parseAsync(content, FileUtils.getLyricsFormat(lyricsPath))
               return parsed.map(line => ({
                 time: line.time,
                 texts: line.texts?.map(t => typeof t === 'string' ? { text: t } : { text: t }) || [],
@@ -3,6 +3,7 @@ import { invoke } from '@tauri-apps/api/core'
 import { useThemeStore } from './theme'
 import { useMusicLibraryStore } from './musicLibrary'
 import logger from '../utils/logger'
+import { FileUtils } from '../utils/fileUtils'
 import { ErrorType, ErrorSeverity, handlePromise } from '../utils/errorHandler'
 import type {
   DirectoryScanConfig,
@@ -12,7 +13,17 @@ import type {
   LyricsConfig,
   UIConfig,
   AudioConfig,
-  AppConfig
+  AppConfig,
+  NetworkConfig,
+  ConnectionTest,
+  NeteaseConfig,
+  ApiConnectionTest,
+  LastfmConfig,
+  LastfmAuthRequest,
+  ListenBrainzConfig,
+  PowerConfig,
+  ScrobbleStatus,
+  AutostartStatus
 } from '@/types'
 
 // 防抖函数（带取消功能）
@@ -59,6 +70,11 @@ interface ConfigState {
   lyrics: LyricsConfig
   ui: UIConfig
   audio: AudioConfig
+  network: NetworkConfig
+  netease: NeteaseConfig
+  lastfm: LastfmConfig
+  listenbrainz: ListenBrainzConfig
+  power: PowerConfig
   _isInitializing: boolean
   _isDirty: boolean
   _lastSavedConfig: Partial<AppConfig> | null
@@ -100,7 +116,7 @@ export const useConfigStore = defineStore('config', {
 
     // 通用设置
     general: {
-      language: 'zh',
+      language: 'system',
       theme: 'auto',
       startupLoadLastConfig: true,
       autoSaveConfig: true,
@@ -108,6 +124,11 @@ export const useConfigStore = defineStore('config', {
       lyricsAlignment: 'center',
       lyricsFontFamily: 'Roboto',
       lyricsStyle: 'modern',
+      globalMediaKeys: true,
+      mediaKeysOnlyWhenPlaying: true,
+      registerFileAssociations: false,
+      trackNotifications: false,
+      notifyOnlyWhenUnfocused: true,
     },
 
     // 歌词设置
@@ -115,6 +136,7 @@ export const useConfigStore = defineStore('config', {
       enableOnlineFetch: false,
       autoSaveOnlineLyrics: true,
       preferTranslation: true,
+      preferRomanization: false,
       onlineSource: 'netease',
     },
 
@@ -128,7 +150,49 @@ export const useConfigStore = defineStore('config', {
     // 音频设置
     audio: {
       exclusiveMode: false,
-      volume: 0.5
+      volume: 0.5,
+      ducking: {
+        mode: 'off',
+        amount: 0.5
+      }
+    },
+
+    // 网络设置
+    network: {
+      proxyUrl: null,
+      noProxy: null,
+      neteaseRequestsPerSecond: 2,
+      offlineMode: false
+    },
+
+    // 网易云音乐接口设置
+    netease: {
+      apiBaseUrl: '',
+      apiFlavor: 'web',
+      userAgent: '',
+      extraHeaders: {},
+      cookie: ''
+    },
+
+    // Last.fm 设置
+    lastfm: {
+      apiKey: '',
+      apiSecret: '',
+      sessionKey: '',
+      username: '',
+      enabled: true
+    },
+
+    // ListenBrainz 设置
+    listenbrainz: {
+      token: '',
+      username: '',
+      enabled: true
+    },
+
+    // 电源设置
+    power: {
+      reduceBackgroundWorkOnBattery: true
     },
 
     // 内部状态（不保存到文件）
@@ -215,6 +279,8 @@ export const useConfigStore = defineStore('config', {
         this.musicDirectories = []
       }
 
+      await FileUtils.loadSupportedExtensions()
+
       setTimeout(() => {
         this.markInitializationComplete()
       }, 1000)
@@ -253,6 +319,7 @@ export const useConfigStore = defineStore('config', {
       if ((result as { success: boolean }).success) {
         this._lastSavedConfig = configToSave
         this._isDirty = false
+        await FileUtils.loadSupportedExtensions()
         logger.debug('Configuration saved successfully')
       }
     },
@@ -302,6 +369,232 @@ export const useConfigStore = defineStore('config', {
       this._markDirty()
     },
 
+    // 设置代理，后端验证并立即生效，返回是否成功
+    async setNetworkProxy(proxyUrl: string | null, noProxy: string | null): Promise<boolean> {
+      const result = await handlePromise(
+        invoke<NetworkConfig>('set_network_proxy', { proxyUrl, noProxy }),
+        {
+          type: ErrorType.CONFIG_INVALID,
+          severity: ErrorSeverity.MEDIUM,
+          context: { proxyUrl, noProxy, action: 'setNetworkProxy' },
+          showToUser: false,
+          throw: false
+        }
+      )
+
+      if (result.success && result.data) {
+        this.network = result.data
+        // 后端已保存，避免被视为未保存的修改
+        if (this._lastSavedConfig) {
+          this._lastSavedConfig.network = result.data
+        }
+      }
+      return result.success
+    },
+
+    // 开启或关闭离线模式，后端立即生效并保存
+    async setOfflineMode(offline: boolean): Promise<boolean> {
+      const result = await handlePromise(
+        invoke('set_offline_mode', { offline }),
+        {
+          type: ErrorType.CONFIG_INVALID,
+          severity: ErrorSeverity.MEDIUM,
+          context: { offline, action: 'setOfflineMode' },
+          showToUser: false,
+          throw: false
+        }
+      )
+
+      if (result.success) {
+        this.network.offlineMode = offline
+        if (this._lastSavedConfig?.network) {
+          this._lastSavedConfig.network.offlineMode = offline
+        }
+      }
+      return result.success
+    },
+
+    // 通过当前代理设置检查网络连接
+    async testNetworkConnection(): Promise<ConnectionTest | null> {
+      const result = await handlePromise(
+        invoke<ConnectionTest>('test_network_connection'),
+        {
+          type: ErrorType.NETWORK,
+          severity: ErrorSeverity.LOW,
+          context: { action: 'testNetworkConnection' },
+          showToUser: false,
+          throw: false
+        }
+      )
+
+      return result.success ? result.data! : null
+    },
+
+    // 设置网易云音乐接口，后端校验后立即生效并保存
+    async setNeteaseApiConfig(config: NeteaseConfig): Promise<boolean> {
+      const result = await handlePromise(
+        invoke<NeteaseConfig>('set_netease_api_config', { config }),
+        {
+          type: ErrorType.CONFIG_INVALID,
+          severity: ErrorSeverity.MEDIUM,
+          context: { config, action: 'setNeteaseApiConfig' },
+          showToUser: false,
+          throw: false
+        }
+      )
+
+      if (result.success && result.data) {
+        this.netease = result.data
+        // 后端已保存，避免被视为未保存的修改
+        if (this._lastSavedConfig) {
+          this._lastSavedConfig.netease = result.data
+        }
+      }
+      return result.success
+    },
+
+    // 用当前的网易云音乐接口设置搜索一次
+    async testNeteaseConnection(): Promise<ApiConnectionTest | null> {
+      const result = await handlePromise(
+        invoke<ApiConnectionTest>('test_netease_connection'),
+        {
+          type: ErrorType.NETWORK,
+          severity: ErrorSeverity.LOW,
+          context: { action: 'testNeteaseConnection' },
+          showToUser: false,
+          throw: false
+        }
+      )
+
+      return result.success ? result.data! : null
+    },
+
+    // 开始 Last.fm 授权，返回授权页面地址和令牌（需要先保存 API 密钥和共享密钥）
+    async getLastfmAuthUrl(): Promise<LastfmAuthRequest | null> {
+      await this.flushPendingSave()
+      const result = await handlePromise(
+        invoke<LastfmAuthRequest>('lastfm_get_auth_url'),
+        {
+          type: ErrorType.NETWORK,
+          severity: ErrorSeverity.MEDIUM,
+          context: { action: 'getLastfmAuthUrl' },
+          showToUser: false,
+          throw: false
+        }
+      )
+
+      return result.success ? result.data! : null
+    },
+
+    // 用户在浏览器中同意授权后完成 Last.fm 授权，后端保存会话密钥
+    async completeLastfmAuth(token: string): Promise<boolean> {
+      const result = await handlePromise(
+        invoke<LastfmConfig>('lastfm_complete_auth', { token }),
+        {
+          type: ErrorType.NETWORK,
+          severity: ErrorSeverity.MEDIUM,
+          context: { action: 'completeLastfmAuth' },
+          showToUser: false,
+          throw: false
+        }
+      )
+
+      if (result.success && result.data) {
+        this.lastfm = result.data
+        // 后端已保存，避免被视为未保存的修改
+        if (this._lastSavedConfig) {
+          this._lastSavedConfig.lastfm = result.data
+        }
+      }
+      return result.success
+    },
+
+    // 检查 ListenBrainz 用户令牌，有效时后端连同用户名保存
+    async validateListenBrainzToken(token: string): Promise<boolean> {
+      const result = await handlePromise(
+        invoke<ListenBrainzConfig>('listenbrainz_validate_token', { token }),
+        {
+          type: ErrorType.NETWORK,
+          severity: ErrorSeverity.MEDIUM,
+          context: { action: 'validateListenBrainzToken' },
+          showToUser: false,
+          throw: false
+        }
+      )
+
+      if (result.success && result.data) {
+        this.listenbrainz = result.data
+        // 后端已保存，避免被视为未保存的修改
+        if (this._lastSavedConfig) {
+          this._lastSavedConfig.listenbrainz = result.data
+        }
+      }
+      return result.success
+    },
+
+    // 各服务播放记录的提交状态
+    async getScrobbleStatus(): Promise<ScrobbleStatus | null> {
+      const result = await handlePromise(
+        invoke<ScrobbleStatus>('get_scrobble_status'),
+        {
+          type: ErrorType.NETWORK,
+          severity: ErrorSeverity.LOW,
+          context: { action: 'getScrobbleStatus' },
+          showToUser: false,
+          throw: false
+        }
+      )
+
+      return result.success ? result.data! : null
+    },
+
+    /**
+     * 开机启动的状态（以系统中的启动项为准，不保存在配置中）
+     */
+    async getAutostartStatus(): Promise<AutostartStatus | null> {
+      const result = await handlePromise(
+        invoke<AutostartStatus>('get_autostart_status'),
+        {
+          type: ErrorType.CONFIG_LOAD_ERROR,
+          severity: ErrorSeverity.LOW,
+          context: { action: 'getAutostartStatus' },
+          showToUser: false,
+          throw: false
+        }
+      )
+
+      return result.success ? result.data! : null
+    },
+
+    /**
+     * 开启或关闭开机启动；启动项指向旧路径时用相同的参数再次开启即可修复
+     */
+    async setAutostart(enabled: boolean, minimized: boolean): Promise<AutostartStatus | null> {
+      const result = await handlePromise(
+        invoke<AutostartStatus>('set_autostart', { enabled, minimized }),
+        {
+          type: ErrorType.CONFIG_SAVE_ERROR,
+          severity: ErrorSeverity.MEDIUM,
+          context: { action: 'setAutostart', enabled, minimized },
+          showToUser: true,
+          throw: false
+        }
+      )
+
+      return result.success ? result.data! : null
+    },
+
+    /**
+     * 让指向旧路径的启动项重新指向当前程序，保留最小化设置
+     */
+    async repairAutostart(): Promise<AutostartStatus | null> {
+      const status = await this.getAutostartStatus()
+      if (!status?.enabled || !status.stale) {
+        return status
+      }
+      return this.setAutostart(true, status.minimized)
+    },
+
     setDirectoryScanConfig(config: Partial<DirectoryScanConfig>): void {
       this.directoryScan = { ...this.directoryScan, ...config }
       this._markDirty()
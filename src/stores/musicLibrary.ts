@@ -2,6 +2,7 @@ import { defineStore } from 'pinia'
 import { invoke } from '@tauri-apps/api/core'
 import { useConfigStore } from './config'
 import logger from '../utils/logger'
+import FileUtils from '../utils/fileUtils'
 import type { Track, Playlist, LibraryStats } from '@/types'
 
 interface PlayHistoryItem extends Track {
@@ -87,7 +88,7 @@ export const useMusicLibraryStore = defineStore('musicLibrary', {
     currentIndex: (state): number => {
       if (!state.currentPlaylist || !state.currentFile) return -1
       return state.currentPlaylist.files.findIndex(file => 
-        file.path === state.currentFile!.path
+        FileUtils.pathsEqual(file.path, state.currentFile!.path)
       )
     },
 
@@ -400,7 +401,7 @@ export const useMusicLibraryStore = defineStore('musicLibrary', {
     removeFileFromPlaylist(filePath: string): void {
       if (!this.currentPlaylist) return
       
-      const index = this.currentPlaylist.files.findIndex(file => file.path === filePath)
+      const index = this.currentPlaylist.files.findIndex(file => FileUtils.pathsEqual(file.path, filePath))
       if (index > -1) {
         this.currentPlaylist.files.splice(index, 1)
         if (this.currentPlaylist.totalFiles) {
@@ -408,12 +409,59 @@ export const useMusicLibraryStore = defineStore('musicLibrary', {
         }
         
         // 如果移除的是当前播放的文件，需要更新当前文件
-        if (this.currentFile && this.currentFile.path === filePath) {
+        if (this.currentFile && FileUtils.pathsEqual(this.currentFile.path, filePath)) {
           this.currentFile = null
         }
       }
     },
 
+    /**
+     * 文件被移动或重命名后，更新播放列表、搜索结果和播放历史中的路径
+     */
+    applyPathChanges(changes: { from: string; to: string }[]): void {
+      if (changes.length === 0) return
+
+      const mapping = new Map(changes.map(c => [FileUtils.pathKey(c.from), c.to]))
+      const remap = <T extends Track>(track: T): T => {
+        const to = mapping.get(FileUtils.pathKey(track.path))
+        return to ? { ...track, path: to, name: to.split(/[\\/]/).pop() || track.name } : track
+      }
+
+      for (const playlist of this.playlists) {
+        playlist.files = playlist.files.map(remap)
+      }
+      this.searchResults = this.searchResults.map(remap)
+      this.playHistory = this.playHistory.map(remap)
+      if (this.currentFile) {
+        this.currentFile = remap(this.currentFile)
+      }
+    },
+
+    /**
+     * 从所有播放列表、搜索结果和播放历史中移除已删除的文件
+     */
+    removeTracks(paths: string[]): void {
+      if (paths.length === 0) return
+
+      const removed = new Set(paths.map(path => FileUtils.pathKey(path)))
+      const keep = (track: Track) => !removed.has(FileUtils.pathKey(track.path))
+
+      for (const playlist of this.playlists) {
+        const files = playlist.files.filter(keep)
+        if (files.length !== playlist.files.length) {
+          if (playlist.totalFiles) {
+            playlist.totalFiles -= playlist.files.length - files.length
+          }
+          playlist.files = files
+        }
+      }
+      this.searchResults = this.searchResults.filter(keep)
+      this.playHistory = this.playHistory.filter(keep)
+      if (this.currentFile && !keep(this.currentFile)) {
+        this.currentFile = null
+      }
+    },
+
     /**
      * 刷新播放列表
      */
@@ -4,9 +4,10 @@ import { listen, type UnlistenFn } from '@tauri-apps/api/event'
 import FileUtils from '../utils/fileUtils'
 import LyricsParser from '../utils/lyricsParser'
 import logger from '../utils/logger'
-import { ErrorType, ErrorSeverity, handlePromise } from '../utils/errorHandler'
+import errorHandler, { ErrorType, ErrorSeverity, handlePromise } from '../utils/errorHandler'
 import { useConfigStore } from './config'
-import type { Track, AudioInfo, LyricLine, RepeatMode, CacheItem } from '@/types'
+import { useMusicLibraryStore } from './musicLibrary'
+import type { Track, AudioInfo, LyricLine, RepeatMode, CacheItem, LibraryRootEvent, MediaControlEvent } from '@/types'
 
 /**
  * 简单的 LRU 缓存实现
@@ -105,12 +106,23 @@ interface PlayerState {
   _isLoading: boolean
   _statusPollId: ReturnType<typeof setTimeout> | null
   lastTrackIndex: number
+  offlineRoots: string[]
   _fileExistsCache: LRUCache<boolean> | null
   _metadataCache: LRUCache<TrackMetadata> | null
   _cleanupTimerId: ReturnType<typeof setInterval> | null
   _isDestroyed: boolean
   _trackEndedUnlisten: UnlistenFn | null
   _positionUnlisten: UnlistenFn | null
+  _pathsChangedUnlisten: UnlistenFn | null
+  _missingPrunedUnlisten: UnlistenFn | null
+  _filesDeletedUnlisten: UnlistenFn | null
+  _filesImportedUnlisten: UnlistenFn | null
+  _metadataChangedUnlisten: UnlistenFn | null
+  _trackMissingUnlisten: UnlistenFn | null
+  _rootOfflineUnlisten: UnlistenFn | null
+  _rootOnlineUnlisten: UnlistenFn | null
+  _mediaControlUnlisten: UnlistenFn | null
+  _openFilesUnlisten: UnlistenFn | null
 }
 
 export const usePlayerStore = defineStore('player', {
@@ -146,6 +158,9 @@ export const usePlayerStore = defineStore('player', {
     _statusPollId: null,
     lastTrackIndex: -1,
 
+    // 离线的音乐目录（如拔出的外接硬盘），其中的音轨在队列中被跳过
+    offlineRoots: [],
+
     // 缓存 - 使用 LRU 缓存，限制大小
     _fileExistsCache: null,
     _metadataCache: null,
@@ -159,12 +174,22 @@ export const usePlayerStore = defineStore('player', {
     // 事件监听器
     _trackEndedUnlisten: null,
     _positionUnlisten: null,
+    _pathsChangedUnlisten: null,
+    _missingPrunedUnlisten: null,
+    _filesDeletedUnlisten: null,
+    _filesImportedUnlisten: null,
+    _metadataChangedUnlisten: null,
+    _trackMissingUnlisten: null,
+    _rootOfflineUnlisten: null,
+    _rootOnlineUnlisten: null,
+    _mediaControlUnlisten: null,
+    _openFilesUnlisten: null,
   }),
 
   getters: {
     currentTrackIndex: (state): number => {
       if (!state.currentTrack || state.playlist.length === 0) return -1
-      return state.playlist.findIndex(track => track.path === state.currentTrack!.path)
+      return state.playlist.findIndex(track => FileUtils.pathsEqual(track.path, state.currentTrack!.path))
     },
     hasNextTrack: (state): boolean => {
       if (!state.currentTrack) return false
@@ -253,7 +278,13 @@ export const usePlayerStore = defineStore('player', {
       
       this._setupTrackEndedListener()
       this._setupPositionListener()
+      this._setupPathsChangedListener()
+      this._setupMetadataChangedListeners()
+      this._setupLibraryRootListeners()
+      this._setupMediaControlListener()
+      await this._setupOpenFilesListener()
       this._startCleanupTask()
+      await this._openLaunchFiles()
       
       logger.info('Player store initialized.')
     },
@@ -284,6 +315,265 @@ export const usePlayerStore = defineStore('player', {
       }
     },
 
+    /**
+     * 系统媒体控件和全局媒体键的播放控制
+     */
+    async _setupMediaControlListener(): Promise<void> {
+      try {
+        this._mediaControlUnlisten = await listen<MediaControlEvent>('media-control', (event) => {
+          if (this._isDestroyed || !event.payload) return
+          this.handleMediaControl(event.payload)
+        })
+      } catch (err) {
+        logger.error('Failed to setup media-control listener:', err)
+      }
+    },
+
+    handleMediaControl(control: MediaControlEvent): void {
+      switch (control.action) {
+        case 'play':
+          if (!this.isPlaying) this.togglePlay()
+          break
+        case 'pause':
+        case 'stop':
+          this.pause()
+          break
+        case 'playPause':
+          this.togglePlay()
+          break
+        case 'next':
+          this.nextTrack()
+          break
+        case 'previous':
+          this.previousTrack()
+          break
+        case 'seek':
+          this.seek(control.positionSecs)
+          break
+        case 'setVolume':
+          this.setVolume(control.volume)
+          break
+      }
+    },
+
+    /**
+     * 从命令行打开的文件（包括再次启动时转发给本实例的文件）
+     */
+    async _setupOpenFilesListener(): Promise<void> {
+      try {
+        this._openFilesUnlisten = await listen<{ tracks: Track[] }>('open-files', (event) => {
+          if (this._isDestroyed || !event.payload?.tracks?.length) return
+          this.openFiles(event.payload.tracks)
+        })
+      } catch (err) {
+        logger.error('Failed to setup open-files listener:', err)
+      }
+    },
+
+    /**
+     * 打开启动参数中的文件；参数指定的文件优先于启动时的其他默认行为
+     */
+    async _openLaunchFiles(): Promise<void> {
+      const result = await handlePromise(
+        invoke<{ tracks: Track[] }>('take_launch_files'),
+        {
+          type: ErrorType.FILE_READ_ERROR,
+          severity: ErrorSeverity.LOW,
+          context: { action: 'takeLaunchFiles' },
+          showToUser: false,
+          throw: false
+        }
+      )
+      if (result.success && result.data!.tracks.length > 0) {
+        await this.openFiles(result.data!.tracks)
+      }
+    },
+
+    /**
+     * 把打开的文件加入播放队列并播放第一个
+     */
+    async openFiles(tracks: Track[]): Promise<void> {
+      if (tracks.length === 0) return
+      this.appendTracks(tracks)
+      const first = this.playlist.find(track => FileUtils.pathsEqual(track.path, tracks[0].path))
+      await this.playTrack(first ?? tracks[0])
+    },
+
+    /**
+     * 文件被整理/移动后，更新队列、当前音轨和缓存中的路径
+     */
+    async _setupPathsChangedListener(): Promise<void> {
+      try {
+        this._pathsChangedUnlisten = await listen<{ from: string; to: string }[]>('track-paths-changed', (event) => {
+          if (this._isDestroyed) return
+          this.applyPathChanges(event.payload || [])
+          useMusicLibraryStore().applyPathChanges(event.payload || [])
+        })
+        this._missingPrunedUnlisten = await listen<string[]>('missing-files-pruned', (event) => {
+          if (this._isDestroyed) return
+          this.removeMissingTracks(event.payload || [])
+        })
+        this._filesDeletedUnlisten = await listen<{ paths: string[]; stoppedPlayback: boolean }>('files-deleted', (event) => {
+          if (this._isDestroyed || !event.payload) return
+          this.removeDeletedTracks(event.payload.paths, event.payload.stoppedPlayback)
+        })
+        this._filesImportedUnlisten = await listen<{ tracks: Track[]; addToQueue: boolean }>('files-imported', (event) => {
+          if (this._isDestroyed || !event.payload?.addToQueue) return
+          this.appendTracks(event.payload.tracks)
+        })
+      } catch (err) {
+        logger.error('Failed to setup track-paths-changed listener:', err)
+      }
+    },
+
+    applyPathChanges(changes: { from: string; to: string }[]): void {
+      if (changes.length === 0) return
+
+      const mapping = new Map(changes.map(c => [FileUtils.pathKey(c.from), c.to]))
+      const remap = (path: string) => mapping.get(FileUtils.pathKey(path))
+
+      this.playlist = this.playlist.map(track => {
+        const to = remap(track.path)
+        return to ? { ...track, path: to } : track
+      })
+
+      if (this.currentTrack) {
+        const to = remap(this.currentTrack.path)
+        if (to) this.currentTrack = { ...this.currentTrack, path: to }
+      }
+
+      const metadataCache = this._getMetadataCache()
+      const fileExistsCache = this._getFileExistsCache()
+      for (const { from, to } of changes) {
+        const metadata = metadataCache.get(from)
+        if (metadata) {
+          metadataCache.delete(from)
+          metadataCache.set(to, metadata)
+        }
+        fileExistsCache.delete(from)
+      }
+    },
+
+    /**
+     * 丢失的文件被清除后，从队列和缓存中移除（正在播放的音轨保留）
+     */
+    removeMissingTracks(paths: string[]): void {
+      if (paths.length === 0) return
+
+      const removed = new Set(paths.map(path => FileUtils.pathKey(path)))
+      const currentPath = this.currentTrack?.path
+
+      this.playlist = this.playlist.filter(track =>
+        (currentPath !== undefined && FileUtils.pathsEqual(track.path, currentPath)) ||
+        !removed.has(FileUtils.pathKey(track.path))
+      )
+
+      const metadataCache = this._getMetadataCache()
+      const fileExistsCache = this._getFileExistsCache()
+      for (const path of paths) {
+        metadataCache.delete(path)
+        fileExistsCache.set(path, false)
+      }
+    },
+
+    /**
+     * 把音轨追加到播放队列末尾，已在队列中的跳过
+     */
+    appendTracks(tracks: Track[]): void {
+      const queued = new Set(this.playlist.map(track => FileUtils.pathKey(track.path)))
+      const added = tracks.filter(track => !queued.has(FileUtils.pathKey(track.path)))
+      if (added.length > 0) {
+        this.playlist = [...this.playlist, ...added]
+      }
+    },
+
+    /**
+     * 文件被删除后，从队列、曲库播放列表和缓存中移除；后端为此停止了播放时同时清空当前音轨
+     */
+    removeDeletedTracks(paths: string[], stoppedPlayback: boolean): void {
+      if (paths.length === 0) return
+
+      const currentPath = this.currentTrack?.path
+      if (stoppedPlayback && currentPath !== undefined && paths.some(path => FileUtils.pathsEqual(path, currentPath))) {
+        this.isPlaying = false
+        this.stopStatusPolling()
+        this.currentTrack = null
+        this.currentTime = 0
+        this.duration = 0
+        this.lyrics = null
+        this.currentLyricIndex = -1
+      }
+
+      this.removeMissingTracks(paths)
+      useMusicLibraryStore().removeTracks(paths)
+    },
+
+    /**
+     * 单个文件被重新读取后更新对应的行；文件丢失时记录到存在性缓存
+     */
+    async _setupMetadataChangedListeners(): Promise<void> {
+      try {
+        this._metadataChangedUnlisten = await listen<Track>('track-metadata-changed', (event) => {
+          if (this._isDestroyed || !event.payload) return
+          this.applyTrackMetadata(event.payload)
+        })
+        this._trackMissingUnlisten = await listen<{ path: string }>('track-missing', (event) => {
+          if (this._isDestroyed || !event.payload) return
+          this._getMetadataCache().delete(event.payload.path)
+          this._getFileExistsCache().set(event.payload.path, false)
+        })
+      } catch (err) {
+        logger.error('Failed to setup track metadata listeners:', err)
+      }
+    },
+
+    /**
+     * 音乐目录离线或恢复时更新离线目录列表；恢复后丢弃该目录下的存在性缓存
+     */
+    async _setupLibraryRootListeners(): Promise<void> {
+      try {
+        this._rootOfflineUnlisten = await listen<LibraryRootEvent>('library-root-offline', (event) => {
+          if (this._isDestroyed || !event.payload) return
+          logger.warn('Library root went offline:', event.payload.path)
+          if (!this.offlineRoots.some(root => FileUtils.pathsEqual(root, event.payload.path))) {
+            this.offlineRoots = [...this.offlineRoots, event.payload.path]
+          }
+        })
+        this._rootOnlineUnlisten = await listen<LibraryRootEvent>('library-root-online', (event) => {
+          if (this._isDestroyed || !event.payload) return
+          const path = event.payload.path
+          logger.info('Library root is back online:', path)
+          this.offlineRoots = this.offlineRoots.filter(root => !FileUtils.pathsEqual(root, path))
+          const fileExistsCache = this._getFileExistsCache()
+          for (const key of Array.from(fileExistsCache.keys())) {
+            if (FileUtils.isUnder(key, path)) fileExistsCache.delete(key)
+          }
+        })
+        const statuses = await FileUtils.getLibraryRootsStatus()
+        this.offlineRoots = statuses.filter(status => !status.online).map(status => status.path)
+      } catch (err) {
+        logger.error('Failed to setup library root listeners:', err)
+      }
+    },
+
+    _offlineRootOf(path: string): string | null {
+      return this.offlineRoots.find(root => FileUtils.isUnder(path, root)) ?? null
+    },
+
+    applyTrackMetadata(metadata: Track): void {
+      // 保留已加载的封面，新元数据不含封面数据
+      const merge = (track: Track): Track => ({ ...track, ...metadata, cover: metadata.cover ?? track.cover, path: track.path })
+
+      this.playlist = this.playlist.map(track => (FileUtils.pathsEqual(track.path, metadata.path) ? merge(track) : track))
+      if (this.currentTrack && FileUtils.pathsEqual(this.currentTrack.path, metadata.path)) {
+        this.currentTrack = merge(this.currentTrack)
+      }
+
+      // 播放信息缓存在下次播放时重新读取
+      this._getMetadataCache().delete(metadata.path)
+      this._getFileExistsCache().set(metadata.path, true)
+    },
+
     // --- 核心行为 ---
 
     play(): void {
@@ -298,6 +588,23 @@ export const usePlayerStore = defineStore('player', {
       if (this._isDestroyed) return
       if (!track || this._isLoading) return
 
+      // 离线目录中的音轨不会被当作丢失：提示后跳到队列中下一首可用的音轨
+      const offlineRoot = this._offlineRootOf(track.path)
+      if (offlineRoot) {
+        errorHandler.handle(new Error(`音轨所在的目录已离线，已跳过: ${track.path}`), {
+          type: ErrorType.FILE_NOT_FOUND,
+          severity: ErrorSeverity.LOW,
+          context: { trackPath: track.path, root: offlineRoot }
+        })
+        const index = this.playlist.findIndex(t => FileUtils.pathsEqual(t.path, track.path))
+        const candidates = this.repeatMode === 'list'
+          ? [...this.playlist.slice(index + 1), ...this.playlist.slice(0, Math.max(index, 0))]
+          : this.playlist.slice(index + 1)
+        const next = candidates.find(t => !this._offlineRootOf(t.path))
+        if (next) return this.playTrack(next)
+        return
+      }
+
       let trackExists = await this._checkFileExists(track.path)
       if (!trackExists && track.path) {
         const altPath = track.path.includes('/') ? track.path.replace(/\//g, '\\') : track.path.replace(/\\/g, '/')
@@ -309,7 +616,7 @@ export const usePlayerStore = defineStore('player', {
 
       if (!trackExists) {
         logger.warn('Track file not found:', track.path)
-        const currentTrackIndex = this.playlist.findIndex(t => t.path === track.path)
+        const currentTrackIndex = this.playlist.findIndex(t => FileUtils.pathsEqual(t.path, track.path))
         if (this.playlist.length > 1 && currentTrackIndex < this.playlist.length - 1) {
           return this.nextTrack()
         } else {
@@ -346,6 +653,7 @@ export const usePlayerStore = defineStore('player', {
         album: metadata.album,
         duration: metadata.duration
       }
+      this._loadCoverIfMissing(track)
       this.duration = metadata.duration || 0
       this.currentTime = 0
       this.lyrics = null
@@ -383,7 +691,7 @@ export const usePlayerStore = defineStore('player', {
           this.isPlaying = false
           this._isLoading = false
           
-          const currentIdx = this.playlist.findIndex(t => t.path === track.path)
+          const currentIdx = this.playlist.findIndex(t => FileUtils.pathsEqual(t.path, track.path))
           if (this.playlist.length > 1 && currentIdx < this.playlist.length - 1) {
             setTimeout(() => this.nextTrack(), 100)
           }
@@ -400,7 +708,7 @@ export const usePlayerStore = defineStore('player', {
         logger.error('Playback error:', error)
         this.isPlaying = false
         
-        const currentIdx = this.playlist.findIndex(t => t.path === track.path)
+        const currentIdx = this.playlist.findIndex(t => FileUtils.pathsEqual(t.path, track.path))
         if (this.playlist.length > 1 && currentIdx < this.playlist.length - 1) {
           setTimeout(() => this.nextTrack(), 100)
         }
@@ -482,6 +790,33 @@ export const usePlayerStore = defineStore('player', {
 
     // --- 文件检查 ---
 
+    /**
+     * 扫描结果不含封面数据，播放时按需加载当前音轨的封面
+     */
+    async _loadCoverIfMissing(track: Track): Promise<void> {
+      if (track.cover || track.hasCover === false) return
+
+      try {
+        const cover = await invoke<string | null>('get_track_cover', { path: track.path })
+        if (cover && this.currentTrack && FileUtils.pathsEqual(this.currentTrack.path, track.path)) {
+          this.currentTrack = { ...this.currentTrack, cover }
+        }
+      } catch (error) {
+        logger.debug('Failed to load cover:', error)
+      }
+    },
+
+    // 一次检查多个文件并写入缓存，避免逐个调用 check_file_exists
+    async prefetchFileExists(paths: string[]): Promise<void> {
+      const cache = this._getFileExistsCache()
+      const unknown = paths.filter(path => path && cache.get(path) === null)
+      const statuses = await FileUtils.filesExist(unknown)
+      unknown.forEach((path, index) => {
+        // 离线网络共享上的文件不缓存，共享恢复后再检查
+        if (statuses[index] !== 'offline') cache.set(path, statuses[index] === 'exists')
+      })
+    },
+
     async _checkFileExists(filePath: string): Promise<boolean> {
       if (!filePath) return false
 
@@ -523,7 +858,7 @@ export const usePlayerStore = defineStore('player', {
       }
 
       try {
-        await invoke('pause_track')
+        await invoke('stop_track')
       } catch (error) {
         logger.error('Error stopping backend playback:', error)
       }
@@ -622,14 +957,49 @@ export const usePlayerStore = defineStore('player', {
     
     setLyricsOffset(offset: number): void {
       this.lyricsOffset = offset
+      void this._saveLyricsOffset()
     },
 
     adjustLyricsOffset(delta: number): void {
       this.lyricsOffset = Math.round((this.lyricsOffset + delta) * 10) / 10
+      void this._saveLyricsOffset()
     },
 
     resetLyricsOffset(): void {
       this.lyricsOffset = 0
+      void this._saveLyricsOffset()
+    },
+
+    // 读取曲目保存的歌词偏移（后端按毫秒保存）
+    async loadLyricsOffset(path: string): Promise<void> {
+      const result = await handlePromise(
+        invoke<number>('get_lyrics_offset', { path }),
+        {
+          type: ErrorType.FILE_READ_ERROR,
+          severity: ErrorSeverity.LOW,
+          context: { path, action: 'loadLyricsOffset' },
+          showToUser: false,
+          throw: false
+        }
+      )
+      this.lyricsOffset = result.success ? (result.data ?? 0) / 1000 : 0
+    },
+
+    // 保存当前曲目的歌词偏移，重新获取歌词后仍然有效
+    async _saveLyricsOffset(): Promise<void> {
+      const path = this.currentTrack?.path
+      if (!path) return
+      const offsetMs = Math.round(this.lyricsOffset * 1000)
+      await handlePromise(
+        invoke('set_lyrics_offset', { path, offsetMs }),
+        {
+          type: ErrorType.FILE_WRITE_ERROR,
+          severity: ErrorSeverity.LOW,
+          context: { path, offsetMs, action: 'saveLyricsOffset' },
+          showToUser: false,
+          throw: false
+        }
+      )
     },
 
     // --- 数据加载 ---
@@ -695,7 +1065,7 @@ export const usePlayerStore = defineStore('player', {
         const lyricsPath = await FileUtils.findLyricsFile(trackPath)
         if (lyricsPath) {
           const lyricsContent = await FileUtils.readFile(lyricsPath)
-          const format = FileUtils.getFileExtension(lyricsPath) as 'lrc' | 'ass' | 'srt'
+          const format = FileUtils.getLyricsFormat(lyricsPath)
           this.lyrics = LyricsParser.parse(lyricsContent, format)
         } else {
           this.lyrics = null
@@ -722,6 +1092,47 @@ export const usePlayerStore = defineStore('player', {
         this._positionUnlisten()
         this._positionUnlisten = null
       }
+
+      if (this._pathsChangedUnlisten) {
+        this._pathsChangedUnlisten()
+        this._pathsChangedUnlisten = null
+      }
+      if (this._missingPrunedUnlisten) {
+        this._missingPrunedUnlisten()
+        this._missingPrunedUnlisten = null
+      }
+      if (this._filesDeletedUnlisten) {
+        this._filesDeletedUnlisten()
+        this._filesDeletedUnlisten = null
+      }
+      if (this._filesImportedUnlisten) {
+        this._filesImportedUnlisten()
+        this._filesImportedUnlisten = null
+      }
+      if (this._metadataChangedUnlisten) {
+        this._metadataChangedUnlisten()
+        this._metadataChangedUnlisten = null
+      }
+      if (this._trackMissingUnlisten) {
+        this._trackMissingUnlisten()
+        this._trackMissingUnlisten = null
+      }
+      if (this._rootOfflineUnlisten) {
+        this._rootOfflineUnlisten()
+        this._rootOfflineUnlisten = null
+      }
+      if (this._rootOnlineUnlisten) {
+        this._rootOnlineUnlisten()
+        this._rootOnlineUnlisten = null
+      }
+      if (this._mediaControlUnlisten) {
+        this._mediaControlUnlisten()
+        this._mediaControlUnlisten = null
+      }
+      if (this._openFilesUnlisten) {
+        this._openFilesUnlisten()
+        this._openFilesUnlisten = null
+      }
       
       try {
         invoke('pause_track').catch(() => {})
@@ -1,4 +1,6 @@
 import { defineStore } from 'pinia'
+import { invoke } from '@tauri-apps/api/core'
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
 import {
   argbFromHex,
   hexFromArgb,
@@ -10,11 +12,20 @@ import {
 import logger from '../utils/logger'
 import { validateThemeContrast } from '../utils/themeContrastValidator'
 import { useConfigStore } from './config'
-import type { TonalVariants, HarmonyColors, ThemePreference } from '@/types'
+import type {
+  TonalVariants,
+  HarmonyColors,
+  ThemePreference,
+  SystemTheme,
+  SystemAccentColor,
+  SystemThemeChangedEvent
+} from '@/types'
 
 // 缓存已生成的主题样式
 const customStyleCache = new Map<string, string>()
 let customStyleElement: HTMLStyleElement | null = null
+// system-theme-changed 事件的监听
+let systemThemeUnlisten: UnlistenFn | null = null
 
 // 检测是否为中性灰色（低饱和度）
 function isNeutralGray(hexColor: string): boolean {
@@ -200,6 +211,8 @@ interface ThemeState {
   primaryColor: string
   enableGlassEffect: boolean
   enableGradients: boolean
+  /** 后端读取的系统主题，unknown/unsupported 时退回到浏览器的 prefers-color-scheme */
+  systemTheme: SystemTheme
 }
 
 export const useThemeStore = defineStore('theme', {
@@ -209,6 +222,7 @@ export const useThemeStore = defineStore('theme', {
     primaryColor: '#2C2C2C',
     enableGlassEffect: true,
     enableGradients: true,
+    systemTheme: 'unknown',
   }),
 
   getters: {
@@ -235,7 +249,7 @@ export const useThemeStore = defineStore('theme', {
     setThemePreference(preference: ThemePreference): void {
       this.themePreference = preference
       if (preference === 'auto') {
-        this.isDarkMode = window.matchMedia('(prefers-color-scheme: dark)').matches
+        this.isDarkMode = this._systemIsDark()
         this.primaryColor = '#2C2C2C'
       } else if (preference === 'light') {
         this.isDarkMode = false
@@ -249,6 +263,57 @@ export const useThemeStore = defineStore('theme', {
       this.applyTheme()
     },
     
+    // 系统当前是否为深色
+    _systemIsDark(): boolean {
+      if (this.systemTheme === 'dark' || this.systemTheme === 'light') {
+        return this.systemTheme === 'dark'
+      }
+      return window.matchMedia('(prefers-color-scheme: dark)').matches
+    },
+
+    // 读取系统主题并监听变化，跟随系统时立即切换
+    async watchSystemTheme(): Promise<void> {
+      try {
+        this.systemTheme = await invoke<SystemTheme>('get_system_theme')
+        if (!systemThemeUnlisten) {
+          systemThemeUnlisten = await listen<SystemThemeChangedEvent>('system-theme-changed', (event) => {
+            this.systemTheme = event.payload.theme
+            this._followSystemTheme()
+          })
+        }
+        this._followSystemTheme()
+      } catch (error) {
+        logger.warn('Failed to read system theme:', error)
+      }
+    },
+
+    stopWatchingSystemTheme(): void {
+      if (systemThemeUnlisten) {
+        systemThemeUnlisten()
+        systemThemeUnlisten = null
+      }
+    },
+
+    _followSystemTheme(): void {
+      if (this.themePreference !== 'auto') return
+      const isDark = this._systemIsDark()
+      if (isDark !== this.isDarkMode) {
+        this.isDarkMode = isDark
+        this.applyTheme()
+      }
+    },
+
+    // 系统强调色（#rrggbb），平台没有强调色或读取失败时返回 null
+    async getSystemAccentColor(): Promise<string | null> {
+      try {
+        const accent = await invoke<SystemAccentColor>('get_system_accent_color')
+        return accent.status === 'color' ? accent.hex : null
+      } catch (error) {
+        logger.warn('Failed to read system accent color:', error)
+        return null
+      }
+    },
+
     setGlassEffect(enabled: boolean): void {
       this.enableGlassEffect = enabled
       this.applyTheme()
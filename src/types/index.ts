@@ -7,13 +7,53 @@ export interface Track {
   displayTitle?: string
   artist?: string
   displayArtist?: string
+  artists?: string[]
   album?: string
+  albumArtist?: string | null
+  artistSort?: string | null
+  albumArtistSort?: string | null
+  albumSort?: string | null
+  genre?: string | null
+  year?: number | null
+  trackNumber?: number | null
+  trackTotal?: number | null
+  discNumber?: number | null
+  isrc?: string | null
+  label?: string | null
+  catalogNumber?: string | null
   duration?: number
+  cover?: string | null
+  hasCover?: boolean
+  coverSource?: 'embedded' | 'folder' | 'none'
   bitrate?: number | null
   sampleRate?: number | null
   channels?: number | null
   bitDepth?: number | null
   format?: string | null
+  encoder?: string | null
+  container?: string | null
+  mp3Info?: Mp3Info | null
+  encodingSuspect?: boolean
+  encodingRepair?: EncodingRepair | null
+  fileSize?: number | null
+  tagError?: string | null
+}
+
+export interface Mp3Info {
+  header: string | null
+  encoder: string | null
+  bitrateMode: 'CBR' | 'VBR' | 'ABR' | 'UNKNOWN'
+  encoderDelay: number | null
+  encoderPadding: number | null
+  frameCount: number | null
+}
+
+export interface EncodingRepair {
+  encoding: string
+  title?: string | null
+  artist?: string | null
+  album?: string | null
+  albumArtist?: string | null
 }
 
 export interface AudioInfo {
@@ -55,6 +95,369 @@ export interface Playlist {
   totalFiles?: number
 }
 
+export type PlaylistFileFormat = 'm3u' | 'pls' | 'xspf'
+
+export interface ImportedPlaylist {
+  playlist: Playlist
+  missing: string[]
+  streams: string[]
+  format: PlaylistFileFormat
+  encoding: string
+}
+
+export interface PlaylistExportResult {
+  outputPath: string
+  format: PlaylistFileFormat
+  exported: number
+}
+
+export interface FileMove {
+  from: string
+  to: string
+}
+
+export interface RelinkCandidate {
+  path: string
+  confidence: number
+  matchedBy: 'fileName' | 'metadata'
+}
+
+export interface RelinkProposal {
+  missing: string
+  candidates: RelinkCandidate[]
+  suggested: string | null
+}
+
+export type DuplicateStrategy = 'exact' | 'tags' | 'filename'
+
+export type DuplicateAction = 'trash' | 'exclude'
+
+export interface DuplicateFile {
+  path: string
+  size: number
+  bitrate: number | null
+  format: string | null
+}
+
+export interface DuplicateTrackGroup {
+  key: string
+  files: DuplicateFile[]
+}
+
+export interface FileFailure {
+  path: string
+  message: string
+}
+
+export interface DuplicateTrackReport {
+  strategy: DuplicateStrategy
+  groups: DuplicateTrackGroup[]
+  failed: FileFailure[]
+}
+
+export interface ResolveDuplicatesResult {
+  action: DuplicateAction
+  removed: string[]
+  failed: FileFailure[]
+}
+
+export interface FileConflict {
+  from: string
+  to: string | null
+  reason: string
+}
+
+export interface MoveFilesResult {
+  moved: FileMove[]
+  conflicts: FileConflict[]
+}
+
+export type CollisionPolicy = 'skip' | 'rename'
+
+export interface ImportFilesOptions {
+  moveFiles?: boolean
+  onCollision?: CollisionPolicy
+  pattern?: string
+  destination?: string
+  addToQueue?: boolean
+}
+
+export interface ImportFilesResult {
+  imported: FileMove[]
+  skipped: FileConflict[]
+  failed: FileConflict[]
+  cancelled: boolean
+}
+
+export interface FormatStats {
+  format: string
+  count: number
+  size: number
+}
+
+/** 批量检查时的文件状态，offline 表示所在网络共享无法访问或根目录离线 */
+export type FileStatus = 'exists' | 'missing' | 'offline'
+
+/** 音乐目录的状态 */
+export interface LibraryRootStatus {
+  path: string
+  online: boolean
+  /** 是否位于可移动或外接的卷上 */
+  removable: boolean
+  network: boolean
+  /** 是否已扫描进曲库 */
+  indexed: boolean
+  trackCount: number
+  /** 被标记为离线的时间（Unix 秒） */
+  offlineSince: number | null
+}
+
+/** library-root-offline / library-root-online 事件负载 */
+export interface LibraryRootEvent {
+  path: string
+  removable: boolean
+  trackCount: number
+}
+
+/** media-control 事件：系统媒体控件或全局媒体键请求的操作 */
+export type MediaControlEvent =
+  | { action: 'play' | 'pause' | 'playPause' | 'stop' | 'next' | 'previous' }
+  | { action: 'seek'; positionSecs: number }
+  | { action: 'setVolume'; volume: number }
+
+/** media-key 事件：按下了哪个全局媒体键 */
+export interface MediaKeyEvent {
+  action: 'playPause' | 'stop' | 'next' | 'previous'
+}
+
+/** 拖放的文件不被支持的原因 */
+export type UnsupportedReason = 'notFound' | 'offline' | 'extensionNotEnabled' | 'notLyrics' | 'unknownType'
+
+/** 拖放路径的分类，目录的 truncated 为 true 时 audioCount 表示"至少"这么多 */
+export type DroppedPath =
+  | { kind: 'audioFile'; path: string; metadata: Track | null }
+  | { kind: 'directory'; path: string; audioCount: number; truncated: boolean }
+  | { kind: 'archive'; path: string; audioCount: number }
+  | { kind: 'playlistFile'; path: string; format: 'm3u' | 'pls' | 'xspf' }
+  | { kind: 'lyricsFile'; path: string }
+  | { kind: 'unsupported'; path: string; reason: UnsupportedReason; extension: string | null }
+
+/** 曲目信息对话框中的文件属性，时间均为 Unix 秒 */
+export interface FileProperties {
+  path: string
+  size: number
+  created?: number
+  modified?: number
+  readOnly: boolean
+  canonicalPath: string
+  /** 包含该文件的音乐目录 */
+  libraryRoot?: string
+  /** 加入曲库的时间 */
+  addedAt?: number
+}
+
+/** 浏览侧栏的目录树节点 */
+export interface FolderNode {
+  path: string
+  name: string
+  /** 直接包含的音频文件数 */
+  audioCount: number
+  /** 子树中是否有音频文件 */
+  hasAudio: boolean
+  /** 是否有子目录，超过深度限制的节点 children 为空 */
+  hasChildren: boolean
+  accessible: boolean
+  children: FolderNode[]
+}
+
+export interface FolderStats {
+  path: string
+  tracks: number
+  totalSize: number
+  totalDuration: number
+  formats: FormatStats[]
+  indexed: boolean
+}
+
+export interface DeleteFilesResult {
+  deleted: string[]
+  failed: FileFailure[]
+  stoppedPlayback: boolean
+}
+
+export interface PathReferences {
+  source: string
+  paths: string[]
+}
+
+export interface MissingFile {
+  path: string
+  sources: string[]
+}
+
+export interface MissingFilesReport {
+  checked: number
+  missing: MissingFile[]
+  /** 当前无法访问的网络根目录 */
+  offlineRoots: string[]
+  /** 位于离线网络目录中而没有检查的文件 */
+  offline: MissingFile[]
+}
+
+export interface PruneResult {
+  removed: string[]
+  restored: string[]
+  offline: string[]
+}
+
+export interface RelinkApplyResult {
+  applied: FileMove[]
+  skipped: { from: string; to: string | null; reason: string }[]
+}
+
+/** 后台扫描进度（scan-progress 事件） */
+export interface ScanProgressEvent {
+  scanId: string
+  discovered: number
+  processed: number
+  current: string | null
+  elapsedMs: number
+}
+
+/** 扫描中无法读取元数据的文件 */
+export interface ScanFailure {
+  path: string
+  reason: string
+}
+
+/** 扫描报告，get_last_scan_report 返回最近一次的报告 */
+export interface ScanReport {
+  scanId: string
+  /** 结束时间（Unix 秒） */
+  finishedAt: number
+  elapsedMs: number
+  cancelled: boolean
+  filesSeen: number
+  audioFiles: number
+  added: number
+  /** 被排除规则或隐藏文件规则跳过的文件和目录数 */
+  excluded: number
+  failed: ScanFailure[]
+  /** 扩展名 -> 音频文件数 */
+  formats: Record<string, number>
+}
+
+/** validate_audio_files 中单个文件的状态，offsetSecs 为解码失败的位置 */
+export type ValidationStatus =
+  | { status: 'ok' }
+  | { status: 'truncated'; expectedSecs: number; actualSecs: number | null }
+  | { status: 'decodeError'; offsetSecs: number; message: string }
+  | { status: 'headerCorrupt'; message: string }
+  | { status: 'checksumMismatch' }
+  | { status: 'unreadable'; message: string }
+
+export type ValidationResult = ValidationStatus & {
+  path: string
+  duration: number | null
+  fullyDecoded: boolean
+  /** FLAC MD5 签名是否一致，没有校验时为 null */
+  md5Verified: boolean | null
+}
+
+/** 完整检查开始前的耗时估计（validation-estimate 事件） */
+export interface ValidationEstimate {
+  taskId: string
+  files: number
+  totalBytes: number
+  estimatedSecs: number
+}
+
+/** 后台扫描结果（scan-complete / scan-cancelled 事件） */
+export interface ScanResultEvent {
+  scanId: string
+  playlists: Playlist[]
+  elapsedMs: number
+  report: ScanReport
+}
+
+export interface ScanWarningEvent {
+  scanId?: string
+  path: string
+  message: string
+}
+
+export interface LibraryFilter {
+  text?: string
+  artist?: string
+  album?: string
+  albumArtist?: string
+  genre?: string
+  year?: number
+  format?: string
+  root?: string
+}
+
+export type SortField =
+  | 'title'
+  | 'artist'
+  | 'album'
+  | 'trackNumber'
+  | 'year'
+  | 'duration'
+  | 'path'
+  | 'dateModified'
+  | 'dateAdded'
+
+export interface SortSpec {
+  field?: SortField
+  direction?: SortOrder
+  secondary?: SortField
+}
+
+export interface LibraryPage {
+  tracks: Track[]
+  total: number
+}
+
+export interface LibraryArtist {
+  id: number
+  name: string
+  sortName?: string
+  trackCount: number
+  albumCount: number
+}
+
+export interface LibraryAlbum {
+  id: number
+  title: string
+  albumArtist: string
+  year?: number
+  trackCount: number
+  duration: number
+  coverTrack?: string
+}
+
+/** 最近添加的一张专辑，没有专辑标签的曲目单独成组 */
+export interface RecentlyAdded {
+  album?: string
+  albumArtist?: string
+  /** 组内最近一首曲目加入曲库的时间（Unix 秒） */
+  addedAt: number
+  tracks: Track[]
+  /** 是否来自曲库数据库 */
+  indexed: boolean
+}
+
+export interface LibraryDbStats {
+  tracks: number
+  albums: number
+  artists: number
+  roots: number
+  totalDuration: number
+  totalSize: number
+  lastScanned?: number
+}
+
 export type RepeatMode = 'none' | 'track' | 'list'
 export type SortOrder = 'asc' | 'desc'
 
@@ -65,6 +468,22 @@ export interface DirectoryScanConfig {
   maxDepth: number
   ignoreHiddenFolders: boolean
   folderBlacklist: string[]
+  excludePatterns?: string[]
+  skipHidden?: boolean
+  hiddenAllowlist?: string[]
+  followSymlinks?: boolean
+  watchDirectories?: boolean
+  guardShrunkRoots?: boolean
+  networkTimeoutSecs?: number
+  audioExtensions?: string[]
+  scanArchives?: boolean
+  resumeInterruptedScans?: boolean
+}
+
+export interface ExcludePatternTest {
+  matches: boolean
+  matchesName: boolean
+  matchesPath: boolean
 }
 
 export interface TitleExtractionConfig {
@@ -91,13 +510,222 @@ export interface GeneralConfig {
   lyricsAlignment: string
   lyricsFontFamily: string
   lyricsStyle: string
+  /** 窗口没有焦点时也响应键盘媒体键 */
+  globalMediaKeys: boolean
+  /** 只在播放时接管媒体键 */
+  mediaKeysOnlyWhenPlaying: boolean
+  /** 登记到系统的“打开方式”列表 */
+  registerFileAssociations: boolean
+  /** 换音轨时显示系统通知 */
+  trackNotifications: boolean
+  /** 只在窗口没有焦点时显示通知 */
+  notifyOnlyWhenUnfocused: boolean
 }
 
 export interface LyricsConfig {
   enableOnlineFetch: boolean
   autoSaveOnlineLyrics: boolean
   preferTranslation: boolean
+  preferRomanization?: boolean
   onlineSource: string
+  /** 在线歌词缓存有效期（天），0 为永不过期 */
+  cacheTtlDays?: number
+  /** 自动匹配歌词时依次尝试的在线来源 */
+  providerOrder?: string[]
+  searchSubfolders?: string[]
+  /** 卡拉 OK 模式：自动匹配时优先选择带逐字时间的歌词 */
+  karaokeMode?: boolean
+}
+
+/** 在线歌词缓存统计 */
+export interface LyricsCacheStats {
+  entries: number
+  expired: number
+  totalBytes: number
+  oldestFetchedAt: number | null
+  newestFetchedAt: number | null
+}
+
+/** 逐字歌词中的一个字（词），时间为从歌曲开始的毫秒数 */
+export interface LyricWord {
+  start: number
+  duration: number
+  text: string
+}
+
+/** 带逐字时间的一行歌词 */
+export interface WordTimedLine {
+  start: number
+  duration: number
+  words: LyricWord[]
+}
+
+/** 歌词自动匹配中候选得分的各个部分 */
+export interface MatchScore {
+  /** 标题相似度（0 到 1） */
+  title: number
+  /** 艺术家相似度，音轨没有艺术家时为 null */
+  artist: number | null
+  /** 专辑相似度，音轨或候选没有专辑时为 null */
+  album: number | null
+  /** 与音轨的时长差（秒） */
+  durationDiff: number | null
+  /** 加权后的总分（满分 1.0） */
+  total: number
+}
+
+/** 歌词自动匹配的候选歌曲 */
+export interface MatchCandidate {
+  provider: string
+  id: string
+  name: string
+  artist: string
+  album: string
+  albumId: string | null
+  coverUrl: string | null
+  duration: number
+  score: MatchScore
+}
+
+/** 在线歌词类型 */
+export type LyricsKind = 'synced' | 'plainText' | 'instrumental' | 'notFound'
+
+/** 批量下载的歌词保存位置 */
+export type LyricsSaveTarget = 'lrcFile' | 'cacheOnly' | 'embed'
+
+export type LyricsBatchStatus = 'matched' | 'alreadyHad' | 'lowConfidence' | 'instrumental' | 'failed'
+
+export interface LyricsBatchItem {
+  path: string
+  status: LyricsBatchStatus
+  songId: string | null
+  score: number | null
+  matchScore: MatchScore | null
+  /** 得分次高的候选（includeRunnersUp 开启时） */
+  runnersUp?: MatchCandidate[]
+  error: string | null
+  /** 在线请求出错时的错误码 */
+  errorCode?: OnlineErrorCode
+}
+
+/** lyrics-batch-item 事件 */
+export interface LyricsBatchItemEvent {
+  taskId: string
+  completed: number
+  total: number
+  item: LyricsBatchItem
+}
+
+/** netease_download_lyrics_batch 的报告 */
+export interface LyricsBatchReport {
+  matched: LyricsBatchItem[]
+  skipped: LyricsBatchItem[]
+  lowConfidence: LyricsBatchItem[]
+  /** 纯音乐，之后不再请求 */
+  instrumental: LyricsBatchItem[]
+  failed: LyricsBatchItem[]
+  cancelled: boolean
+}
+
+export type LyricsMatchConfidence = 'exact' | 'normalized' | 'title'
+
+export interface LyricsCandidate {
+  path: string
+  confidence: LyricsMatchConfidence
+}
+
+export interface LyricsFileMatch {
+  best: LyricsCandidate | null
+  candidates: LyricsCandidate[]
+}
+
+export interface LyricsFileContent {
+  content: string
+  /** 检测到的编码，如 UTF-8、GBK、Shift_JIS */
+  encoding: string
+  hadBom: boolean
+  /** 修改时间（毫秒），写入时作为 expectedModified 传回以检测外部修改 */
+  modified: number
+}
+
+/** save_lyrics_as_lrc 的选项 */
+export interface LrcSaveOptions {
+  /** 在原文行后插入翻译，默认 true */
+  includeTranslation?: boolean
+  /** 在原文行后插入罗马音，默认 false */
+  includeRomanization?: boolean
+  /** 覆盖已有的用户歌词文件 */
+  overwrite?: boolean
+  /** 音轨的歌词时间偏移写入时间标签（bake，默认）还是 [offset:] 标签（tag） */
+  offsetMode?: 'bake' | 'tag'
+}
+
+/** 保存 .lrc 时目标位置已有用户自己的歌词文件 */
+export interface LrcOverwriteConflict {
+  path: string
+  current: string
+  proposed: string
+}
+
+/** 网易云音乐歌单中的一首歌曲 */
+export interface OnlinePlaylistEntry {
+  id: string
+  title: string
+  artist: string
+  album: string
+  /** 时长（毫秒） */
+  duration: number
+}
+
+export interface OnlinePlaylist {
+  id: string
+  name: string
+  creator: string | null
+  entries: OnlinePlaylistEntry[]
+  /** 取不到详情的歌曲数（已下架等） */
+  unavailable: number
+}
+
+/** 在线歌单与曲库的对应结果 */
+export interface PlaylistMatchResult {
+  entries: { entry: OnlinePlaylistEntry; path: string | null; score: MatchScore | null }[]
+  /** 找到的本地文件，按歌单顺序排列 */
+  paths: string[]
+  missing: OnlinePlaylistEntry[]
+}
+
+/** 准备嵌入音频文件的在线封面 */
+export interface EmbedCover {
+  /** 封面缓存中的原图路径 */
+  cachePath: string
+  /** 要嵌入的图片，用于确认前预览 */
+  dataUrl: string
+  width: number
+  height: number
+  /** 是否按最大边长缩小过 */
+  resized: boolean
+}
+
+export interface EmbedCoverResult {
+  cover: EmbedCover
+  /** 写入后的元数据，预览时为 null */
+  metadata: Track | null
+}
+
+export interface AlbumCoverEmbedResult {
+  cover: EmbedCover
+  /** 每个文件的写入结果，预览时为空 */
+  results: { path: string; metadata: Track | null; error: { kind: string; message: string } | null }[]
+}
+
+export interface LyricsFileChangedEvent {
+  trackPath: string
+  paths: string[]
+}
+
+export interface EmbeddedLyrics {
+  text: string
+  synced: boolean
 }
 
 export interface UIConfig {
@@ -109,6 +737,34 @@ export interface UIConfig {
 export interface AudioConfig {
   exclusiveMode: boolean
   volume: number
+  ducking?: DuckingConfig
+}
+
+/** 其他程序通话时的处理：不处理、降低音量或暂停 */
+export type DuckingMode = 'off' | 'duck' | 'pause'
+
+/** 通话时自动闪避的设置（目前只在 Windows 上检测通话） */
+export interface DuckingConfig {
+  mode: DuckingMode
+  /** 降低音量的比例（0–1） */
+  amount: number
+}
+
+/** 闪避状态，也是 ducking-changed 事件的负载 */
+export interface DuckingStatus {
+  /** 当前平台能否检测通话 */
+  supported: boolean
+  callActive: boolean
+  state: 'inactive' | 'ducked' | 'paused'
+}
+
+export interface MetadataConfig {
+  artistSeparators: string[]
+  sortArticles: Record<string, string[]>
+  acoustidApiKey: string
+  coverFileNames: string[]
+  /** 嵌入在线封面时的最大边长（像素），0 表示保持原图 */
+  maxEmbedCoverSize?: number
 }
 
 export interface AppConfig {
@@ -120,10 +776,149 @@ export interface AppConfig {
   lyrics: LyricsConfig
   ui: UIConfig
   audio: AudioConfig
+  metadata?: MetadataConfig
+  network?: NetworkConfig
+  netease?: NeteaseConfig
+  lastfm?: LastfmConfig
+  listenbrainz?: ListenBrainzConfig
+  power?: PowerConfig
+}
+
+/** 电源设置 */
+export interface PowerConfig {
+  /** 使用电池时减少后台工作：扫描只用一个线程，指纹等批量任务暂停，目录监视合并事件的时间更长 */
+  reduceBackgroundWorkOnBattery: boolean
+}
+
+/** get_power_status 的结果，也是 power-status-changed 事件的负载 */
+export interface PowerStatus {
+  /** unknown 表示当前平台无法读取；没有电池的设备为 ac */
+  source: 'ac' | 'battery' | 'unknown'
+  /** 剩余电量（0–100），没有电池时为 null */
+  percentage: number | null
+  /** 是否正在减少后台工作 */
+  reducingBackgroundWork: boolean
+}
+
+/** get_task_status / get_running_tasks 的结果，也是 task-status 事件的负载 */
+export interface TaskStatus {
+  taskId: string
+  /** 使用电池期间为省电暂停，接上电源后自动继续 */
+  paused: boolean
+}
+
+/** 网络设置，proxyUrl 为空时使用系统代理 */
+export interface NetworkConfig {
+  proxyUrl: string | null
+  noProxy: string | null
+  /** 网易云音乐接口每秒最多请求数 */
+  neteaseRequestsPerSecond?: number
+  /** 离线模式：不发出任何网络请求，在线功能只使用缓存 */
+  offlineMode?: boolean
+}
+
+/** 网易云音乐接口类型：官方网页接口或自建的 NeteaseCloudMusicApi */
+export type NeteaseApiFlavor = 'web' | 'cloudMusicApi'
+
+/** 网易云音乐接口设置，apiBaseUrl 为空时直接访问网易云音乐 */
+export interface NeteaseConfig {
+  apiBaseUrl: string
+  apiFlavor: NeteaseApiFlavor
+  /** 为空时模拟浏览器 */
+  userAgent: string
+  extraHeaders: Record<string, string>
+  /** 如 MUSIC_U=... */
+  cookie: string
+}
+
+/** Last.fm 设置，sessionKey 为空时表示未授权 */
+export interface LastfmConfig {
+  apiKey: string
+  apiSecret: string
+  sessionKey: string
+  username: string
+  /** 是否提交播放记录和“正在播放” */
+  enabled: boolean
+}
+
+/** lastfm_get_auth_url 的结果：在浏览器中打开 url 同意授权后，用 token 调用 lastfm_complete_auth */
+export interface LastfmAuthRequest {
+  url: string
+  token: string
+}
+
+/** ListenBrainz 设置，token 为空时不提交 */
+export interface ListenBrainzConfig {
+  token: string
+  /** 令牌对应的用户名，由 listenbrainz_validate_token 写入 */
+  username: string
+  /** 是否提交播放记录和“正在播放” */
+  enabled: boolean
+}
+
+/** 一个服务的播放记录提交状态 */
+export interface ScrobbleServiceStatus {
+  /** 已登录且开启了提交 */
+  enabled: boolean
+  username: string | null
+  /** 队列中等待提交的播放记录数 */
+  pending: number
+  /** 最近一次提交失败的原因 */
+  lastError: string | null
+  /** 最近一次提交成功的时间（Unix 时间戳，秒） */
+  lastScrobbledAt: number | null
+}
+
+/** get_scrobble_status 的结果 */
+export interface ScrobbleStatus {
+  lastfm: ScrobbleServiceStatus
+  listenbrainz: ScrobbleServiceStatus
+}
+
+/** offline-mode-changed 事件 */
+export interface OfflineModeEvent {
+  offline: boolean
+}
+
+/** netease-throttled 事件：网易云音乐请求因限流或风控暂停 */
+export interface NeteaseThrottledEvent {
+  retryAfterSecs: number
+  blocked: boolean
+}
+
+/** test_network_connection 的结果 */
+export interface ConnectionTest {
+  success: boolean
+  latencyMs: number
+  status: number | null
+  error: string | null
+  viaProxy: boolean
+}
+
+/** 网易云音乐接口设置的检查结果 */
+export interface ApiConnectionTest {
+  success: boolean
+  latencyMs: number
+  /** 测试搜索返回的歌曲数 */
+  results: number
+  error: { code: string; message: string } | null
 }
 
 // ============ 错误处理类型 ============
 
+/** 在线命令返回的错误码，与后端 NeteaseError::code 对应 */
+export type OnlineErrorCode =
+  | 'TIMEOUT'
+  | 'RATE_LIMITED'
+  | 'BLOCKED'
+  | 'NOT_FOUND'
+  | 'PRIVATE'
+  | 'PARSE'
+  | 'NETWORK'
+  | 'PROXY'
+  | 'CACHE'
+  | 'OFFLINE_MODE'
+
 export enum ErrorType {
   NETWORK = 'NETWORK',
   NETWORK_TIMEOUT = 'NETWORK_TIMEOUT',
@@ -201,6 +996,67 @@ export interface LogData {
 
 export type ThemePreference = 'auto' | 'light' | 'dark' | string
 
+/** get_system_theme 的结果：unknown 表示读取失败或系统没有偏好，unsupported 表示当前平台无法读取 */
+export type SystemTheme = 'dark' | 'light' | 'unknown' | 'unsupported'
+
+/** get_system_accent_color 的结果 */
+export type SystemAccentColor =
+  | { status: 'color'; hex: string }
+  | { status: 'unknown' }
+  | { status: 'unsupported' }
+
+/** get_autostart_status / set_autostart 的结果 */
+export interface AutostartStatus {
+  /** 当前平台是否支持开机启动 */
+  supported: boolean
+  /** 启动项是否存在 */
+  enabled: boolean
+  /** 是否最小化启动 */
+  minimized: boolean
+  /** 启动项指向的程序 */
+  registeredPath: string | null
+  /** 当前程序的路径 */
+  currentPath: string | null
+  /** 启动项指向旧的程序路径（程序被移动或更新后），需要修复 */
+  stale: boolean
+}
+
+/** get_system_info 的结果，大小和内存均以字节为单位 */
+export interface SystemInfo {
+  os: string
+  /** 系统的名称和版本，读取失败时为 null */
+  osVersion: string | null
+  arch: string
+  family: string
+  musicDir: string | null
+  appVersion: string
+  /** 构建时的 Git 提交，不在 Git 仓库中构建时为 unknown */
+  buildHash: string
+  /** cpal 音频后端（如 WASAPI、ALSA、CoreAudio） */
+  audioHost: string
+  outputDevice: string
+  outputMode: 'shared' | 'exclusive'
+  totalMemory: number
+  availableMemory: number
+  processMemory: number | null
+  /** 程序数据目录（配置、数据和缓存所在的目录） */
+  appDataDir: string
+  appDataSize: number
+  /** 是否以管理员（root）权限运行，当前平台无法判断时为 null */
+  elevated: boolean | null
+  /** 系统语言（BCP-47 标签） */
+  locale: string
+  /** 后端文字使用的语言 */
+  language: 'en' | 'zh-CN'
+  theme: SystemTheme
+}
+
+/** system-theme-changed 事件 */
+export interface SystemThemeChangedEvent {
+  theme: SystemTheme
+  accentColor: SystemAccentColor
+}
+
 export interface TonalVariants {
   [key: string]: string
 }
@@ -248,3 +1104,11 @@ export interface ErrorStats {
   bySeverity: Record<string, number>
   recent: Array<{ error: object; timestamp: string }>
 }
+
+/** copy_cover_image 的结果 */
+export interface CopiedCover {
+  /** 封面所属的音轨 */
+  path: string
+  width: number
+  height: number
+}
@@ -165,8 +165,11 @@ class ErrorHandler {
         context
       )
     } else {
+      // 后端命令的错误序列化为 { code, message }
+      const serialized = typeof error === 'object' && error !== null ? (error as { message?: unknown }) : null
+      const message = typeof serialized?.message === 'string' ? serialized.message : String(error)
       appError = new AppError(
-        String(error) || '未知错误',
+        message || '未知错误',
         type,
         severity,
         error,
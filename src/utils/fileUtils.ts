@@ -2,12 +2,45 @@ import { open } from '@tauri-apps/plugin-dialog'
 import { invoke } from '@tauri-apps/api/core'
 import logger from './logger'
 import { ErrorType, ErrorSeverity, handlePromise } from './errorHandler'
-import type { Playlist } from '@/types'
+import type { DroppedPath, FileStatus, LibraryRootStatus, LyricsFileContent, LyricsFileMatch, Playlist } from '@/types'
+
+/**
+ * 支持的音频扩展名，与后端扫描器使用同一份列表（见 loadSupportedExtensions）
+ */
+let supportedExtensions: string[] = [
+  'mp3', 'flac', 'wav', 'ogg', 'm4a', 'm4b', 'aac', 'opus', 'ape', 'wv', 'aiff', 'aif', 'wma', 'dsf'
+]
+
+/**
+ * 是否运行在 Windows 上（文件名不区分大小写），与后端的 `cfg(windows)` 对应
+ */
+const isWindows = (): boolean =>
+  typeof navigator !== 'undefined' && /windows/i.test(navigator.userAgent)
 
 /**
  * 文件工具类，处理文件和目录相关操作
  */
 export class FileUtils {
+  /**
+   * 从后端读取支持的音频扩展名，修改设置后需要重新调用
+   */
+  static async loadSupportedExtensions(): Promise<string[]> {
+    try {
+      supportedExtensions = await invoke<string[]>('get_supported_extensions')
+    } catch (error) {
+      logger.warn('Failed to load supported extensions', error)
+    }
+    return supportedExtensions
+  }
+
+  /**
+   * 设置支持的音频扩展名（小写、不含点），返回保存后的列表
+   */
+  static async setSupportedExtensions(extensions: string[]): Promise<string[]> {
+    supportedExtensions = await invoke<string[]>('set_supported_extensions', { extensions })
+    return supportedExtensions
+  }
+
   /**
    * 打开文件夹选择对话框
    */
@@ -107,12 +140,78 @@ export class FileUtils {
     return result.success ? result.data! : false
   }
 
+  /**
+   * 批量检查文件是否存在，结果与 paths 一一对应；位于离线网络共享上的文件为 offline
+   */
+  static async filesExist(paths: string[]): Promise<FileStatus[]> {
+    if (!paths.length) return []
+    const result = await handlePromise(
+      invoke<FileStatus[]>('check_files_exist', { paths }),
+      {
+        type: ErrorType.FILE_READ_ERROR,
+        severity: ErrorSeverity.LOW,
+        context: { count: paths.length, action: 'filesExist' },
+        showToUser: false,
+        throw: false
+      }
+    )
+
+    return result.success ? result.data! : paths.map(() => 'missing')
+  }
+
+  /**
+   * 判断拖放到窗口上的路径各是什么，结果与 paths 一一对应
+   */
+  static async classifyDroppedPaths(paths: string[]): Promise<DroppedPath[]> {
+    if (!paths.length) return []
+    const result = await handlePromise(
+      invoke<DroppedPath[]>('classify_dropped_paths', { paths }),
+      {
+        type: ErrorType.FILE_READ_ERROR,
+        severity: ErrorSeverity.LOW,
+        context: { count: paths.length, action: 'classifyDroppedPaths' },
+        showToUser: false,
+        throw: false
+      }
+    )
+
+    return result.success
+      ? result.data!
+      : paths.map(path => ({ kind: 'unsupported', path, reason: 'notFound', extension: null }))
+  }
+
+  /**
+   * 获取各音乐目录是否在线（如外接硬盘是否已连接）
+   */
+  static async getLibraryRootsStatus(): Promise<LibraryRootStatus[]> {
+    const result = await handlePromise(
+      invoke<LibraryRootStatus[]>('get_library_roots_status'),
+      {
+        type: ErrorType.FILE_READ_ERROR,
+        severity: ErrorSeverity.LOW,
+        context: { action: 'getLibraryRootsStatus' },
+        showToUser: false,
+        throw: false
+      }
+    )
+
+    return result.success ? result.data! : []
+  }
+
   /**
    * 读取文件内容
    */
   static async readFile(path: string): Promise<string> {
+    const file = await this.readLyricsFile(path)
+    return file?.content ?? ''
+  }
+
+  /**
+   * 读取歌词文件，后端自动检测编码并返回检测到的编码
+   */
+  static async readLyricsFile(path: string): Promise<LyricsFileContent | null> {
     const result = await handlePromise(
-      invoke<string>('read_lyrics_file', { path }),
+      invoke<LyricsFileContent>('read_lyrics_file', { path }),
       {
         type: ErrorType.FILE_READ_ERROR,
         severity: ErrorSeverity.MEDIUM,
@@ -122,7 +221,7 @@ export class FileUtils {
       }
     )
 
-    return result.success ? result.data! : ''
+    return result.success ? result.data! : null
   }
 
   /**
@@ -159,13 +258,48 @@ export class FileUtils {
     return parts.slice(0, -1).join('/') || ''
   }
 
+  /**
+   * 比较路径和作为映射键时使用的写法，与后端 `paths::canonical_key` 一致：
+   * 去掉 `\\?\` 前缀，分隔符统一为 `/`，合并重复的分隔符并去掉 `.` 和末尾的分隔符，盘符转为小写；
+   * 只有 Windows 上整个路径转为小写（其他平台的文件名区分大小写）
+   */
+  static pathKey(path: string): string {
+    let raw = path
+    if (raw.startsWith('\\\\?\\UNC\\')) {
+      raw = '\\\\' + raw.slice(8)
+    } else if (raw.startsWith('\\\\?\\')) {
+      raw = raw.slice(4)
+    }
+    raw = raw.replace(/\\/g, '/')
+    const lead = raw.startsWith('//') ? '//' : raw.startsWith('/') ? '/' : ''
+    const parts = raw.split('/').filter(part => part !== '' && part !== '.')
+    const key = lead + parts.join('/')
+    if (isWindows()) {
+      return key.toLowerCase()
+    }
+    return /^[A-Za-z]:/.test(key) ? key[0].toLowerCase() + key.slice(1) : key
+  }
+
+  /**
+   * 比较两个路径是否指向同一文件（按 `pathKey` 比较）
+   */
+  static pathsEqual(a: string, b: string): boolean {
+    return FileUtils.pathKey(a) === FileUtils.pathKey(b)
+  }
+
+  /**
+   * 路径是否位于目录下（按 `pathKey` 比较，目录本身不算）
+   */
+  static isUnder(path: string, dir: string): boolean {
+    const root = FileUtils.pathKey(dir)
+    return FileUtils.pathKey(path).startsWith(root.endsWith('/') ? root : root + '/')
+  }
+
   /**
    * 检查文件是否为音频文件
    */
   static isAudioFile(filePath: string): boolean {
-    const extension = this.getFileExtension(filePath)
-    const audioExtensions = ['mp3', 'flac', 'wav', 'ogg', 'm4a', 'aac', 'wma']
-    return audioExtensions.includes(extension)
+    return supportedExtensions.includes(this.getFileExtension(filePath))
   }
 
   /**
@@ -173,34 +307,36 @@ export class FileUtils {
    */
   static isLyricsFile(filePath: string): boolean {
     const extension = this.getFileExtension(filePath)
-    const lyricsExtensions = ['lrc', 'ass', 'srt']
+    const lyricsExtensions = ['lrc', 'ass', 'srt', 'txt']
     return lyricsExtensions.includes(extension)
   }
 
+  /**
+   * 歌词文件的解析格式，包含 LRC 时间标签的 .txt 按 LRC 解析
+   */
+  static getLyricsFormat(lyricsPath: string): 'lrc' | 'ass' | 'srt' {
+    const extension = this.getFileExtension(lyricsPath)
+    return extension === 'ass' || extension === 'srt' ? extension : 'lrc'
+  }
+
+  /**
+   * 查找音频文件对应的歌词文件，返回最佳匹配及其他候选
+   */
+  static async findLyricsCandidates(audioPath: string): Promise<LyricsFileMatch> {
+    try {
+      return await invoke<LyricsFileMatch>('find_lyrics_file', { audioPath })
+    } catch (error) {
+      logger.debug('Failed to find lyrics file:', error)
+      return { best: null, candidates: [] }
+    }
+  }
+
   /**
    * 根据音频文件路径查找对应的歌词文件
    */
   static async findLyricsFile(audioPath: string): Promise<string | null> {
-    const baseName = this.getFileNameWithoutExtension(audioPath)
-    const directory = this.getDirectoryPath(audioPath)
-    
-    // 尝试常见的歌词文件扩展名
-    const lyricsExtensions = ['lrc', 'ass', 'srt']
-    
-    for (const ext of lyricsExtensions) {
-      const lyricsPath = `${directory}/${baseName}.${ext}`
-      try {
-        // 先检查文件是否存在
-        const exists = await this.fileExists(lyricsPath)
-        if (exists) {
-          return lyricsPath
-        }
-      } catch {
-        // 文件不存在，继续尝试下一个扩展名
-      }
-    }
-    
-    return null
+    const match = await this.findLyricsCandidates(audioPath)
+    return match.best?.path ?? null
   }
 
   /**
@@ -6,17 +6,104 @@
 import { invoke } from '@tauri-apps/api/core'
 import logger from './logger'
 import errorHandler, { ErrorType, ErrorSeverity, handlePromise } from './errorHandler'
+import { parseOnlineError } from './onlineErrors'
+import type {
+  AlbumCoverEmbedResult,
+  EmbedCoverResult,
+  LrcOverwriteConflict,
+  LrcSaveOptions,
+  LyricsBatchReport,
+  LyricsCacheStats,
+  LyricsSaveTarget,
+  MatchCandidate,
+  MatchScore,
+  OnlinePlaylist,
+  OnlinePlaylistEntry,
+  PlaylistMatchResult,
+  WordTimedLine,
+  LyricsKind,
+  OnlineErrorCode
+} from '@/types'
 
 interface Song {
+  /** 所在的在线来源（netease、qqmusic） */
+  provider?: string
   id: string
   name: string
   artist: string
+  album: string
+  albumId: string | null
+  coverUrl: string | null
   duration: number
 }
 
+interface SongDetail {
+  id: string
+  name: string
+  artists: string[]
+  album: string
+  albumId: string | null
+  coverUrl: string | null
+  /** 精确时长（毫秒） */
+  duration: number
+  /** 专辑发行时间（Unix 毫秒） */
+  publishTime: number | null
+}
+
+interface AlbumSearchResult {
+  id: string
+  name: string
+  artist: string
+  trackCount: number
+  publishYear: number | null
+  coverUrl: string | null
+}
+
+interface AlbumTrack extends Song {
+  trackNumber: number | null
+  discNumber: number | null
+}
+
+interface MergedLyrics {
+  lines: Array<{ time: number; original: string; translation: string | null; romanization: string | null }>
+  lrc: string
+}
+
+type AutoMatchOutcome =
+  | {
+      status: 'matched'
+      lyrics: LyricsData
+      provider: string
+      songId: string
+      score: number
+      /** 得分的各个部分，使用缓存的匹配结果时为 null */
+      matchScore: MatchScore | null
+      song: Song | null
+      /** 同一来源中得分次高的候选（includeRunnersUp 为 true 时） */
+      runnersUp?: MatchCandidate[]
+      cached: boolean
+    }
+  | { status: 'candidates'; candidates: MatchCandidate[] }
+  | { status: 'noMatch' }
+
+interface CoverImage {
+  path: string
+  dataUrl: string
+  cached: boolean
+}
+
 interface LyricsData {
+  /** 纯音乐或没有歌词时 lrc 为空 */
+  kind?: LyricsKind
   lrc: string
   tlyric?: string
+  romalrc?: string
+  /** 逐字歌词（来源提供逐字时间时），lrc 为展平后的行级歌词 */
+  words?: WordTimedLine[]
+  /** 是否带逐字时间（网易云音乐 YRC、酷狗 KRC） */
+  wordLevel?: boolean
+  /** 已应用的歌词时间偏移（毫秒），正数表示歌词推后 */
+  offsetMs?: number
 }
 
 /**
@@ -24,19 +111,20 @@ interface LyricsData {
  */
 export class NeteaseAPI {
   /**
-   * 搜索歌曲
+   * 搜索歌曲，不指定 provider 时按优先级搜索所有启用的来源
    */
-  async searchSongs(keyword: string, limit: number = 10, offset: number = 0): Promise<Song[]> {
+  async searchSongs(keyword: string, limit: number = 10, offset: number = 0, provider?: string): Promise<Song[]> {
     const result = await handlePromise(
-      invoke<Song[]>('netease_search_songs', {
-        keyword,
+      invoke<Song[]>('lyrics_search', {
+        query: keyword,
+        provider,
         limit,
         offset
       }),
       {
         type: ErrorType.NETWORK,
         severity: ErrorSeverity.MEDIUM,
-        context: { keyword, limit, offset, action: 'searchSongs' },
+        context: { keyword, limit, offset, provider, action: 'searchSongs' },
         showToUser: false,
         throw: false
       }
@@ -46,15 +134,19 @@ export class NeteaseAPI {
   }
 
   /**
-   * 获取歌词
+   * 获取歌词，songId 为 provider 搜索结果中的 ID
    */
-  async getLyrics(songId: string): Promise<LyricsData | null> {
+  async getLyrics(
+    songId: string,
+    forceRefresh: boolean = false,
+    provider: string = 'netease'
+  ): Promise<LyricsData | null> {
     const result = await handlePromise(
-      invoke<LyricsData>('netease_get_lyrics', { songId }),
+      invoke<LyricsData>('lyrics_fetch', { provider, songId, forceRefresh }),
       {
         type: ErrorType.NETWORK,
         severity: ErrorSeverity.MEDIUM,
-        context: { songId, action: 'getLyrics' },
+        context: { songId, forceRefresh, provider, action: 'getLyrics' },
         showToUser: false,
         throw: false
       }
@@ -63,6 +155,296 @@ export class NeteaseAPI {
     return result.success ? result.data : null
   }
 
+  /**
+   * 批量获取网易云音乐歌曲详情，结果按传入顺序排列
+   */
+  async getSongDetails(songIds: string[]): Promise<SongDetail[]> {
+    if (songIds.length === 0) return []
+    const result = await handlePromise(
+      invoke<SongDetail[]>('netease_get_song_detail', { songIds }),
+      {
+        type: ErrorType.NETWORK,
+        severity: ErrorSeverity.LOW,
+        context: { count: songIds.length, action: 'getSongDetails' },
+        showToUser: false,
+        throw: false
+      }
+    )
+
+    return result.success ? result.data! : []
+  }
+
+  /**
+   * 搜索网易云音乐专辑
+   */
+  async searchAlbums(keyword: string, limit: number = 10): Promise<AlbumSearchResult[]> {
+    const result = await handlePromise(
+      invoke<AlbumSearchResult[]>('netease_search_albums', { keyword, limit }),
+      {
+        type: ErrorType.NETWORK,
+        severity: ErrorSeverity.MEDIUM,
+        context: { keyword, limit, action: 'searchAlbums' },
+        showToUser: false,
+        throw: false
+      }
+    )
+
+    return result.success ? result.data! : []
+  }
+
+  /**
+   * 获取网易云音乐专辑的曲目列表，按专辑中的顺序排列
+   */
+  async getAlbumTracks(albumId: string): Promise<AlbumTrack[]> {
+    const result = await handlePromise(
+      invoke<AlbumTrack[]>('netease_get_album_tracks', { albumId }),
+      {
+        type: ErrorType.NETWORK,
+        severity: ErrorSeverity.MEDIUM,
+        context: { albumId, action: 'getAlbumTracks' },
+        showToUser: false,
+        throw: false
+      }
+    )
+
+    return result.success ? result.data! : []
+  }
+
+  /**
+   * 获取网易云音乐歌单，可传入歌单 ID、链接或分享文本
+   * 失败时返回错误码：私密歌单为 PRIVATE，地区限制为 BLOCKED
+   */
+  async getPlaylist(playlist: string): Promise<{ playlist: OnlinePlaylist } | { errorCode: OnlineErrorCode } | null> {
+    const result = await handlePromise(
+      invoke<OnlinePlaylist>('netease_get_playlist', { playlist }),
+      {
+        type: ErrorType.NETWORK,
+        severity: ErrorSeverity.MEDIUM,
+        context: { playlist, action: 'getPlaylist' },
+        showToUser: false,
+        throw: false
+      }
+    )
+
+    if (result.success) return { playlist: result.data! }
+    const online = parseOnlineError(result.error?.originalError)
+    return online ? { errorCode: online.code } : null
+  }
+
+  /**
+   * 把歌单中的歌曲对应到曲库中的本地文件
+   */
+  async matchPlaylistToLibrary(entries: OnlinePlaylistEntry[]): Promise<PlaylistMatchResult | null> {
+    const result = await handlePromise(
+      invoke<PlaylistMatchResult>('match_online_playlist_to_library', { entries }),
+      {
+        type: ErrorType.UNKNOWN,
+        severity: ErrorSeverity.MEDIUM,
+        context: { count: entries.length, action: 'matchPlaylistToLibrary' },
+        showToUser: false,
+        throw: false
+      }
+    )
+
+    return result.success ? result.data! : null
+  }
+
+  /**
+   * 获取在线歌词缓存统计
+   */
+  async getCacheStats(): Promise<LyricsCacheStats | null> {
+    const result = await handlePromise(
+      invoke<LyricsCacheStats>('get_lyrics_cache_stats'),
+      {
+        type: ErrorType.FILE_READ_ERROR,
+        severity: ErrorSeverity.LOW,
+        context: { action: 'getCacheStats' },
+        showToUser: false,
+        throw: false
+      }
+    )
+
+    return result.success ? result.data! : null
+  }
+
+  /**
+   * 清空在线歌词缓存，返回删除的条目数
+   */
+  async clearCache(): Promise<number> {
+    const result = await handlePromise(
+      invoke<number>('clear_lyrics_cache'),
+      {
+        type: ErrorType.FILE_WRITE_ERROR,
+        severity: ErrorSeverity.LOW,
+        context: { action: 'clearCache' },
+        showToUser: false,
+        throw: false
+      }
+    )
+
+    return result.success ? result.data! : 0
+  }
+
+  /**
+   * 根据音轨标签自动匹配歌词，置信度不足时返回候选列表
+   */
+  async autoMatchLyrics(path: string, includeRunnersUp: boolean = false): Promise<AutoMatchOutcome | null> {
+    const result = await handlePromise(
+      invoke<AutoMatchOutcome>('netease_auto_match_lyrics', { path, includeRunnersUp }),
+      {
+        type: ErrorType.NETWORK,
+        severity: ErrorSeverity.MEDIUM,
+        context: { path, includeRunnersUp, action: 'autoMatchLyrics' },
+        showToUser: false,
+        throw: false
+      }
+    )
+
+    return result.success ? result.data! : null
+  }
+
+  /**
+   * 记录音轨对应的歌曲，songId 为 null 时撤销自动匹配；provider 默认为网易云音乐
+   */
+  async setLyricsMatch(path: string, songId: string | null, provider: string = 'netease'): Promise<boolean> {
+    const result = await handlePromise(
+      invoke('netease_set_lyrics_match', { path, songId, provider }),
+      {
+        type: ErrorType.FILE_WRITE_ERROR,
+        severity: ErrorSeverity.LOW,
+        context: { path, songId, provider, action: 'setLyricsMatch' },
+        showToUser: false,
+        throw: false
+      }
+    )
+
+    return result.success
+  }
+
+  /**
+   * 为没有歌词的音轨批量下载歌词，进度通过 task-progress 和 lyrics-batch-item 事件报告
+   * 指定 albumId 时先按曲号和标题对应到该专辑的曲目
+   */
+  async downloadLyricsBatch(
+    paths: string[],
+    saveTo: LyricsSaveTarget = 'lrcFile',
+    taskId?: string,
+    includeRunnersUp: boolean = false,
+    albumId?: string
+  ): Promise<LyricsBatchReport | null> {
+    const result = await handlePromise(
+      invoke<LyricsBatchReport>('netease_download_lyrics_batch', {
+        paths,
+        options: { saveTo, includeRunnersUp, albumId },
+        taskId
+      }),
+      {
+        type: ErrorType.NETWORK,
+        severity: ErrorSeverity.MEDIUM,
+        context: { count: paths.length, saveTo, albumId, action: 'downloadLyricsBatch' },
+        showToUser: false,
+        throw: false
+      }
+    )
+
+    return result.success ? result.data! : null
+  }
+
+  /**
+   * 把歌词保存为音频文件旁的同名 .lrc 文件
+   * 已有用户自己的歌词文件且没有指定 overwrite 时返回 conflict，其中包含现有内容和要写入的内容
+   */
+  async saveLyricsAsLrc(
+    audioPath: string,
+    lyrics: LyricsData,
+    options: LrcSaveOptions = {}
+  ): Promise<{ path: string } | { conflict: LrcOverwriteConflict } | null> {
+    const result = await handlePromise(
+      invoke<string>('save_lyrics_as_lrc', { audioPath, lyrics, options }),
+      {
+        type: ErrorType.FILE_WRITE_ERROR,
+        severity: ErrorSeverity.MEDIUM,
+        context: { audioPath, options, action: 'saveLyricsAsLrc' },
+        showToUser: false,
+        throw: false
+      }
+    )
+
+    if (result.success) return { path: result.data! }
+    const original = result.error?.originalError as { code?: string; details?: LrcOverwriteConflict } | null
+    if (original?.code === 'OVERWRITE_CONFLICT' && original.details) {
+      return { conflict: original.details }
+    }
+    return null
+  }
+
+  /**
+   * 获取歌曲或专辑封面，size 为边长像素，不传时下载原图
+   */
+  async getCover(
+    ids: { songId?: string; albumId?: string },
+    size?: number
+  ): Promise<CoverImage | null> {
+    const result = await handlePromise(
+      invoke<CoverImage>('netease_get_cover', { songId: ids.songId, albumId: ids.albumId, size }),
+      {
+        type: ErrorType.NETWORK,
+        severity: ErrorSeverity.LOW,
+        context: { ...ids, size, action: 'getCover' },
+        showToUser: false,
+        throw: false
+      }
+    )
+
+    return result.success ? result.data! : null
+  }
+
+  /**
+   * 下载在线封面并嵌入音频文件，source 为图片地址、网易云音乐歌曲 ID 或 'auto'
+   * dryRun 时只返回要嵌入的图片供预览
+   */
+  async fetchAndEmbedCover(
+    audioPath: string,
+    source: string,
+    dryRun: boolean = false
+  ): Promise<EmbedCoverResult | null> {
+    const result = await handlePromise(
+      invoke<EmbedCoverResult>('fetch_and_embed_cover', { audioPath, source, dryRun }),
+      {
+        type: ErrorType.NETWORK,
+        severity: ErrorSeverity.MEDIUM,
+        context: { audioPath, source, dryRun, action: 'fetchAndEmbedCover' },
+        showToUser: false,
+        throw: false
+      }
+    )
+
+    return result.success ? result.data! : null
+  }
+
+  /**
+   * 为专辑中的多个文件嵌入同一在线封面，图片只下载一次
+   */
+  async fetchAndEmbedAlbumCover(
+    paths: string[],
+    source: string,
+    dryRun: boolean = false,
+    taskId?: string
+  ): Promise<AlbumCoverEmbedResult | null> {
+    const result = await handlePromise(
+      invoke<AlbumCoverEmbedResult>('fetch_and_embed_album_cover', { paths, source, dryRun, taskId }),
+      {
+        type: ErrorType.NETWORK,
+        severity: ErrorSeverity.MEDIUM,
+        context: { count: paths.length, source, dryRun, action: 'fetchAndEmbedAlbumCover' },
+        showToUser: false,
+        throw: false
+      }
+    )
+
+    return result.success ? result.data! : null
+  }
+
   /**
    * 搜索并获取最匹配的歌词
    */
@@ -95,13 +477,13 @@ export class NeteaseAPI {
       if (!bestMatch) {
         // 如果没有找到匹配，使用第一个结果
         if (songs.length > 0) {
-          return await this.getLyrics(songs[0].id)
+          return await this.getLyrics(songs[0].id, false, songs[0].provider)
         }
         return null
       }
 
       // 获取歌词
-      return await this.getLyrics(bestMatch.id)
+      return await this.getLyrics(bestMatch.id, false, bestMatch.provider)
     } catch (error) {
       errorHandler.handle(error, {
         type: ErrorType.NETWORK,
@@ -175,51 +557,25 @@ export class NeteaseAPI {
   }
 
   /**
-   * 合并原文和翻译歌词
+   * 合并原文、翻译和罗马音歌词
+   * 由后端按时间对齐（允许 500ms 误差），返回多行 LRC；合并失败时返回原文
    */
-  mergeLyrics(lrc: string, tlyric: string): string {
-    if (!tlyric) return lrc
+  async mergeLyrics(lrc: string, tlyric?: string, romalrc?: string): Promise<string> {
+    if (!tlyric && !romalrc) return lrc
     if (!lrc) return ''
 
-    const parseLrc = (text: string): Record<string, string> => {
-      const lines = text.split('\n')
-      const result: Record<string, string> = {}
-      const timeRegex = /\[(\d{2}):(\d{2})\.(\d{2,3})\]/g
-
-      for (const line of lines) {
-        const matches = [...line.matchAll(timeRegex)]
-        if (matches.length === 0) continue
-
-        const lineText = line.replace(timeRegex, '').trim()
-        if (!lineText) continue
-
-        for (const match of matches) {
-          const time = `${match[1]}:${match[2]}.${match[3]}`
-          result[time] = lineText
-        }
-      }
-      return result
-    }
-
-    const origLyrics = parseLrc(lrc)
-    const transLyrics = parseLrc(tlyric)
-
-    const merged: string[] = []
-    const times = [...new Set([...Object.keys(origLyrics), ...Object.keys(transLyrics)])].sort()
-
-    for (const time of times) {
-      const orig = origLyrics[time] || ''
-      const trans = transLyrics[time] || ''
-      
-      if (orig) {
-        merged.push(`[${time}]${orig}`)
-        if (trans && trans !== orig) {
-          merged.push(`[${time}]${trans}`)
-        }
+    const result = await handlePromise(
+      invoke<MergedLyrics>('merge_lyrics', { original: lrc, translation: tlyric, romanization: romalrc }),
+      {
+        type: ErrorType.DATA_PARSE_ERROR,
+        severity: ErrorSeverity.LOW,
+        context: { action: 'mergeLyrics' },
+        showToUser: false,
+        throw: false
       }
-    }
+    )
 
-    return merged.join('\n')
+    return result.success ? result.data!.lrc : lrc
   }
 }
 
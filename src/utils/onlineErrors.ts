@@ -0,0 +1,90 @@
+/**
+ * 在线命令的错误
+ *
+ * 在线命令（搜索、获取歌词、封面、歌单等）失败时返回 `{ code, message, retryAfterSecs? }`，
+ * code 与后端 `NeteaseError::code` 一一对应，不会随版本变化。
+ */
+
+import { ErrorType } from './errorHandler'
+import type { OnlineErrorCode } from '@/types'
+
+export type { OnlineErrorCode }
+
+/** 所有错误码 */
+export const ONLINE_ERROR_CODES: readonly OnlineErrorCode[] = [
+  'TIMEOUT',
+  'RATE_LIMITED',
+  'BLOCKED',
+  'NOT_FOUND',
+  'PRIVATE',
+  'PARSE',
+  'NETWORK',
+  'PROXY',
+  'CACHE',
+  'OFFLINE_MODE'
+]
+
+/** 在线命令返回的错误 */
+export interface OnlineError {
+  code: OnlineErrorCode
+  message: string
+  /** 限流时服务端建议的等待秒数 */
+  retryAfterSecs?: number
+}
+
+/** 显示给用户的提示 */
+const USER_MESSAGES: Record<OnlineErrorCode, string> = {
+  TIMEOUT: '请求超时，请检查网络后重试',
+  RATE_LIMITED: '请求过于频繁，请稍后再试',
+  BLOCKED: '该资源在当前地区不可用',
+  NOT_FOUND: '没有找到对应的歌曲或歌词',
+  PRIVATE: '需要登录或没有访问权限',
+  PARSE: '在线服务返回了无法识别的内容',
+  NETWORK: '网络连接失败',
+  PROXY: '代理连接失败，请检查代理设置',
+  CACHE: '读写本地缓存失败',
+  OFFLINE_MODE: '离线模式已开启'
+}
+
+/** 对应的错误类型 */
+const ERROR_TYPES: Record<OnlineErrorCode, ErrorType> = {
+  TIMEOUT: ErrorType.NETWORK_TIMEOUT,
+  RATE_LIMITED: ErrorType.NETWORK,
+  BLOCKED: ErrorType.NETWORK,
+  NOT_FOUND: ErrorType.NETWORK,
+  PRIVATE: ErrorType.NETWORK,
+  PARSE: ErrorType.DATA_PARSE_ERROR,
+  NETWORK: ErrorType.NETWORK,
+  PROXY: ErrorType.NETWORK,
+  CACHE: ErrorType.FILE_WRITE_ERROR,
+  OFFLINE_MODE: ErrorType.NETWORK_OFFLINE
+}
+
+/**
+ * 识别在线命令返回的错误，不是在线错误时返回 null
+ */
+export function parseOnlineError(error: unknown): OnlineError | null {
+  if (typeof error !== 'object' || error === null) return null
+  const { code, message, retryAfterSecs } = error as Record<string, unknown>
+  if (typeof code !== 'string' || !ONLINE_ERROR_CODES.includes(code as OnlineErrorCode)) return null
+  return {
+    code: code as OnlineErrorCode,
+    message: typeof message === 'string' ? message : '',
+    ...(typeof retryAfterSecs === 'number' ? { retryAfterSecs } : {})
+  }
+}
+
+/**
+ * 显示给用户的提示，限流时附带等待时间
+ */
+export function onlineErrorMessage(error: OnlineError): string {
+  const message = USER_MESSAGES[error.code]
+  return error.retryAfterSecs ? `${message}（${error.retryAfterSecs} 秒后重试）` : message
+}
+
+/**
+ * 错误码对应的错误类型
+ */
+export function onlineErrorType(code: OnlineErrorCode): ErrorType {
+  return ERROR_TYPES[code]
+}
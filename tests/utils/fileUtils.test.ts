@@ -1,4 +1,4 @@
-import { describe, it, expect, beforeEach, vi } from 'vitest'
+import { describe, it, expect, beforeEach, afterEach, vi } from 'vitest'
 import { FileUtils } from '@/utils/fileUtils'
 import { mockInvoke, mockOpen, resetTauriMocks, setupInvokeMocks } from '../mocks/tauri'
 
@@ -60,6 +60,34 @@ describe('FileUtils', () => {
     })
   })
 
+  describe('pathKey', () => {
+    const withUserAgent = (userAgent: string) => {
+      vi.spyOn(navigator, 'userAgent', 'get').mockReturnValue(userAgent)
+    }
+
+    afterEach(() => {
+      vi.restoreAllMocks()
+    })
+
+    it('should normalize separators, prefixes and trailing slashes', () => {
+      withUserAgent('Mozilla/5.0 (X11; Linux x86_64)')
+      expect(FileUtils.pathKey('/home/me//Music/./Album/')).toBe('/home/me/Music/Album')
+      expect(FileUtils.pathKey('\\\\?\\UNC\\server\\share\\a.flac')).toBe('//server/share/a.flac')
+    })
+
+    it('should keep case on case-sensitive platforms', () => {
+      withUserAgent('Mozilla/5.0 (X11; Linux x86_64)')
+      expect(FileUtils.pathKey('/music/Song.flac')).not.toBe(FileUtils.pathKey('/music/song.flac'))
+      expect(FileUtils.pathKey('C:/Music/Song.flac')).toBe('c:/Music/Song.flac')
+    })
+
+    it('should fold case on Windows', () => {
+      withUserAgent('Mozilla/5.0 (Windows NT 10.0; Win64; x64)')
+      expect(FileUtils.pathKey('\\\\?\\C:\\Music\\Song.flac')).toBe('c:/music/song.flac')
+      expect(FileUtils.pathsEqual('C:\\Music\\Song.flac', 'c:/music/song.FLAC')).toBe(true)
+    })
+  })
+
   describe('isAudioFile', () => {
     it('should recognize audio extensions', () => {
       expect(FileUtils.isAudioFile('song.mp3')).toBe(true)
@@ -0,0 +1,79 @@
+import { describe, it, expect } from 'vitest'
+import { ErrorType } from '@/utils/errorHandler'
+import {
+  ONLINE_ERROR_CODES,
+  parseOnlineError,
+  onlineErrorMessage,
+  onlineErrorType,
+} from '@/utils/onlineErrors'
+
+describe('onlineErrors', () => {
+  // 错误码是与后端约定的接口，改动时需要同步修改 NeteaseError::code
+  it('should keep error codes stable', () => {
+    expect(ONLINE_ERROR_CODES).toMatchInlineSnapshot(`
+      [
+        "TIMEOUT",
+        "RATE_LIMITED",
+        "BLOCKED",
+        "NOT_FOUND",
+        "PRIVATE",
+        "PARSE",
+        "NETWORK",
+        "PROXY",
+        "CACHE",
+        "OFFLINE_MODE",
+      ]
+    `)
+  })
+
+  it('should parse serialized online errors', () => {
+    expect(parseOnlineError({ code: 'RATE_LIMITED', message: 'Rate limited, retry after 30s', retryAfterSecs: 30 }))
+      .toMatchInlineSnapshot(`
+        {
+          "code": "RATE_LIMITED",
+          "message": "Rate limited, retry after 30s",
+          "retryAfterSecs": 30,
+        }
+      `)
+    expect(parseOnlineError({ code: 'OFFLINE_MODE', message: 'Offline mode is enabled' })).toEqual({
+      code: 'OFFLINE_MODE',
+      message: 'Offline mode is enabled',
+    })
+  })
+
+  it('should reject values that are not online errors', () => {
+    expect(parseOnlineError('Request failed')).toBeNull()
+    expect(parseOnlineError(null)).toBeNull()
+    expect(parseOnlineError(new Error('boom'))).toBeNull()
+    expect(parseOnlineError({ code: 'IO', message: 'disk full' })).toBeNull()
+  })
+
+  it('should describe every code to the user', () => {
+    const messages = Object.fromEntries(
+      ONLINE_ERROR_CODES.map(code => [code, onlineErrorMessage({ code, message: '' })])
+    )
+    expect(messages).toMatchInlineSnapshot(`
+      {
+        "BLOCKED": "该资源在当前地区不可用",
+        "CACHE": "读写本地缓存失败",
+        "NETWORK": "网络连接失败",
+        "NOT_FOUND": "没有找到对应的歌曲或歌词",
+        "OFFLINE_MODE": "离线模式已开启",
+        "PARSE": "在线服务返回了无法识别的内容",
+        "PRIVATE": "需要登录或没有访问权限",
+        "PROXY": "代理连接失败，请检查代理设置",
+        "RATE_LIMITED": "请求过于频繁，请稍后再试",
+        "TIMEOUT": "请求超时，请检查网络后重试",
+      }
+    `)
+    expect(onlineErrorMessage({ code: 'RATE_LIMITED', message: '', retryAfterSecs: 30 }))
+      .toBe('请求过于频繁，请稍后再试（30 秒后重试）')
+  })
+
+  it('should map codes to error types', () => {
+    expect(onlineErrorType('TIMEOUT')).toBe(ErrorType.NETWORK_TIMEOUT)
+    expect(onlineErrorType('OFFLINE_MODE')).toBe(ErrorType.NETWORK_OFFLINE)
+    expect(onlineErrorType('PARSE')).toBe(ErrorType.DATA_PARSE_ERROR)
+    expect(onlineErrorType('PROXY')).toBe(ErrorType.NETWORK)
+  })
+})